@@ -13,7 +13,7 @@
 //!
 //! Demonstrates Asset, AssetClass, AssetStatus, and ListAssetsParams.
 
-use alpaca_base::{Asset, AssetClass, AssetStatus, ListAssetsParams};
+use alpaca_base::{Asset, AssetClass, AssetStatus, types::ListAssetsParams};
 use uuid::Uuid;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
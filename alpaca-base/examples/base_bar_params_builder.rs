@@ -13,7 +13,7 @@
 //!
 //! Demonstrates MultiBarsParams, CryptoBarsParams, and OptionBarsParams builders.
 
-use alpaca_base::{CryptoBarsParams, DataFeed, MultiBarsParams, OptionBarsParams};
+use alpaca_base::types::{CryptoBarsParams, DataFeed, MultiBarsParams, OptionBarsParams};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Market Data Parameters Builder ===\n");
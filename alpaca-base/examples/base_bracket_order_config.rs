@@ -9,7 +9,10 @@
 //! cargo run -p alpaca-base --example base_bracket_order_config
 //! ```
 
-use alpaca_base::{OrderClass, StopLoss, TakeProfit};
+use alpaca_base::{
+    OrderClass,
+    types::{StopLoss, TakeProfit},
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Bracket Order Configuration ===\n");
@@ -9,7 +9,7 @@
 //! cargo run -p alpaca-base --example base_broker_account_types
 //! ```
 
-use alpaca_base::{
+use alpaca_base::types::{
     Agreement, AgreementType, Contact, Disclosures, Identity, TaxIdType, TrustedContact,
 };
 
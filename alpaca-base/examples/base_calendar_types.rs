@@ -8,7 +8,7 @@
 //! cargo run -p alpaca-base --example base_calendar_types
 //! ```
 
-use alpaca_base::{CalendarDay, CalendarParams, MarketClock, MarketSession};
+use alpaca_base::types::{CalendarDay, CalendarParams, MarketClock, MarketSession};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Calendar Types ===\n");
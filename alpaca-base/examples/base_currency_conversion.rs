@@ -8,7 +8,7 @@
 //! cargo run -p alpaca-base --example base_currency_conversion
 //! ```
 
-use alpaca_base::{Currency, CurrencyPair, ExchangeRate};
+use alpaca_base::types::{Currency, CurrencyPair, ExchangeRate};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Currency Conversion ===\n");
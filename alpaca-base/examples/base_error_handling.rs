@@ -145,12 +145,14 @@ fn demonstrate_error_matching() {
             status,
             message,
             error_code,
+            kind,
             request_id,
         } => {
             println!("  API Error detected:");
             println!("    - Status: {}", status);
             println!("    - Message: {}", message);
             println!("    - Error code: {:?}", error_code);
+            println!("    - Kind: {:?}", kind);
             println!("    - Request ID: {:?}", request_id);
         }
         AlpacaError::RateLimit {
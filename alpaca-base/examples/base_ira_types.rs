@@ -8,7 +8,7 @@
 //! cargo run -p alpaca-base --example base_ira_types
 //! ```
 
-use alpaca_base::{IraAccountType, IraBeneficiary, IraContribution, IraDistribution};
+use alpaca_base::types::{IraAccountType, IraBeneficiary, IraContribution, IraDistribution};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== IRA Types ===\n");
@@ -8,7 +8,10 @@
 //! cargo run -p alpaca-base --example base_oauth_config
 //! ```
 
-use alpaca_base::{OAuthConfig, OAuthScope, OAuthToken};
+use alpaca_base::{
+    OAuthToken,
+    types::{OAuthConfig, OAuthScope},
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== OAuth 2.0 Configuration ===\n");
@@ -8,7 +8,7 @@
 //! cargo run -p alpaca-base --example base_option_contract_params
 //! ```
 
-use alpaca_base::{OptionContractParams, OptionStyle, OptionType};
+use alpaca_base::types::{OptionContractParams, OptionStyle, OptionType};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Option Contract Parameters ===\n");
@@ -8,7 +8,7 @@
 //! cargo run -p alpaca-base --example base_rate_limit_config
 //! ```
 
-use alpaca_base::{RateLimitConfig, RateLimitStatus};
+use alpaca_base::types::{RateLimitConfig, RateLimitStatus};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Rate Limit Configuration ===\n");
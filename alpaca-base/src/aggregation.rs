@@ -0,0 +1,396 @@
+//! Real-time bar aggregation.
+//!
+//! [`BarAggregator`] turns a stream of trades or 1-minute bars for one
+//! symbol into higher-timeframe [`Bar`]s (5/15/30 minutes, hourly, daily)
+//! as data arrives. Intraday bars are aligned to the clock, like the bars
+//! returned by the REST API, and are cut at the session boundaries taken
+//! from the market [`Calendar`]: a bar never spans a close, and data
+//! outside the configured sessions is ignored. Without a calendar the
+//! aggregator treats time as one continuous session, which suits crypto.
+
+use crate::error::{AlpacaError, Result};
+use crate::types::{Bar, Calendar, Timeframe, Trade};
+use crate::utils::{SessionWindow, us_eastern_offset};
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+
+/// Bar being built for the current bucket.
+#[derive(Debug, Clone)]
+struct PartialBar {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
+    notional: f64,
+    trade_count: u64,
+}
+
+impl PartialBar {
+    fn to_bar(&self) -> Bar {
+        Bar {
+            timestamp: self.start,
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            trade_count: Some(self.trade_count),
+            vwap: (self.volume > 0).then(|| self.notional / self.volume as f64),
+        }
+    }
+}
+
+/// One input (a trade or a 1-minute bar) normalised for aggregation.
+struct Sample {
+    timestamp: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
+    notional: f64,
+    trade_count: u64,
+    /// End of the interval the input covers, if it covers one.
+    end: Option<DateTime<Utc>>,
+}
+
+/// Builds higher-timeframe bars for one symbol from trades or 1-minute
+/// bars.
+///
+/// Each `on_*` call returns the bars completed by that input, oldest
+/// first. A bar completes when data for a later bucket arrives, when a
+/// 1-minute bar fills the bucket's last minute, or when
+/// [`flush`](Self::flush) is called at or after the bucket's end. Data older
+/// than the last completed bar is dropped.
+#[derive(Debug, Clone)]
+pub struct BarAggregator {
+    timeframe: Timeframe,
+    period: Option<Duration>,
+    sessions: Vec<SessionWindow>,
+    current: Option<PartialBar>,
+    watermark: Option<DateTime<Utc>>,
+}
+
+impl BarAggregator {
+    /// Create an aggregator for `timeframe` with no session boundaries.
+    ///
+    /// Weekly and monthly timeframes are not supported.
+    pub fn new(timeframe: Timeframe) -> Result<Self> {
+        let period = match timeframe {
+            Timeframe::OneMinute => Some(Duration::minutes(1)),
+            Timeframe::FiveMinutes => Some(Duration::minutes(5)),
+            Timeframe::FifteenMinutes => Some(Duration::minutes(15)),
+            Timeframe::ThirtyMinutes => Some(Duration::minutes(30)),
+            Timeframe::OneHour => Some(Duration::hours(1)),
+            Timeframe::OneDay => None,
+            Timeframe::OneWeek | Timeframe::OneMonth => {
+                return Err(AlpacaError::Validation(format!(
+                    "bar aggregation does not support {timeframe:?}"
+                )));
+            }
+        };
+        Ok(Self {
+            timeframe,
+            period,
+            sessions: Vec::new(),
+            current: None,
+            watermark: None,
+        })
+    }
+
+    /// Restrict aggregation to the regular sessions of `calendar`.
+    pub fn with_calendar(self, calendar: &[Calendar]) -> Result<Self> {
+        let sessions = calendar
+            .iter()
+            .map(SessionWindow::from_calendar)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.with_sessions(sessions))
+    }
+
+    /// Restrict aggregation to `sessions` (for example extended hours
+    /// built with [`SessionWindow::extended_from_calendar`]).
+    #[must_use]
+    pub fn with_sessions(mut self, mut sessions: Vec<SessionWindow>) -> Self {
+        sessions.sort_by_key(|s| s.open);
+        self.sessions = sessions;
+        self
+    }
+
+    /// Target timeframe.
+    #[must_use]
+    pub fn timeframe(&self) -> &Timeframe {
+        &self.timeframe
+    }
+
+    /// The bar currently being built, if any.
+    #[must_use]
+    pub fn current(&self) -> Option<Bar> {
+        self.current.as_ref().map(PartialBar::to_bar)
+    }
+
+    /// Add a trade.
+    pub fn on_trade(&mut self, trade: &Trade) -> Vec<Bar> {
+        self.ingest(Sample {
+            timestamp: trade.timestamp,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: u64::from(trade.size),
+            notional: trade.price * f64::from(trade.size),
+            trade_count: 1,
+            end: None,
+        })
+    }
+
+    /// Add a 1-minute bar.
+    pub fn on_bar(&mut self, bar: &Bar) -> Vec<Bar> {
+        self.ingest(Sample {
+            timestamp: bar.timestamp,
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+            volume: bar.volume,
+            notional: bar.vwap.unwrap_or(bar.close) * bar.volume as f64,
+            trade_count: bar.trade_count.unwrap_or(0),
+            end: Some(bar.timestamp + Duration::minutes(1)),
+        })
+    }
+
+    /// Complete the current bar if its bucket has ended by `now`.
+    ///
+    /// Call this from a timer so bars close on time even when no new data
+    /// arrives (for example at the session close).
+    pub fn flush(&mut self, now: DateTime<Utc>) -> Option<Bar> {
+        if self.current.as_ref()?.end <= now {
+            return self.complete();
+        }
+        None
+    }
+
+    /// Complete the current bar regardless of its bucket end.
+    pub fn finish(&mut self) -> Option<Bar> {
+        self.complete()
+    }
+
+    fn complete(&mut self) -> Option<Bar> {
+        let bar = self.current.take()?;
+        self.watermark = Some(bar.end);
+        Some(bar.to_bar())
+    }
+
+    fn ingest(&mut self, sample: Sample) -> Vec<Bar> {
+        let mut completed: Vec<Bar> = self.flush(sample.timestamp).into_iter().collect();
+        if self.watermark.is_some_and(|w| sample.timestamp < w) {
+            return completed;
+        }
+        let Some((start, end)) = self.bucket(sample.timestamp) else {
+            return completed;
+        };
+
+        match &mut self.current {
+            Some(bar) if bar.start == start => {
+                bar.high = bar.high.max(sample.high);
+                bar.low = bar.low.min(sample.low);
+                bar.close = sample.close;
+                bar.volume += sample.volume;
+                bar.notional += sample.notional;
+                bar.trade_count += sample.trade_count;
+            }
+            Some(bar) if sample.timestamp < bar.start => return completed,
+            _ => {
+                completed.extend(self.complete());
+                self.current = Some(PartialBar {
+                    start,
+                    end,
+                    open: sample.open,
+                    high: sample.high,
+                    low: sample.low,
+                    close: sample.close,
+                    volume: sample.volume,
+                    notional: sample.notional,
+                    trade_count: sample.trade_count,
+                });
+            }
+        }
+
+        if sample.end.is_some_and(|e| e >= end) {
+            completed.extend(self.complete());
+        }
+        completed
+    }
+
+    /// Bucket `[start, end)` containing `timestamp`, or `None` outside all
+    /// sessions. `start` is the bar's timestamp.
+    fn bucket(&self, timestamp: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let session = if self.sessions.is_empty() {
+            None
+        } else {
+            let idx = self.sessions.partition_point(|s| s.close <= timestamp);
+            let session = self.sessions.get(idx).filter(|s| s.contains(timestamp))?;
+            Some(session)
+        };
+
+        match (self.period, session) {
+            (Some(period), _) => {
+                let secs = period.num_seconds();
+                let start = DateTime::from_timestamp(
+                    timestamp.timestamp() - timestamp.timestamp().rem_euclid(secs),
+                    0,
+                )?;
+                let end = start + period;
+                Some((start, session.map_or(end, |s| end.min(s.close))))
+            }
+            (None, Some(session)) => {
+                let midnight = us_eastern_offset(session.date)
+                    .from_local_datetime(&session.date.and_time(NaiveTime::MIN))
+                    .single()?
+                    .with_timezone(&Utc);
+                Some((midnight, session.close))
+            }
+            (None, None) => {
+                let start = timestamp.date_naive().and_time(NaiveTime::MIN).and_utc();
+                Some((start, start + Duration::days(1)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(date: &str) -> Calendar {
+        Calendar {
+            date: date.to_string(),
+            open: "09:30".to_string(),
+            close: "16:00".to_string(),
+            session_open: "0400".to_string(),
+            session_close: "2000".to_string(),
+        }
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn minute_bar(ts: &str, price: f64, volume: u64) -> Bar {
+        Bar {
+            timestamp: at(ts),
+            open: price,
+            high: price + 0.5,
+            low: price - 0.5,
+            close: price,
+            volume,
+            trade_count: Some(10),
+            vwap: Some(price),
+        }
+    }
+
+    fn trade(ts: &str, price: f64, size: u32) -> Trade {
+        Trade {
+            timestamp: at(ts),
+            price,
+            size,
+            exchange: String::new(),
+            conditions: Vec::new(),
+            id: 0,
+        }
+    }
+
+    #[test]
+    fn test_five_minute_bars_from_minute_bars() {
+        let mut agg = BarAggregator::new(Timeframe::FiveMinutes)
+            .unwrap()
+            .with_calendar(&[calendar("2024-07-01")])
+            .unwrap();
+
+        // Pre-market data is outside the regular session.
+        assert!(
+            agg.on_bar(&minute_bar("2024-07-01T13:29:00Z", 99.0, 5))
+                .is_empty()
+        );
+        assert!(agg.current().is_none());
+
+        for (i, minute) in (30..34).enumerate() {
+            let ts = format!("2024-07-01T13:{minute}:00Z");
+            assert!(
+                agg.on_bar(&minute_bar(&ts, 100.0 + i as f64, 100))
+                    .is_empty()
+            );
+        }
+        // The fifth minute fills the bucket and completes it immediately.
+        let done = agg.on_bar(&minute_bar("2024-07-01T13:34:00Z", 104.0, 100));
+        assert_eq!(done.len(), 1);
+        let bar = &done[0];
+        assert_eq!(bar.timestamp, at("2024-07-01T13:30:00Z"));
+        assert_eq!(
+            (bar.open, bar.high, bar.low, bar.close),
+            (100.0, 104.5, 99.5, 104.0)
+        );
+        assert_eq!(bar.volume, 500);
+        assert_eq!(bar.trade_count, Some(50));
+        assert_eq!(bar.vwap, Some(102.0));
+
+        // A late bar for a completed bucket is dropped.
+        assert!(
+            agg.on_bar(&minute_bar("2024-07-01T13:33:00Z", 1.0, 1))
+                .is_empty()
+        );
+        assert!(agg.current().is_none());
+    }
+
+    #[test]
+    fn test_hourly_and_daily_bars_respect_session_close() {
+        let sessions = [calendar("2024-12-02"), calendar("2024-12-03")];
+        let mut hourly = BarAggregator::new(Timeframe::OneHour)
+            .unwrap()
+            .with_calendar(&sessions)
+            .unwrap();
+        let mut daily = BarAggregator::new(Timeframe::OneDay)
+            .unwrap()
+            .with_calendar(&sessions)
+            .unwrap();
+
+        let trades = [
+            trade("2024-12-02T14:31:00Z", 10.0, 10),
+            trade("2024-12-02T14:59:59Z", 11.0, 10),
+            trade("2024-12-02T20:45:00Z", 12.0, 10),
+        ];
+        let mut hourly_bars = Vec::new();
+        for t in &trades {
+            hourly_bars.extend(hourly.on_trade(t));
+            assert!(daily.on_trade(t).is_empty());
+        }
+        // The opening bar is clock-aligned to 09:00 ET but starts at the open.
+        assert_eq!(hourly_bars.len(), 1);
+        assert_eq!(hourly_bars[0].timestamp, at("2024-12-02T14:00:00Z"));
+        assert_eq!(hourly_bars[0].close, 11.0);
+
+        // The last hour is cut at 16:00 ET and closes on flush.
+        assert!(hourly.flush(at("2024-12-02T20:59:00Z")).is_none());
+        let last = hourly.flush(at("2024-12-02T21:00:00Z")).unwrap();
+        assert_eq!(last.timestamp, at("2024-12-02T20:00:00Z"));
+
+        // An after-hours trade completes the daily bar without joining it.
+        let done = daily.on_trade(&trade("2024-12-02T21:15:00Z", 50.0, 10));
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].timestamp, at("2024-12-02T05:00:00Z"));
+        assert_eq!(
+            (done[0].open, done[0].close, done[0].volume),
+            (10.0, 12.0, 30)
+        );
+        assert!(daily.current().is_none());
+        assert!(
+            daily
+                .on_trade(&trade("2024-12-03T14:30:00Z", 13.0, 1))
+                .is_empty()
+        );
+        assert_eq!(daily.current().unwrap().open, 13.0);
+
+        assert!(BarAggregator::new(Timeframe::OneWeek).is_err());
+    }
+}
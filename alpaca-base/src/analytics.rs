@@ -0,0 +1,357 @@
+//! Black-Scholes pricing, implied volatility and Greeks.
+//!
+//! Option snapshots do not always carry Greeks or implied volatility, e.g.
+//! for illiquid contracts or on the indicative feed. [`BlackScholes`]
+//! computes them locally from the option price and the underlying, and
+//! [`OptionSnapshot::compute_missing_greeks`] fills in whatever a snapshot
+//! lacks.
+//!
+//! The model is European without dividends, so values for American equity
+//! options are approximations. Greeks follow the usual quoting convention:
+//! theta per calendar day, vega and rho per percentage point.
+//!
+//! [`OptionSnapshot::compute_missing_greeks`]: crate::types::OptionSnapshot::compute_missing_greeks
+
+use crate::option_events::OccSymbol;
+use crate::types::{OptionGreeks, OptionSnapshot, OptionType};
+use crate::utils::us_eastern_offset;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+
+/// Days per year used for time to expiry and theta.
+const DAYS_PER_YEAR: f64 = 365.0;
+/// Volatility search range of [`BlackScholes::implied_volatility`].
+const MIN_VOLATILITY: f64 = 1e-6;
+const MAX_VOLATILITY: f64 = 10.0;
+/// Price tolerance of the implied volatility search.
+const PRICE_TOLERANCE: f64 = 1e-8;
+
+/// Years from `now` to the 4:00 p.m. US Eastern close on `expiration`,
+/// or zero once expired.
+#[must_use]
+pub fn years_to_expiry(expiration: NaiveDate, now: DateTime<Utc>) -> f64 {
+    let close = expiration.and_time(NaiveTime::from_hms_opt(16, 0, 0).unwrap_or_default());
+    let Some(close) = us_eastern_offset(expiration)
+        .from_local_datetime(&close)
+        .single()
+    else {
+        return 0.0;
+    };
+    let seconds = (close.with_timezone(&Utc) - now).num_seconds().max(0) as f64;
+    seconds / (DAYS_PER_YEAR * 86_400.0)
+}
+
+/// Black-Scholes inputs for one contract, without the volatility.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlackScholes {
+    /// Call or put.
+    pub option_type: OptionType,
+    /// Underlying price.
+    pub spot: f64,
+    /// Strike price.
+    pub strike: f64,
+    /// Time to expiry in years.
+    pub years: f64,
+    /// Continuously compounded risk-free rate, e.g. `0.05`.
+    pub rate: f64,
+}
+
+impl BlackScholes {
+    /// Create the inputs for a contract.
+    #[must_use]
+    pub fn new(option_type: OptionType, spot: f64, strike: f64, years: f64, rate: f64) -> Self {
+        Self {
+            option_type,
+            spot,
+            strike,
+            years,
+            rate,
+        }
+    }
+
+    /// Inputs for the contract `contract` at `now`.
+    #[must_use]
+    pub fn for_contract(contract: &OccSymbol, spot: f64, rate: f64, now: DateTime<Utc>) -> Self {
+        Self::new(
+            contract.option_type.clone(),
+            spot,
+            contract.strike,
+            years_to_expiry(contract.expiration, now),
+            rate,
+        )
+    }
+
+    fn is_valid(&self) -> bool {
+        self.spot > 0.0 && self.strike > 0.0 && self.years > 0.0
+    }
+
+    fn discounted_strike(&self) -> f64 {
+        self.strike * (-self.rate * self.years).exp()
+    }
+
+    fn d1_d2(&self, volatility: f64) -> (f64, f64) {
+        let vol_sqrt_t = volatility * self.years.sqrt();
+        let d1 = ((self.spot / self.strike).ln()
+            + (self.rate + volatility * volatility / 2.0) * self.years)
+            / vol_sqrt_t;
+        (d1, d1 - vol_sqrt_t)
+    }
+
+    /// Theoretical price at `volatility`; the intrinsic value once expired.
+    #[must_use]
+    pub fn price(&self, volatility: f64) -> f64 {
+        if !self.is_valid() || volatility <= 0.0 {
+            return match self.option_type {
+                OptionType::Call => (self.spot - self.discounted_strike()).max(0.0),
+                OptionType::Put => (self.discounted_strike() - self.spot).max(0.0),
+            };
+        }
+        let (d1, d2) = self.d1_d2(volatility);
+        match self.option_type {
+            OptionType::Call => self.spot * norm_cdf(d1) - self.discounted_strike() * norm_cdf(d2),
+            OptionType::Put => self.discounted_strike() * norm_cdf(-d2) - self.spot * norm_cdf(-d1),
+        }
+    }
+
+    /// Greeks at `volatility`, or `None` for an expired contract or a
+    /// non-positive input.
+    #[must_use]
+    pub fn greeks(&self, volatility: f64) -> Option<OptionGreeks> {
+        if !self.is_valid() || volatility <= 0.0 {
+            return None;
+        }
+        let (d1, d2) = self.d1_d2(volatility);
+        let sqrt_t = self.years.sqrt();
+        let discounted = self.discounted_strike();
+        let decay = -self.spot * norm_pdf(d1) * volatility / (2.0 * sqrt_t);
+        let (delta, theta, rho) = match self.option_type {
+            OptionType::Call => (
+                norm_cdf(d1),
+                decay - self.rate * discounted * norm_cdf(d2),
+                self.years * discounted * norm_cdf(d2),
+            ),
+            OptionType::Put => (
+                norm_cdf(d1) - 1.0,
+                decay + self.rate * discounted * norm_cdf(-d2),
+                -self.years * discounted * norm_cdf(-d2),
+            ),
+        };
+        Some(OptionGreeks {
+            delta: Some(delta),
+            gamma: Some(norm_pdf(d1) / (self.spot * volatility * sqrt_t)),
+            theta: Some(theta / DAYS_PER_YEAR),
+            vega: Some(self.spot * norm_pdf(d1) * sqrt_t / 100.0),
+            rho: Some(rho / 100.0),
+        })
+    }
+
+    /// The volatility at which the model price equals `price`, or `None`
+    /// if `price` is outside the no-arbitrage bounds or the contract has
+    /// expired.
+    #[must_use]
+    pub fn implied_volatility(&self, price: f64) -> Option<f64> {
+        if !self.is_valid() || !price.is_finite() {
+            return None;
+        }
+        let (lower, upper) = match self.option_type {
+            OptionType::Call => ((self.spot - self.discounted_strike()).max(0.0), self.spot),
+            OptionType::Put => (
+                (self.discounted_strike() - self.spot).max(0.0),
+                self.discounted_strike(),
+            ),
+        };
+        if price <= lower || price >= upper {
+            return None;
+        }
+
+        // Newton's method, falling back to bisection whenever a step
+        // leaves the bracket; the price is increasing in volatility.
+        let (mut low, mut high) = (MIN_VOLATILITY, MAX_VOLATILITY);
+        let mut volatility = 0.5;
+        for _ in 0..100 {
+            let diff = self.price(volatility) - price;
+            if diff.abs() < PRICE_TOLERANCE {
+                return Some(volatility);
+            }
+            if diff > 0.0 {
+                high = volatility;
+            } else {
+                low = volatility;
+            }
+            let (d1, _) = self.d1_d2(volatility);
+            let vega = self.spot * norm_pdf(d1) * self.years.sqrt();
+            let step = volatility - diff / vega;
+            volatility = if vega > f64::EPSILON && step > low && step < high {
+                step
+            } else {
+                (low + high) / 2.0
+            };
+        }
+        ((high - low) < 1e-6).then_some(volatility)
+    }
+}
+
+impl OptionSnapshot {
+    /// The price used for implied volatility: the quote midpoint when both
+    /// sides are quoted, otherwise the last trade.
+    #[must_use]
+    pub fn reference_price(&self) -> Option<f64> {
+        match &self.latest_quote {
+            Some(quote) if quote.bid_price > 0.0 && quote.ask_price > 0.0 => {
+                Some((quote.bid_price + quote.ask_price) / 2.0)
+            }
+            _ => self.latest_trade.as_ref().map(|trade| trade.price),
+        }
+    }
+
+    /// Fill in a missing implied volatility and missing Greeks from the
+    /// Black-Scholes model, for `contract` with the underlying at
+    /// `underlying_price`. Values the snapshot already has are kept.
+    ///
+    /// Returns whether anything was filled in.
+    pub fn compute_missing_greeks(
+        &mut self,
+        contract: &OccSymbol,
+        underlying_price: f64,
+        risk_free_rate: f64,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let model = BlackScholes::for_contract(contract, underlying_price, risk_free_rate, now);
+        let mut changed = false;
+        if self.implied_volatility.is_none() {
+            self.implied_volatility = self
+                .reference_price()
+                .and_then(|price| model.implied_volatility(price));
+            changed |= self.implied_volatility.is_some();
+        }
+        let Some(computed) = self
+            .implied_volatility
+            .and_then(|volatility| model.greeks(volatility))
+        else {
+            return changed;
+        };
+        let greeks = self.greeks.get_or_insert(OptionGreeks {
+            delta: None,
+            gamma: None,
+            theta: None,
+            vega: None,
+            rho: None,
+        });
+        for (field, value) in [
+            (&mut greeks.delta, computed.delta),
+            (&mut greeks.gamma, computed.gamma),
+            (&mut greeks.theta, computed.theta),
+            (&mut greeks.vega, computed.vega),
+            (&mut greeks.rho, computed.rho),
+        ] {
+            if field.is_none() {
+                *field = value;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Standard normal density.
+fn norm_pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Standard normal cumulative distribution, via the complementary error
+/// function approximation of Numerical Recipes (relative error < 1.2e-7).
+fn norm_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let ans = t * poly.exp();
+    if x >= 0.0 { ans } else { 2.0 - ans }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OptionQuote, OptionTrade};
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_black_scholes_price_and_greeks() {
+        let call = BlackScholes::new(OptionType::Call, 100.0, 100.0, 1.0, 0.05);
+        let put = BlackScholes::new(OptionType::Put, 100.0, 100.0, 1.0, 0.05);
+        assert_close(call.price(0.2), 10.4506, 1e-4);
+        assert_close(put.price(0.2), 5.5735, 1e-4);
+
+        let greeks = call.greeks(0.2).unwrap();
+        assert_close(greeks.delta.unwrap(), 0.6368, 1e-4);
+        assert_close(greeks.gamma.unwrap(), 0.018_76, 1e-5);
+        assert_close(greeks.vega.unwrap(), 0.3752, 1e-4);
+        assert_close(greeks.theta.unwrap(), -6.414 / 365.0, 1e-4);
+        assert_close(greeks.rho.unwrap(), 0.5323, 1e-4);
+        assert_close(put.greeks(0.2).unwrap().delta.unwrap(), -0.3632, 1e-4);
+
+        assert_close(call.implied_volatility(10.4506).unwrap(), 0.2, 1e-4);
+        assert_close(put.implied_volatility(put.price(0.65)).unwrap(), 0.65, 1e-6);
+        assert_eq!(call.implied_volatility(4.0), None, "below intrinsic value");
+        assert_eq!(call.implied_volatility(100.0), None, "at the spot price");
+    }
+
+    #[test]
+    fn test_compute_missing_greeks_fills_snapshot() {
+        let now = "2026-01-16T15:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let contract = OccSymbol::parse("AAPL260320C00200000").unwrap();
+        let model = BlackScholes::for_contract(&contract, 195.0, 0.04, now);
+        // Mar 20 is after the DST switch, so the close is 20:00 UTC.
+        assert_close(model.years, (63.0 + 5.0 / 24.0) / 365.0, 1e-9);
+        let mid = model.price(0.3);
+
+        let mut snapshot = OptionSnapshot {
+            latest_quote: Some(OptionQuote {
+                timestamp: now,
+                bid_price: mid - 0.05,
+                bid_size: 10,
+                ask_price: mid + 0.05,
+                ask_size: 10,
+                bid_exchange: "C".to_string(),
+                ask_exchange: "C".to_string(),
+                conditions: None,
+            }),
+            latest_trade: Some(OptionTrade {
+                timestamp: now,
+                price: mid + 1.0,
+                size: 1,
+                exchange: "C".to_string(),
+                conditions: None,
+            }),
+            greeks: Some(OptionGreeks {
+                delta: Some(0.5),
+                gamma: None,
+                theta: None,
+                vega: None,
+                rho: None,
+            }),
+            implied_volatility: None,
+        };
+        assert!(snapshot.compute_missing_greeks(&contract, 195.0, 0.04, now));
+        assert_close(snapshot.implied_volatility.unwrap(), 0.3, 1e-6);
+        let greeks = snapshot.greeks.as_ref().unwrap();
+        assert_eq!(greeks.delta, Some(0.5), "existing values are kept");
+        assert!(greeks.gamma.unwrap() > 0.0 && greeks.theta.unwrap() < 0.0);
+        assert!(!snapshot.compute_missing_greeks(&contract, 195.0, 0.04, now));
+    }
+}
@@ -0,0 +1,291 @@
+//! Apache Arrow export (requires the `arrow` feature).
+//!
+//! Converts [`Bar`]s, [`Quote`]s and [`Trade`]s into Arrow
+//! [`RecordBatch`]es that Polars, DataFusion or any other Arrow consumer
+//! can take without an intermediate serialization step. Timestamps become
+//! nanosecond `Timestamp` columns in UTC; trade conditions become a list of
+//! strings. Large result sets can be converted lazily in fixed-size chunks
+//! with [`record_batches`] or, for multi-symbol responses, with
+//! [`symbol_record_batches`], which adds a leading `symbol` column.
+
+use crate::error::{AlpacaError, Result};
+use crate::types::{Bar, Quote, Trade};
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::{
+    ArrayRef, Float64Array, RecordBatch, StringArray, TimestampNanosecondArray, UInt32Array,
+    UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A row type with a fixed Arrow schema.
+pub trait ArrowRecord: Sized {
+    /// Column fields, in order.
+    fn fields() -> Vec<Field>;
+
+    /// One array per field for `rows`.
+    fn columns(rows: &[Self]) -> Result<Vec<ArrayRef>>;
+}
+
+/// The schema of `T`, with a leading `symbol` column if `with_symbol`.
+#[must_use]
+pub fn schema<T: ArrowRecord>(with_symbol: bool) -> SchemaRef {
+    let mut fields = Vec::new();
+    if with_symbol {
+        fields.push(Field::new("symbol", DataType::Utf8, false));
+    }
+    fields.extend(T::fields());
+    Arc::new(Schema::new(fields))
+}
+
+/// Convert `rows` into one record batch.
+///
+/// # Errors
+/// Returns [`AlpacaError::InvalidData`] if a timestamp is outside the
+/// nanosecond range or Arrow rejects the columns.
+pub fn to_record_batch<T: ArrowRecord>(rows: &[T]) -> Result<RecordBatch> {
+    RecordBatch::try_new(schema::<T>(false), T::columns(rows)?).map_err(arrow_error)
+}
+
+/// Convert `rows` for `symbol` into one record batch with a leading
+/// `symbol` column.
+///
+/// # Errors
+/// See [`to_record_batch`].
+pub fn to_record_batch_with_symbol<T: ArrowRecord>(
+    symbol: &str,
+    rows: &[T],
+) -> Result<RecordBatch> {
+    let mut columns: Vec<ArrayRef> = vec![Arc::new(StringArray::from(vec![symbol; rows.len()]))];
+    columns.extend(T::columns(rows)?);
+    RecordBatch::try_new(schema::<T>(true), columns).map_err(arrow_error)
+}
+
+/// Lazily convert `rows` into batches of at most `chunk_size` rows.
+pub fn record_batches<T: ArrowRecord>(
+    rows: &[T],
+    chunk_size: usize,
+) -> impl Iterator<Item = Result<RecordBatch>> + '_ {
+    rows.chunks(chunk_size.max(1)).map(to_record_batch)
+}
+
+/// Lazily convert a multi-symbol response, in symbol order, into batches
+/// of at most `chunk_size` rows with a leading `symbol` column.
+pub fn symbol_record_batches<T: ArrowRecord>(
+    data: &HashMap<String, Vec<T>>,
+    chunk_size: usize,
+) -> impl Iterator<Item = Result<RecordBatch>> + '_ {
+    let mut symbols: Vec<&String> = data.keys().collect();
+    symbols.sort();
+    symbols.into_iter().flat_map(move |symbol| {
+        data[symbol]
+            .chunks(chunk_size.max(1))
+            .map(move |chunk| to_record_batch_with_symbol(symbol, chunk))
+    })
+}
+
+fn arrow_error(err: arrow_schema::ArrowError) -> AlpacaError {
+    AlpacaError::InvalidData(format!("arrow conversion failed: {err}"))
+}
+
+fn timestamp_field() -> Field {
+    Field::new(
+        "timestamp",
+        DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+        false,
+    )
+}
+
+fn timestamps<T>(rows: &[T], at: impl Fn(&T) -> DateTime<Utc>) -> Result<ArrayRef> {
+    let nanos = rows
+        .iter()
+        .map(|row| {
+            let timestamp = at(row);
+            timestamp.timestamp_nanos_opt().ok_or_else(|| {
+                AlpacaError::InvalidData(format!("timestamp {timestamp} out of nanosecond range"))
+            })
+        })
+        .collect::<Result<Vec<i64>>>()?;
+    Ok(Arc::new(
+        TimestampNanosecondArray::from(nanos).with_timezone("UTC"),
+    ))
+}
+
+impl ArrowRecord for Bar {
+    fn fields() -> Vec<Field> {
+        vec![
+            timestamp_field(),
+            Field::new("open", DataType::Float64, false),
+            Field::new("high", DataType::Float64, false),
+            Field::new("low", DataType::Float64, false),
+            Field::new("close", DataType::Float64, false),
+            Field::new("volume", DataType::UInt64, false),
+            Field::new("trade_count", DataType::UInt64, true),
+            Field::new("vwap", DataType::Float64, true),
+        ]
+    }
+
+    fn columns(rows: &[Self]) -> Result<Vec<ArrayRef>> {
+        let f64s = |f: fn(&Bar) -> f64| -> ArrayRef {
+            Arc::new(Float64Array::from_iter_values(rows.iter().map(f)))
+        };
+        Ok(vec![
+            timestamps(rows, |b| b.timestamp)?,
+            f64s(|b| b.open),
+            f64s(|b| b.high),
+            f64s(|b| b.low),
+            f64s(|b| b.close),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|b| b.volume))),
+            Arc::new(UInt64Array::from_iter(rows.iter().map(|b| b.trade_count))),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|b| b.vwap))),
+        ])
+    }
+}
+
+impl ArrowRecord for Quote {
+    fn fields() -> Vec<Field> {
+        vec![
+            timestamp_field(),
+            Field::new("bid_price", DataType::Float64, false),
+            Field::new("bid_size", DataType::UInt32, false),
+            Field::new("bid_exchange", DataType::Utf8, false),
+            Field::new("ask_price", DataType::Float64, false),
+            Field::new("ask_size", DataType::UInt32, false),
+            Field::new("ask_exchange", DataType::Utf8, false),
+            Field::new("tape", DataType::Utf8, false),
+        ]
+    }
+
+    fn columns(rows: &[Self]) -> Result<Vec<ArrayRef>> {
+        let strings = |f: fn(&Quote) -> &str| -> ArrayRef {
+            Arc::new(StringArray::from_iter_values(rows.iter().map(f)))
+        };
+        Ok(vec![
+            timestamps(rows, |q| q.timestamp)?,
+            Arc::new(Float64Array::from_iter_values(
+                rows.iter().map(|q| q.bid_price),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                rows.iter().map(|q| q.bid_size),
+            )),
+            strings(|q| &q.bid_exchange),
+            Arc::new(Float64Array::from_iter_values(
+                rows.iter().map(|q| q.ask_price),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                rows.iter().map(|q| q.ask_size),
+            )),
+            strings(|q| &q.ask_exchange),
+            strings(|q| &q.timeframe),
+        ])
+    }
+}
+
+impl ArrowRecord for Trade {
+    fn fields() -> Vec<Field> {
+        vec![
+            timestamp_field(),
+            Field::new("price", DataType::Float64, false),
+            Field::new("size", DataType::UInt32, false),
+            Field::new("exchange", DataType::Utf8, false),
+            Field::new(
+                "conditions",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                false,
+            ),
+            Field::new("id", DataType::UInt64, false),
+        ]
+    }
+
+    fn columns(rows: &[Self]) -> Result<Vec<ArrayRef>> {
+        let mut conditions = ListBuilder::new(StringBuilder::new());
+        for trade in rows {
+            for condition in &trade.conditions {
+                conditions.values().append_value(condition);
+            }
+            conditions.append(true);
+        }
+        Ok(vec![
+            timestamps(rows, |t| t.timestamp)?,
+            Arc::new(Float64Array::from_iter_values(rows.iter().map(|t| t.price))),
+            Arc::new(UInt32Array::from_iter_values(rows.iter().map(|t| t.size))),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|t| t.exchange.as_str()),
+            )),
+            Arc::new(conditions.finish()),
+            Arc::new(UInt64Array::from_iter_values(rows.iter().map(|t| t.id))),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, ListArray};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_bars_and_trades_to_record_batches() {
+        let at = Utc.with_ymd_and_hms(2024, 6, 3, 13, 30, 0).unwrap();
+        let bars: Vec<Bar> = (0..5)
+            .map(|i| Bar {
+                timestamp: at,
+                open: 1.0,
+                high: 2.0,
+                low: 0.5,
+                close: 1.5 + f64::from(i),
+                volume: 100,
+                trade_count: (i % 2 == 0).then_some(10),
+                vwap: None,
+            })
+            .collect();
+
+        let batch = to_record_batch(&bars).unwrap();
+        assert_eq!(batch.num_rows(), 5);
+        assert_eq!(batch.num_columns(), 8);
+        assert_eq!(batch.column(6).null_count(), 2);
+        let ts = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .unwrap();
+        assert_eq!(ts.value(0), at.timestamp_nanos_opt().unwrap());
+
+        let chunks: Vec<_> = record_batches(&bars, 2)
+            .map(|b| b.unwrap().num_rows())
+            .collect();
+        assert_eq!(chunks, vec![2, 2, 1]);
+
+        let trade = Trade {
+            timestamp: at,
+            price: 190.0,
+            size: 5,
+            exchange: "V".to_string(),
+            conditions: vec!["@".to_string(), "I".to_string()],
+            id: 7,
+        };
+        let data = HashMap::from([
+            ("MSFT".to_string(), vec![trade.clone()]),
+            ("AAPL".to_string(), vec![trade.clone(), trade]),
+        ]);
+        let batches: Vec<_> = symbol_record_batches(&data, 10)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].schema().field(0).name(), "symbol");
+        let symbols = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(symbols.value(1), "AAPL");
+        let conditions = batches[0]
+            .column(5)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(conditions.value_length(0), 2);
+    }
+}
@@ -0,0 +1,207 @@
+//! Opening and closing auction submission windows.
+//!
+//! Alpaca accepts market-on-open (`opg`) orders until 2 minutes before the
+//! open and market-on-close (`cls`) orders until 10 minutes before the
+//! close; orders arriving after the cutoff and before 19:00 ET are rejected.
+//! From 19:00 ET the orders queue for the next session. [`auction_timing`]
+//! turns the market [`Calendar`] into the window for the next auction and
+//! says whether an order can go out now or must wait for the window to
+//! open.
+
+use crate::error::{AlpacaError, Result};
+use crate::types::{Calendar, TimeInForce};
+use crate::utils::{SessionWindow, us_eastern_offset};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Minutes before the open after which `opg` orders are rejected.
+pub const OPG_CUTOFF_MINUTES: i64 = 2;
+/// Minutes before the close after which `cls` orders are rejected.
+pub const CLS_CUTOFF_MINUTES: i64 = 10;
+/// Hour (ET) at which auction orders for the next session are accepted.
+pub const AUCTION_WINDOW_OPEN_HOUR: u32 = 19;
+
+/// Opening or closing auction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Auction {
+    /// Opening auction (`opg`).
+    Open,
+    /// Closing auction (`cls`).
+    Close,
+}
+
+impl Auction {
+    /// The time in force that routes an order to this auction.
+    #[must_use]
+    pub fn time_in_force(&self) -> TimeInForce {
+        match self {
+            Self::Open => TimeInForce::Opg,
+            Self::Close => TimeInForce::Cls,
+        }
+    }
+
+    /// The auction an order with `tif` participates in, if any.
+    #[must_use]
+    pub fn from_time_in_force(tif: &TimeInForce) -> Option<Self> {
+        match tif {
+            TimeInForce::Opg => Some(Self::Open),
+            TimeInForce::Cls => Some(Self::Close),
+            _ => None,
+        }
+    }
+}
+
+/// Submission window for one auction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuctionWindow {
+    /// Auction.
+    pub auction: Auction,
+    /// Trading date of the auction.
+    pub session: NaiveDate,
+    /// First time orders are accepted.
+    pub opens: DateTime<Utc>,
+    /// Last time orders are accepted.
+    pub cutoff: DateTime<Utc>,
+}
+
+impl AuctionWindow {
+    /// The window for `auction` on `day`, opening at 19:00 ET on
+    /// `previous` (the prior trading date, or the prior calendar date when
+    /// unknown).
+    pub fn new(auction: Auction, day: &Calendar, previous: Option<NaiveDate>) -> Result<Self> {
+        let session = SessionWindow::from_calendar(day)?;
+        let cutoff = match auction {
+            Auction::Open => session.open - Duration::minutes(OPG_CUTOFF_MINUTES),
+            Auction::Close => session.close - Duration::minutes(CLS_CUTOFF_MINUTES),
+        };
+        let previous = previous
+            .or_else(|| session.date.pred_opt())
+            .ok_or_else(|| AlpacaError::InvalidData("calendar date out of range".to_string()))?;
+        let evening = NaiveTime::from_hms_opt(AUCTION_WINDOW_OPEN_HOUR, 0, 0)
+            .ok_or_else(|| AlpacaError::InvalidData("invalid window hour".to_string()))?;
+        let opens = us_eastern_offset(previous)
+            .from_local_datetime(&previous.and_time(evening))
+            .single()
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok_or_else(|| AlpacaError::InvalidData(format!("invalid window on {previous}")))?;
+        Ok(Self {
+            auction,
+            session: session.date,
+            opens,
+            cutoff,
+        })
+    }
+}
+
+/// When an auction order can be submitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "timing")]
+pub enum AuctionTiming {
+    /// The window is open: submit now.
+    Now(AuctionWindow),
+    /// The window has not opened yet: submit at `window.opens`.
+    Deferred(AuctionWindow),
+}
+
+impl AuctionTiming {
+    /// The targeted window.
+    #[must_use]
+    pub fn window(&self) -> &AuctionWindow {
+        match self {
+            Self::Now(window) | Self::Deferred(window) => window,
+        }
+    }
+}
+
+/// The next `auction` whose cutoff, less `buffer`, is still ahead of `now`,
+/// and whether its window is already open.
+///
+/// `calendar` must be in date order and cover the session targeted, e.g.
+/// today and the following week.
+///
+/// # Errors
+/// Returns [`AlpacaError::Validation`] if no session in `calendar` can
+/// still take the order, or [`AlpacaError::InvalidData`] for malformed
+/// calendar entries.
+pub fn auction_timing(
+    auction: Auction,
+    calendar: &[Calendar],
+    now: DateTime<Utc>,
+    buffer: Duration,
+) -> Result<AuctionTiming> {
+    let mut previous = None;
+    for day in calendar {
+        let window = AuctionWindow::new(auction, day, previous)?;
+        previous = Some(window.session);
+        if now > window.cutoff - buffer {
+            continue;
+        }
+        return Ok(if now >= window.opens {
+            AuctionTiming::Now(window)
+        } else {
+            AuctionTiming::Deferred(window)
+        });
+    }
+    Err(AlpacaError::Validation(format!(
+        "no {auction:?} auction in the calendar can still accept orders"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str) -> Calendar {
+        Calendar {
+            date: date.to_string(),
+            open: "09:30".to_string(),
+            close: "16:00".to_string(),
+            session_open: "04:00".to_string(),
+            session_close: "20:00".to_string(),
+        }
+    }
+
+    fn et(s: &str) -> DateTime<Utc> {
+        // June dates: US Eastern is UTC-4.
+        DateTime::parse_from_rfc3339(&format!("{s}-04:00"))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_auction_timing_windows() {
+        // Friday and Monday sessions.
+        let calendar = [day("2024-06-07"), day("2024-06-10")];
+        let buffer = Duration::minutes(1);
+
+        let timing = auction_timing(Auction::Open, &calendar, et("2024-06-07T08:00:00"), buffer);
+        let AuctionTiming::Now(window) = timing.unwrap() else {
+            panic!("expected the window to be open");
+        };
+        assert_eq!(window.cutoff, et("2024-06-07T09:28:00"));
+        assert_eq!(window.opens, et("2024-06-06T19:00:00"));
+
+        // Past Friday's cutoff (less buffer): Monday's window opens Friday 19:00.
+        let timing =
+            auction_timing(Auction::Open, &calendar, et("2024-06-07T09:27:30"), buffer).unwrap();
+        assert_eq!(
+            timing,
+            AuctionTiming::Deferred(AuctionWindow {
+                auction: Auction::Open,
+                session: NaiveDate::from_ymd_opt(2024, 6, 10).unwrap(),
+                opens: et("2024-06-07T19:00:00"),
+                cutoff: et("2024-06-10T09:28:00"),
+            })
+        );
+
+        let timing =
+            auction_timing(Auction::Close, &calendar, et("2024-06-07T15:45:00"), buffer).unwrap();
+        assert!(matches!(timing, AuctionTiming::Now(w) if w.cutoff == et("2024-06-07T15:50:00")));
+
+        assert!(
+            auction_timing(Auction::Close, &calendar, et("2024-06-10T16:00:00"), buffer).is_err()
+        );
+        assert_eq!(Auction::Close.time_in_force(), TimeInForce::Cls);
+    }
+}
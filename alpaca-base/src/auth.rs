@@ -4,11 +4,13 @@ use chrono::{DateTime, Utc};
 use hmac::{Hmac, KeyInit, Mac};
 use sha2::Sha256;
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::watch;
 
 type HmacSha256 = Hmac<Sha256>;
 
 /// Authentication credentials for Alpaca API.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Credentials {
     /// The API key for authentication.
     pub api_key: String,
@@ -94,6 +96,50 @@ impl Credentials {
     }
 }
 
+/// Shared, rotatable [`Credentials`].
+///
+/// Clients hold a clone of the handle and read the current credentials on
+/// every request or connection, so [`CredentialsHandle::rotate`] takes
+/// effect without rebuilding them. Streaming clients also watch the handle
+/// and re-authenticate when it changes.
+#[derive(Debug, Clone)]
+pub struct CredentialsHandle {
+    sender: Arc<watch::Sender<Credentials>>,
+}
+
+impl CredentialsHandle {
+    /// Create a handle holding `credentials`.
+    #[must_use]
+    pub fn new(credentials: Credentials) -> Self {
+        Self {
+            sender: Arc::new(watch::Sender::new(credentials)),
+        }
+    }
+
+    /// The current credentials.
+    #[must_use]
+    pub fn current(&self) -> Credentials {
+        self.sender.borrow().clone()
+    }
+
+    /// Replace the credentials, notifying every subscriber.
+    pub fn rotate(&self, credentials: Credentials) {
+        self.sender.send_replace(credentials);
+    }
+
+    /// A receiver that is notified on every rotation after this call.
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<Credentials> {
+        self.sender.subscribe()
+    }
+}
+
+impl From<Credentials> for CredentialsHandle {
+    fn from(credentials: Credentials) -> Self {
+        Self::new(credentials)
+    }
+}
+
 /// OAuth token for API access.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct OAuthToken {
@@ -0,0 +1,249 @@
+//! Endpoint, proxy and TLS settings shared by the HTTP and WebSocket
+//! clients.
+//!
+//! By default the clients talk to the URLs of their [`Environment`]. A
+//! [`ClientConfig`] overrides them, e.g. to point at a mock server, a
+//! recording proxy or the Broker API sandbox, routes traffic through an
+//! HTTP(S) proxy and trusts extra root certificates, e.g. a corporate
+//! proxy's CA, on top of the built-in roots.
+//!
+//! ```rust,ignore
+//! let config = ClientConfig::broker_sandbox()
+//!     .proxy(ProxyConfig::new("http://proxy.internal:3128").basic_auth("user", "pass"))
+//!     .add_root_certificate(std::fs::read("corp-ca.pem")?);
+//! let client = AlpacaHttpClient::with_config(credentials, config)?;
+//! ```
+
+use crate::types::Environment;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use std::fmt;
+use std::time::Duration;
+
+/// Broker API sandbox base URL.
+pub const BROKER_SANDBOX_URL: &str = "https://broker-api.sandbox.alpaca.markets";
+
+/// Broker API sandbox market data URL.
+pub const BROKER_SANDBOX_DATA_URL: &str = "https://data.sandbox.alpaca.markets";
+
+/// An HTTP(S) proxy, optionally with basic authentication.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy.internal:3128`.
+    pub url: String,
+    /// Basic authentication user name.
+    pub username: Option<String>,
+    /// Basic authentication password.
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Create a proxy without authentication.
+    #[must_use]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+        }
+    }
+
+    /// Authenticate to the proxy with `username` and `password`.
+    #[must_use]
+    pub fn basic_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// The `Proxy-Authorization` header value, if authentication is set.
+    #[must_use]
+    pub fn authorization(&self) -> Option<String> {
+        let username = self.username.as_deref()?;
+        let password = self.password.as_deref().unwrap_or_default();
+        Some(format!(
+            "Basic {}",
+            STANDARD.encode(format!("{username}:{password}"))
+        ))
+    }
+}
+
+impl fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// Endpoints, proxy and TLS roots of a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientConfig {
+    /// Environment, providing the default URLs.
+    pub environment: Environment,
+    /// Trading (or Broker) REST API base URL. `None` uses the
+    /// environment's.
+    pub base_url: Option<String>,
+    /// Market data REST API base URL. `None` uses the environment's.
+    pub data_url: Option<String>,
+    /// Trading stream URL. `None` uses the environment's.
+    pub stream_url: Option<String>,
+    /// Scheme and host of the market data and news streams, e.g.
+    /// `ws://localhost:8765`; the feed path is kept. `None` uses
+    /// `wss://stream.data.alpaca.markets`.
+    pub data_stream_url: Option<String>,
+    /// Proxy for every request and stream. `None` connects directly.
+    pub proxy: Option<ProxyConfig>,
+    /// PEM encoded root certificates trusted on top of the built-in roots.
+    pub root_certificates: Vec<Vec<u8>>,
+    /// HTTP request timeout.
+    pub timeout: Duration,
+}
+
+impl ClientConfig {
+    /// Create a configuration using the URLs of `environment`.
+    #[must_use]
+    pub fn new(environment: Environment) -> Self {
+        Self {
+            environment,
+            base_url: None,
+            data_url: None,
+            stream_url: None,
+            data_stream_url: None,
+            proxy: None,
+            root_certificates: Vec::new(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Create a configuration for the Broker API sandbox.
+    #[must_use]
+    pub fn broker_sandbox() -> Self {
+        Self::new(Environment::Paper)
+            .base_url(BROKER_SANDBOX_URL)
+            .data_url(BROKER_SANDBOX_DATA_URL)
+    }
+
+    /// Override the trading (or Broker) REST API base URL.
+    #[must_use]
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Override the market data REST API base URL.
+    #[must_use]
+    pub fn data_url(mut self, url: impl Into<String>) -> Self {
+        self.data_url = Some(url.into());
+        self
+    }
+
+    /// Override the trading stream URL.
+    #[must_use]
+    pub fn stream_url(mut self, url: impl Into<String>) -> Self {
+        self.stream_url = Some(url.into());
+        self
+    }
+
+    /// Override the scheme and host of the market data and news streams.
+    #[must_use]
+    pub fn data_stream_url(mut self, url: impl Into<String>) -> Self {
+        self.data_stream_url = Some(url.into());
+        self
+    }
+
+    /// Route requests and streams through `proxy`.
+    #[must_use]
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trust the PEM encoded root certificates in `pem` as well as the
+    /// built-in roots.
+    #[must_use]
+    pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Set the HTTP request timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The trading (or Broker) REST API base URL in effect.
+    #[must_use]
+    pub fn api_base_url(&self) -> &str {
+        self.base_url
+            .as_deref()
+            .unwrap_or_else(|| self.environment.base_url())
+    }
+
+    /// The market data REST API base URL in effect.
+    #[must_use]
+    pub fn data_base_url(&self) -> &str {
+        self.data_url
+            .as_deref()
+            .unwrap_or_else(|| self.environment.data_url())
+    }
+
+    /// The trading stream URL in effect.
+    #[must_use]
+    pub fn trading_stream_url(&self) -> &str {
+        self.stream_url
+            .as_deref()
+            .unwrap_or_else(|| self.environment.websocket_url())
+    }
+
+    /// The market data or news stream URL in effect for the default `url`:
+    /// its path on the overridden scheme and host, or `url` itself.
+    #[must_use]
+    pub fn data_stream_for(&self, url: &str) -> String {
+        let Some(base) = &self.data_stream_url else {
+            return url.to_string();
+        };
+        let path = url
+            .split_once("://")
+            .and_then(|(_, rest)| rest.find('/').map(|i| &rest[i..]))
+            .unwrap_or_default();
+        format!("{}{}", base.trim_end_matches('/'), path)
+    }
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self::new(Environment::Paper)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_config_overrides() {
+        let default = ClientConfig::new(Environment::Live);
+        assert_eq!(default.api_base_url(), "https://api.alpaca.markets");
+        assert_eq!(
+            default.trading_stream_url(),
+            Environment::Live.websocket_url()
+        );
+        let feed = "wss://stream.data.alpaca.markets/v2/iex";
+        assert_eq!(default.data_stream_for(feed), feed);
+
+        let sandbox = ClientConfig::broker_sandbox()
+            .data_stream_url("ws://localhost:8765/")
+            .proxy(ProxyConfig::new("http://proxy:3128").basic_auth("user", "pass"));
+        assert_eq!(sandbox.api_base_url(), BROKER_SANDBOX_URL);
+        assert_eq!(sandbox.data_base_url(), BROKER_SANDBOX_DATA_URL);
+        assert_eq!(sandbox.data_stream_for(feed), "ws://localhost:8765/v2/iex");
+
+        let proxy = sandbox.proxy.unwrap();
+        assert_eq!(proxy.authorization().unwrap(), "Basic dXNlcjpwYXNz");
+        assert!(!format!("{proxy:?}").contains("pass\""));
+    }
+}
@@ -0,0 +1,250 @@
+//! Synthetic quotes for multi-leg option combinations.
+//!
+//! A spread has no quote of its own; [`ComboQuoteBuilder`] derives one from
+//! the quotes of its legs. Buying the combo means paying the ask of every
+//! leg bought and receiving the bid of every leg sold, each times its
+//! ratio; selling the combo is the reverse. A negative net price is a
+//! credit. Sizes are the number of whole combos the displayed leg sizes can
+//! fill. Every leg quote must be younger than the configured maximum age,
+//! since a spread priced off one stale leg is mispriced as a whole.
+//!
+//! [`ComboQuote::limit_price`] turns the result into a limit price for the
+//! spread order, anywhere between joining the near side and crossing to the
+//! far side.
+
+use crate::error::{AlpacaError, Result};
+use crate::types::{OptionQuote, OrderSide};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Minimum price increment of a combo limit price.
+pub const COMBO_TICK: f64 = 0.01;
+
+/// One leg of a combination.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComboLeg {
+    /// Option contract symbol.
+    pub symbol: String,
+    /// Side of the leg when buying the combo.
+    pub side: OrderSide,
+    /// Contracts of this leg per combo.
+    pub ratio: u32,
+}
+
+/// Net quote for a combination.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComboQuote {
+    /// Legs.
+    pub legs: Vec<ComboLeg>,
+    /// Net price received for selling one combo.
+    pub net_bid: f64,
+    /// Net price paid for buying one combo.
+    pub net_ask: f64,
+    /// Combos the leg quotes can fill on the bid.
+    pub bid_size: u64,
+    /// Combos the leg quotes can fill on the ask.
+    pub ask_size: u64,
+    /// Timestamp of the oldest leg quote.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ComboQuote {
+    /// Midpoint of the net bid and ask.
+    #[must_use]
+    pub fn mid(&self) -> f64 {
+        (self.net_bid + self.net_ask) / 2.0
+    }
+
+    /// Width of the net market.
+    #[must_use]
+    pub fn spread(&self) -> f64 {
+        self.net_ask - self.net_bid
+    }
+
+    /// Limit price for trading the combo on `side`, rounded to
+    /// [`COMBO_TICK`] towards the passive side.
+    ///
+    /// `aggressiveness` runs from 0.0 (join the near side: the bid when
+    /// buying, the ask when selling) to 1.0 (cross to the far side); 0.5 is
+    /// the mid.
+    #[must_use]
+    pub fn limit_price(&self, side: OrderSide, aggressiveness: f64) -> f64 {
+        let aggressiveness = aggressiveness.clamp(0.0, 1.0);
+        let ticks = |price: f64| (price / COMBO_TICK * 1e6).round() / 1e6;
+        let price = match side {
+            OrderSide::Buy => {
+                ticks(self.net_bid + self.spread() * aggressiveness).floor() * COMBO_TICK
+            }
+            OrderSide::Sell => {
+                ticks(self.net_ask - self.spread() * aggressiveness).ceil() * COMBO_TICK
+            }
+        };
+        (price * 100.0).round() / 100.0
+    }
+}
+
+/// Builds [`ComboQuote`]s from leg quotes.
+#[derive(Debug, Clone, Default)]
+pub struct ComboQuoteBuilder {
+    legs: Vec<ComboLeg>,
+    max_age: Option<Duration>,
+}
+
+impl ComboQuoteBuilder {
+    /// Create a builder with no legs and no staleness limit.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a leg.
+    #[must_use]
+    pub fn leg(mut self, symbol: impl Into<String>, side: OrderSide, ratio: u32) -> Self {
+        self.legs.push(ComboLeg {
+            symbol: symbol.into(),
+            side,
+            ratio,
+        });
+        self
+    }
+
+    /// Reject leg quotes older than `max_age`.
+    #[must_use]
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// The legs added so far.
+    #[must_use]
+    pub fn legs(&self) -> &[ComboLeg] {
+        &self.legs
+    }
+
+    /// Net quote from `quotes`, keyed by contract symbol, as of `now`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if there are no legs, a ratio
+    /// is zero, or a leg quote is missing or stale, and
+    /// [`AlpacaError::InvalidData`] if a leg quote is crossed.
+    pub fn build(
+        &self,
+        quotes: &HashMap<String, OptionQuote>,
+        now: DateTime<Utc>,
+    ) -> Result<ComboQuote> {
+        if self.legs.is_empty() {
+            return Err(AlpacaError::Validation("combo has no legs".to_string()));
+        }
+        let mut net_bid = 0.0;
+        let mut net_ask = 0.0;
+        let mut bid_size = u64::MAX;
+        let mut ask_size = u64::MAX;
+        let mut timestamp = now;
+        for leg in &self.legs {
+            if leg.ratio == 0 {
+                return Err(AlpacaError::Validation(format!(
+                    "leg {} has a zero ratio",
+                    leg.symbol
+                )));
+            }
+            let quote = quotes.get(&leg.symbol).ok_or_else(|| {
+                AlpacaError::Validation(format!("no quote for leg {}", leg.symbol))
+            })?;
+            if let Some(max_age) = self.max_age
+                && now - quote.timestamp > max_age
+            {
+                return Err(AlpacaError::Validation(format!(
+                    "quote for leg {} is stale ({})",
+                    leg.symbol, quote.timestamp
+                )));
+            }
+            if quote.ask_price > 0.0 && quote.bid_price > quote.ask_price {
+                return Err(AlpacaError::InvalidData(format!(
+                    "crossed quote for leg {}: bid {} > ask {}",
+                    leg.symbol, quote.bid_price, quote.ask_price
+                )));
+            }
+            let ratio = f64::from(leg.ratio);
+            let per_combo = |size: u64| size / u64::from(leg.ratio);
+            match leg.side {
+                OrderSide::Buy => {
+                    net_ask += quote.ask_price * ratio;
+                    net_bid += quote.bid_price * ratio;
+                    ask_size = ask_size.min(per_combo(quote.ask_size));
+                    bid_size = bid_size.min(per_combo(quote.bid_size));
+                }
+                OrderSide::Sell => {
+                    net_ask -= quote.bid_price * ratio;
+                    net_bid -= quote.ask_price * ratio;
+                    ask_size = ask_size.min(per_combo(quote.bid_size));
+                    bid_size = bid_size.min(per_combo(quote.ask_size));
+                }
+            }
+            timestamp = timestamp.min(quote.timestamp);
+        }
+        Ok(ComboQuote {
+            legs: self.legs.clone(),
+            net_bid,
+            net_ask,
+            bid_size,
+            ask_size,
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(bid: f64, ask: f64, size: u64, at: DateTime<Utc>) -> OptionQuote {
+        OptionQuote {
+            timestamp: at,
+            bid_price: bid,
+            bid_size: size,
+            ask_price: ask,
+            ask_size: size,
+            bid_exchange: "C".to_string(),
+            ask_exchange: "C".to_string(),
+            conditions: None,
+        }
+    }
+
+    #[test]
+    fn test_call_butterfly_net_quote() {
+        let now = Utc::now();
+        let quotes = HashMap::from([
+            ("C100".to_string(), quote(5.00, 5.20, 10, now)),
+            ("C105".to_string(), quote(2.40, 2.50, 30, now)),
+            (
+                "C110".to_string(),
+                quote(0.90, 1.00, 10, now - Duration::seconds(2)),
+            ),
+        ]);
+        let builder = ComboQuoteBuilder::new()
+            .leg("C100", OrderSide::Buy, 1)
+            .leg("C105", OrderSide::Sell, 2)
+            .leg("C110", OrderSide::Buy, 1)
+            .max_age(Duration::seconds(5));
+
+        let combo = builder.build(&quotes, now).unwrap();
+        // Buy: 5.20 + 1.00 - 2 * 2.40; sell: 5.00 + 0.90 - 2 * 2.50.
+        assert!((combo.net_ask - 1.40).abs() < 1e-9);
+        assert!((combo.net_bid - 0.90).abs() < 1e-9);
+        assert_eq!(combo.ask_size, 10);
+        assert_eq!(combo.bid_size, 10);
+        assert_eq!(combo.timestamp, now - Duration::seconds(2));
+        assert_eq!(combo.limit_price(OrderSide::Buy, 0.5), 1.15);
+        assert_eq!(combo.limit_price(OrderSide::Sell, 0.0), 1.40);
+        assert_eq!(combo.limit_price(OrderSide::Buy, 1.0), 1.40);
+
+        assert!(builder.build(&quotes, now + Duration::seconds(4)).is_err());
+        assert!(
+            ComboQuoteBuilder::new()
+                .leg("C120", OrderSide::Buy, 1)
+                .build(&quotes, now)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Pluggable sources of API credentials.
+//!
+//! [`CredentialProvider`] is the interface a credential store implements:
+//! environment variables ([`EnvCredentialProvider`]), the OS keyring
+//! ([`KeyringCredentialProvider`], with the `keyring` feature), or any
+//! async secret fetcher such as AWS Secrets Manager or Vault
+//! ([`SecretFetcherProvider`]).
+//!
+//! Clients take a [`CredentialsHandle`]; [`CredentialsHandle::from_provider`]
+//! builds one from a provider, and [`watch_credentials`] re-reads the
+//! provider on an interval and rotates the handle when the keys change, so
+//! running clients pick up rotated keys without a restart.
+
+use crate::auth::{Credentials, CredentialsHandle};
+use crate::error::{AlpacaError, Result};
+use crate::retry::CancellationToken;
+use serde::Deserialize;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// A source of API credentials.
+pub trait CredentialProvider: Send + Sync {
+    /// Fetch the current credentials.
+    fn credentials(&self) -> impl Future<Output = Result<Credentials>> + Send;
+}
+
+/// Credentials from environment variables.
+///
+/// Defaults to `ALPACA_API_KEY` and `ALPACA_API_SECRET`, falling back to
+/// `ALPACA_SECRET_KEY`, like [`Credentials::from_env`]. Variables are read
+/// on every call, but a `.env` file is only loaded by `from_env`.
+#[derive(Debug, Clone)]
+pub struct EnvCredentialProvider {
+    key_var: String,
+    secret_vars: Vec<String>,
+}
+
+impl Default for EnvCredentialProvider {
+    fn default() -> Self {
+        Self {
+            key_var: "ALPACA_API_KEY".to_string(),
+            secret_vars: vec![
+                "ALPACA_API_SECRET".to_string(),
+                "ALPACA_SECRET_KEY".to_string(),
+            ],
+        }
+    }
+}
+
+impl EnvCredentialProvider {
+    /// Create a provider reading the default variables.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the API key from `name`.
+    #[must_use]
+    pub fn key_var(mut self, name: &str) -> Self {
+        self.key_var = name.to_string();
+        self
+    }
+
+    /// Read the secret key from `name` only.
+    #[must_use]
+    pub fn secret_var(mut self, name: &str) -> Self {
+        self.secret_vars = vec![name.to_string()];
+        self
+    }
+
+    fn read(&self) -> Result<Credentials> {
+        let api_key = std::env::var(&self.key_var)
+            .map_err(|_| AlpacaError::Config(format!("{} not found", self.key_var)))?;
+        let secret_key = self
+            .secret_vars
+            .iter()
+            .find_map(|name| std::env::var(name).ok())
+            .ok_or_else(|| {
+                AlpacaError::Config(format!("{} not found", self.secret_vars.join(" or ")))
+            })?;
+        Ok(Credentials::new(api_key, secret_key))
+    }
+}
+
+impl CredentialProvider for EnvCredentialProvider {
+    fn credentials(&self) -> impl Future<Output = Result<Credentials>> + Send {
+        std::future::ready(self.read())
+    }
+}
+
+/// Credentials fetched by an async closure.
+///
+/// Wraps a call to a secret manager. Secrets stored as JSON can be decoded
+/// with [`Credentials::from_json`]:
+///
+/// ```
+/// use alpaca_base::{Credentials, SecretFetcherProvider};
+///
+/// let provider = SecretFetcherProvider::new(|| async {
+///     // e.g. the `SecretString` of an AWS Secrets Manager secret
+///     Credentials::from_json(r#"{"api_key":"PK123","secret_key":"abc"}"#)
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecretFetcherProvider<F> {
+    fetch: F,
+}
+
+impl<F, Fut> SecretFetcherProvider<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Credentials>> + Send,
+{
+    /// Create a provider calling `fetch` for every request.
+    pub fn new(fetch: F) -> Self {
+        Self { fetch }
+    }
+}
+
+impl<F, Fut> CredentialProvider for SecretFetcherProvider<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Credentials>> + Send,
+{
+    fn credentials(&self) -> impl Future<Output = Result<Credentials>> + Send {
+        (self.fetch)()
+    }
+}
+
+/// Credentials stored in the OS keyring (macOS Keychain, Windows
+/// Credential Manager, Linux kernel keyutils).
+///
+/// The API key and secret key are two entries of `service`, with the users
+/// `api_key` and `secret_key`.
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone)]
+pub struct KeyringCredentialProvider {
+    service: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringCredentialProvider {
+    /// Keyring user holding the API key.
+    pub const API_KEY_USER: &'static str = "api_key";
+    /// Keyring user holding the secret key.
+    pub const SECRET_KEY_USER: &'static str = "secret_key";
+
+    /// Create a provider reading the entries of `service`.
+    #[must_use]
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+        }
+    }
+
+    /// Save `credentials` under this provider's service.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Config`] if the keyring rejects the entry.
+    pub fn store(&self, credentials: &Credentials) -> Result<()> {
+        self.entry(Self::API_KEY_USER)
+            .and_then(|entry| entry.set_password(&credentials.api_key))
+            .and_then(|()| self.entry(Self::SECRET_KEY_USER))
+            .and_then(|entry| entry.set_password(&credentials.secret_key))
+            .map_err(|e| AlpacaError::Config(format!("Keyring write failed: {e}")))
+    }
+
+    fn entry(&self, user: &str) -> keyring::Result<keyring::Entry> {
+        keyring::Entry::new(&self.service, user)
+    }
+
+    fn read(&self) -> Result<Credentials> {
+        let get = |user| {
+            self.entry(user)
+                .and_then(|entry| entry.get_password())
+                .map_err(|e| {
+                    AlpacaError::Config(format!(
+                        "Keyring entry {}/{user} unavailable: {e}",
+                        self.service
+                    ))
+                })
+        };
+        Ok(Credentials::new(
+            get(Self::API_KEY_USER)?,
+            get(Self::SECRET_KEY_USER)?,
+        ))
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl CredentialProvider for KeyringCredentialProvider {
+    fn credentials(&self) -> impl Future<Output = Result<Credentials>> + Send {
+        let provider = self.clone();
+        async move {
+            tokio::task::spawn_blocking(move || provider.read())
+                .await
+                .map_err(|e| AlpacaError::Config(format!("Keyring lookup panicked: {e}")))?
+        }
+    }
+}
+
+/// Field names accepted by [`Credentials::from_json`].
+#[derive(Deserialize)]
+struct SecretPayload {
+    #[serde(alias = "ALPACA_API_KEY", alias = "APCA_API_KEY_ID", alias = "key_id")]
+    api_key: String,
+    #[serde(
+        alias = "ALPACA_API_SECRET",
+        alias = "ALPACA_SECRET_KEY",
+        alias = "APCA_API_SECRET_KEY",
+        alias = "secret"
+    )]
+    secret_key: String,
+}
+
+impl Credentials {
+    /// Decode credentials from a JSON secret.
+    ///
+    /// Accepts `api_key`/`secret_key`, the `ALPACA_*` environment variable
+    /// names, or the `APCA_API_KEY_ID`/`APCA_API_SECRET_KEY` header names.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Json`] if the secret has neither form.
+    pub fn from_json(secret: &str) -> Result<Self> {
+        let payload: SecretPayload = serde_json::from_str(secret)?;
+        Ok(Self::new(payload.api_key, payload.secret_key))
+    }
+}
+
+impl CredentialsHandle {
+    /// Create a handle holding the credentials `provider` returns now.
+    pub async fn from_provider<P: CredentialProvider>(provider: &P) -> Result<Self> {
+        Ok(Self::new(provider.credentials().await?))
+    }
+}
+
+/// Re-read `provider` every `interval` and rotate `handle` whenever the
+/// credentials change, until `cancel` is cancelled.
+///
+/// Fetch failures are logged and the current credentials are kept until
+/// the next attempt.
+pub async fn watch_credentials<P: CredentialProvider>(
+    provider: &P,
+    handle: &CredentialsHandle,
+    interval: Duration,
+    cancel: &CancellationToken,
+) {
+    loop {
+        tokio::select! {
+            () = cancel.cancelled() => return,
+            () = tokio::time::sleep(interval) => {}
+        }
+        match provider.credentials().await {
+            Ok(credentials) if credentials != handle.current() => {
+                info!("Credentials changed, rotating");
+                handle.rotate(credentials);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Credential refresh failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_from_json_accepts_known_names() {
+        let plain = Credentials::from_json(r#"{"api_key":"PK1","secret_key":"s1"}"#).unwrap();
+        assert_eq!(plain, Credentials::new("PK1".into(), "s1".into()));
+        let headers =
+            Credentials::from_json(r#"{"APCA_API_KEY_ID":"PK2","APCA_API_SECRET_KEY":"s2"}"#)
+                .unwrap();
+        assert_eq!(headers, Credentials::new("PK2".into(), "s2".into()));
+        assert!(Credentials::from_json(r#"{"api_key":"PK3"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_env_provider_reports_missing_variable() {
+        let provider = EnvCredentialProvider::new()
+            .key_var("ALPACA_TEST_UNSET_KEY_VAR")
+            .secret_var("ALPACA_TEST_UNSET_SECRET_VAR");
+        let err = provider.credentials().await.unwrap_err();
+        assert!(err.to_string().contains("ALPACA_TEST_UNSET_KEY_VAR"));
+    }
+
+    #[tokio::test]
+    async fn test_watch_credentials_rotates_on_change() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let provider = SecretFetcherProvider::new(move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match n {
+                    0 | 1 => Ok(Credentials::new("PK1".into(), "s1".into())),
+                    2 => Err(AlpacaError::Network("vault unreachable".into())),
+                    _ => Ok(Credentials::new("PK2".into(), "s2".into())),
+                }
+            }
+        });
+        let handle = CredentialsHandle::from_provider(&provider).await.unwrap();
+        let mut rotations = handle.subscribe();
+        let cancel = CancellationToken::new();
+
+        let watcher = {
+            let (handle, cancel) = (handle.clone(), cancel.clone());
+            tokio::spawn(async move {
+                watch_credentials(&provider, &handle, Duration::from_millis(5), &cancel).await;
+            })
+        };
+
+        rotations.changed().await.unwrap();
+        assert_eq!(handle.current().api_key, "PK2");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        cancel.cancel();
+        watcher.await.unwrap();
+    }
+}
@@ -0,0 +1,418 @@
+//! Intraday drawdown monitoring with automatic de-risking.
+//!
+//! [`DrawdownMonitor`] tracks the session's equity high-water mark from
+//! live P&L updates and escalates through [`DrawdownLevel`]s as the
+//! drawdown from that peak crosses the configured [`DrawdownThresholds`].
+//! Each escalation runs the [`DrawdownAction`]s configured for the level:
+//! logging, shrinking the position-size multiplier that order sizing reads
+//! through [`DrawdownMonitor::size_multiplier`], or tripping a shared
+//! [`KillSwitch`]. Every escalation and action is kept in an audit log.
+//!
+//! Levels only escalate within a session; a recovery in equity does not
+//! undo a reduction or a halt. Call [`DrawdownMonitor::start_session`] to
+//! reset for the next session.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Shared flag that stops new risk from being taken once tripped.
+#[derive(Debug, Clone, Default)]
+pub struct KillSwitch(Arc<AtomicBool>);
+
+impl KillSwitch {
+    /// Create an untripped kill switch.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trip the switch.
+    pub fn trip(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Reset the switch.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the switch has been tripped.
+    #[must_use]
+    pub fn is_tripped(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Drawdown severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DrawdownLevel {
+    /// Below every threshold.
+    Normal,
+    /// Warning threshold crossed.
+    Warn,
+    /// Reduce threshold crossed.
+    Reduce,
+    /// Halt threshold crossed.
+    Halt,
+}
+
+impl fmt::Display for DrawdownLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Normal => "normal",
+            Self::Warn => "warn",
+            Self::Reduce => "reduce",
+            Self::Halt => "halt",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Drawdown thresholds, as fractions of the session's peak equity
+/// (0.02 = 2%).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawdownThresholds {
+    /// Drawdown that raises a warning.
+    pub warn: f64,
+    /// Drawdown that reduces position sizes.
+    pub reduce: f64,
+    /// Drawdown that halts trading.
+    pub halt: f64,
+}
+
+impl Default for DrawdownThresholds {
+    fn default() -> Self {
+        Self {
+            warn: 0.01,
+            reduce: 0.02,
+            halt: 0.03,
+        }
+    }
+}
+
+impl DrawdownThresholds {
+    /// Create thresholds; they are expected to increase from `warn` to
+    /// `halt`.
+    #[must_use]
+    pub fn new(warn: f64, reduce: f64, halt: f64) -> Self {
+        Self { warn, reduce, halt }
+    }
+
+    /// Level for a drawdown.
+    #[must_use]
+    pub fn level(&self, drawdown: f64) -> DrawdownLevel {
+        if drawdown >= self.halt {
+            DrawdownLevel::Halt
+        } else if drawdown >= self.reduce {
+            DrawdownLevel::Reduce
+        } else if drawdown >= self.warn {
+            DrawdownLevel::Warn
+        } else {
+            DrawdownLevel::Normal
+        }
+    }
+}
+
+/// Action taken when a level is reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawdownAction {
+    /// Log the escalation.
+    Log,
+    /// Cap the position-size multiplier at the given value.
+    ScalePositionSize(f64),
+    /// Trip the kill switch.
+    TripKillSwitch,
+}
+
+/// One entry of the audit log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawdownAuditEntry {
+    /// When it happened.
+    pub at: DateTime<Utc>,
+    /// Level reached.
+    pub level: DrawdownLevel,
+    /// Drawdown from the peak at the time.
+    pub drawdown: f64,
+    /// Equity at the time.
+    pub equity: f64,
+    /// Session peak equity at the time.
+    pub peak: f64,
+    /// Action taken.
+    pub action: DrawdownAction,
+}
+
+/// Escalation reported by [`DrawdownMonitor::update`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawdownEvent {
+    /// Level before the update.
+    pub from: DrawdownLevel,
+    /// Level after the update.
+    pub to: DrawdownLevel,
+    /// Drawdown from the peak.
+    pub drawdown: f64,
+    /// Actions taken.
+    pub actions: Vec<DrawdownAction>,
+}
+
+/// Tracks intraday drawdown and de-risks as thresholds are crossed.
+#[derive(Debug)]
+pub struct DrawdownMonitor {
+    thresholds: DrawdownThresholds,
+    actions: HashMap<DrawdownLevel, Vec<DrawdownAction>>,
+    kill_switch: KillSwitch,
+    start_equity: f64,
+    peak: f64,
+    equity: f64,
+    max_drawdown: f64,
+    level: DrawdownLevel,
+    size_multiplier: f64,
+    audit: Vec<DrawdownAuditEntry>,
+}
+
+impl DrawdownMonitor {
+    /// Create a monitor for a session starting at `start_equity`.
+    ///
+    /// By default every level logs, `Reduce` halves position sizes and
+    /// `Halt` trips the kill switch.
+    #[must_use]
+    pub fn new(thresholds: DrawdownThresholds, start_equity: f64) -> Self {
+        let actions = HashMap::from([
+            (DrawdownLevel::Warn, vec![DrawdownAction::Log]),
+            (
+                DrawdownLevel::Reduce,
+                vec![DrawdownAction::Log, DrawdownAction::ScalePositionSize(0.5)],
+            ),
+            (
+                DrawdownLevel::Halt,
+                vec![DrawdownAction::Log, DrawdownAction::TripKillSwitch],
+            ),
+        ]);
+        Self {
+            thresholds,
+            actions,
+            kill_switch: KillSwitch::new(),
+            start_equity,
+            peak: start_equity,
+            equity: start_equity,
+            max_drawdown: 0.0,
+            level: DrawdownLevel::Normal,
+            size_multiplier: 1.0,
+            audit: Vec::new(),
+        }
+    }
+
+    /// Use a shared kill switch.
+    #[must_use]
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitch) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+
+    /// Replace the actions run when `level` is reached.
+    #[must_use]
+    pub fn with_actions(mut self, level: DrawdownLevel, actions: Vec<DrawdownAction>) -> Self {
+        self.actions.insert(level, actions);
+        self
+    }
+
+    /// Reset for a new session starting at `start_equity`. The kill switch
+    /// is left as it is.
+    pub fn start_session(&mut self, start_equity: f64) {
+        self.start_equity = start_equity;
+        self.peak = start_equity;
+        self.equity = start_equity;
+        self.max_drawdown = 0.0;
+        self.level = DrawdownLevel::Normal;
+        self.size_multiplier = 1.0;
+    }
+
+    /// Record the session's P&L so far.
+    pub fn update_pnl(&mut self, pnl: f64, at: DateTime<Utc>) -> Option<DrawdownEvent> {
+        self.update(self.start_equity + pnl, at)
+    }
+
+    /// Record current equity. Returns the escalation, if any.
+    pub fn update(&mut self, equity: f64, at: DateTime<Utc>) -> Option<DrawdownEvent> {
+        self.equity = equity;
+        self.peak = self.peak.max(equity);
+        let drawdown = self.drawdown();
+        self.max_drawdown = self.max_drawdown.max(drawdown);
+
+        let level = self.thresholds.level(drawdown);
+        if level <= self.level {
+            return None;
+        }
+        let from = self.level;
+        self.level = level;
+
+        // Run the actions of every level skipped over as well, so a gap
+        // straight to halt still reduces sizes.
+        let mut actions = Vec::new();
+        for reached in [
+            DrawdownLevel::Warn,
+            DrawdownLevel::Reduce,
+            DrawdownLevel::Halt,
+        ] {
+            if reached > from && reached <= level {
+                for action in self.actions.get(&reached).cloned().unwrap_or_default() {
+                    self.run(action, reached, drawdown, at);
+                    actions.push(action);
+                }
+            }
+        }
+        Some(DrawdownEvent {
+            from,
+            to: level,
+            drawdown,
+            actions,
+        })
+    }
+
+    fn run(
+        &mut self,
+        action: DrawdownAction,
+        level: DrawdownLevel,
+        drawdown: f64,
+        at: DateTime<Utc>,
+    ) {
+        match action {
+            DrawdownAction::Log => tracing::warn!(
+                level = %level,
+                drawdown_pct = drawdown * 100.0,
+                equity = self.equity,
+                peak = self.peak,
+                "intraday drawdown threshold crossed"
+            ),
+            DrawdownAction::ScalePositionSize(multiplier) => {
+                self.size_multiplier = self.size_multiplier.min(multiplier.clamp(0.0, 1.0));
+                tracing::warn!(multiplier = self.size_multiplier, "position sizes reduced");
+            }
+            DrawdownAction::TripKillSwitch => {
+                self.kill_switch.trip();
+                tracing::error!(drawdown_pct = drawdown * 100.0, "kill switch tripped");
+            }
+        }
+        self.audit.push(DrawdownAuditEntry {
+            at,
+            level,
+            drawdown,
+            equity: self.equity,
+            peak: self.peak,
+            action,
+        });
+    }
+
+    /// Current drawdown from the session peak, as a fraction.
+    #[must_use]
+    pub fn drawdown(&self) -> f64 {
+        if self.peak <= 0.0 {
+            0.0
+        } else {
+            ((self.peak - self.equity) / self.peak).max(0.0)
+        }
+    }
+
+    /// Largest drawdown seen this session.
+    #[must_use]
+    pub fn max_drawdown(&self) -> f64 {
+        self.max_drawdown
+    }
+
+    /// Session peak equity.
+    #[must_use]
+    pub fn peak(&self) -> f64 {
+        self.peak
+    }
+
+    /// Highest level reached this session.
+    #[must_use]
+    pub fn level(&self) -> DrawdownLevel {
+        self.level
+    }
+
+    /// Multiplier to apply to position sizes (1.0 when not reduced).
+    #[must_use]
+    pub fn size_multiplier(&self) -> f64 {
+        self.size_multiplier
+    }
+
+    /// Whether new risk may be taken.
+    #[must_use]
+    pub fn trading_allowed(&self) -> bool {
+        !self.kill_switch.is_tripped()
+    }
+
+    /// The kill switch.
+    #[must_use]
+    pub fn kill_switch(&self) -> &KillSwitch {
+        &self.kill_switch
+    }
+
+    /// Every action taken, oldest first.
+    #[must_use]
+    pub fn audit_log(&self) -> &[DrawdownAuditEntry] {
+        &self.audit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escalation_runs_actions_once() {
+        let now = Utc::now();
+        let mut monitor = DrawdownMonitor::new(DrawdownThresholds::default(), 100_000.0);
+        assert!(monitor.update_pnl(2_000.0, now).is_none());
+        assert_eq!(monitor.peak(), 102_000.0);
+
+        // 1.5% off the peak.
+        let event = monitor.update_pnl(470.0, now).unwrap();
+        assert_eq!(event.to, DrawdownLevel::Warn);
+        assert_eq!(event.actions, vec![DrawdownAction::Log]);
+        assert!(monitor.update_pnl(400.0, now).is_none());
+
+        // Gap straight past reduce to halt.
+        let event = monitor.update_pnl(-2_000.0, now).unwrap();
+        assert_eq!(
+            (event.from, event.to),
+            (DrawdownLevel::Warn, DrawdownLevel::Halt)
+        );
+        assert_eq!(monitor.size_multiplier(), 0.5);
+        assert!(!monitor.trading_allowed());
+        assert_eq!(monitor.audit_log().len(), 5);
+
+        // Recovery does not de-escalate.
+        assert!(monitor.update_pnl(2_000.0, now).is_none());
+        assert_eq!(monitor.level(), DrawdownLevel::Halt);
+        assert!(monitor.max_drawdown() > 0.03);
+    }
+
+    #[test]
+    fn test_custom_actions_and_shared_kill_switch() {
+        let now = Utc::now();
+        let switch = KillSwitch::new();
+        let mut monitor = DrawdownMonitor::new(DrawdownThresholds::new(0.01, 0.02, 0.05), 50_000.0)
+            .with_kill_switch(switch.clone())
+            .with_actions(
+                DrawdownLevel::Reduce,
+                vec![
+                    DrawdownAction::ScalePositionSize(0.25),
+                    DrawdownAction::TripKillSwitch,
+                ],
+            );
+        monitor.update(48_900.0, now);
+        assert_eq!(monitor.size_multiplier(), 0.25);
+        assert!(switch.is_tripped());
+
+        monitor.start_session(48_900.0);
+        assert_eq!(monitor.level(), DrawdownLevel::Normal);
+        assert_eq!(monitor.size_multiplier(), 1.0);
+        assert!(switch.is_tripped());
+        switch.reset();
+        assert!(monitor.trading_allowed());
+    }
+}
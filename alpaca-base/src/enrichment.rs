@@ -0,0 +1,320 @@
+//! Instrument classification from external providers.
+//!
+//! Alpaca does not publish sectors, industries, market caps or betas.
+//! [`InstrumentEnricher`] is the interface a provider of that data
+//! implements (a vendor API, a file, a database); [`StaticEnricher`] serves
+//! a fixed table, and [`CachedEnricher`] wraps any provider with a
+//! per-symbol cache so portfolio constraints and reports can look up
+//! classifications repeatedly without refetching them.
+
+use crate::error::Result;
+use crate::portfolio::PortfolioTracker;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// Bucket for positions without a known sector.
+pub const UNCLASSIFIED: &str = "Unclassified";
+
+/// Classification of one instrument.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct InstrumentProfile {
+    /// Symbol.
+    pub symbol: String,
+    /// Sector, e.g. "Technology".
+    pub sector: Option<String>,
+    /// Industry, e.g. "Semiconductors".
+    pub industry: Option<String>,
+    /// Market capitalization in dollars.
+    pub market_cap: Option<f64>,
+    /// Beta against the market.
+    pub beta: Option<f64>,
+}
+
+impl InstrumentProfile {
+    /// Create an empty profile for `symbol`.
+    #[must_use]
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the sector.
+    #[must_use]
+    pub fn sector(mut self, sector: impl Into<String>) -> Self {
+        self.sector = Some(sector.into());
+        self
+    }
+
+    /// Set the industry.
+    #[must_use]
+    pub fn industry(mut self, industry: impl Into<String>) -> Self {
+        self.industry = Some(industry.into());
+        self
+    }
+
+    /// Set the market capitalization.
+    #[must_use]
+    pub fn market_cap(mut self, market_cap: f64) -> Self {
+        self.market_cap = Some(market_cap);
+        self
+    }
+
+    /// Set the beta.
+    #[must_use]
+    pub fn beta(mut self, beta: f64) -> Self {
+        self.beta = Some(beta);
+        self
+    }
+}
+
+/// A source of instrument classifications.
+pub trait InstrumentEnricher: Send + Sync {
+    /// Profiles for `symbols`. Symbols the provider does not know are
+    /// left out of the result.
+    fn profiles(
+        &self,
+        symbols: &[String],
+    ) -> impl Future<Output = Result<HashMap<String, InstrumentProfile>>> + Send;
+
+    /// The profile for one symbol.
+    fn profile(
+        &self,
+        symbol: &str,
+    ) -> impl Future<Output = Result<Option<InstrumentProfile>>> + Send {
+        let symbols = [symbol.to_string()];
+        async move { Ok(self.profiles(&symbols).await?.remove(symbol)) }
+    }
+}
+
+/// An enricher serving a fixed table, e.g. loaded from a JSON file.
+#[derive(Debug, Clone, Default)]
+pub struct StaticEnricher {
+    profiles: HashMap<String, InstrumentProfile>,
+}
+
+impl StaticEnricher {
+    /// Create an enricher serving `profiles`.
+    #[must_use]
+    pub fn new(profiles: impl IntoIterator<Item = InstrumentProfile>) -> Self {
+        Self {
+            profiles: profiles
+                .into_iter()
+                .map(|profile| (profile.symbol.clone(), profile))
+                .collect(),
+        }
+    }
+
+    /// Load profiles from a JSON array.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Json`](crate::AlpacaError::Json) if the JSON
+    /// is not an array of profiles.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(Self::new(serde_json::from_str::<Vec<InstrumentProfile>>(
+            json,
+        )?))
+    }
+}
+
+impl InstrumentEnricher for StaticEnricher {
+    async fn profiles(&self, symbols: &[String]) -> Result<HashMap<String, InstrumentProfile>> {
+        Ok(symbols
+            .iter()
+            .filter_map(|symbol| {
+                self.profiles
+                    .get(symbol)
+                    .map(|profile| (symbol.clone(), profile.clone()))
+            })
+            .collect())
+    }
+}
+
+/// Caches another enricher's profiles by symbol.
+///
+/// Only symbols missing from the cache, or cached longer than the TTL, are
+/// requested from the provider, in one batch. Symbols the provider does not
+/// know are cached as unknown too, so they are not requested again until
+/// the TTL passes.
+#[derive(Debug)]
+pub struct CachedEnricher<E> {
+    inner: E,
+    ttl: Duration,
+    cache: RwLock<HashMap<String, (Instant, Option<InstrumentProfile>)>>,
+}
+
+impl<E: InstrumentEnricher> CachedEnricher<E> {
+    /// Cache `inner`'s profiles for `ttl`.
+    #[must_use]
+    pub fn new(inner: E, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Drop the cached profile of `symbol`.
+    pub fn invalidate(&self, symbol: &str) {
+        self.cache
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(symbol);
+    }
+
+    /// Drop every cached profile.
+    pub fn clear(&self) {
+        self.cache
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// The wrapped provider.
+    #[must_use]
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+}
+
+impl<E: InstrumentEnricher> InstrumentEnricher for CachedEnricher<E> {
+    async fn profiles(&self, symbols: &[String]) -> Result<HashMap<String, InstrumentProfile>> {
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let cache = self.cache.read().unwrap_or_else(|e| e.into_inner());
+            for symbol in symbols {
+                match cache.get(symbol) {
+                    Some((fetched, Some(profile))) if fetched.elapsed() < self.ttl => {
+                        found.insert(symbol.clone(), profile.clone());
+                    }
+                    Some((fetched, None)) if fetched.elapsed() < self.ttl => {}
+                    _ if !missing.contains(symbol) => missing.push(symbol.clone()),
+                    _ => {}
+                }
+            }
+        }
+        if missing.is_empty() {
+            return Ok(found);
+        }
+
+        let mut fetched = self.inner.profiles(&missing).await?;
+        let now = Instant::now();
+        let mut cache = self.cache.write().unwrap_or_else(|e| e.into_inner());
+        for symbol in missing {
+            let profile = fetched.remove(&symbol);
+            if let Some(profile) = &profile {
+                found.insert(symbol.clone(), profile.clone());
+            }
+            cache.insert(symbol, (now, profile));
+        }
+        Ok(found)
+    }
+}
+
+/// Signed share of equity per sector, with unknown sectors under
+/// [`UNCLASSIFIED`].
+#[must_use]
+pub fn sector_weights(
+    portfolio: &PortfolioTracker,
+    profiles: &HashMap<String, InstrumentProfile>,
+) -> BTreeMap<String, f64> {
+    let equity = portfolio.equity();
+    let mut weights = BTreeMap::new();
+    if equity == 0.0 {
+        return weights;
+    }
+    for position in portfolio.open_positions() {
+        let sector = profiles
+            .get(&position.symbol)
+            .and_then(|profile| profile.sector.clone())
+            .unwrap_or_else(|| UNCLASSIFIED.to_string());
+        *weights.entry(sector).or_insert(0.0) += position.market_value() / equity;
+    }
+    weights
+}
+
+/// Equity-weighted beta of the open positions; positions without a beta
+/// count as 1.
+#[must_use]
+pub fn portfolio_beta(
+    portfolio: &PortfolioTracker,
+    profiles: &HashMap<String, InstrumentProfile>,
+) -> f64 {
+    let equity = portfolio.equity();
+    if equity == 0.0 {
+        return 0.0;
+    }
+    portfolio
+        .open_positions()
+        .map(|position| {
+            let beta = profiles
+                .get(&position.symbol)
+                .and_then(|profile| profile.beta)
+                .unwrap_or(1.0);
+            beta * position.market_value() / equity
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::portfolio::PortfolioFill;
+    use crate::types::OrderSide;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingEnricher {
+        table: StaticEnricher,
+        requested: AtomicUsize,
+    }
+
+    impl InstrumentEnricher for CountingEnricher {
+        async fn profiles(&self, symbols: &[String]) -> Result<HashMap<String, InstrumentProfile>> {
+            self.requested.fetch_add(symbols.len(), Ordering::Relaxed);
+            self.table.profiles(symbols).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_enricher_and_sector_weights() {
+        let table = StaticEnricher::from_json(
+            r#"[{"symbol":"AAPL","sector":"Technology","industry":"Consumer Electronics","market_cap":3.0e12,"beta":1.2},
+                {"symbol":"XOM","sector":"Energy","beta":0.8}]"#,
+        )
+        .unwrap();
+        let cached = CachedEnricher::new(
+            CountingEnricher {
+                table,
+                requested: AtomicUsize::new(0),
+            },
+            Duration::from_secs(3600),
+        );
+        let symbols = ["AAPL", "XOM", "ZZZ"].map(String::from);
+        let profiles = cached.profiles(&symbols).await.unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(
+            profiles["AAPL"].industry.as_deref(),
+            Some("Consumer Electronics")
+        );
+        cached.profiles(&symbols).await.unwrap();
+        assert_eq!(cached.inner().requested.load(Ordering::Relaxed), 3);
+        cached.invalidate("AAPL");
+        assert!(cached.profile("AAPL").await.unwrap().is_some());
+        assert_eq!(cached.inner().requested.load(Ordering::Relaxed), 4);
+
+        let mut portfolio = PortfolioTracker::new(10_000.0);
+        portfolio.apply_fill(&PortfolioFill::new("AAPL", OrderSide::Buy, 10.0, 200.0));
+        portfolio.apply_fill(&PortfolioFill::new("XOM", OrderSide::Buy, 20.0, 100.0));
+        portfolio.apply_fill(&PortfolioFill::new("ZZZ", OrderSide::Buy, 10.0, 100.0));
+        let weights = sector_weights(&portfolio, &profiles);
+        assert!((weights["Technology"] - 0.2).abs() < 1e-9);
+        assert!((weights["Energy"] - 0.2).abs() < 1e-9);
+        assert!((weights[UNCLASSIFIED] - 0.1).abs() < 1e-9);
+        assert!((portfolio_beta(&portfolio, &profiles) - (0.24 + 0.16 + 0.1)).abs() < 1e-9);
+    }
+}
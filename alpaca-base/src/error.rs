@@ -3,6 +3,8 @@
 //! This module provides comprehensive error handling with typed errors
 //! for all API error responses, including Alpaca-specific error codes.
 
+#[cfg(feature = "unstable")]
+use crate::risk::RiskViolation;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use thiserror::Error;
@@ -18,9 +20,12 @@ pub type Result<T> = std::result::Result<T, AlpacaError>;
 pub enum ApiErrorCode {
     /// Malformed request body or parameters.
     MalformedRequest = 40010000,
+    /// Invalid order parameters.
+    InvalidOrder = 40010001,
     /// Invalid or missing authentication credentials.
     InvalidCredentials = 40110000,
-    /// Access forbidden for this resource.
+    /// Access forbidden; also used for buying power, wash trade and
+    /// pattern day trading rejections (see [`ApiErrorKind`]).
     Forbidden = 40310000,
     /// Requested resource not found.
     NotFound = 40410000,
@@ -40,6 +45,7 @@ impl ApiErrorCode {
     pub fn from_code(code: u32) -> Self {
         match code {
             40010000 => Self::MalformedRequest,
+            40010001 => Self::InvalidOrder,
             40110000 => Self::InvalidCredentials,
             40310000 => Self::Forbidden,
             40410000 => Self::NotFound,
@@ -81,6 +87,7 @@ impl fmt::Display for ApiErrorCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::MalformedRequest => write!(f, "malformed request"),
+            Self::InvalidOrder => write!(f, "invalid order"),
             Self::InvalidCredentials => write!(f, "invalid credentials"),
             Self::Forbidden => write!(f, "forbidden"),
             Self::NotFound => write!(f, "not found"),
@@ -92,6 +99,131 @@ impl fmt::Display for ApiErrorCode {
     }
 }
 
+/// What an API error means, classified from its status, error code and
+/// message.
+///
+/// Alpaca reuses a few codes for unrelated rejections (40310000 covers
+/// insufficient buying power, wash trades and pattern day trading
+/// protection alike), so the kind is refined by the message where the code
+/// alone is ambiguous.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorKind {
+    /// Not enough buying power for the order.
+    InsufficientBuyingPower,
+    /// Not enough shares available, e.g. already held for other orders.
+    InsufficientQty,
+    /// Rejected as a potential wash trade against an opposite open order.
+    WashTrade,
+    /// Rejected by pattern day trading protection.
+    PatternDayTrading,
+    /// The asset is not tradable, or not shortable for a short sale.
+    NotTradable,
+    /// The client order ID was already used.
+    DuplicateClientOrderId,
+    /// The order parameters are invalid.
+    InvalidOrder,
+    /// The request was malformed.
+    MalformedRequest,
+    /// Missing or invalid credentials.
+    Unauthorized,
+    /// Forbidden for another reason.
+    Forbidden,
+    /// The resource does not exist.
+    NotFound,
+    /// Rate limit exceeded.
+    RateLimited,
+    /// Server-side failure.
+    ServerError,
+    /// Anything else.
+    Other,
+}
+
+impl ApiErrorKind {
+    /// Classify an error response.
+    ///
+    /// Codes with a single meaning (credentials, not found, rate limit,
+    /// server error, malformed request) decide on their own. Order
+    /// rejections share 403/422 codes and are told apart by phrases in
+    /// the message.
+    #[must_use]
+    pub fn classify(status: u16, code: Option<ApiErrorCode>, message: &str) -> Self {
+        match code {
+            Some(ApiErrorCode::MalformedRequest) => return Self::MalformedRequest,
+            Some(ApiErrorCode::InvalidCredentials) => return Self::Unauthorized,
+            Some(ApiErrorCode::NotFound) => return Self::NotFound,
+            Some(ApiErrorCode::RateLimitExceeded) => return Self::RateLimited,
+            Some(ApiErrorCode::InternalServerError) => return Self::ServerError,
+            _ => {}
+        }
+        if code.is_none_or(|code| code == ApiErrorCode::Unknown) {
+            match status {
+                401 => return Self::Unauthorized,
+                404 => return Self::NotFound,
+                429 => return Self::RateLimited,
+                500.. => return Self::ServerError,
+                _ => {}
+            }
+        }
+
+        let message = message.to_ascii_lowercase();
+        let mentions = |needles: &[&str]| needles.iter().any(|needle| message.contains(needle));
+        if mentions(&[
+            "insufficient buying power",
+            "insufficient day trading buying power",
+        ]) {
+            return Self::InsufficientBuyingPower;
+        }
+        if mentions(&["insufficient qty", "insufficient quantity"]) {
+            return Self::InsufficientQty;
+        }
+        if mentions(&["wash trade"]) {
+            return Self::WashTrade;
+        }
+        if mentions(&["pattern day trad", "day trading protection"]) {
+            return Self::PatternDayTrading;
+        }
+        if mentions(&["not tradable", "not shortable", "cannot be sold short"]) {
+            return Self::NotTradable;
+        }
+        if message.contains("client_order_id") && mentions(&["unique", "duplicate"]) {
+            return Self::DuplicateClientOrderId;
+        }
+        match code {
+            Some(ApiErrorCode::InvalidOrder | ApiErrorCode::UnprocessableEntity) => {
+                return Self::InvalidOrder;
+            }
+            Some(ApiErrorCode::Forbidden) => return Self::Forbidden,
+            _ => {}
+        }
+        match status {
+            400 => Self::MalformedRequest,
+            403 => Self::Forbidden,
+            422 => Self::InvalidOrder,
+            _ => Self::Other,
+        }
+    }
+
+    /// Returns true if retrying the same request may succeed.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::RateLimited | Self::ServerError)
+    }
+
+    /// Returns true if the order was rejected for lack of funds or shares.
+    #[must_use]
+    pub fn is_insufficient_funds(&self) -> bool {
+        matches!(self, Self::InsufficientBuyingPower | Self::InsufficientQty)
+    }
+
+    /// Returns true if the order was rejected by a trading rule (wash
+    /// trade or pattern day trading protection) rather than its contents.
+    #[must_use]
+    pub fn is_trading_restriction(&self) -> bool {
+        matches!(self, Self::WashTrade | Self::PatternDayTrading)
+    }
+}
+
 /// Detailed API error response from Alpaca.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiErrorResponse {
@@ -129,6 +261,13 @@ impl ApiErrorResponse {
     pub fn error_code(&self) -> ApiErrorCode {
         ApiErrorCode::from_code(self.code)
     }
+
+    /// Classifies the error; `status` is the HTTP status of the response.
+    #[must_use]
+    pub fn kind(&self, status: u16) -> ApiErrorKind {
+        let code = (self.code > 0).then(|| self.error_code());
+        ApiErrorKind::classify(status, code, &self.message)
+    }
 }
 
 /// Rate limit information from API response headers.
@@ -224,6 +363,9 @@ pub enum AlpacaError {
         /// Alpaca-specific error code.
         #[source]
         error_code: Option<ApiErrorCode>,
+        /// What the error means, classified from the status, code and
+        /// message.
+        kind: ApiErrorKind,
         /// Request ID for debugging.
         request_id: Option<String>,
     },
@@ -240,6 +382,12 @@ pub enum AlpacaError {
     #[error("websocket error: {0}")]
     WebSocket(String),
 
+    /// The account's streaming connection slot is already in use, either
+    /// by another local process holding the connection lease or as
+    /// reported by the server.
+    #[error("connection limit exceeded: {0}")]
+    ConnectionLimitExceeded(String),
+
     /// Rate limiting errors with retry information.
     #[error("rate limit exceeded, retry after {retry_after_secs} seconds")]
     RateLimit {
@@ -268,18 +416,23 @@ pub enum AlpacaError {
     /// Multiple validation errors.
     #[error("validation errors: {}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))]
     ValidationErrors(Vec<ValidationError>),
+
+    /// The operation was cancelled through its cancellation token.
+    #[error("cancelled: {0}")]
+    Cancelled(String),
+
+    /// The order was rejected locally by a pre-trade risk check (requires
+    /// `unstable` feature).
+    #[cfg(feature = "unstable")]
+    #[error("order rejected by risk check: {0}")]
+    RiskRejected(RiskViolation),
 }
 
 impl AlpacaError {
     /// Creates an API error from HTTP status and message.
     #[must_use]
     pub fn api(status: u16, message: impl Into<String>) -> Self {
-        Self::Api {
-            status,
-            message: message.into(),
-            error_code: None,
-            request_id: None,
-        }
+        Self::api_response(status, None, message, None)
     }
 
     /// Creates an API error with full details.
@@ -290,10 +443,24 @@ impl AlpacaError {
         error_code: ApiErrorCode,
         request_id: Option<String>,
     ) -> Self {
+        Self::api_response(status, Some(error_code), message, request_id)
+    }
+
+    /// Creates an API error from a parsed response, classifying its
+    /// [`ApiErrorKind`].
+    #[must_use]
+    pub fn api_response(
+        status: u16,
+        error_code: Option<ApiErrorCode>,
+        message: impl Into<String>,
+        request_id: Option<String>,
+    ) -> Self {
+        let message = message.into();
         Self::Api {
             status,
-            message: message.into(),
-            error_code: Some(error_code),
+            kind: ApiErrorKind::classify(status, error_code, &message),
+            message,
+            error_code,
             request_id,
         }
     }
@@ -324,14 +491,17 @@ impl AlpacaError {
             Self::Network(_) => true,
             Self::Timeout(_) => true,
             Self::Api {
-                status, error_code, ..
+                status,
+                error_code,
+                kind,
+                ..
             } => {
                 // 5xx errors are retryable
                 if *status >= 500 {
                     return true;
                 }
                 // Check Alpaca-specific error codes
-                error_code.is_some_and(|code| code.is_retryable())
+                error_code.is_some_and(|code| code.is_retryable()) || kind.is_retryable()
             }
             _ => false,
         }
@@ -365,6 +535,54 @@ impl AlpacaError {
             _ => None,
         }
     }
+
+    /// Returns the classified kind if this is an API error.
+    #[must_use]
+    pub fn api_kind(&self) -> Option<ApiErrorKind> {
+        match self {
+            Self::Api { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Returns true if an order was rejected for insufficient buying power
+    /// or insufficient shares.
+    #[must_use]
+    pub fn is_insufficient_funds(&self) -> bool {
+        self.api_kind()
+            .is_some_and(|kind| kind.is_insufficient_funds())
+    }
+
+    /// Returns true if an order was rejected as a potential wash trade.
+    #[must_use]
+    pub fn is_wash_trade(&self) -> bool {
+        self.api_kind() == Some(ApiErrorKind::WashTrade)
+    }
+
+    /// Returns true if an order was rejected by pattern day trading
+    /// protection.
+    #[must_use]
+    pub fn is_pdt_violation(&self) -> bool {
+        self.api_kind() == Some(ApiErrorKind::PatternDayTrading)
+    }
+
+    /// Returns true if the order parameters were rejected as invalid.
+    #[must_use]
+    pub fn is_invalid_order(&self) -> bool {
+        self.api_kind() == Some(ApiErrorKind::InvalidOrder)
+    }
+
+    /// Returns true if the requested resource does not exist.
+    #[must_use]
+    pub fn is_not_found(&self) -> bool {
+        self.api_kind() == Some(ApiErrorKind::NotFound)
+    }
+
+    /// Returns true if the client order ID was already used.
+    #[must_use]
+    pub fn is_duplicate_client_order_id(&self) -> bool {
+        self.api_kind() == Some(ApiErrorKind::DuplicateClientOrderId)
+    }
 }
 
 impl From<serde_json::Error> for AlpacaError {
@@ -472,6 +690,61 @@ mod tests {
         assert!(!err.is_retryable());
     }
 
+    #[test]
+    fn test_api_error_kind_classification() {
+        let buying_power = AlpacaError::api_response(
+            403,
+            Some(ApiErrorCode::Forbidden),
+            "insufficient buying power",
+            None,
+        );
+        assert_eq!(
+            buying_power.api_kind(),
+            Some(ApiErrorKind::InsufficientBuyingPower)
+        );
+        assert!(buying_power.is_insufficient_funds());
+        assert!(!buying_power.is_retryable());
+
+        let wash = AlpacaError::api_response(
+            403,
+            Some(ApiErrorCode::Forbidden),
+            "potential wash trade detected. use complex orders",
+            None,
+        );
+        assert!(wash.is_wash_trade());
+        let pdt = AlpacaError::api(403, "trade denied due to pattern day trading protection");
+        assert!(pdt.is_pdt_violation());
+        // "pdt" inside another word is not a PDT rejection, and the code
+        // wins over message phrases.
+        assert!(!AlpacaError::api(422, "invalid field: updt_time").is_pdt_violation());
+        assert_eq!(
+            ApiErrorKind::classify(
+                404,
+                Some(ApiErrorCode::NotFound),
+                "pattern day trader flag not found"
+            ),
+            ApiErrorKind::NotFound
+        );
+
+        let invalid = AlpacaError::api_response(
+            422,
+            Some(ApiErrorCode::from_code(40010001)),
+            "qty must be > 0",
+            None,
+        );
+        assert!(invalid.is_invalid_order());
+        assert!(
+            AlpacaError::api(422, "client_order_id must be unique").is_duplicate_client_order_id()
+        );
+        assert!(AlpacaError::api(404, "order not found").is_not_found());
+        assert!(AlpacaError::api(503, "service unavailable").is_retryable());
+        assert_eq!(
+            ApiErrorResponse::new(40310000, "forbidden").kind(403),
+            ApiErrorKind::Forbidden
+        );
+        assert_eq!(AlpacaError::Network("reset".to_string()).api_kind(), None);
+    }
+
     #[test]
     fn test_api_error_response() {
         let response = ApiErrorResponse::new(40410000, "not found").with_request_id("req-456");
@@ -0,0 +1,518 @@
+//! Order fill reconciliation.
+//!
+//! [`FillTracker`] follows locally submitted orders through their
+//! lifecycle from transport-neutral [`Execution`]s, which the WebSocket
+//! crate builds from trade updates and the FIX crate from execution
+//! reports. Each order moves through a small state machine:
+//!
+//! ```text
+//! submitted -> accepted -> partially filled -> filled
+//!     |            |              |
+//!     +------------+--------------+--> canceled / rejected / expired / replaced
+//! ```
+//!
+//! States only move forward: a late `accepted` after a partial fill, or
+//! any event after a terminal state, leaves the order as it is. Fills are
+//! de-duplicated by execution ID and by cumulative quantity, so replayed
+//! updates after a reconnect are harmless.
+//!
+//! ```rust,ignore
+//! let mut tracker = FillTracker::new();
+//! tracker.on_change(|change| println!("{}: {:?}", change.client_order_id, change.to));
+//! let order = api.submit_order(&request).await?;
+//! tracker.track(&order);
+//! while let Some(update) = trading_stream.updates().next().await {
+//!     tracker.apply(&Execution::from(&update));
+//! }
+//! ```
+
+use crate::types::{Order, OrderSide};
+use crate::utils::QTY_EPSILON;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Lifecycle state of a tracked order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OrderState {
+    /// Sent, not yet acknowledged.
+    Submitted,
+    /// Acknowledged by the venue.
+    Accepted,
+    /// Partially filled.
+    PartiallyFilled,
+    /// Completely filled.
+    Filled,
+    /// Canceled (possibly after partial fills).
+    Canceled,
+    /// Rejected.
+    Rejected,
+    /// Expired.
+    Expired,
+    /// Replaced by another order.
+    Replaced,
+}
+
+impl OrderState {
+    /// Whether no further events can change the order.
+    #[must_use]
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Filled | Self::Canceled | Self::Rejected | Self::Expired | Self::Replaced
+        )
+    }
+}
+
+/// What an [`Execution`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionKind {
+    /// The order was acknowledged.
+    Accepted,
+    /// Some or all of the order was filled.
+    Fill,
+    /// The order was canceled.
+    Canceled,
+    /// The order was rejected.
+    Rejected,
+    /// The order expired.
+    Expired,
+    /// The order was replaced.
+    Replaced,
+    /// Anything else (pending cancel, pending replace, ...); ignored.
+    Other,
+}
+
+/// An order event from any transport.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    /// Client order ID of the order the event is for.
+    pub client_order_id: String,
+    /// Venue order ID, if known.
+    pub order_id: Option<String>,
+    /// Execution ID, used to drop duplicates.
+    pub exec_id: Option<String>,
+    /// What happened.
+    pub kind: ExecutionKind,
+    /// Quantity of this fill.
+    pub last_qty: Option<f64>,
+    /// Price of this fill.
+    pub last_price: Option<f64>,
+    /// Cumulative filled quantity after the event.
+    pub cum_qty: Option<f64>,
+    /// Average fill price after the event.
+    pub avg_price: Option<f64>,
+    /// Reason text for rejections.
+    pub text: Option<String>,
+    /// Event time.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Execution {
+    /// Create an event with no quantities.
+    #[must_use]
+    pub fn new(client_order_id: &str, kind: ExecutionKind, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            client_order_id: client_order_id.to_string(),
+            order_id: None,
+            exec_id: None,
+            kind,
+            last_qty: None,
+            last_price: None,
+            cum_qty: None,
+            avg_price: None,
+            text: None,
+            timestamp,
+        }
+    }
+
+    /// Create a fill event for `qty` at `price`.
+    #[must_use]
+    pub fn fill(client_order_id: &str, qty: f64, price: f64, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            last_qty: Some(qty),
+            last_price: Some(price),
+            ..Self::new(client_order_id, ExecutionKind::Fill, timestamp)
+        }
+    }
+}
+
+/// A single fill applied to a tracked order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedFill {
+    /// Filled quantity.
+    pub qty: f64,
+    /// Fill price.
+    pub price: f64,
+    /// Fill time.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A locally submitted order and its reconciled fill state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedOrder {
+    /// Client order ID.
+    pub client_order_id: String,
+    /// Venue order ID, once known.
+    pub order_id: Option<String>,
+    /// Symbol.
+    pub symbol: String,
+    /// Side.
+    pub side: OrderSide,
+    /// Ordered quantity.
+    pub qty: f64,
+    /// Current state.
+    pub state: OrderState,
+    /// Filled quantity.
+    pub filled_qty: f64,
+    /// Volume-weighted average fill price.
+    pub avg_fill_price: Option<f64>,
+    /// Fills applied so far.
+    pub fills: Vec<TrackedFill>,
+    /// Reason for a rejection.
+    pub reject_reason: Option<String>,
+    /// Time of the last change.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TrackedOrder {
+    /// Quantity still open; zero once the order is terminal.
+    #[must_use]
+    pub fn remaining_qty(&self) -> f64 {
+        if self.state.is_terminal() {
+            0.0
+        } else {
+            (self.qty - self.filled_qty).max(0.0)
+        }
+    }
+
+    /// Quantity not filled, regardless of state.
+    #[must_use]
+    pub fn unfilled_qty(&self) -> f64 {
+        (self.qty - self.filled_qty).max(0.0)
+    }
+
+    fn apply_fill(&mut self, execution: &Execution) -> bool {
+        let (qty, price) = match (execution.last_qty, execution.last_price, execution.cum_qty) {
+            (Some(qty), Some(price), _) => (qty, price),
+            (_, _, Some(cum)) => {
+                let qty = cum - self.filled_qty;
+                let notional_before = self.avg_fill_price.unwrap_or(0.0) * self.filled_qty;
+                let price = match execution.avg_price {
+                    Some(avg) if qty > QTY_EPSILON => (avg * cum - notional_before) / qty,
+                    _ => execution.last_price.unwrap_or(0.0),
+                };
+                (qty, price)
+            }
+            _ => return false,
+        };
+        if qty <= QTY_EPSILON {
+            return false;
+        }
+        if let Some(cum) = execution.cum_qty
+            && cum <= self.filled_qty + QTY_EPSILON
+        {
+            return false;
+        }
+
+        let notional = self.avg_fill_price.unwrap_or(0.0) * self.filled_qty + qty * price;
+        self.filled_qty += qty;
+        self.avg_fill_price = Some(notional / self.filled_qty);
+        if let Some(cum) = execution.cum_qty {
+            self.filled_qty = cum;
+        }
+        if let Some(avg) = execution.avg_price.filter(|avg| *avg > 0.0) {
+            self.avg_fill_price = Some(avg);
+        }
+        self.fills.push(TrackedFill {
+            qty,
+            price,
+            timestamp: execution.timestamp,
+        });
+        true
+    }
+}
+
+/// A state change of a tracked order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderStateChange {
+    /// Client order ID.
+    pub client_order_id: String,
+    /// Previous state.
+    pub from: OrderState,
+    /// New state.
+    pub to: OrderState,
+    /// Filled quantity after the change.
+    pub filled_qty: f64,
+    /// Remaining quantity after the change.
+    pub remaining_qty: f64,
+    /// Average fill price after the change.
+    pub avg_fill_price: Option<f64>,
+    /// Time of the change.
+    pub timestamp: DateTime<Utc>,
+}
+
+type ChangeCallback = Box<dyn Fn(&OrderStateChange) + Send + Sync>;
+
+/// Reconciles order events against locally submitted orders.
+#[derive(Default)]
+pub struct FillTracker {
+    orders: HashMap<String, TrackedOrder>,
+    order_ids: HashMap<String, String>,
+    seen_exec_ids: HashSet<String>,
+    unmatched: Vec<Execution>,
+    callbacks: Vec<ChangeCallback>,
+}
+
+impl fmt::Debug for FillTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FillTracker")
+            .field("orders", &self.orders)
+            .field("unmatched", &self.unmatched)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+impl FillTracker {
+    /// Create an empty tracker.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `callback` on every state change.
+    pub fn on_change(&mut self, callback: impl Fn(&OrderStateChange) + Send + Sync + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Start tracking an order returned by a submission.
+    pub fn track(&mut self, order: &Order) {
+        let order_id = (!order.id.is_nil()).then(|| order.id.to_string());
+        if let Some(id) = &order_id {
+            self.order_ids
+                .insert(id.clone(), order.client_order_id.clone());
+        }
+        self.orders.insert(
+            order.client_order_id.clone(),
+            TrackedOrder {
+                client_order_id: order.client_order_id.clone(),
+                order_id,
+                symbol: order.symbol.clone(),
+                side: order.side.clone(),
+                qty: order
+                    .qty
+                    .as_deref()
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(0.0),
+                state: OrderState::Submitted,
+                filled_qty: 0.0,
+                avg_fill_price: None,
+                fills: Vec::new(),
+                reject_reason: None,
+                updated_at: order.submitted_at.unwrap_or(order.created_at),
+            },
+        );
+    }
+
+    /// A tracked order by client order ID.
+    #[must_use]
+    pub fn order(&self, client_order_id: &str) -> Option<&TrackedOrder> {
+        self.orders.get(client_order_id)
+    }
+
+    /// All tracked orders.
+    pub fn orders(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.orders.values()
+    }
+
+    /// Tracked orders that are not yet terminal.
+    pub fn open_orders(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.orders.values().filter(|o| !o.state.is_terminal())
+    }
+
+    /// Events that did not match any tracked order.
+    #[must_use]
+    pub fn unmatched(&self) -> &[Execution] {
+        &self.unmatched
+    }
+
+    /// Stop tracking terminal orders, returning them.
+    pub fn drain_terminal(&mut self) -> Vec<TrackedOrder> {
+        let done: Vec<String> = self
+            .orders
+            .values()
+            .filter(|o| o.state.is_terminal())
+            .map(|o| o.client_order_id.clone())
+            .collect();
+        let drained: Vec<TrackedOrder> = done
+            .iter()
+            .filter_map(|id| self.orders.remove(id))
+            .collect();
+        self.order_ids
+            .retain(|_, client_id| !done.contains(client_id));
+        drained
+    }
+
+    /// Apply an event, returning the state change it caused, if any.
+    pub fn apply(&mut self, execution: &Execution) -> Option<OrderStateChange> {
+        if let Some(exec_id) = &execution.exec_id
+            && !self.seen_exec_ids.insert(exec_id.clone())
+        {
+            return None;
+        }
+        let client_order_id = if self.orders.contains_key(&execution.client_order_id) {
+            execution.client_order_id.clone()
+        } else if let Some(id) = execution
+            .order_id
+            .as_ref()
+            .and_then(|id| self.order_ids.get(id))
+        {
+            id.clone()
+        } else {
+            self.unmatched.push(execution.clone());
+            return None;
+        };
+
+        let order = self.orders.get_mut(&client_order_id)?;
+        if order.order_id.is_none()
+            && let Some(id) = &execution.order_id
+        {
+            order.order_id = Some(id.clone());
+            self.order_ids.insert(id.clone(), client_order_id.clone());
+        }
+        if order.state.is_terminal() {
+            return None;
+        }
+
+        let from = order.state;
+        let to = match execution.kind {
+            ExecutionKind::Accepted => from.max(OrderState::Accepted),
+            ExecutionKind::Fill => {
+                if !order.apply_fill(execution) {
+                    return None;
+                }
+                if order.filled_qty + QTY_EPSILON >= order.qty {
+                    OrderState::Filled
+                } else {
+                    OrderState::PartiallyFilled
+                }
+            }
+            ExecutionKind::Canceled => OrderState::Canceled,
+            ExecutionKind::Rejected => {
+                order.reject_reason = execution.text.clone();
+                OrderState::Rejected
+            }
+            ExecutionKind::Expired => OrderState::Expired,
+            ExecutionKind::Replaced => OrderState::Replaced,
+            ExecutionKind::Other => return None,
+        };
+        order.updated_at = execution.timestamp;
+        if to == from && execution.kind != ExecutionKind::Fill {
+            return None;
+        }
+        order.state = to;
+
+        let change = OrderStateChange {
+            client_order_id,
+            from,
+            to,
+            filled_qty: order.filled_qty,
+            remaining_qty: order.remaining_qty(),
+            avg_fill_price: order.avg_fill_price,
+            timestamp: execution.timestamp,
+        };
+        for callback in &self.callbacks {
+            callback(&change);
+        }
+        Some(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderRequest;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    fn tracked(tracker: &mut FillTracker, qty: f64) -> Order {
+        let order = OrderRequest::limit("AAPL", OrderSide::Buy, qty, 190.0).pending_order(
+            Uuid::nil(),
+            "c-1",
+            Utc::now(),
+        );
+        tracker.track(&order);
+        order
+    }
+
+    #[test]
+    fn test_partial_fills_to_filled() {
+        let mut tracker = FillTracker::new();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&changes);
+        tracker.on_change(move |change| sink.lock().unwrap().push(change.to));
+        tracked(&mut tracker, 10.0);
+        let now = Utc::now();
+
+        let mut accepted = Execution::new("c-1", ExecutionKind::Accepted, now);
+        accepted.order_id = Some("o-1".to_string());
+        tracker.apply(&accepted);
+
+        let mut first = Execution::fill("c-1", 4.0, 100.0, now);
+        first.exec_id = Some("e-1".to_string());
+        let change = tracker.apply(&first).unwrap();
+        assert_eq!(change.to, OrderState::PartiallyFilled);
+        assert_eq!(change.remaining_qty, 6.0);
+        assert!(tracker.apply(&first).is_none(), "duplicate exec id");
+
+        // Matched by venue order ID; cumulative fields drive the totals.
+        let mut second = Execution::new("unknown", ExecutionKind::Fill, now);
+        second.order_id = Some("o-1".to_string());
+        second.cum_qty = Some(10.0);
+        second.avg_price = Some(103.0);
+        let change = tracker.apply(&second).unwrap();
+        assert_eq!(change.to, OrderState::Filled);
+        assert_eq!(change.remaining_qty, 0.0);
+        let order = tracker.order("c-1").unwrap();
+        assert_eq!(order.avg_fill_price, Some(103.0));
+        assert!((order.fills[1].price - 105.0).abs() < 1e-9);
+        assert!(
+            tracker
+                .apply(&Execution::new("c-1", ExecutionKind::Canceled, now))
+                .is_none()
+        );
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![
+                OrderState::Accepted,
+                OrderState::PartiallyFilled,
+                OrderState::Filled
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancel_after_partial_and_unmatched() {
+        let mut tracker = FillTracker::new();
+        tracked(&mut tracker, 10.0);
+        let now = Utc::now();
+        tracker.apply(&Execution::fill("c-1", 3.0, 50.0, now));
+        let change = tracker
+            .apply(&Execution::new("c-1", ExecutionKind::Canceled, now))
+            .unwrap();
+        assert_eq!(change.from, OrderState::PartiallyFilled);
+        assert_eq!(change.remaining_qty, 0.0);
+        assert_eq!(tracker.order("c-1").unwrap().unfilled_qty(), 7.0);
+
+        assert!(
+            tracker
+                .apply(&Execution::fill("other", 1.0, 1.0, now))
+                .is_none()
+        );
+        assert_eq!(tracker.unmatched().len(), 1);
+        assert_eq!(tracker.drain_terminal().len(), 1);
+        assert_eq!(tracker.orders().count(), 0);
+    }
+}
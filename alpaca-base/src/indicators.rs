@@ -0,0 +1,585 @@
+//! Incremental technical indicators.
+//!
+//! Every indicator implements [`Indicator`] and is fed one [`Bar`] or
+//! [`Trade`] at a time, whether from historical bars or a live stream, in
+//! O(1) time and memory bounded by its period. An indicator yields `None`
+//! until it has seen enough data to produce a value. Price indicators read
+//! a bar's close or a trade's price; [`Atr`] also reads the high and low
+//! (a trade counts as a bar whose open, high, low and close are its price),
+//! and [`Vwap`] weights by volume.
+//!
+//! Periods of zero are treated as one.
+
+use crate::types::{Bar, Trade};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// An indicator updated incrementally from bars or trades.
+pub trait Indicator {
+    /// Value produced by the indicator.
+    type Output: Copy;
+
+    /// Feed a bar, returning the updated value once available.
+    fn update_bar(&mut self, bar: &Bar) -> Option<Self::Output>;
+
+    /// Feed a trade, returning the updated value once available.
+    fn update_trade(&mut self, trade: &Trade) -> Option<Self::Output>;
+
+    /// The current value, if enough data has been seen.
+    fn value(&self) -> Option<Self::Output>;
+
+    /// Discard all state.
+    fn reset(&mut self);
+
+    /// Whether the indicator has produced a value.
+    fn is_ready(&self) -> bool {
+        self.value().is_some()
+    }
+}
+
+/// Implements [`Indicator`] for a type with `next(price)` driven by closes
+/// and trade prices.
+macro_rules! price_indicator {
+    ($ty:ty, $out:ty) => {
+        impl Indicator for $ty {
+            type Output = $out;
+
+            fn update_bar(&mut self, bar: &Bar) -> Option<$out> {
+                self.next(bar.close)
+            }
+
+            fn update_trade(&mut self, trade: &Trade) -> Option<$out> {
+                self.next(trade.price)
+            }
+
+            fn value(&self) -> Option<$out> {
+                self.current()
+            }
+
+            fn reset(&mut self) {
+                *self = Self::new(self.period());
+            }
+        }
+    };
+}
+
+/// Simple moving average.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    /// Create an SMA over `period` values.
+    #[must_use]
+    pub fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self {
+            period,
+            window: VecDeque::with_capacity(period + 1),
+            sum: 0.0,
+        }
+    }
+
+    /// Period.
+    #[must_use]
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Feed a price.
+    pub fn next(&mut self, price: f64) -> Option<f64> {
+        self.window.push_back(price);
+        self.sum += price;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        self.current()
+    }
+
+    /// Current value.
+    #[must_use]
+    pub fn current(&self) -> Option<f64> {
+        (self.window.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+}
+
+price_indicator!(Sma, f64);
+
+/// Exponential moving average, seeded with the SMA of its first `period`
+/// values.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,
+    alpha: f64,
+    seed_sum: f64,
+    seen: usize,
+    value: Option<f64>,
+}
+
+impl Ema {
+    /// Create an EMA with smoothing `2 / (period + 1)`.
+    #[must_use]
+    pub fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self {
+            period,
+            alpha: 2.0 / (period as f64 + 1.0),
+            seed_sum: 0.0,
+            seen: 0,
+            value: None,
+        }
+    }
+
+    /// Period.
+    #[must_use]
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Feed a price.
+    pub fn next(&mut self, price: f64) -> Option<f64> {
+        match self.value {
+            Some(prev) => self.value = Some(prev + self.alpha * (price - prev)),
+            None => {
+                self.seen += 1;
+                self.seed_sum += price;
+                if self.seen == self.period {
+                    self.value = Some(self.seed_sum / self.period as f64);
+                }
+            }
+        }
+        self.value
+    }
+
+    /// Current value.
+    #[must_use]
+    pub fn current(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+price_indicator!(Ema, f64);
+
+/// Relative strength index with Wilder smoothing, from 0 to 100.
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    period: usize,
+    prev: Option<f64>,
+    seen: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl Rsi {
+    /// Create an RSI over `period` price changes.
+    #[must_use]
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            prev: None,
+            seen: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+
+    /// Period.
+    #[must_use]
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Feed a price.
+    pub fn next(&mut self, price: f64) -> Option<f64> {
+        let prev = self.prev.replace(price)?;
+        let change = price - prev;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        let n = self.period as f64;
+        if self.seen < self.period {
+            self.seen += 1;
+            self.avg_gain += gain / n;
+            self.avg_loss += loss / n;
+        } else {
+            self.avg_gain = (self.avg_gain * (n - 1.0) + gain) / n;
+            self.avg_loss = (self.avg_loss * (n - 1.0) + loss) / n;
+        }
+        self.current()
+    }
+
+    /// Current value.
+    #[must_use]
+    pub fn current(&self) -> Option<f64> {
+        if self.seen < self.period {
+            return None;
+        }
+        if self.avg_loss == 0.0 {
+            return Some(if self.avg_gain == 0.0 { 50.0 } else { 100.0 });
+        }
+        Some(100.0 - 100.0 / (1.0 + self.avg_gain / self.avg_loss))
+    }
+}
+
+price_indicator!(Rsi, f64);
+
+/// MACD line, signal line and histogram.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MacdValue {
+    /// Fast EMA minus slow EMA.
+    pub macd: f64,
+    /// EMA of the MACD line.
+    pub signal: f64,
+    /// MACD minus signal.
+    pub histogram: f64,
+}
+
+/// Moving average convergence/divergence.
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+    value: Option<MacdValue>,
+}
+
+impl Macd {
+    /// Create a MACD from fast, slow and signal periods.
+    #[must_use]
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Self {
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+            value: None,
+        }
+    }
+
+    /// Feed a price.
+    pub fn next(&mut self, price: f64) -> Option<MacdValue> {
+        let fast = self.fast.next(price);
+        let slow = self.slow.next(price);
+        if let (Some(fast), Some(slow)) = (fast, slow) {
+            let macd = fast - slow;
+            if let Some(signal) = self.signal.next(macd) {
+                self.value = Some(MacdValue {
+                    macd,
+                    signal,
+                    histogram: macd - signal,
+                });
+            }
+        }
+        self.value
+    }
+
+    /// Current value.
+    #[must_use]
+    pub fn current(&self) -> Option<MacdValue> {
+        self.value
+    }
+}
+
+impl Default for Macd {
+    /// The conventional 12/26/9 MACD.
+    fn default() -> Self {
+        Self::new(12, 26, 9)
+    }
+}
+
+impl Indicator for Macd {
+    type Output = MacdValue;
+
+    fn update_bar(&mut self, bar: &Bar) -> Option<MacdValue> {
+        self.next(bar.close)
+    }
+
+    fn update_trade(&mut self, trade: &Trade) -> Option<MacdValue> {
+        self.next(trade.price)
+    }
+
+    fn value(&self) -> Option<MacdValue> {
+        self.value
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.fast.period(), self.slow.period(), self.signal.period());
+    }
+}
+
+/// Bollinger band levels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BollingerValue {
+    /// Middle band plus `k` standard deviations.
+    pub upper: f64,
+    /// SMA.
+    pub middle: f64,
+    /// Middle band minus `k` standard deviations.
+    pub lower: f64,
+}
+
+/// Bollinger bands over a population standard deviation.
+#[derive(Debug, Clone)]
+pub struct BollingerBands {
+    sma: Sma,
+    k: f64,
+    sum_sq: f64,
+}
+
+impl BollingerBands {
+    /// Create bands `k` standard deviations around a `period` SMA.
+    #[must_use]
+    pub fn new(period: usize, k: f64) -> Self {
+        Self {
+            sma: Sma::new(period),
+            k,
+            sum_sq: 0.0,
+        }
+    }
+
+    /// Feed a price.
+    pub fn next(&mut self, price: f64) -> Option<BollingerValue> {
+        self.sum_sq += price * price;
+        if self.sma.window.len() == self.sma.period {
+            let evicted = self.sma.window.front().copied().unwrap_or_default();
+            self.sum_sq -= evicted * evicted;
+        }
+        self.sma.next(price);
+        self.current()
+    }
+
+    /// Current value.
+    #[must_use]
+    pub fn current(&self) -> Option<BollingerValue> {
+        let middle = self.sma.current()?;
+        let n = self.sma.period as f64;
+        let deviation = (self.sum_sq / n - middle * middle).max(0.0).sqrt() * self.k;
+        Some(BollingerValue {
+            upper: middle + deviation,
+            middle,
+            lower: middle - deviation,
+        })
+    }
+}
+
+impl Indicator for BollingerBands {
+    type Output = BollingerValue;
+
+    fn update_bar(&mut self, bar: &Bar) -> Option<BollingerValue> {
+        self.next(bar.close)
+    }
+
+    fn update_trade(&mut self, trade: &Trade) -> Option<BollingerValue> {
+        self.next(trade.price)
+    }
+
+    fn value(&self) -> Option<BollingerValue> {
+        self.current()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.sma.period, self.k);
+    }
+}
+
+/// Average true range with Wilder smoothing.
+#[derive(Debug, Clone)]
+pub struct Atr {
+    period: usize,
+    prev_close: Option<f64>,
+    seen: usize,
+    value: f64,
+}
+
+impl Atr {
+    /// Create an ATR over `period` bars.
+    #[must_use]
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            prev_close: None,
+            seen: 0,
+            value: 0.0,
+        }
+    }
+
+    /// Feed a bar's high, low and close.
+    pub fn next(&mut self, high: f64, low: f64, close: f64) -> Option<f64> {
+        let range = high - low;
+        let true_range = match self.prev_close.replace(close) {
+            Some(prev) => range.max((high - prev).abs()).max((low - prev).abs()),
+            None => range,
+        };
+        let n = self.period as f64;
+        if self.seen < self.period {
+            self.seen += 1;
+            self.value += true_range / n;
+        } else {
+            self.value = (self.value * (n - 1.0) + true_range) / n;
+        }
+        self.current()
+    }
+
+    /// Current value.
+    #[must_use]
+    pub fn current(&self) -> Option<f64> {
+        (self.seen == self.period).then_some(self.value)
+    }
+}
+
+impl Indicator for Atr {
+    type Output = f64;
+
+    fn update_bar(&mut self, bar: &Bar) -> Option<f64> {
+        self.next(bar.high, bar.low, bar.close)
+    }
+
+    fn update_trade(&mut self, trade: &Trade) -> Option<f64> {
+        self.next(trade.price, trade.price, trade.price)
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.current()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.period);
+    }
+}
+
+/// Volume-weighted average price since creation or the last reset.
+///
+/// Bars contribute their own VWAP when the feed provides one and their
+/// typical price `(high + low + close) / 3` otherwise. Call
+/// [`Indicator::reset`] at each session open for a session VWAP.
+#[derive(Debug, Clone, Default)]
+pub struct Vwap {
+    notional: f64,
+    volume: f64,
+}
+
+impl Vwap {
+    /// Create an empty VWAP.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a price and the volume traded at it.
+    pub fn next(&mut self, price: f64, volume: f64) -> Option<f64> {
+        self.notional += price * volume;
+        self.volume += volume;
+        self.current()
+    }
+
+    /// Current value.
+    #[must_use]
+    pub fn current(&self) -> Option<f64> {
+        (self.volume > 0.0).then(|| self.notional / self.volume)
+    }
+}
+
+impl Indicator for Vwap {
+    type Output = f64;
+
+    fn update_bar(&mut self, bar: &Bar) -> Option<f64> {
+        let price = bar.vwap.unwrap_or((bar.high + bar.low + bar.close) / 3.0);
+        self.next(price, bar.volume as f64)
+    }
+
+    fn update_trade(&mut self, trade: &Trade) -> Option<f64> {
+        self.next(trade.price, f64::from(trade.size))
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.current()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn bar(high: f64, low: f64, close: f64, volume: u64) -> Bar {
+        Bar {
+            timestamp: Utc::now(),
+            open: close,
+            high,
+            low,
+            close,
+            volume,
+            trade_count: None,
+            vwap: None,
+        }
+    }
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_moving_averages_and_bands() {
+        let mut sma = Sma::new(3);
+        let mut ema = Ema::new(3);
+        let mut bands = BollingerBands::new(3, 2.0);
+        for price in [1.0, 2.0] {
+            assert!(sma.next(price).is_none());
+            assert!(ema.next(price).is_none());
+            assert!(bands.next(price).is_none());
+        }
+        assert_eq!(sma.next(3.0), Some(2.0));
+        assert_eq!(ema.next(3.0), Some(2.0));
+        let b = bands.next(3.0).unwrap();
+        assert!(approx(b.middle, 2.0));
+        assert!(approx(b.upper - b.middle, 2.0 * (2.0f64 / 3.0).sqrt()));
+
+        assert_eq!(sma.next(7.0), Some(4.0));
+        assert_eq!(ema.next(7.0), Some(4.5));
+        assert!(approx(bands.next(7.0).unwrap().middle, 4.0));
+
+        sma.reset();
+        assert!(!sma.is_ready());
+
+        let mut macd = Macd::new(2, 3, 2);
+        let values: Vec<_> = (1..=6).map(|p| macd.next(f64::from(p))).collect();
+        assert!(values[..3].iter().all(Option::is_none));
+        let last = values[5].unwrap();
+        assert!(approx(last.histogram, last.macd - last.signal));
+        assert!(last.macd > 0.0);
+    }
+
+    #[test]
+    fn test_rsi_atr_and_vwap() {
+        let mut rsi = Rsi::new(2);
+        assert!(rsi.next(10.0).is_none());
+        assert!(rsi.next(11.0).is_none());
+        assert_eq!(rsi.next(12.0), Some(100.0));
+        // avg gain (1 + 0) / 2 = 0.5, avg loss (0 + 2) / 2 = 1
+        let value = rsi.next(10.0).unwrap();
+        assert!(approx(value, 100.0 - 100.0 / 1.5));
+
+        let mut atr = Atr::new(2);
+        assert!(atr.update_bar(&bar(11.0, 9.0, 10.0, 100)).is_none());
+        // true range uses the gap from the previous close: 14 - 10 = 4
+        assert_eq!(atr.update_bar(&bar(14.0, 12.0, 13.0, 100)), Some(3.0));
+        assert_eq!(atr.update_bar(&bar(14.0, 13.0, 13.0, 100)), Some(2.0));
+
+        let mut vwap = Vwap::new();
+        assert!(vwap.value().is_none());
+        vwap.update_bar(&bar(12.0, 9.0, 9.0, 100));
+        let trade = Trade {
+            timestamp: Utc::now(),
+            price: 13.0,
+            size: 300,
+            exchange: "V".to_string(),
+            conditions: Vec::new(),
+            id: 1,
+        };
+        assert_eq!(vwap.update_trade(&trade), Some(12.25));
+    }
+}
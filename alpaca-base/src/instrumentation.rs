@@ -0,0 +1,456 @@
+//! Request, response and WebSocket message hooks.
+//!
+//! [`Instrumentation`] is attached to the HTTP client
+//! (`AlpacaHttpClient::with_instrumentation`) or to a WebSocket
+//! configuration (`WebSocketConfig::with_instrumentation`) and calls every
+//! registered [`ClientHooks`] implementation:
+//!
+//! - [`ClientHooks::on_request`] before a request is sent, with a
+//!   process-unique request ID;
+//! - [`ClientHooks::on_response`] when it completes, with the HTTP status
+//!   (or the transport error), the latency to the response headers and the
+//!   server's request ID;
+//! - [`ClientHooks::on_ws_message`] for every WebSocket frame sent or
+//!   received.
+//!
+//! URLs and payloads pass through a [`RedactionPolicy`] first, so the API
+//! key and secret (e.g. in the WebSocket `auth` frame) never reach a hook.
+//! [`TracingHooks`] logs every event as structured `tracing` fields, and
+//! [`Instrumentation::spans`] wraps each HTTP request in a span.
+
+use crate::auth::Credentials;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Source of process-unique request IDs.
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// An HTTP request about to be sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestEvent {
+    /// Process-unique ID, repeated in the matching [`ResponseEvent`].
+    pub id: u64,
+    /// HTTP method.
+    pub method: String,
+    /// Redacted request URL, including the query string.
+    pub url: String,
+}
+
+/// A completed HTTP request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseEvent {
+    /// ID of the matching [`RequestEvent`].
+    pub id: u64,
+    /// HTTP method.
+    pub method: String,
+    /// Redacted request URL.
+    pub url: String,
+    /// HTTP status, or `None` if no response arrived.
+    pub status: Option<u16>,
+    /// Time from sending the request to receiving the response headers.
+    pub latency: Duration,
+    /// The server's request ID (`X-Request-ID`), if it sent one.
+    pub server_request_id: Option<String>,
+    /// Transport error, if no response arrived.
+    pub error: Option<String>,
+}
+
+impl ResponseEvent {
+    /// Whether a 2xx response arrived.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+/// Direction of a WebSocket frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsDirection {
+    /// Received from the server.
+    Inbound,
+    /// Sent to the server.
+    Outbound,
+}
+
+/// A WebSocket frame sent or received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsMessageEvent {
+    /// Stream URL.
+    pub url: String,
+    /// Whether the frame was sent or received.
+    pub direction: WsDirection,
+    /// Redacted frame payload; binary frames are decoded lossily.
+    pub payload: String,
+    /// Frame size in bytes, before redaction.
+    pub size: usize,
+}
+
+/// Callbacks for client traffic. Every method defaults to doing nothing.
+///
+/// Hooks run inline on the request path or the socket reader, so they
+/// should return quickly and hand heavy work to another task.
+pub trait ClientHooks: Send + Sync {
+    /// Called before an HTTP request is sent.
+    fn on_request(&self, _event: &RequestEvent) {}
+
+    /// Called when an HTTP request completes or fails to get a response.
+    fn on_response(&self, _event: &ResponseEvent) {}
+
+    /// Called for every WebSocket frame sent or received.
+    fn on_ws_message(&self, _event: &WsMessageEvent) {}
+}
+
+/// Hooks that log every event as structured `tracing` fields at `DEBUG`
+/// level, under the `alpaca::traffic` target.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingHooks;
+
+impl ClientHooks for TracingHooks {
+    fn on_request(&self, event: &RequestEvent) {
+        tracing::debug!(
+            target: "alpaca::traffic",
+            request_id = event.id,
+            method = %event.method,
+            url = %event.url,
+            "request"
+        );
+    }
+
+    fn on_response(&self, event: &ResponseEvent) {
+        tracing::debug!(
+            target: "alpaca::traffic",
+            request_id = event.id,
+            method = %event.method,
+            url = %event.url,
+            status = event.status,
+            latency_ms = event.latency.as_secs_f64() * 1000.0,
+            server_request_id = event.server_request_id.as_deref(),
+            error = event.error.as_deref(),
+            "response"
+        );
+    }
+
+    fn on_ws_message(&self, event: &WsMessageEvent) {
+        tracing::debug!(
+            target: "alpaca::traffic",
+            url = %event.url,
+            direction = ?event.direction,
+            size = event.size,
+            payload = %event.payload,
+            "websocket message"
+        );
+    }
+}
+
+/// What to hide from URLs and payloads before they reach a hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionPolicy {
+    /// Mask the API key (all but its last four characters) and the secret
+    /// key (entirely).
+    pub credentials: bool,
+    /// Query parameters whose values are replaced by `****`.
+    pub query_params: Vec<String>,
+    /// Truncate payloads to this many bytes; `None` keeps them whole.
+    pub max_payload: Option<usize>,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        Self {
+            credentials: true,
+            query_params: vec!["token".to_string(), "access_token".to_string()],
+            max_payload: Some(4096),
+        }
+    }
+}
+
+impl RedactionPolicy {
+    /// A policy that leaves everything as is. Only for local debugging:
+    /// hooks will see the secret key.
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            credentials: false,
+            query_params: Vec::new(),
+            max_payload: None,
+        }
+    }
+
+    /// Also hide the value of query parameter `name`.
+    #[must_use]
+    pub fn query_param(mut self, name: &str) -> Self {
+        self.query_params.push(name.to_string());
+        self
+    }
+
+    /// Set the payload truncation length.
+    #[must_use]
+    pub fn max_payload(mut self, max_payload: Option<usize>) -> Self {
+        self.max_payload = max_payload;
+        self
+    }
+
+    /// Apply the policy to `text`, masking `credentials` where they occur.
+    #[must_use]
+    pub fn redact(&self, text: &str, credentials: &Credentials) -> String {
+        let mut redacted = text.to_string();
+        if self.credentials {
+            if !credentials.secret_key.is_empty() {
+                redacted = redacted.replace(&credentials.secret_key, "****");
+            }
+            if !credentials.api_key.is_empty() {
+                redacted = redacted.replace(&credentials.api_key, &mask_key(&credentials.api_key));
+            }
+        }
+        for name in &self.query_params {
+            redacted = redact_query_param(&redacted, name);
+        }
+        if let Some(max) = self.max_payload
+            && redacted.len() > max
+        {
+            let mut end = max;
+            while !redacted.is_char_boundary(end) {
+                end -= 1;
+            }
+            redacted.truncate(end);
+            redacted.push_str("...");
+        }
+        redacted
+    }
+}
+
+/// Show only the last four characters of a key, and nothing of a short one.
+fn mask_key(key: &str) -> String {
+    const VISIBLE: usize = 4;
+    let len = key.chars().count();
+    if len <= VISIBLE * 2 {
+        return "****".to_string();
+    }
+    let suffix: String = key.chars().skip(len - VISIBLE).collect();
+    format!("****{suffix}")
+}
+
+/// Replace the value of every `name=` query parameter in `url`.
+fn redact_query_param(url: &str, name: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if key == name => format!("{key}=****"),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{base}?{query}")
+}
+
+/// Hooks, redaction and span settings shared by a client and its clones.
+#[derive(Clone, Default)]
+pub struct Instrumentation {
+    hooks: Vec<Arc<dyn ClientHooks>>,
+    redaction: RedactionPolicy,
+    spans: bool,
+}
+
+impl fmt::Debug for Instrumentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Instrumentation")
+            .field("hooks", &self.hooks.len())
+            .field("redaction", &self.redaction)
+            .field("spans", &self.spans)
+            .finish()
+    }
+}
+
+impl Instrumentation {
+    /// Create instrumentation with no hooks and the default redaction.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `hooks`. Hooks are called in registration order.
+    #[must_use]
+    pub fn hook(mut self, hooks: impl ClientHooks + 'static) -> Self {
+        self.hooks.push(Arc::new(hooks));
+        self
+    }
+
+    /// Replace the redaction policy.
+    #[must_use]
+    pub fn redaction(mut self, policy: RedactionPolicy) -> Self {
+        self.redaction = policy;
+        self
+    }
+
+    /// Wrap each HTTP request in an `alpaca.request` `tracing` span
+    /// carrying the request ID, method and path.
+    #[must_use]
+    pub fn spans(mut self, enabled: bool) -> Self {
+        self.spans = enabled;
+        self
+    }
+
+    /// Whether request spans are enabled.
+    #[must_use]
+    pub fn spans_enabled(&self) -> bool {
+        self.spans
+    }
+
+    /// The redaction policy.
+    #[must_use]
+    pub fn redaction_policy(&self) -> &RedactionPolicy {
+        &self.redaction
+    }
+
+    /// Allocate a request ID and report the request to every hook.
+    pub fn request(&self, method: &str, url: &str, credentials: &Credentials) -> RequestEvent {
+        let event = RequestEvent {
+            id: NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+            method: method.to_string(),
+            url: self.redaction.redact(url, credentials),
+        };
+        for hooks in &self.hooks {
+            hooks.on_request(&event);
+        }
+        event
+    }
+
+    /// Report the outcome of `request` to every hook.
+    pub fn response(
+        &self,
+        request: RequestEvent,
+        status: Option<u16>,
+        latency: Duration,
+        server_request_id: Option<String>,
+        error: Option<String>,
+    ) {
+        let event = ResponseEvent {
+            id: request.id,
+            method: request.method,
+            url: request.url,
+            status,
+            latency,
+            server_request_id,
+            error,
+        };
+        for hooks in &self.hooks {
+            hooks.on_response(&event);
+        }
+    }
+
+    /// Report a WebSocket frame to every hook.
+    pub fn ws_message(
+        &self,
+        url: &str,
+        direction: WsDirection,
+        payload: &[u8],
+        credentials: &Credentials,
+    ) {
+        if self.hooks.is_empty() {
+            return;
+        }
+        let event = WsMessageEvent {
+            url: url.to_string(),
+            direction,
+            payload: self
+                .redaction
+                .redact(&String::from_utf8_lossy(payload), credentials),
+            size: payload.len(),
+        };
+        for hooks in &self.hooks {
+            hooks.on_ws_message(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl ClientHooks for Arc<Recorder> {
+        fn on_request(&self, event: &RequestEvent) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("req {}", event.url));
+        }
+
+        fn on_response(&self, event: &ResponseEvent) {
+            self.events.lock().unwrap().push(format!(
+                "resp {:?} {}",
+                event.status,
+                event.is_success()
+            ));
+        }
+
+        fn on_ws_message(&self, event: &WsMessageEvent) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("ws {:?} {}", event.direction, event.payload));
+        }
+    }
+
+    fn credentials() -> Credentials {
+        Credentials::new("PKTEST12345678".to_string(), "supersecret".to_string())
+    }
+
+    #[test]
+    fn test_redaction_policy() {
+        let policy = RedactionPolicy::default();
+        let auth = r#"{"action":"auth","key":"PKTEST12345678","secret":"supersecret"}"#;
+        assert_eq!(
+            policy.redact(auth, &credentials()),
+            r#"{"action":"auth","key":"****5678","secret":"****"}"#
+        );
+        assert_eq!(
+            policy.redact("https://x/v1/oauth?token=abc&limit=5", &credentials()),
+            "https://x/v1/oauth?token=****&limit=5"
+        );
+        assert_eq!(
+            RedactionPolicy::none()
+                .max_payload(Some(4))
+                .redact("supersecret", &credentials()),
+            "supe..."
+        );
+    }
+
+    #[test]
+    fn test_instrumentation_calls_hooks_with_shared_id() {
+        let recorder = Arc::new(Recorder::default());
+        let instrumentation = Instrumentation::new().hook(Arc::clone(&recorder));
+
+        let first = instrumentation.request("GET", "https://x/v2/account", &credentials());
+        let second = instrumentation.request("GET", "https://x/v2/clock", &credentials());
+        assert!(second.id > first.id);
+        instrumentation.response(first, Some(200), Duration::from_millis(12), None, None);
+        instrumentation.ws_message(
+            "wss://x",
+            WsDirection::Outbound,
+            br#"{"secret":"supersecret"}"#,
+            &credentials(),
+        );
+
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            [
+                "req https://x/v2/account",
+                "req https://x/v2/clock",
+                "resp Some(200) true",
+                r#"ws Outbound {"secret":"****"}"#,
+            ]
+        );
+    }
+}
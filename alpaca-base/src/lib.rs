@@ -3,22 +3,161 @@
 //! Base library with common structs, traits, and logic for Alpaca API clients.
 //! This crate provides shared types, error handling, and utilities used across
 //! all Alpaca API client implementations.
+//!
+//! [`prelude`] re-exports the stable, commonly used types; the crate root
+//! re-exports those plus the main type of each module. Everything else is
+//! reached through its module, e.g. [`types`] and [`utils`].
+//!
+//! Experimental modules require the `unstable` feature and may change in
+//! minor releases: `aggregation`, `auction`, `combo`, `drawdown`,
+//! `enrichment`, `fills`, `indicators`, `pdt`, `portfolio` (with tax lots),
+//! `risk`, `supervisor`, `trailing` and `what_if`.
 
+/// Real-time bar aggregation (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod aggregation;
+/// Black-Scholes implied volatility and Greeks (requires `analytics` feature).
+#[cfg(feature = "analytics")]
+pub mod analytics;
+/// Apache Arrow export (requires `arrow` feature).
+#[cfg(feature = "arrow")]
+pub mod arrow;
+/// Opening and closing auction submission windows (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod auction;
 /// Authentication types and utilities.
 pub mod auth;
+/// Endpoint, proxy and TLS settings of the clients.
+pub mod client_config;
+/// Synthetic quotes for multi-leg option combinations (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod combo;
+/// Pluggable credential sources and rotation.
+pub mod credential_provider;
+/// Intraday drawdown monitoring and de-risking (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod drawdown;
+/// Instrument classification from external providers (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod enrichment;
 /// Error types and handling.
 pub mod error;
+/// Order fill reconciliation (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod fills;
+/// Incremental technical indicators (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod indicators;
+/// Request, response and WebSocket message hooks.
+pub mod instrumentation;
+/// Client metrics via the `metrics` facade (requires `metrics` feature).
+#[cfg(feature = "metrics")]
+pub mod metrics;
+/// Option assignment, exercise and expiration events.
+pub mod option_events;
+/// Pattern day trader protection (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod pdt;
+/// Real-time in-memory portfolio state (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod portfolio;
+/// Curated, stable re-exports.
+pub mod prelude;
+/// Backoff and retry with cancellation.
+pub mod retry;
+/// Pre-trade risk checks (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod risk;
+/// Supervision of long-running strategy tasks (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod supervisor;
+// Tax lots, wash sales and tax-aware sell planning; exported from
+// `portfolio`.
+#[cfg(feature = "unstable")]
+mod tax_lots;
 /// Test utilities and fixtures (requires `test-utils` feature).
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
+/// Transport-neutral trading interface.
+pub mod trading;
+/// Client-side trailing stops (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod trailing;
 /// Core API types and data structures.
 pub mod types;
 /// Utility functions and helpers.
 pub mod utils;
+/// Pre-trade what-if analysis (requires `unstable` feature).
+#[cfg(feature = "unstable")]
+pub mod what_if;
 
-pub use auth::*;
+#[cfg(feature = "unstable")]
+pub use aggregation::BarAggregator;
+#[cfg(feature = "analytics")]
+pub use analytics::{BlackScholes, years_to_expiry};
+#[cfg(feature = "unstable")]
+pub use auction::{Auction, AuctionTiming, AuctionWindow, auction_timing};
+pub use auth::{Credentials, CredentialsHandle, OAuthToken};
+pub use client_config::{BROKER_SANDBOX_DATA_URL, BROKER_SANDBOX_URL, ClientConfig, ProxyConfig};
+#[cfg(feature = "unstable")]
+pub use combo::{COMBO_TICK, ComboLeg, ComboQuote, ComboQuoteBuilder};
+#[cfg(feature = "keyring")]
+pub use credential_provider::KeyringCredentialProvider;
+pub use credential_provider::{
+    CredentialProvider, EnvCredentialProvider, SecretFetcherProvider, watch_credentials,
+};
+#[cfg(feature = "unstable")]
+pub use drawdown::{
+    DrawdownAction, DrawdownAuditEntry, DrawdownEvent, DrawdownLevel, DrawdownMonitor,
+    DrawdownThresholds, KillSwitch,
+};
+#[cfg(feature = "unstable")]
+pub use enrichment::{
+    CachedEnricher, InstrumentEnricher, InstrumentProfile, StaticEnricher, UNCLASSIFIED,
+    portfolio_beta, sector_weights,
+};
 pub use error::{
-    AlpacaError, ApiErrorCode, ApiErrorResponse, RateLimitInfo, Result, ValidationError,
+    AlpacaError, ApiErrorCode, ApiErrorKind, ApiErrorResponse, RateLimitInfo, Result,
+    ValidationError,
+};
+#[cfg(feature = "unstable")]
+pub use fills::{
+    Execution, ExecutionKind, FillTracker, OrderState, OrderStateChange, TrackedFill, TrackedOrder,
+};
+#[cfg(feature = "unstable")]
+pub use indicators::{
+    Atr, BollingerBands, BollingerValue, Ema, Indicator, Macd, MacdValue, Rsi, Sma, Vwap,
+};
+pub use instrumentation::{
+    ClientHooks, Instrumentation, RedactionPolicy, RequestEvent, ResponseEvent, TracingHooks,
+    WsDirection, WsMessageEvent,
+};
+pub use option_events::{
+    CONTRACT_MULTIPLIER, OccSymbol, OptionEventDetail, OptionLifecycleEvent, OptionLifecycleKind,
+};
+#[cfg(feature = "unstable")]
+pub use pdt::{
+    DayTrade, PDT_MAX_DAY_TRADES, PDT_MIN_EQUITY, PDT_WINDOW_DAYS, PdtGuard, PdtMode, PdtTracker,
+};
+#[cfg(feature = "unstable")]
+pub use portfolio::{
+    LotMethod, LotSale, PortfolioFill, PortfolioPosition, PortfolioTracker, RealizedSale, SellPlan,
+    TaxLot, TaxLots, WASH_SALE_WINDOW_DAYS, WashSale, WashSaleRisk,
+};
+pub use retry::{Backoff, CancellationToken, Jitter, RetryPolicy, RetryPredicate};
+#[cfg(feature = "unstable")]
+pub use risk::{RiskEngine, RiskLimits, RiskViolation};
+#[cfg(feature = "unstable")]
+pub use supervisor::{Heartbeat, Supervisor, SupervisorConfig, TaskState, TaskStatus};
+pub use trading::{OrderReplacement, OrderRequest, RoutedTradingApi, TradingApi};
+#[cfg(feature = "unstable")]
+pub use trailing::{StopExit, Trail, TrailingStop, TrailingStopManager};
+pub use types::{
+    Account, AccountStatus, Asset, AssetClass, AssetStatus, Bar, Calendar, Clock, Environment,
+    Order, OrderClass, OrderSide, OrderStatus, OrderType, Position, PositionSide, Quote,
+    TimeInForce, Timeframe, Trade,
+};
+#[cfg(feature = "unstable")]
+pub use what_if::{
+    AllocationDrift, ExposureSnapshot, MaintenanceRates, WhatIf, WhatIfReport, WhatIfWarning,
 };
-pub use types::*;
-pub use utils::*;
@@ -0,0 +1,155 @@
+//! Client metrics via the [`metrics`] facade.
+//!
+//! With the `metrics` feature the REST, WebSocket and FIX clients record
+//! the series below into whichever recorder the application installs, e.g.
+//! `metrics-exporter-prometheus` for a Grafana dashboard. Without a
+//! recorder the calls are no-ops.
+//!
+//! | Name | Kind | Labels |
+//! |------|------|--------|
+//! | [`HTTP_REQUESTS`](crate::metrics::HTTP_REQUESTS) | counter | `method`, `endpoint`, `status` |
+//! | [`HTTP_REQUEST_DURATION`](crate::metrics::HTTP_REQUEST_DURATION) | histogram (seconds) | `method`, `endpoint` |
+//! | [`RATE_LIMIT_WAIT`](crate::metrics::RATE_LIMIT_WAIT) | histogram (seconds) | `priority` |
+//! | [`WS_RECONNECTS`](crate::metrics::WS_RECONNECTS) | counter | `outcome` |
+//! | [`WS_DROPPED_MESSAGES`](crate::metrics::WS_DROPPED_MESSAGES) | counter | `policy` |
+//! | [`ORDER_SUBMIT_DURATION`](crate::metrics::ORDER_SUBMIT_DURATION) | histogram (seconds) | `transport`, `outcome` |
+//! | [`FIX_ROUND_TRIP`](crate::metrics::FIX_ROUND_TRIP) | histogram (seconds) | `msg_type` |
+//!
+//! Call [`describe`](crate::metrics::describe) once after installing the
+//! recorder to publish units and help text.
+
+use crate::types::RequestPriority;
+use ::metrics::{Unit, counter, describe_counter, describe_histogram, histogram};
+use std::time::Duration;
+
+/// REST requests sent, by method, endpoint and HTTP status.
+pub const HTTP_REQUESTS: &str = "alpaca_http_requests_total";
+/// REST request latency, by method and endpoint.
+pub const HTTP_REQUEST_DURATION: &str = "alpaca_http_request_duration_seconds";
+/// Time spent waiting for a client-side rate limiter permit.
+pub const RATE_LIMIT_WAIT: &str = "alpaca_rate_limit_wait_seconds";
+/// WebSocket reconnect attempts, by outcome.
+pub const WS_RECONNECTS: &str = "alpaca_ws_reconnects_total";
+/// WebSocket data updates dropped or coalesced for a slow consumer, by
+/// backpressure policy.
+pub const WS_DROPPED_MESSAGES: &str = "alpaca_ws_dropped_messages_total";
+/// Time from submitting an order to the venue acknowledging it.
+pub const ORDER_SUBMIT_DURATION: &str = "alpaca_order_submit_duration_seconds";
+/// Time from sending a FIX message to receiving its response.
+pub const FIX_ROUND_TRIP: &str = "alpaca_fix_round_trip_seconds";
+
+/// Register units and descriptions for every series.
+pub fn describe() {
+    describe_counter!(HTTP_REQUESTS, Unit::Count, "REST requests sent");
+    describe_histogram!(HTTP_REQUEST_DURATION, Unit::Seconds, "REST request latency");
+    describe_histogram!(
+        RATE_LIMIT_WAIT,
+        Unit::Seconds,
+        "Time spent waiting for a rate limiter permit"
+    );
+    describe_counter!(WS_RECONNECTS, Unit::Count, "WebSocket reconnect attempts");
+    describe_counter!(
+        WS_DROPPED_MESSAGES,
+        Unit::Count,
+        "WebSocket updates dropped for slow consumers"
+    );
+    describe_histogram!(
+        ORDER_SUBMIT_DURATION,
+        Unit::Seconds,
+        "Order submission latency"
+    );
+    describe_histogram!(FIX_ROUND_TRIP, Unit::Seconds, "FIX request round-trip time");
+}
+
+/// Collapse the IDs in a request path so the endpoint label has bounded
+/// cardinality: `/v2/orders/6f1c…` becomes `/v2/orders/{id}`.
+///
+/// A segment is treated as an ID if it contains a digit and is either
+/// longer than 8 characters or purely numeric; version prefixes and
+/// ticker symbols are kept.
+#[must_use]
+pub fn endpoint_label(path: &str) -> String {
+    let path = path.split('?').next().unwrap_or_default();
+    path.split('/')
+        .map(|segment| {
+            let has_digit = segment.bytes().any(|b| b.is_ascii_digit());
+            let numeric = !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit());
+            if has_digit && (segment.len() > 8 || numeric) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Record a REST request; `status` is `None` when no response arrived.
+pub fn record_http_request(method: &str, path: &str, status: Option<u16>, latency: Duration) {
+    let method = method.to_string();
+    let endpoint = endpoint_label(path);
+    let status = status.map_or_else(|| "error".to_string(), |s| s.to_string());
+    counter!(
+        HTTP_REQUESTS,
+        "method" => method.clone(),
+        "endpoint" => endpoint.clone(),
+        "status" => status
+    )
+    .increment(1);
+    histogram!(HTTP_REQUEST_DURATION, "method" => method, "endpoint" => endpoint)
+        .record(latency.as_secs_f64());
+}
+
+/// Record the time a request waited for a rate limiter permit.
+pub fn record_rate_limit_wait(priority: RequestPriority, waited: Duration) {
+    let priority = match priority {
+        RequestPriority::Low => "low",
+        RequestPriority::Normal => "normal",
+        RequestPriority::High => "high",
+        RequestPriority::Critical => "critical",
+    };
+    histogram!(RATE_LIMIT_WAIT, "priority" => priority).record(waited.as_secs_f64());
+}
+
+/// Record a WebSocket reconnect attempt.
+pub fn record_ws_reconnect(success: bool) {
+    let outcome = if success { "success" } else { "failure" };
+    counter!(WS_RECONNECTS, "outcome" => outcome).increment(1);
+}
+
+/// Record a WebSocket data update dropped under the backpressure
+/// `policy`.
+pub fn record_ws_dropped(policy: &'static str) {
+    counter!(WS_DROPPED_MESSAGES, "policy" => policy).increment(1);
+}
+
+/// Record an order submission over `transport` (`"http"` or `"fix"`).
+pub fn record_order_submit(transport: &'static str, success: bool, latency: Duration) {
+    let outcome = if success { "success" } else { "failure" };
+    histogram!(ORDER_SUBMIT_DURATION, "transport" => transport, "outcome" => outcome)
+        .record(latency.as_secs_f64());
+}
+
+/// Record the round trip of a FIX request of type `msg_type`.
+pub fn record_fix_round_trip(msg_type: &str, latency: Duration) {
+    histogram!(FIX_ROUND_TRIP, "msg_type" => msg_type.to_string()).record(latency.as_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_label_collapses_ids() {
+        assert_eq!(
+            endpoint_label("/v2/orders/6f1c8a52-5b7e-4c1e-9f0e-3a5d2b7c1e44"),
+            "/v2/orders/{id}"
+        );
+        assert_eq!(
+            endpoint_label("/v1/accounts/12345/transfers"),
+            "/v1/accounts/{id}/transfers"
+        );
+        assert_eq!(endpoint_label("/v2/positions/AAPL"), "/v2/positions/AAPL");
+        assert_eq!(endpoint_label("/v1beta1/news?limit=5"), "/v1beta1/news");
+    }
+}
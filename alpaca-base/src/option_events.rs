@@ -0,0 +1,383 @@
+//! Typed option assignment, exercise and expiration events.
+//!
+//! Account activities report option lifecycle events with the `OPASN`,
+//! `OPXRC` and `OPEXP` codes but otherwise look like any other non-trade
+//! activity. [`OptionLifecycleEvent`] decodes the contract from its OCC
+//! symbol ([`OccSymbol`]) and works out what the event does to the
+//! underlying position and to cash:
+//!
+//! | event | call | put |
+//! |---|---|---|
+//! | exercise (long) | buy shares at strike | sell shares at strike |
+//! | assignment (short) | sell shares at strike | buy shares at strike |
+//! | expiration | no shares, no cash | no shares, no cash |
+
+use crate::error::{AlpacaError, Result};
+use crate::types::{AccountActivity, ActivityType, NonTradeActivity, OptionType, OrderSide};
+use chrono::NaiveDate;
+use std::fmt;
+
+/// Shares delivered per standard equity option contract.
+pub const CONTRACT_MULTIPLIER: f64 = 100.0;
+
+/// Length of the date, type and strike suffix of an OCC symbol.
+const OCC_SUFFIX_LEN: usize = 15;
+
+/// An option contract decoded from its OCC symbol, e.g.
+/// `AAPL240119C00190000`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccSymbol {
+    /// Root (underlying) symbol.
+    pub underlying: String,
+    /// Expiration date.
+    pub expiration: NaiveDate,
+    /// Call or put.
+    pub option_type: OptionType,
+    /// Strike price in dollars.
+    pub strike: f64,
+}
+
+impl OccSymbol {
+    /// Parse an OCC symbol, with or without the space padding of the root.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if the symbol is malformed.
+    pub fn parse(symbol: &str) -> Result<Self> {
+        let invalid = || AlpacaError::InvalidData(format!("invalid OCC symbol: {symbol}"));
+        let symbol = symbol.trim();
+        if !symbol.is_ascii() || symbol.len() <= OCC_SUFFIX_LEN {
+            return Err(invalid());
+        }
+        let (root, suffix) = symbol.split_at(symbol.len() - OCC_SUFFIX_LEN);
+        let underlying = root.trim_end();
+        if underlying.is_empty() || underlying.len() > 6 {
+            return Err(invalid());
+        }
+        let expiration =
+            NaiveDate::parse_from_str(&suffix[..6], "%y%m%d").map_err(|_| invalid())?;
+        let option_type = match &suffix[6..7] {
+            "C" => OptionType::Call,
+            "P" => OptionType::Put,
+            _ => return Err(invalid()),
+        };
+        let strike_digits = &suffix[7..];
+        if !strike_digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let strike = strike_digits.parse::<u64>().map_err(|_| invalid())? as f64 / 1000.0;
+        Ok(Self {
+            underlying: underlying.to_string(),
+            expiration,
+            option_type,
+            strike,
+        })
+    }
+}
+
+impl fmt::Display for OccSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.option_type {
+            OptionType::Call => 'C',
+            OptionType::Put => 'P',
+        };
+        write!(
+            f,
+            "{}{}{}{:08}",
+            self.underlying,
+            self.expiration.format("%y%m%d"),
+            kind,
+            (self.strike * 1000.0).round() as u64
+        )
+    }
+}
+
+/// Kind of option lifecycle event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionLifecycleKind {
+    /// A short position was assigned (`OPASN`).
+    Assignment,
+    /// A long position was exercised (`OPXRC`).
+    Exercise,
+    /// The contract expired worthless (`OPEXP`).
+    Expiration,
+}
+
+impl OptionLifecycleKind {
+    /// The lifecycle kind for an activity type, if it is one.
+    #[must_use]
+    pub fn from_activity_type(activity_type: &ActivityType) -> Option<Self> {
+        match activity_type {
+            ActivityType::Opasn => Some(Self::Assignment),
+            ActivityType::Opxrc => Some(Self::Exercise),
+            ActivityType::Opexp => Some(Self::Expiration),
+            _ => None,
+        }
+    }
+}
+
+/// What an exercise or assignment did: the contract, how many contracts
+/// and the resulting shares.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionEventDetail {
+    /// The option contract.
+    pub contract: OccSymbol,
+    /// Number of contracts exercised or assigned.
+    pub qty: f64,
+    /// Underlying shares received (positive) or delivered (negative).
+    pub resulting_shares: f64,
+    /// Price per share of the delivery (the strike).
+    pub delivery_price: f64,
+}
+
+impl OptionEventDetail {
+    /// Underlying symbol of the resulting shares.
+    #[must_use]
+    pub fn underlying(&self) -> &str {
+        &self.contract.underlying
+    }
+}
+
+/// An option assignment, exercise or expiration decoded from an account
+/// activity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionLifecycleEvent {
+    /// Activity ID.
+    pub id: String,
+    /// Kind of event.
+    pub kind: OptionLifecycleKind,
+    /// Date of the event.
+    pub date: NaiveDate,
+    /// The option contract.
+    pub contract: OccSymbol,
+    /// Number of contracts affected (always positive).
+    pub contracts: f64,
+    /// Change in underlying shares: positive when shares are received.
+    pub share_delta: f64,
+    /// Cash paid (negative) or received (positive) for the delivered shares.
+    pub cash_impact: f64,
+    /// Net amount as reported on the activity.
+    pub net_amount: f64,
+}
+
+impl OptionLifecycleEvent {
+    /// Decode an event from its parts.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if the activity is not an option
+    /// lifecycle event or a field cannot be parsed.
+    pub fn from_parts(
+        id: &str,
+        activity_type: &ActivityType,
+        date: &str,
+        symbol: Option<&str>,
+        qty: Option<&str>,
+        net_amount: &str,
+    ) -> Result<Self> {
+        let kind = OptionLifecycleKind::from_activity_type(activity_type).ok_or_else(|| {
+            AlpacaError::InvalidData(format!(
+                "activity {id} is not an option lifecycle event: {activity_type:?}"
+            ))
+        })?;
+        let missing =
+            |field: &str| AlpacaError::InvalidData(format!("activity {id}: missing {field}"));
+        let contract = OccSymbol::parse(symbol.ok_or_else(|| missing("symbol"))?)?;
+        let contracts = parse_number(qty.ok_or_else(|| missing("qty"))?)?.abs();
+        let date = date
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .ok_or_else(|| {
+                AlpacaError::InvalidData(format!("activity {id}: invalid date {date}"))
+            })?;
+
+        let shares = contracts * CONTRACT_MULTIPLIER;
+        let share_delta = match (kind, &contract.option_type) {
+            (OptionLifecycleKind::Expiration, _) => 0.0,
+            (OptionLifecycleKind::Exercise, OptionType::Call)
+            | (OptionLifecycleKind::Assignment, OptionType::Put) => shares,
+            (OptionLifecycleKind::Exercise, OptionType::Put)
+            | (OptionLifecycleKind::Assignment, OptionType::Call) => -shares,
+        };
+        Ok(Self {
+            id: id.to_string(),
+            kind,
+            date,
+            cash_impact: -share_delta * contract.strike,
+            contract,
+            contracts,
+            share_delta,
+            net_amount: parse_number(net_amount).unwrap_or(0.0),
+        })
+    }
+
+    /// Decode an event from a non-trade activity.
+    ///
+    /// # Errors
+    /// See [`OptionLifecycleEvent::from_parts`].
+    pub fn from_activity(activity: &NonTradeActivity) -> Result<Self> {
+        Self::from_parts(
+            &activity.id,
+            &activity.activity_type,
+            &activity.date,
+            activity.symbol.as_deref(),
+            activity.qty.as_deref(),
+            &activity.net_amount,
+        )
+    }
+
+    /// Decode an event from an account activity.
+    ///
+    /// # Errors
+    /// See [`OptionLifecycleEvent::from_parts`].
+    pub fn from_account_activity(activity: &AccountActivity) -> Result<Self> {
+        Self::from_parts(
+            &activity.id,
+            &activity.activity_type,
+            &activity.date,
+            activity.symbol.as_deref(),
+            activity.qty.as_deref(),
+            &activity.net_amount,
+        )
+    }
+
+    /// Decode the option lifecycle events in a list of account activities,
+    /// skipping every other activity type.
+    ///
+    /// # Errors
+    /// Returns the first lifecycle activity that cannot be decoded.
+    pub fn collect(activities: &[AccountActivity]) -> Result<Vec<Self>> {
+        activities
+            .iter()
+            .filter(|a| OptionLifecycleKind::from_activity_type(&a.activity_type).is_some())
+            .map(Self::from_account_activity)
+            .collect()
+    }
+
+    /// Whether the event is an exercise or assignment, i.e. delivers shares.
+    #[must_use]
+    pub fn is_exercise_or_assignment(&self) -> bool {
+        self.kind != OptionLifecycleKind::Expiration
+    }
+
+    /// The contract, quantity and resulting shares of an exercise or
+    /// assignment; `None` for expirations.
+    #[must_use]
+    pub fn detail(&self) -> Option<OptionEventDetail> {
+        self.is_exercise_or_assignment().then(|| OptionEventDetail {
+            contract: self.contract.clone(),
+            qty: self.contracts,
+            resulting_shares: self.share_delta,
+            delivery_price: self.contract.strike,
+        })
+    }
+
+    /// Side of the underlying trade the event amounts to, if shares moved.
+    #[must_use]
+    pub fn underlying_side(&self) -> Option<OrderSide> {
+        if self.share_delta > 0.0 {
+            Some(OrderSide::Buy)
+        } else if self.share_delta < 0.0 {
+            Some(OrderSide::Sell)
+        } else {
+            None
+        }
+    }
+
+    /// Change in the option position, in contracts: exercises and
+    /// expirations close long contracts, assignments close short ones.
+    /// Expirations are reported without a side, so they are treated as long.
+    #[must_use]
+    pub fn contract_delta(&self) -> f64 {
+        match self.kind {
+            OptionLifecycleKind::Assignment => self.contracts,
+            OptionLifecycleKind::Exercise | OptionLifecycleKind::Expiration => -self.contracts,
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Result<f64> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| AlpacaError::InvalidData(format!("invalid number: {value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activity(activity_type: ActivityType, symbol: &str, qty: &str) -> AccountActivity {
+        serde_json::from_value(serde_json::json!({
+            "id": "20240119000000000::1",
+            "account_id": "904837e3-3b76-47ec-b432-046db621571b",
+            "activity_type": activity_type,
+            "date": "2024-01-19",
+            "net_amount": "0",
+            "symbol": symbol,
+            "qty": qty,
+            "per_share_amount": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_occ_symbol_round_trip() {
+        let occ = OccSymbol::parse("AAPL240119C00190000").unwrap();
+        assert_eq!(occ.underlying, "AAPL");
+        assert_eq!(
+            occ.expiration,
+            NaiveDate::from_ymd_opt(2024, 1, 19).unwrap()
+        );
+        assert_eq!(occ.option_type, OptionType::Call);
+        assert_eq!(occ.strike, 190.0);
+        assert_eq!(occ.to_string(), "AAPL240119C00190000");
+
+        let padded = OccSymbol::parse("SPY   240621P00512500").unwrap();
+        assert_eq!(padded.underlying, "SPY");
+        assert_eq!(padded.strike, 512.5);
+
+        assert!(OccSymbol::parse("AAPL").is_err());
+        assert!(OccSymbol::parse("AAPL240119X00190000").is_err());
+    }
+
+    #[test]
+    fn test_lifecycle_share_and_cash_impact() {
+        let assigned_put = activity(ActivityType::Opasn, "AAPL240119P00180000", "-2");
+        let event = OptionLifecycleEvent::from_account_activity(&assigned_put).unwrap();
+        assert_eq!(event.kind, OptionLifecycleKind::Assignment);
+        assert_eq!(event.contracts, 2.0);
+        assert_eq!(event.share_delta, 200.0);
+        assert_eq!(event.cash_impact, -36_000.0);
+        assert_eq!(event.underlying_side(), Some(OrderSide::Buy));
+        assert_eq!(event.contract_delta(), 2.0);
+        let detail = event.detail().unwrap();
+        assert_eq!(detail.underlying(), "AAPL");
+        assert_eq!(
+            (detail.qty, detail.resulting_shares, detail.delivery_price),
+            (2.0, 200.0, 180.0)
+        );
+
+        #[cfg(feature = "unstable")]
+        {
+            let mut lots = crate::tax_lots::TaxLots::new();
+            lots.record_option_event(&event);
+            assert_eq!(lots.open_qty("AAPL"), 200.0);
+            assert_eq!(lots.open_lots("AAPL")[0].cost, 180.0);
+        }
+
+        let exercised_put = activity(ActivityType::Opxrc, "AAPL240119P00180000", "1");
+        let event = OptionLifecycleEvent::from_account_activity(&exercised_put).unwrap();
+        assert_eq!(event.share_delta, -100.0);
+        assert_eq!(event.cash_impact, 18_000.0);
+
+        let activities = vec![
+            activity(ActivityType::Opexp, "AAPL240119C00190000", "1"),
+            activity(ActivityType::Div, "AAPL", "10"),
+        ];
+        let events = OptionLifecycleEvent::collect(&activities).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].share_delta, 0.0);
+        assert_eq!(events[0].underlying_side(), None);
+        assert!(events[0].detail().is_none());
+        assert!(OptionLifecycleEvent::from_account_activity(&activities[1]).is_err());
+    }
+}
@@ -0,0 +1,442 @@
+//! Pattern day trader protection.
+//!
+//! FINRA flags a margin account as a pattern day trader after four or more
+//! day trades within five business days, and a flagged account under
+//! $25,000 of equity is restricted from day trading. [`PdtTracker`] counts
+//! day trades in the rolling window from account activities and local fills,
+//! and [`PdtGuard`] wraps any [`TradingApi`] to warn about or block orders
+//! that would close a position opened the same day once no day trade is
+//! left.
+//!
+//! A day trade is a fill that reduces a position in a symbol which was
+//! opened or increased earlier the same US Eastern trading day. Fills of one
+//! order count once. The window is the current and previous four weekdays;
+//! market holidays are not skipped, so the local count errs on the side of
+//! caution.
+
+use crate::error::{AlpacaError, Result};
+use crate::risk::RiskViolation;
+use crate::trading::{OrderReplacement, OrderRequest, TradingApi};
+use crate::types::{Account, Order, OrderSide, Position, TradeActivity};
+use crate::utils::us_eastern_offset;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Equity below which a pattern day trader may not day trade.
+pub const PDT_MIN_EQUITY: f64 = 25_000.0;
+
+/// Day trades allowed in the rolling window before the account is flagged.
+pub const PDT_MAX_DAY_TRADES: u32 = 3;
+
+/// Business days in the rolling day trade window.
+pub const PDT_WINDOW_DAYS: usize = 5;
+
+/// What a [`PdtGuard`] does with an order that would trigger a PDT flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PdtMode {
+    /// Log a warning and submit the order.
+    Warn,
+    /// Reject the order with [`AlpacaError::RiskRejected`].
+    #[default]
+    Block,
+}
+
+/// A day trade counted by a [`PdtTracker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayTrade {
+    /// Symbol traded.
+    pub symbol: String,
+    /// US Eastern trading day of the closing fill.
+    pub date: NaiveDate,
+}
+
+/// Day trades in the rolling five business day window.
+///
+/// Feed it the account with [`Self::update_account`], overnight positions
+/// with [`Self::set_position`], and fills with [`Self::record_activities`]
+/// or [`Self::record_fill`]. The count used is the larger of the local count
+/// and the account's `daytrade_count`, so fills the server has not yet
+/// counted are not missed.
+#[derive(Debug, Clone, Default)]
+pub struct PdtTracker {
+    equity: Option<f64>,
+    server_count: u32,
+    positions: HashMap<String, f64>,
+    opened: HashMap<(String, NaiveDate), f64>,
+    counted_orders: HashSet<Uuid>,
+    day_trades: Vec<DayTrade>,
+}
+
+impl PdtTracker {
+    /// Create a tracker with no positions and unknown equity.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the equity and server day trade count from `account`.
+    pub fn update_account(&mut self, account: &Account) {
+        self.equity = account.equity.parse().ok();
+        self.server_count = u32::try_from(account.daytrade_count).unwrap_or(0);
+    }
+
+    /// Set the position held in `symbol` before today's fills, as a signed
+    /// quantity.
+    pub fn set_position(&mut self, symbol: &str, qty: f64) {
+        self.positions.insert(symbol.to_string(), qty);
+    }
+
+    /// The tracked signed position in `symbol`.
+    #[must_use]
+    pub fn position(&self, symbol: &str) -> f64 {
+        self.positions.get(symbol).copied().unwrap_or(0.0)
+    }
+
+    /// Record account fill activities in time order.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if an activity has an
+    /// unparseable quantity; activities before it are recorded.
+    pub fn record_activities(&mut self, activities: &[TradeActivity]) -> Result<()> {
+        let mut sorted: Vec<&TradeActivity> = activities.iter().collect();
+        sorted.sort_by_key(|a| a.transaction_time);
+        for activity in sorted {
+            let qty = activity.qty.parse::<f64>().map_err(|_| {
+                AlpacaError::InvalidData(format!(
+                    "invalid quantity {:?} in activity {}",
+                    activity.qty, activity.id
+                ))
+            })?;
+            self.apply(
+                &activity.symbol,
+                &activity.side,
+                qty,
+                activity.transaction_time,
+                Some(activity.order_id),
+            );
+        }
+        Ok(())
+    }
+
+    /// Record a local fill of `qty` at `time`. Returns `true` if it was
+    /// counted as a day trade.
+    pub fn record_fill(
+        &mut self,
+        symbol: &str,
+        side: &OrderSide,
+        qty: f64,
+        time: DateTime<Utc>,
+    ) -> bool {
+        self.apply(symbol, side, qty, time, None)
+    }
+
+    fn apply(
+        &mut self,
+        symbol: &str,
+        side: &OrderSide,
+        qty: f64,
+        time: DateTime<Utc>,
+        order_id: Option<Uuid>,
+    ) -> bool {
+        let date = trading_date(time);
+        let current = self.position(symbol);
+        let resulting = current + signed(side, qty);
+        let key = (symbol.to_string(), date);
+
+        let flips = resulting * current < 0.0;
+        let (closed, added) = if flips {
+            (current.abs(), resulting.abs())
+        } else {
+            (
+                (current.abs() - resulting.abs()).max(0.0),
+                (resulting.abs() - current.abs()).max(0.0),
+            )
+        };
+
+        let mut day_trade = false;
+        let opened = self.opened.get(&key).copied().unwrap_or(0.0);
+        if closed > 0.0 && opened > 0.0 {
+            if order_id.is_none_or(|id| self.counted_orders.insert(id)) {
+                self.day_trades.push(DayTrade {
+                    symbol: symbol.to_string(),
+                    date,
+                });
+                day_trade = true;
+            }
+            self.opened.insert(key.clone(), (opened - closed).max(0.0));
+        }
+        if added > 0.0 {
+            *self.opened.entry(key).or_default() += added;
+        }
+        self.positions.insert(symbol.to_string(), resulting);
+        day_trade
+    }
+
+    /// Day trades within the window ending on `now`'s trading day.
+    #[must_use]
+    pub fn day_trades(&self, now: DateTime<Utc>) -> Vec<&DayTrade> {
+        let start = window_start(trading_date(now));
+        self.day_trades.iter().filter(|t| t.date >= start).collect()
+    }
+
+    /// Day trades counted in the window, locally or by the server.
+    #[must_use]
+    pub fn day_trade_count(&self, now: DateTime<Utc>) -> u32 {
+        let local = u32::try_from(self.day_trades(now).len()).unwrap_or(u32::MAX);
+        local.max(self.server_count)
+    }
+
+    /// Whether the account's equity is below [`PDT_MIN_EQUITY`]. Unknown
+    /// equity counts as restricted.
+    #[must_use]
+    pub fn is_restricted(&self) -> bool {
+        self.equity.is_none_or(|equity| equity < PDT_MIN_EQUITY)
+    }
+
+    /// Day trades left before the account is flagged, or `None` when its
+    /// equity is at or above [`PDT_MIN_EQUITY`].
+    #[must_use]
+    pub fn remaining_day_trades(&self) -> Option<u32> {
+        self.remaining_day_trades_at(Utc::now())
+    }
+
+    /// [`Self::remaining_day_trades`] as of `now`.
+    #[must_use]
+    pub fn remaining_day_trades_at(&self, now: DateTime<Utc>) -> Option<u32> {
+        self.is_restricted()
+            .then(|| PDT_MAX_DAY_TRADES.saturating_sub(self.day_trade_count(now)))
+    }
+
+    /// Whether filling `order` at `now` would be a day trade.
+    #[must_use]
+    pub fn is_day_trade(&self, order: &OrderRequest, now: DateTime<Utc>) -> bool {
+        let current = self.position(&order.symbol);
+        let resulting = current + signed(&order.side, order.qty);
+        let reduces = resulting.abs() < current.abs() || resulting * current < 0.0;
+        reduces
+            && self
+                .opened
+                .get(&(order.symbol.clone(), trading_date(now)))
+                .is_some_and(|opened| *opened > 0.0)
+    }
+
+    /// Check whether `order` would take the account over the day trade
+    /// limit.
+    ///
+    /// # Errors
+    /// Returns [`RiskViolation::PatternDayTrade`] if the order is a day
+    /// trade and none are left.
+    pub fn check(
+        &self,
+        order: &OrderRequest,
+        now: DateTime<Utc>,
+    ) -> std::result::Result<(), RiskViolation> {
+        if self.remaining_day_trades_at(now) == Some(0) && self.is_day_trade(order, now) {
+            return Err(RiskViolation::PatternDayTrade {
+                symbol: order.symbol.clone(),
+                day_trades: self.day_trade_count(now),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A [`TradingApi`] that guards order submission against PDT flags.
+///
+/// The [`PdtTracker`] is shared with whatever records fills, so the guard
+/// sees today's opened positions.
+#[derive(Debug)]
+pub struct PdtGuard<T> {
+    inner: T,
+    tracker: Arc<Mutex<PdtTracker>>,
+    mode: PdtMode,
+}
+
+impl<T: TradingApi> PdtGuard<T> {
+    /// Guard orders to `inner` using `tracker`, blocking PDT violations.
+    #[must_use]
+    pub fn new(inner: T, tracker: Arc<Mutex<PdtTracker>>) -> Self {
+        Self {
+            inner,
+            tracker,
+            mode: PdtMode::default(),
+        }
+    }
+
+    /// Set what happens to an order that would trigger a PDT flag.
+    #[must_use]
+    pub fn mode(mut self, mode: PdtMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// The wrapped API.
+    #[must_use]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Day trades left before the account is flagged, or `None` when its
+    /// equity is at or above [`PDT_MIN_EQUITY`].
+    #[must_use]
+    pub fn remaining_day_trades(&self) -> Option<u32> {
+        self.tracker
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remaining_day_trades()
+    }
+
+    fn enforce(&self, order: &OrderRequest) -> Result<()> {
+        let checked = self
+            .tracker
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .check(order, Utc::now());
+        match (checked, self.mode) {
+            (Ok(()), _) => Ok(()),
+            (Err(violation), PdtMode::Warn) => {
+                warn!(symbol = %order.symbol, %violation, "order would trigger a PDT flag");
+                Ok(())
+            }
+            (Err(violation), PdtMode::Block) => {
+                warn!(symbol = %order.symbol, %violation, "order rejected by PDT guard");
+                Err(AlpacaError::RiskRejected(violation))
+            }
+        }
+    }
+}
+
+impl<T: TradingApi> TradingApi for PdtGuard<T> {
+    fn submit_order(&self, order: &OrderRequest) -> impl Future<Output = Result<Order>> + Send {
+        let checked = self.enforce(order);
+        let order = order.clone();
+        async move {
+            checked?;
+            self.inner.submit_order(&order).await
+        }
+    }
+
+    fn cancel_order(&self, order: &Order) -> impl Future<Output = Result<()>> + Send {
+        self.inner.cancel_order(order)
+    }
+
+    fn replace_order(
+        &self,
+        order: &Order,
+        replacement: &OrderReplacement,
+    ) -> impl Future<Output = Result<Order>> + Send {
+        self.inner.replace_order(order, replacement)
+    }
+
+    fn get_positions(&self) -> impl Future<Output = Result<Vec<Position>>> + Send {
+        self.inner.get_positions()
+    }
+
+    /// Fetches the account and refreshes the tracker's equity and server
+    /// day trade count from it.
+    async fn get_account(&self) -> Result<Account> {
+        let account = self.inner.get_account().await?;
+        self.tracker
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .update_account(&account);
+        Ok(account)
+    }
+}
+
+fn signed(side: &OrderSide, qty: f64) -> f64 {
+    match side {
+        OrderSide::Buy => qty,
+        OrderSide::Sell => -qty,
+    }
+}
+
+/// The US Eastern calendar date of `time`.
+fn trading_date(time: DateTime<Utc>) -> NaiveDate {
+    let date = time.date_naive();
+    time.with_timezone(&us_eastern_offset(date)).date_naive()
+}
+
+/// The first day of the window ending on `date`: the weekday
+/// [`PDT_WINDOW_DAYS`] - 1 business days earlier.
+fn window_start(date: NaiveDate) -> NaiveDate {
+    let mut start = date;
+    let mut counted = usize::from(!matches!(date.weekday(), Weekday::Sat | Weekday::Sun));
+    while counted < PDT_WINDOW_DAYS {
+        start -= Duration::days(1);
+        if !matches!(start.weekday(), Weekday::Sat | Weekday::Sun) {
+            counted += 1;
+        }
+    }
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::{MockTradingApi, sample_account};
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_pdt_guard_counts_window_and_blocks() {
+        let at = |day, hour| Utc.with_ymd_and_hms(2024, 6, day, hour, 0, 0).unwrap();
+        let mut tracker = PdtTracker::new();
+        // Mon 3 and Tue 4 June: two round trips; Wed 5: one more.
+        for day in [3, 4, 5] {
+            assert!(!tracker.record_fill("AAPL", &OrderSide::Buy, 10.0, at(day, 14)));
+            assert!(tracker.record_fill("AAPL", &OrderSide::Sell, 10.0, at(day, 15)));
+        }
+        // Overnight position closed the next day is not a day trade.
+        assert!(!tracker.record_fill("MSFT", &OrderSide::Buy, 5.0, at(5, 15)));
+        assert!(!tracker.record_fill("MSFT", &OrderSide::Sell, 5.0, at(6, 14)));
+
+        let now = at(6, 16);
+        assert_eq!(tracker.day_trade_count(now), 3);
+        // Equity unknown counts as restricted.
+        assert_eq!(tracker.remaining_day_trades_at(now), Some(0));
+        // Mon 3 June drops out of the window the following Monday.
+        assert_eq!(tracker.day_trade_count(at(10, 16)), 2);
+
+        let tracker = Arc::new(Mutex::new(tracker));
+        let mut account = sample_account();
+        account.equity = "10000.00".to_string();
+        let api = MockTradingApi::new().with_account(account);
+        let guard = PdtGuard::new(api, Arc::clone(&tracker));
+        guard.get_account().await.unwrap();
+
+        tracker
+            .lock()
+            .unwrap()
+            .record_fill("TSLA", &OrderSide::Buy, 3.0, Utc::now());
+        let close = OrderRequest::market("TSLA", OrderSide::Sell, 3.0);
+        let opening = OrderRequest::market("NVDA", OrderSide::Buy, 3.0);
+        {
+            let mut tracker = tracker.lock().unwrap();
+            for _ in 0..3 {
+                tracker.record_fill("AMD", &OrderSide::Buy, 1.0, Utc::now());
+                tracker.record_fill("AMD", &OrderSide::Sell, 1.0, Utc::now());
+            }
+        }
+        assert_eq!(guard.remaining_day_trades(), Some(0));
+        match guard.submit_order(&close).await {
+            Err(AlpacaError::RiskRejected(RiskViolation::PatternDayTrade { symbol, .. })) => {
+                assert_eq!(symbol, "TSLA");
+            }
+            other => panic!("expected a PDT rejection, got {other:?}"),
+        }
+        guard.submit_order(&opening).await.unwrap();
+
+        let guard = guard.mode(PdtMode::Warn);
+        guard.submit_order(&close).await.unwrap();
+        assert_eq!(guard.inner().submit_count(), 2);
+
+        let mut rich = sample_account();
+        rich.equity = "30000.00".to_string();
+        tracker.lock().unwrap().update_account(&rich);
+        assert_eq!(guard.remaining_day_trades(), None);
+    }
+}
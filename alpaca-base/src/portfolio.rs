@@ -0,0 +1,411 @@
+//! Real-time in-memory portfolio state.
+//!
+//! [`PortfolioTracker`] keeps positions, cash and realized/unrealized P&L
+//! current from fills (built from trade-update events) and latest prices
+//! (from quotes or trades), so strategies do not have to re-poll
+//! `/v2/positions` after every fill. Seed it once from the REST snapshot
+//! with [`PortfolioTracker::from_snapshot`], then export the live state as
+//! [`Position`]s and an [`Account`] at any time.
+//!
+//! Positions use average-cost accounting: adding to a position moves its
+//! average entry price, reducing it realizes P&L against that price, and a
+//! fill that crosses zero closes the position and opens the remainder on
+//! the other side at the fill price.
+//!
+//! For tax reporting, [`TaxLots`] keeps numbered purchase lots from the
+//! same fills ([`TaxLots::record_portfolio_fill`]) and relieves them under
+//! a [`LotMethod`] or specific lot designations, flagging wash sales and
+//! planning tax-aware sells.
+
+use crate::types::{Account, AssetClass, OrderSide, Position, PositionSide, Quote};
+use crate::utils::QTY_EPSILON;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+pub use crate::tax_lots::{
+    LotMethod, LotSale, RealizedSale, SellPlan, TaxLot, TaxLots, WASH_SALE_WINDOW_DAYS, WashSale,
+    WashSaleRisk,
+};
+
+/// A fill applied to a [`PortfolioTracker`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioFill {
+    /// Symbol.
+    pub symbol: String,
+    /// Buy or sell.
+    pub side: OrderSide,
+    /// Filled quantity.
+    pub qty: f64,
+    /// Fill price.
+    pub price: f64,
+    /// Fill time.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl PortfolioFill {
+    /// Create a fill.
+    #[must_use]
+    pub fn new(symbol: &str, side: OrderSide, qty: f64, price: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            qty,
+            price,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Signed quantity: negative for sells.
+    #[must_use]
+    pub fn signed_qty(&self) -> f64 {
+        match self.side {
+            OrderSide::Buy => self.qty,
+            OrderSide::Sell => -self.qty,
+        }
+    }
+}
+
+/// A tracked position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioPosition {
+    /// Symbol.
+    pub symbol: String,
+    /// Signed quantity: negative when short.
+    pub qty: f64,
+    /// Average entry price.
+    pub avg_entry_price: f64,
+    /// P&L realized on this symbol.
+    pub realized_pnl: f64,
+    /// Latest price.
+    pub last_price: Option<f64>,
+    /// Previous close.
+    pub lastday_price: Option<f64>,
+}
+
+impl PortfolioPosition {
+    fn new(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            qty: 0.0,
+            avg_entry_price: 0.0,
+            realized_pnl: 0.0,
+            last_price: None,
+            lastday_price: None,
+        }
+    }
+
+    /// Latest price, falling back to the average entry price.
+    #[must_use]
+    pub fn mark_price(&self) -> f64 {
+        self.last_price.unwrap_or(self.avg_entry_price)
+    }
+
+    /// Market value at the latest price (negative when short).
+    #[must_use]
+    pub fn market_value(&self) -> f64 {
+        self.qty * self.mark_price()
+    }
+
+    /// Cost basis (negative when short).
+    #[must_use]
+    pub fn cost_basis(&self) -> f64 {
+        self.qty * self.avg_entry_price
+    }
+
+    /// Unrealized P&L at the latest price.
+    #[must_use]
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.market_value() - self.cost_basis()
+    }
+
+    /// Whether the position is flat.
+    #[must_use]
+    pub fn is_flat(&self) -> bool {
+        self.qty.abs() < QTY_EPSILON
+    }
+
+    /// Apply a signed fill, returning the P&L it realized.
+    fn apply(&mut self, qty: f64, price: f64) -> f64 {
+        let same_direction = self.is_flat() || (self.qty > 0.0) == (qty > 0.0);
+        if same_direction {
+            let total = self.qty + qty;
+            self.avg_entry_price = (self.cost_basis() + qty * price) / total;
+            self.qty = total;
+            return 0.0;
+        }
+
+        let closed = qty.abs().min(self.qty.abs());
+        let realized = closed * (price - self.avg_entry_price) * self.qty.signum();
+        self.realized_pnl += realized;
+        let remaining = self.qty + qty;
+        if remaining.abs() < QTY_EPSILON {
+            self.qty = 0.0;
+            self.avg_entry_price = 0.0;
+        } else if (remaining > 0.0) != (self.qty > 0.0) {
+            self.qty = remaining;
+            self.avg_entry_price = price;
+        } else {
+            self.qty = remaining;
+        }
+        realized
+    }
+
+    /// The position as an API [`Position`].
+    #[must_use]
+    pub fn to_position(&self) -> Position {
+        let price = self.mark_price();
+        let lastday = self.lastday_price.unwrap_or(price);
+        let cost_basis = self.cost_basis();
+        let unrealized = self.unrealized_pnl();
+        let intraday = self.qty * (price - lastday);
+        let ratio = |num: f64, den: f64| if den == 0.0 { 0.0 } else { num / den.abs() };
+        Position {
+            asset_id: Uuid::nil(),
+            symbol: self.symbol.clone(),
+            exchange: String::new(),
+            asset_class: AssetClass::UsEquity,
+            avg_entry_price: self.avg_entry_price.to_string(),
+            qty: self.qty.to_string(),
+            side: if self.qty < 0.0 {
+                PositionSide::Short
+            } else {
+                PositionSide::Long
+            },
+            market_value: self.market_value().to_string(),
+            cost_basis: cost_basis.to_string(),
+            unrealized_pl: unrealized.to_string(),
+            unrealized_plpc: ratio(unrealized, cost_basis).to_string(),
+            unrealized_intraday_pl: intraday.to_string(),
+            unrealized_intraday_plpc: ratio(intraday, self.qty * lastday).to_string(),
+            current_price: price.to_string(),
+            lastday_price: lastday.to_string(),
+            change_today: ratio(price - lastday, lastday).to_string(),
+        }
+    }
+}
+
+/// In-memory view of positions, cash and P&L.
+#[derive(Debug, Clone, Default)]
+pub struct PortfolioTracker {
+    cash: f64,
+    positions: BTreeMap<String, PortfolioPosition>,
+    realized_pnl: f64,
+    last_equity: Option<f64>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl PortfolioTracker {
+    /// Create a tracker with `cash` and no positions.
+    #[must_use]
+    pub fn new(cash: f64) -> Self {
+        Self {
+            cash,
+            ..Self::default()
+        }
+    }
+
+    /// Seed a tracker from an account and its positions, as returned by
+    /// the REST API. Unparseable numbers are treated as zero.
+    #[must_use]
+    pub fn from_snapshot(account: &Account, positions: &[Position]) -> Self {
+        let num = |value: &str| value.parse::<f64>().unwrap_or(0.0);
+        let mut tracker = Self::new(num(&account.cash));
+        tracker.last_equity = account.last_equity.parse().ok();
+        for position in positions {
+            let mut tracked = PortfolioPosition::new(&position.symbol);
+            tracked.qty = num(&position.qty);
+            if position.side == PositionSide::Short && tracked.qty > 0.0 {
+                tracked.qty = -tracked.qty;
+            }
+            tracked.avg_entry_price = num(&position.avg_entry_price);
+            tracked.last_price = position.current_price.parse().ok();
+            tracked.lastday_price = position.lastday_price.parse().ok();
+            tracker.positions.insert(position.symbol.clone(), tracked);
+        }
+        tracker
+    }
+
+    /// Apply a fill, returning the P&L it realized.
+    pub fn apply_fill(&mut self, fill: &PortfolioFill) -> f64 {
+        let qty = fill.signed_qty();
+        self.cash -= qty * fill.price;
+        let position = self
+            .positions
+            .entry(fill.symbol.clone())
+            .or_insert_with(|| PortfolioPosition::new(&fill.symbol));
+        let realized = position.apply(qty, fill.price);
+        position.last_price = Some(fill.price);
+        self.realized_pnl += realized;
+        self.updated_at = Some(fill.timestamp);
+        realized
+    }
+
+    /// Set the latest price of `symbol`.
+    pub fn update_price(&mut self, symbol: &str, price: f64) {
+        if let Some(position) = self.positions.get_mut(symbol) {
+            position.last_price = Some(price);
+        }
+    }
+
+    /// Mark `symbol` at the mid of a quote, or at whichever side is quoted.
+    pub fn update_quote(&mut self, symbol: &str, quote: &Quote) {
+        let price = match (quote.bid_price > 0.0, quote.ask_price > 0.0) {
+            (true, true) => (quote.bid_price + quote.ask_price) / 2.0,
+            (true, false) => quote.bid_price,
+            (false, true) => quote.ask_price,
+            (false, false) => return,
+        };
+        self.update_price(symbol, price);
+    }
+
+    /// Cash balance.
+    #[must_use]
+    pub fn cash(&self) -> f64 {
+        self.cash
+    }
+
+    /// A tracked position, if the symbol was ever held.
+    #[must_use]
+    pub fn position(&self, symbol: &str) -> Option<&PortfolioPosition> {
+        self.positions.get(symbol)
+    }
+
+    /// Open (non-flat) positions, by symbol.
+    pub fn open_positions(&self) -> impl Iterator<Item = &PortfolioPosition> {
+        self.positions.values().filter(|p| !p.is_flat())
+    }
+
+    /// P&L realized since the tracker was created.
+    #[must_use]
+    pub fn realized_pnl(&self) -> f64 {
+        self.realized_pnl
+    }
+
+    /// Unrealized P&L of open positions at the latest prices.
+    #[must_use]
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.open_positions()
+            .map(PortfolioPosition::unrealized_pnl)
+            .sum()
+    }
+
+    /// Cash plus the market value of open positions.
+    #[must_use]
+    pub fn equity(&self) -> f64 {
+        self.cash
+            + self
+                .open_positions()
+                .map(PortfolioPosition::market_value)
+                .sum::<f64>()
+    }
+
+    /// P&L for the day: the change in equity since the previous close
+    /// (`last_equity` of the seeding snapshot), or the P&L since the
+    /// tracker was created if it was not seeded from one.
+    #[must_use]
+    pub fn day_pnl(&self) -> f64 {
+        match self.last_equity {
+            Some(last_equity) => self.equity() - last_equity,
+            None => self.realized_pnl + self.unrealized_pnl(),
+        }
+    }
+
+    /// Time of the last fill.
+    #[must_use]
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.updated_at
+    }
+
+    /// Open positions as API [`Position`]s.
+    #[must_use]
+    pub fn positions(&self) -> Vec<Position> {
+        self.open_positions()
+            .map(PortfolioPosition::to_position)
+            .collect()
+    }
+
+    /// `account` with its cash, equity and market values replaced by the
+    /// tracked state.
+    #[must_use]
+    pub fn account(&self, account: &Account) -> Account {
+        let (mut long, mut short) = (0.0, 0.0);
+        for position in self.open_positions() {
+            let value = position.market_value();
+            if value >= 0.0 {
+                long += value;
+            } else {
+                short += value;
+            }
+        }
+        let equity = self.equity();
+        let mut snapshot = account.clone();
+        snapshot.cash = self.cash.to_string();
+        snapshot.equity = equity.to_string();
+        snapshot.portfolio_value = equity.to_string();
+        snapshot.long_market_value = long.to_string();
+        snapshot.short_market_value = short.to_string();
+        if let Some(last_equity) = self.last_equity {
+            snapshot.last_equity = last_equity.to_string();
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fills_realize_and_flip() {
+        let mut portfolio = PortfolioTracker::new(10_000.0);
+        portfolio.apply_fill(&PortfolioFill::new("AAPL", OrderSide::Buy, 10.0, 100.0));
+        portfolio.apply_fill(&PortfolioFill::new("AAPL", OrderSide::Buy, 10.0, 110.0));
+        assert_eq!(portfolio.position("AAPL").unwrap().avg_entry_price, 105.0);
+        assert_eq!(portfolio.cash(), 7_900.0);
+
+        let realized =
+            portfolio.apply_fill(&PortfolioFill::new("AAPL", OrderSide::Sell, 25.0, 120.0));
+        assert_eq!(realized, 300.0);
+        let position = portfolio.position("AAPL").unwrap();
+        assert_eq!(position.qty, -5.0);
+        assert_eq!(position.avg_entry_price, 120.0);
+
+        portfolio.update_price("AAPL", 118.0);
+        assert_eq!(portfolio.unrealized_pnl(), 10.0);
+        assert_eq!(portfolio.realized_pnl(), 300.0);
+        assert_eq!(portfolio.equity(), 10_000.0 + 300.0 + 10.0);
+
+        let positions = portfolio.positions();
+        assert_eq!(positions[0].side, PositionSide::Short);
+        assert_eq!(positions[0].qty, "-5");
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let account = crate::test_utils::fixtures::sample_account();
+        let mut position = crate::test_utils::fixtures::sample_position("MSFT", "10", "400");
+        position.current_price = "410".to_string();
+        let mut portfolio = PortfolioTracker::from_snapshot(&account, &[position]);
+        let cash = portfolio.cash();
+        assert_eq!(portfolio.unrealized_pnl(), 100.0);
+
+        portfolio.update_quote(
+            "MSFT",
+            &Quote {
+                timestamp: Utc::now(),
+                timeframe: "real-time".to_string(),
+                bid_price: 419.0,
+                bid_size: 1,
+                ask_price: 421.0,
+                ask_size: 1,
+                bid_exchange: String::new(),
+                ask_exchange: String::new(),
+            },
+        );
+        let snapshot = portfolio.account(&account);
+        assert_eq!(snapshot.long_market_value, "4200");
+        assert_eq!(snapshot.equity, (cash + 4200.0).to_string());
+        assert_eq!(snapshot.id, account.id);
+    }
+}
@@ -0,0 +1,23 @@
+//! Commonly used, stable types.
+//!
+//! The prelude is the curated subset covered by semver: credentials,
+//! errors, the core order, position and market data types, and the
+//! transport-neutral trading interface. Experimental surfaces are only
+//! available with the `unstable` feature.
+//!
+//! ```
+//! use alpaca_base::prelude::*;
+//!
+//! let side = OrderSide::Buy;
+//! assert_eq!(side, OrderSide::Buy);
+//! ```
+
+pub use crate::auth::{Credentials, CredentialsHandle};
+pub use crate::error::{AlpacaError, ApiErrorCode, Result};
+pub use crate::retry::{Backoff, CancellationToken, RetryPolicy};
+pub use crate::trading::{OrderReplacement, OrderRequest, TradingApi};
+pub use crate::types::{
+    Account, AccountStatus, Asset, AssetClass, AssetStatus, Bar, Calendar, Clock, Environment,
+    Order, OrderClass, OrderSide, OrderStatus, OrderType, Position, PositionSide, Quote,
+    TimeInForce, Timeframe, Trade,
+};
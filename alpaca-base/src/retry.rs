@@ -0,0 +1,368 @@
+//! Backoff and retry.
+//!
+//! A [`RetryPolicy`] runs a fallible async operation until it succeeds, the
+//! attempt budget is spent, or it fails with an error the policy's
+//! predicate does not consider retryable (by default
+//! [`AlpacaError::is_retryable`]). Waits between attempts follow a
+//! [`Backoff`] schedule with optional [`Jitter`], never shorter than a
+//! rate limit's `Retry-After`. [`RetryPolicy::retry_with_cancel`] stops at
+//! the next await point once its [`CancellationToken`] is cancelled.
+//!
+//! The same schedule drives HTTP request retries, WebSocket reconnection
+//! and FIX reconnection.
+
+use crate::error::{AlpacaError, Result};
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::warn;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// How the delay between attempts is randomized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Jitter {
+    /// Exactly the exponential delay.
+    #[default]
+    None,
+    /// Uniform between zero and the exponential delay.
+    Full,
+    /// Half the exponential delay plus a uniform share of the other half.
+    Equal,
+    /// Uniform between the initial delay and three times the previous
+    /// delay, capped at the maximum.
+    Decorrelated,
+}
+
+/// Capped exponential backoff schedule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    /// Delay before the first retry.
+    pub initial: Duration,
+    /// Upper bound on any delay.
+    pub max: Duration,
+    /// Growth factor per attempt.
+    pub multiplier: f64,
+    /// Jitter strategy.
+    pub jitter: Jitter,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: Jitter::Full,
+        }
+    }
+}
+
+impl Backoff {
+    /// Create a doubling schedule from `initial` up to `max`, without jitter.
+    #[must_use]
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            multiplier: 2.0,
+            jitter: Jitter::None,
+        }
+    }
+
+    /// Set the growth factor per attempt.
+    #[must_use]
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Set the jitter strategy.
+    #[must_use]
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The un-jittered delay before retry `attempt` (1-based):
+    /// `initial * multiplier^(attempt - 1)`, capped at `max`.
+    #[must_use]
+    pub fn ceiling(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(64) as i32;
+        let secs = self.initial.as_secs_f64() * self.multiplier.powi(exponent);
+        if secs.is_finite() && secs < self.max.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.max
+        }
+    }
+
+    /// The delay before retry `attempt` (1-based), given the delay used
+    /// before the previous one (only [`Jitter::Decorrelated`] reads it).
+    #[must_use]
+    pub fn delay(&self, attempt: u32, previous: Duration) -> Duration {
+        let ceiling = self.ceiling(attempt);
+        match self.jitter {
+            Jitter::None => ceiling,
+            Jitter::Full => ceiling.mul_f64(rand::random::<f64>()),
+            Jitter::Equal => ceiling / 2 + (ceiling / 2).mul_f64(rand::random::<f64>()),
+            Jitter::Decorrelated => {
+                let upper = (previous * 3).max(self.initial);
+                let span = upper - self.initial;
+                (self.initial + span.mul_f64(rand::random::<f64>())).min(self.max)
+            }
+        }
+    }
+
+    /// An endless iterator over successive delays, starting with attempt 1.
+    pub fn delays(&self) -> impl Iterator<Item = Duration> + '_ {
+        let mut previous = Duration::ZERO;
+        (1u32..).map(move |attempt| {
+            previous = self.delay(attempt, previous);
+            previous
+        })
+    }
+}
+
+/// Decides whether an error is worth retrying.
+pub type RetryPredicate = Arc<dyn Fn(&AlpacaError) -> bool + Send + Sync>;
+
+/// Bounded retries with backoff for fallible async operations.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first.
+    pub max_attempts: u32,
+    /// Delay schedule between attempts.
+    pub backoff: Backoff,
+    /// Wait at least a rate limit's `Retry-After` before retrying.
+    pub respect_retry_after: bool,
+    predicate: RetryPredicate,
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .field("respect_retry_after", &self.respect_retry_after)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Backoff::default(),
+            respect_retry_after: true,
+            predicate: Arc::new(AlpacaError::is_retryable),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy with 3 attempts, full-jitter backoff from 100ms to
+    /// 10s, retrying errors for which [`AlpacaError::is_retryable`] holds.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of attempts, including the first.
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay schedule.
+    #[must_use]
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set the jitter strategy of the delay schedule.
+    #[must_use]
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.backoff.jitter = jitter;
+        self
+    }
+
+    /// Set whether a rate limit's `Retry-After` bounds the delay from below.
+    #[must_use]
+    pub fn respect_retry_after(mut self, respect: bool) -> Self {
+        self.respect_retry_after = respect;
+        self
+    }
+
+    /// Retry only errors for which `predicate` returns true.
+    #[must_use]
+    pub fn retry_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&AlpacaError) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Arc::new(predicate);
+        self
+    }
+
+    /// Whether `error`, returned by attempt `attempt` (1-based), should be
+    /// retried.
+    #[must_use]
+    pub fn should_retry(&self, error: &AlpacaError, attempt: u32) -> bool {
+        attempt < self.max_attempts && (self.predicate)(error)
+    }
+
+    /// The delay before retrying after `error` failed attempt `attempt`.
+    #[must_use]
+    pub fn delay_for(&self, error: &AlpacaError, attempt: u32, previous: Duration) -> Duration {
+        let delay = self.backoff.delay(attempt, previous);
+        match error.retry_after() {
+            Some(secs) if self.respect_retry_after => delay.max(Duration::from_secs(secs)),
+            _ => delay,
+        }
+    }
+
+    /// Run `op` until it succeeds or the policy gives up, returning the last
+    /// error in that case. `op` receives the 1-based attempt number.
+    pub async fn retry<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run(None, op).await
+    }
+
+    /// Like [`Self::retry`], but stop with [`AlpacaError::Cancelled`] as
+    /// soon as `token` is cancelled, whether an attempt is in flight or the
+    /// policy is waiting to retry.
+    pub async fn retry_with_cancel<T, F, Fut>(&self, token: &CancellationToken, op: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run(Some(token), op).await
+    }
+
+    async fn run<T, F, Fut>(&self, token: Option<&CancellationToken>, mut op: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut previous = Duration::ZERO;
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            let result = match token {
+                Some(token) => tokio::select! {
+                    biased;
+                    _ = token.cancelled() => return Err(cancelled(attempt)),
+                    result = op(attempt) => result,
+                },
+                None => op(attempt).await,
+            };
+            let error = match result {
+                Ok(value) => return Ok(value),
+                Err(e) if !self.should_retry(&e, attempt) => return Err(e),
+                Err(e) => e,
+            };
+
+            let delay = self.delay_for(&error, attempt, previous);
+            previous = delay;
+            warn!(
+                "Attempt {}/{} failed ({}); retrying in {:?}",
+                attempt, self.max_attempts, error, delay
+            );
+            match token {
+                Some(token) => tokio::select! {
+                    biased;
+                    _ = token.cancelled() => return Err(cancelled(attempt)),
+                    _ = sleep(delay) => {}
+                },
+                None => sleep(delay).await,
+            }
+        }
+    }
+}
+
+fn cancelled(attempt: u32) -> AlpacaError {
+    AlpacaError::Cancelled(format!("cancelled during attempt {attempt}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_backoff_schedule_and_jitter_bounds() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1));
+        let delays: Vec<_> = backoff.delays().take(5).collect();
+        assert_eq!(
+            delays,
+            [100, 200, 400, 800, 1000].map(Duration::from_millis)
+        );
+        assert_eq!(backoff.ceiling(u32::MAX), Duration::from_secs(1));
+
+        for attempt in 1..8 {
+            let ceiling = backoff.ceiling(attempt);
+            let full = backoff.jitter(Jitter::Full).delay(attempt, Duration::ZERO);
+            assert!(full <= ceiling);
+            let equal = backoff.jitter(Jitter::Equal).delay(attempt, Duration::ZERO);
+            assert!(equal >= ceiling / 2 && equal <= ceiling);
+            let decorrelated = backoff
+                .jitter(Jitter::Decorrelated)
+                .delay(attempt, Duration::from_millis(500));
+            assert!(decorrelated >= backoff.initial && decorrelated <= backoff.max);
+        }
+
+        let policy = RetryPolicy::new().jitter(Jitter::None);
+        let limited = AlpacaError::rate_limit(2);
+        assert_eq!(
+            policy.delay_for(&limited, 1, Duration::ZERO),
+            Duration::from_secs(2)
+        );
+        assert!(policy.should_retry(&limited, 2));
+        assert!(!policy.should_retry(&limited, 3));
+        assert!(!policy.should_retry(&AlpacaError::Auth("bad key".into()), 1));
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success_and_cancel() {
+        let policy = RetryPolicy::new()
+            .max_attempts(5)
+            .backoff(Backoff::new(Duration::ZERO, Duration::ZERO));
+        let calls = AtomicU32::new(0);
+        let value = policy
+            .retry(|attempt| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt < 3 {
+                        Err(AlpacaError::Network("reset".into()))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let err = policy
+            .retry(|_| async { Err::<(), _>(AlpacaError::Validation("qty".into())) })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AlpacaError::Validation(_)));
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let err = policy
+            .retry_with_cancel(&token, |_| async { Ok(()) })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AlpacaError::Cancelled(_)));
+    }
+}
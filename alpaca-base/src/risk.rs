@@ -0,0 +1,426 @@
+//! Pre-trade risk checks in front of order submission.
+//!
+//! [`RiskEngine`] wraps any [`TradingApi`] and checks every order against
+//! its [`RiskLimits`] before passing it on:
+//!
+//! - the kill switch, which blocks all new orders once tripped;
+//! - the maximum absolute position per symbol after the order fills;
+//! - the maximum notional of a single order;
+//! - the daily loss limit, from the shared [`PortfolioTracker`].
+//!
+//! Orders that only reduce a position pass the position and daily loss
+//! checks, so a strategy can always flatten. Rejected orders never reach
+//! Alpaca and fail with [`AlpacaError::RiskRejected`] carrying the
+//! [`RiskViolation`]. Cancels and state queries are passed through.
+
+use crate::drawdown::KillSwitch;
+use crate::error::{AlpacaError, Result};
+use crate::portfolio::PortfolioTracker;
+use crate::trading::{OrderReplacement, OrderRequest, TradingApi};
+use crate::types::{Account, Order, OrderSide, Position};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Why an order was rejected by a [`RiskEngine`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskViolation {
+    /// The kill switch is tripped.
+    KillSwitch,
+    /// The order would take the position past its limit.
+    MaxPosition {
+        /// Symbol of the order.
+        symbol: String,
+        /// Maximum absolute position.
+        limit: f64,
+        /// Signed position if the order filled.
+        resulting: f64,
+    },
+    /// The order's notional is above the limit.
+    MaxNotional {
+        /// Symbol of the order.
+        symbol: String,
+        /// Maximum order notional.
+        limit: f64,
+        /// Order quantity times its price.
+        notional: f64,
+    },
+    /// The day's loss has reached the limit.
+    DailyLoss {
+        /// Maximum daily loss, as a positive amount.
+        limit: f64,
+        /// The day's loss so far, as a positive amount.
+        loss: f64,
+    },
+    /// A notional limit is set but no price is known for the order.
+    UnknownPrice {
+        /// Symbol of the order.
+        symbol: String,
+    },
+    /// The order would be a day trade with none left before a pattern day
+    /// trader flag.
+    PatternDayTrade {
+        /// Symbol of the order.
+        symbol: String,
+        /// Day trades in the rolling window.
+        day_trades: u32,
+    },
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KillSwitch => write!(f, "kill switch is tripped"),
+            Self::MaxPosition {
+                symbol,
+                limit,
+                resulting,
+            } => write!(
+                f,
+                "{symbol} position would be {resulting}, above the limit of {limit}"
+            ),
+            Self::MaxNotional {
+                symbol,
+                limit,
+                notional,
+            } => write!(
+                f,
+                "{symbol} order notional {notional:.2} is above the limit of {limit:.2}"
+            ),
+            Self::DailyLoss { limit, loss } => {
+                write!(
+                    f,
+                    "daily loss {loss:.2} has reached the limit of {limit:.2}"
+                )
+            }
+            Self::UnknownPrice { symbol } => {
+                write!(f, "no price known for {symbol} to check the order notional")
+            }
+            Self::PatternDayTrade { symbol, day_trades } => write!(
+                f,
+                "{symbol} order would be a day trade after {day_trades} in five business days"
+            ),
+        }
+    }
+}
+
+/// Limits enforced by a [`RiskEngine`]. Unset limits are not checked.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RiskLimits {
+    /// Maximum absolute position per symbol.
+    pub max_position: HashMap<String, f64>,
+    /// Maximum absolute position for symbols without their own limit.
+    pub default_max_position: Option<f64>,
+    /// Maximum notional of a single order.
+    pub max_order_notional: Option<f64>,
+    /// Maximum loss for the day, as a positive amount.
+    pub max_daily_loss: Option<f64>,
+}
+
+impl RiskLimits {
+    /// Create limits that check nothing but the kill switch.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the absolute position in `symbol`.
+    #[must_use]
+    pub fn max_position(mut self, symbol: &str, qty: f64) -> Self {
+        self.max_position.insert(symbol.to_string(), qty);
+        self
+    }
+
+    /// Limit the absolute position in symbols without their own limit.
+    #[must_use]
+    pub fn default_max_position(mut self, qty: f64) -> Self {
+        self.default_max_position = Some(qty);
+        self
+    }
+
+    /// Limit the notional of a single order.
+    #[must_use]
+    pub fn max_order_notional(mut self, notional: f64) -> Self {
+        self.max_order_notional = Some(notional);
+        self
+    }
+
+    /// Stop opening risk once the day's loss reaches `loss`.
+    #[must_use]
+    pub fn max_daily_loss(mut self, loss: f64) -> Self {
+        self.max_daily_loss = Some(loss);
+        self
+    }
+
+    /// The position limit of `symbol`, if any.
+    #[must_use]
+    pub fn position_limit(&self, symbol: &str) -> Option<f64> {
+        self.max_position
+            .get(symbol)
+            .copied()
+            .or(self.default_max_position)
+    }
+}
+
+/// A [`TradingApi`] that checks orders against [`RiskLimits`] before
+/// submitting them.
+///
+/// The [`PortfolioTracker`] is shared with whatever applies fills, so
+/// position and daily loss checks see the live state. Share the
+/// [`KillSwitch`] with a [`DrawdownMonitor`](crate::DrawdownMonitor) to halt
+/// trading on a drawdown.
+#[derive(Debug)]
+pub struct RiskEngine<T> {
+    inner: T,
+    limits: RiskLimits,
+    portfolio: Arc<Mutex<PortfolioTracker>>,
+    kill_switch: KillSwitch,
+    prices: Mutex<HashMap<String, f64>>,
+}
+
+impl<T: TradingApi> RiskEngine<T> {
+    /// Check orders to `inner` against `limits`, reading positions and P&L
+    /// from `portfolio`.
+    #[must_use]
+    pub fn new(inner: T, limits: RiskLimits, portfolio: Arc<Mutex<PortfolioTracker>>) -> Self {
+        Self {
+            inner,
+            limits,
+            portfolio,
+            kill_switch: KillSwitch::new(),
+            prices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use a shared kill switch.
+    #[must_use]
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitch) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+
+    /// The wrapped API.
+    #[must_use]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// The enforced limits.
+    #[must_use]
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// The kill switch; trip it to reject every new order.
+    #[must_use]
+    pub fn kill_switch(&self) -> &KillSwitch {
+        &self.kill_switch
+    }
+
+    /// Record the latest price of `symbol`, used for the notional of
+    /// market orders.
+    pub fn update_price(&self, symbol: &str, price: f64) {
+        self.prices
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(symbol.to_string(), price);
+    }
+
+    /// Check `order` against the limits without submitting it.
+    ///
+    /// # Errors
+    /// Returns the first [`RiskViolation`] found.
+    pub fn check(&self, order: &OrderRequest) -> std::result::Result<(), RiskViolation> {
+        if self.kill_switch.is_tripped() {
+            return Err(RiskViolation::KillSwitch);
+        }
+
+        let portfolio = self.portfolio.lock().unwrap_or_else(|e| e.into_inner());
+        let position = portfolio.position(&order.symbol);
+        let current = position.map_or(0.0, |p| p.qty);
+        let signed = match order.side {
+            OrderSide::Buy => order.qty,
+            OrderSide::Sell => -order.qty,
+        };
+        let resulting = current + signed;
+        let increases = resulting.abs() > current.abs();
+
+        if increases && let Some(limit) = self.limits.max_daily_loss {
+            let loss = -portfolio.day_pnl();
+            if loss >= limit {
+                return Err(RiskViolation::DailyLoss { limit, loss });
+            }
+        }
+        if increases
+            && let Some(limit) = self.limits.position_limit(&order.symbol)
+            && resulting.abs() > limit
+        {
+            return Err(RiskViolation::MaxPosition {
+                symbol: order.symbol.clone(),
+                limit,
+                resulting,
+            });
+        }
+        if let Some(limit) = self.limits.max_order_notional {
+            let price = order
+                .limit_price
+                .or(order.stop_price)
+                .or_else(|| {
+                    self.prices
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .get(&order.symbol)
+                        .copied()
+                })
+                .or_else(|| position.and_then(|p| p.last_price));
+            let Some(price) = price else {
+                return Err(RiskViolation::UnknownPrice {
+                    symbol: order.symbol.clone(),
+                });
+            };
+            let notional = order.qty * price;
+            if notional > limit {
+                return Err(RiskViolation::MaxNotional {
+                    symbol: order.symbol.clone(),
+                    limit,
+                    notional,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn enforce(&self, order: &OrderRequest) -> Result<()> {
+        self.check(order).map_err(|violation| {
+            warn!(symbol = %order.symbol, %violation, "order rejected by risk check");
+            AlpacaError::RiskRejected(violation)
+        })
+    }
+}
+
+impl<T: TradingApi> TradingApi for RiskEngine<T> {
+    fn submit_order(&self, order: &OrderRequest) -> impl Future<Output = Result<Order>> + Send {
+        let checked = self.enforce(order);
+        let order = order.clone();
+        async move {
+            checked?;
+            self.inner.submit_order(&order).await
+        }
+    }
+
+    fn cancel_order(&self, order: &Order) -> impl Future<Output = Result<()>> + Send {
+        self.inner.cancel_order(order)
+    }
+
+    /// Replacements are checked as a new order with the replaced quantity
+    /// and prices.
+    fn replace_order(
+        &self,
+        order: &Order,
+        replacement: &OrderReplacement,
+    ) -> impl Future<Output = Result<Order>> + Send {
+        let num = |value: &Option<String>| value.as_deref().and_then(|v| v.parse::<f64>().ok());
+        let resulting = OrderRequest {
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            qty: replacement.qty.or_else(|| num(&order.qty)).unwrap_or(0.0),
+            order_type: order.order_type.clone(),
+            time_in_force: order.time_in_force.clone(),
+            limit_price: replacement.limit_price.or_else(|| num(&order.limit_price)),
+            stop_price: replacement.stop_price.or_else(|| num(&order.stop_price)),
+            client_order_id: None,
+        };
+        let checked = self.enforce(&resulting);
+        let (order, replacement) = (order.clone(), replacement.clone());
+        async move {
+            checked?;
+            self.inner.replace_order(&order, &replacement).await
+        }
+    }
+
+    fn get_positions(&self) -> impl Future<Output = Result<Vec<Position>>> + Send {
+        self.inner.get_positions()
+    }
+
+    fn get_account(&self) -> impl Future<Output = Result<Account>> + Send {
+        self.inner.get_account()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::portfolio::PortfolioFill;
+    use crate::test_utils::fixtures::MockTradingApi;
+
+    fn violation(result: Result<Order>) -> RiskViolation {
+        match result {
+            Err(AlpacaError::RiskRejected(violation)) => violation,
+            other => panic!("expected a risk rejection, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_risk_engine_enforces_limits() {
+        let portfolio = Arc::new(Mutex::new(PortfolioTracker::new(100_000.0)));
+        portfolio.lock().unwrap().apply_fill(&PortfolioFill::new(
+            "AAPL",
+            OrderSide::Buy,
+            80.0,
+            200.0,
+        ));
+        let limits = RiskLimits::new()
+            .max_position("AAPL", 100.0)
+            .max_order_notional(10_000.0)
+            .max_daily_loss(1_000.0);
+        let engine = RiskEngine::new(MockTradingApi::new(), limits, Arc::clone(&portfolio));
+
+        let over = engine
+            .submit_order(&OrderRequest::market("AAPL", OrderSide::Buy, 30.0))
+            .await;
+        assert_eq!(
+            violation(over),
+            RiskViolation::MaxPosition {
+                symbol: "AAPL".to_string(),
+                limit: 100.0,
+                resulting: 110.0
+            }
+        );
+        let big = engine
+            .submit_order(&OrderRequest::limit("MSFT", OrderSide::Buy, 30.0, 400.0))
+            .await;
+        assert!(matches!(violation(big), RiskViolation::MaxNotional { .. }));
+        let unpriced = engine
+            .submit_order(&OrderRequest::market("MSFT", OrderSide::Buy, 1.0))
+            .await;
+        assert!(matches!(
+            violation(unpriced),
+            RiskViolation::UnknownPrice { .. }
+        ));
+        engine.update_price("MSFT", 400.0);
+        engine
+            .submit_order(&OrderRequest::market("MSFT", OrderSide::Buy, 1.0))
+            .await
+            .unwrap();
+
+        // A 15% drop is a 2,400 loss: only risk-reducing orders pass.
+        portfolio.lock().unwrap().update_price("AAPL", 170.0);
+        let adding = engine
+            .submit_order(&OrderRequest::market("AAPL", OrderSide::Buy, 1.0))
+            .await;
+        assert!(matches!(violation(adding), RiskViolation::DailyLoss { .. }));
+        engine
+            .submit_order(&OrderRequest::market("AAPL", OrderSide::Sell, 40.0))
+            .await
+            .unwrap();
+
+        engine.kill_switch().trip();
+        let halted = engine
+            .submit_order(&OrderRequest::market("AAPL", OrderSide::Sell, 40.0))
+            .await;
+        assert_eq!(violation(halted), RiskViolation::KillSwitch);
+        assert_eq!(engine.inner().submit_count(), 2);
+    }
+}
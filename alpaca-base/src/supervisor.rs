@@ -0,0 +1,463 @@
+//! Supervision of long-running strategy tasks.
+//!
+//! A [`Supervisor`] runs each registered task on the tokio runtime and
+//! watches it. A task that returns an error, panics, or stops calling
+//! [`Heartbeat::beat`] within the configured timeout is considered crashed
+//! and is restarted from its factory after a [`Backoff`] delay. After
+//! `max_failures` consecutive crashes the supervisor gives up on the task
+//! and trips the shared [`KillSwitch`], so the rest of the bot stops taking
+//! new risk. A run that stays up for `stable_after` clears the failure
+//! count. A task that returns `Ok(())` is finished and is not restarted.
+//!
+//! The state of every task is available at any time through
+//! [`Supervisor::status`] and [`Supervisor::statuses`].
+
+use crate::drawdown::KillSwitch;
+use crate::error::Result;
+use crate::retry::{Backoff, CancellationToken, Jitter};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, sleep, timeout};
+use tracing::{error, warn};
+
+/// Liveness of a supervised task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    /// Running and, if heartbeats are required, beating.
+    Running,
+    /// Crashed; waiting to be restarted.
+    Restarting,
+    /// Gave up after too many consecutive crashes.
+    Failed,
+    /// Returned successfully.
+    Finished,
+    /// Stopped by [`Supervisor::shutdown`].
+    Stopped,
+}
+
+/// Snapshot of a supervised task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskStatus {
+    /// Task name.
+    pub name: String,
+    /// State.
+    pub state: TaskState,
+    /// Number of restarts so far.
+    pub restarts: u32,
+    /// Crashes since the last stable run.
+    pub consecutive_failures: u32,
+    /// Last heartbeat.
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    /// Last crash reason.
+    pub last_error: Option<String>,
+}
+
+impl TaskStatus {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: TaskState::Running,
+            restarts: 0,
+            consecutive_failures: 0,
+            last_heartbeat: None,
+            last_error: None,
+        }
+    }
+}
+
+/// Handle a supervised task uses to signal that it is alive.
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    sender: Arc<watch::Sender<()>>,
+}
+
+impl Heartbeat {
+    /// Signal that the task is alive.
+    pub fn beat(&self) {
+        self.sender.send_replace(());
+    }
+}
+
+/// Settings for a [`Supervisor`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SupervisorConfig {
+    /// Longest time a task may go without a heartbeat; `None` disables
+    /// stall detection.
+    pub heartbeat_timeout: Option<Duration>,
+    /// Delay schedule between restarts.
+    pub restart_backoff: Backoff,
+    /// Consecutive crashes after which the task is abandoned and the kill
+    /// switch tripped.
+    pub max_failures: u32,
+    /// Uptime after which a run counts as stable and clears the failure
+    /// count.
+    pub stable_after: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_timeout: Some(Duration::from_secs(30)),
+            restart_backoff: Backoff::new(Duration::from_secs(1), Duration::from_secs(60))
+                .jitter(Jitter::Equal),
+            max_failures: 5,
+            stable_after: Duration::from_secs(300),
+        }
+    }
+}
+
+impl SupervisorConfig {
+    /// Create a config with a 30 second heartbeat timeout, restarts backing
+    /// off from 1 second to 1 minute, escalation after 5 consecutive
+    /// crashes, and runs counting as stable after 5 minutes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the heartbeat timeout.
+    #[must_use]
+    pub fn heartbeat_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.heartbeat_timeout = timeout;
+        self
+    }
+
+    /// Set the restart delay schedule.
+    #[must_use]
+    pub fn restart_backoff(mut self, backoff: Backoff) -> Self {
+        self.restart_backoff = backoff;
+        self
+    }
+
+    /// Set the consecutive crashes that trigger escalation.
+    #[must_use]
+    pub fn max_failures(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures.max(1);
+        self
+    }
+
+    /// Set the uptime after which a run counts as stable.
+    #[must_use]
+    pub fn stable_after(mut self, stable_after: Duration) -> Self {
+        self.stable_after = stable_after;
+        self
+    }
+}
+
+type Statuses = Arc<Mutex<BTreeMap<String, TaskStatus>>>;
+
+/// Runs, watches and restarts strategy tasks.
+#[derive(Debug)]
+pub struct Supervisor {
+    config: SupervisorConfig,
+    kill_switch: KillSwitch,
+    statuses: Statuses,
+    cancel: CancellationToken,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Create a supervisor that trips `kill_switch` when a task keeps
+    /// crashing.
+    #[must_use]
+    pub fn new(config: SupervisorConfig, kill_switch: KillSwitch) -> Self {
+        Self {
+            config,
+            kill_switch,
+            statuses: Arc::default(),
+            cancel: CancellationToken::new(),
+            handles: Vec::new(),
+        }
+    }
+
+    /// The kill switch tripped on escalation.
+    pub fn kill_switch(&self) -> &KillSwitch {
+        &self.kill_switch
+    }
+
+    /// Start supervising a task named `name`.
+    ///
+    /// `factory` is called for the first run and again for every restart,
+    /// with a [`Heartbeat`] the run must beat at least once per heartbeat
+    /// timeout. Must be called within a tokio runtime.
+    pub fn spawn<F, Fut>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(Heartbeat) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.update(name, |_| {});
+        let task = SupervisedTask {
+            name: name.to_string(),
+            config: self.config,
+            kill_switch: self.kill_switch.clone(),
+            statuses: Arc::clone(&self.statuses),
+            cancel: self.cancel.clone(),
+        };
+        self.handles.push(tokio::spawn(task.run(factory)));
+    }
+
+    /// Snapshot of the task named `name`.
+    #[must_use]
+    pub fn status(&self, name: &str) -> Option<TaskStatus> {
+        lock(&self.statuses).get(name).cloned()
+    }
+
+    /// Snapshots of all tasks, by name.
+    #[must_use]
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        lock(&self.statuses).values().cloned().collect()
+    }
+
+    /// Whether no task has been abandoned.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        lock(&self.statuses)
+            .values()
+            .all(|s| s.state != TaskState::Failed)
+    }
+
+    /// Stop every task and wait for the supervisor loops to exit.
+    pub async fn shutdown(&mut self) {
+        self.cancel.cancel();
+        for handle in self.handles.drain(..) {
+            let _ = handle.await;
+        }
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut TaskStatus)) {
+        update(&self.statuses, name, f);
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+fn lock(statuses: &Statuses) -> std::sync::MutexGuard<'_, BTreeMap<String, TaskStatus>> {
+    statuses.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn update(statuses: &Statuses, name: &str, f: impl FnOnce(&mut TaskStatus)) {
+    let mut statuses = lock(statuses);
+    f(statuses
+        .entry(name.to_string())
+        .or_insert_with(|| TaskStatus::new(name)));
+}
+
+/// How one run of a task ended.
+enum RunEnd {
+    Finished,
+    Cancelled,
+    Crashed(String),
+}
+
+struct SupervisedTask {
+    name: String,
+    config: SupervisorConfig,
+    kill_switch: KillSwitch,
+    statuses: Statuses,
+    cancel: CancellationToken,
+}
+
+impl SupervisedTask {
+    async fn run<F, Fut>(self, factory: F)
+    where
+        F: Fn(Heartbeat) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut previous_delay = Duration::ZERO;
+        loop {
+            let started = Instant::now();
+            let (sender, receiver) = watch::channel(());
+            let heartbeat = Heartbeat {
+                sender: Arc::new(sender),
+            };
+            let handle = tokio::spawn(factory(heartbeat));
+            self.update(|s| s.state = TaskState::Running);
+
+            let reason = match self.watch(handle, receiver).await {
+                RunEnd::Finished => {
+                    self.update(|s| s.state = TaskState::Finished);
+                    return;
+                }
+                RunEnd::Cancelled => {
+                    self.update(|s| s.state = TaskState::Stopped);
+                    return;
+                }
+                RunEnd::Crashed(reason) => reason,
+            };
+
+            let stable = started.elapsed() >= self.config.stable_after;
+            let mut failures = 0;
+            self.update(|s| {
+                if stable {
+                    s.consecutive_failures = 0;
+                }
+                s.consecutive_failures += 1;
+                s.last_error = Some(reason.clone());
+                failures = s.consecutive_failures;
+            });
+            if failures >= self.config.max_failures {
+                error!(
+                    "Task {} crashed {} times in a row ({}); tripping kill switch",
+                    self.name, failures, reason
+                );
+                self.update(|s| s.state = TaskState::Failed);
+                self.kill_switch.trip();
+                return;
+            }
+
+            let delay = self.config.restart_backoff.delay(failures, previous_delay);
+            previous_delay = delay;
+            warn!(
+                "Task {} crashed ({}); restarting in {:?}",
+                self.name, reason, delay
+            );
+            self.update(|s| s.state = TaskState::Restarting);
+            tokio::select! {
+                _ = self.cancel.cancelled() => {
+                    self.update(|s| s.state = TaskState::Stopped);
+                    return;
+                }
+                _ = sleep(delay) => {}
+            }
+            self.update(|s| s.restarts += 1);
+        }
+    }
+
+    /// Wait for the run to end, crash or stall.
+    async fn watch(
+        &self,
+        mut handle: JoinHandle<Result<()>>,
+        receiver: watch::Receiver<()>,
+    ) -> RunEnd {
+        let end = tokio::select! {
+            joined = &mut handle => match joined {
+                Ok(Ok(())) => RunEnd::Finished,
+                Ok(Err(e)) => RunEnd::Crashed(e.to_string()),
+                Err(e) if e.is_panic() => RunEnd::Crashed("task panicked".to_string()),
+                Err(e) => RunEnd::Crashed(e.to_string()),
+            },
+            reason = self.stalled(receiver) => RunEnd::Crashed(reason),
+            _ = self.cancel.cancelled() => RunEnd::Cancelled,
+        };
+        handle.abort();
+        end
+    }
+
+    /// Resolve once the run misses its heartbeat deadline.
+    async fn stalled(&self, mut receiver: watch::Receiver<()>) -> String {
+        let Some(limit) = self.config.heartbeat_timeout else {
+            return std::future::pending().await;
+        };
+        loop {
+            match timeout(limit, receiver.changed()).await {
+                Ok(Ok(())) => self.update(|s| s.last_heartbeat = Some(Utc::now())),
+                Ok(Err(_)) => return std::future::pending().await,
+                Err(_) => return format!("no heartbeat for {limit:?}"),
+            }
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut TaskStatus)) {
+        update(&self.statuses, &self.name, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AlpacaError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn config() -> SupervisorConfig {
+        SupervisorConfig::new()
+            .restart_backoff(Backoff::new(Duration::ZERO, Duration::ZERO))
+            .heartbeat_timeout(Some(Duration::from_millis(50)))
+            .max_failures(3)
+    }
+
+    async fn wait_for(supervisor: &Supervisor, name: &str, state: TaskState) -> TaskStatus {
+        for _ in 0..200 {
+            if let Some(status) = supervisor.status(name).filter(|s| s.state == state) {
+                return status;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+        panic!(
+            "{name} never reached {state:?}: {:?}",
+            supervisor.status(name)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restarts_then_escalates_to_kill_switch() {
+        let mut supervisor = Supervisor::new(config(), KillSwitch::new());
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&runs);
+        supervisor.spawn("flaky", move |_| {
+            let run = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if run == 1 {
+                    panic!("boom");
+                }
+                Err(AlpacaError::Network("feed lost".into()))
+            }
+        });
+
+        let status = wait_for(&supervisor, "flaky", TaskState::Failed).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(status.restarts, 2);
+        assert_eq!(status.consecutive_failures, 3);
+        assert!(supervisor.kill_switch().is_tripped());
+        assert!(!supervisor.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_task_is_restarted_and_shutdown_stops_it() {
+        let mut supervisor = Supervisor::new(config().max_failures(10), KillSwitch::new());
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&runs);
+        supervisor.spawn("strategy", move |heartbeat| {
+            let run = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                loop {
+                    // The first run hangs without beating.
+                    if run > 0 {
+                        heartbeat.beat();
+                    }
+                    sleep(Duration::from_millis(10)).await;
+                }
+            }
+        });
+
+        for _ in 0..200 {
+            if supervisor
+                .status("strategy")
+                .is_some_and(|s| s.restarts == 1 && s.last_heartbeat.is_some())
+            {
+                break;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+        let status = supervisor.status("strategy").unwrap();
+        assert_eq!(status.state, TaskState::Running);
+        assert!(status.last_error.unwrap().contains("no heartbeat"));
+
+        supervisor.shutdown().await;
+        assert_eq!(
+            supervisor.status("strategy").unwrap().state,
+            TaskState::Stopped
+        );
+        assert!(!supervisor.kill_switch().is_tripped());
+    }
+}
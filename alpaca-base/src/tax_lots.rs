@@ -0,0 +1,742 @@
+//! Tax lots, wash-sale detection and tax-aware sell planning.
+//!
+//! [`TaxLots`] ingests fills as they happen, from account activities
+//! ([`TaxLots::record_activity`]), a trade-update stream
+//! ([`TaxLots::record_portfolio_fill`]) or option deliveries
+//! ([`TaxLots::record_option_event`]), and keeps numbered open lots per
+//! symbol. Sells relieve lots under the symbol's [`LotMethod`] (first-in
+//! first-out by default, matching the broker), or under specific
+//! identification when lots were designated for the sell order with
+//! [`TaxLots::designate_lots`]. Every relieved lot becomes a
+//! [`RealizedSale`]. On top of that it:
+//!
+//! - flags wash sales: a loss sale with a purchase of the same symbol
+//!   within [`WASH_SALE_WINDOW_DAYS`] days before or after it
+//!   ([`TaxLots::wash_sales`]);
+//! - warns before a buy that would turn a recent loss into a wash sale
+//!   ([`TaxLots::check_buy`]);
+//! - plans which lots to sell for a given quantity under a [`LotMethod`]
+//!   ([`TaxLots::tax_aware_sell`]).
+//!
+//! These are planning aids, not tax advice: substantially identical
+//! securities, basis adjustments of replacement shares and account-level
+//! elections are out of scope.
+
+use crate::error::{AlpacaError, Result};
+use crate::option_events::OptionLifecycleEvent;
+use crate::portfolio::PortfolioFill;
+use crate::types::{OrderSide, TradeActivity};
+use crate::utils::{QTY_EPSILON, parse_decimal};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Days on either side of a loss sale in which a purchase makes it a wash
+/// sale.
+pub const WASH_SALE_WINDOW_DAYS: i64 = 30;
+
+/// Holding period after which a gain is long-term.
+const LONG_TERM_DAYS: i64 = 365;
+
+/// How lots are chosen when selling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LotMethod {
+    /// Oldest lots first.
+    #[default]
+    Fifo,
+    /// Newest lots first.
+    Lifo,
+    /// Most expensive lots first (smallest gain).
+    HighestCost,
+    /// Cheapest lots first.
+    LowestCost,
+    /// Lots ordered to minimise tax: short-term losses, then long-term
+    /// losses (largest first), then long-term gains, then short-term gains
+    /// (smallest first).
+    MinimizeTax,
+}
+
+/// A purchase lot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaxLot {
+    /// Lot number, unique within the [`TaxLots`].
+    pub id: u64,
+    /// Order that bought the lot, if known.
+    pub order_id: Option<Uuid>,
+    /// Symbol.
+    pub symbol: String,
+    /// Remaining quantity.
+    pub qty: f64,
+    /// Cost per share.
+    pub cost: f64,
+    /// Purchase time.
+    pub acquired_at: DateTime<Utc>,
+}
+
+impl TaxLot {
+    /// Whether a sale at `at` would be long-term.
+    #[must_use]
+    pub fn is_long_term(&self, at: DateTime<Utc>) -> bool {
+        at - self.acquired_at > Duration::days(LONG_TERM_DAYS)
+    }
+}
+
+/// A lot (or part of one) that was sold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealizedSale {
+    /// Number of the lot that was sold.
+    pub lot_id: u64,
+    /// Order that sold it, if known.
+    pub order_id: Option<Uuid>,
+    /// Symbol.
+    pub symbol: String,
+    /// Quantity sold from the lot.
+    pub qty: f64,
+    /// Cost per share.
+    pub cost: f64,
+    /// Sale price per share.
+    pub price: f64,
+    /// Purchase time of the lot.
+    pub acquired_at: DateTime<Utc>,
+    /// Sale time.
+    pub sold_at: DateTime<Utc>,
+}
+
+impl RealizedSale {
+    /// Realized gain (negative for a loss).
+    #[must_use]
+    pub fn gain(&self) -> f64 {
+        (self.price - self.cost) * self.qty
+    }
+
+    /// Whether the gain is long-term.
+    #[must_use]
+    pub fn is_long_term(&self) -> bool {
+        self.sold_at - self.acquired_at > Duration::days(LONG_TERM_DAYS)
+    }
+}
+
+/// A loss sale matched with a replacement purchase inside the window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WashSale {
+    /// Number of the lot sold at a loss.
+    pub lot_id: u64,
+    /// Number of the replacement lot.
+    pub replacement_lot_id: u64,
+    /// Symbol.
+    pub symbol: String,
+    /// Time of the loss sale.
+    pub sold_at: DateTime<Utc>,
+    /// Time of the replacement purchase.
+    pub replacement_at: DateTime<Utc>,
+    /// Quantity whose loss is disallowed.
+    pub qty: f64,
+    /// Disallowed loss (positive amount).
+    pub disallowed_loss: f64,
+}
+
+/// A transaction that would form a wash sale with a planned trade.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WashSaleRisk {
+    /// Symbol.
+    pub symbol: String,
+    /// Time of the conflicting purchase or loss sale.
+    pub conflicting_at: DateTime<Utc>,
+    /// Quantity of the conflicting transaction.
+    pub qty: f64,
+    /// Loss at stake (positive amount).
+    pub loss: f64,
+}
+
+/// One lot in a [`SellPlan`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LotSale {
+    /// The lot, with its quantity before the sale.
+    pub lot: TaxLot,
+    /// Quantity to sell from the lot.
+    pub qty: f64,
+    /// Estimated gain (negative for a loss).
+    pub gain: f64,
+    /// Whether the gain is long-term.
+    pub long_term: bool,
+}
+
+/// Lots selected by [`TaxLots::tax_aware_sell`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SellPlan {
+    /// Symbol.
+    pub symbol: String,
+    /// Quantity to sell.
+    pub qty: f64,
+    /// Assumed sale price.
+    pub price: f64,
+    /// Lots to sell, in selection order.
+    pub lots: Vec<LotSale>,
+    /// Estimated short-term gain.
+    pub short_term_gain: f64,
+    /// Estimated long-term gain.
+    pub long_term_gain: f64,
+    /// Recent purchases that would make the planned loss a wash sale.
+    pub wash_sale_risks: Vec<WashSaleRisk>,
+}
+
+impl SellPlan {
+    /// Total estimated gain.
+    #[must_use]
+    pub fn total_gain(&self) -> f64 {
+        self.short_term_gain + self.long_term_gain
+    }
+}
+
+/// Open lots and realized sales, kept current from fills.
+///
+/// Sells beyond the open quantity (short sales) are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct TaxLots {
+    method: LotMethod,
+    methods: HashMap<String, LotMethod>,
+    next_id: u64,
+    open: HashMap<String, Vec<TaxLot>>,
+    buys: Vec<TaxLot>,
+    sales: Vec<RealizedSale>,
+    designations: HashMap<Uuid, Vec<(u64, f64)>>,
+    seen: HashSet<String>,
+}
+
+impl TaxLots {
+    /// Create an empty ledger relieving lots first-in first-out.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty ledger relieving lots under `method`.
+    #[must_use]
+    pub fn with_method(method: LotMethod) -> Self {
+        Self {
+            method,
+            ..Self::default()
+        }
+    }
+
+    /// Replay trade activities in time order.
+    pub fn from_activities(activities: &[TradeActivity]) -> Result<Self> {
+        let mut sorted: Vec<&TradeActivity> = activities.iter().collect();
+        sorted.sort_by_key(|a| a.transaction_time);
+        let mut lots = Self::new();
+        for activity in sorted {
+            lots.record_activity(activity)?;
+        }
+        Ok(lots)
+    }
+
+    /// Relieve lots of `symbol` under `method` instead of the default.
+    pub fn set_method(&mut self, symbol: &str, method: LotMethod) {
+        self.methods.insert(symbol.to_string(), method);
+    }
+
+    /// The method lots of `symbol` are relieved under.
+    #[must_use]
+    pub fn method(&self, symbol: &str) -> LotMethod {
+        self.methods.get(symbol).copied().unwrap_or(self.method)
+    }
+
+    /// Sell specific lots when `order_id` fills: up to `qty` from each
+    /// `(lot id, qty)` in order. Fills beyond the designated quantity, or
+    /// of lots no longer open, fall back to the symbol's method.
+    pub fn designate_lots(&mut self, order_id: Uuid, lots: Vec<(u64, f64)>) {
+        self.designations.insert(order_id, lots);
+    }
+
+    /// Record a trade activity. Activities already recorded are skipped;
+    /// returns whether this one was new.
+    pub fn record_activity(&mut self, activity: &TradeActivity) -> Result<bool> {
+        if self.seen.contains(&activity.id) {
+            return Ok(false);
+        }
+        let qty = parse_decimal(&activity.qty)?;
+        let price = parse_decimal(&activity.price)?;
+        self.seen.insert(activity.id.clone());
+        self.record_fill(
+            &activity.symbol,
+            &activity.side,
+            qty,
+            price,
+            activity.transaction_time,
+            Some(activity.order_id),
+        );
+        Ok(true)
+    }
+
+    /// Record a fill from a trade-update stream.
+    pub fn record_portfolio_fill(&mut self, fill: &PortfolioFill, order_id: Option<Uuid>) {
+        self.record_fill(
+            &fill.symbol,
+            &fill.side,
+            fill.qty,
+            fill.price,
+            fill.timestamp,
+            order_id,
+        );
+    }
+
+    /// Record the underlying delivery of an option exercise or assignment
+    /// as a fill at the strike price. Expirations deliver nothing and are
+    /// ignored.
+    pub fn record_option_event(&mut self, event: &OptionLifecycleEvent) {
+        if let Some(side) = event.underlying_side() {
+            let at = event
+                .date
+                .and_hms_opt(0, 0, 0)
+                .map_or_else(Utc::now, |dt| dt.and_utc());
+            self.record_fill(
+                &event.contract.underlying,
+                &side,
+                event.share_delta.abs(),
+                event.contract.strike,
+                at,
+                None,
+            );
+        }
+    }
+
+    /// Record a fill of `order_id`. A buy opens a numbered lot; a sell
+    /// relieves lots at once, so fills must arrive in time order.
+    pub fn record_fill(
+        &mut self,
+        symbol: &str,
+        side: &OrderSide,
+        qty: f64,
+        price: f64,
+        at: DateTime<Utc>,
+        order_id: Option<Uuid>,
+    ) {
+        if qty <= QTY_EPSILON {
+            return;
+        }
+        match side {
+            OrderSide::Buy => {
+                self.next_id += 1;
+                let lot = TaxLot {
+                    id: self.next_id,
+                    order_id,
+                    symbol: symbol.to_string(),
+                    qty,
+                    cost: price,
+                    acquired_at: at,
+                };
+                self.buys.push(lot.clone());
+                self.open.entry(symbol.to_string()).or_default().push(lot);
+            }
+            OrderSide::Sell => self.relieve(symbol, qty, price, at, order_id),
+        }
+    }
+
+    fn relieve(
+        &mut self,
+        symbol: &str,
+        qty: f64,
+        price: f64,
+        at: DateTime<Utc>,
+        order_id: Option<Uuid>,
+    ) {
+        let method = self.method(symbol);
+        let Some(lots) = self.open.get_mut(symbol) else {
+            return;
+        };
+
+        // Designated lots first, then the method's order.
+        let mut takes: Vec<(u64, f64)> = Vec::new();
+        let mut remaining = qty;
+        if let Some(id) = order_id
+            && let Some(designated) = self.designations.get_mut(&id)
+        {
+            for (lot_id, left) in designated.iter_mut() {
+                let Some(lot) = lots.iter().find(|lot| lot.id == *lot_id) else {
+                    continue;
+                };
+                let take = remaining.min(*left).min(lot.qty);
+                if take > QTY_EPSILON {
+                    takes.push((*lot_id, take));
+                    *left -= take;
+                    remaining -= take;
+                }
+            }
+            designated.retain(|(_, left)| *left > QTY_EPSILON);
+            if designated.is_empty() {
+                self.designations.remove(&id);
+            }
+        }
+        if remaining > QTY_EPSILON {
+            let mut ordered: Vec<&TaxLot> = lots.iter().collect();
+            sort_lots(&mut ordered, method, price, at);
+            for lot in ordered {
+                let taken: f64 = takes
+                    .iter()
+                    .filter(|(id, _)| *id == lot.id)
+                    .map(|(_, qty)| qty)
+                    .sum();
+                let take = remaining.min(lot.qty - taken);
+                if take > QTY_EPSILON {
+                    takes.push((lot.id, take));
+                    remaining -= take;
+                }
+                if remaining <= QTY_EPSILON {
+                    break;
+                }
+            }
+        }
+
+        for (lot_id, take) in takes {
+            let Some(lot) = lots.iter_mut().find(|lot| lot.id == lot_id) else {
+                continue;
+            };
+            lot.qty -= take;
+            self.sales.push(RealizedSale {
+                lot_id,
+                order_id,
+                symbol: symbol.to_string(),
+                qty: take,
+                cost: lot.cost,
+                price,
+                acquired_at: lot.acquired_at,
+                sold_at: at,
+            });
+        }
+        lots.retain(|lot| lot.qty > QTY_EPSILON);
+    }
+
+    /// Open lots of `symbol`, in purchase order.
+    #[must_use]
+    pub fn open_lots(&self, symbol: &str) -> &[TaxLot] {
+        self.open.get(symbol).map_or(&[], Vec::as_slice)
+    }
+
+    /// Open quantity of `symbol`.
+    #[must_use]
+    pub fn open_qty(&self, symbol: &str) -> f64 {
+        self.open_lots(symbol).iter().map(|lot| lot.qty).sum()
+    }
+
+    /// Realized sales, in time order.
+    #[must_use]
+    pub fn realized(&self) -> &[RealizedSale] {
+        &self.sales
+    }
+
+    /// Realized gain of `symbol`, or of all symbols for `None`.
+    #[must_use]
+    pub fn realized_gain(&self, symbol: Option<&str>) -> f64 {
+        self.sales
+            .iter()
+            .filter(|s| symbol.is_none_or(|symbol| s.symbol == symbol))
+            .map(RealizedSale::gain)
+            .sum()
+    }
+
+    /// Loss sales matched with replacement purchases within the window.
+    ///
+    /// Each purchase replaces at most its own quantity across all loss
+    /// sales, earliest sales first. The lot that was sold never counts as
+    /// its own replacement.
+    #[must_use]
+    pub fn wash_sales(&self) -> Vec<WashSale> {
+        let window = Duration::days(WASH_SALE_WINDOW_DAYS);
+        let mut available: Vec<f64> = self.buys.iter().map(|b| b.qty).collect();
+        let mut washes = Vec::new();
+        for sale in self.sales.iter().filter(|s| s.gain() < 0.0) {
+            let mut unmatched = sale.qty;
+            for (buy, left) in self.buys.iter().zip(available.iter_mut()) {
+                if unmatched <= QTY_EPSILON {
+                    break;
+                }
+                if buy.symbol != sale.symbol
+                    || buy.id == sale.lot_id
+                    || *left <= QTY_EPSILON
+                    || (buy.acquired_at - sale.sold_at).abs() > window
+                {
+                    continue;
+                }
+                let qty = unmatched.min(*left);
+                *left -= qty;
+                unmatched -= qty;
+                washes.push(WashSale {
+                    lot_id: sale.lot_id,
+                    replacement_lot_id: buy.id,
+                    symbol: sale.symbol.clone(),
+                    sold_at: sale.sold_at,
+                    replacement_at: buy.acquired_at,
+                    qty,
+                    disallowed_loss: (sale.cost - sale.price) * qty,
+                });
+            }
+        }
+        washes
+    }
+
+    /// Loss sales of `symbol` that a purchase at `at` would turn into wash
+    /// sales.
+    #[must_use]
+    pub fn check_buy(&self, symbol: &str, at: DateTime<Utc>) -> Vec<WashSaleRisk> {
+        let window = Duration::days(WASH_SALE_WINDOW_DAYS);
+        self.sales
+            .iter()
+            .filter(|s| s.symbol == symbol && s.gain() < 0.0)
+            .filter(|s| s.sold_at <= at && at - s.sold_at <= window)
+            .map(|s| WashSaleRisk {
+                symbol: s.symbol.clone(),
+                conflicting_at: s.sold_at,
+                qty: s.qty,
+                loss: -s.gain(),
+            })
+            .collect()
+    }
+
+    /// Plan a sale of `qty` shares of `symbol` at `price`, as of now.
+    pub fn tax_aware_sell(
+        &self,
+        symbol: &str,
+        qty: f64,
+        method: LotMethod,
+        price: f64,
+    ) -> Result<SellPlan> {
+        self.tax_aware_sell_at(symbol, qty, method, price, Utc::now())
+    }
+
+    /// Plan a sale of `qty` shares of `symbol` at `price`, as of `at`.
+    ///
+    /// Fails if `qty` is not positive or exceeds the open quantity.
+    pub fn tax_aware_sell_at(
+        &self,
+        symbol: &str,
+        qty: f64,
+        method: LotMethod,
+        price: f64,
+        at: DateTime<Utc>,
+    ) -> Result<SellPlan> {
+        if qty <= 0.0 {
+            return Err(AlpacaError::Validation(
+                "sell quantity must be positive".to_string(),
+            ));
+        }
+        let open = self.open_qty(symbol);
+        if qty > open + QTY_EPSILON {
+            return Err(AlpacaError::Validation(format!(
+                "cannot sell {qty} {symbol}: only {open} held"
+            )));
+        }
+
+        let mut lots: Vec<&TaxLot> = self.open_lots(symbol).iter().collect();
+        sort_lots(&mut lots, method, price, at);
+
+        let mut remaining = qty;
+        let mut plan = SellPlan {
+            symbol: symbol.to_string(),
+            qty,
+            price,
+            lots: Vec::new(),
+            short_term_gain: 0.0,
+            long_term_gain: 0.0,
+            wash_sale_risks: Vec::new(),
+        };
+        for lot in lots {
+            if remaining <= QTY_EPSILON {
+                break;
+            }
+            let take = remaining.min(lot.qty);
+            remaining -= take;
+            let gain = (price - lot.cost) * take;
+            let long_term = lot.is_long_term(at);
+            if long_term {
+                plan.long_term_gain += gain;
+            } else {
+                plan.short_term_gain += gain;
+            }
+            plan.lots.push(LotSale {
+                lot: lot.clone(),
+                qty: take,
+                gain,
+                long_term,
+            });
+        }
+
+        let loss: f64 = plan.lots.iter().map(|s| s.gain).filter(|g| *g < 0.0).sum();
+        if loss < 0.0 {
+            let window = Duration::days(WASH_SALE_WINDOW_DAYS);
+            plan.wash_sale_risks = self
+                .buys
+                .iter()
+                .filter(|b| b.symbol == symbol && b.acquired_at <= at)
+                .filter(|b| at - b.acquired_at <= window)
+                .filter(|b| !plan.lots.iter().any(|s| s.gain < 0.0 && s.lot.id == b.id))
+                .map(|b| WashSaleRisk {
+                    symbol: b.symbol.clone(),
+                    conflicting_at: b.acquired_at,
+                    qty: b.qty,
+                    loss: -loss,
+                })
+                .collect();
+        }
+        Ok(plan)
+    }
+}
+
+/// Order `lots` for selling at `price` at time `at` under `method`.
+fn sort_lots(lots: &mut [&TaxLot], method: LotMethod, price: f64, at: DateTime<Utc>) {
+    match method {
+        LotMethod::Fifo => {}
+        LotMethod::Lifo => lots.reverse(),
+        LotMethod::HighestCost => lots.sort_by(|a, b| b.cost.total_cmp(&a.cost)),
+        LotMethod::LowestCost => lots.sort_by(|a, b| a.cost.total_cmp(&b.cost)),
+        LotMethod::MinimizeTax => {
+            let key = |lot: &TaxLot| {
+                let gain = price - lot.cost;
+                let rank = match (gain < 0.0, lot.is_long_term(at)) {
+                    (true, false) => 0,
+                    (true, true) => 1,
+                    (false, true) => 2,
+                    (false, false) => 3,
+                };
+                (rank, gain)
+            };
+            lots.sort_by(|a, b| {
+                let (rank_a, gain_a) = key(a);
+                let (rank_b, gain_b) = key(b);
+                rank_a.cmp(&rank_b).then(gain_a.total_cmp(&gain_b))
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(n: i64) -> DateTime<Utc> {
+        "2024-01-01T15:00:00Z".parse::<DateTime<Utc>>().unwrap() + Duration::days(n)
+    }
+
+    #[test]
+    fn test_wash_sale_detection() {
+        let mut lots = TaxLots::new();
+        lots.record_fill("AAPL", &OrderSide::Buy, 10.0, 100.0, day(0), None);
+        lots.record_fill("AAPL", &OrderSide::Sell, 10.0, 90.0, day(40), None);
+        lots.record_fill("AAPL", &OrderSide::Buy, 4.0, 91.0, day(55), None);
+        lots.record_fill("AAPL", &OrderSide::Buy, 10.0, 92.0, day(90), None);
+        lots.record_fill("MSFT", &OrderSide::Buy, 5.0, 300.0, day(45), None);
+
+        let washes = lots.wash_sales();
+        assert_eq!(washes.len(), 1);
+        assert_eq!(washes[0].replacement_at, day(55));
+        assert_eq!(washes[0].qty, 4.0);
+        assert!((washes[0].disallowed_loss - 40.0).abs() < 1e-9);
+
+        assert_eq!(lots.check_buy("AAPL", day(60)).len(), 1);
+        assert!(lots.check_buy("AAPL", day(71)).is_empty());
+        assert!(lots.check_buy("MSFT", day(60)).is_empty());
+    }
+
+    #[test]
+    fn test_tax_aware_sell_methods() {
+        let mut lots = TaxLots::new();
+        lots.record_fill("AAPL", &OrderSide::Buy, 10.0, 50.0, day(0), None); // long-term gain
+        lots.record_fill("AAPL", &OrderSide::Buy, 10.0, 120.0, day(380), None); // short-term loss
+        lots.record_fill("AAPL", &OrderSide::Buy, 10.0, 90.0, day(390), None); // short-term gain
+        let at = day(400);
+
+        let fifo = lots
+            .tax_aware_sell_at("AAPL", 15.0, LotMethod::Fifo, 100.0, at)
+            .unwrap();
+        assert_eq!(fifo.lots[0].lot.cost, 50.0);
+        assert!((fifo.long_term_gain - 500.0).abs() < 1e-9);
+        assert!((fifo.short_term_gain + 100.0).abs() < 1e-9);
+
+        let min_tax = lots
+            .tax_aware_sell_at("AAPL", 15.0, LotMethod::MinimizeTax, 100.0, at)
+            .unwrap();
+        let costs: Vec<f64> = min_tax.lots.iter().map(|s| s.lot.cost).collect();
+        assert_eq!(costs, [120.0, 50.0]);
+        assert!((min_tax.total_gain() - 50.0).abs() < 1e-9);
+        // The day-390 purchase would wash the loss on the day-380 lot.
+        assert_eq!(min_tax.wash_sale_risks.len(), 1);
+        assert_eq!(min_tax.wash_sale_risks[0].conflicting_at, day(390));
+
+        let lifo = lots
+            .tax_aware_sell_at("AAPL", 5.0, LotMethod::Lifo, 100.0, at)
+            .unwrap();
+        assert_eq!(lifo.lots[0].lot.cost, 90.0);
+        assert!(lifo.wash_sale_risks.is_empty());
+
+        assert!(
+            lots.tax_aware_sell_at("AAPL", 31.0, LotMethod::Fifo, 100.0, at)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_lot_methods_and_designations() {
+        let mut lots = TaxLots::with_method(LotMethod::Fifo);
+        lots.set_method("MSFT", LotMethod::Lifo);
+        for (symbol, price, n) in [
+            ("AAPL", 100.0, 0),
+            ("AAPL", 120.0, 10),
+            ("AAPL", 90.0, 20),
+            ("MSFT", 300.0, 0),
+            ("MSFT", 310.0, 5),
+        ] {
+            lots.record_fill(symbol, &OrderSide::Buy, 10.0, price, day(n), None);
+        }
+        let ids: Vec<u64> = lots.open_lots("AAPL").iter().map(|l| l.id).collect();
+
+        // FIFO relieves the oldest lot.
+        lots.record_fill("AAPL", &OrderSide::Sell, 5.0, 110.0, day(35), None);
+        assert_eq!(lots.realized()[0].lot_id, ids[0]);
+        // A designated sell takes the day-10 lot, then falls back to FIFO.
+        let order_id = Uuid::new_v4();
+        lots.designate_lots(order_id, vec![(ids[1], 10.0)]);
+        lots.record_fill(
+            "AAPL",
+            &OrderSide::Sell,
+            12.0,
+            110.0,
+            day(40),
+            Some(order_id),
+        );
+        let sold: Vec<(u64, f64)> = lots.realized()[1..]
+            .iter()
+            .map(|s| (s.lot_id, s.qty))
+            .collect();
+        assert_eq!(sold, [(ids[1], 10.0), (ids[0], 2.0)]);
+        assert!(lots.designations.is_empty());
+        assert_eq!(lots.open_qty("AAPL"), 13.0);
+        // LIFO for MSFT.
+        lots.record_fill("MSFT", &OrderSide::Sell, 4.0, 305.0, day(40), None);
+        assert_eq!(lots.realized()[3].cost, 310.0);
+
+        assert!((lots.realized_gain(Some("AAPL")) - (50.0 - 100.0 + 20.0)).abs() < 1e-9);
+        assert!(!lots.realized()[1].is_long_term());
+
+        // The day-10 lot closed at a loss; the day-20 purchase replaces it.
+        let washes = lots.wash_sales();
+        assert_eq!(washes.len(), 1);
+        assert_eq!(washes[0].lot_id, ids[1]);
+        assert_eq!(washes[0].replacement_lot_id, ids[2]);
+        assert!((washes[0].disallowed_loss - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_wash_sales_tell_lots_bought_together_apart() {
+        // Two fills of the same order at the same time form separate lots;
+        // selling one at a loss is washed by the other.
+        let mut lots = TaxLots::new();
+        lots.record_fill("AAPL", &OrderSide::Buy, 5.0, 100.0, day(0), None);
+        lots.record_fill("AAPL", &OrderSide::Buy, 5.0, 100.0, day(0), None);
+        lots.record_fill("AAPL", &OrderSide::Sell, 5.0, 90.0, day(10), None);
+
+        let washes = lots.wash_sales();
+        assert_eq!(washes.len(), 1);
+        assert_eq!(washes[0].lot_id, 1);
+        assert_eq!(washes[0].replacement_lot_id, 2);
+        assert_eq!(washes[0].qty, 5.0);
+    }
+}
@@ -10,6 +10,10 @@ use uuid::Uuid;
 /// Test fixtures for common Alpaca types.
 pub mod fixtures {
     use super::*;
+    use crate::error::Result;
+    use crate::trading::{OrderReplacement, OrderRequest, TradingApi};
+    use std::future::Future;
+    use std::sync::Mutex;
 
     /// Creates a sample Account for testing.
     #[must_use]
@@ -166,6 +170,30 @@ pub mod fixtures {
         }
     }
 
+    /// Creates a Quote with the given top of book, otherwise as
+    /// [`sample_quote`] at the current time.
+    #[must_use]
+    pub fn quote_with(bid_price: f64, bid_size: u32, ask_price: f64, ask_size: u32) -> Quote {
+        Quote {
+            bid_price,
+            bid_size,
+            ask_price,
+            ask_size,
+            ..sample_quote(Utc::now())
+        }
+    }
+
+    /// Creates a Trade of `size` at `price`, otherwise as [`sample_trade`]
+    /// at the current time.
+    #[must_use]
+    pub fn trade_with(price: f64, size: u32) -> Trade {
+        Trade {
+            price,
+            size,
+            ..sample_trade(Utc::now())
+        }
+    }
+
     /// Creates a sample Clock for testing.
     #[must_use]
     pub fn sample_clock(is_open: bool) -> Clock {
@@ -177,6 +205,78 @@ pub mod fixtures {
             next_close: now + chrono::Duration::hours(if is_open { 6 } else { 24 }),
         }
     }
+
+    /// In-memory [`TradingApi`] that accepts every order and records it.
+    ///
+    /// Submitted orders come back as [`sample_order`]s; positions are empty
+    /// and the account is [`sample_account`] unless set with
+    /// [`with_account`](Self::with_account).
+    #[derive(Debug)]
+    pub struct MockTradingApi {
+        account: Account,
+        submitted: Mutex<Vec<OrderRequest>>,
+    }
+
+    impl Default for MockTradingApi {
+        fn default() -> Self {
+            Self {
+                account: sample_account(),
+                submitted: Mutex::default(),
+            }
+        }
+    }
+
+    impl MockTradingApi {
+        /// Create a mock with the sample account.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Report `account` from `get_account`.
+        #[must_use]
+        pub fn with_account(mut self, account: Account) -> Self {
+            self.account = account;
+            self
+        }
+
+        /// Orders submitted so far, oldest first.
+        pub fn submitted(&self) -> Vec<OrderRequest> {
+            self.submitted.lock().unwrap().clone()
+        }
+
+        /// Number of orders submitted so far.
+        pub fn submit_count(&self) -> usize {
+            self.submitted.lock().unwrap().len()
+        }
+    }
+
+    impl TradingApi for MockTradingApi {
+        fn submit_order(&self, order: &OrderRequest) -> impl Future<Output = Result<Order>> + Send {
+            self.submitted.lock().unwrap().push(order.clone());
+            let qty = order.qty.to_string();
+            std::future::ready(Ok(sample_order(&order.symbol, order.side.clone(), &qty)))
+        }
+
+        fn cancel_order(&self, _: &Order) -> impl Future<Output = Result<()>> + Send {
+            std::future::ready(Ok(()))
+        }
+
+        fn replace_order(
+            &self,
+            order: &Order,
+            _: &OrderReplacement,
+        ) -> impl Future<Output = Result<Order>> + Send {
+            std::future::ready(Ok(order.clone()))
+        }
+
+        fn get_positions(&self) -> impl Future<Output = Result<Vec<Position>>> + Send {
+            std::future::ready(Ok(Vec::new()))
+        }
+
+        fn get_account(&self) -> impl Future<Output = Result<Account>> + Send {
+            std::future::ready(Ok(self.account.clone()))
+        }
+    }
 }
 
 /// Assertion helpers for testing.
@@ -298,6 +398,128 @@ pub mod json_samples {
     }"#;
 }
 
+/// Contract checks of serde models against recorded API responses.
+///
+/// [`check`](contract::check) deserializes a recorded response into a
+/// model, serializes it back and compares the two documents. Fields present
+/// in the response but lost in the round trip are fields the model does not
+/// know about yet: schema drift that would otherwise go unnoticed until a
+/// field the caller needs turns out to be missing. A response that fails to
+/// deserialize at all is reported as a breaking change.
+pub mod contract {
+    use serde::{Serialize, de::DeserializeOwned};
+    use serde_json::Value;
+    use std::collections::BTreeSet;
+    use std::fmt;
+
+    /// Result of checking one recorded response against a model.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ContractReport {
+        /// Name of the checked model or endpoint.
+        pub name: String,
+        /// JSON paths present in the response but unknown to the model
+        /// (array elements are written as `[]`).
+        pub unknown_fields: Vec<String>,
+        /// Deserialization error, if the response no longer fits the model.
+        pub error: Option<String>,
+    }
+
+    impl ContractReport {
+        /// Whether the model covers the response exactly.
+        #[must_use]
+        pub fn is_clean(&self) -> bool {
+            self.error.is_none() && self.unknown_fields.is_empty()
+        }
+
+        /// Whether the response failed to deserialize.
+        #[must_use]
+        pub fn is_breaking(&self) -> bool {
+            self.error.is_some()
+        }
+    }
+
+    impl fmt::Display for ContractReport {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if let Some(error) = &self.error {
+                return write!(f, "{}: BREAKING: {}", self.name, error);
+            }
+            if self.unknown_fields.is_empty() {
+                return write!(f, "{}: ok", self.name);
+            }
+            write!(
+                f,
+                "{}: {} unknown field(s): {}",
+                self.name,
+                self.unknown_fields.len(),
+                self.unknown_fields.join(", ")
+            )
+        }
+    }
+
+    /// Check a recorded JSON response against model `T`.
+    pub fn check<T>(name: &str, json: &str) -> ContractReport
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let mut report = ContractReport {
+            name: name.to_string(),
+            unknown_fields: Vec::new(),
+            error: None,
+        };
+        let input: Value = match serde_json::from_str(json) {
+            Ok(value) => value,
+            Err(e) => {
+                report.error = Some(format!("invalid JSON: {e}"));
+                return report;
+            }
+        };
+        let model: T = match serde_json::from_value(input.clone()) {
+            Ok(model) => model,
+            Err(e) => {
+                report.error = Some(e.to_string());
+                return report;
+            }
+        };
+        let output = match serde_json::to_value(&model) {
+            Ok(value) => value,
+            Err(e) => {
+                report.error = Some(format!("re-serialization failed: {e}"));
+                return report;
+            }
+        };
+        let mut unknown = BTreeSet::new();
+        collect_unknown(&input, &output, "$", &mut unknown);
+        report.unknown_fields = unknown.into_iter().collect();
+        report
+    }
+
+    /// Record paths of non-null input fields missing from `output`.
+    fn collect_unknown(input: &Value, output: &Value, path: &str, out: &mut BTreeSet<String>) {
+        match (input, output) {
+            (Value::Object(input), Value::Object(output)) => {
+                for (key, value) in input {
+                    let child = format!("{path}.{key}");
+                    match output.get(key) {
+                        Some(known) => collect_unknown(value, known, &child, out),
+                        // Optional fields skipped when `None` come back absent.
+                        None if value.is_null() => {}
+                        None => {
+                            out.insert(child);
+                        }
+                    }
+                }
+            }
+            (Value::Array(input), Value::Array(output)) => {
+                let child = format!("{path}[]");
+                for (value, known) in input.iter().zip(output) {
+                    collect_unknown(value, known, &child, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -360,4 +582,25 @@ mod tests {
         let account = fixtures::sample_account();
         assertions::assert_account_active(&account);
     }
+
+    #[test]
+    fn test_contract_reports_unknown_fields() {
+        let clean = contract::check::<Order>("order", json_samples::ORDER_JSON);
+        assert!(clean.is_clean(), "{clean}");
+
+        let drifted = json_samples::ASSET_JSON.replacen(
+            "\"symbol\"",
+            "\"maintenance_margin_requirement\": 30, \"attributes\": [\"ptp_no_exception\"], \"symbol\"",
+            1,
+        );
+        let report = contract::check::<Asset>("asset", &drifted);
+        assert!(!report.is_breaking());
+        assert_eq!(
+            report.unknown_fields,
+            ["$.attributes", "$.maintenance_margin_requirement"]
+        );
+
+        let broken = contract::check::<Asset>("asset", r#"{"symbol": 1}"#);
+        assert!(broken.is_breaking());
+    }
 }
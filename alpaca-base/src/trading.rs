@@ -0,0 +1,263 @@
+//! Transport-neutral trading interface.
+//!
+//! Strategies written against [`TradingApi`] run unchanged against the live
+//! REST client, the FIX client, the backtester or any other implementation.
+//! [`RoutedTradingApi`] combines two of them, e.g. FIX for order routing and
+//! REST for positions and account state:
+//!
+//! ```rust,ignore
+//! async fn rebalance<T: TradingApi>(api: &T) -> Result<()> {
+//!     let account = api.get_account().await?;
+//!     if api.get_positions().await?.is_empty() {
+//!         api.submit_order(&OrderRequest::market("AAPL", OrderSide::Buy, 10.0))
+//!             .await?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::error::Result;
+use crate::types::{
+    Account, AssetClass, Order, OrderClass, OrderSide, OrderStatus, OrderType, Position,
+    TimeInForce,
+};
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use uuid::Uuid;
+
+/// An order to submit through a [`TradingApi`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OrderRequest {
+    /// Symbol to trade.
+    pub symbol: String,
+    /// Buy or sell.
+    pub side: OrderSide,
+    /// Quantity.
+    pub qty: f64,
+    /// Order type.
+    pub order_type: OrderType,
+    /// Time in force.
+    pub time_in_force: TimeInForce,
+    /// Limit price for limit and stop-limit orders.
+    pub limit_price: Option<f64>,
+    /// Stop price for stop and stop-limit orders.
+    pub stop_price: Option<f64>,
+    /// Client order ID.
+    pub client_order_id: Option<String>,
+}
+
+impl OrderRequest {
+    /// Create a day market order.
+    #[must_use]
+    pub fn market(symbol: &str, side: OrderSide, qty: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            qty,
+            ..Default::default()
+        }
+    }
+
+    /// Create a day limit order.
+    #[must_use]
+    pub fn limit(symbol: &str, side: OrderSide, qty: f64, limit_price: f64) -> Self {
+        Self {
+            order_type: OrderType::Limit,
+            limit_price: Some(limit_price),
+            ..Self::market(symbol, side, qty)
+        }
+    }
+
+    /// Create a day stop order.
+    #[must_use]
+    pub fn stop(symbol: &str, side: OrderSide, qty: f64, stop_price: f64) -> Self {
+        Self {
+            order_type: OrderType::Stop,
+            stop_price: Some(stop_price),
+            ..Self::market(symbol, side, qty)
+        }
+    }
+
+    /// Set the time in force.
+    #[must_use]
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    /// Set the client order ID.
+    #[must_use]
+    pub fn client_order_id(mut self, client_order_id: &str) -> Self {
+        self.client_order_id = Some(client_order_id.to_string());
+        self
+    }
+
+    /// The order as accepted but not yet acknowledged, for transports that
+    /// do not return the order on submission.
+    #[must_use]
+    pub fn pending_order(&self, id: Uuid, client_order_id: &str, at: DateTime<Utc>) -> Order {
+        Order {
+            id,
+            client_order_id: client_order_id.to_string(),
+            created_at: at,
+            updated_at: at,
+            submitted_at: Some(at),
+            filled_at: None,
+            expired_at: None,
+            canceled_at: None,
+            failed_at: None,
+            replaced_at: None,
+            replaced_by: None,
+            replaces: None,
+            asset_id: Uuid::nil(),
+            symbol: self.symbol.clone(),
+            asset_class: AssetClass::UsEquity,
+            notional: None,
+            qty: Some(self.qty.to_string()),
+            filled_qty: "0".to_string(),
+            filled_avg_price: None,
+            order_class: OrderClass::Simple,
+            order_type: self.order_type.clone(),
+            side: self.side.clone(),
+            time_in_force: self.time_in_force.clone(),
+            limit_price: self.limit_price.map(|p| p.to_string()),
+            stop_price: self.stop_price.map(|p| p.to_string()),
+            status: OrderStatus::PendingNew,
+            extended_hours: false,
+            legs: None,
+            trail_percent: None,
+            trail_price: None,
+            hwm: None,
+        }
+    }
+}
+
+/// Changes to an open order; unset fields are left as they are.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OrderReplacement {
+    /// New quantity.
+    pub qty: Option<f64>,
+    /// New limit price.
+    pub limit_price: Option<f64>,
+    /// New stop price.
+    pub stop_price: Option<f64>,
+    /// New time in force.
+    pub time_in_force: Option<TimeInForce>,
+}
+
+impl OrderReplacement {
+    /// Create an empty replacement.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the new quantity.
+    #[must_use]
+    pub fn qty(mut self, qty: f64) -> Self {
+        self.qty = Some(qty);
+        self
+    }
+
+    /// Set the new limit price.
+    #[must_use]
+    pub fn limit_price(mut self, limit_price: f64) -> Self {
+        self.limit_price = Some(limit_price);
+        self
+    }
+
+    /// Set the new stop price.
+    #[must_use]
+    pub fn stop_price(mut self, stop_price: f64) -> Self {
+        self.stop_price = Some(stop_price);
+        self
+    }
+
+    /// Set the new time in force.
+    #[must_use]
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+}
+
+/// Order routing and account state, independent of the transport.
+///
+/// Orders are identified by the [`Order`] returned from
+/// [`submit_order`](TradingApi::submit_order), which carries everything each
+/// transport needs (order ID for REST, client order ID, symbol and side for
+/// FIX).
+pub trait TradingApi: Send + Sync {
+    /// Submit an order.
+    fn submit_order(&self, order: &OrderRequest) -> impl Future<Output = Result<Order>> + Send;
+
+    /// Cancel an open order.
+    fn cancel_order(&self, order: &Order) -> impl Future<Output = Result<()>> + Send;
+
+    /// Replace an open order, returning the replacement.
+    fn replace_order(
+        &self,
+        order: &Order,
+        replacement: &OrderReplacement,
+    ) -> impl Future<Output = Result<Order>> + Send;
+
+    /// Current open positions.
+    fn get_positions(&self) -> impl Future<Output = Result<Vec<Position>>> + Send;
+
+    /// Current account state.
+    fn get_account(&self) -> impl Future<Output = Result<Account>> + Send;
+}
+
+/// Sends orders through one [`TradingApi`] and reads positions and account
+/// state from another.
+#[derive(Debug, Clone)]
+pub struct RoutedTradingApi<O, S> {
+    orders: O,
+    state: S,
+}
+
+impl<O: TradingApi, S: TradingApi> RoutedTradingApi<O, S> {
+    /// Route orders to `orders` and state queries to `state`.
+    #[must_use]
+    pub fn new(orders: O, state: S) -> Self {
+        Self { orders, state }
+    }
+
+    /// The order transport.
+    #[must_use]
+    pub fn orders(&self) -> &O {
+        &self.orders
+    }
+
+    /// The state transport.
+    #[must_use]
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+}
+
+impl<O: TradingApi, S: TradingApi> TradingApi for RoutedTradingApi<O, S> {
+    fn submit_order(&self, order: &OrderRequest) -> impl Future<Output = Result<Order>> + Send {
+        self.orders.submit_order(order)
+    }
+
+    fn cancel_order(&self, order: &Order) -> impl Future<Output = Result<()>> + Send {
+        self.orders.cancel_order(order)
+    }
+
+    fn replace_order(
+        &self,
+        order: &Order,
+        replacement: &OrderReplacement,
+    ) -> impl Future<Output = Result<Order>> + Send {
+        self.orders.replace_order(order, replacement)
+    }
+
+    fn get_positions(&self) -> impl Future<Output = Result<Vec<Position>>> + Send {
+        self.state.get_positions()
+    }
+
+    fn get_account(&self) -> impl Future<Output = Result<Account>> + Send {
+        self.state.get_account()
+    }
+}
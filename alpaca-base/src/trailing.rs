@@ -0,0 +1,381 @@
+//! Client-side trailing stops.
+//!
+//! Alpaca does not offer server-side trailing stops for every asset class,
+//! crypto in particular. [`TrailingStopManager`] keeps synthetic trailing
+//! stops instead: feed it streamed prices with
+//! [`TrailingStopManager::on_price`] and it ratchets each stop behind the
+//! best price seen, submitting a market or limit exit through any
+//! [`TradingApi`] once the price crosses the stop.
+//!
+//! With [`TrailingStopManager::load`] the stops and their high/low water
+//! marks are saved to a JSON file after every change, so a restarted
+//! process resumes trailing from where it stopped.
+
+use crate::error::{AlpacaError, Result};
+use crate::trading::{OrderRequest, TradingApi};
+use crate::types::{Order, OrderSide, TimeInForce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Distance a [`TrailingStop`] keeps from the best price.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trail {
+    /// A fixed price distance.
+    Amount(f64),
+    /// A percentage of the best price, e.g. `2.0` for 2%.
+    Percent(f64),
+}
+
+impl Trail {
+    fn distance(self, price: f64) -> f64 {
+        match self {
+            Trail::Amount(amount) => amount,
+            Trail::Percent(percent) => price * percent / 100.0,
+        }
+    }
+}
+
+/// Order sent when a [`TrailingStop`] triggers.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopExit {
+    /// A market order.
+    #[default]
+    Market,
+    /// A limit order `offset` beyond the stop price: below it for sell
+    /// exits, above it for buy exits.
+    Limit {
+        /// Price distance from the stop price.
+        offset: f64,
+    },
+}
+
+/// A synthetic trailing stop protecting a position.
+///
+/// A sell stop protects a long position and trails below the highest price
+/// seen; a buy stop protects a short and trails above the lowest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrailingStop {
+    /// Symbol of the position.
+    pub symbol: String,
+    /// Side of the exit order.
+    pub side: OrderSide,
+    /// Quantity to exit.
+    pub qty: f64,
+    /// Distance from the best price.
+    pub trail: Trail,
+    /// Order sent on trigger.
+    pub exit: StopExit,
+    /// Time in force of the exit order.
+    pub time_in_force: TimeInForce,
+    /// Best price seen since the stop was added: the high for sell stops,
+    /// the low for buy stops.
+    pub water_mark: Option<f64>,
+}
+
+impl TrailingStop {
+    /// Create a stop exiting `qty` of `symbol` with a `side` order once
+    /// the price moves `trail` against the best price seen. Exits are
+    /// market GTC orders, which crypto accepts.
+    #[must_use]
+    pub fn new(symbol: &str, side: OrderSide, qty: f64, trail: Trail) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            qty,
+            trail,
+            exit: StopExit::Market,
+            time_in_force: TimeInForce::Gtc,
+            water_mark: None,
+        }
+    }
+
+    /// Set the order sent on trigger.
+    #[must_use]
+    pub fn exit(mut self, exit: StopExit) -> Self {
+        self.exit = exit;
+        self
+    }
+
+    /// Set the time in force of the exit order.
+    #[must_use]
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    /// The current stop price, once a price has been seen.
+    #[must_use]
+    pub fn stop_price(&self) -> Option<f64> {
+        let mark = self.water_mark?;
+        let distance = self.trail.distance(mark);
+        Some(match self.side {
+            OrderSide::Sell => mark - distance,
+            OrderSide::Buy => mark + distance,
+        })
+    }
+
+    /// Move the water mark with `price`. Returns `true` if `price` crossed
+    /// the stop.
+    fn update(&mut self, price: f64) -> bool {
+        let better = self.water_mark.is_none_or(|mark| match self.side {
+            OrderSide::Sell => price > mark,
+            OrderSide::Buy => price < mark,
+        });
+        if better {
+            self.water_mark = Some(price);
+        }
+        self.stop_price().is_some_and(|stop| match self.side {
+            OrderSide::Sell => price <= stop,
+            OrderSide::Buy => price >= stop,
+        })
+    }
+
+    /// The exit order for a trigger at the current stop price.
+    fn exit_order(&self) -> OrderRequest {
+        let stop = self.stop_price().unwrap_or_default();
+        let order = match self.exit {
+            StopExit::Market => OrderRequest::market(&self.symbol, self.side.clone(), self.qty),
+            StopExit::Limit { offset } => {
+                let limit = match self.side {
+                    OrderSide::Sell => stop - offset,
+                    OrderSide::Buy => stop + offset,
+                };
+                OrderRequest::limit(&self.symbol, self.side.clone(), self.qty, limit)
+            }
+        };
+        order.time_in_force(self.time_in_force.clone())
+    }
+}
+
+/// Synthetic trailing stops driven by streamed prices.
+///
+/// Stops are keyed by a caller-chosen id, so one symbol may carry several.
+///
+/// ```rust,ignore
+/// let mut stops = TrailingStopManager::load(api, "stops.json")?;
+/// stops.add("btc-long", TrailingStop::new("BTC/USD", OrderSide::Sell, 0.5, Trail::Percent(3.0)))?;
+/// while let Some((symbol, price)) = prices.next().await {
+///     for order in stops.on_price(&symbol, price).await? {
+///         println!("trailing stop exit {}", order.id);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TrailingStopManager<T> {
+    api: T,
+    stops: BTreeMap<String, TrailingStop>,
+    path: Option<PathBuf>,
+}
+
+impl<T: TradingApi> TrailingStopManager<T> {
+    /// Create a manager submitting exits through `api`, keeping its stops
+    /// in memory only.
+    #[must_use]
+    pub fn new(api: T) -> Self {
+        Self {
+            api,
+            stops: BTreeMap::new(),
+            path: None,
+        }
+    }
+
+    /// Create a manager persisting its stops to `path`, resuming the stops
+    /// saved there by an earlier run.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Json`] if the file is not a saved stop set,
+    /// or [`AlpacaError::Config`] if it cannot be read.
+    pub fn load(api: T, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let stops = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(io_error(e)),
+        };
+        if !stops.is_empty() {
+            info!(
+                "Resumed {} trailing stops from {}",
+                stops.len(),
+                path.display()
+            );
+        }
+        Ok(Self {
+            api,
+            stops,
+            path: Some(path),
+        })
+    }
+
+    /// The API exits are submitted through.
+    #[must_use]
+    pub fn api(&self) -> &T {
+        &self.api
+    }
+
+    /// The active stops by id.
+    #[must_use]
+    pub fn stops(&self) -> &BTreeMap<String, TrailingStop> {
+        &self.stops
+    }
+
+    /// Add or replace the stop `id`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if the quantity or trail is not
+    /// positive, or the error of saving the state.
+    pub fn add(&mut self, id: &str, stop: TrailingStop) -> Result<()> {
+        let trail = match stop.trail {
+            Trail::Amount(value) | Trail::Percent(value) => value,
+        };
+        if stop.qty <= 0.0 || trail <= 0.0 {
+            return Err(AlpacaError::Validation(format!(
+                "trailing stop {id} needs a positive quantity and trail"
+            )));
+        }
+        self.stops.insert(id.to_string(), stop);
+        self.save()
+    }
+
+    /// Remove the stop `id`, returning it.
+    ///
+    /// # Errors
+    /// Returns the error of saving the state.
+    pub fn remove(&mut self, id: &str) -> Result<Option<TrailingStop>> {
+        let removed = self.stops.remove(id);
+        if removed.is_some() {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Apply a streamed price of `symbol`, submitting the exit of every
+    /// stop it triggers. Triggered stops are removed once their exit is
+    /// accepted.
+    ///
+    /// # Errors
+    /// Returns the first submission error; that stop and any later
+    /// triggered ones stay armed and fire again on the next price.
+    pub async fn on_price(&mut self, symbol: &str, price: f64) -> Result<Vec<Order>> {
+        let mut triggered = Vec::new();
+        for (id, stop) in self.stops.iter_mut().filter(|(_, s)| s.symbol == symbol) {
+            if stop.update(price) {
+                triggered.push(id.clone());
+            }
+        }
+
+        let mut orders = Vec::new();
+        let mut failure = None;
+        for id in triggered {
+            let order = self.stops[&id].exit_order();
+            info!(
+                "Trailing stop {} triggered on {} at {}, submitting exit",
+                id, symbol, price
+            );
+            match self.api.submit_order(&order).await {
+                Ok(submitted) => {
+                    self.stops.remove(&id);
+                    orders.push(submitted);
+                }
+                Err(e) => {
+                    warn!("Trailing stop {} exit failed: {}", id, e);
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        self.save()?;
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(orders),
+        }
+    }
+
+    /// Replace the state file atomically via a temp file and rename.
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let tmp = path.with_extension("json.tmp");
+        let mut file = File::create(&tmp).map_err(io_error)?;
+        file.write_all(&serde_json::to_vec_pretty(&self.stops)?)
+            .and_then(|()| file.sync_data())
+            .map_err(io_error)?;
+        fs::rename(&tmp, path).map_err(io_error)
+    }
+}
+
+fn io_error(e: std::io::Error) -> AlpacaError {
+    AlpacaError::Config(format!("trailing stop state i/o error: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fixtures::MockTradingApi;
+    use crate::types::OrderType;
+
+    #[tokio::test]
+    async fn test_trailing_stops_trigger_and_persist() {
+        let path =
+            std::env::temp_dir().join(format!("alpaca-trailing-{}.json", uuid::Uuid::new_v4()));
+        let mut manager = TrailingStopManager::load(MockTradingApi::new(), &path).unwrap();
+        manager
+            .add(
+                "long",
+                TrailingStop::new("BTC/USD", OrderSide::Sell, 0.5, Trail::Percent(10.0)),
+            )
+            .unwrap();
+        manager
+            .add(
+                "short",
+                TrailingStop::new("ETH/USD", OrderSide::Buy, 2.0, Trail::Amount(50.0))
+                    .exit(StopExit::Limit { offset: 5.0 }),
+            )
+            .unwrap();
+
+        for price in [100.0, 120.0, 110.0] {
+            assert!(manager.on_price("BTC/USD", price).await.unwrap().is_empty());
+        }
+        assert_eq!(manager.stops()["long"].stop_price(), Some(108.0));
+        assert!(
+            manager
+                .on_price("ETH/USD", 3000.0)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // A restarted manager resumes the water marks.
+        drop(manager);
+        let mut manager = TrailingStopManager::load(MockTradingApi::new(), &path).unwrap();
+        assert_eq!(manager.stops()["long"].water_mark, Some(120.0));
+
+        let exits = manager.on_price("BTC/USD", 107.5).await.unwrap();
+        assert_eq!(exits.len(), 1);
+        assert!(
+            manager
+                .on_price("ETH/USD", 2990.0)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(manager.on_price("ETH/USD", 3040.0).await.unwrap().len(), 1);
+
+        let sent = manager.api().submitted();
+        assert_eq!(sent[0].order_type, OrderType::Market);
+        assert_eq!(sent[0].time_in_force, TimeInForce::Gtc);
+        assert_eq!(sent[1].side, OrderSide::Buy);
+        assert_eq!(sent[1].limit_price, Some(3045.0));
+        assert!(manager.stops().is_empty());
+
+        let manager = TrailingStopManager::load(MockTradingApi::new(), &path).unwrap();
+        assert!(manager.stops().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -4,7 +4,8 @@
 
 #![allow(missing_docs)]
 
-use chrono::{DateTime, Utc};
+use crate::error::{AlpacaError, ValidationError};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -371,7 +372,7 @@ pub enum PositionSide {
 }
 
 /// Market data bar
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Bar {
     #[serde(rename = "t")]
     pub timestamp: DateTime<Utc>,
@@ -1037,12 +1038,74 @@ impl OptionBarsParams {
     }
 }
 
+/// Parameters for querying historical option trades or quotes.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct OptionTicksParams {
+    /// Comma-separated option symbols to query.
+    pub symbols: String,
+    /// Start time (RFC3339 format).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    /// End time (RFC3339 format).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// Maximum number of results per page, across all symbols.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Sort by timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortDirection>,
+    /// Pagination token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl OptionTicksParams {
+    /// Create new parameters for `symbols`.
+    #[must_use]
+    pub fn new(symbols: &[&str]) -> Self {
+        Self {
+            symbols: symbols.join(","),
+            ..Default::default()
+        }
+    }
+
+    /// Set time range.
+    #[must_use]
+    pub fn time_range(mut self, start: &str, end: &str) -> Self {
+        self.start = Some(start.to_string());
+        self.end = Some(end.to_string());
+        self
+    }
+
+    /// Set maximum number of results per page.
+    #[must_use]
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set sort direction.
+    #[must_use]
+    pub fn sort(mut self, sort: SortDirection) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Set pagination token.
+    #[must_use]
+    pub fn page_token(mut self, page_token: &str) -> Self {
+        self.page_token = Some(page_token.to_string());
+        self
+    }
+}
+
 // ============================================================================
 // Enhanced Stock Market Data Types
 // ============================================================================
 
 /// Data feed source.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum DataFeed {
     /// IEX exchange data.
@@ -1060,6 +1123,33 @@ pub enum DataFeed {
     Overnight,
 }
 
+impl DataFeed {
+    /// The feed name used in query parameters and stream URLs.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Iex => "iex",
+            Self::Sip => "sip",
+            Self::Otc => "otc",
+            Self::DelayedSip => "delayed_sip",
+            Self::Boats => "boats",
+            Self::Overnight => "overnight",
+        }
+    }
+
+    /// Whether the feed lags the market (15 minutes for `delayed_sip`).
+    #[must_use]
+    pub fn is_delayed(&self) -> bool {
+        matches!(self, Self::DelayedSip)
+    }
+}
+
+impl std::fmt::Display for DataFeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Stock snapshot with latest market data.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StockSnapshot {
@@ -1143,6 +1233,269 @@ pub struct CorporateAction {
     pub new_rate: Option<String>,
 }
 
+/// Forward split from the `/v1beta1/corporate-actions` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ForwardSplit {
+    /// Unique identifier.
+    pub id: String,
+    /// Symbol.
+    pub symbol: String,
+    /// CUSIP.
+    pub cusip: Option<String>,
+    /// New rate.
+    pub new_rate: f64,
+    /// Old rate.
+    pub old_rate: f64,
+    /// Process date.
+    pub process_date: NaiveDate,
+    /// Ex-date.
+    pub ex_date: Option<NaiveDate>,
+    /// Record date.
+    pub record_date: Option<NaiveDate>,
+    /// Payable date.
+    pub payable_date: Option<NaiveDate>,
+    /// Due bill redemption date.
+    pub due_bill_redemption_date: Option<NaiveDate>,
+}
+
+impl ForwardSplit {
+    /// Shares received per share held.
+    #[must_use]
+    pub fn ratio(&self) -> f64 {
+        self.new_rate / self.old_rate
+    }
+}
+
+/// Reverse split from the `/v1beta1/corporate-actions` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReverseSplit {
+    /// Unique identifier.
+    pub id: String,
+    /// Symbol.
+    pub symbol: String,
+    /// CUSIP before the split.
+    pub old_cusip: Option<String>,
+    /// CUSIP after the split.
+    pub new_cusip: Option<String>,
+    /// New rate.
+    pub new_rate: f64,
+    /// Old rate.
+    pub old_rate: f64,
+    /// Process date.
+    pub process_date: NaiveDate,
+    /// Ex-date.
+    pub ex_date: Option<NaiveDate>,
+    /// Record date.
+    pub record_date: Option<NaiveDate>,
+    /// Payable date.
+    pub payable_date: Option<NaiveDate>,
+}
+
+impl ReverseSplit {
+    /// Shares received per share held.
+    #[must_use]
+    pub fn ratio(&self) -> f64 {
+        self.new_rate / self.old_rate
+    }
+}
+
+/// Cash dividend from the `/v1beta1/corporate-actions` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CashDividend {
+    /// Unique identifier.
+    pub id: String,
+    /// Symbol.
+    pub symbol: String,
+    /// CUSIP.
+    pub cusip: Option<String>,
+    /// Cash amount per share.
+    pub rate: f64,
+    /// Whether the dividend is special.
+    #[serde(default)]
+    pub special: bool,
+    /// Whether the dividend is paid by a foreign issuer.
+    #[serde(default)]
+    pub foreign: bool,
+    /// Process date.
+    pub process_date: NaiveDate,
+    /// Ex-date.
+    pub ex_date: Option<NaiveDate>,
+    /// Record date.
+    pub record_date: Option<NaiveDate>,
+    /// Payable date.
+    pub payable_date: Option<NaiveDate>,
+    /// Due bill on date.
+    pub due_bill_on_date: Option<NaiveDate>,
+    /// Due bill off date.
+    pub due_bill_off_date: Option<NaiveDate>,
+}
+
+/// Stock dividend from the `/v1beta1/corporate-actions` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StockDividend {
+    /// Unique identifier.
+    pub id: String,
+    /// Symbol.
+    pub symbol: String,
+    /// CUSIP.
+    pub cusip: Option<String>,
+    /// Shares distributed per share held.
+    pub rate: f64,
+    /// Process date.
+    pub process_date: NaiveDate,
+    /// Ex-date.
+    pub ex_date: Option<NaiveDate>,
+    /// Record date.
+    pub record_date: Option<NaiveDate>,
+    /// Payable date.
+    pub payable_date: Option<NaiveDate>,
+}
+
+/// Spin-off from the `/v1beta1/corporate-actions` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SpinOff {
+    /// Unique identifier.
+    pub id: String,
+    /// Symbol of the parent company.
+    pub source_symbol: String,
+    /// CUSIP of the parent company.
+    pub source_cusip: Option<String>,
+    /// Rate of the parent company.
+    pub source_rate: f64,
+    /// Symbol of the spun-off company.
+    pub new_symbol: String,
+    /// CUSIP of the spun-off company.
+    pub new_cusip: Option<String>,
+    /// Rate of the spun-off company.
+    pub new_rate: f64,
+    /// Process date.
+    pub process_date: NaiveDate,
+    /// Ex-date.
+    pub ex_date: Option<NaiveDate>,
+    /// Record date.
+    pub record_date: Option<NaiveDate>,
+    /// Payable date.
+    pub payable_date: Option<NaiveDate>,
+    /// Due bill redemption date.
+    pub due_bill_redemption_date: Option<NaiveDate>,
+}
+
+/// Merger from the `/v1beta1/corporate-actions` endpoint.
+///
+/// The same shape covers stock, cash, and stock and cash mergers; the rates
+/// that do not apply to a kind are `None`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Merger {
+    /// Unique identifier.
+    pub id: String,
+    /// Symbol of the acquiring company.
+    pub acquirer_symbol: Option<String>,
+    /// CUSIP of the acquiring company.
+    pub acquirer_cusip: Option<String>,
+    /// Acquirer shares received (stock mergers).
+    pub acquirer_rate: Option<f64>,
+    /// Symbol of the acquired company.
+    pub acquiree_symbol: String,
+    /// CUSIP of the acquired company.
+    pub acquiree_cusip: Option<String>,
+    /// Acquiree shares given up (stock mergers).
+    pub acquiree_rate: Option<f64>,
+    /// Cash per acquiree share (cash mergers).
+    pub rate: Option<f64>,
+    /// Cash per acquiree share (stock and cash mergers).
+    pub cash_rate: Option<f64>,
+    /// Process date.
+    pub process_date: NaiveDate,
+    /// Effective date.
+    pub effective_date: Option<NaiveDate>,
+    /// Payable date.
+    pub payable_date: Option<NaiveDate>,
+}
+
+/// Name change from the `/v1beta1/corporate-actions` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NameChange {
+    /// Unique identifier.
+    pub id: String,
+    /// Symbol before the change.
+    pub old_symbol: String,
+    /// CUSIP before the change.
+    pub old_cusip: Option<String>,
+    /// Symbol after the change.
+    pub new_symbol: String,
+    /// CUSIP after the change.
+    pub new_cusip: Option<String>,
+    /// Process date.
+    pub process_date: NaiveDate,
+}
+
+/// Corporate actions grouped by type, as returned by the
+/// `/v1beta1/corporate-actions` endpoint. Types without actions are empty.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct CorporateActionsV1 {
+    /// Forward splits.
+    pub forward_splits: Vec<ForwardSplit>,
+    /// Reverse splits.
+    pub reverse_splits: Vec<ReverseSplit>,
+    /// Cash dividends.
+    pub cash_dividends: Vec<CashDividend>,
+    /// Stock dividends.
+    pub stock_dividends: Vec<StockDividend>,
+    /// Spin-offs.
+    pub spin_offs: Vec<SpinOff>,
+    /// Stock mergers.
+    pub stock_mergers: Vec<Merger>,
+    /// Cash mergers.
+    pub cash_mergers: Vec<Merger>,
+    /// Stock and cash mergers.
+    pub stock_and_cash_mergers: Vec<Merger>,
+    /// Name changes.
+    pub name_changes: Vec<NameChange>,
+}
+
+impl CorporateActionsV1 {
+    /// All mergers, regardless of kind.
+    pub fn mergers(&self) -> impl Iterator<Item = &Merger> {
+        self.stock_mergers
+            .iter()
+            .chain(&self.cash_mergers)
+            .chain(&self.stock_and_cash_mergers)
+    }
+
+    /// Total number of actions.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.forward_splits.len()
+            + self.reverse_splits.len()
+            + self.cash_dividends.len()
+            + self.stock_dividends.len()
+            + self.spin_offs.len()
+            + self.mergers().count()
+            + self.name_changes.len()
+    }
+
+    /// Whether there are no actions.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append the actions of another page.
+    pub fn extend(&mut self, other: Self) {
+        self.forward_splits.extend(other.forward_splits);
+        self.reverse_splits.extend(other.reverse_splits);
+        self.cash_dividends.extend(other.cash_dividends);
+        self.stock_dividends.extend(other.stock_dividends);
+        self.spin_offs.extend(other.spin_offs);
+        self.stock_mergers.extend(other.stock_mergers);
+        self.cash_mergers.extend(other.cash_mergers);
+        self.stock_and_cash_mergers
+            .extend(other.stock_and_cash_mergers);
+        self.name_changes.extend(other.name_changes);
+    }
+}
+
 /// Limit Up Limit Down (LULD) data.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Luld {
@@ -1396,6 +1749,12 @@ pub struct CorporateActionsParams {
     /// Pagination token.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page_token: Option<String>,
+    /// Filter by CUSIPs (`/v1beta1/corporate-actions` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cusips: Option<String>,
+    /// Sort by process date (`/v1beta1/corporate-actions` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortDirection>,
 }
 
 impl CorporateActionsParams {
@@ -1433,6 +1792,27 @@ impl CorporateActionsParams {
         self.limit = Some(limit);
         self
     }
+
+    /// Filter by CUSIPs.
+    #[must_use]
+    pub fn cusips(mut self, cusips: &str) -> Self {
+        self.cusips = Some(cusips.to_string());
+        self
+    }
+
+    /// Set sort direction.
+    #[must_use]
+    pub fn sort(mut self, sort: SortDirection) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Set pagination token.
+    #[must_use]
+    pub fn page_token(mut self, page_token: &str) -> Self {
+        self.page_token = Some(page_token.to_string());
+        self
+    }
 }
 
 // ============================================================================
@@ -1866,6 +2246,336 @@ pub struct Document {
     pub mime_type: String,
 }
 
+/// W-8BEN certificate of foreign status, as uploaded for international
+/// broker accounts.
+///
+/// Serializes to the `content_data` shape expected by the document upload
+/// endpoint. Build it with [`W8BenDocument::new`] and the setters, then call
+/// [`W8BenDocument::validate`] (the HTTP client does this before uploading).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct W8BenDocument {
+    /// Name of the beneficial owner.
+    pub full_name: String,
+    /// Country of citizenship (ISO 3166-1 alpha-3).
+    pub country_citizen: String,
+    /// Date of birth.
+    pub date_of_birth: chrono::NaiveDate,
+    /// Permanent residence street address.
+    pub permanent_address_street: String,
+    /// Permanent residence city or town, state or province.
+    pub permanent_address_city_state: String,
+    /// Permanent residence postal code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permanent_address_postal_code: Option<String>,
+    /// Permanent residence country (ISO 3166-1 alpha-3).
+    pub permanent_address_country: String,
+    /// Mailing street address, if different from the permanent address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailing_address_street: Option<String>,
+    /// Mailing city or town, state or province.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailing_address_city_state: Option<String>,
+    /// Mailing postal code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailing_address_postal_code: Option<String>,
+    /// Mailing country (ISO 3166-1 alpha-3).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailing_address_country: Option<String>,
+    /// U.S. taxpayer identification number, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tax_id_ssn: Option<String>,
+    /// Foreign tax identifying number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreign_tax_id: Option<String>,
+    /// Whether a foreign TIN is not legally required.
+    #[serde(default)]
+    pub ftin_not_required: bool,
+    /// Reference number(s).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_number: Option<String>,
+    /// Country whose tax treaty benefits are claimed (ISO 3166-1 alpha-3).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub treaty_country: Option<String>,
+    /// Treaty article claimed for special rates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub treaty_article: Option<String>,
+    /// Special withholding rate claimed, in percent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub withholding_rate: Option<String>,
+    /// Type of income the special rate applies to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub income_type: Option<String>,
+    /// Name of the person signing.
+    pub signer_full_name: String,
+    /// Date of signature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<chrono::NaiveDate>,
+    /// IP address the form was signed from.
+    pub ip_address: String,
+    /// Time the form was signed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Form revision.
+    pub revision: String,
+}
+
+impl W8BenDocument {
+    /// Form revision used by default.
+    pub const REVISION: &'static str = "10-2021";
+
+    /// Creates a form for the given beneficial owner.
+    #[must_use]
+    pub fn new(full_name: &str, country_citizen: &str, date_of_birth: chrono::NaiveDate) -> Self {
+        Self {
+            full_name: full_name.to_string(),
+            country_citizen: country_citizen.to_string(),
+            date_of_birth,
+            permanent_address_street: String::new(),
+            permanent_address_city_state: String::new(),
+            permanent_address_postal_code: None,
+            permanent_address_country: String::new(),
+            mailing_address_street: None,
+            mailing_address_city_state: None,
+            mailing_address_postal_code: None,
+            mailing_address_country: None,
+            tax_id_ssn: None,
+            foreign_tax_id: None,
+            ftin_not_required: false,
+            reference_number: None,
+            treaty_country: None,
+            treaty_article: None,
+            withholding_rate: None,
+            income_type: None,
+            signer_full_name: String::new(),
+            date: None,
+            ip_address: String::new(),
+            timestamp: None,
+            revision: Self::REVISION.to_string(),
+        }
+    }
+
+    /// Set the permanent residence address.
+    #[must_use]
+    pub fn permanent_address(mut self, street: &str, city_state: &str, country: &str) -> Self {
+        self.permanent_address_street = street.to_string();
+        self.permanent_address_city_state = city_state.to_string();
+        self.permanent_address_country = country.to_string();
+        self
+    }
+
+    /// Set the permanent residence postal code.
+    #[must_use]
+    pub fn postal_code(mut self, postal_code: &str) -> Self {
+        self.permanent_address_postal_code = Some(postal_code.to_string());
+        self
+    }
+
+    /// Set a mailing address different from the permanent address.
+    #[must_use]
+    pub fn mailing_address(mut self, street: &str, city_state: &str, country: &str) -> Self {
+        self.mailing_address_street = Some(street.to_string());
+        self.mailing_address_city_state = Some(city_state.to_string());
+        self.mailing_address_country = Some(country.to_string());
+        self
+    }
+
+    /// Set the U.S. taxpayer identification number.
+    #[must_use]
+    pub fn us_tax_id(mut self, tax_id: &str) -> Self {
+        self.tax_id_ssn = Some(tax_id.to_string());
+        self
+    }
+
+    /// Set the foreign tax identifying number.
+    #[must_use]
+    pub fn foreign_tax_id(mut self, foreign_tax_id: &str) -> Self {
+        self.foreign_tax_id = Some(foreign_tax_id.to_string());
+        self.ftin_not_required = false;
+        self
+    }
+
+    /// Declare that a foreign TIN is not legally required.
+    #[must_use]
+    pub fn ftin_not_required(mut self) -> Self {
+        self.foreign_tax_id = None;
+        self.ftin_not_required = true;
+        self
+    }
+
+    /// Set the reference number.
+    #[must_use]
+    pub fn reference_number(mut self, reference: &str) -> Self {
+        self.reference_number = Some(reference.to_string());
+        self
+    }
+
+    /// Set the treaty country.
+    #[must_use]
+    pub fn treaty_country(mut self, country: &str) -> Self {
+        self.treaty_country = Some(country.to_string());
+        self
+    }
+
+    /// Claim a special rate under a treaty article.
+    #[must_use]
+    pub fn special_rate(
+        mut self,
+        article: &str,
+        withholding_rate: &str,
+        income_type: &str,
+    ) -> Self {
+        self.treaty_article = Some(article.to_string());
+        self.withholding_rate = Some(withholding_rate.to_string());
+        self.income_type = Some(income_type.to_string());
+        self
+    }
+
+    /// Sign the form, stamping the signature time if not already set.
+    #[must_use]
+    pub fn signature(mut self, signer_full_name: &str, date: chrono::NaiveDate) -> Self {
+        self.signer_full_name = signer_full_name.to_string();
+        self.date = Some(date);
+        self.timestamp.get_or_insert_with(Utc::now);
+        self
+    }
+
+    /// Set the IP address the form was signed from.
+    #[must_use]
+    pub fn ip_address(mut self, ip_address: &str) -> Self {
+        self.ip_address = ip_address.to_string();
+        self
+    }
+
+    /// Set the signature time.
+    #[must_use]
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Check that every field the form requires is present.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::ValidationErrors`] listing every missing or
+    /// malformed field.
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let mut errors = Vec::new();
+        let mut required = |field: &str, value: &str| {
+            if value.trim().is_empty() {
+                errors.push(ValidationError::new(field, "is required"));
+            }
+        };
+        required("full_name", &self.full_name);
+        required("permanent_address_street", &self.permanent_address_street);
+        required(
+            "permanent_address_city_state",
+            &self.permanent_address_city_state,
+        );
+        required("signer_full_name", &self.signer_full_name);
+        required("ip_address", &self.ip_address);
+
+        let countries = [
+            ("country_citizen", Some(&self.country_citizen)),
+            (
+                "permanent_address_country",
+                Some(&self.permanent_address_country),
+            ),
+            ("treaty_country", self.treaty_country.as_ref()),
+            (
+                "mailing_address_country",
+                self.mailing_address_country.as_ref(),
+            ),
+        ];
+        for (field, country) in countries {
+            match country {
+                Some(c) if c.len() == 3 && c.chars().all(|ch| ch.is_ascii_uppercase()) => {}
+                Some(_) => errors.push(ValidationError::new(
+                    field,
+                    "must be an ISO 3166-1 alpha-3 country code",
+                )),
+                None if field == "treaty_country" => {
+                    errors.push(ValidationError::new(field, "is required"));
+                }
+                None => {}
+            }
+        }
+        if self.permanent_address_country == "USA" {
+            errors.push(ValidationError::new(
+                "permanent_address_country",
+                "must not be the United States",
+            ));
+        }
+
+        match (&self.foreign_tax_id, self.ftin_not_required) {
+            (Some(tin), false) if tin.trim().is_empty() => {
+                errors.push(ValidationError::new("foreign_tax_id", "is required"));
+            }
+            (Some(_), true) => errors.push(ValidationError::new(
+                "foreign_tax_id",
+                "must be empty when ftin_not_required is set",
+            )),
+            (None, false) => errors.push(ValidationError::new(
+                "foreign_tax_id",
+                "is required unless ftin_not_required is set",
+            )),
+            _ => {}
+        }
+
+        match self.date {
+            None => errors.push(ValidationError::new("date", "is required")),
+            Some(date) if date < self.date_of_birth => {
+                errors.push(ValidationError::new("date", "is before date_of_birth"));
+            }
+            Some(_) => {}
+        }
+        if self.timestamp.is_none() {
+            errors.push(ValidationError::new("timestamp", "is required"));
+        }
+
+        let special = [
+            &self.treaty_article,
+            &self.withholding_rate,
+            &self.income_type,
+        ];
+        if special.iter().any(|f| f.is_some()) && special.iter().any(|f| f.is_none()) {
+            errors.push(ValidationError::new(
+                "treaty_article",
+                "treaty_article, withholding_rate and income_type must be given together",
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AlpacaError::ValidationErrors(errors))
+        }
+    }
+}
+
+/// Document upload carrying a W-8BEN form as structured data.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct W8BenUpload {
+    /// Always [`DocumentType::W8ben`].
+    pub document_type: DocumentType,
+    /// Always `"Form W-8BEN"`.
+    pub document_sub_type: String,
+    /// The form fields.
+    pub content_data: W8BenDocument,
+    /// Always `"application/json"`.
+    pub mime_type: String,
+}
+
+impl From<W8BenDocument> for W8BenUpload {
+    fn from(content_data: W8BenDocument) -> Self {
+        Self {
+            document_type: DocumentType::W8ben,
+            document_sub_type: "Form W-8BEN".to_string(),
+            content_data,
+            mime_type: "application/json".to_string(),
+        }
+    }
+}
+
 /// Broker account.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BrokerAccount {
@@ -1897,6 +2607,254 @@ pub struct BrokerAccount {
     /// Trusted contact.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trusted_contact: Option<TrustedContact>,
+    /// Latest KYC results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kyc_results: Option<KycResult>,
+}
+
+/// Overall outcome of a KYC review.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KycSummary {
+    /// Every check passed.
+    Pass,
+    /// At least one check failed or needs review.
+    Fail,
+}
+
+/// Details of one KYC check, keyed by its reason code (e.g.
+/// `IDENTITY_VERIFICATION`) in [`KycResult`].
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct KycCheck {
+    /// Explanation shown to the applicant, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Internal note from the reviewer, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// KYC review results of a broker account.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct KycResult {
+    /// Checks that passed.
+    #[serde(default)]
+    pub accept: std::collections::BTreeMap<String, KycCheck>,
+    /// Checks that failed.
+    #[serde(default)]
+    pub reject: std::collections::BTreeMap<String, KycCheck>,
+    /// Checks that could not be decided and need review or more
+    /// information.
+    #[serde(default)]
+    pub indeterminate: std::collections::BTreeMap<String, KycCheck>,
+    /// Information requested from the applicant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub additional_information: Option<String>,
+    /// Overall outcome.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<KycSummary>,
+}
+
+impl KycResult {
+    /// Whether the review passed with nothing rejected or undecided.
+    #[must_use]
+    pub fn is_approved(&self) -> bool {
+        self.summary != Some(KycSummary::Fail)
+            && self.reject.is_empty()
+            && self.indeterminate.is_empty()
+    }
+
+    /// Whether the applicant was asked for more information.
+    #[must_use]
+    pub fn needs_additional_information(&self) -> bool {
+        self.additional_information
+            .as_deref()
+            .is_some_and(|info| !info.trim().is_empty())
+    }
+
+    /// Reason codes blocking the account: rejected checks, then
+    /// undecided ones.
+    #[must_use]
+    pub fn blocking_reasons(&self) -> Vec<&str> {
+        self.reject
+            .keys()
+            .chain(self.indeterminate.keys())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// A change of a broker account's status.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountStatusChange {
+    /// Account ID.
+    pub account_id: String,
+    /// Status before the change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_from: Option<BrokerAccountStatus>,
+    /// Status after the change.
+    pub status_to: BrokerAccountStatus,
+    /// Reason given for the change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// KYC results that led to the change, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kyc_results: Option<KycResult>,
+    /// Time of the change.
+    pub at: DateTime<Utc>,
+}
+
+/// Trading account of a broker sub-account.
+///
+/// Returned by `GET /v1/trading/accounts/{account_id}/account`. Unlike the
+/// retail [`Account`], it carries the withdrawable/transferable cash split,
+/// pending transfers and the previous-close margin snapshot used by
+/// broker back offices.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BrokerTradingAccount {
+    /// Account ID.
+    pub id: String,
+    /// Account number.
+    pub account_number: String,
+    /// Account status.
+    pub status: BrokerAccountStatus,
+    /// Crypto status.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crypto_status: Option<BrokerAccountStatus>,
+    /// Currency.
+    pub currency: String,
+    /// Current buying power.
+    pub buying_power: String,
+    /// Regulation T buying power.
+    pub regt_buying_power: String,
+    /// Day trading buying power.
+    pub daytrading_buying_power: String,
+    /// Effective buying power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_buying_power: Option<String>,
+    /// Non-marginable buying power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_marginable_buying_power: Option<String>,
+    /// Beginning-of-day day trading buying power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bod_dtbp: Option<String>,
+    /// Cash balance.
+    pub cash: String,
+    /// Cash that can be withdrawn now.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cash_withdrawable: Option<String>,
+    /// Cash that can be transferred out now.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cash_transferable: Option<String>,
+    /// Fees accrued but not yet charged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accrued_fees: Option<String>,
+    /// Pending outgoing transfers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_transfer_out: Option<String>,
+    /// Pending incoming transfers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_transfer_in: Option<String>,
+    /// Total portfolio value.
+    pub portfolio_value: String,
+    /// Whether the account is flagged as pattern day trader.
+    pub pattern_day_trader: bool,
+    /// Whether trading is blocked.
+    pub trading_blocked: bool,
+    /// Whether transfers are blocked.
+    pub transfers_blocked: bool,
+    /// Whether the account is blocked.
+    pub account_blocked: bool,
+    /// Created at timestamp.
+    pub created_at: DateTime<Utc>,
+    /// Whether trading is suspended by the user.
+    pub trade_suspended_by_user: bool,
+    /// Margin multiplier.
+    pub multiplier: String,
+    /// Whether shorting is enabled.
+    pub shorting_enabled: bool,
+    /// Current equity.
+    pub equity: String,
+    /// Previous close equity.
+    pub last_equity: String,
+    /// Long positions market value.
+    pub long_market_value: String,
+    /// Short positions market value.
+    pub short_market_value: String,
+    /// Total positions market value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_market_value: Option<String>,
+    /// Initial margin requirement.
+    pub initial_margin: String,
+    /// Maintenance margin requirement.
+    pub maintenance_margin: String,
+    /// Previous close maintenance margin.
+    pub last_maintenance_margin: String,
+    /// Special memorandum account value.
+    pub sma: String,
+    /// Number of day trades in the last 5 trading days.
+    pub daytrade_count: i32,
+    /// Date of the previous close snapshot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_close: Option<String>,
+    /// Previous close long market value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_long_market_value: Option<String>,
+    /// Previous close short market value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_short_market_value: Option<String>,
+    /// Previous close cash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_cash: Option<String>,
+    /// Previous close initial margin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_initial_margin: Option<String>,
+    /// Previous close Regulation T buying power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_regt_buying_power: Option<String>,
+    /// Previous close day trading buying power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_daytrading_buying_power: Option<String>,
+    /// Previous close buying power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_buying_power: Option<String>,
+    /// Previous close day trade count.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_daytrade_count: Option<i32>,
+    /// Clearing broker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clearing_broker: Option<String>,
+    /// Date the balances are as of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_asof: Option<String>,
+    /// Intraday balance adjustments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intraday_adjustments: Option<String>,
+    /// Regulatory TAF fees pending.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_reg_taf_fees: Option<String>,
+}
+
+impl BrokerTradingAccount {
+    /// Withdrawable cash as f64, if reported.
+    #[must_use]
+    pub fn withdrawable_cash(&self) -> Option<f64> {
+        self.cash_withdrawable.as_deref()?.parse().ok()
+    }
+
+    /// Transferable cash as f64, if reported.
+    #[must_use]
+    pub fn transferable_cash(&self) -> Option<f64> {
+        self.cash_transferable.as_deref()?.parse().ok()
+    }
+
+    /// Equity in excess of the maintenance margin requirement.
+    #[must_use]
+    pub fn maintenance_excess(&self) -> Option<f64> {
+        let equity: f64 = self.equity.parse().ok()?;
+        let maintenance: f64 = self.maintenance_margin.parse().ok()?;
+        Some(equity - maintenance)
+    }
 }
 
 /// Request to create a broker account.
@@ -1961,6 +2919,87 @@ impl CreateBrokerAccountRequest {
         self.enabled_assets = Some(assets);
         self
     }
+
+    /// The applicant's country (ISO 3166-1 alpha-3): the country of tax
+    /// residence, or the contact address country if it is not set.
+    #[must_use]
+    pub fn applicant_country(&self) -> &str {
+        self.identity
+            .country_of_tax_residence
+            .as_deref()
+            .unwrap_or(&self.contact.country)
+    }
+}
+
+/// Onboarding requirements for applicants from one country.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CountryInfo {
+    /// Country (ISO 3166-1 alpha-3).
+    pub country: String,
+    /// Country name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Tax ID types accepted from applicants; empty if none is required.
+    #[serde(default)]
+    pub tax_id_types: Vec<TaxIdType>,
+    /// Documents applicants must upload.
+    #[serde(default)]
+    pub required_documents: Vec<DocumentType>,
+}
+
+impl CountryInfo {
+    /// Check `request` against this country's requirements.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::ValidationErrors`] listing every unmet
+    /// requirement.
+    pub fn validate_request(
+        &self,
+        request: &CreateBrokerAccountRequest,
+    ) -> crate::error::Result<()> {
+        let mut errors = Vec::new();
+        if request.applicant_country() != self.country {
+            errors.push(ValidationError::new(
+                "identity.country_of_tax_residence",
+                format!("must be {}", self.country),
+            ));
+        }
+
+        if !self.tax_id_types.is_empty() {
+            let identity = &request.identity;
+            if identity
+                .tax_id
+                .as_deref()
+                .is_none_or(|id| id.trim().is_empty())
+            {
+                errors.push(ValidationError::new("identity.tax_id", "is required"));
+            }
+            match &identity.tax_id_type {
+                Some(kind) if self.tax_id_types.contains(kind) => {}
+                Some(kind) => errors.push(ValidationError::new(
+                    "identity.tax_id_type",
+                    format!("{kind:?} is not accepted for {}", self.country),
+                )),
+                None => errors.push(ValidationError::new("identity.tax_id_type", "is required")),
+            }
+        }
+
+        let documents = request.documents.as_deref().unwrap_or_default();
+        for required in &self.required_documents {
+            if !documents.iter().any(|d| d.document_type == *required) {
+                errors.push(ValidationError::new(
+                    "documents",
+                    format!("{required:?} is required for {}", self.country),
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AlpacaError::ValidationErrors(errors))
+        }
+    }
 }
 
 /// Request to update a broker account.
@@ -2124,6 +3163,18 @@ pub enum TransferStatus {
     Canceled,
 }
 
+impl TransferStatus {
+    /// Whether the transfer is still moving cash: not complete, returned
+    /// or canceled.
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            Self::Queued | Self::Pending | Self::SentToClearing | Self::Approved
+        )
+    }
+}
+
 /// Journal entry type.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -2138,6 +3189,11 @@ pub enum JournalEntryType {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum JournalStatus {
+    /// Queued.
+    Queued,
+    /// Sent to clearing.
+    #[serde(rename = "sent_to_clearing")]
+    SentToClearing,
     /// Pending.
     Pending,
     /// Executed.
@@ -2146,6 +3202,16 @@ pub enum JournalStatus {
     Canceled,
     /// Rejected.
     Rejected,
+    /// Refused.
+    Refused,
+}
+
+impl JournalStatus {
+    /// Whether the journal did not and will not move anything.
+    #[must_use]
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Self::Canceled | Self::Rejected | Self::Refused)
+    }
 }
 
 /// ACH relationship.
@@ -2393,6 +3459,9 @@ pub struct Journal {
     /// System date.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub system_date: Option<String>,
+    /// Error message (for rejected batch entries).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
 }
 
 /// Request to create a journal entry.
@@ -2462,6 +3531,9 @@ pub struct BatchJournalEntry {
     pub to_account: String,
     /// Amount.
     pub amount: String,
+    /// Description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 /// Request to create batch journal entries.
@@ -2478,18 +3550,91 @@ pub struct CreateBatchJournalRequest {
     pub description: Option<String>,
 }
 
-/// Parameters for listing transfers.
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct ListTransfersParams {
-    /// Filter by direction.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub direction: Option<TransferDirection>,
-    /// Maximum number of results.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub limit: Option<u32>,
-    /// Offset for pagination.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub offset: Option<u32>,
+/// Parameters for listing transfers.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ListTransfersParams {
+    /// Filter by direction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<TransferDirection>,
+    /// Maximum number of results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Offset for pagination.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+}
+
+/// Parameters for listing transfers across all accounts.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ListFirmTransfersParams {
+    /// Filter by status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TransferStatus>,
+    /// Filter by direction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<TransferDirection>,
+    /// Only transfers created after this timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Only transfers created before this timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Maximum number of results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Offset for pagination.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+}
+
+impl ListFirmTransfersParams {
+    /// Create empty parameters.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filter by status.
+    #[must_use]
+    pub fn status(mut self, status: TransferStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Filter by direction.
+    #[must_use]
+    pub fn direction(mut self, direction: TransferDirection) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Only transfers created after `after`.
+    #[must_use]
+    pub fn after(mut self, after: DateTime<Utc>) -> Self {
+        self.after = Some(after.to_rfc3339());
+        self
+    }
+
+    /// Only transfers created before `before`.
+    #[must_use]
+    pub fn before(mut self, before: DateTime<Utc>) -> Self {
+        self.before = Some(before.to_rfc3339());
+        self
+    }
+
+    /// Set maximum number of results.
+    #[must_use]
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set pagination offset.
+    #[must_use]
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
 }
 
 /// Parameters for listing journals.
@@ -2719,6 +3864,59 @@ impl CreateCryptoWhitelistRequest {
     }
 }
 
+/// Query for a crypto transfer fee estimate.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeeEstimateParams {
+    /// Asset symbol.
+    pub asset: String,
+    /// Sending address.
+    pub from: String,
+    /// Receiving address.
+    pub to: String,
+    /// Amount to transfer.
+    pub amount: String,
+}
+
+impl FeeEstimateParams {
+    /// Create a fee estimate query for sending `amount` of `asset`.
+    #[must_use]
+    pub fn new(asset: &str, from: &str, to: &str, amount: &str) -> Self {
+        Self {
+            asset: asset.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: amount.to_string(),
+        }
+    }
+}
+
+/// Estimated network fee for a crypto transfer.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeeEstimate {
+    /// Estimated fee, in units of the asset.
+    pub fee: String,
+    /// Asset the fee is charged in, if reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+}
+
+impl FeeEstimate {
+    /// Estimated fee as a number.
+    #[must_use]
+    pub fn fee_amount(&self) -> Option<f64> {
+        self.fee.parse().ok()
+    }
+}
+
+/// Query selecting a crypto wallet's deposit address on one chain.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DepositAddressParams {
+    /// Asset symbol.
+    pub asset: String,
+    /// Chain to receive the deposit on.
+    pub network: CryptoChain,
+}
+
 /// Crypto snapshot with current price data.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CryptoSnapshot {
@@ -3552,6 +4750,152 @@ pub struct EnhancedAsset {
     /// Asset attributes.
     #[serde(default)]
     pub attributes: Vec<AssetAttribute>,
+    /// CUSIP identifier, for US securities.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cusip: Option<String>,
+    /// Composite FIGI identifier.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub composite_figi: Option<String>,
+}
+
+impl EnhancedAsset {
+    /// Whether `key` identifies this asset.
+    #[must_use]
+    pub fn matches(&self, key: &SymbolOrId) -> bool {
+        match key {
+            SymbolOrId::Symbol(symbol) => self.symbol.eq_ignore_ascii_case(symbol),
+            SymbolOrId::Id(id) => self.id == *id,
+            SymbolOrId::Cusip(cusip) => self
+                .cusip
+                .as_deref()
+                .is_some_and(|c| c.eq_ignore_ascii_case(cusip)),
+            SymbolOrId::CompositeFigi(figi) => self
+                .composite_figi
+                .as_deref()
+                .is_some_and(|f| f.eq_ignore_ascii_case(figi)),
+        }
+    }
+}
+
+/// Key for looking up a single asset.
+///
+/// The asset endpoints accept a symbol, an asset ID, a CUSIP or a
+/// composite FIGI in the same path position. `From<&str>` always yields a
+/// [`SymbolOrId::Symbol`]; use [`SymbolOrId::detect`] to recognise IDs
+/// and identifiers by their format.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SymbolOrId {
+    /// Ticker symbol, e.g. `AAPL`.
+    Symbol(String),
+    /// Alpaca asset ID.
+    Id(Uuid),
+    /// Nine-character CUSIP, e.g. `037833100`.
+    Cusip(String),
+    /// Twelve-character composite FIGI, e.g. `BBG000B9XRY4`.
+    CompositeFigi(String),
+}
+
+impl SymbolOrId {
+    /// Classify `value`: an asset ID, then a composite FIGI or CUSIP with a
+    /// valid check digit, otherwise a symbol.
+    #[must_use]
+    pub fn detect(value: &str) -> Self {
+        let value = value.trim();
+        if let Ok(id) = Uuid::parse_str(value) {
+            Self::Id(id)
+        } else if is_valid_figi(value) {
+            Self::CompositeFigi(value.to_ascii_uppercase())
+        } else if is_valid_cusip(value) {
+            Self::Cusip(value.to_ascii_uppercase())
+        } else {
+            Self::Symbol(value.to_string())
+        }
+    }
+}
+
+impl std::fmt::Display for SymbolOrId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Symbol(value) | Self::Cusip(value) | Self::CompositeFigi(value) => {
+                f.write_str(value)
+            }
+            Self::Id(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+impl From<&str> for SymbolOrId {
+    fn from(symbol: &str) -> Self {
+        Self::Symbol(symbol.to_string())
+    }
+}
+
+impl From<String> for SymbolOrId {
+    fn from(symbol: String) -> Self {
+        Self::Symbol(symbol)
+    }
+}
+
+impl From<Uuid> for SymbolOrId {
+    fn from(id: Uuid) -> Self {
+        Self::Id(id)
+    }
+}
+
+/// Value of a CUSIP/FIGI character in check digit sums: digits are
+/// themselves, letters count from 10.
+fn identifier_char_value(c: u8) -> Option<u32> {
+    match c {
+        b'0'..=b'9' => Some(u32::from(c - b'0')),
+        b'A'..=b'Z' => Some(u32::from(c - b'A') + 10),
+        _ => None,
+    }
+}
+
+/// Luhn-style check used by CUSIP and FIGI: every second character's
+/// value is doubled and the digits of each value summed.
+fn identifier_check_digit(body: &[u8], extra: impl Fn(u8) -> Option<u32>) -> Option<u32> {
+    let mut sum = 0;
+    for (i, &c) in body.iter().enumerate() {
+        let mut value = identifier_char_value(c).or_else(|| extra(c))?;
+        if i % 2 == 1 {
+            value *= 2;
+        }
+        sum += value / 10 + value % 10;
+    }
+    Some((10 - sum % 10) % 10)
+}
+
+/// Whether `value` is a CUSIP with a valid check digit.
+#[must_use]
+pub fn is_valid_cusip(value: &str) -> bool {
+    let bytes = value.to_ascii_uppercase().into_bytes();
+    if bytes.len() != 9 {
+        return false;
+    }
+    let extra = |c| match c {
+        b'*' => Some(36),
+        b'@' => Some(37),
+        b'#' => Some(38),
+        _ => None,
+    };
+    identifier_check_digit(&bytes[..8], extra)
+        == identifier_char_value(bytes[8]).filter(|&d| d < 10)
+}
+
+/// Whether `value` is a FIGI with a valid check digit.
+#[must_use]
+pub fn is_valid_figi(value: &str) -> bool {
+    let bytes = value.to_ascii_uppercase().into_bytes();
+    if bytes.len() != 12 || bytes[2] != b'G' {
+        return false;
+    }
+    let consonants = |c: &u8| c.is_ascii_uppercase() && !b"AEIOU".contains(c);
+    if !bytes[..2].iter().all(consonants) || bytes[3..11].iter().any(|c| b"AEIOU".contains(c)) {
+        return false;
+    }
+    identifier_check_digit(&bytes[..11], |_| None)
+        == identifier_char_value(bytes[11]).filter(|&d| d < 10)
 }
 
 /// Parameters for listing assets.
@@ -3800,6 +5144,81 @@ pub struct NonTradeActivity {
     pub description: Option<String>,
 }
 
+/// An account activity, typed by its `activity_type`: fills carry their
+/// order, side, price and cumulative quantity, everything else (dividends,
+/// fees, journals, option events, ...) is a [`NonTradeActivity`].
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+pub enum Activity {
+    /// A fill (`FILL`).
+    Trade(TradeActivity),
+    /// Any other activity type.
+    NonTrade(NonTradeActivity),
+}
+
+impl<'de> Deserialize<'de> for Activity {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let value = serde_json::Value::deserialize(deserializer)?;
+        if value.get("activity_type").and_then(|t| t.as_str()) == Some("FILL") {
+            TradeActivity::deserialize(value)
+                .map(Self::Trade)
+                .map_err(D::Error::custom)
+        } else {
+            NonTradeActivity::deserialize(value)
+                .map(Self::NonTrade)
+                .map_err(D::Error::custom)
+        }
+    }
+}
+
+impl Activity {
+    /// Activity ID.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Trade(trade) => &trade.id,
+            Self::NonTrade(other) => &other.id,
+        }
+    }
+
+    /// Activity type.
+    #[must_use]
+    pub fn activity_type(&self) -> &ActivityType {
+        match self {
+            Self::Trade(trade) => &trade.activity_type,
+            Self::NonTrade(other) => &other.activity_type,
+        }
+    }
+
+    /// Symbol, if the activity concerns one.
+    #[must_use]
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Self::Trade(trade) => Some(&trade.symbol),
+            Self::NonTrade(other) => other.symbol.as_deref(),
+        }
+    }
+
+    /// The fill, if this is one.
+    #[must_use]
+    pub fn as_trade(&self) -> Option<&TradeActivity> {
+        match self {
+            Self::Trade(trade) => Some(trade),
+            Self::NonTrade(_) => None,
+        }
+    }
+
+    /// The non-trade activity, if this is one.
+    #[must_use]
+    pub fn as_non_trade(&self) -> Option<&NonTradeActivity> {
+        match self {
+            Self::Trade(_) => None,
+            Self::NonTrade(other) => Some(other),
+        }
+    }
+}
+
 /// Parameters for listing account activities.
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ListActivitiesParams {
@@ -4121,28 +5540,361 @@ impl RebalanceRunRequest {
     pub fn new(account_id: &str, portfolio_id: &str) -> Self {
         Self {
             account_id: account_id.to_string(),
-            portfolio_id: portfolio_id.to_string(),
-            run_type: None,
+            portfolio_id: portfolio_id.to_string(),
+            run_type: None,
+        }
+    }
+}
+
+/// Rebalance run result.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RebalanceRun {
+    /// Run ID.
+    pub id: Uuid,
+    /// Account ID.
+    pub account_id: String,
+    /// Portfolio ID.
+    pub portfolio_id: String,
+    /// Status.
+    pub status: RebalanceStatus,
+    /// Created at.
+    pub created_at: DateTime<Utc>,
+    /// Completed at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Parameters for listing rebalance runs.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ListRebalanceRunsParams {
+    /// Filter by account ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    /// Filter by run type.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub run_type: Option<String>,
+    /// Maximum number of results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Offset for pagination.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+}
+
+impl ListRebalanceRunsParams {
+    /// Create empty parameters.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filter by account ID.
+    #[must_use]
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.to_string());
+        self
+    }
+
+    /// Filter by run type.
+    #[must_use]
+    pub fn run_type(mut self, run_type: &str) -> Self {
+        self.run_type = Some(run_type.to_string());
+        self
+    }
+
+    /// Set the maximum number of results.
+    #[must_use]
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set the pagination offset.
+    #[must_use]
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+/// Order a rebalance run decided not to place.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SkippedRebalanceOrder {
+    /// Symbol.
+    pub symbol: String,
+    /// Side.
+    pub side: OrderSide,
+    /// Notional value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notional: Option<String>,
+    /// Currency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Reason code, e.g. `ORDER_LESS_THAN_MIN_NOTIONAL`.
+    pub reason: String,
+    /// Human-readable reason.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason_details: Option<String>,
+}
+
+/// Full details of a rebalance run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RebalanceRunDetails {
+    /// Run ID.
+    pub id: Uuid,
+    /// Run type, e.g. `full_rebalance` or `invest_cash`.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub run_type: Option<String>,
+    /// Amount invested or raised, for cash runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    /// What started the run, e.g. `system` or `api`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initiated_from: Option<String>,
+    /// Status.
+    pub status: RebalanceStatus,
+    /// Reason for the status, for failed or canceled runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Account ID.
+    pub account_id: String,
+    /// Portfolio ID.
+    pub portfolio_id: String,
+    /// Target weights the run rebalanced to.
+    #[serde(default)]
+    pub weights: Vec<TargetAllocation>,
+    /// Orders placed by the run.
+    #[serde(default)]
+    pub orders: Vec<Order>,
+    /// Orders the run skipped.
+    #[serde(default)]
+    pub skipped_orders: Vec<SkippedRebalanceOrder>,
+    /// Created at.
+    pub created_at: DateTime<Utc>,
+    /// Updated at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Completed at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Canceled at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canceled_at: Option<DateTime<Utc>>,
+    /// Failed at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_at: Option<DateTime<Utc>>,
+}
+
+/// What happened at one point of a rebalance run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RebalanceRunEventKind {
+    /// The run was created.
+    Created,
+    /// An order was submitted.
+    OrderSubmitted {
+        /// Order ID.
+        order_id: Uuid,
+        /// Symbol.
+        symbol: String,
+        /// Side.
+        side: OrderSide,
+    },
+    /// An order filled.
+    OrderFilled {
+        /// Order ID.
+        order_id: Uuid,
+        /// Symbol.
+        symbol: String,
+        /// Filled quantity.
+        filled_qty: String,
+    },
+    /// An order was skipped.
+    OrderSkipped(SkippedRebalanceOrder),
+    /// The run completed.
+    Completed,
+    /// The run was canceled.
+    Canceled,
+    /// The run failed.
+    Failed {
+        /// Reason, if given.
+        reason: Option<String>,
+    },
+}
+
+/// One entry of a rebalance run's audit trail.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RebalanceRunEvent {
+    /// When it happened; skipped orders carry the run's creation time.
+    pub at: DateTime<Utc>,
+    /// What happened.
+    pub kind: RebalanceRunEventKind,
+}
+
+impl RebalanceRunDetails {
+    /// The run's events in chronological order.
+    #[must_use]
+    pub fn audit_trail(&self) -> Vec<RebalanceRunEvent> {
+        let event = |at, kind| RebalanceRunEvent { at, kind };
+        let mut events = vec![event(self.created_at, RebalanceRunEventKind::Created)];
+        events.extend(self.skipped_orders.iter().map(|skipped| {
+            event(
+                self.created_at,
+                RebalanceRunEventKind::OrderSkipped(skipped.clone()),
+            )
+        }));
+        for order in &self.orders {
+            events.push(event(
+                order.submitted_at.unwrap_or(order.created_at),
+                RebalanceRunEventKind::OrderSubmitted {
+                    order_id: order.id,
+                    symbol: order.symbol.clone(),
+                    side: order.side.clone(),
+                },
+            ));
+            if let Some(filled_at) = order.filled_at {
+                events.push(event(
+                    filled_at,
+                    RebalanceRunEventKind::OrderFilled {
+                        order_id: order.id,
+                        symbol: order.symbol.clone(),
+                        filled_qty: order.filled_qty.clone(),
+                    },
+                ));
+            }
+        }
+        if let Some(at) = self.completed_at {
+            events.push(event(at, RebalanceRunEventKind::Completed));
+        }
+        if let Some(at) = self.canceled_at {
+            events.push(event(at, RebalanceRunEventKind::Canceled));
+        }
+        if let Some(at) = self.failed_at {
+            events.push(event(
+                at,
+                RebalanceRunEventKind::Failed {
+                    reason: self.reason.clone(),
+                },
+            ));
+        }
+        // Stable, so same-time events keep their logical order.
+        events.sort_by_key(|event| event.at);
+        events
+    }
+}
+
+/// Weights an account was rebalanced to by one completed run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountAllocation {
+    /// Account ID.
+    pub account_id: String,
+    /// Portfolio ID.
+    pub portfolio_id: String,
+    /// Run that applied the allocation.
+    pub run_id: Uuid,
+    /// When the run completed.
+    pub as_of: DateTime<Utc>,
+    /// Target weights.
+    pub weights: Vec<TargetAllocation>,
+}
+
+/// Drift of one symbol from its target weight.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AllocationDriftEntry {
+    /// Symbol.
+    pub symbol: String,
+    /// Target weight, as a fraction of equity.
+    pub target_weight: f64,
+    /// Actual weight, as a fraction of equity.
+    pub actual_weight: f64,
+}
+
+impl AllocationDriftEntry {
+    /// Actual minus target weight.
+    #[must_use]
+    pub fn drift(&self) -> f64 {
+        self.actual_weight - self.target_weight
+    }
+}
+
+/// Drift of an account's holdings from its portfolio's target weights.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DriftReport {
+    /// Account ID.
+    pub account_id: String,
+    /// Portfolio ID.
+    pub portfolio_id: Uuid,
+    /// Account equity the weights are relative to.
+    pub equity: f64,
+    /// Per-symbol drift, targets first, then untargeted holdings.
+    pub entries: Vec<AllocationDriftEntry>,
+}
+
+impl DriftReport {
+    /// Compare `positions` with `portfolio`'s weights at `equity`.
+    ///
+    /// Notional weights are converted using `equity`; holdings without a
+    /// target have a target weight of zero.
+    #[must_use]
+    pub fn compute(
+        account_id: &str,
+        portfolio: &RebalancePortfolio,
+        positions: &[Position],
+        equity: f64,
+    ) -> Self {
+        let weight_of = |value: f64| if equity > 0.0 { value / equity } else { 0.0 };
+        let actual = |symbol: &str| {
+            positions
+                .iter()
+                .filter(|position| position.symbol == symbol)
+                .filter_map(|position| position.market_value.parse::<f64>().ok())
+                .map(weight_of)
+                .sum::<f64>()
+        };
+        let mut entries: Vec<AllocationDriftEntry> = portfolio
+            .weights
+            .iter()
+            .map(|target| AllocationDriftEntry {
+                symbol: target.symbol.clone(),
+                target_weight: match (target.percent, &target.notional) {
+                    (Some(percent), _) => percent / 100.0,
+                    (None, Some(notional)) => notional.parse::<f64>().map_or(0.0, weight_of),
+                    (None, None) => 0.0,
+                },
+                actual_weight: actual(&target.symbol),
+            })
+            .collect();
+        for position in positions {
+            if !entries.iter().any(|entry| entry.symbol == position.symbol) {
+                entries.push(AllocationDriftEntry {
+                    symbol: position.symbol.clone(),
+                    target_weight: 0.0,
+                    actual_weight: actual(&position.symbol),
+                });
+            }
+        }
+        Self {
+            account_id: account_id.to_string(),
+            portfolio_id: portfolio.id,
+            equity,
+            entries,
         }
     }
-}
 
-/// Rebalance run result.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct RebalanceRun {
-    /// Run ID.
-    pub id: Uuid,
-    /// Account ID.
-    pub account_id: String,
-    /// Portfolio ID.
-    pub portfolio_id: String,
-    /// Status.
-    pub status: RebalanceStatus,
-    /// Created at.
-    pub created_at: DateTime<Utc>,
-    /// Completed at.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub completed_at: Option<DateTime<Utc>>,
+    /// Largest absolute drift.
+    #[must_use]
+    pub fn max_drift(&self) -> f64 {
+        self.entries
+            .iter()
+            .map(|entry| entry.drift().abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Entries drifting by more than `band` (a fraction of equity).
+    pub fn exceeding(&self, band: f64) -> impl Iterator<Item = &AllocationDriftEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.drift().abs() > band)
+    }
 }
 
 /// Close position request.
@@ -4738,6 +6490,33 @@ impl TradingEnvironment {
             Self::Live
         }
     }
+
+    /// The environment an API key is known to belong to: `PK` keys are
+    /// paper keys and `AK` keys live keys. `None` for other keys, e.g.
+    /// Broker API keys.
+    #[must_use]
+    pub fn known_from_api_key(api_key: &str) -> Option<Self> {
+        if api_key.starts_with("PK") {
+            Some(Self::Paper)
+        } else if api_key.starts_with("AK") {
+            Some(Self::Live)
+        } else {
+            None
+        }
+    }
+
+    /// The environment a trading API base URL points at. `None` for hosts
+    /// other than the Alpaca trading API hosts.
+    #[must_use]
+    pub fn from_base_url(url: &str) -> Option<Self> {
+        let host = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let host = host.split(['/', ':']).next().unwrap_or_default();
+        match host.to_ascii_lowercase().as_str() {
+            "api.alpaca.markets" => Some(Self::Live),
+            "paper-api.alpaca.markets" => Some(Self::Paper),
+            _ => None,
+        }
+    }
 }
 
 /// Paper trading account reset request.
@@ -4764,12 +6543,26 @@ impl ResetAccountRequest {
 }
 
 /// Environment safety guard.
+///
+/// Installed on a client, it refuses orders in live mode unless live
+/// trading is allowed, and can require a [`LiveConfirmation`] for live
+/// orders above a notional threshold.
 #[derive(Debug, Clone)]
 pub struct EnvironmentGuard {
     /// Current environment.
     environment: TradingEnvironment,
     /// Whether live trading is allowed.
     allow_live: bool,
+    /// Live orders above this notional need a confirmation.
+    confirmation_threshold: Option<f64>,
+}
+
+/// Explicit acknowledgement that an order goes to the live market, from
+/// [`EnvironmentGuard::confirm_live`].
+#[derive(Debug)]
+#[must_use]
+pub struct LiveConfirmation {
+    _private: (),
 }
 
 impl EnvironmentGuard {
@@ -4779,6 +6572,7 @@ impl EnvironmentGuard {
         Self {
             environment: TradingEnvironment::Paper,
             allow_live: false,
+            confirmation_threshold: None,
         }
     }
 
@@ -4788,9 +6582,25 @@ impl EnvironmentGuard {
         Self {
             environment,
             allow_live: true,
+            confirmation_threshold: None,
         }
     }
 
+    /// Require a [`LiveConfirmation`] for live orders with a notional above
+    /// `notional`.
+    #[must_use]
+    pub fn require_confirmation_above(mut self, notional: f64) -> Self {
+        self.confirmation_threshold = Some(notional);
+        self
+    }
+
+    /// Guard `environment` instead.
+    #[must_use]
+    pub fn with_environment(mut self, environment: TradingEnvironment) -> Self {
+        self.environment = environment;
+        self
+    }
+
     /// Check if current operation is allowed.
     #[must_use]
     pub fn is_allowed(&self) -> bool {
@@ -4802,6 +6612,69 @@ impl EnvironmentGuard {
     pub fn environment(&self) -> TradingEnvironment {
         self.environment
     }
+
+    /// Notional above which live orders need a confirmation.
+    #[must_use]
+    pub fn confirmation_threshold(&self) -> Option<f64> {
+        self.confirmation_threshold
+    }
+
+    /// Whether a live order of `notional` needs a [`LiveConfirmation`].
+    #[must_use]
+    pub fn needs_confirmation(&self, notional: f64) -> bool {
+        self.environment.is_live()
+            && self
+                .confirmation_threshold
+                .is_some_and(|threshold| notional > threshold)
+    }
+
+    /// Acknowledge that the next order goes to the live market.
+    pub fn confirm_live(&self) -> LiveConfirmation {
+        LiveConfirmation { _private: () }
+    }
+
+    /// Check an order of `notional`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if the environment is live and
+    /// live trading is not allowed, or the order needs a confirmation and
+    /// `confirmation` is `None`.
+    pub fn check_order(
+        &self,
+        notional: f64,
+        confirmation: Option<&LiveConfirmation>,
+    ) -> crate::error::Result<()> {
+        if !self.is_allowed() {
+            return Err(AlpacaError::Validation(
+                "live trading is not allowed by the environment guard".to_string(),
+            ));
+        }
+        if confirmation.is_none() && self.needs_confirmation(notional) {
+            return Err(AlpacaError::Validation(format!(
+                "live order notional {notional:.2} is above {:.2}; confirm it with confirm_live()",
+                self.confirmation_threshold.unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that `api_key` and `base_url` belong to the same environment,
+    /// when both are recognisable.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Config`] for a paper key with the live API
+    /// or a live key with the paper API.
+    pub fn check_credentials(api_key: &str, base_url: &str) -> crate::error::Result<()> {
+        match (
+            TradingEnvironment::known_from_api_key(api_key),
+            TradingEnvironment::from_base_url(base_url),
+        ) {
+            (Some(key), Some(url)) if key != url => Err(AlpacaError::Config(format!(
+                "{key:?} API key used with the {url:?} API at {base_url}"
+            ))),
+            _ => Ok(()),
+        }
+    }
 }
 
 // ============================================================================
@@ -5159,6 +7032,7 @@ pub struct AccountDocument {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub account_id: Option<String>,
     /// Document type.
+    #[serde(rename = "type", alias = "document_type")]
     pub document_type: StatementType,
     /// Document date.
     pub date: String,
@@ -5212,7 +7086,7 @@ pub struct DocumentParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<String>,
     /// Document type filter.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub document_type: Option<StatementType>,
 }
 
@@ -5250,7 +7124,7 @@ impl DocumentParams {
 // ============================================================================
 
 /// Supported currencies for Local Currency Trading.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 #[derive(Default)]
 pub enum Currency {
@@ -5327,8 +7201,25 @@ impl ExchangeRate {
     }
 }
 
+impl Currency {
+    /// Parse an ISO 4217 code such as `"EUR"`, ignoring case.
+    #[must_use]
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.to_ascii_uppercase().as_str() {
+            "USD" => Some(Self::Usd),
+            "EUR" => Some(Self::Eur),
+            "GBP" => Some(Self::Gbp),
+            "CAD" => Some(Self::Cad),
+            "AUD" => Some(Self::Aud),
+            "JPY" => Some(Self::Jpy),
+            "CHF" => Some(Self::Chf),
+            _ => None,
+        }
+    }
+}
+
 /// Currency pair for exchange rate queries.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CurrencyPair {
     /// Base currency.
     pub base: Currency,
@@ -5348,6 +7239,146 @@ impl CurrencyPair {
     pub fn as_string(&self) -> String {
         format!("{}/{}", self.base, self.quote)
     }
+
+    /// Get the forex data API symbol (e.g., "EURUSD").
+    #[must_use]
+    pub fn symbol(&self) -> String {
+        format!("{}{}", self.base, self.quote)
+    }
+
+    /// Parse a pair from "EURUSD" or "EUR/USD".
+    #[must_use]
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        let symbol = symbol.replace('/', "");
+        if symbol.len() != 6 || !symbol.is_ascii() {
+            return None;
+        }
+        Some(Self::new(
+            Currency::from_code(&symbol[..3])?,
+            Currency::from_code(&symbol[3..])?,
+        ))
+    }
+
+    /// The pair with base and quote swapped.
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        Self::new(self.quote, self.base)
+    }
+}
+
+/// Forex rate from the `/v1beta1/forex` endpoints: units of the quote
+/// currency per unit of the base currency.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FxRate {
+    /// Timestamp.
+    #[serde(rename = "t")]
+    pub timestamp: DateTime<Utc>,
+    /// Bid price.
+    #[serde(rename = "bp")]
+    pub bid_price: f64,
+    /// Mid price.
+    #[serde(rename = "mp")]
+    pub mid_price: f64,
+    /// Ask price.
+    #[serde(rename = "ap")]
+    pub ask_price: f64,
+}
+
+impl FxRate {
+    /// Convert amount from base to quote currency at the mid price.
+    #[must_use]
+    pub fn convert(&self, amount: f64) -> f64 {
+        amount * self.mid_price
+    }
+
+    /// Convert amount from quote to base currency at the mid price.
+    #[must_use]
+    pub fn convert_inverse(&self, amount: f64) -> f64 {
+        if self.mid_price != 0.0 {
+            amount / self.mid_price
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Parameters for historical forex rates.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FxRatesParams {
+    /// Comma-separated currency pairs (e.g., "EURUSD,USDJPY").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency_pairs: Option<String>,
+    /// Timeframe (e.g., "5Sec", "1Min", "1Day").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeframe: Option<String>,
+    /// Start time (RFC3339).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    /// End time (RFC3339).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// Maximum number of rates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Sort by timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortDirection>,
+    /// Pagination token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+impl FxRatesParams {
+    /// Create parameters for `pairs`.
+    #[must_use]
+    pub fn new(pairs: &[CurrencyPair]) -> Self {
+        Self {
+            currency_pairs: Some(
+                pairs
+                    .iter()
+                    .map(CurrencyPair::symbol)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            ..Self::default()
+        }
+    }
+
+    /// Set timeframe.
+    #[must_use]
+    pub fn timeframe(mut self, timeframe: &str) -> Self {
+        self.timeframe = Some(timeframe.to_string());
+        self
+    }
+
+    /// Set time range.
+    #[must_use]
+    pub fn time_range(mut self, start: &str, end: &str) -> Self {
+        self.start = Some(start.to_string());
+        self.end = Some(end.to_string());
+        self
+    }
+
+    /// Set limit.
+    #[must_use]
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set sort direction.
+    #[must_use]
+    pub fn sort(mut self, sort: SortDirection) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Set pagination token.
+    #[must_use]
+    pub fn page_token(mut self, page_token: &str) -> Self {
+        self.page_token = Some(page_token.to_string());
+        self
+    }
 }
 
 /// Local currency position values.
@@ -5706,6 +7737,21 @@ mod tests {
         assert_eq!(params.limit, Some(100));
     }
 
+    #[test]
+    fn test_option_ticks_params_query() {
+        let params = OptionTicksParams::new(&["AAPL240315C00150000", "AAPL240315P00150000"])
+            .limit(500)
+            .sort(SortDirection::Desc);
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            serde_json::json!({
+                "symbols": "AAPL240315C00150000,AAPL240315P00150000",
+                "limit": 500,
+                "sort": "desc"
+            })
+        );
+    }
+
     #[test]
     fn test_data_feed_serialization() {
         let feed = DataFeed::Sip;
@@ -5727,6 +7773,10 @@ mod tests {
         let feed = DataFeed::Overnight;
         let json = serde_json::to_string(&feed).unwrap();
         assert_eq!(json, "\"overnight\"");
+
+        assert!(DataFeed::DelayedSip.is_delayed());
+        assert!(!DataFeed::Sip.is_delayed());
+        assert_eq!(DataFeed::DelayedSip.to_string(), "delayed_sip");
     }
 
     #[test]
@@ -5768,6 +7818,52 @@ mod tests {
         assert_eq!(params.limit, Some(50));
     }
 
+    #[test]
+    fn test_corporate_actions_v1_deserialization() {
+        let json = r#"{
+            "forward_splits": [{"id": "fs1", "symbol": "NVDA", "cusip": "67066G104",
+                "new_rate": 10, "old_rate": 1, "process_date": "2024-06-10",
+                "ex_date": "2024-06-10", "record_date": "2024-06-06", "payable_date": "2024-06-07"}],
+            "cash_dividends": [{"id": "cd1", "symbol": "AAPL", "cusip": "037833100",
+                "rate": 0.25, "special": false, "foreign": false, "process_date": "2024-05-16",
+                "ex_date": "2024-05-10", "payable_date": "2024-05-16"}],
+            "cash_mergers": [{"id": "cm1", "acquiree_symbol": "XYZ", "rate": 12.5,
+                "process_date": "2024-03-01", "effective_date": "2024-03-01"}],
+            "stock_and_cash_mergers": [{"id": "sc1", "acquirer_symbol": "ABC", "acquirer_rate": 0.5,
+                "acquiree_symbol": "DEF", "acquiree_rate": 1, "cash_rate": 3.1,
+                "process_date": "2024-04-01"}],
+            "name_changes": [{"id": "nc1", "old_symbol": "FB", "new_symbol": "META",
+                "process_date": "2022-06-09"}]
+        }"#;
+        let mut actions: CorporateActionsV1 = serde_json::from_str(json).unwrap();
+        assert_eq!(actions.len(), 5);
+        assert_eq!(actions.forward_splits[0].ratio(), 10.0);
+        assert_eq!(
+            actions.cash_dividends[0].ex_date,
+            NaiveDate::from_ymd_opt(2024, 5, 10)
+        );
+        assert!(actions.reverse_splits.is_empty());
+        let acquirees: Vec<&str> = actions
+            .mergers()
+            .map(|m| m.acquiree_symbol.as_str())
+            .collect();
+        assert_eq!(acquirees, ["XYZ", "DEF"]);
+
+        actions.extend(CorporateActionsV1 {
+            name_changes: actions.name_changes.clone(),
+            ..CorporateActionsV1::default()
+        });
+        assert_eq!(actions.name_changes.len(), 2);
+
+        let params = CorporateActionsParams::new()
+            .cusips("037833100")
+            .sort(SortDirection::Desc)
+            .page_token("abc");
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value["sort"], "desc");
+        assert_eq!(value["page_token"], "abc");
+    }
+
     #[test]
     fn test_broker_account_status_serialization() {
         let status = BrokerAccountStatus::Active;
@@ -5779,6 +7875,147 @@ mod tests {
         assert_eq!(json, "\"ONBOARDING\"");
     }
 
+    #[test]
+    fn test_kyc_result_blocking_reasons() {
+        let result: KycResult = serde_json::from_str(
+            r#"{
+                "reject": {"IDENTITY_VERIFICATION": {"message": "Name mismatch"}},
+                "accept": {"WATCHLIST": {}},
+                "indeterminate": {"TAX_IDENTIFICATION": {}},
+                "additional_information": "Upload a passport",
+                "summary": "fail"
+            }"#,
+        )
+        .unwrap();
+        assert!(!result.is_approved());
+        assert!(result.needs_additional_information());
+        assert_eq!(
+            result.blocking_reasons(),
+            ["IDENTITY_VERIFICATION", "TAX_IDENTIFICATION"]
+        );
+        assert_eq!(
+            result.reject["IDENTITY_VERIFICATION"].message.as_deref(),
+            Some("Name mismatch")
+        );
+
+        let passed: KycResult = serde_json::from_str(r#"{"summary":"pass"}"#).unwrap();
+        assert!(passed.is_approved());
+    }
+
+    #[test]
+    fn test_country_info_validates_request() {
+        let info: CountryInfo = serde_json::from_str(
+            r#"{"country":"BRA","tax_id_types":["BRA_CPF"],"required_documents":["identity_verification"]}"#,
+        )
+        .unwrap();
+        let identity = Identity::new("Ana", "Souza", "1990-01-01").tax_id("123", TaxIdType::BraCpf);
+        let request = CreateBrokerAccountRequest::new(
+            Contact::new("ana@example.com", "Sao Paulo", "01000-000", "BRA"),
+            identity,
+            Disclosures::new(),
+            Vec::new(),
+        );
+        let Err(AlpacaError::ValidationErrors(errors)) = info.validate_request(&request) else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "documents");
+
+        let request = request.documents(vec![Document {
+            document_type: DocumentType::IdentityVerification,
+            document_sub_type: None,
+            content: String::new(),
+            mime_type: "image/png".to_string(),
+        }]);
+        assert!(info.validate_request(&request).is_ok());
+        let mut wrong_tax_id = request.clone();
+        wrong_tax_id.identity.tax_id_type = Some(TaxIdType::UsaSsn);
+        assert!(info.validate_request(&wrong_tax_id).is_err());
+    }
+
+    #[test]
+    fn test_w8ben_document_validation() {
+        let dob = chrono::NaiveDate::from_ymd_opt(1985, 3, 14).unwrap();
+        let signed = chrono::NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+        let form = W8BenDocument::new("Ana Pereira", "PRT", dob)
+            .permanent_address("Rua Augusta 100", "Lisboa", "PRT")
+            .foreign_tax_id("123456789")
+            .treaty_country("PRT")
+            .ip_address("192.0.2.10")
+            .signature("Ana Pereira", signed);
+        assert!(form.validate().is_ok());
+
+        let upload = serde_json::to_value(W8BenUpload::from(form.clone())).unwrap();
+        assert_eq!(upload["document_type"], "w8ben");
+        assert_eq!(upload["document_sub_type"], "Form W-8BEN");
+        assert_eq!(upload["content_data"]["date"], "2024-05-02");
+        assert_eq!(upload["content_data"]["revision"], "10-2021");
+
+        let incomplete = W8BenDocument::new("Ana Pereira", "Portugal", dob);
+        let Err(AlpacaError::ValidationErrors(errors)) = incomplete.validate() else {
+            panic!("expected validation errors");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        for field in [
+            "country_citizen",
+            "treaty_country",
+            "foreign_tax_id",
+            "date",
+            "signer_full_name",
+        ] {
+            assert!(fields.contains(&field), "missing {field}");
+        }
+        assert!(form.ftin_not_required().validate().is_ok());
+    }
+
+    #[test]
+    fn test_broker_trading_account_deserialization() {
+        let json = r#"{
+            "id": "c8f1ef5d-edc0-4f23-9ee4-378f19cb92a4",
+            "account_number": "927584925",
+            "status": "ACTIVE",
+            "crypto_status": "ACTIVE",
+            "currency": "USD",
+            "buying_power": "103556.8572572922",
+            "regt_buying_power": "103556.8572572922",
+            "daytrading_buying_power": "0",
+            "effective_buying_power": "103556.8572572922",
+            "non_marginable_buying_power": "51778.42",
+            "bod_dtbp": "0",
+            "cash": "51778.4286286461",
+            "cash_withdrawable": "51778.4286286461",
+            "cash_transferable": "51278.4286286461",
+            "pending_transfer_out": "500",
+            "portfolio_value": "51778.4286286461",
+            "pattern_day_trader": false,
+            "trading_blocked": false,
+            "transfers_blocked": false,
+            "account_blocked": false,
+            "created_at": "2022-01-21T21:25:26.583576Z",
+            "trade_suspended_by_user": false,
+            "multiplier": "2",
+            "shorting_enabled": true,
+            "equity": "51778.4286286461",
+            "last_equity": "51778.4286286461",
+            "long_market_value": "0",
+            "short_market_value": "0",
+            "initial_margin": "0",
+            "maintenance_margin": "1778.4286286461",
+            "last_maintenance_margin": "0",
+            "sma": "0",
+            "daytrade_count": 0,
+            "previous_close": "2022-04-28T20:00:00-04:00",
+            "last_daytrade_count": 0,
+            "clearing_broker": "ALPACA_APCA"
+        }"#;
+        let account: BrokerTradingAccount = serde_json::from_str(json).unwrap();
+        assert_eq!(account.status, BrokerAccountStatus::Active);
+        assert_eq!(account.withdrawable_cash(), Some(51778.4286286461));
+        assert_eq!(account.transferable_cash(), Some(51278.4286286461));
+        assert!((account.maintenance_excess().unwrap() - 50000.0).abs() < 1e-6);
+        assert!(account.pending_transfer_in.is_none());
+    }
+
     #[test]
     fn test_agreement_type_serialization() {
         let agreement = AgreementType::CustomerAgreement;
@@ -5830,6 +8067,20 @@ mod tests {
         assert_eq!(json, "\"COMPLETE\"");
     }
 
+    #[test]
+    fn test_list_firm_transfers_params() {
+        let params = ListFirmTransfersParams::new()
+            .status(TransferStatus::Queued)
+            .direction(TransferDirection::Outgoing)
+            .limit(50);
+        let json = serde_json::to_value(&params).unwrap();
+        assert_eq!(json["status"], "QUEUED");
+        assert_eq!(json["limit"], 50);
+        assert!(json.get("offset").is_none());
+        assert!(TransferStatus::SentToClearing.is_pending());
+        assert!(!TransferStatus::Returned.is_pending());
+    }
+
     #[test]
     fn test_create_ach_relationship_request() {
         let request = CreateAchRelationshipRequest::new(
@@ -5889,6 +8140,18 @@ mod tests {
         assert_eq!(request.label, Some("My Hardware Wallet".to_string()));
     }
 
+    #[test]
+    fn test_fee_estimate_deserialization() {
+        let estimate: FeeEstimate = serde_json::from_str(r#"{"fee":"0.00012"}"#).unwrap();
+        assert_eq!(estimate.fee_amount(), Some(0.00012));
+        assert_eq!(estimate.asset, None);
+
+        let params = FeeEstimateParams::new("BTC", "bc1qfrom", "bc1qto", "0.5");
+        let json = serde_json::to_value(&params).unwrap();
+        assert_eq!(json["from"], "bc1qfrom");
+        assert_eq!(json["to"], "bc1qto");
+    }
+
     #[test]
     fn test_crypto_bars_params_builder() {
         let params = CryptoBarsParams::new("BTC/USD,ETH/USD")
@@ -5999,6 +8262,38 @@ mod tests {
         assert_eq!(json, "\"DIV\"");
     }
 
+    #[test]
+    fn test_activity_deserializes_by_type() {
+        let json = r#"[
+            {"id": "20240102093000000::1", "activity_type": "FILL",
+             "transaction_time": "2024-01-02T14:30:00Z", "type": "partial_fill",
+             "price": "190.5", "qty": "5", "side": "buy", "symbol": "AAPL",
+             "leaves_qty": "5", "order_id": "61e69015-8549-4bfd-b9c3-01e75843f47d",
+             "cum_qty": "5", "order_status": "partially_filled"},
+            {"id": "20240103000000000::2", "activity_type": "DIV",
+             "date": "2024-01-03", "net_amount": "12.34", "symbol": "AAPL",
+             "qty": "50", "per_share_amount": "0.24"}
+        ]"#;
+        let activities: Vec<Activity> = serde_json::from_str(json).unwrap();
+
+        let fill = activities[0].as_trade().unwrap();
+        assert_eq!(fill.side, OrderSide::Buy);
+        assert_eq!(fill.price, "190.5");
+        assert_eq!(fill.cum_qty.as_deref(), Some("5"));
+        assert_eq!(
+            fill.order_id.to_string(),
+            "61e69015-8549-4bfd-b9c3-01e75843f47d"
+        );
+        let dividend = activities[1].as_non_trade().unwrap();
+        assert_eq!(dividend.net_amount, "12.34");
+        assert_eq!(activities[1].activity_type(), &ActivityType::Div);
+        assert_eq!(activities[1].symbol(), Some("AAPL"));
+
+        let missing_order: Result<Activity, _> =
+            serde_json::from_str(r#"{"id": "1", "activity_type": "FILL", "symbol": "AAPL"}"#);
+        assert!(missing_order.is_err());
+    }
+
     #[test]
     fn test_list_activities_params_builder() {
         let params = ListActivitiesParams::new()
@@ -6031,6 +8326,84 @@ mod tests {
         assert!(alloc.notional.is_none());
     }
 
+    #[test]
+    fn test_account_documents() {
+        let params = DocumentParams::new()
+            .document_type(StatementType::AccountStatement)
+            .start("2024-01-01")
+            .end("2024-03-31");
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            serde_json::json!({"type": "account_statement", "start": "2024-01-01", "end": "2024-03-31"})
+        );
+
+        let documents: Vec<AccountDocument> = serde_json::from_str(
+            r#"[{"id":"d1","type":"trade_confirmation","date":"2024-02-15","name":"confirm.pdf"}]"#,
+        )
+        .unwrap();
+        assert_eq!(documents[0].document_type, StatementType::TradeConfirmation);
+        assert_eq!(documents[0].date, "2024-02-15");
+    }
+
+    #[test]
+    fn test_rebalance_run_details_and_drift() {
+        use crate::test_utils::fixtures::{sample_order, sample_position};
+
+        let mut order = sample_order("AAPL", OrderSide::Buy, "5");
+        order.filled_at = order.submitted_at.map(|t| t + chrono::Duration::seconds(2));
+        let json = serde_json::json!({
+            "id": "3a6f9e0e-7bbd-4f4e-8a36-0c2f5c3a1f10",
+            "type": "full_rebalance",
+            "initiated_from": "api",
+            "status": "completed",
+            "account_id": "acc-1",
+            "portfolio_id": "pf-1",
+            "weights": [{"symbol": "AAPL", "percent": 60.0}, {"symbol": "SPY", "percent": 40.0}],
+            "orders": [order],
+            "skipped_orders": [{"symbol": "SPY", "side": "buy", "notional": "0.5",
+                                "reason": "ORDER_LESS_THAN_MIN_NOTIONAL"}],
+            "created_at": "2024-01-02T15:00:00Z",
+            "completed_at": "2099-01-02T15:00:00Z"
+        });
+        let run: RebalanceRunDetails = serde_json::from_value(json).unwrap();
+        let trail = run.audit_trail();
+        assert_eq!(trail.first().unwrap().kind, RebalanceRunEventKind::Created);
+        assert!(
+            matches!(trail[1].kind, RebalanceRunEventKind::OrderSkipped(ref s) if s.symbol == "SPY")
+        );
+        assert!(matches!(
+            trail[2].kind,
+            RebalanceRunEventKind::OrderSubmitted { .. }
+        ));
+        assert!(matches!(
+            trail[3].kind,
+            RebalanceRunEventKind::OrderFilled { .. }
+        ));
+        assert_eq!(trail.last().unwrap().kind, RebalanceRunEventKind::Completed);
+
+        let portfolio = RebalancePortfolio {
+            id: Uuid::new_v4(),
+            name: "Growth".to_string(),
+            description: None,
+            weights: run.weights.clone(),
+            created_at: run.created_at,
+            updated_at: run.created_at,
+        };
+        let mut aapl = sample_position("AAPL", "10", "150");
+        aapl.market_value = "7000".to_string();
+        let mut tsla = sample_position("TSLA", "1", "200");
+        tsla.market_value = "1000".to_string();
+        let report = DriftReport::compute("acc-1", &portfolio, &[aapl, tsla], 10_000.0);
+        let drifts: Vec<(&str, f64)> = report
+            .entries
+            .iter()
+            .map(|e| (e.symbol.as_str(), (e.drift() * 100.0).round()))
+            .collect();
+        assert_eq!(drifts, [("AAPL", 10.0), ("SPY", -40.0), ("TSLA", 10.0)]);
+        assert!((report.max_drift() - 0.4).abs() < 1e-9);
+        assert_eq!(report.exceeding(0.2).count(), 1);
+    }
+
     #[test]
     fn test_rate_limit_config_builder() {
         let config = RateLimitConfig::new()
@@ -6124,6 +8497,34 @@ mod tests {
 
         let live_guard = EnvironmentGuard::allow_live(TradingEnvironment::Live);
         assert!(live_guard.is_allowed());
+
+        let blocked = guard.clone().with_environment(TradingEnvironment::Live);
+        assert!(blocked.check_order(1.0, None).is_err());
+        let live_guard = live_guard.require_confirmation_above(10_000.0);
+        assert!(live_guard.check_order(10_000.0, None).is_ok());
+        assert!(live_guard.check_order(10_001.0, None).is_err());
+        let confirmation = live_guard.confirm_live();
+        assert!(
+            live_guard
+                .check_order(10_001.0, Some(&confirmation))
+                .is_ok()
+        );
+        let paper = live_guard.with_environment(TradingEnvironment::Paper);
+        assert!(paper.check_order(10_001.0, None).is_ok());
+
+        assert!(
+            EnvironmentGuard::check_credentials("PKABC", "https://api.alpaca.markets").is_err()
+        );
+        assert!(
+            EnvironmentGuard::check_credentials("AKABC", "https://paper-api.alpaca.markets/v2")
+                .is_err()
+        );
+        assert!(
+            EnvironmentGuard::check_credentials("PKABC", "https://paper-api.alpaca.markets")
+                .is_ok()
+        );
+        assert!(EnvironmentGuard::check_credentials("CKABC", "https://api.alpaca.markets").is_ok());
+        assert!(EnvironmentGuard::check_credentials("AKABC", "http://127.0.0.1:8080").is_ok());
     }
 
     #[test]
@@ -6188,6 +8589,26 @@ mod tests {
     fn test_currency_pair() {
         let pair = CurrencyPair::new(Currency::Eur, Currency::Usd);
         assert_eq!(pair.as_string(), "EUR/USD");
+        assert_eq!(pair.symbol(), "EURUSD");
+        assert_eq!(CurrencyPair::from_symbol("EUR/USD"), Some(pair));
+        assert_eq!(
+            CurrencyPair::from_symbol("usdjpy").map(|p| p.quote),
+            Some(Currency::Jpy)
+        );
+        assert_eq!(CurrencyPair::from_symbol("USDXYZ"), None);
+    }
+
+    #[test]
+    fn test_fx_rate_deserialization() {
+        let json = r#"{"t":"2024-06-03T14:00:00Z","bp":156.99,"mp":157.0,"ap":157.01}"#;
+        let rate: FxRate = serde_json::from_str(json).unwrap();
+        assert_eq!(rate.convert(2.0), 314.0);
+        assert!((rate.convert_inverse(157.0) - 1.0).abs() < 1e-12);
+        let params = FxRatesParams::new(&[
+            CurrencyPair::new(Currency::Usd, Currency::Jpy),
+            CurrencyPair::new(Currency::Eur, Currency::Usd),
+        ]);
+        assert_eq!(params.currency_pairs.as_deref(), Some("USDJPY,EURUSD"));
     }
 
     #[test]
@@ -6204,4 +8625,36 @@ mod tests {
         assert_eq!(req.amount, "5000.00");
         assert_eq!(req.tax_year, 2024);
     }
+
+    #[test]
+    fn test_symbol_or_id_detect() {
+        let id = Uuid::new_v4();
+        assert_eq!(SymbolOrId::detect(&id.to_string()), SymbolOrId::Id(id));
+        assert_eq!(
+            SymbolOrId::detect("037833100"),
+            SymbolOrId::Cusip("037833100".to_string())
+        );
+        assert_eq!(
+            SymbolOrId::detect("bbg000b9xry4"),
+            SymbolOrId::CompositeFigi("BBG000B9XRY4".to_string())
+        );
+        assert_eq!(
+            SymbolOrId::detect("037833101"),
+            SymbolOrId::Symbol("037833101".to_string())
+        );
+        assert_eq!(SymbolOrId::detect("AAPL"), SymbolOrId::from("AAPL"));
+        assert_eq!(SymbolOrId::from(id).to_string(), id.to_string());
+
+        let asset: EnhancedAsset = serde_json::from_str(
+            r#"{"id":"b0b6dd9d-8b9b-48a9-ba46-b9d54906e415","class":"us_equity",
+                "exchange":"NASDAQ","symbol":"AAPL","status":"active","tradable":true,
+                "marginable":true,"shortable":true,"easy_to_borrow":true,
+                "fractionable":true,"cusip":"037833100","composite_figi":"BBG000B9XRY4"}"#,
+        )
+        .unwrap();
+        assert!(asset.matches(&SymbolOrId::detect("BBG000B9XRY4")));
+        assert!(asset.matches(&SymbolOrId::detect("037833100")));
+        assert!(asset.matches(&"aapl".into()));
+        assert!(!asset.matches(&SymbolOrId::Cusip("594918104".to_string())));
+    }
 }
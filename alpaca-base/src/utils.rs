@@ -3,11 +3,15 @@
 #![allow(missing_docs)]
 
 use crate::error::{AlpacaError, Result};
-use chrono::{DateTime, Utc};
+use crate::types::Calendar;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use uuid::Uuid;
 
+/// Share quantities closer than this are treated as equal.
+pub const QTY_EPSILON: f64 = 1e-9;
+
 /// Generate a random client order ID
 pub fn generate_client_order_id() -> String {
     Uuid::new_v4().to_string()
@@ -25,6 +29,25 @@ pub fn format_decimal(value: f64, precision: usize) -> String {
     format!("{:.prec$}", value, prec = precision)
 }
 
+/// Format an order price with two decimals, or four below $1 (sub-penny
+/// rule).
+pub fn format_price(price: f64) -> String {
+    if price.abs() >= 1.0 {
+        format_decimal(price, 2)
+    } else {
+        format_decimal(price, 4)
+    }
+}
+
+/// Format a share quantity without trailing zeros, at most nine decimals.
+pub fn format_qty(qty: f64) -> String {
+    let formatted = format_decimal(qty, 9);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
 /// Validate symbol format
 pub fn validate_symbol(symbol: &str) -> Result<()> {
     if symbol.is_empty() {
@@ -87,6 +110,78 @@ pub fn parse_rfc3339(timestamp: &str) -> Result<DateTime<Utc>> {
         .map_err(|e| AlpacaError::InvalidData(format!("Invalid timestamp format: {}", e)))
 }
 
+/// UTC offset of US Eastern time (where the calendar is published) on
+/// `date`, following the US daylight saving rules in force since 2007.
+#[must_use]
+pub fn us_eastern_offset(date: NaiveDate) -> FixedOffset {
+    let year = date.year();
+    let dst_start = NaiveDate::from_weekday_of_month_opt(year, 3, Weekday::Sun, 2);
+    let dst_end = NaiveDate::from_weekday_of_month_opt(year, 11, Weekday::Sun, 1);
+    let hours = match (dst_start, dst_end) {
+        (Some(start), Some(end)) if date >= start && date < end => 4,
+        _ => 5,
+    };
+    FixedOffset::west_opt(hours * 3600).expect("offset within range")
+}
+
+/// One trading session in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionWindow {
+    /// Trading date (US Eastern).
+    pub date: NaiveDate,
+    /// Session open.
+    pub open: DateTime<Utc>,
+    /// Session close.
+    pub close: DateTime<Utc>,
+}
+
+impl SessionWindow {
+    /// Regular trading hours for a calendar day.
+    pub fn from_calendar(day: &Calendar) -> Result<Self> {
+        Self::parse(&day.date, &day.open, &day.close)
+    }
+
+    /// Extended hours (pre-market open to after-hours close) for a
+    /// calendar day.
+    pub fn extended_from_calendar(day: &Calendar) -> Result<Self> {
+        Self::parse(&day.date, &day.session_open, &day.session_close)
+    }
+
+    fn parse(date: &str, open: &str, close: &str) -> Result<Self> {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| AlpacaError::InvalidData(format!("calendar date '{date}': {e}")))?;
+        let offset = us_eastern_offset(date);
+        let at = |time: &str| -> Result<DateTime<Utc>> {
+            let time = NaiveTime::parse_from_str(time, "%H:%M")
+                .or_else(|_| NaiveTime::parse_from_str(time, "%H%M"))
+                .map_err(|e| AlpacaError::InvalidData(format!("calendar time '{time}': {e}")))?;
+            offset
+                .from_local_datetime(&date.and_time(time))
+                .single()
+                .map(|dt| dt.with_timezone(&Utc))
+                .ok_or_else(|| AlpacaError::InvalidData(format!("invalid session time {time}")))
+        };
+        let session = Self {
+            date,
+            open: at(open)?,
+            close: at(close)?,
+        };
+        if session.close <= session.open {
+            return Err(AlpacaError::InvalidData(format!(
+                "session on {date} closes before it opens"
+            )));
+        }
+        Ok(session)
+    }
+
+    /// Whether `timestamp` falls within the session (open inclusive,
+    /// close exclusive).
+    #[must_use]
+    pub fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        self.open <= timestamp && timestamp < self.close
+    }
+}
+
 /// Rate limiter for API requests
 #[derive(Debug)]
 pub struct RateLimiter {
@@ -274,6 +369,14 @@ mod tests {
         assert!(validate_quantity("invalid").is_err());
     }
 
+    #[test]
+    fn test_format_price_and_qty() {
+        assert_eq!(format_price(175.5), "175.50");
+        assert_eq!(format_price(0.12345), "0.1235");
+        assert_eq!(format_qty(10.0), "10");
+        assert_eq!(format_qty(0.1 + 0.2), "0.3");
+    }
+
     #[test]
     fn test_url_builder() {
         let url = UrlBuilder::new("https://api.example.com")
@@ -297,4 +400,32 @@ mod tests {
         assert!(!limiter.can_make_request());
         assert_eq!(limiter.remaining_requests(), 0);
     }
+
+    fn calendar(date: &str) -> Calendar {
+        Calendar {
+            date: date.to_string(),
+            open: "09:30".to_string(),
+            close: "16:00".to_string(),
+            session_open: "0400".to_string(),
+            session_close: "2000".to_string(),
+        }
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_sessions_follow_daylight_saving() {
+        let summer = SessionWindow::from_calendar(&calendar("2024-07-01")).unwrap();
+        assert_eq!(summer.open, at("2024-07-01T13:30:00Z"));
+        let winter = SessionWindow::from_calendar(&calendar("2024-12-02")).unwrap();
+        assert_eq!(winter.close, at("2024-12-02T21:00:00Z"));
+        let extended = SessionWindow::extended_from_calendar(&calendar("2024-03-11")).unwrap();
+        assert_eq!(extended.open, at("2024-03-11T08:00:00Z"));
+        assert_eq!(
+            us_eastern_offset(NaiveDate::from_ymd_opt(2024, 11, 3).unwrap()),
+            FixedOffset::west_opt(5 * 3600).unwrap()
+        );
+    }
 }
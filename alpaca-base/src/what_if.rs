@@ -0,0 +1,357 @@
+//! Pre-trade what-if analysis.
+//!
+//! [`WhatIf`] replays a set of hypothetical orders against a copy of a
+//! [`PortfolioTracker`] and reports exposure, margin, buying power and
+//! allocation drift before and after, so a strategy can see what a batch
+//! of orders would do to the account before sending any of them.
+//!
+//! Orders are assumed to fill completely: limit and stop-limit orders at
+//! their limit price, stop orders at their stop price, and market orders at
+//! the latest known price. Margin follows Reg T: the initial requirement is
+//! the gross position value divided by the account multiplier (1 for cash
+//! accounts, 2 for margin accounts), buying power is the equity above that
+//! requirement times the multiplier, and maintenance uses separate long
+//! and short rates.
+
+use crate::error::{AlpacaError, Result};
+use crate::portfolio::{PortfolioFill, PortfolioTracker};
+use crate::trading::OrderRequest;
+use crate::types::{Account, OrderType, Position};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Maintenance margin rates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceRates {
+    /// Fraction of long market value.
+    pub long: f64,
+    /// Fraction of short market value.
+    pub short: f64,
+}
+
+impl Default for MaintenanceRates {
+    fn default() -> Self {
+        Self {
+            long: 0.25,
+            short: 0.30,
+        }
+    }
+}
+
+/// Account exposure at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExposureSnapshot {
+    /// Equity.
+    pub equity: f64,
+    /// Cash.
+    pub cash: f64,
+    /// Market value of long positions.
+    pub long_market_value: f64,
+    /// Market value of short positions (positive).
+    pub short_market_value: f64,
+    /// Long plus short market value.
+    pub gross_exposure: f64,
+    /// Long minus short market value.
+    pub net_exposure: f64,
+    /// Gross exposure over equity.
+    pub leverage: f64,
+    /// Initial margin requirement.
+    pub initial_margin: f64,
+    /// Maintenance margin requirement.
+    pub maintenance_margin: f64,
+    /// Buying power (never negative).
+    pub buying_power: f64,
+}
+
+/// Weight of one symbol before and after the orders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AllocationDrift {
+    /// Symbol.
+    pub symbol: String,
+    /// Signed share of equity before.
+    pub weight_before: f64,
+    /// Signed share of equity after.
+    pub weight_after: f64,
+    /// Target weight, if one was set.
+    pub target: Option<f64>,
+}
+
+impl AllocationDrift {
+    /// Distance from the target after the orders.
+    #[must_use]
+    pub fn drift(&self) -> Option<f64> {
+        self.target.map(|target| self.weight_after - target)
+    }
+}
+
+/// Problem the orders would cause.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WhatIfWarning {
+    /// The orders need more initial margin than the equity provides.
+    InsufficientBuyingPower {
+        /// Equity missing to meet the initial requirement.
+        shortfall: f64,
+    },
+    /// Equity would be below the maintenance requirement.
+    MaintenanceDeficit {
+        /// Equity missing to meet the maintenance requirement.
+        shortfall: f64,
+    },
+}
+
+/// Projected effect of a set of orders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhatIfReport {
+    /// Exposure now.
+    pub before: ExposureSnapshot,
+    /// Exposure once every order fills.
+    pub after: ExposureSnapshot,
+    /// Per-symbol weights, by symbol.
+    pub allocations: Vec<AllocationDrift>,
+    /// Problems found.
+    pub warnings: Vec<WhatIfWarning>,
+}
+
+impl WhatIfReport {
+    /// Whether the orders raise no warnings.
+    #[must_use]
+    pub fn is_feasible(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// The allocation entry for `symbol`.
+    #[must_use]
+    pub fn allocation(&self, symbol: &str) -> Option<&AllocationDrift> {
+        self.allocations.iter().find(|a| a.symbol == symbol)
+    }
+}
+
+/// Projects hypothetical orders onto a portfolio.
+#[derive(Debug, Clone)]
+pub struct WhatIf {
+    portfolio: PortfolioTracker,
+    multiplier: f64,
+    maintenance: MaintenanceRates,
+    prices: HashMap<String, f64>,
+    targets: BTreeMap<String, f64>,
+}
+
+impl WhatIf {
+    /// Analyse orders against `portfolio` as a cash account.
+    #[must_use]
+    pub fn new(portfolio: PortfolioTracker) -> Self {
+        Self {
+            portfolio,
+            multiplier: 1.0,
+            maintenance: MaintenanceRates::default(),
+            prices: HashMap::new(),
+            targets: BTreeMap::new(),
+        }
+    }
+
+    /// Analyse orders against an account and its positions, as returned by
+    /// the REST API, using the account's margin multiplier.
+    #[must_use]
+    pub fn from_snapshot(account: &Account, positions: &[Position]) -> Self {
+        let multiplier = account.multiplier.parse().unwrap_or(1.0);
+        Self::new(PortfolioTracker::from_snapshot(account, positions)).multiplier(multiplier)
+    }
+
+    /// Set the margin multiplier.
+    #[must_use]
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Set the maintenance margin rates.
+    #[must_use]
+    pub fn maintenance_rates(mut self, rates: MaintenanceRates) -> Self {
+        self.maintenance = rates;
+        self
+    }
+
+    /// Set the price used for market orders in `symbol`, e.g. for symbols
+    /// not yet held.
+    #[must_use]
+    pub fn price(mut self, symbol: &str, price: f64) -> Self {
+        self.prices.insert(symbol.to_string(), price);
+        self
+    }
+
+    /// Set target weights (signed shares of equity) to measure drift
+    /// against.
+    #[must_use]
+    pub fn target_weights<S: Into<String>>(
+        mut self,
+        targets: impl IntoIterator<Item = (S, f64)>,
+    ) -> Self {
+        self.targets = targets.into_iter().map(|(s, w)| (s.into(), w)).collect();
+        self
+    }
+
+    /// Project the effect of `orders`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if an order has a non-positive
+    /// quantity or no price can be determined for it.
+    pub fn evaluate(&self, orders: &[OrderRequest]) -> Result<WhatIfReport> {
+        let mut after = self.portfolio.clone();
+        for (symbol, price) in &self.prices {
+            after.update_price(symbol, *price);
+        }
+        let before = after.clone();
+        for order in orders {
+            if order.qty.is_nan() || order.qty <= 0.0 {
+                return Err(AlpacaError::Validation(format!(
+                    "order for {} has quantity {}",
+                    order.symbol, order.qty
+                )));
+            }
+            let price = self.fill_price(&after, order)?;
+            after.apply_fill(&PortfolioFill::new(
+                &order.symbol,
+                order.side.clone(),
+                order.qty,
+                price,
+            ));
+            // Value the position at the current price, not the fill price.
+            if let Some(mark) = self.mark(&before, &order.symbol) {
+                after.update_price(&order.symbol, mark);
+            }
+        }
+
+        let before_snapshot = self.snapshot(&before);
+        let after_snapshot = self.snapshot(&after);
+        let mut warnings = Vec::new();
+        let excess = after_snapshot.equity - after_snapshot.initial_margin;
+        if excess < 0.0 && after_snapshot.initial_margin > before_snapshot.initial_margin {
+            warnings.push(WhatIfWarning::InsufficientBuyingPower { shortfall: -excess });
+        }
+        if after_snapshot.equity < after_snapshot.maintenance_margin {
+            warnings.push(WhatIfWarning::MaintenanceDeficit {
+                shortfall: after_snapshot.maintenance_margin - after_snapshot.equity,
+            });
+        }
+
+        let symbols: BTreeSet<&String> = before
+            .open_positions()
+            .chain(after.open_positions())
+            .map(|p| &p.symbol)
+            .chain(self.targets.keys())
+            .collect();
+        let weight = |portfolio: &PortfolioTracker, equity: f64, symbol: &str| match portfolio
+            .position(symbol)
+        {
+            Some(p) if equity != 0.0 => p.market_value() / equity,
+            _ => 0.0,
+        };
+        let allocations = symbols
+            .into_iter()
+            .map(|symbol| AllocationDrift {
+                symbol: symbol.clone(),
+                weight_before: weight(&before, before_snapshot.equity, symbol),
+                weight_after: weight(&after, after_snapshot.equity, symbol),
+                target: self.targets.get(symbol).copied(),
+            })
+            .collect();
+
+        Ok(WhatIfReport {
+            before: before_snapshot,
+            after: after_snapshot,
+            allocations,
+            warnings,
+        })
+    }
+
+    fn mark(&self, portfolio: &PortfolioTracker, symbol: &str) -> Option<f64> {
+        self.prices.get(symbol).copied().or_else(|| {
+            portfolio.position(symbol).and_then(|p| {
+                p.last_price
+                    .or_else(|| (!p.is_flat()).then(|| p.mark_price()))
+            })
+        })
+    }
+
+    fn fill_price(&self, portfolio: &PortfolioTracker, order: &OrderRequest) -> Result<f64> {
+        let price = match order.order_type {
+            OrderType::Limit | OrderType::StopLimit => order.limit_price,
+            OrderType::Stop => order.stop_price,
+            _ => None,
+        };
+        price
+            .or_else(|| self.mark(portfolio, &order.symbol))
+            .ok_or_else(|| AlpacaError::Validation(format!("no price for {}", order.symbol)))
+    }
+
+    fn snapshot(&self, portfolio: &PortfolioTracker) -> ExposureSnapshot {
+        let (mut long, mut short) = (0.0, 0.0);
+        for position in portfolio.open_positions() {
+            let value = position.market_value();
+            if value >= 0.0 {
+                long += value;
+            } else {
+                short -= value;
+            }
+        }
+        let equity = portfolio.equity();
+        let gross = long + short;
+        let initial_margin = gross / self.multiplier;
+        ExposureSnapshot {
+            equity,
+            cash: portfolio.cash(),
+            long_market_value: long,
+            short_market_value: short,
+            gross_exposure: gross,
+            net_exposure: long - short,
+            leverage: if equity > 0.0 { gross / equity } else { 0.0 },
+            initial_margin,
+            maintenance_margin: long * self.maintenance.long + short * self.maintenance.short,
+            buying_power: ((equity - initial_margin) * self.multiplier).max(0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::OrderSide;
+
+    #[test]
+    fn test_what_if_margin_and_drift() {
+        let mut portfolio = PortfolioTracker::new(10_000.0);
+        portfolio.apply_fill(&PortfolioFill::new("AAPL", OrderSide::Buy, 20.0, 200.0));
+        let what_if = WhatIf::new(portfolio)
+            .multiplier(2.0)
+            .price("AAPL", 250.0)
+            .price("MSFT", 400.0)
+            .target_weights([("AAPL", 0.3), ("MSFT", 0.5)]);
+
+        // Equity 6,000 + 5,000 = 11,000.
+        let report = what_if
+            .evaluate(&[OrderRequest::market("MSFT", OrderSide::Buy, 10.0)])
+            .unwrap();
+        assert!(report.is_feasible());
+        assert!((report.before.equity - 11_000.0).abs() < 1e-9);
+        assert!((report.before.buying_power - 17_000.0).abs() < 1e-9);
+        assert!((report.after.gross_exposure - 9_000.0).abs() < 1e-9);
+        assert!((report.after.buying_power - 13_000.0).abs() < 1e-9);
+        let msft = report.allocation("MSFT").unwrap();
+        assert_eq!(msft.weight_before, 0.0);
+        assert!((msft.drift().unwrap() - (4_000.0 / 11_000.0 - 0.5)).abs() < 1e-9);
+
+        let too_big = what_if
+            .evaluate(&[OrderRequest::market("MSFT", OrderSide::Buy, 60.0)])
+            .unwrap();
+        assert!(matches!(
+            too_big.warnings[0],
+            WhatIfWarning::InsufficientBuyingPower { shortfall } if (shortfall - 3_500.0).abs() < 1e-9
+        ));
+        assert!(
+            what_if
+                .evaluate(&[OrderRequest::market("TSLA", OrderSide::Buy, 1.0)])
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! # FIX Market Data Stream
+//!
+//! This example demonstrates decoding market data snapshot (W) and
+//! incremental refresh (X) messages into typed updates, as yielded by
+//! `FixClient::subscribe_market_data`.
+//!
+//! ## Prerequisites
+//!
+//! Set environment variables:
+//! - `ALPACA_API_KEY`: Your Alpaca API key
+//! - `ALPACA_API_SECRET`: Your Alpaca secret key
+//!
+//! ## Usage
+//!
+//! ```bash
+//! cargo run -p alpaca-fix --example fix_market_data_stream
+//! ```
+//!
+//! **Note**: FIX protocol requires special access from Alpaca.
+
+use alpaca_fix::codec::FixDecoder;
+use alpaca_fix::market_data::{parse_incremental, parse_snapshot};
+use alpaca_fix::{MarketDataUpdate, MdEntryType};
+
+fn main() {
+    println!("=== FIX Market Data Stream ===\n");
+
+    let decoder = FixDecoder::new();
+
+    // Snapshot / full refresh (W)
+    println!("--- Snapshot (W) ---");
+    let raw = "8=FIX.4.4\x0135=W\x01262=req1\x0155=AAPL\x01268=2\x01\
+               269=0\x01270=175.50\x01271=100\x01\
+               269=1\x01270=175.55\x01271=200\x0110=000\x01";
+    let snapshot = parse_snapshot(&decoder.decode(raw).expect("valid message")).expect("snapshot");
+    println!("  Symbol: {}", snapshot.symbol);
+    for entry in &snapshot.entries {
+        println!(
+            "    {:?} - Price: ${:.2}, Size: {}",
+            MdEntryType::from_char(entry.md_entry_type),
+            entry.md_entry_px,
+            entry.md_entry_size
+        );
+    }
+
+    // Incremental refresh (X)
+    println!("\n--- Incremental Refresh (X) ---");
+    let raw = "8=FIX.4.4\x0135=X\x01262=req1\x01268=2\x01\
+               279=1\x01269=0\x0155=AAPL\x01270=175.51\x01271=300\x01\
+               279=0\x01269=2\x0155=AAPL\x01270=175.53\x01271=25\x0110=000\x01";
+    let updates = parse_incremental(&decoder.decode(raw).expect("valid message")).expect("refresh");
+    for update in updates {
+        match update {
+            MarketDataUpdate::Book(book) => println!(
+                "  Book {:?} {:?} {} @ ${:.2} x {}",
+                book.action, book.side, book.symbol, book.price, book.size
+            ),
+            MarketDataUpdate::Trade(trade) => println!(
+                "  Trade {} @ ${:.2} x {}",
+                trade.symbol, trade.price, trade.size
+            ),
+            other => println!("  {:?}", other),
+        }
+    }
+
+    // Streaming pattern
+    println!("\n--- Streaming Pattern ---");
+    println!("  let mut stream = client.subscribe_market_data(&[\"AAPL\"], 1).await?;");
+    println!("  while let Some(update) = stream.next().await {{");
+    println!("      match update {{");
+    println!("          MarketDataUpdate::Snapshot(s) => {{ /* reset book */ }}");
+    println!("          MarketDataUpdate::Book(b) => {{ /* apply level change */ }}");
+    println!("          MarketDataUpdate::Trade(t) => {{ /* record trade */ }}");
+    println!("          MarketDataUpdate::Rejected {{ .. }} => break,");
+    println!("      }}");
+    println!("  }}");
+    println!("  client.unsubscribe_market_data(&stream).await?;");
+
+    println!("\n=== Example Complete ===");
+}
@@ -3,16 +3,20 @@
 use crate::codec::{FixDecoder, FixMessage, tags};
 use crate::config::FixConfig;
 use crate::error::{FixError, Result};
+use crate::market_data::{self, MarketDataRoute, MarketDataRoutes, MarketDataStream, MdEntryType};
 use crate::messages::{
-    ExecType, ExecutionReport, MarketDataRequest, MsgType, NewOrderSingle, OrdStatus,
-    OrderCancelReplaceRequest, OrderCancelRequest, Side,
+    ExecType, ExecutionReport, MarketDataRequest, MsgType, NewOrderMultileg, NewOrderSingle,
+    OrdStatus, OrderCancelReplaceRequest, OrderCancelRequest, Side,
 };
 use crate::session::{FixSession, SessionState};
 use crate::transport::{self, FixTransport};
-use alpaca_base::Credentials;
+use alpaca_base::{AlpacaError, CancellationToken, Credentials, CredentialsHandle};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{Mutex, mpsc};
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+use tokio::sync::{Mutex, mpsc, watch};
+use tokio::task::JoinHandle;
 use tokio::time::{interval, timeout};
 
 /// Channel buffer size for incoming messages.
@@ -24,8 +28,7 @@ const DEFAULT_TIMEOUT_SECS: u64 = 30;
 /// FIX protocol client for Alpaca.
 pub struct FixClient {
     /// Alpaca credentials.
-    #[allow(dead_code)]
-    credentials: Credentials,
+    credentials: CredentialsHandle,
     /// FIX session.
     session: Arc<Mutex<FixSession>>,
     /// TCP transport.
@@ -39,6 +42,15 @@ pub struct FixClient {
     message_rx: Arc<Mutex<Option<mpsc::Receiver<FixMessage>>>>,
     /// Shutdown signal sender.
     shutdown_tx: Arc<Mutex<Option<mpsc::Sender<()>>>>,
+    /// Receive and heartbeat tasks of the current session.
+    session_tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    /// Active market data subscriptions.
+    md_routes: MarketDataRoutes,
+    /// Task re-logging on when the credentials handle is rotated.
+    credential_watcher: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Order requests awaiting their first response.
+    #[cfg(feature = "metrics")]
+    round_trips: RoundTrips,
 }
 
 impl std::fmt::Debug for FixClient {
@@ -52,19 +64,49 @@ impl std::fmt::Debug for FixClient {
 impl FixClient {
     /// Create a new FIX client.
     #[must_use]
-    pub fn new(credentials: Credentials, config: FixConfig) -> Self {
+    pub fn new(credentials: impl Into<CredentialsHandle>, config: FixConfig) -> Self {
         let session = FixSession::new(config.clone());
         Self {
-            credentials,
+            credentials: credentials.into(),
             session: Arc::new(Mutex::new(session)),
             transport: Arc::new(Mutex::new(None)),
             decoder: FixDecoder::new(),
             config,
             message_rx: Arc::new(Mutex::new(None)),
             shutdown_tx: Arc::new(Mutex::new(None)),
+            session_tasks: Arc::new(Mutex::new(Vec::new())),
+            md_routes: MarketDataRoutes::default(),
+            credential_watcher: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "metrics")]
+            round_trips: RoundTrips::default(),
         }
     }
 
+    /// A second client driving the same session, for background tasks.
+    fn shared(&self) -> Self {
+        Self {
+            credentials: self.credentials.clone(),
+            session: Arc::clone(&self.session),
+            transport: Arc::clone(&self.transport),
+            decoder: FixDecoder::new(),
+            config: self.config.clone(),
+            message_rx: Arc::clone(&self.message_rx),
+            shutdown_tx: Arc::clone(&self.shutdown_tx),
+            session_tasks: Arc::clone(&self.session_tasks),
+            md_routes: Arc::clone(&self.md_routes),
+            credential_watcher: Arc::clone(&self.credential_watcher),
+            #[cfg(feature = "metrics")]
+            round_trips: self.round_trips.clone(),
+        }
+    }
+
+    /// The credentials handle, shareable with the REST and WebSocket
+    /// clients.
+    #[must_use]
+    pub fn credentials(&self) -> &CredentialsHandle {
+        &self.credentials
+    }
+
     /// Get the current session state.
     pub async fn state(&self) -> SessionState {
         self.session.lock().await.state()
@@ -72,9 +114,24 @@ impl FixClient {
 
     /// Connect to the FIX server and establish a session.
     ///
+    /// Once connected, the client watches its [`CredentialsHandle`]: a
+    /// rotation, through [`Self::rotate_credentials`] or any clone of the
+    /// handle, logs the session out and on again with the new keys and
+    /// resubscribes open market data streams.
+    ///
     /// # Errors
     /// Returns error if connection or logon fails.
     pub async fn connect(&self) -> Result<()> {
+        // Subscribe before logging on so a rotation racing the logon is
+        // not missed.
+        let rotation = self.credentials.subscribe();
+        self.logon().await?;
+        self.watch_credentials(rotation).await;
+        Ok(())
+    }
+
+    /// Open the transport and log on with the current credentials.
+    async fn logon(&self) -> Result<()> {
         let mut session = self.session.lock().await;
         session.set_state(SessionState::Connecting);
 
@@ -96,7 +153,7 @@ impl FixClient {
         session.set_state(SessionState::LoggingOn);
 
         // Send logon message
-        let logon = session.create_logon();
+        let logon = session.create_logon(&self.credentials.current());
         self.send_raw(&logon).await?;
 
         // Wait for logon response
@@ -108,6 +165,10 @@ impl FixClient {
         if let Some(msg_type) = logon_response.msg_type() {
             match MsgType::from_fix_str(msg_type) {
                 Some(MsgType::Logon) => {
+                    if let Err(e) = session.check_encrypt_method(&logon_response) {
+                        session.set_state(SessionState::Disconnected);
+                        return Err(e);
+                    }
                     tracing::info!("Logon successful");
                     session.set_state(SessionState::Active);
                 }
@@ -139,15 +200,112 @@ impl FixClient {
         Ok(())
     }
 
+    /// Spawn the credential watcher unless it is already running.
+    async fn watch_credentials(&self, mut rotation: watch::Receiver<Credentials>) {
+        let mut watcher = self.credential_watcher.lock().await;
+        if watcher.as_ref().is_some_and(|task| !task.is_finished()) {
+            return;
+        }
+        let client = self.shared();
+        *watcher = Some(tokio::spawn(async move {
+            while rotation.changed().await.is_ok() {
+                if let Err(e) = client.relogon().await {
+                    tracing::error!("Re-logon with rotated credentials failed: {}", e);
+                }
+            }
+        }));
+    }
+
+    /// Log out and on again with the current credentials, then re-send
+    /// every market data subscription under its original MDReqID so open
+    /// [`MarketDataStream`]s keep receiving updates. No-op without an
+    /// active session.
+    async fn relogon(&self) -> Result<()> {
+        if self.state().await != SessionState::Active {
+            return Ok(());
+        }
+        tracing::info!("Re-logging on with rotated credentials");
+        self.close_session().await?;
+        self.logon().await?;
+        self.resubscribe_market_data().await
+    }
+
+    /// Connect, retrying under [`FixConfig::reconnect_policy`] until the
+    /// session is established, the attempts run out, or `cancel` is
+    /// cancelled.
+    ///
+    /// Errors are reported as [`AlpacaError`] so the policy can tell
+    /// transient failures (connection, timeout) from permanent ones (a
+    /// rejected logon).
+    ///
+    /// # Errors
+    /// Returns the last connection error, or [`AlpacaError::Cancelled`].
+    pub async fn connect_with_retry(&self, cancel: &CancellationToken) -> alpaca_base::Result<()> {
+        self.config
+            .reconnect_policy()
+            .retry_with_cancel(cancel, |_| async {
+                self.connect().await.map_err(AlpacaError::from)
+            })
+            .await
+    }
+
+    /// Switch to new credentials by rotating the client's
+    /// [`CredentialsHandle`].
+    ///
+    /// With an active session the credential watcher started by
+    /// [`Self::connect`] logs out and on again with the new API key and
+    /// secret and resubscribes market data; a failed re-logon is logged and
+    /// leaves the session disconnected. Otherwise the credentials are used
+    /// by the next [`Self::connect`].
+    pub fn rotate_credentials(&self, credentials: Credentials) {
+        self.credentials.rotate(credentials);
+    }
+
+    /// Re-send the market data requests of every registered subscription.
+    async fn resubscribe_market_data(&self) -> Result<()> {
+        let requests: Vec<MarketDataRequest> = self
+            .md_routes
+            .lock()
+            .await
+            .iter()
+            .map(|(md_req_id, route)| {
+                let mut request =
+                    MarketDataRequest::subscribe(route.symbols.clone()).with_depth(route.depth);
+                request.md_req_id = md_req_id.clone();
+                request
+            })
+            .collect();
+        for request in &requests {
+            self.request_market_data(request).await?;
+        }
+        Ok(())
+    }
+
     /// Disconnect from the FIX server.
     ///
     /// # Errors
     /// Returns error if disconnect fails.
     pub async fn disconnect(&self) -> Result<()> {
+        if let Some(watcher) = self.credential_watcher.lock().await.take() {
+            watcher.abort();
+        }
+        self.close_session().await
+    }
+
+    /// Log out and close the transport, leaving the credential watcher
+    /// running.
+    async fn close_session(&self) -> Result<()> {
         // Send shutdown signal to background tasks
         if let Some(tx) = self.shutdown_tx.lock().await.take() {
             let _ = tx.send(()).await;
         }
+        // Wait for them to stop, so the receive loop neither takes the
+        // Logout reply nor marks a following session disconnected.
+        let tasks = std::mem::take(&mut *self.session_tasks.lock().await);
+        for task in tasks {
+            task.abort();
+            let _ = task.await;
+        }
 
         let mut session = self.session.lock().await;
 
@@ -199,11 +357,42 @@ impl FixClient {
         drop(session);
 
         self.send_raw(&msg).await?;
+        #[cfg(feature = "metrics")]
+        self.round_trips
+            .start(&order.cl_ord_id, MsgType::NewOrderSingle);
 
         tracing::debug!("Sent new order: cl_ord_id={}", order.cl_ord_id);
         Ok(order.cl_ord_id.clone())
     }
 
+    /// Send a multileg order, e.g. an option spread.
+    ///
+    /// # Arguments
+    /// * `order` - New order multileg message
+    ///
+    /// # Errors
+    /// Returns error if the order is invalid or sending fails.
+    pub async fn send_multileg_order(&self, order: &NewOrderMultileg) -> Result<String> {
+        order.validate()?;
+        let session = self.session.lock().await;
+
+        if session.state() != SessionState::Active {
+            return Err(FixError::Session("session not active".to_string()));
+        }
+
+        let fields = Self::build_multileg_fields(order);
+        let msg = session.encode_message(MsgType::NewOrderMultileg.as_str(), &fields);
+        drop(session);
+
+        self.send_raw(&msg).await?;
+        #[cfg(feature = "metrics")]
+        self.round_trips
+            .start(&order.cl_ord_id, MsgType::NewOrderMultileg);
+
+        tracing::debug!("Sent multileg order: cl_ord_id={}", order.cl_ord_id);
+        Ok(order.cl_ord_id.clone())
+    }
+
     /// Cancel an order.
     ///
     /// # Arguments
@@ -229,6 +418,9 @@ impl FixClient {
         drop(session);
 
         self.send_raw(&msg).await?;
+        #[cfg(feature = "metrics")]
+        self.round_trips
+            .start(&cancel.cl_ord_id, MsgType::OrderCancelRequest);
 
         tracing::debug!("Sent cancel request: cl_ord_id={}", cancel.cl_ord_id);
         Ok(cancel.cl_ord_id.clone())
@@ -265,6 +457,9 @@ impl FixClient {
         drop(session);
 
         self.send_raw(&msg).await?;
+        #[cfg(feature = "metrics")]
+        self.round_trips
+            .start(&replace.cl_ord_id, MsgType::OrderCancelReplaceRequest);
 
         tracing::debug!("Sent replace request: cl_ord_id={}", replace.cl_ord_id);
         Ok(replace.cl_ord_id.clone())
@@ -284,15 +479,7 @@ impl FixClient {
             return Err(FixError::Session("session not active".to_string()));
         }
 
-        let fields = vec![
-            (tags::MD_REQ_ID, request.md_req_id.clone()),
-            (
-                tags::SUBSCRIPTION_REQUEST_TYPE,
-                request.subscription_request_type.to_string(),
-            ),
-            (tags::MARKET_DEPTH, request.market_depth.to_string()),
-        ];
-
+        let fields = Self::build_market_data_fields(request);
         let msg = session.encode_message(MsgType::MarketDataRequest.as_str(), &fields);
         drop(session);
 
@@ -302,6 +489,63 @@ impl FixClient {
         Ok(request.md_req_id.clone())
     }
 
+    /// Subscribe to streaming market data.
+    ///
+    /// Sends a Market Data Request (V) for bids, offers and trades and
+    /// returns a stream of the matching snapshot (W) and incremental
+    /// refresh (X) messages. Those messages are consumed by the stream and
+    /// no longer delivered through [`Self::next_message`].
+    ///
+    /// # Arguments
+    /// * `symbols` - Symbols to subscribe
+    /// * `depth` - Market depth (0 = full book, 1 = top of book)
+    ///
+    /// # Errors
+    /// Returns error if the session is not active or the request fails.
+    pub async fn subscribe_market_data(
+        &self,
+        symbols: &[&str],
+        depth: u32,
+    ) -> Result<MarketDataStream> {
+        let symbols: Vec<String> = symbols.iter().map(ToString::to_string).collect();
+        let request = MarketDataRequest::subscribe(symbols.clone()).with_depth(depth);
+        let (sender, receiver) = mpsc::channel(MESSAGE_CHANNEL_SIZE);
+
+        // Register before sending so the first snapshot cannot race the route.
+        self.md_routes.lock().await.insert(
+            request.md_req_id.clone(),
+            MarketDataRoute {
+                symbols,
+                depth,
+                sender,
+            },
+        );
+
+        if let Err(e) = self.request_market_data(&request).await {
+            self.md_routes.lock().await.remove(&request.md_req_id);
+            return Err(e);
+        }
+
+        Ok(MarketDataStream::new(request.md_req_id, receiver))
+    }
+
+    /// Cancel a market data subscription.
+    ///
+    /// Sends an unsubscribe request (263=2) for the stream's MDReqID and
+    /// ends the stream.
+    ///
+    /// # Errors
+    /// Returns error if the session is not active or the request fails.
+    pub async fn unsubscribe_market_data(&self, stream: &MarketDataStream) -> Result<()> {
+        let Some(route) = self.md_routes.lock().await.remove(stream.md_req_id()) else {
+            return Ok(());
+        };
+        let mut request = MarketDataRequest::unsubscribe(route.symbols);
+        request.md_req_id = stream.md_req_id().to_string();
+        self.request_market_data(&request).await?;
+        Ok(())
+    }
+
     /// Receive the next message from the server.
     ///
     /// # Errors
@@ -481,6 +725,96 @@ impl FixClient {
             fields.push((tags::ACCOUNT, account.clone()));
         }
 
+        fields.push((
+            tags::SECURITY_TYPE,
+            order.security_type().as_str().to_string(),
+        ));
+        if let Some(ref option) = order.option {
+            fields.push((tags::MATURITY_MONTH_YEAR, option.maturity_month_year()));
+            fields.push((tags::MATURITY_DATE, option.maturity_date_str()));
+            fields.push((tags::STRIKE_PRICE, option.strike_price.to_string()));
+            fields.push((tags::PUT_OR_CALL, option.put_or_call.as_char().to_string()));
+        }
+
+        if let Some(effect) = order.position_effect {
+            fields.push((tags::POSITION_EFFECT, effect.as_char().to_string()));
+        }
+
+        fields
+    }
+
+    /// Build FIX fields for a multileg order.
+    fn build_multileg_fields(order: &NewOrderMultileg) -> Vec<(u32, String)> {
+        let mut fields = vec![
+            (tags::CL_ORD_ID, order.cl_ord_id.clone()),
+            (tags::ORD_TYPE, order.ord_type.as_char().to_string()),
+            (tags::ORDER_QTY, order.order_qty.to_string()),
+            (
+                tags::TIME_IN_FORCE,
+                order.time_in_force.as_char().to_string(),
+            ),
+        ];
+
+        if let Some(price) = order.price {
+            fields.push((tags::PRICE, price.to_string()));
+        }
+
+        if let Some(ref account) = order.account {
+            fields.push((tags::ACCOUNT, account.clone()));
+        }
+
+        fields.push((tags::NO_LEGS, order.legs.len().to_string()));
+        for leg in &order.legs {
+            fields.push((tags::LEG_SYMBOL, leg.symbol.clone()));
+            fields.push((
+                tags::LEG_SECURITY_TYPE,
+                leg.security_type().as_str().to_string(),
+            ));
+            if let Some(ref option) = leg.option {
+                fields.push((tags::LEG_MATURITY_DATE, option.maturity_date_str()));
+                fields.push((tags::LEG_STRIKE_PRICE, option.strike_price.to_string()));
+                fields.push((
+                    tags::LEG_PUT_OR_CALL,
+                    option.put_or_call.as_char().to_string(),
+                ));
+            }
+            fields.push((tags::LEG_RATIO_QTY, leg.ratio_qty.to_string()));
+            fields.push((tags::LEG_SIDE, leg.side.as_char().to_string()));
+            if let Some(effect) = leg.position_effect {
+                fields.push((tags::LEG_POSITION_EFFECT, effect.as_char().to_string()));
+            }
+        }
+
+        fields
+    }
+
+    /// Build FIX fields for a market data request.
+    fn build_market_data_fields(request: &MarketDataRequest) -> Vec<(u32, String)> {
+        let mut fields = vec![
+            (tags::MD_REQ_ID, request.md_req_id.clone()),
+            (
+                tags::SUBSCRIPTION_REQUEST_TYPE,
+                request.subscription_request_type.to_string(),
+            ),
+            (tags::MARKET_DEPTH, request.market_depth.to_string()),
+        ];
+
+        if request.subscription_request_type == '1' {
+            // Incremental refresh after the initial snapshot.
+            fields.push((tags::MD_UPDATE_TYPE, "1".to_string()));
+        }
+
+        let entry_types = [MdEntryType::Bid, MdEntryType::Offer, MdEntryType::Trade];
+        fields.push((tags::NO_MD_ENTRY_TYPES, entry_types.len().to_string()));
+        for entry_type in entry_types {
+            fields.push((tags::MD_ENTRY_TYPE, entry_type.as_char().to_string()));
+        }
+
+        fields.push((tags::NO_RELATED_SYM, request.symbols.len().to_string()));
+        for symbol in &request.symbols {
+            fields.push((tags::SYMBOL, symbol.clone()));
+        }
+
         fields
     }
 
@@ -533,9 +867,12 @@ impl FixClient {
         // Spawn message receiver task
         let transport_recv = Arc::clone(&transport);
         let session_recv = Arc::clone(&session);
+        let md_routes = Arc::clone(&self.md_routes);
+        #[cfg(feature = "metrics")]
+        let round_trips = self.round_trips.clone();
         let msg_tx_clone = msg_tx.clone();
 
-        tokio::spawn(async move {
+        let receiver = tokio::spawn(async move {
             loop {
                 tokio::select! {
                     _ = shutdown_rx.recv() => {
@@ -560,6 +897,9 @@ impl FixClient {
                     } => {
                         match result {
                             Ok(msg) => {
+                                #[cfg(feature = "metrics")]
+                                round_trips.finish(&msg);
+
                                 // Process session-level messages
                                 if let Some(msg_type) = msg.msg_type() {
                                     match MsgType::from_fix_str(msg_type) {
@@ -585,6 +925,11 @@ impl FixClient {
                                     }
                                 }
 
+                                // Market data for an active subscription goes to its stream
+                                if market_data::route(&md_routes, &msg).await {
+                                    continue;
+                                }
+
                                 // Forward message to channel
                                 if msg_tx_clone.send(msg).await.is_err() {
                                     tracing::debug!("Message channel closed");
@@ -613,7 +958,7 @@ impl FixClient {
         let transport_hb = Arc::clone(&transport);
         let session_hb = Arc::clone(&session);
 
-        tokio::spawn(async move {
+        let heartbeat = tokio::spawn(async move {
             let mut heartbeat_timer = interval(Duration::from_secs(heartbeat_interval.into()));
 
             loop {
@@ -639,6 +984,57 @@ impl FixClient {
                 }
             }
         });
+
+        *self.session_tasks.lock().await = vec![receiver, heartbeat];
+    }
+}
+
+/// Send times of order requests by `ClOrdID`, completed by the first
+/// execution report or cancel reject for that ID.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Default)]
+struct RoundTrips(Arc<std::sync::Mutex<std::collections::HashMap<String, (MsgType, Instant)>>>);
+
+#[cfg(feature = "metrics")]
+impl RoundTrips {
+    fn start(&self, cl_ord_id: &str, msg_type: MsgType) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(cl_ord_id.to_string(), (msg_type, Instant::now()));
+    }
+
+    /// Record the round trip `msg` completes, if any. New orders also
+    /// record their submit latency, failed if the venue rejected them.
+    fn finish(&self, msg: &FixMessage) {
+        let response = msg.msg_type().and_then(MsgType::from_fix_str);
+        if !matches!(
+            response,
+            Some(MsgType::ExecutionReport | MsgType::OrderCancelReject)
+        ) {
+            return;
+        }
+        let Some(cl_ord_id) = msg.get(tags::CL_ORD_ID) else {
+            return;
+        };
+        let Some((request, sent)) = self
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(cl_ord_id)
+        else {
+            return;
+        };
+        let latency = sent.elapsed();
+        alpaca_base::metrics::record_fix_round_trip(request.as_str(), latency);
+        if matches!(request, MsgType::NewOrderSingle | MsgType::NewOrderMultileg) {
+            let rejected = msg
+                .get(tags::EXEC_TYPE)
+                .and_then(|v| v.chars().next())
+                .and_then(ExecType::from_char)
+                == Some(ExecType::Rejected);
+            alpaca_base::metrics::record_order_submit("fix", !rejected, latency);
+        }
     }
 }
 
@@ -646,9 +1042,10 @@ impl FixClient {
 mod tests {
     use super::*;
     use crate::config::FixVersion;
+    use crate::messages::{MultilegLeg, OptionContract, PositionEffect, PutOrCall};
 
-    fn test_credentials() -> Credentials {
-        Credentials::new("test_key".to_string(), "test_secret".to_string())
+    fn test_credentials() -> alpaca_base::Credentials {
+        alpaca_base::Credentials::new("test_key".to_string(), "test_secret".to_string())
     }
 
     #[tokio::test]
@@ -676,4 +1073,193 @@ mod tests {
         let result = client.send_order(&order).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_subscribe_market_data_requires_active_session() {
+        let config = FixConfig::builder()
+            .sender_comp_id("SENDER")
+            .target_comp_id("TARGET")
+            .build();
+
+        let client = FixClient::new(test_credentials(), config);
+        assert!(client.subscribe_market_data(&["AAPL"], 1).await.is_err());
+        assert!(client.md_routes.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_credentials_while_disconnected() {
+        let config = FixConfig::builder()
+            .sender_comp_id("SENDER")
+            .target_comp_id("TARGET")
+            .build();
+        let client = FixClient::new(test_credentials(), config);
+        let rotated = alpaca_base::Credentials::new("NEW_KEY".to_string(), "NEW".to_string());
+        client.rotate_credentials(rotated);
+        assert_eq!(client.credentials().current().api_key, "NEW_KEY");
+        assert_eq!(client.state().await, SessionState::Disconnected);
+    }
+
+    /// Accept FIX connections one after another, answer logons and
+    /// logouts, and report every received message as `(MsgType, 553)`.
+    async fn fake_server() -> (u16, mpsc::UnboundedReceiver<(String, String)>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (seen_tx, seen_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let session = FixSession::new(
+                FixConfig::builder()
+                    .sender_comp_id("TARGET")
+                    .target_comp_id("SENDER")
+                    .build(),
+            );
+            while let Ok((stream, _)) = listener.accept().await {
+                let transport = FixTransport::new(stream);
+                while let Ok(msg) = transport.receive().await {
+                    let msg_type = msg.msg_type().unwrap_or_default().to_string();
+                    let username = msg.get(tags::USERNAME).unwrap_or_default().to_string();
+                    let reply = match MsgType::from_fix_str(&msg_type) {
+                        Some(MsgType::Logon) => Some(session.create_logon(&test_credentials())),
+                        Some(MsgType::Logout) => Some(session.create_logout(None)),
+                        _ => None,
+                    };
+                    let _ = seen_tx.send((msg_type, username));
+                    if let Some(reply) = reply
+                        && transport.send(&reply).await.is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        });
+        (port, seen_rx)
+    }
+
+    /// Next non-heartbeat message seen by the fake server.
+    async fn next_seen(seen: &mut mpsc::UnboundedReceiver<(String, String)>) -> (String, String) {
+        loop {
+            let next = timeout(Duration::from_secs(5), seen.recv())
+                .await
+                .expect("no message from client")
+                .unwrap();
+            if next.0 != MsgType::Heartbeat.as_str() {
+                return next;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shared_handle_rotation_relogs_on() {
+        let (port, mut seen) = fake_server().await;
+        let config = FixConfig::builder()
+            .host("127.0.0.1")
+            .port(port)
+            .sender_comp_id("SENDER")
+            .target_comp_id("TARGET")
+            .build();
+        let handle = CredentialsHandle::new(test_credentials());
+        let client = FixClient::new(handle.clone(), config);
+
+        client.connect().await.unwrap();
+        assert_eq!(
+            next_seen(&mut seen).await,
+            ("A".to_string(), "test_key".to_string())
+        );
+        let stream = client.subscribe_market_data(&["AAPL"], 1).await.unwrap();
+        assert_eq!(next_seen(&mut seen).await.0, "V");
+
+        // Rotating a clone of the handle, as a REST client sharing it would.
+        handle.rotate(Credentials::new("NEW_KEY".to_string(), "NEW".to_string()));
+        assert_eq!(next_seen(&mut seen).await.0, "5");
+        assert_eq!(
+            next_seen(&mut seen).await,
+            ("A".to_string(), "NEW_KEY".to_string())
+        );
+        assert_eq!(next_seen(&mut seen).await.0, "V");
+        assert!(
+            client
+                .md_routes
+                .lock()
+                .await
+                .contains_key(stream.md_req_id())
+        );
+        assert_eq!(client.state().await, SessionState::Active);
+
+        client.disconnect().await.unwrap();
+        assert!(client.credential_watcher.lock().await.is_none());
+    }
+
+    #[test]
+    fn test_build_market_data_fields() {
+        let request = MarketDataRequest::subscribe(vec!["AAPL".to_string(), "MSFT".to_string()])
+            .with_depth(0);
+        let fields = FixClient::build_market_data_fields(&request);
+        let tags_in_order: Vec<u32> = fields.iter().map(|(tag, _)| *tag).collect();
+
+        assert_eq!(
+            tags_in_order,
+            vec![
+                tags::MD_REQ_ID,
+                tags::SUBSCRIPTION_REQUEST_TYPE,
+                tags::MARKET_DEPTH,
+                tags::MD_UPDATE_TYPE,
+                tags::NO_MD_ENTRY_TYPES,
+                tags::MD_ENTRY_TYPE,
+                tags::MD_ENTRY_TYPE,
+                tags::MD_ENTRY_TYPE,
+                tags::NO_RELATED_SYM,
+                tags::SYMBOL,
+                tags::SYMBOL,
+            ]
+        );
+        assert_eq!(fields[2].1, "0");
+        assert_eq!(fields[8].1, "2");
+    }
+
+    #[test]
+    fn test_build_multileg_fields() {
+        let expiry = chrono::NaiveDate::from_ymd_opt(2025, 1, 17).unwrap();
+        let spread = NewOrderMultileg::limit(
+            vec![
+                MultilegLeg::option(
+                    "AAPL",
+                    OptionContract::new(expiry, 190.0, PutOrCall::Call),
+                    Side::Buy,
+                    1.0,
+                )
+                .with_position_effect(PositionEffect::Open),
+                MultilegLeg::option(
+                    "AAPL",
+                    OptionContract::new(expiry, 200.0, PutOrCall::Call),
+                    Side::Sell,
+                    1.0,
+                ),
+            ],
+            5.0,
+            3.25,
+        );
+        assert!(spread.validate().is_ok());
+        let fields = FixClient::build_multileg_fields(&spread);
+        let value = |tag: u32| -> Vec<&str> {
+            fields
+                .iter()
+                .filter(|(t, _)| *t == tag)
+                .map(|(_, v)| v.as_str())
+                .collect()
+        };
+        assert_eq!(value(tags::NO_LEGS), ["2"]);
+        assert_eq!(value(tags::PRICE), ["3.25"]);
+        assert_eq!(value(tags::LEG_SECURITY_TYPE), ["OPT", "OPT"]);
+        assert_eq!(value(tags::LEG_MATURITY_DATE), ["20250117", "20250117"]);
+        assert_eq!(value(tags::LEG_STRIKE_PRICE), ["190", "200"]);
+        assert_eq!(value(tags::LEG_SIDE), ["1", "2"]);
+        assert_eq!(value(tags::LEG_POSITION_EFFECT), ["O"]);
+        let first_leg = fields
+            .iter()
+            .position(|(t, _)| *t == tags::LEG_SYMBOL)
+            .unwrap();
+        assert_eq!(fields[first_leg - 1], (tags::NO_LEGS, "2".to_string()));
+
+        let single = NewOrderMultileg::market(spread.legs[..1].to_vec(), 1.0);
+        assert!(single.validate().is_err());
+    }
 }
@@ -69,6 +69,10 @@ pub mod tags {
     pub const HEART_BT_INT: u32 = 108;
     /// Encrypt method.
     pub const ENCRYPT_METHOD: u32 = 98;
+    /// Username (API key ID).
+    pub const USERNAME: u32 = 553;
+    /// Password (API secret key).
+    pub const PASSWORD: u32 = 554;
     /// Reset sequence number flag.
     pub const RESET_SEQ_NUM_FLAG: u32 = 141;
     /// Test request ID.
@@ -89,6 +93,48 @@ pub mod tags {
     pub const MD_ENTRY_PX: u32 = 270;
     /// MD entry size.
     pub const MD_ENTRY_SIZE: u32 = 271;
+    /// Number of related symbols (repeating group count).
+    pub const NO_RELATED_SYM: u32 = 146;
+    /// MD update type (full refresh or incremental).
+    pub const MD_UPDATE_TYPE: u32 = 265;
+    /// Number of MD entry types (repeating group count).
+    pub const NO_MD_ENTRY_TYPES: u32 = 267;
+    /// Number of MD entries (repeating group count).
+    pub const NO_MD_ENTRIES: u32 = 268;
+    /// MD update action.
+    pub const MD_UPDATE_ACTION: u32 = 279;
+    /// MD request reject reason.
+    pub const MD_REQ_REJ_REASON: u32 = 281;
+    /// Security type (CS or OPT).
+    pub const SECURITY_TYPE: u32 = 167;
+    /// Maturity month and year (YYYYMM).
+    pub const MATURITY_MONTH_YEAR: u32 = 200;
+    /// Put or call.
+    pub const PUT_OR_CALL: u32 = 201;
+    /// Strike price.
+    pub const STRIKE_PRICE: u32 = 202;
+    /// Maturity date (YYYYMMDD).
+    pub const MATURITY_DATE: u32 = 541;
+    /// Position effect (open or close).
+    pub const POSITION_EFFECT: u32 = 77;
+    /// Number of legs (repeating group count).
+    pub const NO_LEGS: u32 = 555;
+    /// Leg position effect.
+    pub const LEG_POSITION_EFFECT: u32 = 564;
+    /// Leg symbol.
+    pub const LEG_SYMBOL: u32 = 600;
+    /// Leg security type.
+    pub const LEG_SECURITY_TYPE: u32 = 609;
+    /// Leg maturity date.
+    pub const LEG_MATURITY_DATE: u32 = 611;
+    /// Leg strike price.
+    pub const LEG_STRIKE_PRICE: u32 = 612;
+    /// Leg ratio quantity.
+    pub const LEG_RATIO_QTY: u32 = 623;
+    /// Leg side.
+    pub const LEG_SIDE: u32 = 624;
+    /// Leg put or call.
+    pub const LEG_PUT_OR_CALL: u32 = 1358;
 }
 
 /// Raw FIX message representation.
@@ -132,6 +178,22 @@ impl FixMessage {
     pub fn has(&self, tag: u32) -> bool {
         self.fields.contains_key(&tag)
     }
+
+    /// All fields in wire order, including repeated tags.
+    ///
+    /// `fields` keeps only the last value of a repeated tag, so repeating
+    /// groups must be read from here. Parsed from `raw`; messages built
+    /// by hand with an empty `raw` yield no fields.
+    #[must_use]
+    pub fn ordered_fields(&self) -> Vec<(u32, &str)> {
+        self.raw
+            .split(SOH)
+            .filter_map(|field| {
+                let (tag, value) = field.split_once('=')?;
+                Some((tag.parse().ok()?, value))
+            })
+            .collect()
+    }
 }
 
 impl Default for FixMessage {
@@ -227,6 +289,11 @@ impl FixDecoder {
     }
 
     /// Decode a FIX message.
+    ///
+    /// Any numeric tag is accepted, including ones this crate has no
+    /// constant for; repeated tags keep their last value in
+    /// [`FixMessage::fields`]. Repeating groups are read with
+    /// [`FixMessage::group`].
     pub fn decode(&self, data: &str) -> Result<FixMessage> {
         let mut msg = FixMessage::new();
         msg.raw = data.to_string();
@@ -259,19 +326,25 @@ impl FixDecoder {
     }
 
     /// Validate message checksum.
+    ///
+    /// Returns `false`, never panics, for truncated or malformed input.
     #[must_use]
     pub fn validate_checksum(&self, data: &str) -> bool {
-        // Find checksum field position
-        if let Some(pos) = data.rfind(&format!("{}=", tags::CHECKSUM)) {
-            let body = &data[..pos];
-            let checksum_str = &data[pos + 3..data.len() - 1]; // Skip "10=" and trailing SOH
-
-            if let Ok(expected) = checksum_str.parse::<u8>() {
+        let Some(message) = data.strip_suffix(SOH) else {
+            return false;
+        };
+        // The checksum is the last field; the body ends with the SOH before it.
+        let Some(pos) = message.rfind(&format!("{}{}=", SOH, tags::CHECKSUM)) else {
+            return false;
+        };
+        let (body, checksum) = (&message[..=pos], &message[pos + 4..]);
+        match checksum.parse::<u8>() {
+            Ok(expected) => {
                 let calculated = body.bytes().fold(0u32, |acc, b| acc + b as u32) as u8;
-                return calculated == expected;
+                calculated == expected
             }
+            Err(_) => false,
         }
-        false
     }
 }
 
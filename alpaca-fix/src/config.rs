@@ -1,6 +1,8 @@
 //! FIX protocol configuration types.
 
+use alpaca_base::{Backoff, Jitter, RetryPolicy};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// FIX protocol version.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -31,6 +33,60 @@ impl std::fmt::Display for FixVersion {
     }
 }
 
+/// Message encryption method (Tag 98) proposed at logon.
+///
+/// The server echoes the method it accepts; a session only starts when it
+/// matches. Alpaca sessions run over TLS and use [`EncryptMethod::None`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum EncryptMethod {
+    /// No message-level encryption (0).
+    #[default]
+    None,
+    /// PKCS (1).
+    Pkcs,
+    /// DES, ECB mode (2).
+    Des,
+    /// PKCS/DES (3).
+    PkcsDes,
+    /// PGP/DES (4).
+    PgpDes,
+    /// PGP/DES-MD5 (5).
+    PgpDesMd5,
+    /// PEM/DES-MD5 (6).
+    PemDesMd5,
+}
+
+impl EncryptMethod {
+    /// Get the FIX value.
+    #[must_use]
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Pkcs => 1,
+            Self::Des => 2,
+            Self::PkcsDes => 3,
+            Self::PgpDes => 4,
+            Self::PgpDesMd5 => 5,
+            Self::PemDesMd5 => 6,
+        }
+    }
+
+    /// Parse from FIX value.
+    #[must_use]
+    pub fn from_fix_str(s: &str) -> Option<Self> {
+        match s {
+            "0" => Some(Self::None),
+            "1" => Some(Self::Pkcs),
+            "2" => Some(Self::Des),
+            "3" => Some(Self::PkcsDes),
+            "4" => Some(Self::PgpDes),
+            "5" => Some(Self::PgpDesMd5),
+            "6" => Some(Self::PemDesMd5),
+            _ => None,
+        }
+    }
+}
+
 /// FIX session configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FixConfig {
@@ -56,6 +112,9 @@ pub struct FixConfig {
     pub message_logging: bool,
     /// Reset sequence numbers on logon.
     pub reset_on_logon: bool,
+    /// Encryption method proposed at logon.
+    #[serde(default)]
+    pub encrypt_method: EncryptMethod,
 }
 
 impl Default for FixConfig {
@@ -72,6 +131,7 @@ impl Default for FixConfig {
             reconnect_delay_ms: 1000,
             message_logging: false,
             reset_on_logon: false,
+            encrypt_method: EncryptMethod::None,
         }
     }
 }
@@ -82,6 +142,25 @@ impl FixConfig {
     pub fn builder() -> FixConfigBuilder {
         FixConfigBuilder::default()
     }
+
+    /// The reconnection retry policy: `reconnect_max_attempts` attempts
+    /// (one when reconnection is disabled), starting `reconnect_delay_ms`
+    /// apart and doubling with equal jitter up to one minute.
+    #[must_use]
+    pub fn reconnect_policy(&self) -> RetryPolicy {
+        let attempts = if self.reconnect_enabled {
+            self.reconnect_max_attempts
+        } else {
+            1
+        };
+        RetryPolicy::new().max_attempts(attempts).backoff(
+            Backoff::new(
+                Duration::from_millis(self.reconnect_delay_ms),
+                Duration::from_secs(60),
+            )
+            .jitter(Jitter::Equal),
+        )
+    }
 }
 
 /// Builder for FIX configuration.
@@ -168,6 +247,13 @@ impl FixConfigBuilder {
         self
     }
 
+    /// Set the encryption method proposed at logon.
+    #[must_use]
+    pub fn encrypt_method(mut self, method: EncryptMethod) -> Self {
+        self.config.encrypt_method = method;
+        self
+    }
+
     /// Build the configuration.
     #[must_use]
     pub fn build(self) -> FixConfig {
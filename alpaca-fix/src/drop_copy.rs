@@ -0,0 +1,152 @@
+//! Drop-copy sessions.
+//!
+//! A drop copy is a second, receive-only FIX session on which the server
+//! copies the execution reports of all of an account's order flow,
+//! including orders placed over the REST API or another FIX session. Risk
+//! and compliance systems use it to see every fill without routing orders
+//! themselves.
+//!
+//! [`DropCopySession`] logs on with its own CompIDs and exposes only
+//! session management and a [`DropCopyStream`] of [`ExecutionReport`]s;
+//! it has no way to send orders. Heartbeats and test requests are answered
+//! by the underlying session as usual.
+
+use crate::client::FixClient;
+use crate::config::FixConfig;
+use crate::error::{FixError, Result};
+use crate::messages::{ExecutionReport, MsgType};
+use crate::session::SessionState;
+use alpaca_base::{CancellationToken, CredentialsHandle};
+use futures_util::stream::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Channel buffer size for execution reports.
+const REPORT_CHANNEL_SIZE: usize = 1000;
+
+/// A receive-only FIX session for execution reports across all order flow.
+#[derive(Debug, Clone)]
+pub struct DropCopySession {
+    client: Arc<FixClient>,
+}
+
+impl DropCopySession {
+    /// Create a drop-copy session. `config` carries the CompIDs assigned
+    /// to the drop-copy session, which differ from the order-routing ones.
+    #[must_use]
+    pub fn new(credentials: impl Into<CredentialsHandle>, config: FixConfig) -> Self {
+        Self {
+            client: Arc::new(FixClient::new(credentials, config)),
+        }
+    }
+
+    /// Get the current session state.
+    pub async fn state(&self) -> SessionState {
+        self.client.state().await
+    }
+
+    /// Connect and log on.
+    ///
+    /// # Errors
+    /// Returns error if connection or logon fails.
+    pub async fn connect(&self) -> Result<()> {
+        self.client.connect().await
+    }
+
+    /// Connect, retrying under [`FixConfig::reconnect_policy`].
+    ///
+    /// # Errors
+    /// Returns the last connection error, or
+    /// [`AlpacaError::Cancelled`](alpaca_base::AlpacaError::Cancelled).
+    pub async fn connect_with_retry(&self, cancel: &CancellationToken) -> alpaca_base::Result<()> {
+        self.client.connect_with_retry(cancel).await
+    }
+
+    /// Log out and disconnect.
+    ///
+    /// # Errors
+    /// Returns error if disconnect fails.
+    pub async fn disconnect(&self) -> Result<()> {
+        self.client.disconnect().await
+    }
+
+    /// Stream the execution reports received on the session.
+    ///
+    /// Messages other than execution reports are skipped, as are reports
+    /// that fail to parse (logged). The stream ends when the session is
+    /// disconnected; call it once per connection, since concurrent streams
+    /// would split the reports between them.
+    #[must_use]
+    pub fn execution_reports(&self) -> DropCopyStream {
+        let (sender, receiver) = mpsc::channel(REPORT_CHANNEL_SIZE);
+        let client = Arc::clone(&self.client);
+        tokio::spawn(async move {
+            loop {
+                let msg = match client.next_message().await {
+                    Ok(msg) => msg,
+                    Err(FixError::Connection(_) | FixError::Session(_)) => break,
+                    Err(e) => {
+                        tracing::warn!("Drop-copy receive failed: {}", e);
+                        continue;
+                    }
+                };
+                if msg.msg_type().and_then(MsgType::from_fix_str) != Some(MsgType::ExecutionReport)
+                {
+                    continue;
+                }
+                match client.parse_execution_report(&msg) {
+                    Ok(report) => {
+                        if sender.send(report).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => tracing::warn!("Skipping drop-copy execution report: {}", e),
+                }
+            }
+            tracing::debug!("Drop-copy stream ended");
+        });
+        DropCopyStream { receiver }
+    }
+}
+
+/// Execution reports from a [`DropCopySession`].
+pub struct DropCopyStream {
+    receiver: mpsc::Receiver<ExecutionReport>,
+}
+
+impl std::fmt::Debug for DropCopyStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DropCopyStream").finish()
+    }
+}
+
+impl Stream for DropCopyStream {
+    type Item = ExecutionReport;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn test_execution_reports_end_when_disconnected() {
+        let config = FixConfig::builder()
+            .sender_comp_id("DROPCOPY")
+            .target_comp_id("ALPACA")
+            .build();
+        let session = DropCopySession::new(
+            alpaca_base::Credentials::new("key".to_string(), "secret".to_string()),
+            config,
+        );
+        assert_eq!(session.state().await, SessionState::Disconnected);
+        let mut reports = session.execution_reports();
+        assert!(reports.next().await.is_none());
+    }
+}
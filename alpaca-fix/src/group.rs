@@ -0,0 +1,425 @@
+//! Typed repeating groups.
+//!
+//! A repeating group is a count field (e.g. NoMDEntries, Tag 268) followed
+//! by that many entries, each starting with the same delimiter tag.
+//! [`FixMessage::group`] splits one out of a decoded message and decodes
+//! every entry with its [`GroupMember`] implementation:
+//!
+//! ```rust,ignore
+//! let entries: RepeatingGroup<MarketDataEntry> = msg.group()?;
+//! let legs: RepeatingGroup<MultilegLeg> = msg.group()?;
+//! ```
+//!
+//! Parsing is tolerant of tags the member type does not know: inside an
+//! entry they are kept (and ignored by the decoder); after the last entry,
+//! the first unknown tag ends the group. A count that does not match the
+//! entries found, or an entry not starting with the delimiter, is a
+//! [`FixError::Decoding`] error rather than a silently truncated group.
+
+use crate::codec::{FixMessage, tags};
+use crate::error::{FixError, Result};
+use crate::messages::{
+    MarketDataEntry, MultilegLeg, OptionContract, PositionEffect, PutOrCall, SecurityType, Side,
+};
+use chrono::NaiveDate;
+use std::str::FromStr;
+
+/// One entry of a repeating group, in wire order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupEntry<'a> {
+    fields: Vec<(u32, &'a str)>,
+}
+
+impl<'a> GroupEntry<'a> {
+    /// The first value of `tag` in the entry.
+    #[must_use]
+    pub fn get(&self, tag: u32) -> Option<&'a str> {
+        self.fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, value)| *value)
+    }
+
+    /// The fields of the entry, including unknown tags.
+    #[must_use]
+    pub fn fields(&self) -> &[(u32, &'a str)] {
+        &self.fields
+    }
+
+    /// The first character of `tag`.
+    #[must_use]
+    pub fn get_char(&self, tag: u32) -> Option<char> {
+        self.get(tag).and_then(|value| value.chars().next())
+    }
+
+    /// Parse the required field `tag`, called `name` in errors.
+    ///
+    /// # Errors
+    /// Returns [`FixError::InvalidMessage`] if it is missing and
+    /// [`FixError::Decoding`] if it does not parse.
+    pub fn parse<T: FromStr>(&self, tag: u32, name: &str) -> Result<T> {
+        self.parse_opt(tag, name)?
+            .ok_or_else(|| FixError::InvalidMessage(format!("missing {name}")))
+    }
+
+    /// Parse the optional field `tag`, called `name` in errors.
+    ///
+    /// # Errors
+    /// Returns [`FixError::Decoding`] if it is present but does not parse.
+    pub fn parse_opt<T: FromStr>(&self, tag: u32, name: &str) -> Result<Option<T>> {
+        self.get(tag)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| FixError::Decoding(format!("invalid {name}: {value}")))
+            })
+            .transpose()
+    }
+}
+
+/// A type decoded from one entry of a repeating group.
+pub trait GroupMember: Sized {
+    /// Tag of the entry count (e.g. NoMDEntries, Tag 268).
+    const COUNT_TAG: u32;
+    /// Tag every entry starts with.
+    const DELIMITER: u32;
+    /// Tags that belong to an entry; any other tag after the last entry
+    /// ends the group.
+    const TAGS: &'static [u32];
+
+    /// Decode one entry.
+    ///
+    /// # Errors
+    /// Returns an error if a required field is missing or malformed.
+    fn decode(entry: &GroupEntry<'_>) -> Result<Self>;
+}
+
+/// The decoded entries of a repeating group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepeatingGroup<T> {
+    entries: Vec<T>,
+}
+
+impl<T> Default for RepeatingGroup<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> RepeatingGroup<T> {
+    /// Number of entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the group has no entries (or is absent).
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries, in wire order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.entries.iter()
+    }
+
+    /// The entries, in wire order.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.entries
+    }
+
+    /// Take the entries.
+    #[must_use]
+    pub fn into_vec(self) -> Vec<T> {
+        self.entries
+    }
+}
+
+impl<T> IntoIterator for RepeatingGroup<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RepeatingGroup<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl FixMessage {
+    /// Decode the repeating group of `T`; empty if the message has none.
+    ///
+    /// # Errors
+    /// Returns [`FixError::Decoding`] if the group is malformed, or the
+    /// error decoding an entry.
+    pub fn group<T: GroupMember>(&self) -> Result<RepeatingGroup<T>> {
+        let entries = self
+            .group_entries(T::COUNT_TAG, T::DELIMITER, T::TAGS)?
+            .iter()
+            .map(T::decode)
+            .collect::<Result<_>>()?;
+        Ok(RepeatingGroup { entries })
+    }
+
+    /// Split the repeating group counted by `count_tag` into entries
+    /// starting at `delimiter`, ending at the first tag outside `member_tags`
+    /// after the last entry. Empty if `count_tag` is absent.
+    ///
+    /// # Errors
+    /// Returns [`FixError::Decoding`] if the count is not a number, an
+    /// entry does not start with `delimiter`, or the number of entries does
+    /// not match the count.
+    pub fn group_entries(
+        &self,
+        count_tag: u32,
+        delimiter: u32,
+        member_tags: &[u32],
+    ) -> Result<Vec<GroupEntry<'_>>> {
+        let fields = self.ordered_fields();
+        let Some(start) = fields.iter().position(|(tag, _)| *tag == count_tag) else {
+            return Ok(Vec::new());
+        };
+        let count: usize = fields[start].1.parse().map_err(|_| {
+            FixError::Decoding(format!(
+                "invalid count for group {count_tag}: {}",
+                fields[start].1
+            ))
+        })?;
+
+        let mut entries: Vec<GroupEntry<'_>> = Vec::new();
+        for &(tag, value) in &fields[start + 1..] {
+            if tag == tags::CHECKSUM {
+                break;
+            }
+            if tag == delimiter {
+                if entries.len() == count {
+                    return Err(FixError::Decoding(format!(
+                        "group {count_tag} has more than {count} entries"
+                    )));
+                }
+                entries.push(GroupEntry::default());
+            } else if entries.len() == count && (entries.is_empty() || !member_tags.contains(&tag))
+            {
+                break;
+            }
+            let Some(entry) = entries.last_mut() else {
+                return Err(FixError::Decoding(format!(
+                    "group {count_tag} entry starts with tag {tag}, expected {delimiter}"
+                )));
+            };
+            entry.fields.push((tag, value));
+        }
+        if entries.len() != count {
+            return Err(FixError::Decoding(format!(
+                "group {count_tag} declares {count} entries, found {}",
+                entries.len()
+            )));
+        }
+        Ok(entries)
+    }
+}
+
+/// Entries of a Market Data Snapshot/Full Refresh (MsgType W).
+impl GroupMember for MarketDataEntry {
+    const COUNT_TAG: u32 = tags::NO_MD_ENTRIES;
+    const DELIMITER: u32 = tags::MD_ENTRY_TYPE;
+    const TAGS: &'static [u32] = &[tags::MD_ENTRY_TYPE, tags::MD_ENTRY_PX, tags::MD_ENTRY_SIZE];
+
+    fn decode(entry: &GroupEntry<'_>) -> Result<Self> {
+        Ok(Self {
+            md_entry_type: entry
+                .get_char(tags::MD_ENTRY_TYPE)
+                .ok_or_else(|| FixError::InvalidMessage("missing MDEntryType".to_string()))?,
+            md_entry_px: entry.parse(tags::MD_ENTRY_PX, "MDEntryPx")?,
+            md_entry_size: entry
+                .parse_opt(tags::MD_ENTRY_SIZE, "MDEntrySize")?
+                .unwrap_or(0.0),
+        })
+    }
+}
+
+/// An instrument of a NoRelatedSym group (Tag 146).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelatedSymbol {
+    /// Symbol (Tag 55); the underlying for options.
+    pub symbol: String,
+    /// Security type (Tag 167).
+    pub security_type: Option<SecurityType>,
+    /// Option contract terms (Tags 541, 202, 201), if all are present.
+    pub option: Option<OptionContract>,
+}
+
+impl GroupMember for RelatedSymbol {
+    const COUNT_TAG: u32 = tags::NO_RELATED_SYM;
+    const DELIMITER: u32 = tags::SYMBOL;
+    const TAGS: &'static [u32] = &[
+        tags::SYMBOL,
+        tags::SECURITY_TYPE,
+        tags::MATURITY_MONTH_YEAR,
+        tags::MATURITY_DATE,
+        tags::STRIKE_PRICE,
+        tags::PUT_OR_CALL,
+    ];
+
+    fn decode(entry: &GroupEntry<'_>) -> Result<Self> {
+        let option = match (
+            entry.get(tags::MATURITY_DATE),
+            entry.parse_opt(tags::STRIKE_PRICE, "StrikePrice")?,
+            entry.get_char(tags::PUT_OR_CALL),
+        ) {
+            (Some(date), Some(strike), Some(put_or_call)) => Some(OptionContract::new(
+                parse_date(date, "MaturityDate")?,
+                strike,
+                put_or_call_from(put_or_call, "PutOrCall")?,
+            )),
+            _ => None,
+        };
+        Ok(Self {
+            symbol: entry.parse(tags::SYMBOL, "Symbol")?,
+            security_type: entry
+                .get(tags::SECURITY_TYPE)
+                .and_then(SecurityType::from_fix_str),
+            option,
+        })
+    }
+}
+
+/// Legs of a New Order Multileg (MsgType AB) or multileg execution report.
+impl GroupMember for MultilegLeg {
+    const COUNT_TAG: u32 = tags::NO_LEGS;
+    const DELIMITER: u32 = tags::LEG_SYMBOL;
+    const TAGS: &'static [u32] = &[
+        tags::LEG_SYMBOL,
+        tags::LEG_SECURITY_TYPE,
+        tags::LEG_MATURITY_DATE,
+        tags::LEG_STRIKE_PRICE,
+        tags::LEG_PUT_OR_CALL,
+        tags::LEG_RATIO_QTY,
+        tags::LEG_SIDE,
+        tags::LEG_POSITION_EFFECT,
+    ];
+
+    fn decode(entry: &GroupEntry<'_>) -> Result<Self> {
+        let option = if entry.get(tags::LEG_SECURITY_TYPE) == Some(SecurityType::Option.as_str()) {
+            let date = entry
+                .get(tags::LEG_MATURITY_DATE)
+                .ok_or_else(|| FixError::InvalidMessage("missing LegMaturityDate".to_string()))?;
+            let put_or_call = entry
+                .get_char(tags::LEG_PUT_OR_CALL)
+                .ok_or_else(|| FixError::InvalidMessage("missing LegPutOrCall".to_string()))?;
+            Some(OptionContract::new(
+                parse_date(date, "LegMaturityDate")?,
+                entry.parse(tags::LEG_STRIKE_PRICE, "LegStrikePrice")?,
+                put_or_call_from(put_or_call, "LegPutOrCall")?,
+            ))
+        } else {
+            None
+        };
+        let side = entry
+            .get_char(tags::LEG_SIDE)
+            .ok_or_else(|| FixError::InvalidMessage("missing LegSide".to_string()))?;
+        Ok(Self {
+            symbol: entry.parse(tags::LEG_SYMBOL, "LegSymbol")?,
+            side: Side::from_char(side)
+                .ok_or_else(|| FixError::Decoding(format!("invalid LegSide: {side}")))?,
+            ratio_qty: entry
+                .parse_opt(tags::LEG_RATIO_QTY, "LegRatioQty")?
+                .unwrap_or(1.0),
+            option,
+            position_effect: match entry.get_char(tags::LEG_POSITION_EFFECT) {
+                Some(c) => Some(PositionEffect::from_char(c).ok_or_else(|| {
+                    FixError::Decoding(format!("invalid LegPositionEffect: {c}"))
+                })?),
+                None => None,
+            },
+        })
+    }
+}
+
+fn parse_date(value: &str, name: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .map_err(|_| FixError::Decoding(format!("invalid {name}: {value}")))
+}
+
+fn put_or_call_from(c: char, name: &str) -> Result<PutOrCall> {
+    PutOrCall::from_char(c).ok_or_else(|| FixError::Decoding(format!("invalid {name}: {c}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::FixDecoder;
+
+    fn decode(body: &str) -> FixMessage {
+        FixDecoder::new()
+            .decode(&format!("8=FIX.4.4\x01{body}10=000\x01"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_repeating_groups() {
+        // The 9999 vendor tag inside an entry is tolerated; 58 after the
+        // last entry ends the group.
+        let msg = decode(
+            "35=AB\x0111=c1\x01555=2\x01\
+             600=AAPL\x01609=OPT\x01611=20250117\x01612=190\x011358=1\x01\
+             9999=x\x01623=1\x01624=1\x01564=O\x01\
+             600=AAPL\x01609=CS\x01623=100\x01624=2\x01\
+             58=note\x01",
+        );
+        let legs: RepeatingGroup<MultilegLeg> = msg.group().unwrap();
+        assert_eq!(legs.len(), 2);
+        let option = legs.as_slice()[0].option.unwrap();
+        assert_eq!(option.strike_price, 190.0);
+        assert_eq!(option.put_or_call, PutOrCall::Call);
+        assert_eq!(legs.as_slice()[1].side, Side::Sell);
+        assert_eq!(legs.as_slice()[1].ratio_qty, 100.0);
+        assert!(msg.group::<RelatedSymbol>().unwrap().is_empty());
+
+        let request = decode("35=V\x01262=r\x01146=2\x0155=AAPL\x0155=MSFT\x01167=CS\x01");
+        let symbols: Vec<_> = request
+            .group::<RelatedSymbol>()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.symbol)
+            .collect();
+        assert_eq!(symbols, ["AAPL", "MSFT"]);
+
+        for malformed in [
+            "35=W\x01268=2\x01269=0\x01270=1\x01",
+            "35=W\x01268=1\x01269=0\x01270=1\x01269=1\x01270=2\x01",
+            "35=W\x01268=1\x01270=1\x01269=0\x01",
+            "35=W\x01268=-1\x01",
+        ] {
+            assert!(matches!(
+                decode(malformed).group::<MarketDataEntry>(),
+                Err(FixError::Decoding(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_truncated_input_never_panics() {
+        let raw = "8=FIX.4.4\x019=60\x0135=W\x0155=AAPL\x01268=2\x01\
+                   269=0\x01270=175.5\x01271=100\x01269=1\x01270=175.6\x0110=123\x01";
+        let decoder = FixDecoder::new();
+        for end in 0..=raw.len() {
+            let data = &raw[..end];
+            let _ = decoder.validate_checksum(data);
+            if let Ok(msg) = decoder.decode(data) {
+                let _ = msg.group::<MarketDataEntry>();
+            }
+        }
+    }
+}
@@ -9,9 +9,13 @@
 //!
 //! - FIX session management with heartbeat and sequence numbers
 //! - Order routing (New Order Single, Cancel, Cancel/Replace)
-//! - Execution reports
-//! - Market data requests and snapshots
+//! - Option orders and multileg spreads (New Order Multileg)
+//! - Execution reports, including a receive-only drop-copy session
+//!   (requires the `unstable` feature)
+//! - Market data subscriptions streamed as typed book and trade updates
+//! - Typed repeating groups (NoMDEntries, NoRelatedSym, NoLegs)
 //! - Session recovery
+//! - [`TradingApi`](alpaca_base::TradingApi) implementation for transport-agnostic strategies
 //!
 //! ## Example
 //!
@@ -26,17 +30,33 @@
 //!
 //! let client = FixClient::new(credentials, config);
 //! ```
+//!
+//! [`prelude`] re-exports the stable, commonly used types; experimental
+//! surfaces require the `unstable` feature and may change in minor releases.
 
 pub mod client;
 pub mod codec;
 pub mod config;
+#[cfg(feature = "unstable")]
+pub mod drop_copy;
 pub mod error;
+pub mod group;
+pub mod market_data;
 pub mod messages;
+pub mod prelude;
 pub mod session;
+pub mod trading;
 pub mod transport;
 
 pub use client::FixClient;
-pub use config::{FixConfig, FixVersion};
+pub use config::{EncryptMethod, FixConfig, FixVersion};
+#[cfg(feature = "unstable")]
+pub use drop_copy::{DropCopySession, DropCopyStream};
 pub use error::FixError;
+pub use group::{GroupEntry, GroupMember, RelatedSymbol, RepeatingGroup};
+pub use market_data::{
+    BookUpdate, MarketDataIncrement, MarketDataStream, MarketDataUpdate, MdEntryType,
+    MdUpdateAction, TradeUpdate,
+};
 pub use messages::*;
 pub use transport::FixTransport;
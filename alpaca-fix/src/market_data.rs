@@ -0,0 +1,523 @@
+//! Market data subscription streaming.
+//!
+//! [`FixClient::subscribe_market_data`](crate::FixClient::subscribe_market_data)
+//! sends a Market Data Request (V) and returns a [`MarketDataStream`] that
+//! yields typed updates decoded from the snapshot (W), incremental refresh
+//! (X), and request reject (Y) messages carrying the same MDReqID.
+
+use crate::codec::{FixMessage, tags};
+use crate::error::{FixError, Result};
+use crate::group::{GroupEntry, GroupMember};
+use crate::messages::{MarketDataEntry, MarketDataSnapshot, MsgType};
+use futures_util::stream::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// Market data entry type (Tag 269).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MdEntryType {
+    /// Bid.
+    Bid,
+    /// Offer.
+    Offer,
+    /// Trade.
+    Trade,
+}
+
+impl MdEntryType {
+    /// Get the FIX tag value.
+    #[must_use]
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Bid => '0',
+            Self::Offer => '1',
+            Self::Trade => '2',
+        }
+    }
+
+    /// Parse from FIX value.
+    #[must_use]
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '0' => Some(Self::Bid),
+            '1' => Some(Self::Offer),
+            '2' => Some(Self::Trade),
+            _ => None,
+        }
+    }
+}
+
+/// Market data update action (Tag 279).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MdUpdateAction {
+    /// New price level.
+    New,
+    /// Changed price level.
+    Change,
+    /// Deleted price level.
+    Delete,
+}
+
+impl MdUpdateAction {
+    /// Get the FIX tag value.
+    #[must_use]
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::New => '0',
+            Self::Change => '1',
+            Self::Delete => '2',
+        }
+    }
+
+    /// Parse from FIX value.
+    #[must_use]
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '0' => Some(Self::New),
+            '1' => Some(Self::Change),
+            '2' => Some(Self::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Incremental change to one side of a symbol's order book.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookUpdate {
+    /// Symbol (Tag 55).
+    pub symbol: String,
+    /// Update action (Tag 279).
+    pub action: MdUpdateAction,
+    /// Book side: [`MdEntryType::Bid`] or [`MdEntryType::Offer`].
+    pub side: MdEntryType,
+    /// Price level (Tag 270).
+    pub price: f64,
+    /// Size at the level (Tag 271).
+    pub size: f64,
+}
+
+/// Trade print from an incremental refresh.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeUpdate {
+    /// Symbol (Tag 55).
+    pub symbol: String,
+    /// Trade price (Tag 270).
+    pub price: f64,
+    /// Trade size (Tag 271).
+    pub size: f64,
+}
+
+/// Typed market data update yielded by a [`MarketDataStream`].
+#[derive(Debug, Clone)]
+pub enum MarketDataUpdate {
+    /// Full refresh of a symbol's book (MsgType W).
+    Snapshot(MarketDataSnapshot),
+    /// Incremental book change (MsgType X, bid/offer entry).
+    Book(BookUpdate),
+    /// Trade (MsgType X, trade entry).
+    Trade(TradeUpdate),
+    /// The server rejected the request (MsgType Y). No further updates follow.
+    Rejected {
+        /// Request ID (Tag 262).
+        md_req_id: String,
+        /// Reject reason (Tag 281) or text (Tag 58), if provided.
+        reason: Option<String>,
+    },
+}
+
+/// Stream of market data updates for one subscription.
+///
+/// Ends when the FIX session's receive loop stops, the subscription is
+/// cancelled with
+/// [`FixClient::unsubscribe_market_data`](crate::FixClient::unsubscribe_market_data),
+/// or the consumer falls so far behind that the stream's buffer fills; the
+/// receive loop never waits for a slow consumer.
+pub struct MarketDataStream {
+    md_req_id: String,
+    receiver: mpsc::Receiver<MarketDataUpdate>,
+}
+
+impl MarketDataStream {
+    /// Create a new market data stream.
+    pub(crate) fn new(md_req_id: String, receiver: mpsc::Receiver<MarketDataUpdate>) -> Self {
+        Self {
+            md_req_id,
+            receiver,
+        }
+    }
+
+    /// Request ID (Tag 262) of the subscription backing this stream.
+    #[must_use]
+    pub fn md_req_id(&self) -> &str {
+        &self.md_req_id
+    }
+}
+
+impl std::fmt::Debug for MarketDataStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MarketDataStream")
+            .field("md_req_id", &self.md_req_id)
+            .finish()
+    }
+}
+
+impl Stream for MarketDataStream {
+    type Item = MarketDataUpdate;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// An active subscription registered with the client's receive loop.
+#[derive(Debug)]
+pub(crate) struct MarketDataRoute {
+    /// Symbols requested, used to route refreshes that omit MDReqID.
+    pub(crate) symbols: Vec<String>,
+    /// Market depth requested, used to resubscribe after a new logon.
+    pub(crate) depth: u32,
+    /// Channel feeding the subscriber's stream.
+    pub(crate) sender: mpsc::Sender<MarketDataUpdate>,
+}
+
+/// Active subscriptions keyed by MDReqID.
+pub(crate) type MarketDataRoutes = Arc<Mutex<HashMap<String, MarketDataRoute>>>;
+
+/// Deliver a market data message to its subscriber.
+///
+/// Returns `true` when the message was a market data message owned by a
+/// registered subscription (and therefore consumed), `false` when it should
+/// be forwarded to the general message channel.
+pub(crate) async fn route(routes: &MarketDataRoutes, msg: &FixMessage) -> bool {
+    let Some(msg_type) = msg.msg_type().and_then(MsgType::from_fix_str) else {
+        return false;
+    };
+    let updates = match msg_type {
+        MsgType::MarketDataSnapshot => match parse_snapshot(msg) {
+            Ok(snapshot) => vec![MarketDataUpdate::Snapshot(snapshot)],
+            Err(e) => {
+                tracing::warn!("Failed to parse market data snapshot: {}", e);
+                return false;
+            }
+        },
+        MsgType::MarketDataIncrementalRefresh => match parse_incremental(msg) {
+            Ok(updates) => updates,
+            Err(e) => {
+                tracing::warn!("Failed to parse market data refresh: {}", e);
+                return false;
+            }
+        },
+        MsgType::MarketDataRequestReject => vec![MarketDataUpdate::Rejected {
+            md_req_id: msg.get(tags::MD_REQ_ID).unwrap_or_default().to_string(),
+            reason: msg
+                .get(tags::TEXT)
+                .or_else(|| msg.get(tags::MD_REQ_REJ_REASON))
+                .map(String::from),
+        }],
+        _ => return false,
+    };
+
+    let (md_req_id, sender) = {
+        let guard = routes.lock().await;
+        let found = match msg.get(tags::MD_REQ_ID) {
+            Some(id) => guard.get_key_value(id),
+            None => guard.iter().find(|(_, route)| {
+                updates.iter().any(|update| {
+                    update_symbol(update).is_some_and(|s| route.symbols.iter().any(|r| r == s))
+                })
+            }),
+        };
+        match found {
+            Some((id, route)) => (id.clone(), route.sender.clone()),
+            None => return false,
+        }
+    };
+
+    // Never wait on a subscriber from the session's receive loop: a stream
+    // that falls a full channel behind is closed rather than fed a book
+    // with silent gaps.
+    let rejected = msg_type == MsgType::MarketDataRequestReject;
+    for update in updates {
+        match sender.try_send(update) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("Market data stream {} fell behind; closing it", md_req_id);
+                routes.lock().await.remove(&md_req_id);
+                return true;
+            }
+            Err(TrySendError::Closed(_)) => {
+                tracing::debug!("Market data stream {} dropped", md_req_id);
+                routes.lock().await.remove(&md_req_id);
+                return true;
+            }
+        }
+    }
+    if rejected {
+        routes.lock().await.remove(&md_req_id);
+    }
+    true
+}
+
+/// One entry of a Market Data Incremental Refresh (MsgType X).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketDataIncrement {
+    /// Update action (Tag 279).
+    pub action: MdUpdateAction,
+    /// Entry type (Tag 269); `None` for types other than bid, offer and
+    /// trade.
+    pub entry_type: Option<MdEntryType>,
+    /// Symbol (Tag 55), if the entry carries one.
+    pub symbol: Option<String>,
+    /// Price (Tag 270).
+    pub price: Option<f64>,
+    /// Size (Tag 271); 0 if absent.
+    pub size: f64,
+}
+
+impl GroupMember for MarketDataIncrement {
+    const COUNT_TAG: u32 = tags::NO_MD_ENTRIES;
+    const DELIMITER: u32 = tags::MD_UPDATE_ACTION;
+    const TAGS: &'static [u32] = &[
+        tags::MD_UPDATE_ACTION,
+        tags::MD_ENTRY_TYPE,
+        tags::SYMBOL,
+        tags::MD_ENTRY_PX,
+        tags::MD_ENTRY_SIZE,
+    ];
+
+    fn decode(entry: &GroupEntry<'_>) -> Result<Self> {
+        Ok(Self {
+            action: entry
+                .get_char(tags::MD_UPDATE_ACTION)
+                .and_then(MdUpdateAction::from_char)
+                .ok_or_else(|| FixError::InvalidMessage("invalid MDUpdateAction".to_string()))?,
+            entry_type: entry
+                .get_char(tags::MD_ENTRY_TYPE)
+                .and_then(MdEntryType::from_char),
+            symbol: entry.get(tags::SYMBOL).map(String::from),
+            price: entry.parse_opt(tags::MD_ENTRY_PX, "MDEntryPx")?,
+            size: entry
+                .parse_opt(tags::MD_ENTRY_SIZE, "MDEntrySize")?
+                .unwrap_or(0.0),
+        })
+    }
+}
+
+/// Symbol carried by an update, if any.
+fn update_symbol(update: &MarketDataUpdate) -> Option<&str> {
+    match update {
+        MarketDataUpdate::Snapshot(snapshot) => Some(&snapshot.symbol),
+        MarketDataUpdate::Book(book) => Some(&book.symbol),
+        MarketDataUpdate::Trade(trade) => Some(&trade.symbol),
+        MarketDataUpdate::Rejected { .. } => None,
+    }
+}
+
+/// Parse a Market Data Snapshot/Full Refresh (MsgType W).
+///
+/// # Errors
+/// Returns error if required fields are missing or malformed.
+pub fn parse_snapshot(msg: &FixMessage) -> Result<MarketDataSnapshot> {
+    let symbol = msg
+        .get(tags::SYMBOL)
+        .ok_or_else(|| FixError::InvalidMessage("missing Symbol".to_string()))?
+        .to_string();
+    let md_req_id = msg.get(tags::MD_REQ_ID).unwrap_or_default().to_string();
+
+    let entries = msg.group::<MarketDataEntry>()?.into_vec();
+
+    Ok(MarketDataSnapshot {
+        md_req_id,
+        symbol,
+        entries,
+    })
+}
+
+/// Parse a Market Data Incremental Refresh (MsgType X) into book and trade
+/// updates. Entries of unsupported types are skipped; entries that omit the
+/// symbol inherit it from the previous entry.
+///
+/// # Errors
+/// Returns error if required fields are missing or malformed, including a
+/// first entry without a symbol.
+pub fn parse_incremental(msg: &FixMessage) -> Result<Vec<MarketDataUpdate>> {
+    // Only carry a symbol forward from an entry that sets it; the message-level
+    // lookup would return the last entry's tag 55.
+    let mut symbol: Option<String> = None;
+    let mut updates = Vec::new();
+
+    for entry in msg.group::<MarketDataIncrement>()? {
+        if let Some(s) = entry.symbol {
+            symbol = Some(s);
+        }
+        let symbol = symbol
+            .clone()
+            .ok_or_else(|| FixError::InvalidMessage("missing Symbol".to_string()))?;
+        let Some(entry_type) = entry.entry_type else {
+            continue;
+        };
+        let price = entry
+            .price
+            .ok_or_else(|| FixError::InvalidMessage("missing MDEntryPx".to_string()))?;
+        let (action, size) = (entry.action, entry.size);
+
+        updates.push(match entry_type {
+            MdEntryType::Trade => MarketDataUpdate::Trade(TradeUpdate {
+                symbol,
+                price,
+                size,
+            }),
+            side => MarketDataUpdate::Book(BookUpdate {
+                symbol,
+                action,
+                side,
+                price,
+                size,
+            }),
+        });
+    }
+
+    Ok(updates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::FixDecoder;
+
+    fn decode(body: &str) -> FixMessage {
+        FixDecoder::new()
+            .decode(&format!("8=FIX.4.4\x01{}10=000\x01", body))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_snapshot() {
+        let msg = decode(
+            "35=W\x01262=req1\x0155=AAPL\x01268=2\x01\
+             269=0\x01270=175.50\x01271=100\x01\
+             269=1\x01270=175.55\x01271=200\x01",
+        );
+        let snapshot = parse_snapshot(&msg).unwrap();
+        assert_eq!(snapshot.md_req_id, "req1");
+        assert_eq!(snapshot.symbol, "AAPL");
+        assert_eq!(snapshot.entries.len(), 2);
+        assert_eq!(snapshot.entries[1].md_entry_type, '1');
+        assert_eq!(snapshot.entries[1].md_entry_px, 175.55);
+    }
+
+    #[test]
+    fn test_parse_incremental() {
+        let msg = decode(
+            "35=X\x01262=req1\x01268=3\x01\
+             279=0\x01269=0\x0155=AAPL\x01270=175.49\x01271=300\x01\
+             279=2\x01269=1\x01270=175.55\x01271=0\x01\
+             279=0\x01269=2\x0155=MSFT\x01270=410.10\x01271=5\x01",
+        );
+        let updates = parse_incremental(&msg).unwrap();
+        assert_eq!(updates.len(), 3);
+        assert!(matches!(
+            &updates[0],
+            MarketDataUpdate::Book(b) if b.symbol == "AAPL" && b.side == MdEntryType::Bid
+        ));
+        assert!(matches!(
+            &updates[1],
+            MarketDataUpdate::Book(b) if b.symbol == "AAPL" && b.action == MdUpdateAction::Delete
+        ));
+        assert!(matches!(
+            &updates[2],
+            MarketDataUpdate::Trade(t) if t.symbol == "MSFT" && t.size == 5.0
+        ));
+    }
+
+    #[test]
+    fn test_parse_incremental_symbol_carry() {
+        let msg = decode(
+            "35=X\x01262=req1\x01268=3\x01\
+             279=0\x01269=0\x0155=AAPL\x01270=175.49\x01271=300\x01\
+             279=0\x01269=0\x0155=MSFT\x01270=410.00\x01271=10\x01\
+             279=1\x01269=1\x01270=410.20\x01271=20\x01",
+        );
+        let updates = parse_incremental(&msg).unwrap();
+        let symbols: Vec<_> = updates.iter().filter_map(update_symbol).collect();
+        assert_eq!(symbols, ["AAPL", "MSFT", "MSFT"]);
+
+        // An entry before any tag 55 does not borrow a later entry's symbol.
+        let msg = decode(
+            "35=X\x01262=req1\x01268=2\x01\
+             279=0\x01269=0\x01270=175.49\x01271=300\x01\
+             279=0\x01269=0\x0155=MSFT\x01270=410.00\x01271=10\x01",
+        );
+        assert!(parse_incremental(&msg).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_route_by_req_id_and_symbol() {
+        let routes: MarketDataRoutes = Arc::default();
+        let (tx, mut rx) = mpsc::channel(8);
+        routes.lock().await.insert(
+            "req1".to_string(),
+            MarketDataRoute {
+                symbols: vec!["AAPL".to_string()],
+                depth: 1,
+                sender: tx,
+            },
+        );
+
+        let snapshot = decode("35=W\x01262=req1\x0155=AAPL\x01268=0\x01");
+        assert!(route(&routes, &snapshot).await);
+        assert!(matches!(
+            rx.recv().await,
+            Some(MarketDataUpdate::Snapshot(_))
+        ));
+
+        let refresh = decode("35=X\x01268=1\x01279=1\x01269=0\x0155=AAPL\x01270=1\x01271=1\x01");
+        assert!(route(&routes, &refresh).await);
+        assert!(matches!(rx.recv().await, Some(MarketDataUpdate::Book(_))));
+
+        let other = decode("35=X\x01268=1\x01279=1\x01269=0\x0155=TSLA\x01270=1\x01271=1\x01");
+        assert!(!route(&routes, &other).await);
+
+        let report = decode("35=8\x01262=req1\x01");
+        assert!(!route(&routes, &report).await);
+
+        let reject = decode("35=Y\x01262=req1\x0158=unknown symbol\x01");
+        assert!(route(&routes, &reject).await);
+        assert!(matches!(
+            rx.recv().await,
+            Some(MarketDataUpdate::Rejected { reason: Some(r), .. }) if r == "unknown symbol"
+        ));
+        assert!(routes.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_route_closes_full_stream() {
+        let routes: MarketDataRoutes = Arc::default();
+        let (tx, mut rx) = mpsc::channel(1);
+        routes.lock().await.insert(
+            "req1".to_string(),
+            MarketDataRoute {
+                symbols: vec!["AAPL".to_string()],
+                depth: 1,
+                sender: tx,
+            },
+        );
+
+        let snapshot = decode("35=W\x01262=req1\x0155=AAPL\x01268=0\x01");
+        assert!(route(&routes, &snapshot).await);
+        // The consumer has not read the first update; the second must not
+        // block the caller.
+        assert!(route(&routes, &snapshot).await);
+        assert!(routes.lock().await.is_empty());
+        assert!(matches!(
+            rx.recv().await,
+            Some(MarketDataUpdate::Snapshot(_))
+        ));
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -1,5 +1,6 @@
 //! FIX message types.
 
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
 /// FIX message type identifiers.
@@ -35,6 +36,10 @@ pub enum MsgType {
     MarketDataSnapshot,
     /// Market Data Incremental Refresh (X).
     MarketDataIncrementalRefresh,
+    /// Market Data Request Reject (Y).
+    MarketDataRequestReject,
+    /// New Order Multileg (AB).
+    NewOrderMultileg,
 }
 
 impl MsgType {
@@ -57,6 +62,8 @@ impl MsgType {
             Self::MarketDataRequest => "V",
             Self::MarketDataSnapshot => "W",
             Self::MarketDataIncrementalRefresh => "X",
+            Self::MarketDataRequestReject => "Y",
+            Self::NewOrderMultileg => "AB",
         }
     }
 
@@ -79,6 +86,8 @@ impl MsgType {
             "V" => Some(Self::MarketDataRequest),
             "W" => Some(Self::MarketDataSnapshot),
             "X" => Some(Self::MarketDataIncrementalRefresh),
+            "Y" => Some(Self::MarketDataRequestReject),
+            "AB" => Some(Self::NewOrderMultileg),
             _ => None,
         }
     }
@@ -320,6 +329,131 @@ impl OrdStatus {
     }
 }
 
+/// Security type (Tag 167).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityType {
+    /// Common stock (CS).
+    CommonStock,
+    /// Option (OPT).
+    Option,
+}
+
+impl SecurityType {
+    /// Get the FIX value.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CommonStock => "CS",
+            Self::Option => "OPT",
+        }
+    }
+
+    /// Parse from FIX value.
+    #[must_use]
+    pub fn from_fix_str(s: &str) -> Option<Self> {
+        match s {
+            "CS" => Some(Self::CommonStock),
+            "OPT" => Some(Self::Option),
+            _ => None,
+        }
+    }
+}
+
+/// Put or call (Tag 201).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PutOrCall {
+    /// Put (0).
+    Put,
+    /// Call (1).
+    Call,
+}
+
+impl PutOrCall {
+    /// Get the FIX character value.
+    #[must_use]
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Put => '0',
+            Self::Call => '1',
+        }
+    }
+
+    /// Parse from FIX character value.
+    #[must_use]
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '0' => Some(Self::Put),
+            '1' => Some(Self::Call),
+            _ => None,
+        }
+    }
+}
+
+/// Position effect (Tag 77).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PositionEffect {
+    /// Open a position (O).
+    Open,
+    /// Close a position (C).
+    Close,
+}
+
+impl PositionEffect {
+    /// Get the FIX character value.
+    #[must_use]
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Open => 'O',
+            Self::Close => 'C',
+        }
+    }
+
+    /// Parse from FIX character value.
+    #[must_use]
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'O' => Some(Self::Open),
+            'C' => Some(Self::Close),
+            _ => None,
+        }
+    }
+}
+
+/// Option contract terms carried alongside the underlying symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OptionContract {
+    /// Expiration date (Tag 541; Tag 200 carries its month).
+    pub maturity_date: NaiveDate,
+    /// Strike price (Tag 202).
+    pub strike_price: f64,
+    /// Put or call (Tag 201).
+    pub put_or_call: PutOrCall,
+}
+
+impl OptionContract {
+    /// Create an option contract.
+    #[must_use]
+    pub fn new(maturity_date: NaiveDate, strike_price: f64, put_or_call: PutOrCall) -> Self {
+        Self {
+            maturity_date,
+            strike_price,
+            put_or_call,
+        }
+    }
+
+    /// Maturity month in FIX `YYYYMM` form.
+    #[must_use]
+    pub fn maturity_month_year(&self) -> String {
+        self.maturity_date.format("%Y%m").to_string()
+    }
+
+    /// Maturity date in FIX `YYYYMMDD` form.
+    #[must_use]
+    pub fn maturity_date_str(&self) -> String {
+        self.maturity_date.format("%Y%m%d").to_string()
+    }
+}
+
 /// New Order Single message (MsgType D).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewOrderSingle {
@@ -341,6 +475,12 @@ pub struct NewOrderSingle {
     pub time_in_force: TimeInForce,
     /// Account (Tag 1).
     pub account: Option<String>,
+    /// Option contract terms; sent with SecurityType OPT (Tag 167).
+    #[serde(default)]
+    pub option: Option<OptionContract>,
+    /// Position effect (Tag 77).
+    #[serde(default)]
+    pub position_effect: Option<PositionEffect>,
 }
 
 impl NewOrderSingle {
@@ -357,6 +497,8 @@ impl NewOrderSingle {
             stop_px: None,
             time_in_force: TimeInForce::Day,
             account: None,
+            option: None,
+            position_effect: None,
         }
     }
 
@@ -373,6 +515,8 @@ impl NewOrderSingle {
             stop_px: None,
             time_in_force: TimeInForce::Day,
             account: None,
+            option: None,
+            position_effect: None,
         }
     }
 
@@ -389,6 +533,167 @@ impl NewOrderSingle {
             stop_px: Some(stop_price),
             time_in_force: TimeInForce::Day,
             account: None,
+            option: None,
+            position_effect: None,
+        }
+    }
+
+    /// Set client order ID.
+    #[must_use]
+    pub fn with_cl_ord_id(mut self, id: &str) -> Self {
+        self.cl_ord_id = id.to_string();
+        self
+    }
+
+    /// Set time in force.
+    #[must_use]
+    pub fn with_time_in_force(mut self, tif: TimeInForce) -> Self {
+        self.time_in_force = tif;
+        self
+    }
+
+    /// Set account.
+    #[must_use]
+    pub fn with_account(mut self, account: &str) -> Self {
+        self.account = Some(account.to_string());
+        self
+    }
+
+    /// Route the order for an option on `symbol` instead of the stock.
+    #[must_use]
+    pub fn with_option(mut self, contract: OptionContract) -> Self {
+        self.option = Some(contract);
+        self
+    }
+
+    /// Set position effect.
+    #[must_use]
+    pub fn with_position_effect(mut self, effect: PositionEffect) -> Self {
+        self.position_effect = Some(effect);
+        self
+    }
+
+    /// Security type implied by the order.
+    #[must_use]
+    pub fn security_type(&self) -> SecurityType {
+        if self.option.is_some() {
+            SecurityType::Option
+        } else {
+            SecurityType::CommonStock
+        }
+    }
+}
+
+/// Maximum number of legs in a multileg order.
+pub const MAX_LEGS: usize = 4;
+
+/// One leg of a New Order Multileg message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultilegLeg {
+    /// Leg symbol (Tag 600); the underlying for option legs.
+    pub symbol: String,
+    /// Leg side (Tag 624).
+    pub side: Side,
+    /// Leg ratio quantity (Tag 623).
+    pub ratio_qty: f64,
+    /// Option contract terms (Tags 609, 611, 612, 1358).
+    pub option: Option<OptionContract>,
+    /// Leg position effect (Tag 564).
+    pub position_effect: Option<PositionEffect>,
+}
+
+impl MultilegLeg {
+    /// Create an option leg.
+    #[must_use]
+    pub fn option(symbol: &str, contract: OptionContract, side: Side, ratio_qty: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            ratio_qty,
+            option: Some(contract),
+            position_effect: None,
+        }
+    }
+
+    /// Create a stock leg, e.g. for a covered call.
+    #[must_use]
+    pub fn stock(symbol: &str, side: Side, ratio_qty: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            ratio_qty,
+            option: None,
+            position_effect: None,
+        }
+    }
+
+    /// Set position effect.
+    #[must_use]
+    pub fn with_position_effect(mut self, effect: PositionEffect) -> Self {
+        self.position_effect = Some(effect);
+        self
+    }
+
+    /// Security type of the leg.
+    #[must_use]
+    pub fn security_type(&self) -> SecurityType {
+        if self.option.is_some() {
+            SecurityType::Option
+        } else {
+            SecurityType::CommonStock
+        }
+    }
+}
+
+/// New Order Multileg message (MsgType AB), e.g. an option spread.
+///
+/// The order quantity counts spreads; each leg trades `ratio_qty` times
+/// that. The limit price is the net price of one spread, positive for a
+/// debit and negative for a credit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewOrderMultileg {
+    /// Client order ID (Tag 11).
+    pub cl_ord_id: String,
+    /// Legs (repeating group, Tag 555).
+    pub legs: Vec<MultilegLeg>,
+    /// Order type (Tag 40); market or limit.
+    pub ord_type: OrdType,
+    /// Order quantity (Tag 38).
+    pub order_qty: f64,
+    /// Net limit price (Tag 44).
+    pub price: Option<f64>,
+    /// Time in force (Tag 59).
+    pub time_in_force: TimeInForce,
+    /// Account (Tag 1).
+    pub account: Option<String>,
+}
+
+impl NewOrderMultileg {
+    /// Create a market multileg order.
+    #[must_use]
+    pub fn market(legs: Vec<MultilegLeg>, qty: f64) -> Self {
+        Self {
+            cl_ord_id: uuid::Uuid::new_v4().to_string(),
+            legs,
+            ord_type: OrdType::Market,
+            order_qty: qty,
+            price: None,
+            time_in_force: TimeInForce::Day,
+            account: None,
+        }
+    }
+
+    /// Create a limit multileg order at a net price.
+    #[must_use]
+    pub fn limit(legs: Vec<MultilegLeg>, qty: f64, price: f64) -> Self {
+        Self {
+            cl_ord_id: uuid::Uuid::new_v4().to_string(),
+            legs,
+            ord_type: OrdType::Limit,
+            order_qty: qty,
+            price: Some(price),
+            time_in_force: TimeInForce::Day,
+            account: None,
         }
     }
 
@@ -412,6 +717,43 @@ impl NewOrderSingle {
         self.account = Some(account.to_string());
         self
     }
+
+    /// Check the order can be routed: two to [`MAX_LEGS`] legs with
+    /// positive ratios, a positive quantity, and a price only on limit
+    /// orders.
+    ///
+    /// # Errors
+    /// Returns [`FixError::InvalidMessage`](crate::error::FixError::InvalidMessage)
+    /// describing the first problem found.
+    pub fn validate(&self) -> crate::error::Result<()> {
+        use crate::error::FixError;
+
+        if !(2..=MAX_LEGS).contains(&self.legs.len()) {
+            return Err(FixError::InvalidMessage(format!(
+                "multileg order needs 2 to {MAX_LEGS} legs, got {}",
+                self.legs.len()
+            )));
+        }
+        if let Some(leg) = self.legs.iter().find(|leg| leg.ratio_qty <= 0.0) {
+            return Err(FixError::InvalidMessage(format!(
+                "leg {} has ratio {}",
+                leg.symbol, leg.ratio_qty
+            )));
+        }
+        if self.order_qty <= 0.0 {
+            return Err(FixError::InvalidMessage(format!(
+                "multileg order has quantity {}",
+                self.order_qty
+            )));
+        }
+        match (self.ord_type, self.price) {
+            (OrdType::Market, None) | (OrdType::Limit, Some(_)) => Ok(()),
+            (ord_type, _) => Err(FixError::InvalidMessage(format!(
+                "multileg {ord_type:?} order has price {:?}",
+                self.price
+            ))),
+        }
+    }
 }
 
 /// Order Cancel Request message (MsgType F).
@@ -584,6 +926,13 @@ impl MarketDataRequest {
             symbols,
         }
     }
+
+    /// Set market depth (0 = full book, 1 = top of book).
+    #[must_use]
+    pub fn with_depth(mut self, depth: u32) -> Self {
+        self.market_depth = depth;
+        self
+    }
 }
 
 /// Market Data Snapshot message (MsgType W).
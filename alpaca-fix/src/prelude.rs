@@ -0,0 +1,23 @@
+//! Commonly used, stable types: the FIX client, its configuration and the
+//! order and execution messages.
+//!
+//! FIX has its own [`Side`], [`OrdType`] and [`TimeInForce`] enums, so
+//! unlike the other crates' preludes this one does not include the
+//! [`alpaca_base::prelude`]; only the credential types are re-exported.
+//!
+//! ```
+//! use alpaca_fix::prelude::*;
+//!
+//! let order = NewOrderSingle::market("AAPL", Side::Buy, 10.0);
+//! assert_eq!(order.symbol, "AAPL");
+//! ```
+
+pub use crate::client::FixClient;
+pub use crate::config::{FixConfig, FixVersion};
+pub use crate::error::FixError;
+pub use crate::messages::{
+    ExecType, ExecutionReport, NewOrderSingle, OrdStatus, OrdType, OrderCancelReplaceRequest,
+    OrderCancelRequest, Side, TimeInForce,
+};
+pub use crate::session::SessionState;
+pub use alpaca_base::{Credentials, CredentialsHandle};
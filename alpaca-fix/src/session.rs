@@ -1,9 +1,10 @@
 //! FIX session management.
 
 use crate::codec::{FixEncoder, FixMessage, tags};
-use crate::config::FixConfig;
+use crate::config::{EncryptMethod, FixConfig};
 use crate::error::{FixError, Result};
 use crate::messages::MsgType;
+use alpaca_base::Credentials;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -141,15 +142,21 @@ impl FixSession {
         Arc::clone(&self.seq_nums)
     }
 
-    /// Create logon message.
+    /// Create logon message carrying the API key ID and secret as
+    /// Username (553) and Password (554).
     #[must_use]
-    pub fn create_logon(&self) -> String {
+    pub fn create_logon(&self, credentials: &Credentials) -> String {
         let mut fields = vec![
-            (tags::ENCRYPT_METHOD, "0".to_string()),
+            (
+                tags::ENCRYPT_METHOD,
+                self.config.encrypt_method.as_u8().to_string(),
+            ),
             (
                 tags::HEART_BT_INT,
                 self.config.heartbeat_interval_secs.to_string(),
             ),
+            (tags::USERNAME, credentials.api_key.clone()),
+            (tags::PASSWORD, credentials.secret_key.clone()),
         ];
 
         if self.config.reset_on_logon {
@@ -163,6 +170,27 @@ impl FixSession {
         )
     }
 
+    /// Check that a logon response accepts the proposed encryption method.
+    ///
+    /// A response without EncryptMethod is taken as accepting it.
+    ///
+    /// # Errors
+    /// Returns [`FixError::Authentication`] if the server answered with a
+    /// different method.
+    pub fn check_encrypt_method(&self, response: &FixMessage) -> Result<()> {
+        let Some(value) = response.get(tags::ENCRYPT_METHOD) else {
+            return Ok(());
+        };
+        let proposed = self.config.encrypt_method;
+        match EncryptMethod::from_fix_str(value) {
+            Some(method) if method == proposed => Ok(()),
+            _ => Err(FixError::Authentication(format!(
+                "server answered EncryptMethod {value}, proposed {}",
+                proposed.as_u8()
+            ))),
+        }
+    }
+
     /// Create logout message.
     #[must_use]
     pub fn create_logout(&self, text: Option<&str>) -> String {
@@ -296,10 +324,26 @@ mod tests {
             .heartbeat_interval_secs(30)
             .build();
         let session = FixSession::new(config);
-        let logon = session.create_logon();
+        let credentials = Credentials::new("KEY".to_string(), "SECRET".to_string());
+        let logon = session.create_logon(&credentials);
 
         assert!(logon.contains("35=A"));
+        assert!(logon.contains("98=0"));
         assert!(logon.contains("108=30"));
+        assert!(logon.contains("\x01553=KEY\x01554=SECRET\x01"));
+
+        let mut response = FixMessage::new();
+        response
+            .fields
+            .insert(tags::ENCRYPT_METHOD, "0".to_string());
+        assert!(session.check_encrypt_method(&response).is_ok());
+        response
+            .fields
+            .insert(tags::ENCRYPT_METHOD, "2".to_string());
+        assert!(matches!(
+            session.check_encrypt_method(&response),
+            Err(FixError::Authentication(_))
+        ));
     }
 
     #[test]
@@ -0,0 +1,242 @@
+//! [`TradingApi`] over FIX.
+//!
+//! Orders are routed as New Order Single, Order Cancel Request and Order
+//! Cancel/Replace Request messages. FIX acknowledges orders asynchronously
+//! through execution reports, so the [`Order`] returned on submission is a
+//! `pending_new` placeholder identified by its client order ID (its `id`
+//! is nil until the execution report assigns one).
+//!
+//! Positions and account state are not available over the FIX session;
+//! pair the client with the REST client in a
+//! [`RoutedTradingApi`](alpaca_base::RoutedTradingApi) for those.
+
+use crate::client::FixClient;
+use crate::error::FixError;
+#[cfg(feature = "unstable")]
+use crate::messages::{ExecType, ExecutionReport};
+use crate::messages::{
+    NewOrderSingle, OptionContract, OrdType, OrderCancelReplaceRequest, OrderCancelRequest,
+    PutOrCall, Side, TimeInForce,
+};
+use alpaca_base::types::{self, Account, Order, OrderSide, OrderStatus, OrderType, Position};
+use alpaca_base::{AlpacaError, OccSymbol, OrderReplacement, OrderRequest, Result, TradingApi};
+#[cfg(feature = "unstable")]
+use alpaca_base::{Execution, ExecutionKind};
+use chrono::Utc;
+use uuid::Uuid;
+
+impl From<FixError> for AlpacaError {
+    fn from(err: FixError) -> Self {
+        match err {
+            FixError::Connection(_) | FixError::Io(_) | FixError::Session(_) => {
+                Self::Network(err.to_string())
+            }
+            FixError::Timeout(msg) => Self::Timeout(msg),
+            FixError::Authentication(msg) => Self::Auth(msg),
+            FixError::Configuration(msg) => Self::Config(msg),
+            FixError::Rejected(msg) => Self::Validation(msg),
+            _ => Self::InvalidData(err.to_string()),
+        }
+    }
+}
+
+fn side(side: &OrderSide) -> Side {
+    match side {
+        OrderSide::Buy => Side::Buy,
+        OrderSide::Sell => Side::Sell,
+    }
+}
+
+fn ord_type(order_type: &OrderType) -> Result<OrdType> {
+    match order_type {
+        OrderType::Market => Ok(OrdType::Market),
+        OrderType::Limit => Ok(OrdType::Limit),
+        OrderType::Stop => Ok(OrdType::Stop),
+        OrderType::StopLimit => Ok(OrdType::StopLimit),
+        OrderType::TrailingStop => Err(AlpacaError::Validation(
+            "trailing stop orders are not supported over FIX".to_string(),
+        )),
+    }
+}
+
+fn time_in_force(tif: &types::TimeInForce) -> Result<TimeInForce> {
+    match tif {
+        types::TimeInForce::Day => Ok(TimeInForce::Day),
+        types::TimeInForce::Gtc => Ok(TimeInForce::Gtc),
+        types::TimeInForce::Ioc => Ok(TimeInForce::Ioc),
+        types::TimeInForce::Fok => Ok(TimeInForce::Fok),
+        other => Err(AlpacaError::Validation(format!(
+            "time in force {other:?} is not supported over FIX"
+        ))),
+    }
+}
+
+impl From<&OccSymbol> for OptionContract {
+    fn from(occ: &OccSymbol) -> Self {
+        let put_or_call = match occ.option_type {
+            types::OptionType::Call => PutOrCall::Call,
+            types::OptionType::Put => PutOrCall::Put,
+        };
+        Self::new(occ.expiration, occ.strike, put_or_call)
+    }
+}
+
+fn parse_price(value: Option<&str>) -> Option<f64> {
+    value.and_then(|v| v.parse().ok())
+}
+
+impl TryFrom<&OrderRequest> for NewOrderSingle {
+    type Error = AlpacaError;
+
+    /// OCC option symbols (as used by the REST API) are routed as an
+    /// option on the underlying.
+    fn try_from(order: &OrderRequest) -> Result<Self> {
+        let occ = OccSymbol::parse(&order.symbol).ok();
+        Ok(Self {
+            cl_ord_id: order
+                .client_order_id
+                .clone()
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            symbol: occ
+                .as_ref()
+                .map_or_else(|| order.symbol.clone(), |occ| occ.underlying.clone()),
+            side: side(&order.side),
+            ord_type: ord_type(&order.order_type)?,
+            order_qty: order.qty,
+            price: order.limit_price,
+            stop_px: order.stop_price,
+            time_in_force: time_in_force(&order.time_in_force)?,
+            account: None,
+            option: occ.as_ref().map(OptionContract::from),
+            position_effect: None,
+        })
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl From<&ExecutionReport> for Execution {
+    fn from(report: &ExecutionReport) -> Self {
+        let kind = match report.exec_type {
+            ExecType::New => ExecutionKind::Accepted,
+            ExecType::PartialFill | ExecType::Fill => ExecutionKind::Fill,
+            ExecType::Canceled => ExecutionKind::Canceled,
+            ExecType::Replaced => ExecutionKind::Replaced,
+            ExecType::Rejected => ExecutionKind::Rejected,
+            ExecType::Expired => ExecutionKind::Expired,
+            ExecType::PendingCancel | ExecType::PendingNew => ExecutionKind::Other,
+        };
+        let fill = kind == ExecutionKind::Fill;
+        Self {
+            client_order_id: report.cl_ord_id.clone(),
+            order_id: Some(report.order_id.clone()).filter(|id| !id.is_empty()),
+            exec_id: Some(report.exec_id.clone()).filter(|id| !id.is_empty()),
+            kind,
+            last_qty: report.last_qty,
+            last_price: report.last_px,
+            cum_qty: fill.then_some(report.cum_qty),
+            avg_price: fill.then_some(report.avg_px),
+            text: report.text.clone(),
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+impl TradingApi for FixClient {
+    async fn submit_order(&self, order: &OrderRequest) -> Result<Order> {
+        let message = NewOrderSingle::try_from(order)?;
+        let cl_ord_id = self.send_order(&message).await?;
+        Ok(order.pending_order(Uuid::nil(), &cl_ord_id, Utc::now()))
+    }
+
+    async fn cancel_order(&self, order: &Order) -> Result<()> {
+        let cancel =
+            OrderCancelRequest::new(&order.client_order_id, &order.symbol, side(&order.side));
+        FixClient::cancel_order(self, &cancel).await?;
+        Ok(())
+    }
+
+    async fn replace_order(&self, order: &Order, replacement: &OrderReplacement) -> Result<Order> {
+        if replacement.stop_price.is_some() || replacement.time_in_force.is_some() {
+            return Err(AlpacaError::Validation(
+                "only qty and limit price can be replaced over FIX".to_string(),
+            ));
+        }
+        let qty = match replacement.qty {
+            Some(qty) => qty,
+            None => parse_price(order.qty.as_deref()).ok_or_else(|| {
+                AlpacaError::InvalidData(format!("order {} has no qty", order.client_order_id))
+            })?,
+        };
+        let mut replace = OrderCancelReplaceRequest::new(
+            &order.client_order_id,
+            &order.symbol,
+            side(&order.side),
+            ord_type(&order.order_type)?,
+            qty,
+        );
+        replace.price = replacement
+            .limit_price
+            .or_else(|| parse_price(order.limit_price.as_deref()));
+        let cl_ord_id = FixClient::replace_order(self, &replace).await?;
+
+        let now = Utc::now();
+        let mut replaced = order.clone();
+        replaced.id = Uuid::nil();
+        replaced.client_order_id = cl_ord_id;
+        replaced.replaces = (!order.id.is_nil()).then_some(order.id);
+        replaced.qty = Some(qty.to_string());
+        replaced.limit_price = replace.price.map(|p| p.to_string());
+        replaced.status = OrderStatus::PendingReplace;
+        replaced.created_at = now;
+        replaced.updated_at = now;
+        Ok(replaced)
+    }
+
+    async fn get_positions(&self) -> Result<Vec<Position>> {
+        Err(AlpacaError::Config(
+            "positions are not available over FIX; use RoutedTradingApi with the REST client"
+                .to_string(),
+        ))
+    }
+
+    async fn get_account(&self) -> Result<Account> {
+        Err(AlpacaError::Config(
+            "account state is not available over FIX; use RoutedTradingApi with the REST client"
+                .to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_request_to_new_order_single() {
+        let request = OrderRequest::limit("AAPL", OrderSide::Sell, 10.0, 190.5)
+            .time_in_force(types::TimeInForce::Gtc)
+            .client_order_id("strategy-1");
+        let message = NewOrderSingle::try_from(&request).unwrap();
+        assert_eq!(message.cl_ord_id, "strategy-1");
+        assert_eq!(message.side, Side::Sell);
+        assert_eq!(message.ord_type, OrdType::Limit);
+        assert_eq!(message.price, Some(190.5));
+        assert_eq!(message.time_in_force, TimeInForce::Gtc);
+        assert!(message.option.is_none());
+
+        let call = OrderRequest::limit("AAPL250117C00190000", OrderSide::Buy, 1.0, 4.2);
+        let message = NewOrderSingle::try_from(&call).unwrap();
+        assert_eq!(message.symbol, "AAPL");
+        let option = message.option.unwrap();
+        assert_eq!(option.maturity_date_str(), "20250117");
+        assert_eq!(option.strike_price, 190.0);
+        assert_eq!(option.put_or_call, PutOrCall::Call);
+
+        let opg = OrderRequest::market("AAPL", OrderSide::Buy, 1.0)
+            .time_in_force(types::TimeInForce::Opg);
+        assert!(NewOrderSingle::try_from(&opg).is_err());
+
+        let err: AlpacaError = FixError::Timeout("logon".to_string()).into();
+        assert!(matches!(err, AlpacaError::Timeout(_)));
+    }
+}
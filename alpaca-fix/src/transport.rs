@@ -69,11 +69,12 @@ impl FixTransport {
         let mut reader = self.reader.lock().await;
         let mut buffer = self.buffer.lock().await;
 
-        // Read until we have a complete message (ends with checksum field)
+        // Read field by field until we have a complete message (ends with
+        // checksum field). FIX fields end with SOH; there are no newlines.
         loop {
-            let mut line = String::new();
+            let mut field = Vec::new();
             let bytes_read = reader
-                .read_line(&mut line)
+                .read_until(SOH as u8, &mut field)
                 .await
                 .map_err(|e| FixError::Connection(format!("read error: {}", e)))?;
 
@@ -81,7 +82,7 @@ impl FixTransport {
                 return Err(FixError::Connection("connection closed".to_string()));
             }
 
-            buffer.push_str(&line);
+            buffer.push_str(&String::from_utf8_lossy(&field));
 
             // Check if we have a complete message (contains checksum tag 10=)
             if buffer.contains(&format!("{}10=", SOH)) {
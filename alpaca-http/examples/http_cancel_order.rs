@@ -16,7 +16,7 @@
 //!
 //! **WARNING**: This example cancels real orders on your Paper account!
 
-use alpaca_base::{Environment, OrderQueryStatus};
+use alpaca_base::{Environment, types::OrderQueryStatus};
 use alpaca_http::{AlpacaHttpClient, OrderParams};
 
 #[tokio::main]
@@ -15,7 +15,7 @@
 //! cargo run -p alpaca-http --example http_corporate_actions
 //! ```
 
-use alpaca_base::CorporateActionsParams;
+use alpaca_base::types::CorporateActionsParams;
 
 fn main() {
     println!("=== Corporate Actions ===\n");
@@ -18,7 +18,7 @@
 //! **Note**: This example demonstrates the API structure but does not
 //! actually create accounts to avoid unintended side effects.
 
-use alpaca_base::{
+use alpaca_base::types::{
     Agreement, AgreementType, Contact, CreateBrokerAccountRequest, Disclosures, Identity,
     TaxIdType, TrustedContact,
 };
@@ -15,7 +15,7 @@
 //! cargo run -p alpaca-http --example http_exchange_rates
 //! ```
 
-use alpaca_base::{Currency, ExchangeRate};
+use alpaca_base::types::{Currency, ExchangeRate};
 
 fn main() {
     println!("=== Exchange Rates ===\n");
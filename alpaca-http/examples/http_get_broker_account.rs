@@ -18,7 +18,7 @@
 //! **Note**: This example demonstrates the API structure. Broker API
 //! requires special credentials.
 
-use alpaca_base::{BrokerAccountStatus, ListBrokerAccountsParams};
+use alpaca_base::types::{BrokerAccountStatus, ListBrokerAccountsParams};
 
 fn main() {
     println!("=== Get Broker Account ===\n");
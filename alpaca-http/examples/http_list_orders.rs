@@ -14,7 +14,7 @@
 //! cargo run -p alpaca-http --example http_list_orders
 //! ```
 
-use alpaca_base::{Environment, OrderQueryStatus};
+use alpaca_base::{Environment, types::OrderQueryStatus};
 use alpaca_http::{AlpacaHttpClient, OrderParams};
 
 #[tokio::main]
@@ -15,7 +15,7 @@
 //! cargo run -p alpaca-http --example http_news
 //! ```
 
-use alpaca_base::NewsParams;
+use alpaca_base::types::NewsParams;
 
 fn main() {
     println!("=== Market News ===\n");
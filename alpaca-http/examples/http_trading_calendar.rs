@@ -15,7 +15,7 @@
 //! cargo run -p alpaca-http --example http_trading_calendar
 //! ```
 
-use alpaca_base::CalendarParams;
+use alpaca_base::types::CalendarParams;
 
 fn main() {
     println!("=== Trading Calendar ===\n");
@@ -16,7 +16,10 @@
 //! cargo run -p alpaca-http --example market_data
 //! ```
 
-use alpaca_base::{CorporateActionsParams, Credentials, DataFeed, Environment, MultiBarsParams};
+use alpaca_base::{
+    Credentials, Environment,
+    types::{CorporateActionsParams, DataFeed, MultiBarsParams},
+};
 use alpaca_http::AlpacaHttpClient;
 use dotenvy::dotenv;
 use std::env;
@@ -15,7 +15,10 @@
 //! cargo run -p alpaca-http --example options_trading
 //! ```
 
-use alpaca_base::{Credentials, Environment, OptionContractParams, OptionType};
+use alpaca_base::{
+    Credentials, Environment,
+    types::{OptionContractParams, OptionType},
+};
 use alpaca_http::AlpacaHttpClient;
 use dotenvy::dotenv;
 use std::env;
@@ -19,7 +19,8 @@
 //! ```
 
 use alpaca_base::{
-    Credentials, Environment, OrderQueryStatus, OrderSide, OrderType, StopLoss, TakeProfit,
+    Credentials, Environment, OrderSide, OrderType,
+    types::{OrderQueryStatus, StopLoss, TakeProfit},
 };
 use alpaca_http::{AlpacaHttpClient, CreateOrderRequest, OrderParams};
 use std::env;
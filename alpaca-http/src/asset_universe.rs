@@ -0,0 +1,385 @@
+//! In-memory asset metadata with lookup indices.
+//!
+//! Validating symbols against `/v2/assets` on every order is slow: the
+//! listing is several megabytes. [`AssetUniverse`] loads it once, indexes
+//! it by symbol, asset ID and exchange, and answers lookups and filters
+//! from memory. It is `Send + Sync`, so one instance behind an [`Arc`] can
+//! serve every task, while [`AssetUniverse::run_refresh`] reloads it on a
+//! fixed interval. Each reload swaps in a complete new [`AssetIndex`], so
+//! readers never see a half-built universe.
+//!
+//! ```no_run
+//! # async fn example(client: alpaca_http::AlpacaHttpClient) -> alpaca_base::Result<()> {
+//! use alpaca_http::{AssetFilter, AssetUniverse};
+//! use alpaca_http::endpoints::AssetParams;
+//! use std::sync::Arc;
+//!
+//! let universe = Arc::new(AssetUniverse::load(client, AssetParams::default()).await?);
+//! let cancel = alpaca_base::CancellationToken::new();
+//! tokio::spawn({
+//!     let (universe, cancel) = (Arc::clone(&universe), cancel.clone());
+//!     async move {
+//!         universe
+//!             .run_refresh(std::time::Duration::from_secs(3600), &cancel)
+//!             .await
+//!     }
+//! });
+//!
+//! assert!(universe.contains("AAPL"));
+//! let shortable = universe.filter(&AssetFilter::new().shortable(true));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::CachedResource;
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::AssetParams;
+use alpaca_base::{CancellationToken, Result, types::Asset};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Attribute value that marks options-enabled assets.
+const OPTIONS_ENABLED: &str = "options_enabled";
+
+/// Criteria for [`AssetUniverse::filter`]. Unset criteria match anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetFilter {
+    /// Required `tradable` flag.
+    pub tradable: Option<bool>,
+    /// Required `fractionable` flag.
+    pub fractionable: Option<bool>,
+    /// Required `shortable` flag.
+    pub shortable: Option<bool>,
+    /// Whether the asset must (or must not) have listed options.
+    pub options_enabled: Option<bool>,
+    /// Required exchange.
+    pub exchange: Option<String>,
+}
+
+impl AssetFilter {
+    /// Create a filter that matches every asset.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the `tradable` flag.
+    #[must_use]
+    pub fn tradable(mut self, tradable: bool) -> Self {
+        self.tradable = Some(tradable);
+        self
+    }
+
+    /// Require the `fractionable` flag.
+    #[must_use]
+    pub fn fractionable(mut self, fractionable: bool) -> Self {
+        self.fractionable = Some(fractionable);
+        self
+    }
+
+    /// Require the `shortable` flag.
+    #[must_use]
+    pub fn shortable(mut self, shortable: bool) -> Self {
+        self.shortable = Some(shortable);
+        self
+    }
+
+    /// Require options to be (or not be) enabled.
+    #[must_use]
+    pub fn options_enabled(mut self, options_enabled: bool) -> Self {
+        self.options_enabled = Some(options_enabled);
+        self
+    }
+
+    /// Require an exchange.
+    #[must_use]
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.exchange = Some(exchange.to_string());
+        self
+    }
+}
+
+/// An immutable, indexed snapshot of the asset list.
+#[derive(Debug, Clone)]
+pub struct AssetIndex {
+    assets: Vec<Asset>,
+    by_symbol: HashMap<String, usize>,
+    by_id: HashMap<Uuid, usize>,
+    by_exchange: HashMap<String, Vec<usize>>,
+    options_enabled: HashSet<Uuid>,
+    loaded_at: DateTime<Utc>,
+}
+
+impl AssetIndex {
+    /// Index `assets`, marking the IDs in `options_enabled`.
+    ///
+    /// If a symbol appears more than once, the last asset wins the symbol
+    /// lookup; all of them stay in [`Self::assets`].
+    #[must_use]
+    pub fn new(assets: Vec<Asset>, options_enabled: HashSet<Uuid>) -> Self {
+        let mut by_symbol = HashMap::with_capacity(assets.len());
+        let mut by_id = HashMap::with_capacity(assets.len());
+        let mut by_exchange: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, asset) in assets.iter().enumerate() {
+            by_symbol.insert(asset.symbol.clone(), i);
+            by_id.insert(asset.id, i);
+            by_exchange
+                .entry(asset.exchange.clone())
+                .or_default()
+                .push(i);
+        }
+        Self {
+            assets,
+            by_symbol,
+            by_id,
+            by_exchange,
+            options_enabled,
+            loaded_at: Utc::now(),
+        }
+    }
+
+    /// Every indexed asset.
+    #[must_use]
+    pub fn assets(&self) -> &[Asset] {
+        &self.assets
+    }
+
+    /// Number of indexed assets.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// Whether no asset is indexed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+
+    /// When the snapshot was built.
+    #[must_use]
+    pub fn loaded_at(&self) -> DateTime<Utc> {
+        self.loaded_at
+    }
+
+    /// The asset with `symbol`.
+    #[must_use]
+    pub fn by_symbol(&self, symbol: &str) -> Option<&Asset> {
+        self.by_symbol.get(symbol).map(|&i| &self.assets[i])
+    }
+
+    /// The asset with `id`.
+    #[must_use]
+    pub fn by_id(&self, id: &Uuid) -> Option<&Asset> {
+        self.by_id.get(id).map(|&i| &self.assets[i])
+    }
+
+    /// Assets listed on `exchange`.
+    pub fn by_exchange<'a>(&'a self, exchange: &str) -> impl Iterator<Item = &'a Asset> + 'a {
+        self.by_exchange
+            .get(exchange)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.assets[i])
+    }
+
+    /// Whether the asset has listed options.
+    #[must_use]
+    pub fn is_options_enabled(&self, asset: &Asset) -> bool {
+        self.options_enabled.contains(&asset.id)
+    }
+
+    /// Whether `asset` satisfies `filter`.
+    #[must_use]
+    pub fn matches(&self, asset: &Asset, filter: &AssetFilter) -> bool {
+        filter.tradable.is_none_or(|v| asset.tradable == v)
+            && filter.fractionable.is_none_or(|v| asset.fractionable == v)
+            && filter.shortable.is_none_or(|v| asset.shortable == v)
+            && filter
+                .options_enabled
+                .is_none_or(|v| self.is_options_enabled(asset) == v)
+            && filter
+                .exchange
+                .as_ref()
+                .is_none_or(|exchange| &asset.exchange == exchange)
+    }
+
+    /// Assets satisfying `filter`, in listing order.
+    #[must_use]
+    pub fn filter(&self, filter: &AssetFilter) -> Vec<&Asset> {
+        match &filter.exchange {
+            Some(exchange) => self
+                .by_exchange(exchange)
+                .filter(|asset| self.matches(asset, filter))
+                .collect(),
+            None => self
+                .assets
+                .iter()
+                .filter(|asset| self.matches(asset, filter))
+                .collect(),
+        }
+    }
+}
+
+/// Shared, periodically refreshed asset metadata.
+#[derive(Debug)]
+pub struct AssetUniverse {
+    client: AlpacaHttpClient,
+    params: AssetParams,
+    index: RwLock<Arc<AssetIndex>>,
+}
+
+impl AssetUniverse {
+    /// Load the assets matching `params` and index them.
+    ///
+    /// Makes two requests: the listing itself, and the same listing
+    /// restricted to options-enabled assets.
+    pub async fn load(client: AlpacaHttpClient, params: AssetParams) -> Result<Self> {
+        let index = fetch_index(&client, &params).await?;
+        Ok(Self {
+            client,
+            params,
+            index: RwLock::new(Arc::new(index)),
+        })
+    }
+
+    /// Reload the assets and swap in the new index.
+    ///
+    /// Cached `/v2/assets` responses are dropped first, so a refresh
+    /// always reaches the API. On error the previous index is kept.
+    pub async fn refresh(&self) -> Result<()> {
+        self.client.invalidate_cache(CachedResource::Assets);
+        let index = fetch_index(&self.client, &self.params).await?;
+        *self.index.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(index);
+        Ok(())
+    }
+
+    /// Refresh every `interval` until `cancel` is cancelled.
+    ///
+    /// Failed refreshes are logged and the previous index is kept until
+    /// the next attempt.
+    pub async fn run_refresh(&self, interval: Duration, cancel: &CancellationToken) {
+        loop {
+            tokio::select! {
+                () = cancel.cancelled() => return,
+                () = tokio::time::sleep(interval) => {}
+            }
+            if let Err(e) = self.refresh().await {
+                warn!("Asset universe refresh failed: {}", e);
+            }
+        }
+    }
+
+    /// The current snapshot. It stays valid, unchanged, across refreshes.
+    #[must_use]
+    pub fn snapshot(&self) -> Arc<AssetIndex> {
+        Arc::clone(&self.index.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// The asset with `symbol`.
+    #[must_use]
+    pub fn get(&self, symbol: &str) -> Option<Asset> {
+        self.snapshot().by_symbol(symbol).cloned()
+    }
+
+    /// The asset with `id`.
+    #[must_use]
+    pub fn get_by_id(&self, id: &Uuid) -> Option<Asset> {
+        self.snapshot().by_id(id).cloned()
+    }
+
+    /// Whether `symbol` is a known asset.
+    #[must_use]
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.snapshot().by_symbol(symbol).is_some()
+    }
+
+    /// Assets listed on `exchange`.
+    #[must_use]
+    pub fn by_exchange(&self, exchange: &str) -> Vec<Asset> {
+        self.snapshot().by_exchange(exchange).cloned().collect()
+    }
+
+    /// Assets satisfying `filter`.
+    #[must_use]
+    pub fn filter(&self, filter: &AssetFilter) -> Vec<Asset> {
+        self.snapshot()
+            .filter(filter)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+}
+
+async fn fetch_index(client: &AlpacaHttpClient, params: &AssetParams) -> Result<AssetIndex> {
+    let assets = client.get_assets(params).await?;
+    let options_params = AssetParams {
+        status: params.status.clone(),
+        asset_class: params.asset_class.clone(),
+        exchange: params.exchange.clone(),
+        attributes: Some(OPTIONS_ENABLED.to_string()),
+    };
+    let options_enabled = client
+        .get_assets(&options_params)
+        .await?
+        .into_iter()
+        .map(|asset| asset.id)
+        .collect();
+    Ok(AssetIndex::new(assets, options_enabled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_asset;
+
+    #[test]
+    fn test_asset_index_lookup_and_filter() {
+        let aapl = sample_asset("AAPL");
+        let mut brk = sample_asset("BRK.A");
+        brk.exchange = "NYSE".to_string();
+        brk.fractionable = false;
+        let mut gme = sample_asset("GME");
+        gme.shortable = false;
+        let index = AssetIndex::new(
+            vec![aapl.clone(), brk.clone(), gme.clone()],
+            HashSet::from([aapl.id, gme.id]),
+        );
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.by_symbol("BRK.A").map(|a| a.id), Some(brk.id));
+        assert_eq!(index.by_id(&gme.id).map(|a| a.symbol.as_str()), Some("GME"));
+        assert!(index.by_symbol("MSFT").is_none());
+        let nasdaq: Vec<_> = index.by_exchange("NASDAQ").map(|a| &a.symbol).collect();
+        assert_eq!(nasdaq, ["AAPL", "GME"]);
+
+        let symbols = |filter: AssetFilter| -> Vec<String> {
+            index
+                .filter(&filter)
+                .into_iter()
+                .map(|a| a.symbol.clone())
+                .collect()
+        };
+        assert_eq!(symbols(AssetFilter::new()).len(), 3);
+        assert_eq!(
+            symbols(AssetFilter::new().fractionable(true)),
+            ["AAPL", "GME"]
+        );
+        assert_eq!(
+            symbols(AssetFilter::new().options_enabled(true).shortable(true)),
+            ["AAPL"]
+        );
+        assert_eq!(
+            symbols(AssetFilter::new().options_enabled(false)),
+            ["BRK.A"]
+        );
+        assert_eq!(
+            symbols(AssetFilter::new().exchange("NASDAQ").shortable(false)),
+            ["GME"]
+        );
+    }
+}
@@ -0,0 +1,853 @@
+//! Backtesting against historical bars through the [`TradingApi`] trait.
+//!
+//! A [`Backtester`] replays bars in time order and simulates a cash account
+//! behind the same [`TradingApi`] surface as the live client, so a strategy
+//! written against the trait runs unchanged in either mode:
+//!
+//! ```rust,ignore
+//! let bt = Backtester::load(&client, &MultiBarsParams::new("AAPL,MSFT").timeframe("1Day")
+//!     .time_range("2024-01-01T00:00:00Z", "2024-06-30T00:00:00Z"), BacktestConfig::default())
+//!     .await?;
+//! while bt.step().is_some() {
+//!     my_strategy(&bt).await?;
+//! }
+//! println!("final equity {:.2}", bt.equity());
+//! ```
+//!
+//! Orders submitted while a bar is current fill against the next bar of
+//! that symbol, so strategies cannot trade on prices they have not seen:
+//!
+//! - market orders fill at the open;
+//! - limit orders fill at the open or the limit, whichever is better, once
+//!   the bar trades through the limit;
+//! - stop orders trigger when the bar trades through the stop and fill at
+//!   the open or the stop, whichever is worse; stop-limit orders then
+//!   also need the limit to be reachable.
+//!
+//! Market and stop fills pay the configured [`SlippageModel`]; every fill
+//! pays the [`CommissionModel`]. Day orders expire at the end of the
+//! session they were placed in, IOC and FOK orders after one bar. Fills
+//! take the whole quantity; partial fills and trailing stops are not
+//! simulated.
+
+use crate::client::AlpacaHttpClient;
+use alpaca_base::{
+    AlpacaError, OrderReplacement, OrderRequest, Result, TradingApi,
+    types::{
+        Account, AccountStatus, AssetClass, Bar, MultiBarsParams, Order, OrderSide, OrderStatus,
+        OrderType, Position, PositionSide, TimeInForce,
+    },
+    utils::us_eastern_offset,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Price slippage applied to market and stop fills, always against the
+/// order.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SlippageModel {
+    /// Fill at the simulated price.
+    #[default]
+    None,
+    /// Fixed basis points of the price.
+    Bps(f64),
+    /// Fixed amount per share.
+    PerShare(f64),
+}
+
+impl SlippageModel {
+    /// Price after slippage for a fill on `side`.
+    #[must_use]
+    pub fn apply(&self, side: &OrderSide, price: f64) -> f64 {
+        let slip = match self {
+            Self::None => 0.0,
+            Self::Bps(bps) => price * bps / 10_000.0,
+            Self::PerShare(amount) => *amount,
+        };
+        match side {
+            OrderSide::Buy => price + slip,
+            OrderSide::Sell => price - slip,
+        }
+    }
+}
+
+/// Commission charged per fill.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CommissionModel {
+    /// Commission-free.
+    #[default]
+    None,
+    /// Fixed amount per order.
+    PerOrder(f64),
+    /// Amount per share with a per-order minimum.
+    PerShare {
+        /// Amount per share.
+        rate: f64,
+        /// Minimum per order.
+        minimum: f64,
+    },
+    /// Basis points of the traded notional.
+    Bps(f64),
+}
+
+impl CommissionModel {
+    /// Commission for a fill of `qty` at `price`.
+    #[must_use]
+    pub fn commission(&self, qty: f64, price: f64) -> f64 {
+        match self {
+            Self::None => 0.0,
+            Self::PerOrder(amount) => *amount,
+            Self::PerShare { rate, minimum } => (qty * rate).max(*minimum),
+            Self::Bps(bps) => qty * price * bps / 10_000.0,
+        }
+    }
+}
+
+/// Backtest settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestConfig {
+    /// Starting cash.
+    pub initial_cash: f64,
+    /// Slippage model.
+    pub slippage: SlippageModel,
+    /// Commission model.
+    pub commission: CommissionModel,
+    /// Whether sells may open short positions.
+    pub allow_short: bool,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            initial_cash: 100_000.0,
+            slippage: SlippageModel::None,
+            commission: CommissionModel::None,
+            allow_short: false,
+        }
+    }
+}
+
+impl BacktestConfig {
+    /// Create a config with the given starting cash.
+    #[must_use]
+    pub fn new(initial_cash: f64) -> Self {
+        Self {
+            initial_cash,
+            ..Default::default()
+        }
+    }
+
+    /// Set the slippage model.
+    #[must_use]
+    pub fn slippage(mut self, slippage: SlippageModel) -> Self {
+        self.slippage = slippage;
+        self
+    }
+
+    /// Set the commission model.
+    #[must_use]
+    pub fn commission(mut self, commission: CommissionModel) -> Self {
+        self.commission = commission;
+        self
+    }
+
+    /// Allow short selling.
+    #[must_use]
+    pub fn allow_short(mut self, allow: bool) -> Self {
+        self.allow_short = allow;
+        self
+    }
+}
+
+/// A simulated fill.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestFill {
+    /// Order ID.
+    pub order_id: Uuid,
+    /// Symbol.
+    pub symbol: String,
+    /// Side.
+    pub side: OrderSide,
+    /// Quantity.
+    pub qty: f64,
+    /// Fill price after slippage.
+    pub price: f64,
+    /// Commission paid.
+    pub commission: f64,
+    /// Fill time (the bar's timestamp).
+    pub time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Holding {
+    /// Signed quantity: negative when short.
+    qty: f64,
+    avg_price: f64,
+}
+
+#[derive(Debug)]
+struct PendingOrder {
+    request: OrderRequest,
+    order_index: usize,
+    session: NaiveDate,
+    bars_seen: u32,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    now: Option<DateTime<Utc>>,
+    cash: f64,
+    holdings: HashMap<String, Holding>,
+    last: HashMap<String, Bar>,
+    /// Close of the last bar of the previous session, per symbol.
+    prev_close: HashMap<String, f64>,
+    session: Option<NaiveDate>,
+    last_equity: f64,
+    pending: Vec<PendingOrder>,
+    orders: Vec<Order>,
+    fills: Vec<BacktestFill>,
+    equity_curve: Vec<(DateTime<Utc>, f64)>,
+}
+
+impl State {
+    fn price(&self, symbol: &str) -> Option<f64> {
+        self.last.get(symbol).map(|b| b.close)
+    }
+
+    fn equity(&self) -> f64 {
+        self.cash
+            + self
+                .holdings
+                .iter()
+                .map(|(symbol, h)| h.qty * self.price(symbol).unwrap_or(h.avg_price))
+                .sum::<f64>()
+    }
+}
+
+/// Simulated account replaying historical bars.
+#[derive(Debug)]
+pub struct Backtester {
+    config: BacktestConfig,
+    account_id: Uuid,
+    bars: BTreeMap<DateTime<Utc>, Vec<(String, Bar)>>,
+    state: Mutex<State>,
+}
+
+impl Backtester {
+    /// Create an empty backtest; add data with [`Backtester::with_bars`].
+    #[must_use]
+    pub fn new(config: BacktestConfig) -> Self {
+        let state = State {
+            cash: config.initial_cash,
+            last_equity: config.initial_cash,
+            ..Default::default()
+        };
+        Self {
+            config,
+            account_id: Uuid::new_v4(),
+            bars: BTreeMap::new(),
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Add bars for a symbol.
+    #[must_use]
+    pub fn with_bars(mut self, symbol: &str, bars: Vec<Bar>) -> Self {
+        for bar in bars {
+            self.bars
+                .entry(bar.timestamp)
+                .or_default()
+                .push((symbol.to_string(), bar));
+        }
+        self
+    }
+
+    /// Build a backtest from every page of
+    /// [`get_stock_bars`](AlpacaHttpClient::get_stock_bars).
+    pub async fn load(
+        client: &AlpacaHttpClient,
+        params: &MultiBarsParams,
+        config: BacktestConfig,
+    ) -> Result<Self> {
+        let mut backtest = Self::new(config);
+        let mut params = params.clone();
+        loop {
+            let page = client.get_stock_bars(&params).await?;
+            for (symbol, bars) in page.bars {
+                backtest = backtest.with_bars(&symbol, bars);
+            }
+            match page.next_page_token {
+                Some(token) => params.page_token = Some(token),
+                None => break,
+            }
+        }
+        Ok(backtest)
+    }
+
+    /// Advance to the next timestamp with data, filling pending orders
+    /// against its bars. Returns `None` once the data is exhausted.
+    pub fn step(&self) -> Option<DateTime<Utc>> {
+        let mut state = self.lock();
+        let (&time, bars) = match state.now {
+            Some(now) => self
+                .bars
+                .range((std::ops::Bound::Excluded(now), std::ops::Bound::Unbounded))
+                .next()?,
+            None => self.bars.iter().next()?,
+        };
+
+        let session = session_date(time);
+        if state.session.is_some_and(|s| s != session) {
+            state.last_equity = state.equity();
+            let closes: Vec<_> = state
+                .last
+                .iter()
+                .map(|(s, b)| (s.clone(), b.close))
+                .collect();
+            state.prev_close.extend(closes);
+        }
+        state.session = Some(session);
+        state.now = Some(time);
+        self.expire_pending(&mut state, session, time);
+
+        for (symbol, bar) in bars {
+            self.fill_pending(&mut state, symbol, bar, time);
+            state.last.insert(symbol.clone(), bar.clone());
+        }
+        self.expire_pending(&mut state, session, time);
+
+        let equity = state.equity();
+        state.equity_curve.push((time, equity));
+        Some(time)
+    }
+
+    /// Current simulated time.
+    #[must_use]
+    pub fn now(&self) -> Option<DateTime<Utc>> {
+        self.lock().now
+    }
+
+    /// Current equity, marked to the latest closes.
+    #[must_use]
+    pub fn equity(&self) -> f64 {
+        self.lock().equity()
+    }
+
+    /// Current cash.
+    #[must_use]
+    pub fn cash(&self) -> f64 {
+        self.lock().cash
+    }
+
+    /// Equity after each step.
+    #[must_use]
+    pub fn equity_curve(&self) -> Vec<(DateTime<Utc>, f64)> {
+        self.lock().equity_curve.clone()
+    }
+
+    /// Every order submitted, with its latest status.
+    #[must_use]
+    pub fn orders(&self) -> Vec<Order> {
+        self.lock().orders.clone()
+    }
+
+    /// Every simulated fill.
+    #[must_use]
+    pub fn fills(&self) -> Vec<BacktestFill> {
+        self.lock().fills.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn fill_pending(&self, state: &mut State, symbol: &str, bar: &Bar, time: DateTime<Utc>) {
+        let mut i = 0;
+        while i < state.pending.len() {
+            if state.pending[i].request.symbol != symbol {
+                i += 1;
+                continue;
+            }
+            state.pending[i].bars_seen += 1;
+            match self.fill_price(&state.pending[i].request, bar) {
+                Some(price) => {
+                    let pending = state.pending.remove(i);
+                    self.apply_fill(state, pending, price, time);
+                }
+                None => i += 1,
+            }
+        }
+    }
+
+    fn fill_price(&self, order: &OrderRequest, bar: &Bar) -> Option<f64> {
+        let buy = order.side == OrderSide::Buy;
+        let limit_fill = |limit: f64| {
+            if buy && bar.low <= limit {
+                Some(bar.open.min(limit))
+            } else if !buy && bar.high >= limit {
+                Some(bar.open.max(limit))
+            } else {
+                None
+            }
+        };
+        let stop_fill = |stop: f64| {
+            if buy && bar.high >= stop {
+                Some(bar.open.max(stop))
+            } else if !buy && bar.low <= stop {
+                Some(bar.open.min(stop))
+            } else {
+                None
+            }
+        };
+        let slip = |price: f64| self.config.slippage.apply(&order.side, price);
+        match order.order_type {
+            OrderType::Market => Some(slip(bar.open)),
+            OrderType::Limit => limit_fill(order.limit_price?),
+            OrderType::Stop => stop_fill(order.stop_price?).map(slip),
+            OrderType::StopLimit => {
+                let triggered = stop_fill(order.stop_price?)?;
+                let limit = order.limit_price?;
+                limit_fill(limit)?;
+                Some(if buy {
+                    triggered.min(limit)
+                } else {
+                    triggered.max(limit)
+                })
+            }
+            OrderType::TrailingStop => None,
+        }
+    }
+
+    fn apply_fill(
+        &self,
+        state: &mut State,
+        pending: PendingOrder,
+        price: f64,
+        time: DateTime<Utc>,
+    ) {
+        let request = pending.request;
+        let commission = self.config.commission.commission(request.qty, price);
+        let signed = match request.side {
+            OrderSide::Buy => request.qty,
+            OrderSide::Sell => -request.qty,
+        };
+
+        let holding = state.holdings.entry(request.symbol.clone()).or_default();
+        let new_qty = holding.qty + signed;
+        if holding.qty == 0.0 || holding.qty.signum() == signed.signum() {
+            holding.avg_price = (holding.avg_price * holding.qty + price * signed) / new_qty;
+        } else if new_qty != 0.0 && new_qty.signum() != holding.qty.signum() {
+            // Flipped through zero: the remainder opens at the fill price.
+            holding.avg_price = price;
+        }
+        holding.qty = new_qty;
+        if holding.qty.abs() < 1e-9 {
+            state.holdings.remove(&request.symbol);
+        }
+        state.cash -= signed * price + commission;
+
+        let order = &mut state.orders[pending.order_index];
+        order.status = OrderStatus::Filled;
+        order.filled_qty = request.qty.to_string();
+        order.filled_avg_price = Some(price.to_string());
+        order.filled_at = Some(time);
+        order.updated_at = time;
+        state.fills.push(BacktestFill {
+            order_id: order.id,
+            symbol: request.symbol,
+            side: request.side,
+            qty: request.qty,
+            price,
+            commission,
+            time,
+        });
+    }
+
+    fn expire_pending(&self, state: &mut State, session: NaiveDate, time: DateTime<Utc>) {
+        let State {
+            pending, orders, ..
+        } = state;
+        pending.retain(|p| {
+            let expired = match p.request.time_in_force {
+                TimeInForce::Ioc | TimeInForce::Fok => p.bars_seen > 0,
+                TimeInForce::Gtc => false,
+                _ => p.session < session,
+            };
+            if expired {
+                let order = &mut orders[p.order_index];
+                order.status = match p.request.time_in_force {
+                    TimeInForce::Ioc | TimeInForce::Fok => OrderStatus::Canceled,
+                    _ => OrderStatus::Expired,
+                };
+                order.updated_at = time;
+            }
+            !expired
+        });
+    }
+
+    fn check_order(&self, state: &State, order: &OrderRequest) -> Result<f64> {
+        let rejected = |message: &str| Err(AlpacaError::api(422, message));
+        if order.qty.is_nan() || order.qty <= 0.0 {
+            return rejected("qty must be positive");
+        }
+        let price_ok = |p: Option<f64>| p.is_some_and(|p| p > 0.0);
+        match order.order_type {
+            OrderType::Market => {}
+            OrderType::Limit if !price_ok(order.limit_price) => {
+                return rejected("limit_price is required for limit orders");
+            }
+            OrderType::Stop if !price_ok(order.stop_price) => {
+                return rejected("stop_price is required for stop orders");
+            }
+            OrderType::StopLimit if !price_ok(order.limit_price) || !price_ok(order.stop_price) => {
+                return rejected("stop_price and limit_price are required for stop-limit orders");
+            }
+            OrderType::TrailingStop => {
+                return rejected("trailing stop orders are not simulated");
+            }
+            _ => {}
+        }
+        let Some(last) = state.price(&order.symbol) else {
+            return rejected(&format!("no market data for {}", order.symbol));
+        };
+
+        let held = state.holdings.get(&order.symbol).map_or(0.0, |h| h.qty);
+        let committed: f64 = state
+            .pending
+            .iter()
+            .filter(|p| p.request.symbol == order.symbol && p.request.side == order.side)
+            .map(|p| p.request.qty)
+            .sum();
+        match order.side {
+            OrderSide::Buy => {
+                let price = order.limit_price.unwrap_or(last).max(last);
+                let covering = (-held - committed).max(0.0).min(order.qty);
+                let cost = (order.qty - covering) * price
+                    + self.config.commission.commission(order.qty, price);
+                let reserved: f64 = state
+                    .pending
+                    .iter()
+                    .filter(|p| p.request.side == OrderSide::Buy)
+                    .map(|p| p.request.qty * p.request.limit_price.unwrap_or(last))
+                    .sum();
+                if cost > state.cash - reserved {
+                    return Err(AlpacaError::api(403, "insufficient buying power"));
+                }
+            }
+            OrderSide::Sell => {
+                if !self.config.allow_short && order.qty > held - committed + 1e-9 {
+                    return Err(AlpacaError::api(
+                        403,
+                        "insufficient qty available for order",
+                    ));
+                }
+            }
+        }
+        Ok(last)
+    }
+
+    fn take_pending(&self, state: &mut State, order_id: Uuid) -> Result<PendingOrder> {
+        let index = state
+            .pending
+            .iter()
+            .position(|p| state.orders[p.order_index].id == order_id)
+            .ok_or_else(|| AlpacaError::api(422, format!("order {order_id} is not open")))?;
+        Ok(state.pending.remove(index))
+    }
+
+    fn position(&self, state: &State, symbol: &str, holding: &Holding) -> Position {
+        let price = state.price(symbol).unwrap_or(holding.avg_price);
+        let lastday = state.prev_close.get(symbol).copied().unwrap_or(price);
+        let market_value = holding.qty * price;
+        let cost_basis = holding.qty * holding.avg_price;
+        let unrealized = market_value - cost_basis;
+        let intraday = holding.qty * (price - lastday);
+        let ratio = |num: f64, den: f64| if den == 0.0 { 0.0 } else { num / den.abs() };
+        Position {
+            asset_id: Uuid::nil(),
+            symbol: symbol.to_string(),
+            exchange: String::new(),
+            asset_class: AssetClass::UsEquity,
+            avg_entry_price: holding.avg_price.to_string(),
+            qty: holding.qty.to_string(),
+            side: if holding.qty < 0.0 {
+                PositionSide::Short
+            } else {
+                PositionSide::Long
+            },
+            market_value: market_value.to_string(),
+            cost_basis: cost_basis.to_string(),
+            unrealized_pl: unrealized.to_string(),
+            unrealized_plpc: ratio(unrealized, cost_basis).to_string(),
+            unrealized_intraday_pl: intraday.to_string(),
+            unrealized_intraday_plpc: ratio(intraday, holding.qty * lastday).to_string(),
+            current_price: price.to_string(),
+            lastday_price: lastday.to_string(),
+            change_today: ratio(price - lastday, lastday).to_string(),
+        }
+    }
+}
+
+impl TradingApi for Backtester {
+    async fn submit_order(&self, order: &OrderRequest) -> Result<Order> {
+        let mut state = self.lock();
+        let Some(now) = state.now else {
+            return Err(AlpacaError::api(
+                422,
+                "backtest has not started; call step() first",
+            ));
+        };
+        self.check_order(&state, order)?;
+
+        let created = new_order(order, now);
+        state.orders.push(created.clone());
+        let order_index = state.orders.len() - 1;
+        state.pending.push(PendingOrder {
+            request: order.clone(),
+            order_index,
+            session: session_date(now),
+            bars_seen: 0,
+        });
+        Ok(created)
+    }
+
+    async fn cancel_order(&self, order: &Order) -> Result<()> {
+        let mut state = self.lock();
+        let now = state.now.unwrap_or_else(Utc::now);
+        let pending = self.take_pending(&mut state, order.id)?;
+        let canceled = &mut state.orders[pending.order_index];
+        canceled.status = OrderStatus::Canceled;
+        canceled.canceled_at = Some(now);
+        canceled.updated_at = now;
+        Ok(())
+    }
+
+    async fn replace_order(&self, order: &Order, replacement: &OrderReplacement) -> Result<Order> {
+        let mut state = self.lock();
+        let now = state.now.unwrap_or_else(Utc::now);
+        let pending = self.take_pending(&mut state, order.id)?;
+
+        let mut request = pending.request.clone();
+        request.qty = replacement.qty.unwrap_or(request.qty);
+        request.limit_price = replacement.limit_price.or(request.limit_price);
+        request.stop_price = replacement.stop_price.or(request.stop_price);
+        if let Some(tif) = &replacement.time_in_force {
+            request.time_in_force = tif.clone();
+        }
+        request.client_order_id = None;
+        if let Err(e) = self.check_order(&state, &request) {
+            state.pending.push(pending);
+            return Err(e);
+        }
+
+        let mut created = new_order(&request, now);
+        created.replaces = Some(order.id);
+        let old = &mut state.orders[pending.order_index];
+        old.status = OrderStatus::Replaced;
+        old.replaced_at = Some(now);
+        old.replaced_by = Some(created.id);
+        old.updated_at = now;
+
+        state.orders.push(created.clone());
+        let order_index = state.orders.len() - 1;
+        state.pending.push(PendingOrder {
+            request,
+            order_index,
+            session: session_date(now),
+            bars_seen: 0,
+        });
+        Ok(created)
+    }
+
+    async fn get_positions(&self) -> Result<Vec<Position>> {
+        let state = self.lock();
+        let mut positions: Vec<_> = state
+            .holdings
+            .iter()
+            .map(|(symbol, holding)| self.position(&state, symbol, holding))
+            .collect();
+        positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        Ok(positions)
+    }
+
+    async fn get_account(&self) -> Result<Account> {
+        let state = self.lock();
+        let (mut long, mut short) = (0.0, 0.0);
+        for (symbol, holding) in &state.holdings {
+            let value = holding.qty * state.price(symbol).unwrap_or(holding.avg_price);
+            if value >= 0.0 {
+                long += value;
+            } else {
+                short += value;
+            }
+        }
+        let equity = state.equity();
+        Ok(Account {
+            id: self.account_id,
+            account_number: "BACKTEST".to_string(),
+            status: AccountStatus::Active,
+            currency: "USD".to_string(),
+            buying_power: state.cash.max(0.0).to_string(),
+            regt_buying_power: state.cash.max(0.0).to_string(),
+            daytrading_buying_power: "0".to_string(),
+            cash: state.cash.to_string(),
+            portfolio_value: equity.to_string(),
+            pattern_day_trader: false,
+            trading_blocked: false,
+            transfers_blocked: false,
+            account_blocked: false,
+            created_at: self.bars.keys().next().copied().unwrap_or_else(Utc::now),
+            trade_suspended_by_user: false,
+            multiplier: "1".to_string(),
+            shorting_enabled: self.config.allow_short,
+            equity: equity.to_string(),
+            last_equity: state.last_equity.to_string(),
+            long_market_value: long.to_string(),
+            short_market_value: short.to_string(),
+            initial_margin: "0".to_string(),
+            maintenance_margin: "0".to_string(),
+            last_maintenance_margin: "0".to_string(),
+            sma: "0".to_string(),
+            daytrade_count: 0,
+        })
+    }
+}
+
+/// Trading session (US Eastern date) of a timestamp.
+fn session_date(time: DateTime<Utc>) -> NaiveDate {
+    let utc_date = time.date_naive();
+    time.with_timezone(&us_eastern_offset(utc_date))
+        .date_naive()
+}
+
+fn new_order(request: &OrderRequest, now: DateTime<Utc>) -> Order {
+    let id = Uuid::new_v4();
+    let client_order_id = request
+        .client_order_id
+        .clone()
+        .unwrap_or_else(|| id.to_string());
+    let mut order = request.pending_order(id, &client_order_id, now);
+    order.status = OrderStatus::New;
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn bar(day: u32, hour: u32, open: f64, high: f64, low: f64, close: f64) -> Bar {
+        Bar {
+            timestamp: Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap(),
+            open,
+            high,
+            low,
+            close,
+            volume: 1_000,
+            trade_count: None,
+            vwap: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_market_order_fills_next_bar_with_costs() {
+        let config = BacktestConfig::new(10_000.0)
+            .slippage(SlippageModel::PerShare(0.05))
+            .commission(CommissionModel::PerOrder(1.0));
+        let bt = Backtester::new(config).with_bars(
+            "AAPL",
+            vec![
+                bar(4, 15, 99.0, 101.0, 98.0, 100.0),
+                bar(4, 16, 102.0, 103.0, 101.0, 102.5),
+                bar(4, 17, 104.0, 105.0, 103.0, 105.0),
+            ],
+        );
+        assert!(
+            bt.submit_order(&OrderRequest::market("AAPL", OrderSide::Buy, 10.0))
+                .await
+                .is_err()
+        );
+
+        bt.step();
+        let order = bt
+            .submit_order(&OrderRequest::market("AAPL", OrderSide::Buy, 10.0))
+            .await
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::New);
+        assert!(bt.get_positions().await.unwrap().is_empty());
+
+        let resting = bt
+            .submit_order(&OrderRequest::limit("AAPL", OrderSide::Buy, 5.0, 50.0))
+            .await
+            .unwrap();
+        let replaced = bt
+            .replace_order(&resting, &OrderReplacement::new().limit_price(51.0))
+            .await
+            .unwrap();
+        assert_eq!(replaced.replaces, Some(resting.id));
+        assert_eq!(replaced.limit_price.as_deref(), Some("51"));
+        assert_eq!(bt.orders()[1].status, OrderStatus::Replaced);
+        bt.cancel_order(&replaced).await.unwrap();
+
+        bt.step();
+        let fills = bt.fills();
+        assert_eq!(fills.len(), 1);
+        assert!((fills[0].price - 102.05).abs() < 1e-9);
+        assert!((bt.cash() - (10_000.0 - 1_020.5 - 1.0)).abs() < 1e-9);
+
+        bt.step();
+        let positions = bt.get_positions().await.unwrap();
+        assert_eq!(positions[0].qty, "10");
+        assert_eq!(positions[0].current_price, "105");
+        let account = bt.get_account().await.unwrap();
+        let equity: f64 = account.equity.parse().unwrap();
+        assert!((equity - (10_000.0 - 1_021.5 + 1_050.0)).abs() < 1e-9);
+        assert_eq!(bt.orders()[0].status, OrderStatus::Filled);
+        assert!(bt.step().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_limit_orders_and_day_expiry() {
+        let bt = Backtester::new(BacktestConfig::default()).with_bars(
+            "SPY",
+            vec![
+                bar(4, 15, 500.0, 501.0, 499.0, 500.0),
+                bar(4, 16, 500.0, 502.0, 498.5, 499.0),
+                bar(4, 17, 499.0, 500.0, 497.0, 498.0),
+                bar(5, 15, 490.0, 491.0, 480.0, 485.0),
+            ],
+        );
+        bt.step();
+        bt.submit_order(&OrderRequest::limit("SPY", OrderSide::Buy, 1.0, 497.5))
+            .await
+            .unwrap();
+        bt.submit_order(&OrderRequest::limit("SPY", OrderSide::Buy, 1.0, 490.0))
+            .await
+            .unwrap();
+        let far = bt
+            .submit_order(&OrderRequest::limit("SPY", OrderSide::Buy, 1.0, 450.0))
+            .await
+            .unwrap();
+        bt.cancel_order(&far).await.unwrap();
+        assert!(bt.cancel_order(&far).await.is_err());
+        bt.step();
+        assert!(bt.fills().is_empty());
+        bt.step();
+        assert_eq!(bt.fills().len(), 1);
+        assert_eq!(bt.fills()[0].price, 497.5);
+        assert_eq!(bt.orders()[2].status, OrderStatus::Canceled);
+
+        // The unfilled day order expires before the next session's bars.
+        bt.step();
+        assert_eq!(bt.fills().len(), 1);
+        assert_eq!(bt.orders()[1].status, OrderStatus::Expired);
+
+        let short = OrderRequest::market("SPY", OrderSide::Sell, 5.0);
+        assert!(matches!(
+            bt.submit_order(&short).await,
+            Err(AlpacaError::Api { status: 403, .. })
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+//! Batch cash journals with reconciliation and idempotent retries.
+//!
+//! `create_batch_journals` can book some entries and reject others, and a
+//! timeout leaves it unknown which entries were booked at all. A
+//! [`JournalBatch`] gives every entry an idempotency key, embedded in the
+//! entry description as `[idem:<key>]`. After each attempt the returned
+//! journals are matched back to the entries by key (falling back to
+//! recipient and amount), and only the entries that failed or were not
+//! confirmed are retried. Before a retry, the journals already booked from
+//! the source account are searched for the pending keys, so an entry whose
+//! outcome was lost is never booked twice.
+
+use alpaca_base::types::{BatchJournalEntry, CreateBatchJournalRequest, Journal, JournalEntryType};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Prefix of the idempotency tag in entry descriptions.
+pub const IDEMPOTENCY_TAG: &str = "[idem:";
+
+/// One cash movement in a [`JournalBatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalBatchEntry {
+    /// Idempotency key.
+    pub key: String,
+    /// To account ID.
+    pub to_account: String,
+    /// Amount.
+    pub amount: String,
+    /// Description.
+    pub description: Option<String>,
+}
+
+impl JournalBatchEntry {
+    /// Create an entry with a random idempotency key.
+    #[must_use]
+    pub fn new(to_account: &str, amount: &str) -> Self {
+        Self {
+            key: Uuid::new_v4().to_string(),
+            to_account: to_account.to_string(),
+            amount: amount.to_string(),
+            description: None,
+        }
+    }
+
+    /// Set the idempotency key, e.g. a payout ID from the partner's ledger.
+    #[must_use]
+    pub fn key(mut self, key: &str) -> Self {
+        self.key = key.to_string();
+        self
+    }
+
+    /// Set the description.
+    #[must_use]
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// The description sent to the API, carrying the idempotency tag.
+    #[must_use]
+    pub fn tagged_description(&self) -> String {
+        let tag = format!("{IDEMPOTENCY_TAG}{}]", self.key);
+        match &self.description {
+            Some(description) => format!("{description} {tag}"),
+            None => tag,
+        }
+    }
+
+    /// Whether `journal` was booked for this entry, judged by its tag.
+    #[must_use]
+    pub fn is_tagged_on(&self, journal: &Journal) -> bool {
+        idempotency_key(journal) == Some(self.key.as_str())
+    }
+
+    fn to_request(&self) -> BatchJournalEntry {
+        BatchJournalEntry {
+            to_account: self.to_account.clone(),
+            amount: self.amount.clone(),
+            description: Some(self.tagged_description()),
+        }
+    }
+
+    fn matches_amount(&self, journal: &Journal) -> bool {
+        if journal.to_account != self.to_account {
+            return false;
+        }
+        let Some(net_amount) = journal.net_amount.as_deref() else {
+            return false;
+        };
+        match (net_amount.parse::<f64>(), self.amount.parse::<f64>()) {
+            (Ok(a), Ok(b)) => (a.abs() - b.abs()).abs() < 1e-9,
+            _ => net_amount == self.amount,
+        }
+    }
+}
+
+/// The idempotency key tagged on a journal's description, if any.
+#[must_use]
+pub fn idempotency_key(journal: &Journal) -> Option<&str> {
+    let description = journal.description.as_deref()?;
+    let start = description.rfind(IDEMPOTENCY_TAG)? + IDEMPOTENCY_TAG.len();
+    let len = description[start..].find(']')?;
+    Some(&description[start..start + len])
+}
+
+/// Cash journals from one account to many, submitted together.
+#[derive(Debug, Clone)]
+pub struct JournalBatch {
+    /// From account ID.
+    pub from_account: String,
+    /// Entries.
+    pub entries: Vec<JournalBatchEntry>,
+    /// Maximum number of submissions, including the first.
+    pub max_attempts: u32,
+    /// Delay between submissions.
+    pub retry_delay: Duration,
+}
+
+impl JournalBatch {
+    /// Create an empty batch from `from_account`, with 3 attempts one
+    /// second apart.
+    #[must_use]
+    pub fn new(from_account: &str) -> Self {
+        Self {
+            from_account: from_account.to_string(),
+            entries: Vec::new(),
+            max_attempts: 3,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Add an entry.
+    #[must_use]
+    pub fn entry(mut self, entry: JournalBatchEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Set the maximum number of submissions.
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay between submissions.
+    #[must_use]
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// The batch request for the entries at `indices`.
+    #[must_use]
+    pub fn request(&self, indices: &[usize]) -> CreateBatchJournalRequest {
+        CreateBatchJournalRequest {
+            from_account: self.from_account.clone(),
+            entry_type: JournalEntryType::Jnlc,
+            entries: indices
+                .iter()
+                .map(|&i| self.entries[i].to_request())
+                .collect(),
+            description: None,
+        }
+    }
+}
+
+/// Match returned journals to the entries at `indices`.
+///
+/// Journals are matched by idempotency tag first, then by recipient and
+/// amount; each journal matches at most one entry. The result is aligned
+/// with `indices`.
+#[must_use]
+pub fn match_journals<'a>(
+    entries: &[JournalBatchEntry],
+    indices: &[usize],
+    journals: &'a [Journal],
+) -> Vec<Option<&'a Journal>> {
+    let mut matched: Vec<Option<&Journal>> = vec![None; indices.len()];
+    let mut used = vec![false; journals.len()];
+    for (slot, &i) in indices.iter().enumerate() {
+        if let Some(j) = journals.iter().position(|j| entries[i].is_tagged_on(j)) {
+            matched[slot] = Some(&journals[j]);
+            used[j] = true;
+        }
+    }
+    for (slot, &i) in indices.iter().enumerate() {
+        if matched[slot].is_some() {
+            continue;
+        }
+        if let Some(j) = (0..journals.len()).find(|&j| {
+            !used[j]
+                && idempotency_key(&journals[j]).is_none()
+                && entries[i].matches_amount(&journals[j])
+        }) {
+            matched[slot] = Some(&journals[j]);
+            used[j] = true;
+        }
+    }
+    matched
+}
+
+/// Final state of a batch entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalEntryOutcome {
+    /// A journal was booked for the entry.
+    Booked,
+    /// The entry was rejected on every attempt.
+    Failed,
+    /// No journal could be found for the entry; it was never confirmed
+    /// either way.
+    Unconfirmed,
+}
+
+/// Per-entry result of a [`JournalBatch`] submission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntryReport {
+    /// Idempotency key.
+    pub key: String,
+    /// To account ID.
+    pub to_account: String,
+    /// Amount.
+    pub amount: String,
+    /// Outcome.
+    pub outcome: JournalEntryOutcome,
+    /// The booked (or last rejected) journal.
+    pub journal: Option<Journal>,
+    /// Last error.
+    pub error: Option<String>,
+    /// Number of submissions that included the entry.
+    pub attempts: u32,
+}
+
+impl JournalEntryReport {
+    /// A report for an entry not yet submitted.
+    #[must_use]
+    pub fn pending(entry: &JournalBatchEntry) -> Self {
+        Self {
+            key: entry.key.clone(),
+            to_account: entry.to_account.clone(),
+            amount: entry.amount.clone(),
+            outcome: JournalEntryOutcome::Unconfirmed,
+            journal: None,
+            error: None,
+            attempts: 0,
+        }
+    }
+
+    /// Record the journal returned for the entry, or its absence.
+    pub fn record(&mut self, journal: Option<&Journal>) {
+        match journal {
+            Some(journal) if journal.status.is_failed() || journal.error_message.is_some() => {
+                self.outcome = JournalEntryOutcome::Failed;
+                self.error = Some(
+                    journal
+                        .error_message
+                        .clone()
+                        .unwrap_or_else(|| format!("journal {:?}", journal.status)),
+                );
+                self.journal = Some(journal.clone());
+            }
+            Some(journal) => {
+                self.outcome = JournalEntryOutcome::Booked;
+                self.error = None;
+                self.journal = Some(journal.clone());
+            }
+            None => {
+                self.outcome = JournalEntryOutcome::Unconfirmed;
+                self.error = Some("no journal returned for entry".to_string());
+            }
+        }
+    }
+
+    /// Whether the entry was booked.
+    #[must_use]
+    pub fn is_booked(&self) -> bool {
+        self.outcome == JournalEntryOutcome::Booked
+    }
+}
+
+/// Result of a [`JournalBatch`] submission, one report per entry in
+/// request order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchJournalReport {
+    /// From account ID.
+    pub from_account: String,
+    /// Per-entry reports.
+    pub entries: Vec<JournalEntryReport>,
+}
+
+impl BatchJournalReport {
+    /// Whether every entry was booked.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.entries.iter().all(JournalEntryReport::is_booked)
+    }
+
+    /// Entries that were booked.
+    pub fn booked(&self) -> impl Iterator<Item = &JournalEntryReport> {
+        self.entries.iter().filter(|e| e.is_booked())
+    }
+
+    /// Entries that were not booked.
+    pub fn unbooked(&self) -> impl Iterator<Item = &JournalEntryReport> {
+        self.entries.iter().filter(|e| !e.is_booked())
+    }
+
+    /// Indices of the entries still to be booked.
+    #[must_use]
+    pub fn pending_indices(&self) -> Vec<usize> {
+        (0..self.entries.len())
+            .filter(|&i| !self.entries[i].is_booked())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::types::JournalStatus;
+
+    fn journal(
+        to: &str,
+        amount: &str,
+        status: JournalStatus,
+        description: Option<String>,
+    ) -> Journal {
+        serde_json::from_value(serde_json::json!({
+            "id": format!("j-{to}"),
+            "from_account": "firm",
+            "to_account": to,
+            "entry_type": "JNLC",
+            "status": status,
+            "net_amount": amount,
+            "description": description
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_match_journals_by_tag_then_amount() {
+        let batch = JournalBatch::new("firm")
+            .entry(
+                JournalBatchEntry::new("acc1", "10.00")
+                    .key("payout-1")
+                    .description("bonus"),
+            )
+            .entry(JournalBatchEntry::new("acc2", "20.00").key("payout-2"))
+            .entry(JournalBatchEntry::new("acc3", "30.00").key("payout-3"));
+        assert_eq!(
+            batch.entries[0].tagged_description(),
+            "bonus [idem:payout-1]"
+        );
+        let request = batch.request(&[0, 1, 2]);
+        assert_eq!(
+            request.entries[1].description.as_deref(),
+            Some("[idem:payout-2]")
+        );
+
+        let journals = vec![
+            journal(
+                "acc2",
+                "20",
+                JournalStatus::Rejected,
+                Some("[idem:payout-2]".into()),
+            ),
+            journal("acc1", "10", JournalStatus::Queued, None),
+        ];
+        let matched = match_journals(&batch.entries, &[0, 1, 2], &journals);
+        assert_eq!(matched[0].map(|j| j.id.as_str()), Some("j-acc1"));
+        assert_eq!(matched[1].map(|j| j.id.as_str()), Some("j-acc2"));
+        assert!(matched[2].is_none());
+
+        let mut report = BatchJournalReport {
+            from_account: batch.from_account.clone(),
+            entries: batch
+                .entries
+                .iter()
+                .map(JournalEntryReport::pending)
+                .collect(),
+        };
+        for (i, journal) in matched.into_iter().enumerate() {
+            report.entries[i].record(journal);
+        }
+        assert_eq!(report.entries[0].outcome, JournalEntryOutcome::Booked);
+        assert_eq!(report.entries[1].outcome, JournalEntryOutcome::Failed);
+        assert_eq!(report.entries[2].outcome, JournalEntryOutcome::Unconfirmed);
+        assert_eq!(report.pending_indices(), vec![1, 2]);
+        assert!(!report.is_complete());
+    }
+}
@@ -0,0 +1,277 @@
+//! Adjusting the exit legs of live bracket orders.
+//!
+//! The take-profit and stop-loss of a bracket are child orders with IDs of
+//! their own, listed in the parent's `legs`. [`AlpacaHttpClient::adjust_take_profit`]
+//! and [`AlpacaHttpClient::adjust_stop_loss`] take the parent order ID
+//! instead: they fetch the parent with its legs, pick the matching leg and
+//! replace its price.
+//!
+//! ```rust,ignore
+//! let order = client.create_order(&bracket_request).await?;
+//! // Once filled, trail the stop up to break-even.
+//! client.adjust_stop_loss(&order.id, 180.0).await?;
+//! ```
+//!
+//! OCO and OTO orders work the same way; an OCO parent is itself the
+//! take-profit. Before replacing, the leg must still be open and the new
+//! price must stay on its side of the other leg (above the stop for a
+//! long's take-profit, below it for a short's). A stop-limit stop loss
+//! moves its limit price along with its stop. The replacement order is
+//! checked to carry the new price before it is returned.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::ReplaceOrderRequest;
+use crate::protection::{is_closed, same};
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{Order, OrderClass, OrderSide, OrderStatus, OrderType},
+    utils::{format_price, parse_decimal},
+};
+use uuid::Uuid;
+
+/// Exit leg of a bracket, OCO or OTO order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketLeg {
+    /// The take-profit limit order.
+    TakeProfit,
+    /// The stop-loss stop or stop-limit order.
+    StopLoss,
+}
+
+impl BracketLeg {
+    fn name(self) -> &'static str {
+        match self {
+            Self::TakeProfit => "take-profit",
+            Self::StopLoss => "stop-loss",
+        }
+    }
+
+    fn matches(self, order: &Order) -> bool {
+        match self {
+            Self::TakeProfit => order.order_type == OrderType::Limit,
+            Self::StopLoss => matches!(order.order_type, OrderType::Stop | OrderType::StopLimit),
+        }
+    }
+
+    /// The price this leg triggers at.
+    fn price(self, order: &Order) -> Option<&str> {
+        match self {
+            Self::TakeProfit => order.limit_price.as_deref(),
+            Self::StopLoss => order.stop_price.as_deref(),
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Self::TakeProfit => Self::StopLoss,
+            Self::StopLoss => Self::TakeProfit,
+        }
+    }
+}
+
+impl AlpacaHttpClient {
+    /// Move the take-profit of the bracket, OCO or OTO order `order_id` to
+    /// `price`, returning the replacement leg.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if the order has no open
+    /// take-profit leg or `price` would cross the stop loss, and
+    /// [`AlpacaError::InvalidData`] if the replacement does not carry the
+    /// new price.
+    pub async fn adjust_take_profit(&self, order_id: &Uuid, price: f64) -> Result<Order> {
+        self.adjust_bracket_leg(order_id, BracketLeg::TakeProfit, price)
+            .await
+    }
+
+    /// Move the stop loss of the bracket, OCO or OTO order `order_id` to
+    /// `price`, returning the replacement leg.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if the order has no open
+    /// stop-loss leg or `price` would cross the take-profit, and
+    /// [`AlpacaError::InvalidData`] if the replacement does not carry the
+    /// new price.
+    pub async fn adjust_stop_loss(&self, order_id: &Uuid, price: f64) -> Result<Order> {
+        self.adjust_bracket_leg(order_id, BracketLeg::StopLoss, price)
+            .await
+    }
+
+    /// Move `leg` of the multi-leg order `order_id` to `price`.
+    ///
+    /// # Errors
+    /// See [`Self::adjust_take_profit`] and [`Self::adjust_stop_loss`].
+    pub async fn adjust_bracket_leg(
+        &self,
+        order_id: &Uuid,
+        leg: BracketLeg,
+        price: f64,
+    ) -> Result<Order> {
+        let parent: Order = self
+            .get(&format!("/v2/orders/{}?nested=true", order_id))
+            .await?;
+        let (leg_id, request) = plan_adjustment(&parent, leg, price)?;
+        let replacement = self.replace_order(&leg_id, &request).await?;
+        verify_replacement(&replacement, leg, price)?;
+        Ok(replacement)
+    }
+}
+
+/// The leg of `parent` to replace and the replacement moving it to
+/// `price`.
+pub(crate) fn plan_adjustment(
+    parent: &Order,
+    leg: BracketLeg,
+    price: f64,
+) -> Result<(Uuid, ReplaceOrderRequest)> {
+    if !price.is_finite() || price <= 0.0 {
+        return Err(AlpacaError::Validation(format!(
+            "{} price must be positive, got {price}",
+            leg.name()
+        )));
+    }
+    let order = find_leg(parent, leg)?;
+    if is_closed(&order.status) {
+        return Err(AlpacaError::Validation(format!(
+            "{} leg {} of order {} is {:?}",
+            leg.name(),
+            order.id,
+            parent.id,
+            order.status
+        )));
+    }
+    if let Ok(other) = find_leg(parent, leg.other())
+        && let Some(other_price) = leg.other().price(other)
+    {
+        let other_price = parse_decimal(other_price)?;
+        // Exits of a long sell: take-profit above the stop. Exits of a
+        // short buy: take-profit below the stop.
+        let (take_profit, stop_loss) = match leg {
+            BracketLeg::TakeProfit => (price, other_price),
+            BracketLeg::StopLoss => (other_price, price),
+        };
+        let crosses = match order.side {
+            OrderSide::Sell => take_profit <= stop_loss,
+            OrderSide::Buy => take_profit >= stop_loss,
+        };
+        if crosses {
+            return Err(AlpacaError::Validation(format!(
+                "{} at {price} would cross the {} at {other_price}",
+                leg.name(),
+                leg.other().name()
+            )));
+        }
+    }
+
+    let request = match (leg, &order.order_type) {
+        (BracketLeg::TakeProfit, _) => ReplaceOrderRequest::new().limit_price(format_price(price)),
+        (BracketLeg::StopLoss, OrderType::StopLimit) => {
+            // Keep the stop-to-limit distance.
+            let stop = parse_decimal(order.stop_price.as_deref().unwrap_or_default())?;
+            let limit = parse_decimal(order.limit_price.as_deref().unwrap_or_default())?;
+            ReplaceOrderRequest::new()
+                .stop_price(format_price(price))
+                .limit_price(format_price(limit + price - stop))
+        }
+        (BracketLeg::StopLoss, _) => ReplaceOrderRequest::new().stop_price(format_price(price)),
+    };
+    Ok((order.id, request))
+}
+
+/// Check that `replacement` moved `leg` to `price`.
+pub(crate) fn verify_replacement(replacement: &Order, leg: BracketLeg, price: f64) -> Result<()> {
+    if replacement.status == OrderStatus::Rejected {
+        return Err(AlpacaError::InvalidData(format!(
+            "replacement {} leg {} was rejected",
+            leg.name(),
+            replacement.id
+        )));
+    }
+    // Compare at the precision the price was sent with.
+    let sent = parse_decimal(&format_price(price))?;
+    if !same(leg.price(replacement), sent) {
+        return Err(AlpacaError::InvalidData(format!(
+            "replacement {} leg {} has price {:?}, expected {sent}",
+            leg.name(),
+            replacement.id,
+            leg.price(replacement)
+        )));
+    }
+    Ok(())
+}
+
+fn find_leg(parent: &Order, leg: BracketLeg) -> Result<&Order> {
+    if parent.order_class == OrderClass::Simple {
+        return Err(AlpacaError::Validation(format!(
+            "order {} is a simple order without legs",
+            parent.id
+        )));
+    }
+    // An OCO parent is the take-profit; a bracket or OTO parent is the
+    // entry.
+    let parent_leg = (parent.order_class == OrderClass::Oco).then_some(parent);
+    parent_leg
+        .into_iter()
+        .chain(parent.legs.iter().flatten())
+        .find(|order| leg.matches(order))
+        .ok_or_else(|| {
+            AlpacaError::Validation(format!("order {} has no {} leg", parent.id, leg.name()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_order;
+
+    fn bracket(side: OrderSide) -> Order {
+        let exit = match side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let mut take_profit = sample_order("AAPL", exit.clone(), "10");
+        take_profit.order_type = OrderType::Limit;
+        take_profit.limit_price = Some("190.00".to_string());
+        let mut stop_loss = sample_order("AAPL", exit, "10");
+        stop_loss.order_type = OrderType::StopLimit;
+        stop_loss.stop_price = Some("170.00".to_string());
+        stop_loss.limit_price = Some("169.50".to_string());
+        stop_loss.status = OrderStatus::Accepted;
+        let mut parent = sample_order("AAPL", side, "10");
+        parent.order_class = OrderClass::Bracket;
+        parent.status = OrderStatus::Filled;
+        parent.legs = Some(vec![take_profit, stop_loss]);
+        parent
+    }
+
+    #[test]
+    fn test_adjust_bracket_legs() {
+        let parent = bracket(OrderSide::Buy);
+        let legs = parent.legs.clone().unwrap();
+
+        let (id, request) = plan_adjustment(&parent, BracketLeg::TakeProfit, 195.0).unwrap();
+        assert_eq!(id, legs[0].id);
+        assert_eq!(request.limit_price.as_deref(), Some("195.00"));
+        assert!(request.stop_price.is_none());
+
+        let (id, request) = plan_adjustment(&parent, BracketLeg::StopLoss, 180.0).unwrap();
+        assert_eq!(id, legs[1].id);
+        assert_eq!(request.stop_price.as_deref(), Some("180.00"));
+        assert_eq!(request.limit_price.as_deref(), Some("179.50"));
+
+        // A long's stop cannot move above its take-profit, nor a short's
+        // below.
+        assert!(plan_adjustment(&parent, BracketLeg::StopLoss, 191.0).is_err());
+        assert!(plan_adjustment(&bracket(OrderSide::Sell), BracketLeg::StopLoss, 180.0).is_err());
+        assert!(plan_adjustment(&parent, BracketLeg::TakeProfit, -1.0).is_err());
+
+        let mut filled = parent.clone();
+        filled.legs.as_mut().unwrap()[0].status = OrderStatus::Filled;
+        assert!(plan_adjustment(&filled, BracketLeg::TakeProfit, 195.0).is_err());
+        assert!(plan_adjustment(&legs[0], BracketLeg::TakeProfit, 195.0).is_err());
+
+        let mut replacement = legs[1].clone();
+        replacement.stop_price = Some("180.00".to_string());
+        assert!(verify_replacement(&replacement, BracketLeg::StopLoss, 180.0).is_ok());
+        assert!(verify_replacement(&replacement, BracketLeg::StopLoss, 181.0).is_err());
+    }
+}
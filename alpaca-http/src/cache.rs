@@ -0,0 +1,237 @@
+//! Response caching for slow-changing resources.
+//!
+//! Bots that call [`AlpacaHttpClient::get_clock`] or
+//! [`AlpacaHttpClient::get_account`] on every loop iteration mostly fetch the
+//! same response again. [`AlpacaHttpClient::with_cache`] keeps the account,
+//! account configurations, asset list, calendar and clock responses for a
+//! per-resource TTL; the
+//! cache is shared by all clones of the client. Nothing else is cached. The
+//! account is invalidated whenever the client itself submits, replaces or
+//! cancels an order or closes a position; call [`ResponseCache::invalidate`]
+//! after changes it cannot see, e.g. the account after an order fills.
+//!
+//! [`AlpacaHttpClient::get_clock`]: crate::AlpacaHttpClient::get_clock
+//! [`AlpacaHttpClient::get_account`]: crate::AlpacaHttpClient::get_account
+//! [`AlpacaHttpClient::with_cache`]: crate::AlpacaHttpClient::with_cache
+
+use crate::query::QueryParams;
+use alpaca_base::Result;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A cacheable resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachedResource {
+    /// `GET /v2/account`.
+    Account,
+    /// `GET /v2/account/configurations`.
+    AccountConfigurations,
+    /// `GET /v2/assets`, per query.
+    Assets,
+    /// `GET /v2/calendar`, per query.
+    Calendar,
+    /// `GET /v2/clock`.
+    Clock,
+}
+
+/// Per-resource TTLs; `None` disables caching for that resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Account TTL.
+    pub account: Option<Duration>,
+    /// Account configurations TTL.
+    pub account_configurations: Option<Duration>,
+    /// Asset list TTL.
+    pub assets: Option<Duration>,
+    /// Calendar TTL.
+    pub calendar: Option<Duration>,
+    /// Clock TTL.
+    pub clock: Option<Duration>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            account: Some(Duration::from_secs(5)),
+            account_configurations: Some(Duration::from_secs(60)),
+            assets: Some(Duration::from_secs(3600)),
+            calendar: Some(Duration::from_secs(3600)),
+            clock: Some(Duration::from_secs(1)),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Create a config with the default TTLs: account 5s, account
+    /// configurations 1min, assets and calendar 1h, clock 1s.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the TTL for `resource`; `None` disables caching it.
+    #[must_use]
+    pub fn ttl(mut self, resource: CachedResource, ttl: Option<Duration>) -> Self {
+        *self.slot(resource) = ttl;
+        self
+    }
+
+    /// The TTL for `resource`.
+    #[must_use]
+    pub fn ttl_for(&self, resource: CachedResource) -> Option<Duration> {
+        match resource {
+            CachedResource::Account => self.account,
+            CachedResource::AccountConfigurations => self.account_configurations,
+            CachedResource::Assets => self.assets,
+            CachedResource::Calendar => self.calendar,
+            CachedResource::Clock => self.clock,
+        }
+    }
+
+    fn slot(&mut self, resource: CachedResource) -> &mut Option<Duration> {
+        match resource {
+            CachedResource::Account => &mut self.account,
+            CachedResource::AccountConfigurations => &mut self.account_configurations,
+            CachedResource::Assets => &mut self.assets,
+            CachedResource::Calendar => &mut self.calendar,
+            CachedResource::Clock => &mut self.clock,
+        }
+    }
+}
+
+struct CacheEntry {
+    expires: Instant,
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+/// TTL cache of decoded responses, keyed by resource and query string.
+pub struct ResponseCache {
+    config: CacheConfig,
+    entries: Mutex<HashMap<(CachedResource, String), CacheEntry>>,
+}
+
+impl std::fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("config", &self.config)
+            .field("entries", &self.len())
+            .finish()
+    }
+}
+
+impl ResponseCache {
+    /// Create an empty cache.
+    #[must_use]
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The configured TTLs.
+    #[must_use]
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// The unexpired value for `resource` and `key`, if any.
+    #[must_use]
+    pub fn get<T: Clone + 'static>(&self, resource: CachedResource, key: &str) -> Option<T> {
+        let mut entries = self.lock();
+        let entry_key = (resource, key.to_string());
+        match entries.get(&entry_key) {
+            Some(entry) if entry.expires > Instant::now() => entry.value.downcast_ref().cloned(),
+            Some(_) => {
+                entries.remove(&entry_key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Store `value` for `resource` and `key`, unless caching of `resource`
+    /// is disabled.
+    pub fn insert<T: Send + Sync + 'static>(&self, resource: CachedResource, key: &str, value: T) {
+        let Some(ttl) = self.config.ttl_for(resource) else {
+            return;
+        };
+        self.lock().insert(
+            (resource, key.to_string()),
+            CacheEntry {
+                expires: Instant::now() + ttl,
+                value: Arc::new(value),
+            },
+        );
+    }
+
+    /// Drop every cached response for `resource`.
+    pub fn invalidate(&self, resource: CachedResource) {
+        self.lock().retain(|(cached, _), _| *cached != resource);
+    }
+
+    /// Drop every cached response.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Number of stored responses, including expired ones not yet evicted.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether nothing is stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(CachedResource, String), CacheEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Cache key for a query: its URL-encoded form.
+pub(crate) fn cache_key<P: Serialize>(params: &P) -> Result<String> {
+    Ok(QueryParams::from_serialize(params)?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_cache_ttl_and_invalidation() {
+        let config = CacheConfig::new()
+            .ttl(CachedResource::Clock, Some(Duration::ZERO))
+            .ttl(CachedResource::Calendar, None);
+        let cache = ResponseCache::new(config);
+
+        cache.insert(CachedResource::Account, "", 42u32);
+        cache.insert(CachedResource::Assets, "status=active", vec!["AAPL"]);
+        cache.insert(CachedResource::Assets, "", vec!["AAPL", "MSFT"]);
+        cache.insert(CachedResource::Calendar, "", 1u8);
+        cache.insert(CachedResource::Clock, "", true);
+
+        assert_eq!(cache.get::<u32>(CachedResource::Account, ""), Some(42));
+        assert_eq!(cache.get::<u64>(CachedResource::Account, ""), None);
+        assert_eq!(
+            cache.get::<Vec<&str>>(CachedResource::Assets, "status=active"),
+            Some(vec!["AAPL"])
+        );
+        assert_eq!(cache.get::<u8>(CachedResource::Calendar, ""), None);
+        assert_eq!(cache.get::<bool>(CachedResource::Clock, ""), None);
+        assert_eq!(cache.len(), 3);
+
+        cache.invalidate(CachedResource::Assets);
+        assert_eq!(cache.get::<Vec<&str>>(CachedResource::Assets, ""), None);
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
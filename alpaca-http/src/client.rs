@@ -2,43 +2,289 @@
 //!
 //! This module provides the main HTTP client for interacting with the Alpaca REST API.
 
+use crate::cache::{CacheConfig, CachedResource, ResponseCache};
+use crate::query::QueryParams;
+use crate::rate_limit::{PriorityRateLimiter, default_priority};
 use alpaca_base::{
-    AlpacaError, ApiErrorCode, RateLimitInfo, Result, auth::Credentials, types::Environment,
+    AlpacaError, ApiErrorCode, ClientConfig, Instrumentation, RateLimitInfo, Result, RetryPolicy,
+    auth::{Credentials, CredentialsHandle},
+    types::{
+        Environment, EnvironmentGuard, LiveConfirmation, RateLimitConfig, RequestPriority,
+        TradingEnvironment,
+    },
     utils::UrlBuilder,
 };
-use reqwest::{Client, Method, RequestBuilder, Response};
+use reqwest::{Certificate, Client, Method, Proxy, RequestBuilder, Response};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
-use std::time::Duration;
-use tracing::{debug, error, warn};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{Instrument, debug, error, warn};
 
 /// HTTP client for Alpaca API
 #[derive(Debug, Clone)]
 pub struct AlpacaHttpClient {
     client: Client,
-    credentials: Credentials,
+    credentials: CredentialsHandle,
     environment: Environment,
     base_url: String,
     data_url: String,
+    rate_limiter: Option<Arc<PriorityRateLimiter>>,
+    priority: Option<RequestPriority>,
+    retry_policy: Option<RetryPolicy>,
+    cache: Option<Arc<ResponseCache>>,
+    instrumentation: Option<Arc<Instrumentation>>,
+    dry_run: bool,
+    environment_guard: Option<EnvironmentGuard>,
 }
 
 impl AlpacaHttpClient {
-    /// Create a new HTTP client
-    pub fn new(credentials: Credentials, environment: Environment) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent("alpaca-rs/0.1.0")
+    /// Create a new HTTP client.
+    ///
+    /// Pass a [`CredentialsHandle`] instead of plain [`Credentials`] to
+    /// rotate keys later without rebuilding the client.
+    pub fn new(
+        credentials: impl Into<CredentialsHandle>,
+        environment: Environment,
+    ) -> Result<Self> {
+        Self::with_config(credentials, ClientConfig::new(environment))
+    }
+
+    /// Create a new HTTP client with custom endpoints, proxy or TLS roots.
+    ///
+    /// Fails with [`AlpacaError::Config`] if the proxy URL or a root
+    /// certificate is invalid, or if a paper API key is used with the live
+    /// trading API or a live key with the paper API (see
+    /// [`EnvironmentGuard::check_credentials`]).
+    pub fn with_config(
+        credentials: impl Into<CredentialsHandle>,
+        config: ClientConfig,
+    ) -> Result<Self> {
+        let credentials = credentials.into();
+        EnvironmentGuard::check_credentials(&credentials.current().api_key, config.api_base_url())?;
+        let mut builder = Client::builder()
+            .timeout(config.timeout)
+            .user_agent("alpaca-rs/0.1.0");
+        if let Some(proxy) = &config.proxy {
+            let mut reqwest_proxy = Proxy::all(&proxy.url)
+                .map_err(|e| AlpacaError::Config(format!("invalid proxy URL: {e}")))?;
+            if let Some(username) = &proxy.username {
+                reqwest_proxy = reqwest_proxy
+                    .basic_auth(username, proxy.password.as_deref().unwrap_or_default());
+            }
+            builder = builder.proxy(reqwest_proxy);
+        }
+        for pem in &config.root_certificates {
+            let certificates = Certificate::from_pem_bundle(pem)
+                .map_err(|e| AlpacaError::Config(format!("invalid root certificate: {e}")))?;
+            if certificates.is_empty() {
+                return Err(AlpacaError::Config(
+                    "no certificate found in root certificate PEM".to_string(),
+                ));
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        let client = builder
             .build()
             .map_err(|e| AlpacaError::Http(e.to_string()))?;
 
         Ok(Self {
             client,
             credentials,
-            base_url: environment.base_url().to_string(),
-            data_url: environment.data_url().to_string(),
-            environment,
+            base_url: config.api_base_url().to_string(),
+            data_url: config.data_base_url().to_string(),
+            environment: config.environment,
+            rate_limiter: None,
+            priority: None,
+            retry_policy: None,
+            cache: None,
+            instrumentation: None,
+            dry_run: false,
+            environment_guard: None,
+        })
+    }
+
+    /// Enable client-side rate limiting.
+    ///
+    /// The limiter is shared by all clones of the returned client. Queued
+    /// requests are released strictly by priority: see
+    /// [`default_priority`] and [`Self::with_priority`].
+    #[must_use]
+    pub fn with_rate_limit(mut self, config: &RateLimitConfig) -> Self {
+        self.rate_limiter = Some(Arc::new(PriorityRateLimiter::new(config)));
+        self
+    }
+
+    /// Return a clone that sends every request at `priority`, sharing this
+    /// client's rate limiter.
+    ///
+    /// Use [`RequestPriority::Critical`] for kill-switch traffic so it
+    /// preempts anything queued behind the limiter.
+    #[must_use]
+    pub fn with_priority(&self, priority: RequestPriority) -> Self {
+        Self {
+            priority: Some(priority),
+            ..self.clone()
+        }
+    }
+
+    /// Retry failed idempotent requests (`GET`, `PUT` and `DELETE`) under
+    /// `policy`.
+    ///
+    /// `POST` and `PATCH` requests are never retried, since a request that
+    /// timed out may still have been applied. Each attempt waits for its own
+    /// rate limiter permit and reads the current credentials.
+    #[must_use]
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Cache account, asset list, calendar and clock responses for the
+    /// TTLs in `config`.
+    ///
+    /// The cache is shared by all clones of the returned client. The cached
+    /// account is dropped whenever the client submits, replaces or cancels
+    /// an order or closes a position; other responses are only dropped on
+    /// expiry or by [`Self::invalidate_cache`] and [`Self::clear_cache`].
+    #[must_use]
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(Arc::new(ResponseCache::new(config)));
+        self
+    }
+
+    /// Report every request and response to the hooks of
+    /// `instrumentation`, shared by all clones of the returned client.
+    ///
+    /// Each request gets a process-unique ID; its response event carries
+    /// the status, the latency to the response headers and the server's
+    /// request ID. URLs are redacted by the instrumentation's
+    /// [`RedactionPolicy`](alpaca_base::RedactionPolicy).
+    #[must_use]
+    pub fn with_instrumentation(mut self, instrumentation: Instrumentation) -> Self {
+        self.instrumentation = Some(Arc::new(instrumentation));
+        self
+    }
+
+    /// Preview orders instead of submitting them: with `dry_run` set,
+    /// [`Self::create_order`] returns the simulated order of
+    /// [`Self::dry_run_order`], or an error if it would not fit in the
+    /// buying power. Every other request is sent as usual.
+    #[must_use]
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Whether orders are only previewed. See [`Self::with_dry_run`].
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Check every [`Self::create_order`] against `guard`, which takes the
+    /// client's environment: orders are refused in live mode unless the
+    /// guard allows live trading, and live orders above its confirmation
+    /// threshold must go through [`Self::create_order_confirmed`]. Orders
+    /// that cannot be priced count as above the threshold.
+    #[must_use]
+    pub fn with_environment_guard(mut self, guard: EnvironmentGuard) -> Self {
+        self.environment_guard = Some(guard.with_environment(self.trading_environment()));
+        self
+    }
+
+    /// The environment guard, if one is installed.
+    pub fn environment_guard(&self) -> Option<&EnvironmentGuard> {
+        self.environment_guard.as_ref()
+    }
+
+    /// The environment orders go to: the one of the trading API host, or
+    /// the configured environment for other hosts.
+    pub fn trading_environment(&self) -> TradingEnvironment {
+        TradingEnvironment::from_base_url(&self.base_url).unwrap_or(match self.environment {
+            Environment::Paper => TradingEnvironment::Paper,
+            Environment::Live => TradingEnvironment::Live,
         })
     }
 
+    /// Acknowledge that the next order goes to the live market; pass the
+    /// token to [`Self::create_order_confirmed`].
+    pub fn confirm_live(&self) -> LiveConfirmation {
+        self.environment_guard
+            .clone()
+            .unwrap_or_else(|| EnvironmentGuard::allow_live(self.trading_environment()))
+            .confirm_live()
+    }
+
+    /// The shared response cache, if caching is enabled.
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_deref()
+    }
+
+    /// Drop cached responses for `resource`, if caching is enabled.
+    pub fn invalidate_cache(&self, resource: CachedResource) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(resource);
+        }
+    }
+
+    /// Drop every cached response, if caching is enabled.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Serve `resource` for `key` from the cache, or `fetch` it and cache
+    /// the result.
+    pub(crate) async fn cached<T, F, Fut>(
+        &self,
+        resource: CachedResource,
+        key: &str,
+        fetch: F,
+    ) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let Some(cache) = &self.cache else {
+            return fetch().await;
+        };
+        if let Some(hit) = cache.get(resource, key) {
+            debug!("Cache hit for {:?} {}", resource, key);
+            return Ok(hit);
+        }
+        let value = fetch().await?;
+        cache.insert(resource, key, value.clone());
+        Ok(value)
+    }
+
+    /// The credentials handle; rotating it affects every later request.
+    pub fn credentials(&self) -> &CredentialsHandle {
+        &self.credentials
+    }
+
+    /// The shared rate limiter, if rate limiting is enabled.
+    pub fn rate_limiter(&self) -> Option<&PriorityRateLimiter> {
+        self.rate_limiter.as_deref()
+    }
+
+    /// Wait for a rate limiter permit, if rate limiting is enabled.
+    async fn throttle(&self, method: &Method, path: &str) {
+        if let Some(limiter) = &self.rate_limiter {
+            let priority = self
+                .priority
+                .unwrap_or_else(|| default_priority(method, path));
+            #[cfg(feature = "metrics")]
+            let started = Instant::now();
+            limiter.acquire(priority).await;
+            #[cfg(feature = "metrics")]
+            alpaca_base::metrics::record_rate_limit_wait(priority, started.elapsed());
+        }
+    }
+
     /// Create a new client from environment variables
     pub fn from_env(environment: Environment) -> Result<Self> {
         let credentials = Credentials::from_env()?;
@@ -59,19 +305,9 @@ impl AlpacaHttpClient {
         T: DeserializeOwned,
         P: Serialize,
     {
-        // Serialize params to query string
-        let query_string = serde_urlencoded::to_string(params)
-            .map_err(|e| AlpacaError::Json(format!("Failed to serialize query params: {}", e)))?;
-
-        let url = if query_string.is_empty() {
-            self.build_url(path)?
-        } else {
-            format!("{}?{}", self.build_url(path)?, query_string)
-        };
-
-        let request = self.client.get(&url).headers(self.build_headers()?);
+        let url = QueryParams::from_serialize(params)?.append_to(&self.build_url(path)?);
 
-        self.execute_request(request).await
+        self.dispatch::<T, ()>(Method::GET, path, &url, None).await
     }
 
     /// Make a POST request
@@ -116,30 +352,143 @@ impl AlpacaHttpClient {
         B: Serialize,
     {
         let url = self.build_url(path)?;
+        self.dispatch(method, path, &url, body).await
+    }
+
+    /// Send a request, retrying it under the retry policy if it is
+    /// idempotent.
+    async fn dispatch<T, B>(
+        &self,
+        method: Method,
+        path: &str,
+        url: &str,
+        body: Option<&B>,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+        B: Serialize,
+    {
+        let idempotent = matches!(method, Method::GET | Method::PUT | Method::DELETE);
+        let changes_account = changes_account(&method, path);
+        let result = match &self.retry_policy {
+            Some(policy) if idempotent => {
+                policy
+                    .retry(|_| self.send(method.clone(), path, url, body))
+                    .await
+            }
+            _ => self.send(method, path, url, body).await,
+        };
+        // Even a failed order request may have reached the server.
+        if changes_account {
+            self.invalidate_cache(CachedResource::Account);
+        }
+        result
+    }
+
+    /// Send a request once.
+    async fn send<T, B>(&self, method: Method, path: &str, url: &str, body: Option<&B>) -> Result<T>
+    where
+        T: DeserializeOwned,
+        B: Serialize,
+    {
         let mut request = self
             .client
-            .request(method.clone(), &url)
+            .request(method.clone(), url)
             .headers(self.build_headers()?);
 
         if let Some(body) = body {
             request = request.json(body);
         }
 
+        self.throttle(&method, path).await;
         debug!("Making {} request to {}", method, url);
-        self.execute_request(request).await
+        let response = self.transmit(&method, path, url, request).await?;
+        self.handle_response(response).await
     }
 
-    /// Execute the request and handle the response
-    async fn execute_request<T>(&self, request: RequestBuilder) -> Result<T>
-    where
-        T: DeserializeOwned,
-    {
-        let response = request
-            .send()
+    /// Send the request, reporting it and its outcome to the
+    /// instrumentation hooks, if any.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    async fn transmit(
+        &self,
+        method: &Method,
+        path: &str,
+        url: &str,
+        request: RequestBuilder,
+    ) -> Result<Response> {
+        let Some(instrumentation) = &self.instrumentation else {
+            #[cfg(feature = "metrics")]
+            let started = Instant::now();
+            let sent = request.send().await;
+            #[cfg(feature = "metrics")]
+            alpaca_base::metrics::record_http_request(
+                method.as_str(),
+                path,
+                sent.as_ref().ok().map(|r| r.status().as_u16()),
+                started.elapsed(),
+            );
+            return sent.map_err(|e| AlpacaError::Network(e.to_string()));
+        };
+
+        let event = instrumentation.request(method.as_str(), url, &self.credentials.current());
+        let started = Instant::now();
+        let sent = if instrumentation.spans_enabled() {
+            let span = tracing::info_span!(
+                "alpaca.request",
+                request_id = event.id,
+                method = %method,
+                url = %event.url,
+            );
+            request.send().instrument(span).await
+        } else {
+            request.send().await
+        };
+        let latency = started.elapsed();
+        #[cfg(feature = "metrics")]
+        alpaca_base::metrics::record_http_request(
+            method.as_str(),
+            path,
+            sent.as_ref().ok().map(|r| r.status().as_u16()),
+            latency,
+        );
+        match &sent {
+            Ok(response) => instrumentation.response(
+                event,
+                Some(response.status().as_u16()),
+                latency,
+                server_request_id(response.headers()),
+                None,
+            ),
+            Err(e) => instrumentation.response(event, None, latency, None, Some(e.to_string())),
+        }
+        sent.map_err(|e| AlpacaError::Network(e.to_string()))
+    }
+
+    /// Make a GET request for a binary body, such as a PDF document.
+    ///
+    /// The whole body is buffered; see [`Self::get_response`] to read it in
+    /// chunks instead.
+    pub async fn get_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let response = self.get_response(path).await?;
+        let bytes = response
+            .bytes()
             .await
             .map_err(|e| AlpacaError::Network(e.to_string()))?;
+        Ok(bytes.to_vec())
+    }
 
-        self.handle_response(response).await
+    /// Make a GET request and return the successful response unread, so a
+    /// large body can be streamed with [`Response::chunk`].
+    pub async fn get_response(&self, path: &str) -> Result<Response> {
+        let url = self.build_url(path)?;
+        let request = self
+            .client
+            .request(Method::GET, &url)
+            .headers(self.build_headers()?);
+        self.throttle(&Method::GET, path).await;
+        debug!("Making GET request to {}", url);
+        let response = self.transmit(&Method::GET, path, &url, request).await?;
+        self.check_status(response).await
     }
 
     /// Handle the HTTP response with comprehensive error parsing.
@@ -147,17 +496,31 @@ impl AlpacaHttpClient {
     where
         T: DeserializeOwned,
     {
+        let response = self.check_status(response).await?;
+        let response_text = response
+            .text()
+            .await
+            .map_err(|e| AlpacaError::Network(e.to_string()))?;
+
+        // Parse successful response
+        serde_json::from_str(&response_text).map_err(|e| {
+            AlpacaError::Json(format!(
+                "Failed to parse response: {} - Response: {}",
+                e, response_text
+            ))
+        })
+    }
+
+    /// Pass a successful response through, or turn a failed one into the
+    /// matching error.
+    async fn check_status(&self, response: Response) -> Result<Response> {
         let status = response.status();
         let headers = response.headers().clone();
 
         debug!("Response status: {}", status);
 
         // Extract request ID from headers for debugging
-        let request_id = headers
-            .get("x-request-id")
-            .or_else(|| headers.get("apca-request-id"))
-            .and_then(|h| h.to_str().ok())
-            .map(String::from);
+        let request_id = server_request_id(&headers);
 
         // Parse rate limit headers
         let rate_limit_info = self.parse_rate_limit_headers(&headers);
@@ -179,13 +542,12 @@ impl AlpacaHttpClient {
             return Err(AlpacaError::rate_limit_with_info(info));
         }
 
-        // Get response text for error handling
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| AlpacaError::Network(e.to_string()))?;
-
         if !status.is_success() {
+            // Get response text for error handling
+            let response_text = response
+                .text()
+                .await
+                .map_err(|e| AlpacaError::Network(e.to_string()))?;
             error!("API error response: {}", response_text);
 
             // Try to parse structured error response
@@ -197,12 +559,12 @@ impl AlpacaHttpClient {
                     None
                 };
 
-                return Err(AlpacaError::Api {
-                    status: status.as_u16(),
-                    message: error_response.message,
+                return Err(AlpacaError::api_response(
+                    status.as_u16(),
                     error_code,
+                    error_response.message,
                     request_id,
-                });
+                ));
             }
 
             // Try to parse simple error response
@@ -214,29 +576,23 @@ impl AlpacaHttpClient {
                     .unwrap_or(&response_text)
                     .to_string();
 
-                return Err(AlpacaError::Api {
-                    status: status.as_u16(),
+                return Err(AlpacaError::api_response(
+                    status.as_u16(),
+                    None,
                     message,
-                    error_code: None,
                     request_id,
-                });
+                ));
             }
 
-            return Err(AlpacaError::Api {
-                status: status.as_u16(),
-                message: response_text,
-                error_code: None,
+            return Err(AlpacaError::api_response(
+                status.as_u16(),
+                None,
+                response_text,
                 request_id,
-            });
+            ));
         }
 
-        // Parse successful response
-        serde_json::from_str(&response_text).map_err(|e| {
-            AlpacaError::Json(format!(
-                "Failed to parse response: {} - Response: {}",
-                e, response_text
-            ))
-        })
+        Ok(response)
     }
 
     /// Parse rate limit information from response headers.
@@ -271,9 +627,16 @@ impl AlpacaHttpClient {
     }
 
     /// Build the full URL for a request
-    fn build_url(&self, path: &str) -> Result<String> {
+    pub(crate) fn build_url(&self, path: &str) -> Result<String> {
         // Use data URL for market data endpoints
-        let base_url = if path.starts_with("/v2/stocks") || path.starts_with("/v1beta1/crypto") {
+        let base_url = if path.starts_with("/v2/stocks")
+            || path.starts_with("/v1beta1/crypto")
+            || path.starts_with("/v1beta3/crypto")
+            || path.starts_with("/v1beta1/news")
+            || path.starts_with("/v1beta1/options")
+            || path.starts_with("/v1beta1/forex")
+            || path == "/v1beta1/corporate-actions"
+        {
             &self.data_url
         } else {
             &self.base_url
@@ -287,10 +650,11 @@ impl AlpacaHttpClient {
     /// Build authentication headers
     fn build_headers(&self) -> Result<reqwest::header::HeaderMap> {
         let mut headers = reqwest::header::HeaderMap::new();
+        let credentials = self.credentials.current();
 
         headers.insert(
             "APCA-API-KEY-ID",
-            self.credentials
+            credentials
                 .api_key
                 .parse()
                 .map_err(|_| AlpacaError::Auth("Invalid API key format".to_string()))?,
@@ -298,7 +662,7 @@ impl AlpacaHttpClient {
 
         headers.insert(
             "APCA-API-SECRET-KEY",
-            self.credentials
+            credentials
                 .secret_key
                 .parse()
                 .map_err(|_| AlpacaError::Auth("Invalid secret key format".to_string()))?,
@@ -325,6 +689,15 @@ impl AlpacaHttpClient {
     }
 }
 
+/// The server's request ID, from `X-Request-ID` or `Apca-Request-Id`.
+fn server_request_id(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get("x-request-id")
+        .or_else(|| headers.get("apca-request-id"))
+        .and_then(|h| h.to_str().ok())
+        .map(String::from)
+}
+
 /// Internal struct for parsing API error responses.
 #[derive(Debug, Deserialize)]
 struct ApiErrorResponseBody {
@@ -336,6 +709,12 @@ struct ApiErrorResponseBody {
     message: String,
 }
 
+/// Whether a request may change buying power, cash or equity: any
+/// order or position mutation.
+fn changes_account(method: &Method, path: &str) -> bool {
+    *method != Method::GET && (path.starts_with("/v2/orders") || path.starts_with("/v2/positions"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -351,6 +730,111 @@ mod tests {
 
         let data_url = client.build_url("/v2/stocks/AAPL/bars").unwrap();
         assert_eq!(data_url, "https://data.alpaca.markets/v2/stocks/AAPL/bars");
+
+        let news_url = client.build_url("/v1beta1/news").unwrap();
+        assert_eq!(news_url, "https://data.alpaca.markets/v1beta1/news");
+
+        let actions_url = client.build_url("/v1beta1/corporate-actions").unwrap();
+        assert_eq!(
+            actions_url,
+            "https://data.alpaca.markets/v1beta1/corporate-actions"
+        );
+
+        let book_url = client
+            .build_url("/v1beta3/crypto/us/latest/orderbooks")
+            .unwrap();
+        assert_eq!(
+            book_url,
+            "https://data.alpaca.markets/v1beta3/crypto/us/latest/orderbooks"
+        );
+
+        let option_trades_url = client.build_url("/v1beta1/options/trades").unwrap();
+        assert_eq!(
+            option_trades_url,
+            "https://data.alpaca.markets/v1beta1/options/trades"
+        );
+
+        let rates_url = client.build_url("/v1beta1/forex/latest/rates").unwrap();
+        assert_eq!(
+            rates_url,
+            "https://data.alpaca.markets/v1beta1/forex/latest/rates"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_order_requests_invalidate_cached_account() {
+        let credentials = Credentials::new("test_key".to_string(), "test_secret".to_string());
+        // Nothing listens on the discard port, so requests fail fast.
+        let config = ClientConfig::new(Environment::Paper).base_url("http://127.0.0.1:9");
+        let client = AlpacaHttpClient::with_config(credentials, config)
+            .unwrap()
+            .with_cache(CacheConfig::default());
+        let cache = client.cache().unwrap();
+
+        cache.insert(CachedResource::Account, "", 1u8);
+        assert!(client.get_order(&uuid::Uuid::new_v4()).await.is_err());
+        assert_eq!(cache.get::<u8>(CachedResource::Account, ""), Some(1));
+        assert!(client.cancel_order(&uuid::Uuid::new_v4()).await.is_err());
+        assert_eq!(cache.get::<u8>(CachedResource::Account, ""), None);
+
+        assert!(changes_account(&Method::POST, "/v2/orders"));
+        assert!(changes_account(&Method::DELETE, "/v2/positions/AAPL"));
+        assert!(!changes_account(&Method::PATCH, "/v2/watchlists/1"));
+    }
+
+    #[test]
+    fn test_with_config_overrides_urls() {
+        let credentials = Credentials::new("test_key".to_string(), "test_secret".to_string());
+        let config = ClientConfig::broker_sandbox()
+            .proxy(alpaca_base::ProxyConfig::new("http://localhost:3128").basic_auth("u", "p"));
+        let client = AlpacaHttpClient::with_config(credentials.clone(), config).unwrap();
+        assert_eq!(
+            client.build_url("/v1/accounts").unwrap(),
+            "https://broker-api.sandbox.alpaca.markets/v1/accounts"
+        );
+        assert_eq!(client.data_url(), alpaca_base::BROKER_SANDBOX_DATA_URL);
+
+        let invalid = ClientConfig::new(Environment::Paper).add_root_certificate("not a pem");
+        assert!(matches!(
+            AlpacaHttpClient::with_config(credentials, invalid),
+            Err(AlpacaError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_with_priority_shares_rate_limiter() {
+        let credentials = Credentials::new("test_key".to_string(), "test_secret".to_string());
+        let client = AlpacaHttpClient::new(credentials, Environment::Paper)
+            .unwrap()
+            .with_rate_limit(&RateLimitConfig::new());
+        let critical = client.with_priority(RequestPriority::Critical);
+
+        assert_eq!(critical.priority, Some(RequestPriority::Critical));
+        assert!(std::ptr::eq(
+            client.rate_limiter().unwrap(),
+            critical.rate_limiter().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_rotated_credentials_used_for_headers() {
+        let handle = CredentialsHandle::new(Credentials::new(
+            "old_key".to_string(),
+            "old_secret".to_string(),
+        ));
+        let client = AlpacaHttpClient::new(handle.clone(), Environment::Paper).unwrap();
+        assert_eq!(
+            client.build_headers().unwrap()["APCA-API-KEY-ID"],
+            "old_key"
+        );
+
+        handle.rotate(Credentials::new(
+            "new_key".to_string(),
+            "new_secret".to_string(),
+        ));
+        let headers = client.build_headers().unwrap();
+        assert_eq!(headers["APCA-API-KEY-ID"], "new_key");
+        assert_eq!(headers["APCA-API-SECRET-KEY"], "new_secret");
     }
 
     #[test]
@@ -362,4 +846,40 @@ mod tests {
         assert_eq!(Environment::Live.base_url(), "https://api.alpaca.markets");
         assert_eq!(Environment::Paper.data_url(), "https://data.alpaca.markets");
     }
+
+    #[tokio::test]
+    async fn test_environment_interlocks() {
+        let paper_key = Credentials::new("PKTEST".to_string(), "secret".to_string());
+        let live_key = Credentials::new("AKTEST".to_string(), "secret".to_string());
+        assert!(matches!(
+            AlpacaHttpClient::new(paper_key.clone(), Environment::Live),
+            Err(AlpacaError::Config(_))
+        ));
+        assert!(AlpacaHttpClient::new(live_key.clone(), Environment::Paper).is_err());
+        assert!(AlpacaHttpClient::new(paper_key, Environment::Paper).is_ok());
+
+        let guard = EnvironmentGuard::allow_live(TradingEnvironment::Paper)
+            .require_confirmation_above(1_000.0);
+        let client = AlpacaHttpClient::new(live_key, Environment::Live)
+            .unwrap()
+            .with_environment_guard(guard);
+        assert!(client.trading_environment().is_live());
+        // Priced from the limit, so refused before anything is sent.
+        let order = crate::endpoints::CreateOrderRequest::limit(
+            "AAPL",
+            alpaca_base::types::OrderSide::Buy,
+            "100",
+            "50",
+        );
+        assert!(matches!(
+            client.create_order(&order).await,
+            Err(AlpacaError::Validation(message)) if message.contains("confirm_live")
+        ));
+
+        let blocked = client.with_environment_guard(EnvironmentGuard::paper_only());
+        assert!(matches!(
+            blocked.create_order(&order).await,
+            Err(AlpacaError::Validation(_))
+        ));
+    }
 }
@@ -0,0 +1,305 @@
+//! Upcoming dividends of held positions and projected income.
+//!
+//! [`DividendCalendar`] matches the account's positions against cash
+//! dividends from `/v1beta1/corporate-actions` and keeps a typed
+//! [`DividendSchedule`]: one [`DividendEvent`] per position and dividend,
+//! with its ex, record and payable dates and the expected amount, plus the
+//! projected income per month. Like
+//! [`AssetUniverse`](crate::AssetUniverse) it is `Send + Sync` and
+//! [`DividendCalendar::run_refresh`] rebuilds it on a fixed interval,
+//! swapping in complete schedules.
+//!
+//! Amounts assume the current quantity is still held on the ex-date; short
+//! positions owe the dividend and project a negative amount.
+
+use crate::client::AlpacaHttpClient;
+use alpaca_base::{
+    CancellationToken, Result,
+    types::{CashDividend, CorporateActionsParams, Position},
+    utils::{parse_decimal, us_eastern_offset},
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// Default number of days ahead the calendar covers.
+pub const DEFAULT_HORIZON_DAYS: i64 = 90;
+
+/// A dividend expected on a held position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DividendEvent {
+    /// Symbol.
+    pub symbol: String,
+    /// Quantity held (negative when short).
+    pub qty: f64,
+    /// Cash amount per share.
+    pub rate: f64,
+    /// Expected amount: quantity times rate.
+    pub amount: f64,
+    /// Whether the dividend is special.
+    pub special: bool,
+    /// Ex-date.
+    pub ex_date: Option<NaiveDate>,
+    /// Record date.
+    pub record_date: Option<NaiveDate>,
+    /// Payable date.
+    pub payable_date: Option<NaiveDate>,
+}
+
+impl DividendEvent {
+    /// The date the amount is expected: the payable date, else the
+    /// ex-date.
+    #[must_use]
+    pub fn income_date(&self) -> Option<NaiveDate> {
+        self.payable_date.or(self.ex_date)
+    }
+}
+
+/// Projected dividend income of one calendar month.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonthlyIncome {
+    /// Year.
+    pub year: i32,
+    /// Month (1-12).
+    pub month: u32,
+    /// Projected amount.
+    pub amount: f64,
+}
+
+/// Dividends expected on held positions from a given day on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DividendSchedule {
+    /// When the schedule was built.
+    pub generated_at: DateTime<Utc>,
+    /// First day covered.
+    pub from: NaiveDate,
+    /// Events ordered by ex-date, then symbol.
+    pub events: Vec<DividendEvent>,
+}
+
+impl DividendSchedule {
+    /// Match `positions` against `dividends`, keeping dividends not yet
+    /// paid on `from`: an upcoming ex-date, or an ex-date passed with the
+    /// payable date still ahead.
+    #[must_use]
+    pub fn new(
+        positions: &[Position],
+        dividends: &[CashDividend],
+        from: NaiveDate,
+        generated_at: DateTime<Utc>,
+    ) -> Self {
+        let held: HashMap<&str, f64> = positions
+            .iter()
+            .filter_map(|p| Some((p.symbol.as_str(), parse_decimal(&p.qty).ok()?)))
+            .collect();
+        let mut events: Vec<DividendEvent> = dividends
+            .iter()
+            .filter(|d| {
+                d.payable_date
+                    .or(d.ex_date)
+                    .is_some_and(|date| date >= from)
+            })
+            .filter_map(|d| {
+                let qty = *held.get(d.symbol.as_str())?;
+                Some(DividendEvent {
+                    symbol: d.symbol.clone(),
+                    qty,
+                    rate: d.rate,
+                    amount: qty * d.rate,
+                    special: d.special,
+                    ex_date: d.ex_date,
+                    record_date: d.record_date,
+                    payable_date: d.payable_date,
+                })
+            })
+            .collect();
+        events.sort_by(|a, b| a.ex_date.cmp(&b.ex_date).then(a.symbol.cmp(&b.symbol)));
+        Self {
+            generated_at,
+            from,
+            events,
+        }
+    }
+
+    /// Events whose ex-date is still ahead.
+    pub fn upcoming_ex_dates(&self) -> impl Iterator<Item = &DividendEvent> {
+        self.events
+            .iter()
+            .filter(|e| e.ex_date.is_some_and(|date| date >= self.from))
+    }
+
+    /// Events of `symbol`.
+    pub fn for_symbol<'a>(&'a self, symbol: &'a str) -> impl Iterator<Item = &'a DividendEvent> {
+        self.events.iter().filter(move |e| e.symbol == symbol)
+    }
+
+    /// Projected income per month of the income date, in month order.
+    #[must_use]
+    pub fn monthly_income(&self) -> Vec<MonthlyIncome> {
+        let mut months: BTreeMap<(i32, u32), f64> = BTreeMap::new();
+        for event in &self.events {
+            if let Some(date) = event.income_date() {
+                *months.entry((date.year(), date.month())).or_default() += event.amount;
+            }
+        }
+        months
+            .into_iter()
+            .map(|((year, month), amount)| MonthlyIncome {
+                year,
+                month,
+                amount,
+            })
+            .collect()
+    }
+
+    /// Total projected income.
+    #[must_use]
+    pub fn total_income(&self) -> f64 {
+        self.events.iter().map(|e| e.amount).sum()
+    }
+}
+
+/// Shared, periodically refreshed dividend schedule of held positions.
+#[derive(Debug)]
+pub struct DividendCalendar {
+    client: AlpacaHttpClient,
+    horizon: Duration,
+    schedule: RwLock<Arc<DividendSchedule>>,
+}
+
+impl DividendCalendar {
+    /// Build the schedule for the next [`DEFAULT_HORIZON_DAYS`] days.
+    pub async fn load(client: AlpacaHttpClient) -> Result<Self> {
+        Self::load_with_horizon(client, DEFAULT_HORIZON_DAYS).await
+    }
+
+    /// Build the schedule for the next `days` days.
+    pub async fn load_with_horizon(client: AlpacaHttpClient, days: i64) -> Result<Self> {
+        let horizon = Duration::days(days);
+        let schedule = fetch_schedule(&client, horizon).await?;
+        Ok(Self {
+            client,
+            horizon,
+            schedule: RwLock::new(Arc::new(schedule)),
+        })
+    }
+
+    /// Refetch positions and dividends and swap in the new schedule. On
+    /// error the previous schedule is kept.
+    pub async fn refresh(&self) -> Result<()> {
+        let schedule = fetch_schedule(&self.client, self.horizon).await?;
+        *self.schedule.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(schedule);
+        Ok(())
+    }
+
+    /// Refresh every `interval` until `cancel` is cancelled.
+    ///
+    /// Failed refreshes are logged and the previous schedule is kept until
+    /// the next attempt.
+    pub async fn run_refresh(&self, interval: std::time::Duration, cancel: &CancellationToken) {
+        loop {
+            tokio::select! {
+                () = cancel.cancelled() => return,
+                () = tokio::time::sleep(interval) => {}
+            }
+            if let Err(e) = self.refresh().await {
+                warn!("Dividend calendar refresh failed: {}", e);
+            }
+        }
+    }
+
+    /// The current schedule. It stays valid, unchanged, across refreshes.
+    #[must_use]
+    pub fn snapshot(&self) -> Arc<DividendSchedule> {
+        Arc::clone(&self.schedule.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Projected income per month.
+    #[must_use]
+    pub fn monthly_income(&self) -> Vec<MonthlyIncome> {
+        self.snapshot().monthly_income()
+    }
+}
+
+async fn fetch_schedule(client: &AlpacaHttpClient, horizon: Duration) -> Result<DividendSchedule> {
+    let now = Utc::now();
+    let today = now
+        .with_timezone(&us_eastern_offset(now.date_naive()))
+        .date_naive();
+    let positions = client.get_positions().await?;
+    if positions.is_empty() {
+        return Ok(DividendSchedule::new(&[], &[], today, now));
+    }
+    let symbols: Vec<&str> = positions.iter().map(|p| p.symbol.as_str()).collect();
+    // Dividends whose ex-date has passed are paid later; look back for
+    // those still pending.
+    let params = CorporateActionsParams::new()
+        .symbols(&symbols.join(","))
+        .types("cash_dividend")
+        .date_range(
+            &(today - horizon).to_string(),
+            &(today + horizon).to_string(),
+        );
+    let actions = client.get_all_corporate_actions_v1(&params).await?;
+    Ok(DividendSchedule::new(
+        &positions,
+        &actions.cash_dividends,
+        today,
+        now,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_position;
+
+    fn dividend(symbol: &str, rate: f64, ex: &str, payable: &str) -> CashDividend {
+        CashDividend {
+            id: format!("{symbol}-{ex}"),
+            symbol: symbol.to_string(),
+            cusip: None,
+            rate,
+            special: false,
+            foreign: false,
+            process_date: payable.parse().unwrap(),
+            ex_date: Some(ex.parse().unwrap()),
+            record_date: None,
+            payable_date: Some(payable.parse().unwrap()),
+            due_bill_on_date: None,
+            due_bill_off_date: None,
+        }
+    }
+
+    #[test]
+    fn test_dividend_schedule_projects_monthly_income() {
+        let positions = [
+            sample_position("AAPL", "100", "150"),
+            sample_position("KO", "-10", "60"),
+        ];
+        let dividends = [
+            dividend("KO", 0.5, "2024-06-14", "2024-07-01"),
+            dividend("AAPL", 0.25, "2024-05-10", "2024-05-16"), // already paid
+            dividend("AAPL", 0.25, "2024-05-31", "2024-06-13"), // ex passed, pending
+            dividend("AAPL", 0.5, "2024-08-12", "2024-08-15"),
+            dividend("MSFT", 0.75, "2024-08-15", "2024-09-12"), // not held
+        ];
+        let from = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+        let schedule = DividendSchedule::new(&positions, &dividends, from, Utc::now());
+
+        let symbols: Vec<&str> = schedule.events.iter().map(|e| e.symbol.as_str()).collect();
+        assert_eq!(symbols, ["AAPL", "KO", "AAPL"]);
+        assert_eq!(schedule.upcoming_ex_dates().count(), 2);
+        assert_eq!(schedule.for_symbol("KO").next().unwrap().amount, -5.0);
+
+        let months: Vec<(u32, f64)> = schedule
+            .monthly_income()
+            .iter()
+            .map(|m| (m.month, m.amount))
+            .collect();
+        assert_eq!(months, [(6, 25.0), (7, -5.0), (8, 50.0)]);
+        assert_eq!(schedule.total_income(), 70.0);
+    }
+}
@@ -0,0 +1,582 @@
+//! Bulk historical market data download.
+//!
+//! [`HistoricalDownloader`] pages through the multi-symbol stock bars,
+//! trades or quotes endpoints and writes one file per symbol as CSV, JSON
+//! Lines or, with the `parquet` feature, Parquet. Each page is fetched under
+//! a [`RetryPolicy`] that honours `Retry-After` on HTTP 429, so a long
+//! download backs off instead of failing; pair it with
+//! [`AlpacaHttpClient::with_rate_limit`] to stay under the limit in the
+//! first place.
+//!
+//! After every page the downloader saves a checkpoint next to the output
+//! with the next page token and the length of each file. Running the same
+//! download into the same directory again resumes from the checkpoint,
+//! truncating any partially written page, so an interrupted download
+//! neither loses nor duplicates rows.
+
+use crate::client::AlpacaHttpClient;
+use alpaca_base::{
+    AlpacaError, Result, RetryPolicy,
+    types::{Bar, DataFeed, MultiBarsParams, MultiQuotesParams, MultiTradesParams, Quote, Trade},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Maximum rows per page the data API accepts.
+pub const MAX_PAGE_LIMIT: u32 = 10_000;
+
+/// Data to download.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum DownloadKind {
+    /// Bars of a timeframe, e.g. `"1Min"` or `"1Day"`.
+    Bars {
+        /// Timeframe.
+        timeframe: String,
+    },
+    /// Trades.
+    Trades,
+    /// Quotes.
+    Quotes,
+}
+
+impl DownloadKind {
+    /// Bars of `timeframe`.
+    #[must_use]
+    pub fn bars(timeframe: &str) -> Self {
+        Self::Bars {
+            timeframe: timeframe.to_string(),
+        }
+    }
+
+    /// Name used in output file names.
+    #[must_use]
+    pub fn name(&self) -> String {
+        match self {
+            Self::Bars { timeframe } => format!("bars_{timeframe}"),
+            Self::Trades => "trades".to_string(),
+            Self::Quotes => "quotes".to_string(),
+        }
+    }
+}
+
+/// Output file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// Comma-separated values with a header row.
+    #[default]
+    Csv,
+    /// One JSON object per line, in the API's field names.
+    JsonLines,
+    /// Apache Parquet, one part file per page (requires the `parquet`
+    /// feature).
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl OutputFormat {
+    /// File extension.
+    #[must_use]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::JsonLines => "jsonl",
+            #[cfg(feature = "parquet")]
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Progress saved after every page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadCheckpoint {
+    /// Description of the download the checkpoint belongs to.
+    pub request: String,
+    /// Token of the next page to fetch.
+    pub page_token: Option<String>,
+    /// Pages written.
+    pub pages: u64,
+    /// Rows written.
+    pub rows: u64,
+    /// Whether the last page was written.
+    pub complete: bool,
+    /// Length in bytes of each output file after the last page.
+    pub file_lengths: BTreeMap<String, u64>,
+}
+
+/// Outcome of [`HistoricalDownloader::download`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadReport {
+    /// Pages written, including those of earlier runs.
+    pub pages: u64,
+    /// Rows written, including those of earlier runs.
+    pub rows: u64,
+    /// Whether the download continued from a checkpoint.
+    pub resumed: bool,
+    /// Output files written by this run.
+    pub files: Vec<PathBuf>,
+}
+
+/// Pages historical stock data into files, one per symbol.
+#[derive(Debug, Clone)]
+pub struct HistoricalDownloader {
+    client: AlpacaHttpClient,
+    symbols: Vec<String>,
+    kind: DownloadKind,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    feed: Option<DataFeed>,
+    page_limit: u32,
+    format: OutputFormat,
+    retry_policy: RetryPolicy,
+}
+
+impl HistoricalDownloader {
+    /// Create a downloader for `symbols` between `start` and `end`, writing
+    /// CSV in pages of 10,000 rows.
+    pub fn new(
+        client: AlpacaHttpClient,
+        symbols: &[&str],
+        kind: DownloadKind,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            client,
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            kind,
+            start,
+            end,
+            feed: None,
+            page_limit: MAX_PAGE_LIMIT,
+            format: OutputFormat::default(),
+            retry_policy: RetryPolicy::new().max_attempts(5),
+        }
+    }
+
+    /// Set the data feed.
+    #[must_use]
+    pub fn feed(mut self, feed: DataFeed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+
+    /// Set the rows per page, capped at [`MAX_PAGE_LIMIT`].
+    #[must_use]
+    pub fn page_limit(mut self, limit: u32) -> Self {
+        self.page_limit = limit.clamp(1, MAX_PAGE_LIMIT);
+        self
+    }
+
+    /// Set the output format.
+    #[must_use]
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the retry policy for each page request.
+    #[must_use]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Path of the checkpoint file in `dir`.
+    #[must_use]
+    pub fn checkpoint_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.checkpoint.json", self.kind.name()))
+    }
+
+    /// Download every page into `dir`, resuming from a checkpoint there.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if `dir` holds the checkpoint of
+    /// a different download, [`AlpacaError::Config`] on file errors, or the
+    /// last API error once the retry policy gives up. Rows written before
+    /// the error are kept and the next run picks up after them.
+    pub async fn download(&self, dir: impl AsRef<Path>) -> Result<DownloadReport> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(io_error)?;
+        let request = self.describe();
+        let checkpoint_path = self.checkpoint_path(dir);
+        let (mut checkpoint, resumed) = match read_checkpoint(&checkpoint_path)? {
+            Some(checkpoint) if checkpoint.request != request => {
+                return Err(AlpacaError::Validation(format!(
+                    "{} belongs to a different download ({}); remove it to start over",
+                    checkpoint_path.display(),
+                    checkpoint.request
+                )));
+            }
+            Some(checkpoint) => (checkpoint, true),
+            None => (
+                DownloadCheckpoint {
+                    request,
+                    ..Default::default()
+                },
+                false,
+            ),
+        };
+        // Without a checkpoint this empties leftovers of an earlier run.
+        self.truncate_to_checkpoint(dir, &checkpoint)?;
+
+        let mut files = Vec::new();
+        while !checkpoint.complete {
+            let token = checkpoint.page_token.clone();
+            let page = self
+                .retry_policy
+                .retry(|_| self.fetch_page(token.clone()))
+                .await?;
+            let ((rows, written), next) = match page {
+                Page::Bars(data, next) => (self.write_page(dir, &mut checkpoint, &data)?, next),
+                Page::Trades(data, next) => (self.write_page(dir, &mut checkpoint, &data)?, next),
+                Page::Quotes(data, next) => (self.write_page(dir, &mut checkpoint, &data)?, next),
+            };
+            files.extend(written);
+            checkpoint.rows += rows;
+            checkpoint.pages += 1;
+            checkpoint.complete = next.is_none();
+            checkpoint.page_token = next;
+            write_checkpoint(&checkpoint_path, &checkpoint)?;
+            tracing::debug!(
+                "downloaded page {} ({} rows total)",
+                checkpoint.pages,
+                checkpoint.rows
+            );
+        }
+        files.sort();
+        files.dedup();
+
+        Ok(DownloadReport {
+            pages: checkpoint.pages,
+            rows: checkpoint.rows,
+            resumed,
+            files,
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} {} {}..{} feed={} format={}",
+            self.kind.name(),
+            self.symbols.join(","),
+            self.start.to_rfc3339(),
+            self.end.to_rfc3339(),
+            self.feed.map_or("default", |f| f.as_str()),
+            self.format.extension(),
+        )
+    }
+
+    async fn fetch_page(&self, page_token: Option<String>) -> Result<Page> {
+        let symbols = self.symbols.join(",");
+        let start = Some(self.start.to_rfc3339());
+        let end = Some(self.end.to_rfc3339());
+        let limit = Some(self.page_limit);
+        Ok(match &self.kind {
+            DownloadKind::Bars { timeframe } => {
+                let params = MultiBarsParams {
+                    symbols: Some(symbols),
+                    timeframe: Some(timeframe.clone()),
+                    start,
+                    end,
+                    limit,
+                    feed: self.feed,
+                    page_token,
+                };
+                let response = self.client.get_stock_bars(&params).await?;
+                Page::Bars(response.bars, response.next_page_token)
+            }
+            DownloadKind::Trades => {
+                let params = MultiTradesParams {
+                    symbols: Some(symbols),
+                    start,
+                    end,
+                    limit,
+                    feed: self.feed,
+                    page_token,
+                };
+                let response = self.client.get_stock_trades(&params).await?;
+                Page::Trades(response.trades, response.next_page_token)
+            }
+            DownloadKind::Quotes => {
+                let params = MultiQuotesParams {
+                    symbols: Some(symbols),
+                    start,
+                    end,
+                    limit,
+                    feed: self.feed,
+                    page_token,
+                };
+                let response = self.client.get_stock_quotes(&params).await?;
+                Page::Quotes(response.quotes, response.next_page_token)
+            }
+        })
+    }
+
+    fn file_name(&self, symbol: &str, part: Option<u64>) -> String {
+        let symbol = symbol.replace(['/', '\\'], "-");
+        match part {
+            Some(part) => format!(
+                "{symbol}_{}.part-{part:05}.{}",
+                self.kind.name(),
+                self.format.extension()
+            ),
+            None => format!("{symbol}_{}.{}", self.kind.name(), self.format.extension()),
+        }
+    }
+
+    /// Cut appendable files back to their checkpointed length, dropping a
+    /// page that was written but not checkpointed.
+    fn truncate_to_checkpoint(&self, dir: &Path, checkpoint: &DownloadCheckpoint) -> Result<()> {
+        for symbol in &self.symbols {
+            let name = self.file_name(symbol, None);
+            let path = dir.join(&name);
+            if !path.exists() {
+                continue;
+            }
+            let length = checkpoint.file_lengths.get(&name).copied().unwrap_or(0);
+            OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_len(length))
+                .map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    /// Write one page; returns the rows and files written.
+    fn write_page<T: Record>(
+        &self,
+        dir: &Path,
+        checkpoint: &mut DownloadCheckpoint,
+        data: &HashMap<String, Vec<T>>,
+    ) -> Result<(u64, Vec<PathBuf>)> {
+        let mut rows = 0;
+        let mut files = Vec::new();
+        for (symbol, records) in data {
+            if records.is_empty() {
+                continue;
+            }
+            let path = match self.format {
+                OutputFormat::Csv | OutputFormat::JsonLines => {
+                    let name = self.file_name(symbol, None);
+                    let path = dir.join(&name);
+                    let length = append_rows(&path, self.format, records)?;
+                    checkpoint.file_lengths.insert(name, length);
+                    path
+                }
+                #[cfg(feature = "parquet")]
+                OutputFormat::Parquet => {
+                    let path = dir.join(self.file_name(symbol, Some(checkpoint.pages + 1)));
+                    write_parquet(&path, records)?;
+                    path
+                }
+            };
+            rows += records.len() as u64;
+            files.push(path);
+        }
+        Ok((rows, files))
+    }
+}
+
+enum Page {
+    Bars(HashMap<String, Vec<Bar>>, Option<String>),
+    Trades(HashMap<String, Vec<Trade>>, Option<String>),
+    Quotes(HashMap<String, Vec<Quote>>, Option<String>),
+}
+
+/// A row that can be written in every output format.
+#[cfg(not(feature = "parquet"))]
+trait Record: Serialize {
+    const CSV_HEADER: &'static str;
+
+    fn csv_row(&self) -> String;
+}
+
+/// A row that can be written in every output format.
+#[cfg(feature = "parquet")]
+trait Record: Serialize + alpaca_base::arrow::ArrowRecord {
+    const CSV_HEADER: &'static str;
+
+    fn csv_row(&self) -> String;
+}
+
+impl Record for Bar {
+    const CSV_HEADER: &'static str = "timestamp,open,high,low,close,volume,trade_count,vwap";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.timestamp.to_rfc3339(),
+            self.open,
+            self.high,
+            self.low,
+            self.close,
+            self.volume,
+            self.trade_count.map(|n| n.to_string()).unwrap_or_default(),
+            self.vwap.map(|v| v.to_string()).unwrap_or_default(),
+        )
+    }
+}
+
+impl Record for Trade {
+    const CSV_HEADER: &'static str = "timestamp,price,size,exchange,conditions,id";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{}",
+            self.timestamp.to_rfc3339(),
+            self.price,
+            self.size,
+            csv_field(&self.exchange),
+            csv_field(&self.conditions.join(" ")),
+            self.id,
+        )
+    }
+}
+
+impl Record for Quote {
+    const CSV_HEADER: &'static str =
+        "timestamp,bid_price,bid_size,bid_exchange,ask_price,ask_size,ask_exchange,tape";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.timestamp.to_rfc3339(),
+            self.bid_price,
+            self.bid_size,
+            csv_field(&self.bid_exchange),
+            self.ask_price,
+            self.ask_size,
+            csv_field(&self.ask_exchange),
+            csv_field(&self.timeframe),
+        )
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Append `records` to `path`, writing a CSV header into a new file;
+/// returns the file length afterwards.
+fn append_rows<T: Record>(path: &Path, format: OutputFormat, records: &[T]) -> Result<u64> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(io_error)?;
+    let is_new = file.metadata().map_err(io_error)?.len() == 0;
+    let mut out = BufWriter::new(file);
+    if format == OutputFormat::Csv && is_new {
+        writeln!(out, "{}", T::CSV_HEADER).map_err(io_error)?;
+    }
+    for record in records {
+        match format {
+            OutputFormat::Csv => writeln!(out, "{}", record.csv_row()).map_err(io_error)?,
+            _ => {
+                serde_json::to_writer(&mut out, record)?;
+                out.write_all(b"\n").map_err(io_error)?;
+            }
+        }
+    }
+    let file = out.into_inner().map_err(|e| io_error(e.into_error()))?;
+    file.sync_data().map_err(io_error)?;
+    Ok(file.metadata().map_err(io_error)?.len())
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet<T: Record>(path: &Path, records: &[T]) -> Result<()> {
+    use parquet::arrow::ArrowWriter;
+
+    let batch = alpaca_base::arrow::to_record_batch(records)?;
+    let parquet_error = |e: parquet::errors::ParquetError| {
+        AlpacaError::InvalidData(format!("parquet write failed: {e}"))
+    };
+    let file = File::create(path).map_err(io_error)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).map_err(parquet_error)?;
+    writer.write(&batch).map_err(parquet_error)?;
+    writer.close().map_err(parquet_error)?;
+    Ok(())
+}
+
+fn read_checkpoint(path: &Path) -> Result<Option<DownloadCheckpoint>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(serde_json::from_str(&text)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(io_error(e)),
+    }
+}
+
+/// Replace the checkpoint atomically via a temp file and rename.
+fn write_checkpoint(path: &Path, checkpoint: &DownloadCheckpoint) -> Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp).map_err(io_error)?;
+    file.write_all(&serde_json::to_vec_pretty(checkpoint)?)
+        .and_then(|()| file.sync_data())
+        .map_err(io_error)?;
+    fs::rename(&tmp, path).map_err(io_error)
+}
+
+fn io_error(e: std::io::Error) -> AlpacaError {
+    AlpacaError::Config(format!("historical download i/o error: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_append_and_truncate_to_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("alpaca-download-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let client = AlpacaHttpClient::new(
+            alpaca_base::auth::Credentials::new("key".to_string(), "secret".to_string()),
+            alpaca_base::types::Environment::Paper,
+        )
+        .unwrap();
+        let at = Utc.with_ymd_and_hms(2024, 6, 3, 13, 30, 0).unwrap();
+        let downloader =
+            HistoricalDownloader::new(client, &["BRK/B"], DownloadKind::Trades, at, at);
+        let trade = Trade {
+            timestamp: at,
+            price: 1.5,
+            size: 10,
+            exchange: "V".to_string(),
+            conditions: vec!["@".to_string(), "I".to_string()],
+            id: 1,
+        };
+
+        let mut checkpoint = DownloadCheckpoint::default();
+        let page = HashMap::from([("BRK/B".to_string(), vec![trade.clone()])]);
+        let (rows, files) = downloader.write_page(&dir, &mut checkpoint, &page).unwrap();
+        assert_eq!(rows, 1);
+        assert!(files[0].ends_with("BRK-B_trades.csv"));
+
+        // A second page written but never checkpointed is dropped on resume.
+        let saved = checkpoint.clone();
+        downloader.write_page(&dir, &mut checkpoint, &page).unwrap();
+        downloader.truncate_to_checkpoint(&dir, &saved).unwrap();
+        let csv = fs::read_to_string(&files[0]).unwrap();
+        assert_eq!(
+            csv,
+            "timestamp,price,size,exchange,conditions,id\n\
+             2024-06-03T13:30:00+00:00,1.5,10,V,@ I,1\n"
+        );
+
+        let path = downloader.checkpoint_path(&dir);
+        write_checkpoint(&path, &saved).unwrap();
+        assert_eq!(read_checkpoint(&path).unwrap(), Some(saved));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
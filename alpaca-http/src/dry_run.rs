@@ -0,0 +1,285 @@
+//! Order previews that never reach the order endpoint.
+//!
+//! [`AlpacaHttpClient::dry_run_order`] runs the same checks as
+//! [`AlpacaHttpClient::preflight_order`], prices the order and estimates its
+//! buying power impact with a [`BuyingPowerCalculator`], and returns a
+//! [`DryRunOrder`] holding the simulated [`Order`] the API would have
+//! accepted. The account, configurations, position and latest quote are
+//! read as usual; nothing is submitted.
+//!
+//! A client built with [`AlpacaHttpClient::with_dry_run`] does this for
+//! every [`AlpacaHttpClient::create_order`] call, so a strategy can be
+//! sanity-checked against a live account without changing its code:
+//!
+//! ```rust,ignore
+//! let client = AlpacaHttpClient::new(credentials, Environment::Live)?.with_dry_run(true);
+//! let preview = client.dry_run_order(&CreateOrderRequest::market("AAPL", OrderSide::Buy, "10")).await?;
+//! println!("~${:.2}, {:.2} buying power left", preview.notional, preview.buying_power_after);
+//! ```
+//!
+//! Market and trailing stop orders are priced at the latest ask (buys) or
+//! bid (sells), limit and stop-limit orders at their limit price and stop
+//! orders at their stop price. Buys use buying power for their whole
+//! notional; sells only for the part that opens or extends a short.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::CreateOrderRequest;
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{
+        Account, AssetClass, BuyingPowerCalculator, Order, OrderClass, OrderSide, OrderStatus,
+        OrderType, Quote,
+    },
+    utils::parse_decimal,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+/// The outcome of an order dry run.
+#[derive(Debug, Clone)]
+pub struct DryRunOrder {
+    /// The order as the API would have accepted it.
+    pub order: Order,
+    /// Price the order was valued at.
+    pub price: f64,
+    /// Estimated quantity; derived from the price for notional orders.
+    pub qty: f64,
+    /// Estimated notional value.
+    pub notional: f64,
+    /// Buying power before the order.
+    pub buying_power: f64,
+    /// Buying power the order would use.
+    pub buying_power_impact: f64,
+    /// Buying power left after the order; negative when it does not fit.
+    pub buying_power_after: f64,
+    /// Most whole shares the buying power affords at `price`.
+    pub max_qty: u64,
+}
+
+impl DryRunOrder {
+    /// Estimate `order` for `account`. `quote` prices market and trailing
+    /// stop orders; `position_qty` is the signed quantity held in the
+    /// order's symbol (negative when short).
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if the order has no price and
+    /// `quote` has none either, or if an amount does not parse.
+    pub fn estimate(
+        order: &CreateOrderRequest,
+        account: &Account,
+        quote: Option<&Quote>,
+        position_qty: f64,
+    ) -> Result<Self> {
+        let price = reference_price(order, quote)?;
+        let (qty, notional) = size(order, price)?;
+        let buying_power_impact = match order.side {
+            OrderSide::Buy => notional,
+            OrderSide::Sell => (qty - position_qty.max(0.0)).max(0.0) * price,
+        };
+        let calculator = BuyingPowerCalculator::new(
+            parse_decimal(&account.cash)?,
+            parse_decimal(&account.portfolio_value)?,
+            parse_decimal(&account.multiplier)?,
+        );
+        let buying_power = calculator.buying_power();
+        Ok(Self {
+            order: simulated_order(order),
+            price,
+            qty,
+            notional,
+            buying_power,
+            buying_power_impact,
+            buying_power_after: buying_power - buying_power_impact,
+            max_qty: calculator.max_shares(price),
+        })
+    }
+
+    /// Whether the order fits in the buying power.
+    #[must_use]
+    pub fn fits_buying_power(&self) -> bool {
+        self.buying_power_after >= 0.0
+    }
+}
+
+impl AlpacaHttpClient {
+    /// Validate and price `order` without submitting it.
+    ///
+    /// # Errors
+    /// Returns the first validation or account restriction the order
+    /// violates (as [`Self::preflight_order`] does), or an error fetching
+    /// the account state or the latest quote. Insufficient buying power is
+    /// not an error here; see [`DryRunOrder::fits_buying_power`].
+    pub async fn dry_run_order(&self, order: &CreateOrderRequest) -> Result<DryRunOrder> {
+        let (account, position_qty) = self.preflight_checked(order).await?;
+        let quote = if needs_quote(order) {
+            Some(self.get_latest_quote(&order.symbol).await?.quote)
+        } else {
+            None
+        };
+        DryRunOrder::estimate(order, &account, quote.as_ref(), position_qty)
+    }
+
+    /// Estimated notional of `order`, priced as in [`Self::dry_run_order`].
+    pub(crate) async fn order_notional(&self, order: &CreateOrderRequest) -> Result<f64> {
+        if order.qty.is_none()
+            && let Some(notional) = &order.notional
+        {
+            return parse_decimal(notional);
+        }
+        let quote = if needs_quote(order) {
+            Some(self.get_latest_quote(&order.symbol).await?.quote)
+        } else {
+            None
+        };
+        let price = reference_price(order, quote.as_ref())?;
+        Ok(size(order, price)?.1)
+    }
+
+    /// The simulated order for `order` in dry-run mode.
+    pub(crate) async fn dry_run_create_order(&self, order: &CreateOrderRequest) -> Result<Order> {
+        let preview = self.dry_run_order(order).await?;
+        if !preview.fits_buying_power() {
+            return Err(AlpacaError::Validation(format!(
+                "{:?} order for {} needs {:.2} buying power, {:.2} available",
+                order.side, order.symbol, preview.buying_power_impact, preview.buying_power
+            )));
+        }
+        Ok(preview.order)
+    }
+}
+
+/// Whether `order` is priced from the latest quote.
+fn needs_quote(order: &CreateOrderRequest) -> bool {
+    match order.order_type {
+        OrderType::Market | OrderType::TrailingStop => true,
+        OrderType::Limit => order.limit_price.is_none(),
+        OrderType::Stop | OrderType::StopLimit => false,
+    }
+}
+
+/// Quantity and notional of `order` at `price`.
+fn size(order: &CreateOrderRequest, price: f64) -> Result<(f64, f64)> {
+    match (&order.qty, &order.notional) {
+        (Some(qty), _) => {
+            let qty = parse_decimal(qty)?;
+            Ok((qty, qty * price))
+        }
+        (None, Some(notional)) => {
+            let notional = parse_decimal(notional)?;
+            Ok((notional / price, notional))
+        }
+        (None, None) => Err(AlpacaError::InvalidData(
+            "either qty or notional is required".to_string(),
+        )),
+    }
+}
+
+fn reference_price(order: &CreateOrderRequest, quote: Option<&Quote>) -> Result<f64> {
+    let fixed = match order.order_type {
+        OrderType::Limit | OrderType::StopLimit => order
+            .limit_price
+            .as_deref()
+            .or(order.take_profit.as_ref().map(|tp| tp.limit_price.as_str())),
+        OrderType::Stop => order.stop_price.as_deref(),
+        OrderType::Market | OrderType::TrailingStop => None,
+    };
+    if let Some(price) = fixed {
+        return parse_decimal(price);
+    }
+    let quoted = quote.and_then(|quote| {
+        let (near, far) = match order.side {
+            OrderSide::Buy => (quote.ask_price, quote.bid_price),
+            OrderSide::Sell => (quote.bid_price, quote.ask_price),
+        };
+        [near, far].into_iter().find(|price| *price > 0.0)
+    });
+    quoted.ok_or_else(|| {
+        AlpacaError::InvalidData(format!("no quote to price the order for {}", order.symbol))
+    })
+}
+
+fn simulated_order(order: &CreateOrderRequest) -> Order {
+    let id = Uuid::new_v4();
+    let now = Utc::now();
+    Order {
+        id,
+        client_order_id: order
+            .client_order_id
+            .clone()
+            .unwrap_or_else(|| id.to_string()),
+        created_at: now,
+        updated_at: now,
+        submitted_at: None,
+        filled_at: None,
+        expired_at: None,
+        canceled_at: None,
+        failed_at: None,
+        replaced_at: None,
+        replaced_by: None,
+        replaces: None,
+        asset_id: Uuid::nil(),
+        symbol: order.symbol.clone(),
+        asset_class: if order.symbol.contains('/') {
+            AssetClass::Crypto
+        } else {
+            AssetClass::UsEquity
+        },
+        notional: order.notional.clone(),
+        qty: order.qty.clone(),
+        filled_qty: "0".to_string(),
+        filled_avg_price: None,
+        order_class: order.order_class.clone().unwrap_or(OrderClass::Simple),
+        order_type: order.order_type.clone(),
+        side: order.side.clone(),
+        time_in_force: order.time_in_force.clone(),
+        limit_price: order.limit_price.clone(),
+        stop_price: order.stop_price.clone(),
+        status: OrderStatus::Accepted,
+        extended_hours: order.extended_hours.unwrap_or(false),
+        legs: None,
+        trail_percent: order.trail_percent.clone(),
+        trail_price: order.trail_price.clone(),
+        hwm: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::{sample_account, sample_quote};
+
+    #[test]
+    fn test_dry_run_estimates_notional_and_buying_power() {
+        let mut account = sample_account();
+        account.cash = "10000".to_string();
+        account.multiplier = "2".to_string();
+        let mut quote = sample_quote(Utc::now());
+        quote.bid_price = 99.0;
+        quote.ask_price = 100.0;
+
+        let buy = CreateOrderRequest::market("AAPL", OrderSide::Buy, "150");
+        assert!(needs_quote(&buy));
+        let preview = DryRunOrder::estimate(&buy, &account, Some(&quote), 0.0).unwrap();
+        assert_eq!(preview.price, 100.0);
+        assert_eq!(preview.notional, 15_000.0);
+        assert_eq!(preview.buying_power, 20_000.0);
+        assert_eq!(preview.buying_power_after, 5_000.0);
+        assert_eq!(preview.max_qty, 200);
+        assert!(preview.fits_buying_power());
+        assert_eq!(preview.order.status, OrderStatus::Accepted);
+        assert_eq!(preview.order.qty.as_deref(), Some("150"));
+
+        // Selling 300 against 100 held long shorts 200 at the bid.
+        let sell = CreateOrderRequest::market("AAPL", OrderSide::Sell, "300");
+        let preview = DryRunOrder::estimate(&sell, &account, Some(&quote), 100.0).unwrap();
+        assert_eq!(preview.buying_power_impact, 19_800.0);
+
+        let limit = CreateOrderRequest::limit("AAPL", OrderSide::Buy, "1000", "50");
+        assert!(!needs_quote(&limit));
+        let preview = DryRunOrder::estimate(&limit, &account, None, 0.0).unwrap();
+        assert_eq!(preview.notional, 50_000.0);
+        assert!(!preview.fits_buying_power());
+
+        assert!(DryRunOrder::estimate(&buy, &account, None, 0.0).is_err());
+    }
+}
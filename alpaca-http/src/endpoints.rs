@@ -6,9 +6,32 @@
 
 #![allow(missing_docs)]
 
+use crate::batch_journals::{
+    BatchJournalReport, JournalBatch, JournalEntryOutcome, JournalEntryReport, match_journals,
+};
+use crate::cache::{CachedResource, cache_key};
 use crate::client::AlpacaHttpClient;
-use alpaca_base::{OAuthToken, Result, types::*};
+use crate::execution::{AlgoControl, AlgoStatus, ExecutionAlgo, ExecutionReport};
+use crate::liquidation::{LiquidationOptions, LiquidationReport};
+use crate::multi_status::MultiStatus;
+use crate::order_batch::{self, OrderBatchConfig, OrderBatchReport};
+use crate::preflight::AccountRestrictions;
+use crate::protection::{Protection, ProtectionOutcome, ProtectionPlan, is_closed};
+use crate::submit_policy::SubmitPolicy;
+#[cfg(feature = "unstable")]
+use alpaca_base::auction::{Auction, AuctionTiming, auction_timing};
+use alpaca_base::{
+    AlpacaError, OAuthToken, OptionLifecycleEvent, OrderReplacement, OrderRequest, Result,
+    TradingApi, ValidationError,
+    types::*,
+    utils::{
+        SessionWindow, parse_decimal, us_eastern_offset, validate_price, validate_quantity,
+        validate_symbol,
+    },
+};
 use chrono::{DateTime, NaiveDate, Utc};
+#[cfg(feature = "unstable")]
+use chrono::{Days, Duration};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -17,12 +40,16 @@ impl AlpacaHttpClient {
 
     /// Get account information
     pub async fn get_account(&self) -> Result<Account> {
-        self.get("/v2/account").await
+        self.cached(CachedResource::Account, "", || self.get("/v2/account"))
+            .await
     }
 
     /// Get account configurations
     pub async fn get_account_configurations(&self) -> Result<AccountConfigurations> {
-        self.get("/v2/account/configurations").await
+        self.cached(CachedResource::AccountConfigurations, "", || {
+            self.get("/v2/account/configurations")
+        })
+        .await
     }
 
     /// Update account configurations
@@ -30,14 +57,14 @@ impl AlpacaHttpClient {
         &self,
         config: &AccountConfigurations,
     ) -> Result<AccountConfigurations> {
-        self.patch("/v2/account/configurations", config).await
+        let updated = self.patch("/v2/account/configurations", config).await?;
+        self.invalidate_cache(CachedResource::AccountConfigurations);
+        Ok(updated)
     }
 
-    /// Get account activities
-    pub async fn get_account_activities(
-        &self,
-        params: &ActivityParams,
-    ) -> Result<Vec<AccountActivity>> {
+    /// Get account activities, typed as fills ([`Activity::Trade`]) or
+    /// non-trade activities ([`Activity::NonTrade`]) by `activity_type`.
+    pub async fn get_account_activities(&self, params: &ActivityParams) -> Result<Vec<Activity>> {
         self.get_with_params("/v2/account/activities", params).await
     }
 
@@ -54,7 +81,11 @@ impl AlpacaHttpClient {
 
     /// Get all assets
     pub async fn get_assets(&self, params: &AssetParams) -> Result<Vec<Asset>> {
-        self.get_with_params("/v2/assets", params).await
+        let key = cache_key(params)?;
+        self.cached(CachedResource::Assets, &key, || {
+            self.get_with_params("/v2/assets", params)
+        })
+        .await
     }
 
     /// Get asset by ID
@@ -75,8 +106,203 @@ impl AlpacaHttpClient {
     }
 
     /// Create a new order
+    ///
+    /// With an environment guard installed, live orders above its
+    /// confirmation threshold are refused; see
+    /// [`Self::create_order_confirmed`].
     pub async fn create_order(&self, order: &CreateOrderRequest) -> Result<Order> {
-        self.post("/v2/orders", order).await
+        self.submit_order(order, None).await
+    }
+
+    /// Create a new order that the environment guard lets through above
+    /// its live confirmation threshold. See
+    /// [`Self::with_environment_guard`].
+    pub async fn create_order_confirmed(
+        &self,
+        order: &CreateOrderRequest,
+        confirmation: LiveConfirmation,
+    ) -> Result<Order> {
+        self.submit_order(order, Some(&confirmation)).await
+    }
+
+    async fn submit_order(
+        &self,
+        order: &CreateOrderRequest,
+        confirmation: Option<&LiveConfirmation>,
+    ) -> Result<Order> {
+        if self.is_dry_run() {
+            return self.dry_run_create_order(order).await;
+        }
+        if let Some(guard) = self.environment_guard() {
+            let notional = if confirmation.is_none() && guard.needs_confirmation(f64::INFINITY) {
+                // Orders that cannot be priced count as above the threshold.
+                self.order_notional(order).await.unwrap_or(f64::INFINITY)
+            } else {
+                0.0
+            };
+            guard.check_order(notional, confirmation)?;
+        }
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let result = self.post("/v2/orders", order).await;
+        #[cfg(feature = "metrics")]
+        alpaca_base::metrics::record_order_submit("http", result.is_ok(), started.elapsed());
+        result
+    }
+
+    /// Check `order` locally and against the account before submitting it.
+    ///
+    /// Runs [`CreateOrderRequest::validate`], then checks the account's
+    /// trading and shorting flags and its configurations (see
+    /// [`crate::preflight`]). The account and configurations come from the
+    /// response cache when [`Self::with_cache`] is enabled; for sells, the
+    /// current position is fetched to tell a sell from a short. The
+    /// `gtd_date` of GTD orders is checked with
+    /// [`Self::validate_gtd_date`].
+    ///
+    /// # Errors
+    /// Returns the first validation or account restriction the order
+    /// violates, or an error fetching the account state.
+    pub async fn preflight_order(&self, order: &CreateOrderRequest) -> Result<()> {
+        self.preflight_checked(order).await.map(|_| ())
+    }
+
+    /// Run the checks of [`Self::preflight_order`], returning the account
+    /// and the signed position quantity they used.
+    pub(crate) async fn preflight_checked(
+        &self,
+        order: &CreateOrderRequest,
+    ) -> Result<(Account, f64)> {
+        order.validate()?;
+        if let (TimeInForce::Gtd, Some(date)) = (&order.time_in_force, order.gtd_date) {
+            self.validate_gtd_date(date).await?;
+        }
+        let account = self.get_account().await?;
+        let configurations = self.get_account_configurations().await?;
+        let position_qty = if order.side == OrderSide::Sell {
+            match self.get_position(&order.symbol).await {
+                Ok(position) => {
+                    let qty = parse_decimal(&position.qty)?;
+                    if position.side == PositionSide::Short {
+                        -qty.abs()
+                    } else {
+                        qty
+                    }
+                }
+                Err(e) if e.is_not_found() => 0.0,
+                Err(e) => return Err(e),
+            }
+        } else {
+            0.0
+        };
+        AccountRestrictions::new(&account, Some(&configurations)).check(order, position_qty)?;
+        Ok((account, position_qty))
+    }
+
+    /// Run [`Self::preflight_order`] and submit the order if it passes.
+    pub async fn create_order_checked(&self, order: &CreateOrderRequest) -> Result<Order> {
+        self.preflight_order(order).await?;
+        self.create_order(order).await
+    }
+
+    /// Submit `order`, resubmitting it as `policy` says when it is
+    /// rejected for a transient reason. See [`crate::submit_policy`].
+    ///
+    /// # Errors
+    /// Returns the last rejection once the policy has no rule for it or
+    /// its attempts are used up, or an error fetching the clock for
+    /// [`Reshape::DelayUntilOpen`](crate::Reshape::DelayUntilOpen).
+    pub async fn create_order_with_policy(
+        &self,
+        order: &CreateOrderRequest,
+        policy: &SubmitPolicy,
+    ) -> Result<Order> {
+        let mut order = order.clone();
+        let mut attempt = 1;
+        loop {
+            let error = match self.create_order(&order).await {
+                Ok(submitted) => return Ok(submitted),
+                Err(e) => e,
+            };
+            let Some(resubmission) = policy.resubmission(&order, &error, attempt) else {
+                return Err(error);
+            };
+            tracing::warn!(
+                "Order for {} rejected ({:?}), resubmitting: {}",
+                order.symbol,
+                resubmission.reason,
+                error
+            );
+            if resubmission.wait_for_open {
+                let clock = self.get_clock().await?;
+                if !clock.is_open {
+                    let wait = (clock.next_open - clock.timestamp)
+                        .to_std()
+                        .unwrap_or_default();
+                    tokio::time::sleep(wait).await;
+                }
+            }
+            tokio::time::sleep(resubmission.delay).await;
+            order = resubmission.order;
+            attempt += 1;
+        }
+    }
+
+    /// Submit `orders` concurrently with the default [`OrderBatchConfig`].
+    pub async fn create_orders_batch(&self, orders: Vec<CreateOrderRequest>) -> OrderBatchReport {
+        self.create_orders_batch_with_config(orders, &OrderBatchConfig::default())
+            .await
+    }
+
+    /// Submit `orders` concurrently, reporting the result of each.
+    ///
+    /// See [`crate::order_batch`] for how rate limits are handled. Pair
+    /// with [`Self::with_rate_limit`] to also stay under the limit up front.
+    pub async fn create_orders_batch_with_config(
+        &self,
+        orders: Vec<CreateOrderRequest>,
+        config: &OrderBatchConfig,
+    ) -> OrderBatchReport {
+        order_batch::submit_orders(self, orders, config).await
+    }
+
+    /// Submit a market-on-open or market-on-close order, checking the
+    /// current time against the auction cutoffs from the market calendar.
+    ///
+    /// An order past today's cutoff (less `buffer`) targets the next
+    /// session; if that session's window has not opened yet, this waits
+    /// until it does before submitting. See [`alpaca_base::auction`].
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if the order's time in force is
+    /// not `opg` or `cls`, or no upcoming session can take the order.
+    #[cfg(feature = "unstable")]
+    pub async fn submit_auction_order(
+        &self,
+        order: &CreateOrderRequest,
+        buffer: Duration,
+    ) -> Result<Order> {
+        let auction = Auction::from_time_in_force(&order.time_in_force).ok_or_else(|| {
+            AlpacaError::Validation(format!(
+                "time in force {:?} does not target an auction",
+                order.time_in_force
+            ))
+        })?;
+        // Start a day early so the first session's window has its prior
+        // trading day.
+        let today = Utc::now().date_naive();
+        let params = CalendarParams::new()
+            .start(&(today - Days::new(1)).to_string())
+            .end(&(today + Days::new(10)).to_string());
+        let calendar = self.get_calendar(&params).await?;
+
+        if let AuctionTiming::Deferred(window) =
+            auction_timing(auction, &calendar, Utc::now(), buffer)?
+        {
+            let wait = (window.opens - Utc::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+        }
+        self.create_order(order).await
     }
 
     /// Get order by ID
@@ -108,8 +334,102 @@ impl AlpacaHttpClient {
     }
 
     /// Cancel all orders
-    pub async fn cancel_all_orders(&self) -> Result<Vec<CancelOrderResponse>> {
-        self.delete("/v2/orders").await
+    ///
+    /// The response holds one status per order; see [`MultiStatus`].
+    pub async fn cancel_all_orders(&self) -> Result<MultiStatus<Uuid>> {
+        let responses: Vec<CancelOrderResponse> = self.delete("/v2/orders").await?;
+        Ok(responses.into())
+    }
+
+    /// Retry the retryable failures of a bulk cancellation one order at a
+    /// time.
+    pub async fn retry_failed_cancels(&self, results: MultiStatus<Uuid>) -> MultiStatus<Uuid> {
+        results
+            .retry_failed(|order_id| {
+                let order_id = *order_id;
+                async move { self.cancel_order(&order_id).await.map(|()| None) }
+            })
+            .await
+    }
+
+    /// Cancel every open order for `symbol`.
+    ///
+    /// Shorthand for [`Self::cancel_orders_matching`] with a symbol filter.
+    pub async fn cancel_orders_for_symbol(&self, symbol: &str) -> Result<Vec<CancelOrderResult>> {
+        self.cancel_orders_matching(&CancelOrdersFilter::new().symbol(symbol))
+            .await
+    }
+
+    /// Cancel every open order matching `filter`.
+    ///
+    /// Lists open orders (narrowed server-side by symbol and side where
+    /// possible), applies the rest of the filter locally, then cancels the
+    /// matches with up to [`CancelOrdersFilter::concurrency`] requests in
+    /// flight. Fails only if listing fails; individual cancel failures are
+    /// reported per order, in listing order.
+    pub async fn cancel_orders_matching(
+        &self,
+        filter: &CancelOrdersFilter,
+    ) -> Result<Vec<CancelOrderResult>> {
+        let mut params = OrderParams::new()
+            .status(OrderQueryStatus::Open)
+            .limit(500)
+            .direction(SortDirection::Asc);
+        if !filter.symbols.is_empty() {
+            params = params.symbols(filter.symbols.join(","));
+        }
+        if let Some(side) = &filter.side {
+            params = params.side(side.clone());
+        }
+
+        let mut matches = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        loop {
+            let page = self.get_orders(&params).await?;
+            let full = page.len() == 500;
+            let last_created = page.last().map(|order| order.created_at);
+            matches.extend(
+                page.into_iter()
+                    .filter(|order| seen.insert(order.id) && filter.matches(order)),
+            );
+            match last_created {
+                Some(created) if full => params = params.after(created),
+                _ => break,
+            }
+        }
+
+        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(filter.concurrency.max(1)));
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, order) in matches.iter().enumerate() {
+            let client = self.clone();
+            let semaphore = semaphore.clone();
+            let order_id = order.id;
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                (index, client.cancel_order(&order_id).await)
+            });
+        }
+
+        let mut outcomes: Vec<Option<Result<()>>> = matches.iter().map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, outcome)) => outcomes[index] = Some(outcome),
+                Err(e) => tracing::error!("cancel task failed: {}", e),
+            }
+        }
+
+        Ok(matches
+            .into_iter()
+            .zip(outcomes)
+            .map(|(order, outcome)| CancelOrderResult {
+                order_id: order.id,
+                client_order_id: order.client_order_id,
+                symbol: order.symbol,
+                outcome: outcome.unwrap_or_else(|| {
+                    Err(AlpacaError::Network("cancel task aborted".to_string()))
+                }),
+            })
+            .collect())
     }
 
     // Position endpoints
@@ -125,12 +445,27 @@ impl AlpacaHttpClient {
     }
 
     /// Close all positions
-    pub async fn close_all_positions(
-        &self,
-        cancel_orders: bool,
-    ) -> Result<Vec<ClosePositionResponse>> {
+    ///
+    /// The response holds one status per symbol; see [`MultiStatus`].
+    pub async fn close_all_positions(&self, cancel_orders: bool) -> Result<MultiStatus<String>> {
         let url = format!("/v2/positions?cancel_orders={}", cancel_orders);
-        self.delete(&url).await
+        let responses: Vec<ClosePositionResponse> = self.delete(&url).await?;
+        Ok(responses.into())
+    }
+
+    /// Retry the retryable failures of closing all positions one symbol at
+    /// a time.
+    pub async fn retry_failed_closes(&self, results: MultiStatus<String>) -> MultiStatus<String> {
+        results
+            .retry_failed(|symbol| {
+                let symbol = symbol.clone();
+                async move {
+                    self.close_position(&symbol, &ClosePositionRequest::default())
+                        .await
+                        .map(Some)
+                }
+            })
+            .await
     }
 
     /// Close position by symbol
@@ -142,6 +477,177 @@ impl AlpacaHttpClient {
         self.delete(&format!("/v2/positions/{}", symbol)).await
     }
 
+    /// Flatten the account: cancel open orders, close every position one
+    /// at a time in the order set by `options`, then poll until no
+    /// position is left or `options.timeout` passes. See
+    /// [`crate::liquidation`].
+    ///
+    /// # Errors
+    /// Returns an error only if orders or positions cannot be listed;
+    /// failed cancels and closes are reported in
+    /// [`LiquidationReport::failures`].
+    pub async fn liquidate_portfolio(
+        &self,
+        options: &LiquidationOptions,
+    ) -> Result<LiquidationReport> {
+        let started = std::time::Instant::now();
+        let deadline = started + options.timeout;
+        let mut report = LiquidationReport::default();
+
+        if options.cancel_orders {
+            for item in self.cancel_all_orders().await?.items {
+                match item.outcome {
+                    Ok(_) => report.orders_cancelled += 1,
+                    Err(e) => report.failures.push((item.key.to_string(), e)),
+                }
+            }
+            // Open orders hold the quantity a close would need.
+            let open = OrderParams::new().status(OrderQueryStatus::Open).limit(1);
+            while !self.get_orders(&open).await?.is_empty() && std::time::Instant::now() < deadline
+            {
+                tokio::time::sleep(options.poll_interval).await;
+            }
+        }
+
+        let positions = options.close_order(self.get_positions().await?);
+        for position in positions {
+            match self
+                .close_position(&position.symbol, &ClosePositionRequest::default())
+                .await
+            {
+                Ok(order) => report.orders.push(order),
+                Err(e) => report.failures.push((position.symbol, e)),
+            }
+        }
+
+        loop {
+            report.remaining = self.get_positions().await?;
+            if report.remaining.is_empty() || std::time::Instant::now() >= deadline {
+                break;
+            }
+            tokio::time::sleep(options.poll_interval).await;
+        }
+        report.elapsed = started.elapsed();
+        Ok(report)
+    }
+
+    /// Work `algo` until its parent is filled, its deadline passes or
+    /// `control` cancels it, polling child orders every `poll_interval`.
+    /// At most one child is submitted per poll, and children still open
+    /// when the run ends are cancelled. A child that cannot be submitted
+    /// or queried ends the run with [`AlgoStatus::Failed`] and the error in
+    /// [`ExecutionReport::error`]. See [`crate::execution`].
+    pub async fn execute_algo(
+        &self,
+        mut algo: ExecutionAlgo,
+        control: &AlgoControl,
+        poll_interval: std::time::Duration,
+    ) -> ExecutionReport {
+        let mut error = None;
+        let status = loop {
+            algo.on_market_trade(control.take_market_volume());
+            for id in algo.open_children() {
+                match self.get_order(&id).await {
+                    Ok(order) => algo.on_child_update(&order),
+                    Err(e) => error = Some(e),
+                }
+            }
+
+            let now = Utc::now();
+            let status = if error.is_some() {
+                AlgoStatus::Failed
+            } else if algo.is_complete() {
+                AlgoStatus::Completed
+            } else if control.is_cancelled() {
+                AlgoStatus::Cancelled
+            } else if algo.is_expired(now) {
+                AlgoStatus::Expired
+            } else if control.is_paused() {
+                AlgoStatus::Paused
+            } else {
+                AlgoStatus::Running
+            };
+            if status.is_finished() {
+                break status;
+            }
+            if status == AlgoStatus::Running
+                && let Some(request) = algo.next_child(now)
+            {
+                match self.create_order(&request).await {
+                    Ok(order) => algo.on_child_submitted(&order),
+                    Err(e) => {
+                        error = Some(e);
+                        break AlgoStatus::Failed;
+                    }
+                }
+            }
+            control.publish(algo.progress(status));
+            tokio::time::sleep(poll_interval).await;
+        };
+
+        for id in algo.open_children() {
+            if let Err(e) = self.cancel_order(&id).await {
+                tracing::warn!("Failed to cancel algo child order {}: {}", id, e);
+            }
+            if let Ok(order) = self.get_order(&id).await {
+                algo.on_child_update(&order);
+            }
+        }
+        control.publish(algo.progress(status));
+        algo.report(status, error, Utc::now())
+    }
+
+    /// Keep protective exit orders on the position in `symbol`.
+    ///
+    /// Submits an OCO for a stop plus a target, or a single stop or limit
+    /// order, sized to the position; existing exit-side orders that do not
+    /// match are cancelled first. Repeating the call with the same prices
+    /// returns [`ProtectionOutcome::Unchanged`]. See [`crate::protection`].
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] for a flat position or invalid
+    /// prices, and [`AlpacaError::Timeout`] if a cancelled order does not
+    /// close in time; nothing new is submitted in either case.
+    pub async fn protect_position(
+        &self,
+        symbol: &str,
+        stop: Option<f64>,
+        target: Option<f64>,
+    ) -> Result<ProtectionOutcome> {
+        let position = self.get_position(symbol).await?;
+        let params = OrderParams::new()
+            .status(OrderQueryStatus::Open)
+            .symbols(symbol)
+            .nested(true)
+            .limit(500);
+        let open_orders = self.get_orders(&params).await?;
+
+        let (cancel, request) = match (Protection { stop, target }).plan(&position, &open_orders)? {
+            ProtectionPlan::Keep(order) => return Ok(ProtectionOutcome::Unchanged(order)),
+            ProtectionPlan::Replace { cancel, request } => (cancel, request),
+        };
+        let mut replaced = Vec::with_capacity(cancel.len());
+        for order in &cancel {
+            self.cancel_order(&order.id).await?;
+            replaced.push(order.id);
+        }
+        // The exit quantity stays reserved until the cancels complete.
+        for order_id in &replaced {
+            let mut attempts = 0;
+            while !is_closed(&self.get_order(order_id).await?.status) {
+                attempts += 1;
+                if attempts >= 20 {
+                    return Err(AlpacaError::Timeout(format!(
+                        "order {order_id} still open after cancel"
+                    )));
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+            }
+        }
+        let order = self.create_order(&request).await?;
+        Ok(ProtectionOutcome::Placed { order, replaced })
+    }
+
     // Watchlist endpoints
 
     /// Get all watchlists
@@ -194,20 +700,44 @@ impl AlpacaHttpClient {
 
     /// Get bars for a symbol
     pub async fn get_bars(&self, symbol: &str, params: &BarsParams) -> Result<BarsResponse> {
-        self.get_with_params(&format!("/v2/stocks/{}/bars", symbol), params)
-            .await
+        let mut response: BarsResponse = self
+            .get_with_params(&format!("/v2/stocks/{}/bars", symbol), params)
+            .await?;
+        response.feed = params.feed;
+        if params.regular_hours_only
+            && params.timeframe.as_deref().is_some_and(is_intraday)
+            && let (Some(first), Some(last)) = (response.bars.first(), response.bars.last())
+        {
+            let date = |bar: &Bar| {
+                bar.timestamp
+                    .with_timezone(&us_eastern_offset(bar.timestamp.date_naive()))
+                    .date_naive()
+                    .to_string()
+            };
+            let calendar = self
+                .get_calendar(&CalendarParams::new().start(&date(first)).end(&date(last)))
+                .await?;
+            retain_regular_hours(&mut response.bars, &calendar)?;
+        }
+        Ok(response)
     }
 
     /// Get quotes for a symbol
     pub async fn get_quotes(&self, symbol: &str, params: &QuotesParams) -> Result<QuotesResponse> {
-        self.get_with_params(&format!("/v2/stocks/{}/quotes", symbol), params)
-            .await
+        let mut response: QuotesResponse = self
+            .get_with_params(&format!("/v2/stocks/{}/quotes", symbol), params)
+            .await?;
+        response.feed = params.feed;
+        Ok(response)
     }
 
     /// Get trades for a symbol
     pub async fn get_trades(&self, symbol: &str, params: &TradesParams) -> Result<TradesResponse> {
-        self.get_with_params(&format!("/v2/stocks/{}/trades", symbol), params)
-            .await
+        let mut response: TradesResponse = self
+            .get_with_params(&format!("/v2/stocks/{}/trades", symbol), params)
+            .await?;
+        response.feed = params.feed;
+        Ok(response)
     }
 
     /// Get latest bar for a symbol
@@ -232,12 +762,17 @@ impl AlpacaHttpClient {
 
     /// Get market calendar
     pub async fn get_calendar(&self, params: &CalendarParams) -> Result<Vec<Calendar>> {
-        self.get_with_params("/v2/calendar", params).await
+        let key = cache_key(params)?;
+        self.cached(CachedResource::Calendar, &key, || {
+            self.get_with_params("/v2/calendar", params)
+        })
+        .await
     }
 
     /// Get market clock
     pub async fn get_clock(&self) -> Result<Clock> {
-        self.get("/v2/clock").await
+        self.cached(CachedResource::Clock, "", || self.get("/v2/clock"))
+            .await
     }
 
     // News endpoints
@@ -282,7 +817,7 @@ impl AlpacaHttpClient {
 
 // Request/Response types
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountConfigurations {
     pub dtbp_check: Option<String>,
     pub trade_confirm_email: Option<String>,
@@ -408,7 +943,7 @@ impl OrderParams {
 /// Request to create a new order.
 ///
 /// Supports all order types including simple, bracket, OCO, and OTO orders.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CreateOrderRequest {
     /// The symbol to trade.
     pub symbol: String,
@@ -559,6 +1094,42 @@ impl CreateOrderRequest {
         }
     }
 
+    /// Creates a market-on-open order request (`opg`), filled in the
+    /// opening auction.
+    ///
+    /// With the `unstable` feature, submit it with
+    /// [`AlpacaHttpClient::submit_auction_order`] to check the submission
+    /// window first.
+    #[must_use]
+    pub fn market_on_open(
+        symbol: impl Into<String>,
+        side: OrderSide,
+        qty: impl Into<String>,
+    ) -> Self {
+        Self {
+            time_in_force: TimeInForce::Opg,
+            ..Self::market(symbol, side, qty)
+        }
+    }
+
+    /// Creates a market-on-close order request (`cls`), filled in the
+    /// closing auction.
+    ///
+    /// With the `unstable` feature, submit it with
+    /// [`AlpacaHttpClient::submit_auction_order`] to check the submission
+    /// window first.
+    #[must_use]
+    pub fn market_on_close(
+        symbol: impl Into<String>,
+        side: OrderSide,
+        qty: impl Into<String>,
+    ) -> Self {
+        Self {
+            time_in_force: TimeInForce::Cls,
+            ..Self::market(symbol, side, qty)
+        }
+    }
+
     /// Creates a new bracket order request.
     ///
     /// A bracket order is a set of three orders: a primary order and two
@@ -677,6 +1248,144 @@ impl CreateOrderRequest {
         self.gtd_date = Some(date);
         self
     }
+
+    /// Validates the request locally before it is submitted.
+    ///
+    /// Checks the symbol, quantity/notional, the prices required by the
+    /// order type, and that bracket/OCO legs sit on the correct side of
+    /// each other and of the entry limit price.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        validate_symbol(&self.symbol)?;
+
+        match (&self.qty, &self.notional) {
+            (Some(qty), None) => validate_quantity(qty)?,
+            (None, Some(notional)) => validate_price(notional)?,
+            (Some(_), Some(_)) => {
+                return Err(AlpacaError::InvalidData(
+                    "qty and notional are mutually exclusive".to_string(),
+                ));
+            }
+            (None, None) => {
+                return Err(AlpacaError::InvalidData(
+                    "either qty or notional is required".to_string(),
+                ));
+            }
+        }
+
+        let required = |field: &Option<String>, name: &str| match field {
+            Some(price) => validate_price(price),
+            None => Err(AlpacaError::InvalidData(format!(
+                "{name} is required for {:?} orders",
+                self.order_type
+            ))),
+        };
+        match self.order_type {
+            OrderType::Market => {}
+            // OCO orders carry their limit price in the take-profit leg.
+            OrderType::Limit if self.order_class == Some(OrderClass::Oco) => {}
+            OrderType::Limit => required(&self.limit_price, "limit_price")?,
+            OrderType::Stop => required(&self.stop_price, "stop_price")?,
+            OrderType::StopLimit => {
+                required(&self.stop_price, "stop_price")?;
+                required(&self.limit_price, "limit_price")?;
+            }
+            OrderType::TrailingStop => match (&self.trail_price, &self.trail_percent) {
+                (Some(trail), None) | (None, Some(trail)) => validate_price(trail)?,
+                _ => {
+                    return Err(AlpacaError::InvalidData(
+                        "exactly one of trail_price or trail_percent is required".to_string(),
+                    ));
+                }
+            },
+        }
+
+        if matches!(
+            self.order_class,
+            Some(OrderClass::Bracket | OrderClass::Oco)
+        ) {
+            let (Some(take_profit), Some(stop_loss)) = (&self.take_profit, &self.stop_loss) else {
+                return Err(AlpacaError::InvalidData(
+                    "take_profit and stop_loss are required for bracket and OCO orders".to_string(),
+                ));
+            };
+            let take_profit = parse_decimal(&take_profit.limit_price)?;
+            let stop = parse_decimal(&stop_loss.stop_price)?;
+            // Bracket legs close a new position, OCO legs close an existing
+            // one: a long position takes profit above and stops below.
+            let long_exit = matches!(
+                (&self.order_class, &self.side),
+                (Some(OrderClass::Bracket), OrderSide::Buy)
+                    | (Some(OrderClass::Oco), OrderSide::Sell)
+            );
+            let (upper, lower) = if long_exit {
+                (take_profit, stop)
+            } else {
+                (stop, take_profit)
+            };
+            let entry = match self.order_class {
+                Some(OrderClass::Bracket) => self.limit_price.as_deref().map(parse_decimal),
+                _ => None,
+            }
+            .transpose()?;
+            if upper <= lower || entry.is_some_and(|entry| entry <= lower || entry >= upper) {
+                return Err(AlpacaError::InvalidData(format!(
+                    "take_profit {take_profit} and stop_loss {stop} are on the wrong side \
+                     for a {:?} order",
+                    self.side
+                )));
+            }
+        }
+
+        if self.time_in_force == TimeInForce::Gtd && self.gtd_date.is_none() {
+            return Err(AlpacaError::InvalidData(
+                "gtd_date is required for GTD orders".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&OrderRequest> for CreateOrderRequest {
+    fn from(order: &OrderRequest) -> Self {
+        Self {
+            symbol: order.symbol.clone(),
+            qty: Some(order.qty.to_string()),
+            side: order.side.clone(),
+            order_type: order.order_type.clone(),
+            time_in_force: order.time_in_force.clone(),
+            limit_price: order.limit_price.map(|p| p.to_string()),
+            stop_price: order.stop_price.map(|p| p.to_string()),
+            client_order_id: order.client_order_id.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl TradingApi for AlpacaHttpClient {
+    async fn submit_order(&self, order: &OrderRequest) -> Result<Order> {
+        self.create_order(&CreateOrderRequest::from(order)).await
+    }
+
+    async fn cancel_order(&self, order: &Order) -> Result<()> {
+        AlpacaHttpClient::cancel_order(self, &order.id).await
+    }
+
+    async fn replace_order(&self, order: &Order, replacement: &OrderReplacement) -> Result<Order> {
+        AlpacaHttpClient::replace_order(self, &order.id, &ReplaceOrderRequest::from(replacement))
+            .await
+    }
+
+    async fn get_positions(&self) -> Result<Vec<Position>> {
+        AlpacaHttpClient::get_positions(self).await
+    }
+
+    async fn get_account(&self) -> Result<Account> {
+        AlpacaHttpClient::get_account(self).await
+    }
 }
 
 /// Request to replace (modify) an existing order.
@@ -696,6 +1405,18 @@ pub struct ReplaceOrderRequest {
     pub client_order_id: Option<String>,
 }
 
+impl From<&OrderReplacement> for ReplaceOrderRequest {
+    fn from(replacement: &OrderReplacement) -> Self {
+        Self {
+            qty: replacement.qty.map(|q| q.to_string()),
+            time_in_force: replacement.time_in_force.clone(),
+            limit_price: replacement.limit_price.map(|p| p.to_string()),
+            stop_price: replacement.stop_price.map(|p| p.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
 impl ReplaceOrderRequest {
     /// Creates a new empty replace order request.
     #[must_use]
@@ -746,16 +1467,158 @@ impl ReplaceOrderRequest {
     }
 }
 
+/// One entry of the multi-status response to cancelling all orders.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CancelOrderResponse {
+    /// Order ID.
     pub id: Uuid,
-    pub status: i32,
+    /// HTTP status of the cancellation.
+    pub status: u16,
+    /// The order, or an error body.
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+}
+
+/// Selects open orders for [`AlpacaHttpClient::cancel_orders_matching`].
+///
+/// An empty filter matches every open order.
+#[derive(Debug, Clone)]
+pub struct CancelOrdersFilter {
+    /// Only orders for these symbols (any symbol when empty).
+    pub symbols: Vec<String>,
+    /// Only orders on this side.
+    pub side: Option<OrderSide>,
+    /// Only orders of this type.
+    pub order_type: Option<OrderType>,
+    /// Only orders whose client order ID starts with this prefix.
+    pub client_order_id_prefix: Option<String>,
+    /// Only orders created at or after this time.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only orders created at or before this time.
+    pub created_before: Option<DateTime<Utc>>,
+    /// Maximum cancel requests in flight (default 8).
+    pub concurrency: usize,
+}
+
+impl Default for CancelOrdersFilter {
+    fn default() -> Self {
+        Self {
+            symbols: Vec::new(),
+            side: None,
+            order_type: None,
+            client_order_id_prefix: None,
+            created_after: None,
+            created_before: None,
+            concurrency: 8,
+        }
+    }
 }
 
+impl CancelOrdersFilter {
+    /// Creates a filter matching every open order.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a symbol to match.
+    #[must_use]
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.symbols.push(symbol.into());
+        self
+    }
+
+    /// Sets the side to match.
+    #[must_use]
+    pub fn side(mut self, side: OrderSide) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    /// Sets the order type to match.
+    #[must_use]
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = Some(order_type);
+        self
+    }
+
+    /// Sets the client order ID prefix to match.
+    #[must_use]
+    pub fn client_order_id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.client_order_id_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Matches orders created at or after `time`.
+    #[must_use]
+    pub fn created_after(mut self, time: DateTime<Utc>) -> Self {
+        self.created_after = Some(time);
+        self
+    }
+
+    /// Matches orders created at or before `time`.
+    #[must_use]
+    pub fn created_before(mut self, time: DateTime<Utc>) -> Self {
+        self.created_before = Some(time);
+        self
+    }
+
+    /// Sets the maximum number of cancel requests in flight.
+    #[must_use]
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Whether `order` satisfies the filter.
+    #[must_use]
+    pub fn matches(&self, order: &Order) -> bool {
+        (self.symbols.is_empty() || self.symbols.contains(&order.symbol))
+            && self.side.as_ref().is_none_or(|side| *side == order.side)
+            && self
+                .order_type
+                .as_ref()
+                .is_none_or(|order_type| *order_type == order.order_type)
+            && self
+                .client_order_id_prefix
+                .as_deref()
+                .is_none_or(|prefix| order.client_order_id.starts_with(prefix))
+            && self.created_after.is_none_or(|t| order.created_at >= t)
+            && self.created_before.is_none_or(|t| order.created_at <= t)
+    }
+}
+
+/// Outcome of cancelling one order in a bulk cancellation.
+#[derive(Debug)]
+pub struct CancelOrderResult {
+    /// Order ID.
+    pub order_id: Uuid,
+    /// Client order ID.
+    pub client_order_id: String,
+    /// Order symbol.
+    pub symbol: String,
+    /// Whether the cancel request was accepted.
+    pub outcome: Result<()>,
+}
+
+impl CancelOrderResult {
+    /// Whether the cancel request was accepted.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+/// One entry of the multi-status response to closing all positions.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClosePositionResponse {
+    /// Symbol.
     pub symbol: String,
-    pub status: i32,
+    /// HTTP status of the close.
+    pub status: u16,
+    /// The liquidating order, or an error body.
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
 }
 
 /// Request to close a position.
@@ -814,8 +1677,23 @@ pub struct BarsParams {
     pub page_token: Option<String>,
     pub limit: Option<u32>,
     pub asof: Option<String>,
-    pub feed: Option<String>,
+    pub feed: Option<DataFeed>,
     pub sort: Option<String>,
+    /// Drop intraday bars outside regular trading hours. Applied by
+    /// [`AlpacaHttpClient::get_bars`]; not sent to the API.
+    #[serde(skip)]
+    pub regular_hours_only: bool,
+}
+
+impl BarsParams {
+    /// Keep only intraday bars that start within regular trading hours,
+    /// using the market calendar, so half days end at their early close.
+    /// Daily and longer bars are returned unchanged.
+    #[must_use]
+    pub fn regular_hours_only(mut self) -> Self {
+        self.regular_hours_only = true;
+        self
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -823,6 +1701,9 @@ pub struct BarsResponse {
     pub bars: Vec<Bar>,
     pub symbol: String,
     pub next_page_token: Option<String>,
+    /// Feed the data came from, when the request named one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed: Option<DataFeed>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -832,7 +1713,7 @@ pub struct QuotesParams {
     pub page_token: Option<String>,
     pub limit: Option<u32>,
     pub asof: Option<String>,
-    pub feed: Option<String>,
+    pub feed: Option<DataFeed>,
     pub sort: Option<String>,
 }
 
@@ -841,6 +1722,9 @@ pub struct QuotesResponse {
     pub quotes: Vec<Quote>,
     pub symbol: String,
     pub next_page_token: Option<String>,
+    /// Feed the data came from, when the request named one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed: Option<DataFeed>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -850,7 +1734,7 @@ pub struct TradesParams {
     pub page_token: Option<String>,
     pub limit: Option<u32>,
     pub asof: Option<String>,
-    pub feed: Option<String>,
+    pub feed: Option<DataFeed>,
     pub sort: Option<String>,
 }
 
@@ -859,6 +1743,9 @@ pub struct TradesResponse {
     pub trades: Vec<Trade>,
     pub symbol: String,
     pub next_page_token: Option<String>,
+    /// Feed the data came from, when the request named one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed: Option<DataFeed>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -996,6 +1883,24 @@ pub struct OptionBarsResponse {
     pub next_page_token: Option<String>,
 }
 
+/// Response for historical option trades.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OptionTradesResponse {
+    /// Map of symbol to trades.
+    pub trades: std::collections::HashMap<String, Vec<OptionTrade>>,
+    /// Token for next page of results.
+    pub next_page_token: Option<String>,
+}
+
+/// Response for historical option quotes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OptionQuotesResponse {
+    /// Map of symbol to quotes.
+    pub quotes: std::collections::HashMap<String, Vec<OptionQuote>>,
+    /// Token for next page of results.
+    pub next_page_token: Option<String>,
+}
+
 /// Response for option snapshots.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OptionSnapshotsResponse {
@@ -1060,6 +1965,84 @@ impl AlpacaHttpClient {
         self.get_with_params("/v1beta1/options/bars", params).await
     }
 
+    /// Get historical trades for option contracts.
+    ///
+    /// # Arguments
+    /// * `params` - Query parameters including symbols and date range
+    ///
+    /// # Returns
+    /// One page of trades per requested option
+    pub async fn get_option_trades(
+        &self,
+        params: &OptionTicksParams,
+    ) -> Result<OptionTradesResponse> {
+        self.get_with_params("/v1beta1/options/trades", params)
+            .await
+    }
+
+    /// Get every trade matching `params`, following `next_page_token` from
+    /// `params.page_token`.
+    ///
+    /// # Returns
+    /// All matching trades per option, in the requested sort order
+    pub async fn get_all_option_trades(
+        &self,
+        params: &OptionTicksParams,
+    ) -> Result<std::collections::HashMap<String, Vec<OptionTrade>>> {
+        let mut params = params.clone();
+        let mut trades: std::collections::HashMap<String, Vec<OptionTrade>> =
+            std::collections::HashMap::new();
+        loop {
+            let page = self.get_option_trades(&params).await?;
+            for (symbol, page_trades) in page.trades {
+                trades.entry(symbol).or_default().extend(page_trades);
+            }
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => params.page_token = Some(token),
+                _ => return Ok(trades),
+            }
+        }
+    }
+
+    /// Get historical quotes for option contracts.
+    ///
+    /// # Arguments
+    /// * `params` - Query parameters including symbols and date range
+    ///
+    /// # Returns
+    /// One page of quotes per requested option
+    pub async fn get_option_quotes(
+        &self,
+        params: &OptionTicksParams,
+    ) -> Result<OptionQuotesResponse> {
+        self.get_with_params("/v1beta1/options/quotes", params)
+            .await
+    }
+
+    /// Get every quote matching `params`, following `next_page_token` from
+    /// `params.page_token`.
+    ///
+    /// # Returns
+    /// All matching quotes per option, in the requested sort order
+    pub async fn get_all_option_quotes(
+        &self,
+        params: &OptionTicksParams,
+    ) -> Result<std::collections::HashMap<String, Vec<OptionQuote>>> {
+        let mut params = params.clone();
+        let mut quotes: std::collections::HashMap<String, Vec<OptionQuote>> =
+            std::collections::HashMap::new();
+        loop {
+            let page = self.get_option_quotes(&params).await?;
+            for (symbol, page_quotes) in page.quotes {
+                quotes.entry(symbol).or_default().extend(page_quotes);
+            }
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => params.page_token = Some(token),
+                _ => return Ok(quotes),
+            }
+        }
+    }
+
     /// Get snapshots for option contracts.
     ///
     /// # Arguments
@@ -1107,6 +2090,9 @@ pub struct MultiBarsResponse {
     pub bars: std::collections::HashMap<String, Vec<Bar>>,
     /// Token for next page of results.
     pub next_page_token: Option<String>,
+    /// Feed the data came from, when the request named one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed: Option<DataFeed>,
 }
 
 /// Response for multi-symbol quotes.
@@ -1116,6 +2102,9 @@ pub struct MultiQuotesResponse {
     pub quotes: std::collections::HashMap<String, Vec<Quote>>,
     /// Token for next page of results.
     pub next_page_token: Option<String>,
+    /// Feed the data came from, when the request named one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed: Option<DataFeed>,
 }
 
 /// Response for multi-symbol trades.
@@ -1125,6 +2114,9 @@ pub struct MultiTradesResponse {
     pub trades: std::collections::HashMap<String, Vec<Trade>>,
     /// Token for next page of results.
     pub next_page_token: Option<String>,
+    /// Feed the data came from, when the request named one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed: Option<DataFeed>,
 }
 
 /// Response for stock snapshots.
@@ -1144,6 +2136,16 @@ pub struct CorporateActionsResponse {
     pub next_page_token: Option<String>,
 }
 
+/// Response for corporate actions from the `/v1beta1/corporate-actions`
+/// endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CorporateActionsV1Response {
+    /// Corporate actions grouped by type.
+    pub corporate_actions: CorporateActionsV1,
+    /// Token for next page of results.
+    pub next_page_token: Option<String>,
+}
+
 /// Response for latest bars.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LatestBarsResponse {
@@ -1178,7 +2180,10 @@ impl AlpacaHttpClient {
     /// # Returns
     /// Historical bar data for all requested symbols
     pub async fn get_stock_bars(&self, params: &MultiBarsParams) -> Result<MultiBarsResponse> {
-        self.get_with_params("/v2/stocks/bars", params).await
+        let mut response: MultiBarsResponse =
+            self.get_with_params("/v2/stocks/bars", params).await?;
+        response.feed = params.feed;
+        Ok(response)
     }
 
     /// Get historical quotes for multiple symbols.
@@ -1192,7 +2197,10 @@ impl AlpacaHttpClient {
         &self,
         params: &MultiQuotesParams,
     ) -> Result<MultiQuotesResponse> {
-        self.get_with_params("/v2/stocks/quotes", params).await
+        let mut response: MultiQuotesResponse =
+            self.get_with_params("/v2/stocks/quotes", params).await?;
+        response.feed = params.feed;
+        Ok(response)
     }
 
     /// Get historical trades for multiple symbols.
@@ -1206,7 +2214,10 @@ impl AlpacaHttpClient {
         &self,
         params: &MultiTradesParams,
     ) -> Result<MultiTradesResponse> {
-        self.get_with_params("/v2/stocks/trades", params).await
+        let mut response: MultiTradesResponse =
+            self.get_with_params("/v2/stocks/trades", params).await?;
+        response.feed = params.feed;
+        Ok(response)
     }
 
     /// Get snapshots for multiple symbols.
@@ -1295,6 +2306,43 @@ impl AlpacaHttpClient {
         self.get_with_params("/v1beta1/corporate-actions/announcements", params)
             .await
     }
+
+    /// Get corporate actions grouped by type from the market data API.
+    ///
+    /// # Arguments
+    /// * `params` - Query parameters; `types` takes values such as
+    ///   `forward_split`, `cash_dividend` or `name_change`
+    ///
+    /// # Returns
+    /// One page of corporate actions
+    pub async fn get_corporate_actions_v1(
+        &self,
+        params: &CorporateActionsParams,
+    ) -> Result<CorporateActionsV1Response> {
+        self.get_with_params("/v1beta1/corporate-actions", params)
+            .await
+    }
+
+    /// Get every corporate action matching `params`, following
+    /// `next_page_token` from `params.page_token`.
+    ///
+    /// # Returns
+    /// All matching corporate actions grouped by type
+    pub async fn get_all_corporate_actions_v1(
+        &self,
+        params: &CorporateActionsParams,
+    ) -> Result<CorporateActionsV1> {
+        let mut params = params.clone();
+        let mut actions = CorporateActionsV1::default();
+        loop {
+            let page = self.get_corporate_actions_v1(&params).await?;
+            actions.extend(page.corporate_actions);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => params.page_token = Some(token),
+                _ => return Ok(actions),
+            }
+        }
+    }
 }
 
 // ============================================================================
@@ -1320,6 +2368,51 @@ impl AlpacaHttpClient {
         self.post("/v1/accounts", request).await
     }
 
+    /// List the countries accounts can be opened for, with each
+    /// country's onboarding requirements.
+    ///
+    /// # Returns
+    /// Accepted tax ID types and required documents per country
+    pub async fn get_country_info(&self) -> Result<Vec<CountryInfo>> {
+        self.get("/v1/countries").await
+    }
+
+    /// Check `request` against the onboarding requirements of the
+    /// applicant's country (see
+    /// [`CreateBrokerAccountRequest::applicant_country`]).
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::ValidationErrors`] if the country is not
+    /// supported or a requirement is unmet, or an error fetching the
+    /// requirements.
+    pub async fn validate_broker_account(
+        &self,
+        request: &CreateBrokerAccountRequest,
+    ) -> Result<()> {
+        let country = request.applicant_country();
+        self.get_country_info()
+            .await?
+            .into_iter()
+            .find(|info| info.country == country)
+            .ok_or_else(|| {
+                AlpacaError::ValidationErrors(vec![ValidationError::new(
+                    "identity.country_of_tax_residence",
+                    format!("{country} is not supported for onboarding"),
+                )])
+            })?
+            .validate_request(request)
+    }
+
+    /// Run [`Self::validate_broker_account`] and create the account if
+    /// it passes.
+    pub async fn create_broker_account_checked(
+        &self,
+        request: &CreateBrokerAccountRequest,
+    ) -> Result<BrokerAccount> {
+        self.validate_broker_account(request).await?;
+        self.create_broker_account(request).await
+    }
+
     /// List all broker accounts.
     ///
     /// # Arguments
@@ -1334,52 +2427,269 @@ impl AlpacaHttpClient {
         self.get_with_params("/v1/accounts", params).await
     }
 
-    /// Get a broker account by ID.
+    /// Get a broker account by ID.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    ///
+    /// # Returns
+    /// The broker account
+    pub async fn get_broker_account(&self, account_id: &str) -> Result<BrokerAccount> {
+        self.get(&format!("/v1/accounts/{}", account_id)).await
+    }
+
+    /// Update a broker account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    /// * `request` - Update request with fields to change
+    ///
+    /// # Returns
+    /// The updated broker account
+    pub async fn update_broker_account(
+        &self,
+        account_id: &str,
+        request: &UpdateBrokerAccountRequest,
+    ) -> Result<BrokerAccount> {
+        self.patch(&format!("/v1/accounts/{}", account_id), request)
+            .await
+    }
+
+    /// Close a broker account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID to close
+    pub async fn close_broker_account(&self, account_id: &str) -> Result<()> {
+        self.delete(&format!("/v1/accounts/{}", account_id)).await
+    }
+
+    /// Get the KYC results of a broker account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    ///
+    /// # Returns
+    /// Accepted, rejected and undecided checks, and any additional
+    /// information requested from the applicant
+    pub async fn get_kyc_results(&self, account_id: &str) -> Result<KycResult> {
+        self.get(&format!("/v1/accounts/{}/kyc_results", account_id))
+            .await
+    }
+
+    /// List the status changes of a broker account, oldest first.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    ///
+    /// # Returns
+    /// Each status transition with its reason and KYC results
+    pub async fn list_account_status_history(
+        &self,
+        account_id: &str,
+    ) -> Result<Vec<AccountStatusChange>> {
+        self.get(&format!("/v1/accounts/{}/status_history", account_id))
+            .await
+    }
+
+    /// Get trading account details for a broker account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    ///
+    /// # Returns
+    /// Trading account details, including withdrawable cash and margin fields
+    pub async fn get_broker_trading_account(
+        &self,
+        account_id: &str,
+    ) -> Result<BrokerTradingAccount> {
+        self.get(&format!("/v1/trading/accounts/{}/account", account_id))
+            .await
+    }
+
+    // ========================================================================
+    // Broker Trading Endpoints (on behalf of sub-accounts)
+    // ========================================================================
+
+    /// Get trading configurations for a broker sub-account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    pub async fn get_broker_account_configurations(
+        &self,
+        account_id: &str,
+    ) -> Result<AccountConfigurations> {
+        self.get(&format!(
+            "/v1/trading/accounts/{}/account/configurations",
+            account_id
+        ))
+        .await
+    }
+
+    /// Update trading configurations for a broker sub-account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    /// * `config` - Configurations to apply
+    ///
+    /// # Returns
+    /// The updated configurations
+    pub async fn update_broker_account_configurations(
+        &self,
+        account_id: &str,
+        config: &AccountConfigurations,
+    ) -> Result<AccountConfigurations> {
+        self.patch(
+            &format!("/v1/trading/accounts/{}/account/configurations", account_id),
+            config,
+        )
+        .await
+    }
+
+    /// Submit an order for a broker sub-account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    /// * `order` - Order to submit
+    pub async fn create_broker_order(
+        &self,
+        account_id: &str,
+        order: &CreateOrderRequest,
+    ) -> Result<Order> {
+        self.post(
+            &format!("/v1/trading/accounts/{}/orders", account_id),
+            order,
+        )
+        .await
+    }
+
+    /// List orders of a broker sub-account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    /// * `params` - Query parameters for filtering
+    pub async fn list_broker_orders(
+        &self,
+        account_id: &str,
+        params: &OrderParams,
+    ) -> Result<Vec<Order>> {
+        self.get_with_params(
+            &format!("/v1/trading/accounts/{}/orders", account_id),
+            params,
+        )
+        .await
+    }
+
+    /// Get an order of a broker sub-account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    /// * `order_id` - The order ID
+    pub async fn get_broker_order(&self, account_id: &str, order_id: &Uuid) -> Result<Order> {
+        self.get(&format!(
+            "/v1/trading/accounts/{}/orders/{}",
+            account_id, order_id
+        ))
+        .await
+    }
+
+    /// Replace an order of a broker sub-account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    /// * `order_id` - The order to replace
+    /// * `order` - Fields to change
+    pub async fn replace_broker_order(
+        &self,
+        account_id: &str,
+        order_id: &Uuid,
+        order: &ReplaceOrderRequest,
+    ) -> Result<Order> {
+        self.patch(
+            &format!("/v1/trading/accounts/{}/orders/{}", account_id, order_id),
+            order,
+        )
+        .await
+    }
+
+    /// Cancel an order of a broker sub-account.
     ///
     /// # Arguments
-    /// * `account_id` - The account ID
-    ///
-    /// # Returns
-    /// The broker account
-    pub async fn get_broker_account(&self, account_id: &str) -> Result<BrokerAccount> {
-        self.get(&format!("/v1/accounts/{}", account_id)).await
+    /// * `account_id` - The broker account ID
+    /// * `order_id` - The order to cancel
+    pub async fn cancel_broker_order(&self, account_id: &str, order_id: &Uuid) -> Result<()> {
+        self.delete(&format!(
+            "/v1/trading/accounts/{}/orders/{}",
+            account_id, order_id
+        ))
+        .await
     }
 
-    /// Update a broker account.
+    /// Cancel all open orders of a broker sub-account.
     ///
     /// # Arguments
-    /// * `account_id` - The account ID
-    /// * `request` - Update request with fields to change
+    /// * `account_id` - The broker account ID
+    pub async fn cancel_all_broker_orders(&self, account_id: &str) -> Result<MultiStatus<Uuid>> {
+        let responses: Vec<CancelOrderResponse> = self
+            .delete(&format!("/v1/trading/accounts/{}/orders", account_id))
+            .await?;
+        Ok(responses.into())
+    }
+
+    /// List open positions of a broker sub-account.
     ///
-    /// # Returns
-    /// The updated broker account
-    pub async fn update_broker_account(
-        &self,
-        account_id: &str,
-        request: &UpdateBrokerAccountRequest,
-    ) -> Result<BrokerAccount> {
-        self.patch(&format!("/v1/accounts/{}", account_id), request)
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    pub async fn list_broker_positions(&self, account_id: &str) -> Result<Vec<Position>> {
+        self.get(&format!("/v1/trading/accounts/{}/positions", account_id))
             .await
     }
 
-    /// Close a broker account.
+    /// Get a position of a broker sub-account.
     ///
     /// # Arguments
-    /// * `account_id` - The account ID to close
-    pub async fn close_broker_account(&self, account_id: &str) -> Result<()> {
-        self.delete(&format!("/v1/accounts/{}", account_id)).await
+    /// * `account_id` - The broker account ID
+    /// * `symbol` - Symbol or asset ID
+    pub async fn get_broker_position(&self, account_id: &str, symbol: &str) -> Result<Position> {
+        self.get(&format!(
+            "/v1/trading/accounts/{}/positions/{}",
+            account_id, symbol
+        ))
+        .await
     }
 
-    /// Get trading account details for a broker account.
+    /// Close a position of a broker sub-account.
     ///
     /// # Arguments
     /// * `account_id` - The broker account ID
+    /// * `symbol` - Symbol or asset ID
     ///
     /// # Returns
-    /// Trading account details
-    pub async fn get_broker_trading_account(&self, account_id: &str) -> Result<Account> {
-        self.get(&format!("/v1/accounts/{}/trading", account_id))
-            .await
+    /// The liquidating order
+    pub async fn close_broker_position(&self, account_id: &str, symbol: &str) -> Result<Order> {
+        self.delete(&format!(
+            "/v1/trading/accounts/{}/positions/{}",
+            account_id, symbol
+        ))
+        .await
+    }
+
+    /// Close all positions of a broker sub-account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The broker account ID
+    /// * `cancel_orders` - Whether to cancel open orders first
+    pub async fn close_all_broker_positions(
+        &self,
+        account_id: &str,
+        cancel_orders: bool,
+    ) -> Result<MultiStatus<String>> {
+        let responses: Vec<ClosePositionResponse> = self
+            .delete(&format!(
+                "/v1/trading/accounts/{}/positions?cancel_orders={}",
+                account_id, cancel_orders
+            ))
+            .await?;
+        Ok(responses.into())
     }
 
     // ========================================================================
@@ -1434,6 +2744,31 @@ impl AlpacaHttpClient {
         .await
     }
 
+    /// Upload a W-8BEN form for an international account.
+    ///
+    /// The form is validated locally and sent with the W-8BEN document
+    /// type and sub-type, so no raw [`Document`] payload is needed.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    /// * `form` - Completed W-8BEN form
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::ValidationErrors`] without sending anything if
+    /// required fields are missing.
+    pub async fn upload_w8ben(
+        &self,
+        account_id: &str,
+        form: &W8BenDocument,
+    ) -> Result<DocumentUploadResponse> {
+        form.validate()?;
+        self.post(
+            &format!("/v1/accounts/{}/documents/upload", account_id),
+            &W8BenUpload::from(form.clone()),
+        )
+        .await
+    }
+
     /// List documents for an account.
     ///
     /// # Arguments
@@ -1474,6 +2809,85 @@ impl AlpacaHttpClient {
         ))
         .await
     }
+
+    /// List the statements and trade confirmations of an account.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    /// * `params` - Type and date range filters
+    ///
+    /// # Returns
+    /// List of account documents
+    pub async fn list_account_documents(
+        &self,
+        account_id: &str,
+        params: &DocumentParams,
+    ) -> Result<Vec<AccountDocument>> {
+        self.get_with_params(&format!("/v1/accounts/{}/documents", account_id), params)
+            .await
+    }
+
+    /// Download an account document as PDF bytes.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    /// * `document_id` - The document ID
+    ///
+    /// # Returns
+    /// The PDF file
+    pub async fn download_document(&self, account_id: &str, document_id: &str) -> Result<Vec<u8>> {
+        self.get_bytes(&format!(
+            "/v1/accounts/{}/documents/{}/download",
+            account_id, document_id
+        ))
+        .await
+    }
+
+    /// Download an account document into `writer` chunk by chunk, without
+    /// buffering the whole file.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    /// * `document_id` - The document ID
+    /// * `writer` - Destination, e.g. a [`tokio::fs::File`]
+    ///
+    /// # Returns
+    /// Number of bytes written
+    pub async fn download_document_to<W>(
+        &self,
+        account_id: &str,
+        document_id: &str,
+        writer: &mut W,
+    ) -> Result<u64>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::AsyncWriteExt;
+
+        let mut response = self
+            .get_response(&format!(
+                "/v1/accounts/{}/documents/{}/download",
+                account_id, document_id
+            ))
+            .await?;
+        let mut written = 0u64;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| AlpacaError::Network(e.to_string()))?
+        {
+            writer
+                .write_all(&chunk)
+                .await
+                .map_err(|e| AlpacaError::Network(e.to_string()))?;
+            written += chunk.len() as u64;
+        }
+        writer
+            .flush()
+            .await
+            .map_err(|e| AlpacaError::Network(e.to_string()))?;
+        Ok(written)
+    }
 }
 
 /// Response for document upload.
@@ -1597,6 +3011,46 @@ impl AlpacaHttpClient {
             .await
     }
 
+    /// List transfers across all accounts of the firm.
+    ///
+    /// # Arguments
+    /// * `params` - Status, direction, date and pagination filters
+    ///
+    /// # Returns
+    /// One page of transfers
+    pub async fn list_firm_transfers(
+        &self,
+        params: &ListFirmTransfersParams,
+    ) -> Result<Vec<Transfer>> {
+        self.get_with_params("/v1/transfers", params).await
+    }
+
+    /// List every transfer across all accounts matching `params`, following
+    /// offset pagination from `params.offset` in pages of `params.limit`
+    /// (default 100).
+    ///
+    /// # Returns
+    /// All matching transfers
+    pub async fn list_all_firm_transfers(
+        &self,
+        params: &ListFirmTransfersParams,
+    ) -> Result<Vec<Transfer>> {
+        let page_size = params.limit.unwrap_or(100).max(1);
+        let mut params = params.clone().limit(page_size);
+        let mut offset = params.offset.unwrap_or(0);
+        let mut transfers = Vec::new();
+        loop {
+            params = params.offset(offset);
+            let page = self.list_firm_transfers(&params).await?;
+            let count = page.len() as u32;
+            transfers.extend(page);
+            if count < page_size {
+                return Ok(transfers);
+            }
+            offset += count;
+        }
+    }
+
     /// Get a specific transfer.
     ///
     /// # Arguments
@@ -1715,6 +3169,86 @@ impl AlpacaHttpClient {
         self.post("/v1/journals/batch", request).await
     }
 
+    /// Submit a batch of cash journals, retrying only the entries that
+    /// failed or were not confirmed, and report the outcome of each entry.
+    ///
+    /// Before each retry the journals already booked from the source account
+    /// are searched for the pending entries' idempotency keys, so an entry
+    /// is never booked twice. See [`crate::batch_journals`].
+    ///
+    /// # Errors
+    /// Returns an error only if looking up booked journals fails; errors
+    /// from the batch submission itself are recorded on the entries.
+    pub async fn submit_journal_batch(&self, batch: &JournalBatch) -> Result<BatchJournalReport> {
+        let mut report = BatchJournalReport {
+            from_account: batch.from_account.clone(),
+            entries: batch
+                .entries
+                .iter()
+                .map(JournalEntryReport::pending)
+                .collect(),
+        };
+        let since = Utc::now().date_naive().to_string();
+
+        for attempt in 1..=batch.max_attempts {
+            let mut pending = report.pending_indices();
+            if attempt > 1 {
+                if pending.is_empty() {
+                    break;
+                }
+                tokio::time::sleep(batch.retry_delay).await;
+                let params = ListJournalsParams {
+                    after: Some(since.clone()),
+                    entry_type: Some(JournalEntryType::Jnlc),
+                    from_account: Some(batch.from_account.clone()),
+                    ..Default::default()
+                };
+                let booked: Vec<Journal> = self
+                    .list_journals(&params)
+                    .await?
+                    .into_iter()
+                    .filter(|j| !j.status.is_failed())
+                    .collect();
+                for &i in &pending {
+                    if let Some(journal) = booked.iter().find(|j| batch.entries[i].is_tagged_on(j))
+                    {
+                        report.entries[i].record(Some(journal));
+                    }
+                }
+                pending = report.pending_indices();
+            }
+            if pending.is_empty() {
+                break;
+            }
+
+            let result = self.create_batch_journals(&batch.request(&pending)).await;
+            for &i in &pending {
+                report.entries[i].attempts += 1;
+            }
+            match result {
+                Ok(journals) => {
+                    let matched = match_journals(&batch.entries, &pending, &journals);
+                    for (&i, journal) in pending.iter().zip(matched) {
+                        report.entries[i].record(journal);
+                    }
+                }
+                Err(e) => {
+                    let retryable = e.is_retryable();
+                    for &i in &pending {
+                        report.entries[i].error = Some(e.to_string());
+                        if !retryable {
+                            report.entries[i].outcome = JournalEntryOutcome::Failed;
+                        }
+                    }
+                    if !retryable {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
     /// Delete a journal entry.
     ///
     /// # Arguments
@@ -1892,6 +3426,50 @@ impl AlpacaHttpClient {
         .await
     }
 
+    /// Estimate the network fee of a crypto transfer.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    /// * `params` - Asset, sending and receiving addresses, and amount
+    ///
+    /// # Returns
+    /// The estimated fee
+    pub async fn estimate_crypto_fee(
+        &self,
+        account_id: &str,
+        params: &FeeEstimateParams,
+    ) -> Result<FeeEstimate> {
+        self.get_with_params(
+            &format!("/v1/accounts/{}/wallets/fees/estimate", account_id),
+            params,
+        )
+        .await
+    }
+
+    /// Get the deposit address of an account's wallet for `asset` on
+    /// `chain`, generating one if the account has none yet.
+    ///
+    /// # Arguments
+    /// * `account_id` - The account ID
+    /// * `asset` - The asset symbol (e.g., USDC)
+    /// * `chain` - The chain to deposit on
+    ///
+    /// # Returns
+    /// The wallet holding the deposit address
+    pub async fn get_crypto_deposit_address(
+        &self,
+        account_id: &str,
+        asset: &str,
+        chain: CryptoChain,
+    ) -> Result<BrokerCryptoWallet> {
+        let params = DepositAddressParams {
+            asset: asset.to_string(),
+            network: chain,
+        };
+        self.get_with_params(&format!("/v1/accounts/{}/wallets", account_id), &params)
+            .await
+    }
+
     // ========================================================================
     // Enhanced Crypto Market Data Endpoints
     // ========================================================================
@@ -2001,6 +3579,103 @@ impl AlpacaHttpClient {
     }
 }
 
+// ============================================================================
+// Forex Market Data Endpoints
+// ============================================================================
+
+/// Response for latest forex rates.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LatestFxRatesResponse {
+    /// Map of currency pair (e.g. "EURUSD") to latest rate.
+    pub rates: std::collections::HashMap<String, FxRate>,
+}
+
+impl LatestFxRatesResponse {
+    /// The latest rate of `pair`.
+    #[must_use]
+    pub fn rate(&self, pair: &CurrencyPair) -> Option<&FxRate> {
+        self.rates.get(&pair.symbol())
+    }
+
+    /// Convert `amount` from `from` to `to` at the mid price, using the
+    /// pair quoted either way round.
+    #[must_use]
+    pub fn convert(&self, amount: f64, from: Currency, to: Currency) -> Option<f64> {
+        if from == to {
+            return Some(amount);
+        }
+        let pair = CurrencyPair::new(from, to);
+        self.rate(&pair)
+            .map(|rate| rate.convert(amount))
+            .or_else(|| {
+                self.rate(&pair.inverse())
+                    .map(|rate| rate.convert_inverse(amount))
+            })
+    }
+}
+
+/// Response for historical forex rates.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FxRatesResponse {
+    /// Map of currency pair (e.g. "EURUSD") to rates in time order.
+    pub rates: std::collections::HashMap<String, Vec<FxRate>>,
+    /// Token for the next page, if any.
+    pub next_page_token: Option<String>,
+}
+
+impl AlpacaHttpClient {
+    /// Get the latest forex rates.
+    ///
+    /// # Arguments
+    /// * `pairs` - Currency pairs to fetch
+    ///
+    /// # Returns
+    /// Latest rate for each pair
+    pub async fn get_latest_fx_rates(
+        &self,
+        pairs: &[CurrencyPair],
+    ) -> Result<LatestFxRatesResponse> {
+        let params = FxRatesParams::new(pairs);
+        self.get_with_params("/v1beta1/forex/latest/rates", &params)
+            .await
+    }
+
+    /// Get historical forex rates.
+    ///
+    /// # Arguments
+    /// * `params` - Query parameters
+    ///
+    /// # Returns
+    /// One page of rates for each pair
+    pub async fn get_fx_rates(&self, params: &FxRatesParams) -> Result<FxRatesResponse> {
+        self.get_with_params("/v1beta1/forex/rates", params).await
+    }
+
+    /// Get every historical forex rate matching `params`, following
+    /// `next_page_token` from `params.page_token`.
+    ///
+    /// # Returns
+    /// All matching rates for each pair
+    pub async fn get_all_fx_rates(
+        &self,
+        params: &FxRatesParams,
+    ) -> Result<std::collections::HashMap<String, Vec<FxRate>>> {
+        let mut params = params.clone();
+        let mut rates: std::collections::HashMap<String, Vec<FxRate>> =
+            std::collections::HashMap::new();
+        loop {
+            let page = self.get_fx_rates(&params).await?;
+            for (pair, page_rates) in page.rates {
+                rates.entry(pair).or_default().extend(page_rates);
+            }
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => params.page_token = Some(token),
+                _ => return Ok(rates),
+            }
+        }
+    }
+}
+
 // ============================================================================
 // News API Endpoints
 // ============================================================================
@@ -2024,7 +3699,7 @@ impl AlpacaHttpClient {
     /// List of enhanced news articles with pagination
     pub async fn get_enhanced_news(
         &self,
-        params: &alpaca_base::NewsParams,
+        params: &alpaca_base::types::NewsParams,
     ) -> Result<EnhancedNewsResponse> {
         self.get_with_params("/v1beta1/news", params).await
     }
@@ -2042,7 +3717,9 @@ impl AlpacaHttpClient {
         symbols: &str,
         limit: u32,
     ) -> Result<EnhancedNewsResponse> {
-        let params = alpaca_base::NewsParams::new().symbols(symbols).limit(limit);
+        let params = alpaca_base::types::NewsParams::new()
+            .symbols(symbols)
+            .limit(limit);
         self.get_enhanced_news(&params).await
     }
 
@@ -2054,7 +3731,9 @@ impl AlpacaHttpClient {
     /// # Returns
     /// List of latest enhanced news articles
     pub async fn get_latest_enhanced_news(&self, limit: u32) -> Result<EnhancedNewsResponse> {
-        let params = alpaca_base::NewsParams::new().sort_desc().limit(limit);
+        let params = alpaca_base::types::NewsParams::new()
+            .sort_desc()
+            .limit(limit);
         self.get_enhanced_news(&params).await
     }
 }
@@ -2219,15 +3898,15 @@ impl AlpacaHttpClient {
         self.get_with_params("/v2/assets", params).await
     }
 
-    /// Get enhanced asset by symbol or ID.
+    /// Get enhanced asset by symbol, asset ID, CUSIP or composite FIGI.
     ///
     /// # Arguments
-    /// * `symbol_or_id` - Symbol or asset ID
+    /// * `key` - Symbol (a plain `&str`), asset ID, CUSIP or composite FIGI
     ///
     /// # Returns
     /// Enhanced asset details
-    pub async fn get_enhanced_asset(&self, symbol_or_id: &str) -> Result<EnhancedAsset> {
-        self.get(&format!("/v2/assets/{}", symbol_or_id)).await
+    pub async fn get_enhanced_asset(&self, key: impl Into<SymbolOrId>) -> Result<EnhancedAsset> {
+        self.get(&format!("/v2/assets/{}", key.into())).await
     }
 
     /// Get options contracts for an underlying symbol.
@@ -2311,6 +3990,46 @@ impl AlpacaHttpClient {
             .await
     }
 
+    /// List option assignments, exercises and expirations as typed events.
+    ///
+    /// # Arguments
+    /// * `params` - Query parameters for filtering (`activity_types` is overridden)
+    ///
+    /// # Returns
+    /// Decoded option lifecycle events
+    pub async fn list_option_lifecycle_events(
+        &self,
+        params: &ListActivitiesParams,
+    ) -> Result<Vec<OptionLifecycleEvent>> {
+        let mut params = params.clone();
+        params.activity_types = Some("OPASN,OPXRC,OPEXP".to_string());
+        let activities = self.list_activities(&params).await?;
+        OptionLifecycleEvent::collect(&activities)
+    }
+
+    /// List option exercises and assignments (`OPXRC`, `OPASN`), e.g. for
+    /// option sellers tracking assignments. Use
+    /// [`OptionLifecycleEvent::detail`] for the contract, quantity and
+    /// resulting shares of each.
+    ///
+    /// # Arguments
+    /// * `params` - Query parameters for filtering (`activity_types` is overridden)
+    ///
+    /// # Returns
+    /// Decoded exercise and assignment events
+    pub async fn list_option_exercise_events(
+        &self,
+        params: &ListActivitiesParams,
+    ) -> Result<Vec<OptionLifecycleEvent>> {
+        let mut params = params.clone();
+        params.activity_types = Some("OPASN,OPXRC".to_string());
+        let activities = self.list_activities(&params).await?;
+        Ok(OptionLifecycleEvent::collect(&activities)?
+            .into_iter()
+            .filter(OptionLifecycleEvent::is_exercise_or_assignment)
+            .collect())
+    }
+
     /// List all broker accounts activities.
     ///
     /// # Arguments
@@ -2412,6 +4131,88 @@ impl AlpacaHttpClient {
     pub async fn list_rebalance_runs(&self) -> Result<Vec<RebalanceRun>> {
         self.get("/v1/rebalancing/runs").await
     }
+
+    /// List rebalance runs matching `params`.
+    ///
+    /// # Arguments
+    /// * `params` - Account, type and pagination filters
+    ///
+    /// # Returns
+    /// List of rebalance runs
+    pub async fn list_rebalance_runs_with_params(
+        &self,
+        params: &ListRebalanceRunsParams,
+    ) -> Result<Vec<RebalanceRun>> {
+        self.get_with_params("/v1/rebalancing/runs", params).await
+    }
+
+    /// Get the full details of a rebalance run, including its orders,
+    /// skipped orders and timestamps; see
+    /// [`RebalanceRunDetails::audit_trail`].
+    ///
+    /// # Arguments
+    /// * `run_id` - Run ID
+    ///
+    /// # Returns
+    /// Rebalance run details
+    pub async fn get_rebalance_run_details(&self, run_id: &Uuid) -> Result<RebalanceRunDetails> {
+        self.get(&format!("/v1/rebalancing/runs/{}", run_id)).await
+    }
+
+    /// Allocation history of an account: the weights applied by each of
+    /// its completed rebalance runs, oldest first.
+    ///
+    /// # Arguments
+    /// * `account_id` - Broker account ID
+    ///
+    /// # Returns
+    /// Allocations in completion order
+    pub async fn list_account_allocations(
+        &self,
+        account_id: &str,
+    ) -> Result<Vec<AccountAllocation>> {
+        let runs = self
+            .list_rebalance_runs_with_params(&ListRebalanceRunsParams::new().account_id(account_id))
+            .await?;
+        let mut allocations = Vec::new();
+        for run in runs {
+            if run.status != RebalanceStatus::Completed {
+                continue;
+            }
+            let details = self.get_rebalance_run_details(&run.id).await?;
+            allocations.push(AccountAllocation {
+                account_id: details.account_id,
+                portfolio_id: details.portfolio_id,
+                run_id: details.id,
+                as_of: details.completed_at.unwrap_or(details.created_at),
+                weights: details.weights,
+            });
+        }
+        allocations.sort_by_key(|allocation| allocation.as_of);
+        Ok(allocations)
+    }
+
+    /// Drift of an account's holdings from a portfolio's target weights.
+    ///
+    /// # Arguments
+    /// * `account_id` - Broker account ID
+    /// * `portfolio_id` - Portfolio ID
+    ///
+    /// # Returns
+    /// Per-symbol drift report
+    pub async fn get_drift_report(
+        &self,
+        account_id: &str,
+        portfolio_id: &str,
+    ) -> Result<DriftReport> {
+        let portfolio = self.get_rebalance_portfolio(portfolio_id).await?;
+        let positions = self.list_broker_positions(account_id).await?;
+        let account = self.get_broker_trading_account(account_id).await?;
+        let equity = parse_decimal(&account.equity)?;
+        Ok(DriftReport::compute(
+            account_id, &portfolio, &positions, equity,
+        ))
+    }
 }
 
 // ============================================================================
@@ -2540,6 +4341,22 @@ impl AlpacaHttpClient {
     }
 }
 
+/// Whether a bars timeframe such as `5Min` or `1Hour` is intraday.
+fn is_intraday(timeframe: &str) -> bool {
+    let unit = timeframe.trim_start_matches(|c: char| c.is_ascii_digit());
+    matches!(unit, "Min" | "T" | "Hour" | "H")
+}
+
+/// Keep the bars that start within a regular session of `calendar`.
+fn retain_regular_hours(bars: &mut Vec<Bar>, calendar: &[Calendar]) -> Result<()> {
+    let sessions = calendar
+        .iter()
+        .map(SessionWindow::from_calendar)
+        .collect::<Result<Vec<_>>>()?;
+    bars.retain(|bar| sessions.iter().any(|s| s.contains(bar.timestamp)));
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2606,6 +4423,56 @@ mod tests {
         assert_eq!(order.stop_loss.unwrap().stop_price, "140.00");
     }
 
+    #[test]
+    fn test_create_order_request_validate() {
+        assert!(
+            CreateOrderRequest::market("AAPL", OrderSide::Buy, "10")
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            CreateOrderRequest::market("AAPL", OrderSide::Buy, "0")
+                .validate()
+                .is_err()
+        );
+        assert!(
+            CreateOrderRequest::market("", OrderSide::Buy, "1")
+                .validate()
+                .is_err()
+        );
+
+        let mut limit = CreateOrderRequest::limit("AAPL", OrderSide::Buy, "1", "150.00");
+        assert!(limit.validate().is_ok());
+        limit.limit_price = None;
+        assert!(limit.validate().is_err());
+
+        let bracket = |side, limit: &str, tp: &str, sl: &str| {
+            CreateOrderRequest::bracket(
+                "AAPL",
+                side,
+                "10",
+                OrderType::Limit,
+                TakeProfit::new(tp),
+                StopLoss::new(sl),
+            )
+            .with_limit_price(limit)
+            .validate()
+        };
+        assert!(bracket(OrderSide::Buy, "150", "160", "140").is_ok());
+        assert!(bracket(OrderSide::Buy, "150", "140", "160").is_err());
+        assert!(bracket(OrderSide::Buy, "165", "160", "140").is_err());
+        assert!(bracket(OrderSide::Sell, "150", "140", "160").is_ok());
+
+        let oco = CreateOrderRequest::oco(
+            "AAPL",
+            OrderSide::Sell,
+            "10",
+            TakeProfit::new("160"),
+            StopLoss::new("140"),
+        );
+        assert!(oco.validate().is_ok());
+    }
+
     #[test]
     fn test_create_order_request_oco() {
         let tp = TakeProfit::new("160.00");
@@ -2676,6 +4543,39 @@ mod tests {
         assert_eq!(params.direction, Some(SortDirection::Desc));
     }
 
+    #[test]
+    fn test_cancel_orders_filter_matches() {
+        use alpaca_base::test_utils::fixtures::sample_order;
+
+        let mut order = sample_order("AAPL", OrderSide::Buy, "10");
+        order.order_type = OrderType::Limit;
+        order.client_order_id = "grid-7".to_string();
+
+        assert!(CancelOrdersFilter::new().matches(&order));
+        assert!(CancelOrdersFilter::new().symbol("AAPL").matches(&order));
+        assert!(!CancelOrdersFilter::new().symbol("MSFT").matches(&order));
+        assert!(
+            CancelOrdersFilter::new()
+                .symbol("MSFT")
+                .symbol("AAPL")
+                .side(OrderSide::Buy)
+                .order_type(OrderType::Limit)
+                .client_order_id_prefix("grid-")
+                .matches(&order)
+        );
+        assert!(
+            !CancelOrdersFilter::new()
+                .side(OrderSide::Sell)
+                .matches(&order)
+        );
+        assert!(
+            !CancelOrdersFilter::new()
+                .created_after(order.created_at + chrono::Duration::seconds(1))
+                .matches(&order)
+        );
+        assert_eq!(CancelOrdersFilter::new().concurrency(0).concurrency, 1);
+    }
+
     #[test]
     fn test_create_order_request_serialization() {
         let order = CreateOrderRequest::limit("AAPL", OrderSide::Buy, "10", "150.00")
@@ -2701,4 +4601,55 @@ mod tests {
         assert!(json.contains("\"take_profit\""));
         assert!(json.contains("\"stop_loss\""));
     }
+
+    #[test]
+    fn test_latest_fx_rates_convert() {
+        let response: LatestFxRatesResponse = serde_json::from_str(
+            r#"{"rates":{"EURUSD":{"t":"2024-06-03T14:00:00Z","bp":1.0799,"mp":1.08,"ap":1.0801}}}"#,
+        )
+        .unwrap();
+        let eur_usd = response
+            .convert(100.0, Currency::Eur, Currency::Usd)
+            .unwrap();
+        assert!((eur_usd - 108.0).abs() < 1e-9);
+        let usd_eur = response
+            .convert(108.0, Currency::Usd, Currency::Eur)
+            .unwrap();
+        assert!((usd_eur - 100.0).abs() < 1e-9);
+        assert_eq!(response.convert(1.0, Currency::Usd, Currency::Jpy), None);
+    }
+
+    #[test]
+    fn test_retain_regular_hours_handles_half_days() {
+        use alpaca_base::test_utils::fixtures::sample_bar;
+        let day = |date: &str, close: &str| Calendar {
+            date: date.to_string(),
+            open: "09:30".to_string(),
+            close: close.to_string(),
+            session_open: "0400".to_string(),
+            session_close: "2000".to_string(),
+        };
+        // 2024-11-29 is a half day closing at 13:00 ET (18:00 UTC).
+        let calendar = [day("2024-11-27", "16:00"), day("2024-11-29", "13:00")];
+        let at = |time: &str| sample_bar(time.parse().unwrap());
+        let mut bars = vec![
+            at("2024-11-27T14:00:00Z"), // 09:00 ET pre-market
+            at("2024-11-27T14:30:00Z"), // open
+            at("2024-11-27T20:59:00Z"), // last minute
+            at("2024-11-27T21:00:00Z"), // after hours
+            at("2024-11-29T17:59:00Z"), // last minute of the half day
+            at("2024-11-29T18:00:00Z"), // after the early close
+        ];
+        retain_regular_hours(&mut bars, &calendar).unwrap();
+        let kept: Vec<String> = bars.iter().map(|b| b.timestamp.to_rfc3339()).collect();
+        assert_eq!(
+            kept,
+            [
+                "2024-11-27T14:30:00+00:00",
+                "2024-11-27T20:59:00+00:00",
+                "2024-11-29T17:59:00+00:00"
+            ]
+        );
+        assert!(is_intraday("5Min") && is_intraday("1Hour") && !is_intraday("1Day"));
+    }
 }
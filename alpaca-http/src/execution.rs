@@ -0,0 +1,643 @@
+//! Time- and volume-sliced execution of large orders.
+//!
+//! An [`ExecutionAlgo`] splits a [`ParentOrder`] into child
+//! [`CreateOrderRequest`]s according to an [`AlgoStrategy`]:
+//!
+//! - **TWAP** releases equal slices at evenly spaced times over the
+//!   parent's duration;
+//! - **VWAP** follows the market, keeping the quantity worked at the
+//!   parent's participation rate of the volume reported from streamed
+//!   trades;
+//! - **Iceberg** shows one child of the display quantity at a time and
+//!   sends the next once it is done.
+//!
+//! Quantity that a child leaves unfilled (cancelled, expired) is sliced
+//! again, so the parent keeps working until it is filled, its deadline
+//! passes or it is cancelled. Fills of every child are aggregated into one
+//! [`ExecutionReport`] with the volume-weighted average price.
+//!
+//! [`AlpacaHttpClient::execute_algo`] drives an algo against the REST API;
+//! an [`AlgoControl`] shared with it pauses, resumes or cancels the run,
+//! feeds market volume and reports [`AlgoProgress`].
+//!
+//! [`AlpacaHttpClient::execute_algo`]: crate::AlpacaHttpClient::execute_algo
+
+use crate::endpoints::CreateOrderRequest;
+use crate::protection::is_closed;
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{Order, OrderSide, TimeInForce},
+    utils::{QTY_EPSILON, format_price, format_qty},
+};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// How an [`ExecutionAlgo`] slices its parent order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlgoStrategy {
+    /// `slices` equal children spread evenly over the parent's duration.
+    Twap {
+        /// Number of slices.
+        slices: u32,
+    },
+    /// Children sized to the parent's participation limit of the market
+    /// volume fed through [`ExecutionAlgo::on_market_trade`].
+    Vwap,
+    /// One visible child of `display_qty` at a time.
+    Iceberg {
+        /// Quantity shown per child.
+        display_qty: f64,
+    },
+}
+
+/// The order an [`ExecutionAlgo`] works.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParentOrder {
+    /// Symbol to trade.
+    pub symbol: String,
+    /// Buy or sell.
+    pub side: OrderSide,
+    /// Total quantity.
+    pub qty: f64,
+    /// Time to work the order; required for TWAP, a deadline otherwise.
+    pub duration: Option<Duration>,
+    /// Maximum fraction of market volume to trade, e.g. `0.1` for 10%;
+    /// required for VWAP, where it is the target rate.
+    pub participation_limit: Option<f64>,
+    /// Limit price of every child; children are market orders without it.
+    pub limit_price: Option<f64>,
+    /// Time in force of the children.
+    pub time_in_force: TimeInForce,
+    /// Children are rounded down to multiples of this quantity, except the
+    /// last one.
+    pub lot_size: f64,
+}
+
+impl ParentOrder {
+    /// Create a parent order for `qty` of `symbol` with whole-share
+    /// children, sent as day market orders.
+    #[must_use]
+    pub fn new(symbol: &str, side: OrderSide, qty: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            qty,
+            duration: None,
+            participation_limit: None,
+            limit_price: None,
+            time_in_force: TimeInForce::Day,
+            lot_size: 1.0,
+        }
+    }
+
+    /// Set the time to work the order.
+    #[must_use]
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Set the maximum fraction of market volume to trade.
+    #[must_use]
+    pub fn participation_limit(mut self, limit: f64) -> Self {
+        self.participation_limit = Some(limit);
+        self
+    }
+
+    /// Send children as limit orders at `price`.
+    #[must_use]
+    pub fn limit_price(mut self, price: f64) -> Self {
+        self.limit_price = Some(price);
+        self
+    }
+
+    /// Set the time in force of the children.
+    #[must_use]
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    /// Set the lot size children are rounded to, e.g. `0.0001` for crypto.
+    #[must_use]
+    pub fn lot_size(mut self, lot_size: f64) -> Self {
+        self.lot_size = lot_size;
+        self
+    }
+}
+
+/// State of an algo run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlgoStatus {
+    /// Slicing and submitting children.
+    Running,
+    /// Paused: working children stay open, no new ones are sent.
+    Paused,
+    /// The parent quantity is filled.
+    Completed,
+    /// Cancelled through its [`AlgoControl`].
+    Cancelled,
+    /// The deadline passed before the parent was filled.
+    Expired,
+    /// A child could not be submitted or queried.
+    Failed,
+}
+
+impl AlgoStatus {
+    /// Whether the run has ended.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, Self::Running | Self::Paused)
+    }
+}
+
+/// Snapshot of an algo run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlgoProgress {
+    /// Run state.
+    pub status: AlgoStatus,
+    /// Quantity filled across children.
+    pub filled_qty: f64,
+    /// Unfilled quantity of open children.
+    pub working_qty: f64,
+    /// Quantity not yet filled.
+    pub remaining_qty: f64,
+    /// Volume-weighted average fill price.
+    pub avg_fill_price: Option<f64>,
+    /// Children submitted so far.
+    pub children: usize,
+    /// Market volume reported so far.
+    pub market_volume: f64,
+}
+
+/// The aggregated outcome of an algo run, as if it were one order.
+#[derive(Debug)]
+pub struct ExecutionReport {
+    /// Symbol traded.
+    pub symbol: String,
+    /// Buy or sell.
+    pub side: OrderSide,
+    /// Parent quantity.
+    pub requested_qty: f64,
+    /// Quantity filled across children.
+    pub filled_qty: f64,
+    /// Volume-weighted average fill price.
+    pub avg_fill_price: Option<f64>,
+    /// IDs of the children, in submission order.
+    pub child_orders: Vec<Uuid>,
+    /// How the run ended.
+    pub status: AlgoStatus,
+    /// The error that ended a [`AlgoStatus::Failed`] run.
+    pub error: Option<AlpacaError>,
+    /// Start of the run.
+    pub started_at: DateTime<Utc>,
+    /// End of the run.
+    pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct ChildOrder {
+    id: Uuid,
+    qty: f64,
+    filled_qty: f64,
+    avg_price: f64,
+    open: bool,
+}
+
+impl ChildOrder {
+    fn working_qty(&self) -> f64 {
+        if self.open {
+            (self.qty - self.filled_qty).max(0.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Slicing and fill aggregation for one [`ParentOrder`].
+///
+/// The algo does no I/O: ask it for the [`Self::next_child`] to submit,
+/// and feed back submitted children, their updates and market volume.
+/// [`AlpacaHttpClient::execute_algo`] does this against the REST API.
+///
+/// [`AlpacaHttpClient::execute_algo`]: crate::AlpacaHttpClient::execute_algo
+#[derive(Debug, Clone)]
+pub struct ExecutionAlgo {
+    parent: ParentOrder,
+    strategy: AlgoStrategy,
+    started_at: DateTime<Utc>,
+    market_volume: f64,
+    children: Vec<ChildOrder>,
+}
+
+impl ExecutionAlgo {
+    /// Start working `parent` with `strategy` at `now`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if the quantity, lot size,
+    /// participation limit or display quantity is not positive, TWAP has
+    /// no duration or slices, or VWAP has no participation limit.
+    pub fn new(parent: ParentOrder, strategy: AlgoStrategy, now: DateTime<Utc>) -> Result<Self> {
+        let invalid = |reason: &str| Err(AlpacaError::Validation(reason.to_string()));
+        if parent.qty <= 0.0 || parent.lot_size <= 0.0 {
+            return invalid("parent quantity and lot size must be positive");
+        }
+        if parent
+            .participation_limit
+            .is_some_and(|limit| limit <= 0.0 || limit > 1.0)
+        {
+            return invalid("participation limit must be in (0, 1]");
+        }
+        match strategy {
+            AlgoStrategy::Twap { slices } => {
+                if slices == 0 || parent.duration.is_none_or(|d| d <= Duration::zero()) {
+                    return invalid("TWAP needs a positive duration and at least one slice");
+                }
+            }
+            AlgoStrategy::Vwap => {
+                if parent.participation_limit.is_none() {
+                    return invalid("VWAP needs a participation limit");
+                }
+            }
+            AlgoStrategy::Iceberg { display_qty } => {
+                if display_qty <= 0.0 {
+                    return invalid("iceberg display quantity must be positive");
+                }
+            }
+        }
+        Ok(Self {
+            parent,
+            strategy,
+            started_at: now,
+            market_volume: 0.0,
+            children: Vec::new(),
+        })
+    }
+
+    /// The parent order.
+    #[must_use]
+    pub fn parent(&self) -> &ParentOrder {
+        &self.parent
+    }
+
+    /// Record `size` traded in the market, e.g. from streamed trades.
+    pub fn on_market_trade(&mut self, size: f64) {
+        self.market_volume += size.max(0.0);
+    }
+
+    /// Time after which no new children are sent.
+    #[must_use]
+    pub fn deadline(&self) -> Option<DateTime<Utc>> {
+        self.parent.duration.map(|d| self.started_at + d)
+    }
+
+    /// Whether the deadline has passed at `now`.
+    #[must_use]
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.deadline().is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Quantity filled across children.
+    #[must_use]
+    pub fn filled_qty(&self) -> f64 {
+        self.children.iter().map(|c| c.filled_qty).sum()
+    }
+
+    /// Unfilled quantity of open children.
+    #[must_use]
+    pub fn working_qty(&self) -> f64 {
+        self.children.iter().map(ChildOrder::working_qty).sum()
+    }
+
+    /// Whether the parent quantity is filled.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.filled_qty() >= self.parent.qty - QTY_EPSILON
+    }
+
+    /// Volume-weighted average fill price across children.
+    #[must_use]
+    pub fn avg_fill_price(&self) -> Option<f64> {
+        let filled = self.filled_qty();
+        (filled > 0.0).then(|| {
+            self.children
+                .iter()
+                .map(|c| c.filled_qty * c.avg_price)
+                .sum::<f64>()
+                / filled
+        })
+    }
+
+    /// IDs of children that are still open.
+    #[must_use]
+    pub fn open_children(&self) -> Vec<Uuid> {
+        self.children
+            .iter()
+            .filter(|c| c.open)
+            .map(|c| c.id)
+            .collect()
+    }
+
+    /// Quantity the schedule wants filled or working at `now`.
+    fn target_qty(&self, now: DateTime<Utc>) -> f64 {
+        let committed = self.filled_qty() + self.working_qty();
+        let mut target = match self.strategy {
+            AlgoStrategy::Twap { slices } => {
+                let interval = self.parent.duration.unwrap_or_default()
+                    / i32::try_from(slices).unwrap_or(i32::MAX);
+                let elapsed = (now - self.started_at).num_milliseconds();
+                let due = (elapsed / interval.num_milliseconds().max(1) + 1).min(slices.into());
+                self.parent.qty * due as f64 / f64::from(slices)
+            }
+            AlgoStrategy::Vwap => {
+                self.market_volume * self.parent.participation_limit.unwrap_or_default()
+            }
+            AlgoStrategy::Iceberg { display_qty } => {
+                if self.working_qty() > QTY_EPSILON {
+                    committed
+                } else {
+                    committed + display_qty
+                }
+            }
+        };
+        if let Some(limit) = self.parent.participation_limit {
+            target = target.min(self.market_volume * limit);
+        }
+        target.min(self.parent.qty)
+    }
+
+    /// The child to submit at `now`, if the schedule calls for one.
+    #[must_use]
+    pub fn next_child(&self, now: DateTime<Utc>) -> Option<CreateOrderRequest> {
+        if self.is_expired(now) {
+            return None;
+        }
+        let committed = self.filled_qty() + self.working_qty();
+        let remaining = self.parent.qty - committed;
+        let wanted = self.target_qty(now) - committed;
+        let qty = if wanted >= remaining - QTY_EPSILON {
+            remaining
+        } else {
+            (wanted / self.parent.lot_size).floor() * self.parent.lot_size
+        };
+        if qty <= QTY_EPSILON {
+            return None;
+        }
+        let qty = format_qty(qty);
+        let mut request = match self.parent.limit_price {
+            Some(price) => CreateOrderRequest::limit(
+                &self.parent.symbol,
+                self.parent.side.clone(),
+                qty,
+                format_price(price),
+            ),
+            None => CreateOrderRequest::market(&self.parent.symbol, self.parent.side.clone(), qty),
+        };
+        request.time_in_force = self.parent.time_in_force.clone();
+        Some(request)
+    }
+
+    /// Record a submitted child.
+    pub fn on_child_submitted(&mut self, order: &Order) {
+        let qty = order
+            .qty
+            .as_deref()
+            .and_then(|q| q.parse().ok())
+            .unwrap_or_default();
+        self.children.push(ChildOrder {
+            id: order.id,
+            qty,
+            filled_qty: 0.0,
+            avg_price: 0.0,
+            open: true,
+        });
+        self.on_child_update(order);
+    }
+
+    /// Apply the latest state of a child. Orders that are not children of
+    /// this algo are ignored.
+    pub fn on_child_update(&mut self, order: &Order) {
+        let Some(child) = self.children.iter_mut().find(|c| c.id == order.id) else {
+            return;
+        };
+        child.filled_qty = order.filled_qty.parse().unwrap_or(child.filled_qty);
+        child.avg_price = order
+            .filled_avg_price
+            .as_deref()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(child.avg_price);
+        child.open = !is_closed(&order.status);
+    }
+
+    /// Snapshot of the run with `status`.
+    #[must_use]
+    pub fn progress(&self, status: AlgoStatus) -> AlgoProgress {
+        let filled_qty = self.filled_qty();
+        AlgoProgress {
+            status,
+            filled_qty,
+            working_qty: self.working_qty(),
+            remaining_qty: (self.parent.qty - filled_qty).max(0.0),
+            avg_fill_price: self.avg_fill_price(),
+            children: self.children.len(),
+            market_volume: self.market_volume,
+        }
+    }
+
+    /// The aggregated report of a run that ended with `status` at `now`.
+    #[must_use]
+    pub fn report(
+        &self,
+        status: AlgoStatus,
+        error: Option<AlpacaError>,
+        now: DateTime<Utc>,
+    ) -> ExecutionReport {
+        ExecutionReport {
+            symbol: self.parent.symbol.clone(),
+            side: self.parent.side.clone(),
+            requested_qty: self.parent.qty,
+            filled_qty: self.filled_qty(),
+            avg_fill_price: self.avg_fill_price(),
+            child_orders: self.children.iter().map(|c| c.id).collect(),
+            status,
+            error,
+            started_at: self.started_at,
+            finished_at: now,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ControlState {
+    paused: bool,
+    cancelled: bool,
+    market_volume: f64,
+    progress: Option<AlgoProgress>,
+}
+
+/// Handle to a running [`AlpacaHttpClient::execute_algo`]; clones share
+/// the same run.
+///
+/// [`AlpacaHttpClient::execute_algo`]: crate::AlpacaHttpClient::execute_algo
+#[derive(Debug, Clone, Default)]
+pub struct AlgoControl {
+    state: Arc<Mutex<ControlState>>,
+}
+
+impl AlgoControl {
+    /// Create a control for one run.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, ControlState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stop sending new children; working children stay open.
+    pub fn pause(&self) {
+        self.state().paused = true;
+    }
+
+    /// Resume sending children after [`Self::pause`].
+    pub fn resume(&self) {
+        self.state().paused = false;
+    }
+
+    /// End the run, cancelling its open children.
+    pub fn cancel(&self) {
+        self.state().cancelled = true;
+    }
+
+    /// Whether the run is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.state().paused
+    }
+
+    /// Whether the run was cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.state().cancelled
+    }
+
+    /// Report `size` traded in the market, e.g. from a trade stream; drives
+    /// VWAP and the participation limit.
+    pub fn on_market_trade(&self, size: f64) {
+        self.state().market_volume += size.max(0.0);
+    }
+
+    /// The latest progress of the run, once it has started.
+    #[must_use]
+    pub fn progress(&self) -> Option<AlgoProgress> {
+        self.state().progress.clone()
+    }
+
+    /// Take the market volume reported since the last call.
+    pub(crate) fn take_market_volume(&self) -> f64 {
+        std::mem::take(&mut self.state().market_volume)
+    }
+
+    pub(crate) fn publish(&self, progress: AlgoProgress) {
+        self.state().progress = Some(progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_order;
+    use alpaca_base::types::{OrderStatus, OrderType};
+    use chrono::TimeZone;
+
+    fn fill(request: &CreateOrderRequest, filled: &str, price: &str) -> Order {
+        let mut order = sample_order(
+            &request.symbol,
+            request.side.clone(),
+            request.qty.as_deref().unwrap(),
+        );
+        order.id = Uuid::new_v4();
+        order.filled_qty = filled.to_string();
+        order.filled_avg_price = Some(price.to_string());
+        order.status = if order.qty.as_deref() == Some(filled) {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::Canceled
+        };
+        order
+    }
+
+    #[test]
+    fn test_execution_algo_slicing_and_aggregation() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 3, 14, 0, 0).unwrap();
+        let at = |minutes| start + Duration::minutes(minutes);
+
+        let parent = ParentOrder::new("AAPL", OrderSide::Buy, 100.0).duration(Duration::minutes(4));
+        let mut twap = ExecutionAlgo::new(parent, AlgoStrategy::Twap { slices: 4 }, start).unwrap();
+        let first = twap.next_child(at(0)).unwrap();
+        assert_eq!(first.qty.as_deref(), Some("25"));
+        assert_eq!(first.order_type, OrderType::Market);
+        twap.on_child_submitted(&fill(&first, "25", "100.00"));
+        assert!(twap.next_child(at(0)).is_none());
+
+        // A partly filled, cancelled child's remainder is sliced again.
+        let second = twap.next_child(at(1)).unwrap();
+        assert_eq!(second.qty.as_deref(), Some("25"));
+        twap.on_child_submitted(&fill(&second, "15", "102.00"));
+        let third = twap.next_child(at(2)).unwrap();
+        assert_eq!(third.qty.as_deref(), Some("35"));
+        twap.on_child_submitted(&fill(&third, "35", "101.00"));
+        let progress = twap.progress(AlgoStatus::Running);
+        assert_eq!(progress.filled_qty, 75.0);
+        assert_eq!(progress.children, 3);
+        let avg = progress.avg_fill_price.unwrap();
+        assert!((avg - (2500.0 + 1530.0 + 3535.0) / 75.0).abs() < 1e-9);
+        assert!(twap.next_child(at(4)).is_none());
+        assert!(twap.is_expired(at(4)));
+
+        // VWAP keeps to 10% of reported volume, in whole lots.
+        let parent = ParentOrder::new("AAPL", OrderSide::Sell, 50.0)
+            .participation_limit(0.1)
+            .limit_price(190.5);
+        let mut vwap = ExecutionAlgo::new(parent, AlgoStrategy::Vwap, start).unwrap();
+        assert!(vwap.next_child(at(0)).is_none());
+        vwap.on_market_trade(125.0);
+        let child = vwap.next_child(at(0)).unwrap();
+        assert_eq!(child.qty.as_deref(), Some("12"));
+        assert_eq!(child.limit_price.as_deref(), Some("190.50"));
+        assert!(
+            ExecutionAlgo::new(
+                ParentOrder::new("AAPL", OrderSide::Buy, 1.0),
+                AlgoStrategy::Vwap,
+                start
+            )
+            .is_err()
+        );
+
+        // An iceberg shows one child at a time.
+        let parent = ParentOrder::new("AAPL", OrderSide::Buy, 25.0).limit_price(100.0);
+        let mut iceberg =
+            ExecutionAlgo::new(parent, AlgoStrategy::Iceberg { display_qty: 10.0 }, start).unwrap();
+        let shown = iceberg.next_child(at(0)).unwrap();
+        let mut working = fill(&shown, "0", "0");
+        working.status = OrderStatus::New;
+        iceberg.on_child_submitted(&working);
+        assert!(iceberg.next_child(at(1)).is_none());
+        working.filled_qty = "10".to_string();
+        working.status = OrderStatus::Filled;
+        iceberg.on_child_update(&working);
+        assert_eq!(
+            iceberg.next_child(at(1)).unwrap().qty.as_deref(),
+            Some("10")
+        );
+
+        let control = AlgoControl::new();
+        control.on_market_trade(30.0);
+        control.pause();
+        assert!(control.clone().is_paused());
+        assert_eq!(control.take_market_volume(), 30.0);
+        assert_eq!(control.take_market_volume(), 0.0);
+    }
+}
@@ -0,0 +1,487 @@
+//! Good-till-date order validation and expiry tracking.
+//!
+//! A GTD order is canceled by Alpaca at the close of its `gtd_date`, and
+//! nothing on the [`Order`] says when that is. [`GtdTracker`] remembers the
+//! date of the orders it submits or is told about and, from a background
+//! loop, notifies the caller when one is about to expire or rolls it: the
+//! open order is canceled and its unfilled quantity resubmitted with a
+//! later `gtd_date`.
+//!
+//! ```rust,ignore
+//! let tracker = GtdTracker::new(client, GtdTrackerConfig::default().roll(5));
+//! tracker.submit(&CreateOrderRequest::limit("AAPL", OrderSide::Buy, "10", "150").gtd_date(date)).await?;
+//! tracker.run(&cancel, |event| async move { println!("{event:?}") }).await;
+//! ```
+//!
+//! [`AlpacaHttpClient::validate_gtd_date`] checks that a date is a trading
+//! day that has not passed, using the calendar endpoint;
+//! [`AlpacaHttpClient::preflight_order`] runs it for GTD orders.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::{CalendarParams, CreateOrderRequest};
+use crate::protection::is_closed;
+use crate::scheduler::TradingSession;
+use alpaca_base::{
+    AlpacaError, CancellationToken, Result,
+    types::{Calendar, Order, TimeInForce},
+    utils::{parse_decimal, us_eastern_offset},
+};
+use chrono::{DateTime, Days, Duration, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Calendar days fetched past the latest tracked `gtd_date`, so rolls can
+/// find their next sessions.
+const ROLL_LOOKAHEAD_DAYS: u64 = 14;
+
+/// What [`GtdTracker`] does with an order close to expiry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GtdExpiryAction {
+    /// Report [`GtdEvent::Expiring`] once and let the order expire.
+    Notify,
+    /// Cancel the order and resubmit its unfilled quantity with a
+    /// `gtd_date` this many sessions later.
+    Roll {
+        /// Trading sessions to extend the order by.
+        sessions: usize,
+    },
+}
+
+/// Settings of a [`GtdTracker`].
+#[derive(Debug, Clone)]
+pub struct GtdTrackerConfig {
+    /// How long before expiry an order is acted on.
+    pub notice: Duration,
+    /// Delay between checks in [`GtdTracker::run`].
+    pub poll_interval: std::time::Duration,
+    /// What to do with an order close to expiry.
+    pub action: GtdExpiryAction,
+}
+
+impl Default for GtdTrackerConfig {
+    fn default() -> Self {
+        Self {
+            notice: Duration::minutes(30),
+            poll_interval: std::time::Duration::from_secs(60),
+            action: GtdExpiryAction::Notify,
+        }
+    }
+}
+
+impl GtdTrackerConfig {
+    /// Act on orders `notice` before they expire.
+    #[must_use]
+    pub fn notice(mut self, notice: Duration) -> Self {
+        self.notice = notice;
+        self
+    }
+
+    /// Set the delay between checks.
+    #[must_use]
+    pub fn poll_interval(mut self, poll_interval: std::time::Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Roll expiring orders `sessions` trading sessions forward instead of
+    /// only notifying.
+    #[must_use]
+    pub fn roll(mut self, sessions: usize) -> Self {
+        self.action = GtdExpiryAction::Roll {
+            sessions: sessions.max(1),
+        };
+        self
+    }
+}
+
+/// An order watched by a [`GtdTracker`].
+#[derive(Debug, Clone)]
+pub struct TrackedGtdOrder {
+    /// Order ID.
+    pub order_id: Uuid,
+    /// Request the order was submitted with; rolls resubmit it.
+    pub request: CreateOrderRequest,
+    /// Date the order expires at the close of.
+    pub gtd_date: NaiveDate,
+    /// Whether the order was already acted on.
+    pub handled: bool,
+}
+
+/// Something [`GtdTracker`] did with an order close to expiry.
+#[derive(Debug, Clone)]
+pub enum GtdEvent {
+    /// The order expires at `expires_at`.
+    Expiring {
+        /// The order, as last fetched.
+        order: Box<Order>,
+        /// When the order expires.
+        expires_at: DateTime<Utc>,
+    },
+    /// The order was canceled and its unfilled quantity resubmitted.
+    Rolled {
+        /// The canceled order.
+        previous: Box<Order>,
+        /// The resubmitted order.
+        order: Box<Order>,
+        /// The new `gtd_date`.
+        gtd_date: NaiveDate,
+    },
+    /// Rolling the order failed; it is not retried.
+    RollFailed {
+        /// The order, as last fetched.
+        order: Box<Order>,
+        /// Why the roll failed.
+        error: String,
+    },
+}
+
+/// Watches GTD orders and notifies or rolls them before they expire.
+#[derive(Debug, Clone)]
+pub struct GtdTracker {
+    client: AlpacaHttpClient,
+    config: GtdTrackerConfig,
+    orders: Arc<Mutex<HashMap<Uuid, TrackedGtdOrder>>>,
+}
+
+impl GtdTracker {
+    /// Create a tracker using `client`. Clones share the tracked orders.
+    #[must_use]
+    pub fn new(client: AlpacaHttpClient, config: GtdTrackerConfig) -> Self {
+        Self {
+            client,
+            config,
+            orders: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Validate the `gtd_date` of `request`, submit it and track the order.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if `request` is not a GTD order
+    /// or its date is not an upcoming trading day, or the submission error.
+    pub async fn submit(&self, request: &CreateOrderRequest) -> Result<Order> {
+        let gtd_date = gtd_date(request)?;
+        self.client.validate_gtd_date(gtd_date).await?;
+        let order = self.client.create_order(request).await?;
+        self.track(&order, request)?;
+        Ok(order)
+    }
+
+    /// Track `order`, submitted with `request`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if `request` is not a GTD order.
+    pub fn track(&self, order: &Order, request: &CreateOrderRequest) -> Result<()> {
+        let gtd_date = gtd_date(request)?;
+        self.lock().insert(
+            order.id,
+            TrackedGtdOrder {
+                order_id: order.id,
+                request: request.clone(),
+                gtd_date,
+                handled: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stop tracking `order_id`.
+    pub fn untrack(&self, order_id: &Uuid) -> Option<TrackedGtdOrder> {
+        self.lock().remove(order_id)
+    }
+
+    /// The tracked orders, in no particular order.
+    #[must_use]
+    pub fn tracked(&self) -> Vec<TrackedGtdOrder> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// Act on the tracked orders within the notice period of their expiry.
+    ///
+    /// Orders found closed are no longer tracked. Each order is acted on
+    /// once.
+    ///
+    /// # Errors
+    /// Returns an error fetching the calendar; errors fetching, canceling
+    /// or resubmitting a single order are logged or reported as
+    /// [`GtdEvent::RollFailed`].
+    pub async fn check(&self) -> Result<Vec<GtdEvent>> {
+        let pending: Vec<_> = self.tracked().into_iter().filter(|t| !t.handled).collect();
+        let Some(last) = pending.iter().map(|t| t.gtd_date).max() else {
+            return Ok(Vec::new());
+        };
+        let today = eastern_today();
+        let end = last + Days::new(ROLL_LOOKAHEAD_DAYS + 2 * self.roll_sessions() as u64);
+        let params = CalendarParams::new()
+            .start(&today.min(last).to_string())
+            .end(&end.to_string());
+        let sessions = self
+            .client
+            .get_calendar(&params)
+            .await?
+            .iter()
+            .map(TradingSession::from_calendar)
+            .collect::<Result<Vec<_>>>()?;
+
+        let now = Utc::now();
+        let mut events = Vec::new();
+        for tracked in pending {
+            let expires_at = expires_at(&sessions, tracked.gtd_date, now);
+            if expires_at - self.config.notice > now {
+                continue;
+            }
+            let order = match self.client.get_order(&tracked.order_id).await {
+                Ok(order) => order,
+                Err(e) => {
+                    warn!("GTD order {} lookup failed: {}", tracked.order_id, e);
+                    continue;
+                }
+            };
+            if is_closed(&order.status) {
+                self.untrack(&order.id);
+                continue;
+            }
+            self.mark_handled(&order.id);
+            let event = match self.config.action {
+                GtdExpiryAction::Notify => GtdEvent::Expiring {
+                    order: Box::new(order),
+                    expires_at,
+                },
+                GtdExpiryAction::Roll { sessions: n } => {
+                    match roll_date(&sessions, tracked.gtd_date.max(today), n) {
+                        Some(gtd_date) => self.roll(order, &tracked.request, gtd_date).await,
+                        None => GtdEvent::RollFailed {
+                            order: Box::new(order),
+                            error: format!("no trading session {n} sessions after expiry"),
+                        },
+                    }
+                }
+            };
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    /// Run [`Self::check`] every poll interval, passing each event to
+    /// `callback`, until `cancel` is triggered.
+    ///
+    /// Calendar lookup failures are logged and retried at the next check.
+    pub async fn run<F, Fut>(&self, cancel: &CancellationToken, mut callback: F)
+    where
+        F: FnMut(GtdEvent) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        loop {
+            match self.check().await {
+                Ok(events) => {
+                    for event in events {
+                        callback(event).await;
+                    }
+                }
+                Err(e) => warn!("GTD expiry check failed: {}", e),
+            }
+            tokio::select! {
+                () = cancel.cancelled() => return,
+                () = tokio::time::sleep(self.config.poll_interval) => {}
+            }
+        }
+    }
+
+    async fn roll(
+        &self,
+        order: Order,
+        request: &CreateOrderRequest,
+        gtd_date: NaiveDate,
+    ) -> GtdEvent {
+        let mut request = CreateOrderRequest {
+            client_order_id: None,
+            gtd_date: Some(gtd_date),
+            ..request.clone()
+        };
+        let resubmitted = async {
+            match &order.qty {
+                Some(qty) => {
+                    let remaining = parse_decimal(qty)? - parse_decimal(&order.filled_qty)?;
+                    request.qty = Some(remaining.to_string());
+                }
+                None if parse_decimal(&order.filled_qty)? > 0.0 => {
+                    return Err(AlpacaError::Validation(
+                        "partially filled notional orders cannot be rolled".to_string(),
+                    ));
+                }
+                None => {}
+            }
+            self.client.cancel_order(&order.id).await?;
+            self.client.create_order(&request).await
+        }
+        .await;
+        match resubmitted {
+            Ok(rolled) => {
+                self.untrack(&order.id);
+                // `request` is a GTD order, so tracking cannot fail.
+                let _ = self.track(&rolled, &request);
+                GtdEvent::Rolled {
+                    previous: Box::new(order),
+                    order: Box::new(rolled),
+                    gtd_date,
+                }
+            }
+            Err(e) => GtdEvent::RollFailed {
+                order: Box::new(order),
+                error: e.to_string(),
+            },
+        }
+    }
+
+    fn roll_sessions(&self) -> usize {
+        match self.config.action {
+            GtdExpiryAction::Notify => 0,
+            GtdExpiryAction::Roll { sessions } => sessions,
+        }
+    }
+
+    fn mark_handled(&self, order_id: &Uuid) {
+        if let Some(tracked) = self.lock().get_mut(order_id) {
+            tracked.handled = true;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, TrackedGtdOrder>> {
+        self.orders.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl AlpacaHttpClient {
+    /// Check that `date` is a trading day that has not passed (US
+    /// Eastern), using the market calendar.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if it is not, or an error
+    /// fetching the calendar.
+    pub async fn validate_gtd_date(&self, date: NaiveDate) -> Result<()> {
+        let today = eastern_today();
+        if date < today {
+            return Err(AlpacaError::Validation(format!(
+                "gtd_date {date} is in the past"
+            )));
+        }
+        let day = date.to_string();
+        let calendar = self
+            .get_calendar(&CalendarParams::new().start(&day).end(&day))
+            .await?;
+        check_trading_day(&calendar, date)
+    }
+}
+
+/// The `gtd_date` of a GTD `request`.
+fn gtd_date(request: &CreateOrderRequest) -> Result<NaiveDate> {
+    match (&request.time_in_force, request.gtd_date) {
+        (TimeInForce::Gtd, Some(date)) => Ok(date),
+        _ => Err(AlpacaError::Validation(format!(
+            "order for {} is not a GTD order with a gtd_date",
+            request.symbol
+        ))),
+    }
+}
+
+/// Fail unless `calendar` has a session on `date`.
+fn check_trading_day(calendar: &[Calendar], date: NaiveDate) -> Result<()> {
+    let day = date.to_string();
+    if calendar.iter().any(|c| c.date == day) {
+        Ok(())
+    } else {
+        Err(AlpacaError::Validation(format!(
+            "gtd_date {date} is not a trading day"
+        )))
+    }
+}
+
+/// When an order good till `date` expires: the close of the last session
+/// on or before `date`, or `now` if there is none.
+fn expires_at(sessions: &[TradingSession], date: NaiveDate, now: DateTime<Utc>) -> DateTime<Utc> {
+    sessions
+        .iter()
+        .filter(|session| session.date <= date)
+        .map(|session| session.regular.close)
+        .max()
+        .unwrap_or(now)
+}
+
+/// The date of the session `n` sessions after `from`.
+fn roll_date(sessions: &[TradingSession], from: NaiveDate, n: usize) -> Option<NaiveDate> {
+    let mut dates: Vec<_> = sessions
+        .iter()
+        .map(|session| session.date)
+        .filter(|date| *date > from)
+        .collect();
+    dates.sort_unstable();
+    dates.get(n.checked_sub(1)?).copied()
+}
+
+pub(crate) fn eastern_today() -> NaiveDate {
+    let now = Utc::now();
+    now.with_timezone(&us_eastern_offset(now.date_naive()))
+        .date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(date: &str, close: &str) -> Calendar {
+        Calendar {
+            date: date.to_string(),
+            open: "09:30".to_string(),
+            close: close.to_string(),
+            session_open: "0400".to_string(),
+            session_close: "2000".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_gtd_expiry_and_roll_dates() {
+        let date = |s: &str| s.parse::<NaiveDate>().unwrap();
+        let calendar = [
+            calendar("2024-11-27", "16:00"),
+            calendar("2024-11-29", "13:00"),
+            calendar("2024-12-02", "16:00"),
+        ];
+        assert!(check_trading_day(&calendar, date("2024-11-29")).is_ok());
+        // Thanksgiving.
+        assert!(check_trading_day(&calendar, date("2024-11-28")).is_err());
+
+        let sessions: Vec<_> = calendar
+            .iter()
+            .map(|c| TradingSession::from_calendar(c).unwrap())
+            .collect();
+        let now = "2024-11-20T15:00:00Z".parse().unwrap();
+        // The half-day closes at 13:00 ET.
+        assert_eq!(
+            expires_at(&sessions, date("2024-11-29"), now),
+            "2024-11-29T18:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        // A holiday expires with the session before it.
+        assert_eq!(
+            expires_at(&sessions, date("2024-11-28"), now),
+            "2024-11-27T21:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(expires_at(&sessions, date("2024-11-01"), now), now);
+
+        assert_eq!(
+            roll_date(&sessions, date("2024-11-27"), 2),
+            Some(date("2024-12-02"))
+        );
+        assert_eq!(roll_date(&sessions, date("2024-11-27"), 3), None);
+
+        let request =
+            CreateOrderRequest::limit("AAPL", alpaca_base::types::OrderSide::Buy, "1", "1");
+        assert!(gtd_date(&request).is_err());
+        assert_eq!(
+            gtd_date(&request.gtd_date(date("2024-11-29"))).unwrap(),
+            date("2024-11-29")
+        );
+    }
+}
@@ -0,0 +1,571 @@
+//! Options overlays hedging equity positions.
+//!
+//! [`Hedger`] picks contracts for a protective put or a collar (a put
+//! bought and financed by a call sold) on a stock position. Contracts are
+//! chosen by delta from the snapshots of the options chain, at the first
+//! expiration inside the configured window where every leg is available:
+//!
+//! ```rust,ignore
+//! let position = client.get_position("AAPL").await?;
+//! let plan = Hedger::collar(0.30, 0.25).expiration_days(30, 60).quote(&client, &position).await?;
+//! println!("net cost ${:.2} ({:.2}/share)", plan.net_cost, plan.net_cost_per_share());
+//! let orders = plan.submit(&client).await?;
+//! ```
+//!
+//! Positions are hedged in whole contracts, so the hedged quantity is
+//! rounded down to a multiple of the contract size. Short positions are
+//! protected with calls instead of puts (and a collar sells puts). Orders
+//! are day limit orders at the mid price; [`HedgePlan::net_cost`] is the
+//! cost at those limits and [`HedgePlan::max_net_cost`] the cost of
+//! crossing the spread.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::CreateOrderRequest;
+use crate::gtd::eastern_today;
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{
+        OptionChainEntry, OptionContract, OptionContractParams, OptionSnapshot, OptionType, Order,
+        OrderSide, Position, PositionIntent, PositionSide, TimeInForce,
+    },
+    utils::{format_price, parse_decimal},
+};
+use chrono::{Days, NaiveDate};
+use std::collections::BTreeMap;
+
+/// Most option symbols requested per snapshot call.
+const SNAPSHOT_BATCH: usize = 100;
+
+/// Shares per contract when the contract does not say.
+const DEFAULT_CONTRACT_SIZE: f64 = 100.0;
+
+/// The options overlay to put on a position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HedgeSpec {
+    /// Buy puts (calls for a short position) near the absolute `delta`.
+    ProtectivePut {
+        /// Target absolute delta of the bought option, e.g. `0.30`.
+        delta: f64,
+    },
+    /// Buy puts near `put_delta` and sell calls near `call_delta` (the
+    /// other way round for a short position), same expiration.
+    Collar {
+        /// Target absolute delta of the protective option.
+        put_delta: f64,
+        /// Target absolute delta of the financing option.
+        call_delta: f64,
+    },
+}
+
+/// Finds and prices options overlays for equity positions.
+#[derive(Debug, Clone)]
+pub struct Hedger {
+    spec: HedgeSpec,
+    min_days: u64,
+    max_days: u64,
+    coverage: f64,
+}
+
+impl Hedger {
+    /// Hedge with `spec`, expiring 30 to 60 days out, covering the whole
+    /// position.
+    #[must_use]
+    pub fn new(spec: HedgeSpec) -> Self {
+        Self {
+            spec,
+            min_days: 30,
+            max_days: 60,
+            coverage: 1.0,
+        }
+    }
+
+    /// Protective puts near the absolute `delta`.
+    #[must_use]
+    pub fn protective_put(delta: f64) -> Self {
+        Self::new(HedgeSpec::ProtectivePut { delta })
+    }
+
+    /// A collar of puts near `put_delta` and calls near `call_delta`.
+    #[must_use]
+    pub fn collar(put_delta: f64, call_delta: f64) -> Self {
+        Self::new(HedgeSpec::Collar {
+            put_delta,
+            call_delta,
+        })
+    }
+
+    /// Only use contracts expiring `min` to `max` calendar days from today.
+    #[must_use]
+    pub fn expiration_days(mut self, min: u64, max: u64) -> Self {
+        self.min_days = min;
+        self.max_days = max.max(min);
+        self
+    }
+
+    /// Hedge this fraction of the position (default `1.0`).
+    #[must_use]
+    pub fn coverage(mut self, coverage: f64) -> Self {
+        self.coverage = coverage.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The legs as (option type, side, target absolute delta).
+    fn legs(&self, position: &Position) -> Vec<(OptionType, OrderSide, f64)> {
+        let (protect, finance) = match position.side {
+            PositionSide::Long => (OptionType::Put, OptionType::Call),
+            PositionSide::Short => (OptionType::Call, OptionType::Put),
+        };
+        match self.spec {
+            HedgeSpec::ProtectivePut { delta } => vec![(protect, OrderSide::Buy, delta)],
+            HedgeSpec::Collar {
+                put_delta,
+                call_delta,
+            } => vec![
+                (protect, OrderSide::Buy, put_delta),
+                (finance, OrderSide::Sell, call_delta),
+            ],
+        }
+    }
+
+    /// Plan the hedge of `position` from `chain` (contracts of its
+    /// underlying with their snapshots), as of `today`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if the position is too small for
+    /// one contract or no expiration in the window has priced contracts
+    /// with deltas for every leg, and [`AlpacaError::InvalidData`] if the
+    /// position or a contract does not parse.
+    pub fn plan(
+        &self,
+        position: &Position,
+        chain: &[OptionChainEntry],
+        today: NaiveDate,
+    ) -> Result<HedgePlan> {
+        let shares = parse_decimal(&position.qty)?.abs();
+        let earliest = today + Days::new(self.min_days);
+        let latest = today + Days::new(self.max_days);
+
+        let mut by_expiration: BTreeMap<NaiveDate, Vec<Candidate<'_>>> = BTreeMap::new();
+        for entry in chain {
+            if let Some(candidate) = Candidate::new(entry, &position.symbol)?
+                && (earliest..=latest).contains(&candidate.expiration)
+            {
+                by_expiration
+                    .entry(candidate.expiration)
+                    .or_default()
+                    .push(candidate);
+            }
+        }
+
+        let legs = self.legs(position);
+        let (expiration, picks) = by_expiration
+            .iter()
+            .find_map(|(expiration, candidates)| {
+                let picks: Option<Vec<_>> = legs
+                    .iter()
+                    .map(|(option_type, _, delta)| closest(candidates, option_type, *delta))
+                    .collect();
+                picks.map(|picks| (*expiration, picks))
+            })
+            .ok_or_else(|| {
+                AlpacaError::Validation(format!(
+                    "no {} options expiring {earliest} to {latest} with deltas for every leg",
+                    position.symbol
+                ))
+            })?;
+
+        let size = picks[0].size;
+        let contracts = (shares * self.coverage / size).floor() as u64;
+        if contracts == 0 {
+            return Err(AlpacaError::Validation(format!(
+                "{shares} shares of {} is less than one contract of {size}",
+                position.symbol
+            )));
+        }
+        let hedge_legs: Vec<HedgeLeg> = picks
+            .into_iter()
+            .zip(legs)
+            .map(|(pick, (_, side, _))| HedgeLeg::new(pick, side, contracts))
+            .collect();
+        if let [protect, finance] = hedge_legs.as_slice() {
+            let protect_strike = parse_decimal(&protect.contract.strike_price)?;
+            let finance_strike = parse_decimal(&finance.contract.strike_price)?;
+            let inverted = match position.side {
+                PositionSide::Long => protect_strike >= finance_strike,
+                PositionSide::Short => protect_strike <= finance_strike,
+            };
+            if inverted {
+                return Err(AlpacaError::Validation(format!(
+                    "collar strikes {protect_strike} and {finance_strike} overlap; \
+                     lower the deltas"
+                )));
+            }
+        }
+
+        Ok(HedgePlan {
+            symbol: position.symbol.clone(),
+            expiration,
+            hedged_shares: contracts as f64 * size,
+            net_cost: hedge_legs.iter().map(HedgeLeg::cost).sum(),
+            max_net_cost: hedge_legs.iter().map(HedgeLeg::max_cost).sum(),
+            legs: hedge_legs,
+        })
+    }
+
+    /// Find contracts for hedging `position` and price them from their
+    /// snapshots.
+    ///
+    /// # Errors
+    /// Returns an error fetching contracts or snapshots, or any error of
+    /// [`Self::plan`].
+    pub async fn quote(&self, client: &AlpacaHttpClient, position: &Position) -> Result<HedgePlan> {
+        let today = eastern_today();
+        let mut params = OptionContractParams::new().underlying_symbol(&position.symbol);
+        params.expiration_date_gte = Some((today + Days::new(self.min_days)).to_string());
+        params.expiration_date_lte = Some((today + Days::new(self.max_days)).to_string());
+        if let [(option_type, _, _)] = self.legs(position).as_slice() {
+            params = params.option_type(option_type.clone());
+        }
+
+        let mut contracts = Vec::new();
+        loop {
+            let page = client.get_option_contracts(&params).await?;
+            contracts.extend(page.option_contracts.into_iter().filter(|c| c.tradable));
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => params.page_token = Some(token),
+                _ => break,
+            }
+        }
+
+        let mut chain = Vec::with_capacity(contracts.len());
+        for batch in contracts.chunks(SNAPSHOT_BATCH) {
+            let symbols: Vec<&str> = batch.iter().map(|c| c.symbol.as_str()).collect();
+            let mut snapshots = client
+                .get_option_snapshots(&symbols.join(","))
+                .await?
+                .snapshots;
+            chain.extend(batch.iter().map(|contract| OptionChainEntry {
+                snapshot: snapshots.remove(&contract.symbol),
+                contract: contract.clone(),
+            }));
+        }
+        self.plan(position, &chain, today)
+    }
+}
+
+/// A chain entry usable as a hedge leg.
+struct Candidate<'a> {
+    contract: &'a OptionContract,
+    snapshot: &'a OptionSnapshot,
+    expiration: NaiveDate,
+    delta: f64,
+    size: f64,
+}
+
+impl<'a> Candidate<'a> {
+    /// `None` for contracts without a delta and a two-sided quote.
+    fn new(entry: &'a OptionChainEntry, underlying: &str) -> Result<Option<Self>> {
+        let contract = &entry.contract;
+        let Some(snapshot) = entry.snapshot.as_ref() else {
+            return Ok(None);
+        };
+        let delta = snapshot.greeks.as_ref().and_then(|greeks| greeks.delta);
+        let quoted = snapshot
+            .latest_quote
+            .as_ref()
+            .is_some_and(|quote| quote.bid_price > 0.0 && quote.ask_price > 0.0);
+        let (Some(delta), true) = (delta, quoted) else {
+            return Ok(None);
+        };
+        if !contract.tradable || contract.underlying_symbol != underlying {
+            return Ok(None);
+        }
+        let expiration =
+            NaiveDate::parse_from_str(&contract.expiration_date, "%Y-%m-%d").map_err(|e| {
+                AlpacaError::InvalidData(format!(
+                    "bad expiration {:?} for {}: {e}",
+                    contract.expiration_date, contract.symbol
+                ))
+            })?;
+        let size = match contract.size.as_deref() {
+            Some(size) => parse_decimal(size)?,
+            None => DEFAULT_CONTRACT_SIZE,
+        };
+        Ok(Some(Self {
+            contract,
+            snapshot,
+            expiration,
+            delta,
+            size,
+        }))
+    }
+}
+
+/// The candidate of `option_type` with the absolute delta closest to
+/// `target`.
+fn closest<'c, 'a>(
+    candidates: &'c [Candidate<'a>],
+    option_type: &OptionType,
+    target: f64,
+) -> Option<&'c Candidate<'a>> {
+    candidates
+        .iter()
+        .filter(|c| c.contract.option_type == *option_type)
+        .min_by(|a, b| {
+            (a.delta.abs() - target)
+                .abs()
+                .total_cmp(&(b.delta.abs() - target).abs())
+        })
+}
+
+/// One option order of a [`HedgePlan`].
+#[derive(Debug, Clone)]
+pub struct HedgeLeg {
+    /// The chosen contract.
+    pub contract: OptionContract,
+    /// Its snapshot.
+    pub snapshot: OptionSnapshot,
+    /// Delta of the contract.
+    pub delta: f64,
+    /// Buy for protection, sell for financing.
+    pub side: OrderSide,
+    /// Number of contracts.
+    pub contracts: u64,
+    /// Shares per contract.
+    pub contract_size: f64,
+    /// Limit price per share: the quote mid.
+    pub limit_price: f64,
+    /// Price per share when crossing the spread: the ask for buys, the bid
+    /// for sells.
+    pub cross_price: f64,
+}
+
+impl HedgeLeg {
+    fn new(candidate: &Candidate<'_>, side: OrderSide, contracts: u64) -> Self {
+        // Candidates always have a two-sided quote.
+        let (bid, ask) = candidate
+            .snapshot
+            .latest_quote
+            .as_ref()
+            .map_or((0.0, 0.0), |quote| (quote.bid_price, quote.ask_price));
+        let cross_price = match side {
+            OrderSide::Buy => ask,
+            OrderSide::Sell => bid,
+        };
+        Self {
+            contract: candidate.contract.clone(),
+            snapshot: candidate.snapshot.clone(),
+            delta: candidate.delta,
+            side,
+            contracts,
+            contract_size: candidate.size,
+            limit_price: parse_decimal(&format_price((bid + ask) / 2.0)).unwrap_or(cross_price),
+            cross_price,
+        }
+    }
+
+    fn signed(&self, price: f64) -> f64 {
+        let value = price * self.contract_size * self.contracts as f64;
+        match self.side {
+            OrderSide::Buy => value,
+            OrderSide::Sell => -value,
+        }
+    }
+
+    /// Cost at the limit price; negative for premium received.
+    #[must_use]
+    pub fn cost(&self) -> f64 {
+        self.signed(self.limit_price)
+    }
+
+    /// Cost when crossing the spread; negative for premium received.
+    #[must_use]
+    pub fn max_cost(&self) -> f64 {
+        self.signed(self.cross_price)
+    }
+
+    /// The order opening this leg.
+    #[must_use]
+    pub fn order(&self) -> CreateOrderRequest {
+        let intent = match self.side {
+            OrderSide::Buy => PositionIntent::BuyToOpen,
+            OrderSide::Sell => PositionIntent::SellToOpen,
+        };
+        CreateOrderRequest::limit(
+            &self.contract.symbol,
+            self.side.clone(),
+            self.contracts.to_string(),
+            format_price(self.limit_price),
+        )
+        .time_in_force(TimeInForce::Day)
+        .position_intent(intent)
+    }
+}
+
+/// A priced options overlay for one position.
+#[derive(Debug, Clone)]
+pub struct HedgePlan {
+    /// The hedged stock.
+    pub symbol: String,
+    /// Expiration of every leg.
+    pub expiration: NaiveDate,
+    /// Shares covered by the contracts.
+    pub hedged_shares: f64,
+    /// The option legs, protection first.
+    pub legs: Vec<HedgeLeg>,
+    /// Total cost at the limit prices; negative for a net credit.
+    pub net_cost: f64,
+    /// Total cost when crossing every spread.
+    pub max_net_cost: f64,
+}
+
+impl HedgePlan {
+    /// Net cost per hedged share.
+    #[must_use]
+    pub fn net_cost_per_share(&self) -> f64 {
+        self.net_cost / self.hedged_shares
+    }
+
+    /// The orders opening the hedge, protection first.
+    #[must_use]
+    pub fn orders(&self) -> Vec<CreateOrderRequest> {
+        self.legs.iter().map(HedgeLeg::order).collect()
+    }
+
+    /// Submit the orders, protection first so a collar never leaves the
+    /// financing option sold on its own.
+    ///
+    /// # Errors
+    /// Returns the first submission error; legs before it stay submitted.
+    pub async fn submit(&self, client: &AlpacaHttpClient) -> Result<Vec<Order>> {
+        let mut orders = Vec::with_capacity(self.legs.len());
+        for order in self.orders() {
+            orders.push(client.create_order(&order).await?);
+        }
+        Ok(orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_position;
+
+    fn entry(
+        expiration: &str,
+        option_type: OptionType,
+        strike: f64,
+        delta: f64,
+    ) -> OptionChainEntry {
+        let kind = if option_type == OptionType::Put {
+            'P'
+        } else {
+            'C'
+        };
+        let symbol = format!(
+            "AAPL{}{kind}{:08}",
+            &expiration.replace('-', "")[2..],
+            (strike * 1000.0) as u64
+        );
+        let contract = serde_json::from_value(serde_json::json!({
+            "id": uuid::Uuid::new_v4(),
+            "symbol": symbol,
+            "name": symbol,
+            "status": "active",
+            "tradable": true,
+            "expiration_date": expiration,
+            "strike_price": strike.to_string(),
+            "type": option_type,
+            "style": "american",
+            "underlying_symbol": "AAPL",
+            "underlying_asset_id": uuid::Uuid::new_v4(),
+            "root_symbol": "AAPL",
+            "size": "100"
+        }))
+        .unwrap();
+        let snapshot = serde_json::from_value(serde_json::json!({
+            "latestQuote": {
+                "t": "2024-06-03T14:30:00Z", "bp": 1.00, "bs": 10, "ap": 1.10, "as": 10,
+                "bx": "C", "ax": "C"
+            },
+            "latestTrade": null,
+            "greeks": { "delta": delta, "gamma": null, "theta": null, "vega": null, "rho": null },
+            "impliedVolatility": 0.25
+        }))
+        .unwrap();
+        OptionChainEntry {
+            contract,
+            snapshot: Some(snapshot),
+        }
+    }
+
+    #[test]
+    fn test_hedger_picks_contracts_by_delta() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+        let chain = vec![
+            // Too soon.
+            entry("2024-06-14", OptionType::Put, 140.0, -0.30),
+            // Only puts at the first expiration in the window.
+            entry("2024-07-19", OptionType::Put, 140.0, -0.28),
+            entry("2024-08-16", OptionType::Put, 135.0, -0.20),
+            entry("2024-08-16", OptionType::Put, 140.0, -0.31),
+            entry("2024-08-16", OptionType::Call, 160.0, 0.26),
+            entry("2024-08-16", OptionType::Call, 170.0, 0.15),
+        ];
+        let position = sample_position("AAPL", "250", "150.00");
+
+        let plan = Hedger::protective_put(0.30)
+            .expiration_days(30, 90)
+            .plan(&position, &chain, today)
+            .unwrap();
+        assert_eq!(plan.expiration.to_string(), "2024-07-19");
+        assert_eq!(plan.hedged_shares, 200.0);
+        assert_eq!(plan.legs[0].delta, -0.28);
+        // 2 contracts at the 1.05 mid, or the 1.10 ask.
+        assert!((plan.net_cost - 210.0).abs() < 1e-9);
+        assert!((plan.max_net_cost - 220.0).abs() < 1e-9);
+        let order = &plan.orders()[0];
+        assert_eq!(order.qty.as_deref(), Some("2"));
+        assert_eq!(order.limit_price.as_deref(), Some("1.05"));
+        assert_eq!(order.position_intent, Some(PositionIntent::BuyToOpen));
+
+        let plan = Hedger::collar(0.30, 0.25)
+            .expiration_days(30, 90)
+            .plan(&position, &chain, today)
+            .unwrap();
+        assert_eq!(plan.expiration.to_string(), "2024-08-16");
+        assert_eq!(plan.legs[0].contract.strike_price, "140");
+        assert_eq!(plan.legs[1].contract.strike_price, "160");
+        assert_eq!(plan.legs[1].side, OrderSide::Sell);
+        // Premium paid and received cancel out at the mid.
+        assert!(plan.net_cost.abs() < 1e-9);
+
+        assert!(
+            Hedger::protective_put(0.30)
+                .plan(&sample_position("AAPL", "50", "150.00"), &chain, today)
+                .is_err()
+        );
+        assert!(
+            Hedger::protective_put(0.30)
+                .expiration_days(100, 200)
+                .plan(&position, &chain, today)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_hedger_quote_hosts() {
+        use alpaca_base::{Credentials, Environment};
+        let credentials = Credentials::new("key".to_string(), "secret".to_string());
+        let client = AlpacaHttpClient::new(credentials, Environment::Paper).unwrap();
+        // Contracts come from the trading API, snapshots from market data.
+        assert_eq!(
+            client.build_url("/v2/options/contracts").unwrap(),
+            "https://paper-api.alpaca.markets/v2/options/contracts"
+        );
+        assert_eq!(
+            client.build_url("/v1beta1/options/snapshots").unwrap(),
+            "https://data.alpaca.markets/v1beta1/options/snapshots"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Idempotent order submission.
+//!
+//! A submission that times out may or may not have reached the exchange,
+//! and blindly retrying it risks a duplicate order. [`IdempotencyManager`]
+//! derives a deterministic `client_order_id` from a caller-chosen key and
+//! records the submission as in flight until its outcome is known. When an
+//! attempt fails ambiguously (network error, timeout or 5xx), the order is
+//! looked up with `GET /v2/orders:by_client_order_id` before anything is
+//! resent; if it exists, it is returned instead of being submitted again.
+//! Alpaca rejects a reused `client_order_id`, so a retry can never place the
+//! same order twice either.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::CreateOrderRequest;
+use alpaca_base::{AlpacaError, Result, RetryPolicy, types::Order};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Prefix of generated client order IDs.
+pub const CLIENT_ORDER_ID_PREFIX: &str = "idem-";
+
+/// A submission whose outcome is not yet known.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InFlightOrder {
+    /// Idempotency key.
+    pub key: String,
+    /// Client order ID.
+    pub client_order_id: String,
+    /// Symbol.
+    pub symbol: String,
+    /// First submission time.
+    pub submitted_at: DateTime<Utc>,
+    /// Number of submissions sent.
+    pub attempts: u32,
+}
+
+/// Submits orders at most once per idempotency key.
+#[derive(Debug)]
+pub struct IdempotencyManager {
+    client: AlpacaHttpClient,
+    scope: String,
+    policy: RetryPolicy,
+    in_flight: Mutex<HashMap<String, InFlightOrder>>,
+}
+
+impl IdempotencyManager {
+    /// Create a manager whose client order IDs are scoped to `scope`, e.g.
+    /// a strategy or user ID, retrying under the default [`RetryPolicy`].
+    #[must_use]
+    pub fn new(client: AlpacaHttpClient, scope: &str) -> Self {
+        Self {
+            client,
+            scope: scope.to_string(),
+            policy: RetryPolicy::new(),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the retry policy for submissions.
+    #[must_use]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The client order ID for `key`: the same key in the same scope always
+    /// yields the same ID.
+    #[must_use]
+    pub fn client_order_id(&self, key: &str) -> String {
+        let digest = Sha256::new()
+            .chain_update(self.scope.as_bytes())
+            .chain_update([0u8])
+            .chain_update(key.as_bytes())
+            .finalize();
+        let mut id = String::from(CLIENT_ORDER_ID_PREFIX);
+        for byte in &digest[..16] {
+            let _ = write!(id, "{byte:02x}");
+        }
+        id
+    }
+
+    /// Submissions whose outcome is still unknown.
+    #[must_use]
+    pub fn in_flight(&self) -> Vec<InFlightOrder> {
+        let in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        in_flight.values().cloned().collect()
+    }
+
+    /// Submit `order` once for `key`.
+    ///
+    /// The order's `client_order_id` is set from `key` unless already
+    /// given. Failed attempts are retried under the retry policy; after an
+    /// ambiguous failure, or a rejection of the ID as a duplicate, the
+    /// existing order is looked up and returned if found.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if the same client order ID is
+    /// already in flight, or the last submission error. The submission stays
+    /// in flight if that error leaves its outcome unknown; see
+    /// [`Self::resolve_in_flight`].
+    pub async fn submit(&self, key: &str, mut order: CreateOrderRequest) -> Result<Order> {
+        let client_order_id = order
+            .client_order_id
+            .clone()
+            .unwrap_or_else(|| self.client_order_id(key));
+        order.client_order_id = Some(client_order_id.clone());
+        self.begin(key, &client_order_id, &order.symbol)?;
+
+        let result = self
+            .policy
+            .retry(|_| self.attempt(&client_order_id, &order))
+            .await;
+        match &result {
+            Err(e) if is_ambiguous(e) => {}
+            _ => self.finish(&client_order_id),
+        }
+        result
+    }
+
+    /// Look up every in-flight submission, dropping those whose outcome is
+    /// now known. Returns each resolved submission with its order, or
+    /// `None` if it was never placed.
+    pub async fn resolve_in_flight(&self) -> Vec<(InFlightOrder, Option<Order>)> {
+        let mut resolved = Vec::new();
+        for entry in self.in_flight() {
+            let Ok(order) = self.lookup(&entry.client_order_id).await else {
+                continue;
+            };
+            self.finish(&entry.client_order_id);
+            resolved.push((entry, order));
+        }
+        resolved
+    }
+
+    async fn attempt(&self, client_order_id: &str, order: &CreateOrderRequest) -> Result<Order> {
+        self.record_attempt(client_order_id);
+        let error = match self.client.create_order(order).await {
+            Ok(order) => return Ok(order),
+            Err(e) => e,
+        };
+        if (is_ambiguous(&error) || is_duplicate(&error))
+            && let Ok(Some(existing)) = self.lookup(client_order_id).await
+        {
+            info!(
+                "Order {} was already placed ({}); not resubmitting",
+                client_order_id, error
+            );
+            return Ok(existing);
+        }
+        Err(error)
+    }
+
+    async fn lookup(&self, client_order_id: &str) -> Result<Option<Order>> {
+        match self.client.get_order_by_client_id(client_order_id).await {
+            Ok(order) => Ok(Some(order)),
+            Err(e) if e.status_code() == Some(404) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn begin(&self, key: &str, client_order_id: &str, symbol: &str) -> Result<()> {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if in_flight.contains_key(client_order_id) {
+            return Err(AlpacaError::Validation(format!(
+                "order {client_order_id} is already being submitted"
+            )));
+        }
+        in_flight.insert(
+            client_order_id.to_string(),
+            InFlightOrder {
+                key: key.to_string(),
+                client_order_id: client_order_id.to_string(),
+                symbol: symbol.to_string(),
+                submitted_at: Utc::now(),
+                attempts: 0,
+            },
+        );
+        Ok(())
+    }
+
+    fn record_attempt(&self, client_order_id: &str) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = in_flight.get_mut(client_order_id) {
+            entry.attempts += 1;
+        }
+    }
+
+    fn finish(&self, client_order_id: &str) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        in_flight.remove(client_order_id);
+    }
+}
+
+/// Whether a failed submission may still have been accepted.
+fn is_ambiguous(error: &AlpacaError) -> bool {
+    match error {
+        AlpacaError::Network(_) | AlpacaError::Timeout(_) => true,
+        AlpacaError::Api { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+/// Whether the submission was rejected for reusing its client order ID.
+fn is_duplicate(error: &AlpacaError) -> bool {
+    error.is_duplicate_client_order_id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::{Credentials, types::Environment};
+
+    fn manager(scope: &str) -> IdempotencyManager {
+        let client = AlpacaHttpClient::new(
+            Credentials::new("key".to_string(), "secret".to_string()),
+            Environment::Paper,
+        )
+        .unwrap();
+        IdempotencyManager::new(client, scope)
+    }
+
+    #[test]
+    fn test_client_order_ids_are_deterministic_and_scoped() {
+        let a = manager("strategy-a");
+        let id = a.client_order_id("rebalance-2024-06-03-AAPL");
+        assert_eq!(id, a.client_order_id("rebalance-2024-06-03-AAPL"));
+        assert!(id.starts_with(CLIENT_ORDER_ID_PREFIX));
+        assert_eq!(id.len(), CLIENT_ORDER_ID_PREFIX.len() + 32);
+        assert_ne!(id, a.client_order_id("rebalance-2024-06-04-AAPL"));
+        assert_ne!(
+            id,
+            manager("strategy-b").client_order_id("rebalance-2024-06-03-AAPL")
+        );
+
+        a.begin("k", &id, "AAPL").unwrap();
+        assert!(matches!(
+            a.begin("k", &id, "AAPL"),
+            Err(AlpacaError::Validation(_))
+        ));
+        a.record_attempt(&id);
+        assert_eq!(a.in_flight()[0].attempts, 1);
+        a.finish(&id);
+        assert!(a.in_flight().is_empty());
+
+        assert!(is_ambiguous(&AlpacaError::Timeout("read".into())));
+        assert!(!is_ambiguous(&AlpacaError::rate_limit(1)));
+        assert!(is_duplicate(&AlpacaError::api(
+            422,
+            "client_order_id must be unique"
+        )));
+    }
+}
@@ -2,12 +2,95 @@
 //!
 //! HTTP REST API client for Alpaca trading platform.
 //! This crate provides a comprehensive client for interacting with Alpaca's REST API endpoints.
+//!
+//! [`prelude`] re-exports the stable, commonly used types. Experimental
+//! modules ([`rebalancer`]) require the `unstable` feature and may change
+//! in minor releases.
 
+pub mod asset_universe;
+pub mod backtest;
+pub mod batch_journals;
+pub mod bracket;
+pub mod cache;
 pub mod client;
+pub mod dividend_calendar;
+pub mod downloader;
+pub mod dry_run;
 pub mod endpoints;
 pub mod error;
+pub mod execution;
+pub mod gtd;
+pub mod hedger;
+pub mod idempotency;
+pub mod liquidation;
+pub mod multi_status;
+pub mod order_batch;
+pub mod order_templates;
+pub mod pnl_report;
+pub mod preflight;
+pub mod prelude;
+pub mod protection;
+pub mod query;
+pub mod rate_limit;
+#[cfg(feature = "unstable")]
+pub mod rebalancer;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+pub mod scheduler;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+pub mod submit_policy;
+pub mod trade_journal;
+pub mod watchlist_sync;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 pub use alpaca_base::*;
+pub use asset_universe::{AssetFilter, AssetIndex, AssetUniverse};
+pub use backtest::{BacktestConfig, BacktestFill, Backtester, CommissionModel, SlippageModel};
+pub use batch_journals::{
+    BatchJournalReport, JournalBatch, JournalBatchEntry, JournalEntryOutcome, JournalEntryReport,
+};
+pub use bracket::BracketLeg;
+pub use cache::{CacheConfig, CachedResource, ResponseCache};
 pub use client::AlpacaHttpClient;
-pub use endpoints::{ClosePositionRequest, CreateOrderRequest, OrderParams, ReplaceOrderRequest};
+pub use dividend_calendar::{
+    DEFAULT_HORIZON_DAYS, DividendCalendar, DividendEvent, DividendSchedule, MonthlyIncome,
+};
+pub use downloader::{
+    DownloadCheckpoint, DownloadKind, DownloadReport, HistoricalDownloader, OutputFormat,
+};
+pub use dry_run::DryRunOrder;
+pub use endpoints::{
+    CancelOrderResult, CancelOrdersFilter, ClosePositionRequest, CreateOrderRequest, OrderParams,
+    ReplaceOrderRequest,
+};
 pub use error::HttpError;
+pub use execution::{
+    AlgoControl, AlgoProgress, AlgoStatus, AlgoStrategy, ExecutionAlgo, ExecutionReport,
+    ParentOrder,
+};
+pub use gtd::{GtdEvent, GtdExpiryAction, GtdTracker, GtdTrackerConfig, TrackedGtdOrder};
+pub use hedger::{HedgeLeg, HedgePlan, HedgeSpec, Hedger};
+pub use idempotency::{IdempotencyManager, InFlightOrder};
+pub use liquidation::{LiquidationOptions, LiquidationReport};
+pub use multi_status::{MultiStatus, MultiStatusItem};
+pub use order_batch::{OrderBatchConfig, OrderBatchReport, OrderBatchResult};
+pub use order_templates::{OrderTemplate, OrderTemplates, TemplateParams};
+pub use pnl_report::{DailyPnl, PnlReport, ReportBuilder, SymbolPnl};
+pub use preflight::AccountRestrictions;
+pub use protection::{Protection, ProtectionOutcome};
+pub use query::QueryParams;
+pub use rate_limit::PriorityRateLimiter;
+#[cfg(feature = "unstable")]
+pub use rebalancer::{RebalancePlan, RebalanceTrade, Rebalancer};
+#[cfg(feature = "sandbox")]
+pub use sandbox::{SANDBOX_BANK_ACCOUNT_NUMBER, SANDBOX_BANK_ROUTING_NUMBER, SandboxOptions};
+pub use scheduler::{MarketScheduler, ScheduledEvent, SessionEvent, TradingSession};
+#[cfg(feature = "simulator")]
+pub use simulator::{SimulatedExchange, SimulatorConfig};
+pub use submit_policy::{RejectionReason, Reshape, Resubmission, SubmitPolicy};
+pub use trade_journal::{JournalGenerator, RoundTrip, TradeDirection, TradingJournal};
+pub use watchlist_sync::{WatchlistDiff, WatchlistSync, WatchlistSyncReport};
+#[cfg(feature = "webhook")]
+pub use webhook::{WebhookServer, sign_payload, verify_signature};
@@ -0,0 +1,239 @@
+//! Flattening a whole portfolio.
+//!
+//! [`AlpacaHttpClient::close_all_positions`] fires one request and leaves
+//! the rest to the server. [`AlpacaHttpClient::liquidate_portfolio`] runs
+//! the full procedure instead: open orders are cancelled first (and the
+//! cancellations awaited, so they no longer hold any quantity), then each
+//! position is closed on its own in the order set by
+//! [`LiquidationOptions`], and finally positions are polled until the
+//! account is flat or the timeout passes. Every order placed and every
+//! failure is collected in a [`LiquidationReport`] instead of aborting.
+//!
+//! [`AlpacaHttpClient::close_all_positions`]: crate::AlpacaHttpClient::close_all_positions
+//! [`AlpacaHttpClient::liquidate_portfolio`]: crate::AlpacaHttpClient::liquidate_portfolio
+
+use alpaca_base::{
+    AlpacaError, OccSymbol,
+    types::{Order, Position},
+    utils::parse_decimal,
+};
+use std::time::Duration;
+
+/// How [`AlpacaHttpClient::liquidate_portfolio`] closes positions.
+///
+/// [`AlpacaHttpClient::liquidate_portfolio`]: crate::AlpacaHttpClient::liquidate_portfolio
+#[derive(Debug, Clone)]
+pub struct LiquidationOptions {
+    /// Whether open orders are cancelled first.
+    pub cancel_orders: bool,
+    /// Whether option positions are closed before other positions.
+    pub options_first: bool,
+    /// Whether positions with the largest unrealized loss are closed first.
+    pub largest_loss_first: bool,
+    /// How long to wait for cancellations and for the account to go flat.
+    pub timeout: Duration,
+    /// Delay between polls.
+    pub poll_interval: Duration,
+}
+
+impl Default for LiquidationOptions {
+    fn default() -> Self {
+        Self {
+            cancel_orders: true,
+            options_first: true,
+            largest_loss_first: true,
+            timeout: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl LiquidationOptions {
+    /// Create options that cancel orders, close options first, then the
+    /// largest losses, and wait up to a minute.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether open orders are cancelled first.
+    #[must_use]
+    pub fn cancel_orders(mut self, cancel_orders: bool) -> Self {
+        self.cancel_orders = cancel_orders;
+        self
+    }
+
+    /// Set whether option positions are closed first.
+    #[must_use]
+    pub fn options_first(mut self, options_first: bool) -> Self {
+        self.options_first = options_first;
+        self
+    }
+
+    /// Set whether the largest losses are closed first.
+    #[must_use]
+    pub fn largest_loss_first(mut self, largest_loss_first: bool) -> Self {
+        self.largest_loss_first = largest_loss_first;
+        self
+    }
+
+    /// Set the timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the delay between polls.
+    #[must_use]
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// `positions` in the order they are closed. Ties keep symbol order.
+    #[must_use]
+    pub fn close_order(&self, mut positions: Vec<Position>) -> Vec<Position> {
+        positions.sort_by(|a, b| {
+            let option_rank = |p: &Position| !(self.options_first && is_option(p));
+            let loss_rank = |p: &Position| {
+                if self.largest_loss_first {
+                    parse_decimal(&p.unrealized_pl).unwrap_or(0.0)
+                } else {
+                    0.0
+                }
+            };
+            option_rank(a)
+                .cmp(&option_rank(b))
+                .then(loss_rank(a).total_cmp(&loss_rank(b)))
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        positions
+    }
+}
+
+/// Whether `position` is an option contract.
+fn is_option(position: &Position) -> bool {
+    OccSymbol::parse(&position.symbol).is_ok()
+}
+
+/// Outcome of [`AlpacaHttpClient::liquidate_portfolio`].
+///
+/// [`AlpacaHttpClient::liquidate_portfolio`]: crate::AlpacaHttpClient::liquidate_portfolio
+#[derive(Debug, Default)]
+pub struct LiquidationReport {
+    /// Number of open orders cancelled.
+    pub orders_cancelled: usize,
+    /// Closing orders placed, in submission order.
+    pub orders: Vec<Order>,
+    /// Symbols or order IDs that failed, with the error.
+    pub failures: Vec<(String, AlpacaError)>,
+    /// Positions still open when polling stopped.
+    pub remaining: Vec<Position>,
+    /// Time the liquidation took.
+    pub elapsed: Duration,
+}
+
+impl LiquidationReport {
+    /// Whether the account ended flat.
+    #[must_use]
+    pub fn is_flat(&self) -> bool {
+        self.remaining.is_empty()
+    }
+
+    /// Whether the account ended flat without any failure.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.is_flat() && self.failures.is_empty()
+    }
+}
+
+impl std::fmt::Display for LiquidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} orders cancelled, {} positions closed, {} remaining",
+            self.orders_cancelled,
+            self.orders.len(),
+            self.remaining.len()
+        )?;
+        if !self.failures.is_empty() {
+            write!(f, " ({} failed)", self.failures.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_position;
+
+    #[test]
+    fn test_liquidation_close_order() {
+        let with_pl = |symbol: &str, pl: &str| Position {
+            unrealized_pl: pl.to_string(),
+            ..sample_position(symbol, "1", "100")
+        };
+        let positions = vec![
+            with_pl("AAPL", "50"),
+            with_pl("MSFT", "-200"),
+            with_pl("AAPL240119C00190000", "10"),
+            with_pl("TSLA", "-20"),
+            with_pl("SPY240119P00400000", "-5"),
+        ];
+        let symbols = |options: LiquidationOptions| -> Vec<String> {
+            options
+                .close_order(positions.clone())
+                .into_iter()
+                .map(|p| p.symbol)
+                .collect()
+        };
+        assert_eq!(
+            symbols(LiquidationOptions::new()),
+            [
+                "SPY240119P00400000",
+                "AAPL240119C00190000",
+                "MSFT",
+                "TSLA",
+                "AAPL"
+            ]
+        );
+        assert_eq!(
+            symbols(LiquidationOptions::new().options_first(false)),
+            [
+                "MSFT",
+                "TSLA",
+                "SPY240119P00400000",
+                "AAPL240119C00190000",
+                "AAPL"
+            ]
+        );
+        assert_eq!(
+            symbols(
+                LiquidationOptions::new()
+                    .options_first(false)
+                    .largest_loss_first(false)
+            ),
+            [
+                "AAPL",
+                "AAPL240119C00190000",
+                "MSFT",
+                "SPY240119P00400000",
+                "TSLA"
+            ]
+        );
+
+        let report = LiquidationReport {
+            orders_cancelled: 2,
+            remaining: vec![with_pl("TSLA", "0")],
+            failures: vec![("TSLA".to_string(), AlpacaError::api(403, "forbidden"))],
+            ..LiquidationReport::default()
+        };
+        assert!(!report.is_flat());
+        assert_eq!(
+            report.to_string(),
+            "2 orders cancelled, 0 positions closed, 1 remaining (1 failed)"
+        );
+    }
+}
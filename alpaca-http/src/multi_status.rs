@@ -0,0 +1,195 @@
+//! Per-item results of multi-status (HTTP 207) responses.
+//!
+//! Bulk endpoints such as cancelling all orders or closing all positions
+//! answer with one status per order or symbol: some items can succeed
+//! while others fail (e.g. a 500 for one symbol). [`MultiStatus`] turns
+//! that payload into typed results, partitions it into successes and
+//! failures, and retries just the failures with
+//! [`MultiStatus::retry_failed`].
+
+use crate::endpoints::{CancelOrderResponse, ClosePositionResponse};
+use alpaca_base::{AlpacaError, ApiErrorCode, ApiErrorResponse, Result, types::Order};
+use std::future::Future;
+use uuid::Uuid;
+
+/// Outcome of one item of a multi-status response.
+#[derive(Debug)]
+pub struct MultiStatusItem<K> {
+    /// The item the status refers to: an order ID or a symbol.
+    pub key: K,
+    /// HTTP status of the item.
+    pub status: u16,
+    /// The order returned for the item, if any, or the item's error.
+    pub outcome: Result<Option<Order>>,
+}
+
+impl<K> MultiStatusItem<K> {
+    /// Decode an item from its status and body.
+    ///
+    /// A successful body is read as the affected order; a failed body as
+    /// an API error, classified like a failed request.
+    #[must_use]
+    pub fn new(key: K, status: u16, body: Option<serde_json::Value>) -> Self {
+        let outcome = if (200..300).contains(&status) {
+            Ok(body.and_then(|body| serde_json::from_value::<Order>(body).ok()))
+        } else {
+            let error = body
+                .and_then(|body| serde_json::from_value::<ApiErrorResponse>(body).ok())
+                .unwrap_or_else(|| ApiErrorResponse::new(0, format!("item failed with {status}")));
+            let code = (error.code > 0).then(|| ApiErrorCode::from_code(error.code));
+            Err(AlpacaError::api_response(
+                status,
+                code,
+                error.message,
+                error.request_id,
+            ))
+        };
+        Self {
+            key,
+            status,
+            outcome,
+        }
+    }
+
+    /// Whether the item succeeded.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+/// Typed results of a multi-status response, in response order.
+#[derive(Debug)]
+pub struct MultiStatus<K> {
+    /// Per-item results.
+    pub items: Vec<MultiStatusItem<K>>,
+}
+
+impl<K> MultiStatus<K> {
+    /// Items that succeeded.
+    pub fn succeeded(&self) -> impl Iterator<Item = &MultiStatusItem<K>> {
+        self.items.iter().filter(|item| item.is_ok())
+    }
+
+    /// Items that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &MultiStatusItem<K>> {
+        self.items.iter().filter(|item| !item.is_ok())
+    }
+
+    /// Keys of the items that failed.
+    #[must_use]
+    pub fn failed_keys(&self) -> Vec<&K> {
+        self.failed().map(|item| &item.key).collect()
+    }
+
+    /// Whether every item succeeded.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.items.iter().all(MultiStatusItem::is_ok)
+    }
+
+    /// Split into succeeded and failed items.
+    #[must_use]
+    pub fn partition(self) -> (Vec<MultiStatusItem<K>>, Vec<MultiStatusItem<K>>) {
+        self.items.into_iter().partition(MultiStatusItem::is_ok)
+    }
+
+    /// Retry the failed items whose error is retryable (rate limits,
+    /// server errors, network failures) with `retry`, one at a time.
+    ///
+    /// Other items are kept as they are, so e.g. a cancel rejected because
+    /// the order already filled is not sent again.
+    pub async fn retry_failed<F, Fut>(mut self, mut retry: F) -> Self
+    where
+        F: FnMut(&K) -> Fut,
+        Fut: Future<Output = Result<Option<Order>>>,
+    {
+        for item in &mut self.items {
+            if !matches!(&item.outcome, Err(e) if e.is_retryable()) {
+                continue;
+            }
+            let outcome = retry(&item.key).await;
+            item.status = match &outcome {
+                Ok(_) => 200,
+                Err(e) => e.status_code().unwrap_or(item.status),
+            };
+            item.outcome = outcome;
+        }
+        self
+    }
+}
+
+impl From<CancelOrderResponse> for MultiStatusItem<Uuid> {
+    fn from(response: CancelOrderResponse) -> Self {
+        Self::new(response.id, response.status, response.body)
+    }
+}
+
+impl From<ClosePositionResponse> for MultiStatusItem<String> {
+    fn from(response: ClosePositionResponse) -> Self {
+        Self::new(response.symbol, response.status, response.body)
+    }
+}
+
+impl<K, R: Into<MultiStatusItem<K>>> From<Vec<R>> for MultiStatus<K> {
+    fn from(responses: Vec<R>) -> Self {
+        Self {
+            items: responses.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_order;
+    use alpaca_base::types::OrderSide;
+
+    #[tokio::test]
+    async fn test_multi_status_partition_and_retry() {
+        let order = sample_order("AAPL", OrderSide::Sell, "10");
+        let responses: Vec<ClosePositionResponse> = serde_json::from_value(serde_json::json!([
+            {"symbol": "AAPL", "status": 200, "body": order},
+            {"symbol": "MSFT", "status": 500, "body": {"code": 50010000, "message": "internal error"}},
+            {"symbol": "TSLA", "status": 403, "body": {"code": 40310000, "message": "insufficient qty available for order"}}
+        ]))
+        .unwrap();
+        let results = MultiStatus::from(responses);
+        assert!(!results.is_complete());
+        assert_eq!(results.succeeded().count(), 1);
+        assert_eq!(
+            results.failed_keys(),
+            [&"MSFT".to_string(), &"TSLA".to_string()]
+        );
+        assert_eq!(
+            results.items[0]
+                .outcome
+                .as_ref()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .id,
+            order.id
+        );
+        assert!(
+            results.items[2]
+                .outcome
+                .as_ref()
+                .unwrap_err()
+                .is_insufficient_funds()
+        );
+
+        let mut retried = Vec::new();
+        let results = results
+            .retry_failed(|symbol: &String| {
+                retried.push(symbol.clone());
+                async { Ok(None) }
+            })
+            .await;
+        assert_eq!(retried, ["MSFT"]);
+        let (succeeded, failed) = results.partition();
+        assert_eq!(succeeded.len(), 2);
+        assert_eq!(succeeded[1].status, 200);
+        assert_eq!(failed[0].key, "TSLA");
+    }
+}
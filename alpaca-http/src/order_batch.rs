@@ -0,0 +1,203 @@
+//! Concurrent order submission.
+//!
+//! [`AlpacaHttpClient::create_orders_batch`] submits many orders at once,
+//! at most [`OrderBatchConfig::max_concurrency`] in flight, and reports the
+//! result of each order by its index in the input. A rate-limited order
+//! (HTTP 429) was never accepted, so it is retried after the server's
+//! `Retry-After`; until then every other order in the batch holds off too,
+//! rather than piling more requests onto an exhausted limit. Other
+//! failures are reported, not retried.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::CreateOrderRequest;
+use alpaca_base::{AlpacaError, Backoff, Result, RetryPolicy, types::Order};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::{Instant, sleep_until};
+
+/// Settings for [`AlpacaHttpClient::create_orders_batch_with_config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderBatchConfig {
+    /// Maximum number of orders in flight.
+    pub max_concurrency: usize,
+    /// Maximum submissions per order while rate limited, including the
+    /// first.
+    pub max_attempts: u32,
+}
+
+impl Default for OrderBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 5,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl OrderBatchConfig {
+    /// Create a config with 5 orders in flight and 5 attempts per order.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of orders in flight.
+    #[must_use]
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Set the maximum submissions per order while rate limited.
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+}
+
+/// Result of one order in a batch.
+#[derive(Debug)]
+pub struct OrderBatchResult {
+    /// Index of the order in the submitted batch.
+    pub index: usize,
+    /// Symbol.
+    pub symbol: String,
+    /// Client order ID, if one was set.
+    pub client_order_id: Option<String>,
+    /// The accepted order, or the last error.
+    pub result: Result<Order>,
+}
+
+impl OrderBatchResult {
+    /// Whether the order was accepted.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Results of a batch, one per order in submission order.
+#[derive(Debug, Default)]
+pub struct OrderBatchReport {
+    /// Per-order results.
+    pub results: Vec<OrderBatchResult>,
+}
+
+impl OrderBatchReport {
+    /// Whether every order was accepted.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.results.iter().all(OrderBatchResult::is_ok)
+    }
+
+    /// Orders that were accepted.
+    pub fn succeeded(&self) -> impl Iterator<Item = (usize, &Order)> {
+        self.results
+            .iter()
+            .filter_map(|r| r.result.as_ref().ok().map(|order| (r.index, order)))
+    }
+
+    /// Orders that failed.
+    pub fn failed(&self) -> impl Iterator<Item = (usize, &AlpacaError)> {
+        self.results
+            .iter()
+            .filter_map(|r| r.result.as_ref().err().map(|error| (r.index, error)))
+    }
+}
+
+/// Submit `orders` through `client` under `config`.
+pub(crate) async fn submit_orders(
+    client: &AlpacaHttpClient,
+    orders: Vec<CreateOrderRequest>,
+    config: &OrderBatchConfig,
+) -> OrderBatchReport {
+    let permits = Arc::new(Semaphore::new(config.max_concurrency.max(1)));
+    let resume_at = Arc::new(Mutex::new(Instant::now()));
+    let policy = RetryPolicy::new()
+        .max_attempts(config.max_attempts)
+        .backoff(Backoff::new(
+            Duration::from_millis(500),
+            Duration::from_secs(30),
+        ))
+        .retry_if(|e| matches!(e, AlpacaError::RateLimit { .. }));
+
+    let count = orders.len();
+    let mut tasks = JoinSet::new();
+    for (index, order) in orders.into_iter().enumerate() {
+        let client = client.clone();
+        let permits = Arc::clone(&permits);
+        let resume_at = Arc::clone(&resume_at);
+        let policy = policy.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let result = policy
+                .retry(|_| async {
+                    let until = *resume_at.lock().await;
+                    sleep_until(until).await;
+                    let result = client.create_order(&order).await;
+                    if let Err(AlpacaError::RateLimit {
+                        retry_after_secs, ..
+                    }) = &result
+                    {
+                        let mut resume_at = resume_at.lock().await;
+                        let until = Instant::now() + Duration::from_secs(*retry_after_secs);
+                        *resume_at = (*resume_at).max(until);
+                    }
+                    result
+                })
+                .await;
+            OrderBatchResult {
+                index,
+                symbol: order.symbol,
+                client_order_id: order.client_order_id,
+                result,
+            }
+        });
+    }
+
+    let mut results = Vec::with_capacity(count);
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => tracing::error!("order batch task failed: {}", e),
+        }
+    }
+    results.sort_by_key(|r| r.index);
+    OrderBatchReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_batch_report() {
+        let report = OrderBatchReport {
+            results: vec![
+                OrderBatchResult {
+                    index: 0,
+                    symbol: "AAPL".to_string(),
+                    client_order_id: None,
+                    result: Err(AlpacaError::rate_limit(1)),
+                },
+                OrderBatchResult {
+                    index: 1,
+                    symbol: "MSFT".to_string(),
+                    client_order_id: None,
+                    result: Err(AlpacaError::api(422, "insufficient buying power")),
+                },
+            ],
+        };
+        assert!(!report.is_complete());
+        assert_eq!(report.succeeded().count(), 0);
+        let failed: Vec<usize> = report.failed().map(|(i, _)| i).collect();
+        assert_eq!(failed, vec![0, 1]);
+
+        let config = OrderBatchConfig::new().max_concurrency(0).max_attempts(2);
+        assert_eq!(config.max_concurrency, 1);
+        assert_eq!(config.max_attempts, 2);
+    }
+}
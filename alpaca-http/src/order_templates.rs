@@ -0,0 +1,368 @@
+//! Declarative order templates with parameter substitution.
+//!
+//! Strategies often submit the same order shapes over and over, e.g. a
+//! "scalp entry" limit order with a bracket one risk unit below and two
+//! above. An [`OrderTemplate`] describes such a shape once, with price
+//! fields written as expressions over named parameters:
+//!
+//! ```json
+//! {
+//!   "scalp_entry": {
+//!     "side": "buy",
+//!     "type": "limit",
+//!     "limit_price": "{price}",
+//!     "take_profit": "{price} + 2*{r}",
+//!     "stop_loss": "{price} - {r}"
+//!   }
+//! }
+//! ```
+//!
+//! [`OrderTemplates::instantiate`] substitutes the symbol, quantity and
+//! numeric parameters at runtime and returns a [`CreateOrderRequest`] that
+//! has passed [`CreateOrderRequest::validate`].
+//!
+//! Expressions are sums of terms separated by `+` or `-`, where each term is
+//! a number, a `{parameter}`, or a product of the two (`2*{r}`).
+
+use crate::endpoints::CreateOrderRequest;
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{OrderClass, OrderSide, OrderType, StopLoss, TakeProfit, TimeInForce},
+    utils::{format_price, format_qty},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// A reusable order shape with parameterized prices.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderTemplate {
+    /// Order side.
+    pub side: OrderSide,
+    /// Order type.
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    /// Time in force.
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    /// Quantity expression; defaults to the `qty` parameter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qty: Option<String>,
+    /// Limit price expression.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<String>,
+    /// Stop price expression.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_price: Option<String>,
+    /// Trail price expression for trailing stops.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trail_price: Option<String>,
+    /// Trail percent expression for trailing stops.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trail_percent: Option<String>,
+    /// Take-profit limit price expression. With `stop_loss`, makes the
+    /// order a bracket unless `order_class` says otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<String>,
+    /// Stop-loss stop price expression.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<String>,
+    /// Stop-loss limit price expression (stop-limit exit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss_limit: Option<String>,
+    /// Explicit order class.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_class: Option<OrderClass>,
+    /// Allow extended hours trading.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extended_hours: Option<bool>,
+}
+
+impl OrderTemplate {
+    /// Build an order from this template.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if an expression references a
+    /// missing parameter or cannot be parsed, or if the resulting order
+    /// fails validation.
+    pub fn instantiate(&self, params: &TemplateParams) -> Result<CreateOrderRequest> {
+        let price = |expr: &Option<String>| -> Result<Option<String>> {
+            expr.as_deref()
+                .map(|e| evaluate(e, &params.values).map(format_price))
+                .transpose()
+        };
+
+        let qty = match &self.qty {
+            Some(expr) => format_qty(evaluate(expr, &params.values)?),
+            None => params
+                .qty
+                .clone()
+                .or_else(|| params.values.get("qty").map(|q| format_qty(*q)))
+                .ok_or_else(|| {
+                    AlpacaError::InvalidData("missing template parameter: qty".to_string())
+                })?,
+        };
+
+        let take_profit = price(&self.take_profit)?.map(TakeProfit::new);
+        let stop_loss = match (price(&self.stop_loss)?, price(&self.stop_loss_limit)?) {
+            (Some(stop), Some(limit)) => Some(StopLoss::with_limit(stop, limit)),
+            (Some(stop), None) => Some(StopLoss::new(stop)),
+            (None, _) => None,
+        };
+        let order_class = self
+            .order_class
+            .clone()
+            .or(match (&take_profit, &stop_loss) {
+                (Some(_), Some(_)) => Some(OrderClass::Bracket),
+                (None, Some(_)) => Some(OrderClass::Oto),
+                _ => None,
+            });
+
+        let order = CreateOrderRequest {
+            symbol: params.symbol.clone(),
+            qty: Some(qty),
+            side: self.side.clone(),
+            order_type: self.order_type.clone(),
+            time_in_force: self.time_in_force.clone(),
+            limit_price: price(&self.limit_price)?,
+            stop_price: price(&self.stop_price)?,
+            trail_price: price(&self.trail_price)?,
+            trail_percent: price(&self.trail_percent)?,
+            extended_hours: self.extended_hours,
+            client_order_id: params.client_order_id.clone(),
+            order_class,
+            take_profit,
+            stop_loss,
+            ..Default::default()
+        };
+        order.validate()?;
+        Ok(order)
+    }
+}
+
+/// Runtime parameters substituted into a template.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateParams {
+    /// Symbol to trade.
+    pub symbol: String,
+    /// Quantity, used when the template has no `qty` expression.
+    pub qty: Option<String>,
+    /// Client order ID for the instantiated order.
+    pub client_order_id: Option<String>,
+    /// Named numeric parameters referenced as `{name}` in expressions.
+    pub values: HashMap<String, f64>,
+}
+
+impl TemplateParams {
+    /// Create parameters for `symbol`.
+    #[must_use]
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the order quantity.
+    #[must_use]
+    pub fn qty(mut self, qty: impl Into<String>) -> Self {
+        self.qty = Some(qty.into());
+        self
+    }
+
+    /// Set the reference price, available as `{price}`.
+    #[must_use]
+    pub fn price(self, price: f64) -> Self {
+        self.set("price", price)
+    }
+
+    /// Set a named parameter.
+    #[must_use]
+    pub fn set(mut self, name: &str, value: f64) -> Self {
+        self.values.insert(name.to_string(), value);
+        self
+    }
+
+    /// Set the client order ID.
+    #[must_use]
+    pub fn client_order_id(mut self, id: impl Into<String>) -> Self {
+        self.client_order_id = Some(id.into());
+        self
+    }
+}
+
+/// A named collection of order templates, typically loaded from config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OrderTemplates {
+    templates: BTreeMap<String, OrderTemplate>,
+}
+
+impl OrderTemplates {
+    /// Create an empty collection.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse templates from a JSON object keyed by template name.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Load templates from a JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| AlpacaError::Config(format!("failed to read {}: {e}", path.display())))?;
+        Self::from_json(&json)
+    }
+
+    /// Add or replace a template.
+    pub fn insert(&mut self, name: impl Into<String>, template: OrderTemplate) {
+        self.templates.insert(name.into(), template);
+    }
+
+    /// Get a template by name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&OrderTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Template names in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    /// Instantiate the named template.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] for an unknown template or any
+    /// error from [`OrderTemplate::instantiate`].
+    pub fn instantiate(&self, name: &str, params: &TemplateParams) -> Result<CreateOrderRequest> {
+        self.get(name)
+            .ok_or_else(|| AlpacaError::InvalidData(format!("unknown order template: {name}")))?
+            .instantiate(params)
+    }
+}
+
+/// Evaluate a template expression against named parameters.
+fn evaluate(expr: &str, values: &HashMap<String, f64>) -> Result<f64> {
+    let invalid =
+        |msg: &str| AlpacaError::InvalidData(format!("invalid expression {expr:?}: {msg}"));
+
+    let mut total = 0.0;
+    let mut sign = 1.0;
+    let mut term = String::new();
+    let mut terms = Vec::new();
+    for c in expr.chars().filter(|c| !c.is_whitespace()) {
+        if (c == '+' || c == '-') && !term.is_empty() {
+            terms.push((sign, std::mem::take(&mut term)));
+            sign = if c == '-' { -1.0 } else { 1.0 };
+        } else if (c == '+' || c == '-') && term.is_empty() {
+            sign *= if c == '-' { -1.0 } else { 1.0 };
+        } else {
+            term.push(c);
+        }
+    }
+    if term.is_empty() {
+        return Err(invalid("empty term"));
+    }
+    terms.push((sign, term));
+
+    for (sign, term) in terms {
+        let mut product = 1.0;
+        for factor in term.split('*') {
+            product *= match factor.strip_prefix('{').and_then(|f| f.strip_suffix('}')) {
+                Some(name) => *values.get(name).ok_or_else(|| {
+                    AlpacaError::InvalidData(format!("missing template parameter: {name}"))
+                })?,
+                None => factor
+                    .parse::<f64>()
+                    .map_err(|_| invalid(&format!("bad factor {factor:?}")))?,
+            };
+        }
+        total += sign * product;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "scalp_entry": {
+            "side": "buy",
+            "type": "limit",
+            "limit_price": "{price}",
+            "take_profit": "{price} + 2*{r}",
+            "stop_loss": "{price} - {r}"
+        },
+        "half_out": {
+            "side": "sell",
+            "type": "market",
+            "time_in_force": "gtc",
+            "qty": "{position} * 0.5"
+        }
+    }"#;
+
+    #[test]
+    fn test_evaluate_expressions() {
+        let values = HashMap::from([("price".to_string(), 100.0), ("r".to_string(), 0.5)]);
+        assert_eq!(evaluate("{price}", &values).unwrap(), 100.0);
+        assert_eq!(evaluate("{price} + 2*{r}", &values).unwrap(), 101.0);
+        assert_eq!(evaluate("{price}-{r} - 0.25", &values).unwrap(), 99.25);
+        assert_eq!(evaluate("-1.5*{r}", &values).unwrap(), -0.75);
+        assert!(evaluate("{missing}", &values).is_err());
+        assert!(evaluate("{price} +", &values).is_err());
+        assert!(evaluate("abc", &values).is_err());
+    }
+
+    #[test]
+    fn test_instantiate_bracket_template() {
+        let templates = OrderTemplates::from_json(CONFIG).unwrap();
+        assert_eq!(
+            templates.names().collect::<Vec<_>>(),
+            ["half_out", "scalp_entry"]
+        );
+
+        let params = TemplateParams::new("AAPL")
+            .qty("10")
+            .price(187.3)
+            .set("r", 0.4);
+        let order = templates.instantiate("scalp_entry", &params).unwrap();
+        assert_eq!(order.symbol, "AAPL");
+        assert_eq!(order.qty.as_deref(), Some("10"));
+        assert_eq!(order.limit_price.as_deref(), Some("187.30"));
+        assert_eq!(order.order_class, Some(OrderClass::Bracket));
+        assert_eq!(order.take_profit.unwrap().limit_price, "188.10");
+        assert_eq!(order.stop_loss.unwrap().stop_price, "186.90");
+
+        let order = templates
+            .instantiate(
+                "half_out",
+                &TemplateParams::new("AAPL").set("position", 30.0),
+            )
+            .unwrap();
+        assert_eq!(order.qty.as_deref(), Some("15"));
+        assert_eq!(order.time_in_force, TimeInForce::Gtc);
+    }
+
+    #[test]
+    fn test_instantiate_rejects_invalid_orders() {
+        let templates = OrderTemplates::from_json(CONFIG).unwrap();
+        // Negative risk flips the bracket legs to the wrong side.
+        let params = TemplateParams::new("AAPL")
+            .qty("10")
+            .price(100.0)
+            .set("r", -1.0);
+        assert!(templates.instantiate("scalp_entry", &params).is_err());
+        assert!(
+            templates
+                .instantiate("scalp_entry", &TemplateParams::new("AAPL").qty("1"))
+                .is_err()
+        );
+        assert!(templates.instantiate("nope", &params).is_err());
+    }
+}
@@ -0,0 +1,482 @@
+//! Daily and periodic P&L reports.
+//!
+//! [`ReportBuilder`] combines three sources into a [`PnlReport`]:
+//!
+//! - account activities: fills give realized P&L, fee activities the fees
+//!   and dividend activities the dividends (net of withholding);
+//! - current positions: unrealized P&L per symbol;
+//! - portfolio history: equity and account P&L per day.
+//!
+//! Realized P&L relieves lots first-in first-out, long and short. Fills
+//! before the report period are replayed to know the cost of positions
+//! opened earlier but closed within it, so the activity history should
+//! reach back to when those positions were opened (see
+//! [`ReportBuilder::history_start`]). Reports export to JSON or CSV.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::PortfolioHistoryParams;
+use alpaca_base::{
+    Result,
+    types::{
+        Activity, ActivityType, ListActivitiesParams, OrderSide, PortfolioHistory, Position,
+        SortDirection, TradeActivity,
+    },
+    utils::{QTY_EPSILON, parse_decimal, us_eastern_offset},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Write;
+
+/// Page size used when fetching activities.
+const ACTIVITY_PAGE_SIZE: u32 = 100;
+
+/// P&L of one symbol over a day or the whole period. Fees and dividends
+/// not tied to a symbol are reported under an empty symbol.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SymbolPnl {
+    /// Symbol.
+    pub symbol: String,
+    /// Realized P&L from closing fills.
+    pub realized: f64,
+    /// Unrealized P&L of the open position; only set in period totals.
+    pub unrealized: f64,
+    /// Fees paid, as a positive amount.
+    pub fees: f64,
+    /// Dividends received, net of withholding.
+    pub dividends: f64,
+}
+
+impl SymbolPnl {
+    /// Realized plus unrealized P&L and dividends, less fees.
+    #[must_use]
+    pub fn net(&self) -> f64 {
+        self.realized + self.unrealized + self.dividends - self.fees
+    }
+
+    fn add(&mut self, other: &SymbolPnl) {
+        self.realized += other.realized;
+        self.unrealized += other.unrealized;
+        self.fees += other.fees;
+        self.dividends += other.dividends;
+    }
+}
+
+/// P&L of one trading day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyPnl {
+    /// Date (US Eastern).
+    pub date: NaiveDate,
+    /// Closing equity from the portfolio history.
+    pub equity: Option<f64>,
+    /// Account P&L from the portfolio history.
+    pub profit_loss: Option<f64>,
+    /// Per-symbol realized P&L, fees and dividends, ordered by symbol.
+    pub symbols: Vec<SymbolPnl>,
+}
+
+impl DailyPnl {
+    /// The day's totals across symbols.
+    #[must_use]
+    pub fn total(&self) -> SymbolPnl {
+        total(&self.symbols)
+    }
+}
+
+/// P&L over a period, per day and per symbol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PnlReport {
+    /// When the report was generated.
+    pub generated_at: DateTime<Utc>,
+    /// First day of the period.
+    pub start: NaiveDate,
+    /// Last day of the period.
+    pub end: NaiveDate,
+    /// Days with activity or portfolio history, in date order.
+    pub days: Vec<DailyPnl>,
+    /// Period totals per symbol, with unrealized P&L of current positions.
+    pub symbols: Vec<SymbolPnl>,
+}
+
+impl PnlReport {
+    /// Build a report for `start..=end` from fetched data. `activities`
+    /// may reach back before `start`; only their effect within the period
+    /// is reported.
+    #[must_use]
+    pub fn from_parts(
+        start: NaiveDate,
+        end: NaiveDate,
+        activities: &[Activity],
+        positions: &[Position],
+        history: Option<&PortfolioHistory>,
+        generated_at: DateTime<Utc>,
+    ) -> Self {
+        let mut days: BTreeMap<NaiveDate, BTreeMap<String, SymbolPnl>> = BTreeMap::new();
+        let in_period = |date: NaiveDate| date >= start && date <= end;
+        let mut fills: Vec<&TradeActivity> =
+            activities.iter().filter_map(Activity::as_trade).collect();
+        fills.sort_by_key(|f| f.transaction_time);
+        let mut lots = FifoLots::default();
+        for fill in fills {
+            let (Ok(qty), Ok(price)) = (parse_decimal(&fill.qty), parse_decimal(&fill.price))
+            else {
+                continue;
+            };
+            let realized = lots.fill(&fill.symbol, &fill.side, qty, price);
+            let date = eastern_date(fill.transaction_time);
+            if in_period(date) {
+                symbol_entry(days.entry(date).or_default(), &fill.symbol).realized += realized;
+            }
+        }
+
+        for activity in activities.iter().filter_map(Activity::as_non_trade) {
+            let kind = classify(&activity.activity_type);
+            let (Some(kind), Some(date), Ok(amount)) = (
+                kind,
+                activity.date.get(..10).and_then(|d| d.parse().ok()),
+                parse_decimal(&activity.net_amount),
+            ) else {
+                continue;
+            };
+            let symbol = activity.symbol.as_deref().unwrap_or_default();
+            if in_period(date) {
+                let pnl = symbol_entry(days.entry(date).or_default(), symbol);
+                match kind {
+                    CashKind::Fee => pnl.fees -= amount,
+                    CashKind::Dividend => pnl.dividends += amount,
+                }
+            }
+        }
+
+        let mut history_days: BTreeMap<NaiveDate, (Option<f64>, Option<f64>)> = BTreeMap::new();
+        if let Some(history) = history {
+            for (i, timestamp) in history.timestamp.iter().enumerate() {
+                let Some(time) = DateTime::from_timestamp(*timestamp, 0) else {
+                    continue;
+                };
+                let date = eastern_date(time);
+                if in_period(date) {
+                    history_days.insert(
+                        date,
+                        (
+                            history.equity.get(i).copied().flatten(),
+                            history.profit_loss.get(i).copied().flatten(),
+                        ),
+                    );
+                    days.entry(date).or_default();
+                }
+            }
+        }
+
+        let mut totals: BTreeMap<String, SymbolPnl> = BTreeMap::new();
+        let days = days
+            .into_iter()
+            .map(|(date, symbols)| {
+                for pnl in symbols.values() {
+                    symbol_entry(&mut totals, &pnl.symbol).add(pnl);
+                }
+                let (equity, profit_loss) = history_days.get(&date).copied().unwrap_or_default();
+                DailyPnl {
+                    date,
+                    equity,
+                    profit_loss,
+                    symbols: symbols.into_values().collect(),
+                }
+            })
+            .collect();
+        for position in positions {
+            let unrealized = parse_decimal(&position.unrealized_pl).unwrap_or(0.0);
+            symbol_entry(&mut totals, &position.symbol).unrealized += unrealized;
+        }
+
+        Self {
+            generated_at,
+            start,
+            end,
+            days,
+            symbols: totals.into_values().collect(),
+        }
+    }
+
+    /// The period's totals across symbols.
+    #[must_use]
+    pub fn total(&self) -> SymbolPnl {
+        total(&self.symbols)
+    }
+
+    /// Serialize the report as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Render the report as CSV: one row per day and symbol, then one
+    /// `total` row per symbol carrying the unrealized P&L.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("date,symbol,realized,unrealized,fees,dividends,net\n");
+        let mut row = |date: &str, pnl: &SymbolPnl| {
+            let _ = writeln!(
+                out,
+                "{},{},{:.2},{:.2},{:.2},{:.2},{:.2}",
+                date,
+                pnl.symbol,
+                pnl.realized,
+                pnl.unrealized,
+                pnl.fees,
+                pnl.dividends,
+                pnl.net()
+            );
+        };
+        for day in &self.days {
+            let date = day.date.to_string();
+            for pnl in &day.symbols {
+                row(&date, pnl);
+            }
+        }
+        for pnl in &self.symbols {
+            row("total", pnl);
+        }
+        out
+    }
+}
+
+fn symbol_entry<'a>(
+    symbols: &'a mut BTreeMap<String, SymbolPnl>,
+    symbol: &str,
+) -> &'a mut SymbolPnl {
+    symbols
+        .entry(symbol.to_string())
+        .or_insert_with(|| SymbolPnl {
+            symbol: symbol.to_string(),
+            ..SymbolPnl::default()
+        })
+}
+
+fn total(symbols: &[SymbolPnl]) -> SymbolPnl {
+    symbols.iter().fold(SymbolPnl::default(), |mut sum, pnl| {
+        sum.add(pnl);
+        sum
+    })
+}
+
+/// Cash activities counted in the report.
+enum CashKind {
+    Fee,
+    Dividend,
+}
+
+fn classify(activity_type: &ActivityType) -> Option<CashKind> {
+    match activity_type {
+        ActivityType::TransactionFee | ActivityType::Ptc | ActivityType::Divfee => {
+            Some(CashKind::Fee)
+        }
+        ActivityType::Div
+        | ActivityType::Divcgl
+        | ActivityType::Divcgs
+        | ActivityType::Divft
+        | ActivityType::Divnra
+        | ActivityType::Divroc
+        | ActivityType::Divtw
+        | ActivityType::Divtxex => Some(CashKind::Dividend),
+        _ => None,
+    }
+}
+
+/// Open lots per symbol as signed quantity and price, oldest first.
+#[derive(Default)]
+struct FifoLots(HashMap<String, VecDeque<(f64, f64)>>);
+
+impl FifoLots {
+    /// Apply a fill and return the P&L it realizes.
+    fn fill(&mut self, symbol: &str, side: &OrderSide, qty: f64, price: f64) -> f64 {
+        let mut remaining = match side {
+            OrderSide::Buy => qty,
+            OrderSide::Sell => -qty,
+        };
+        let lots = self.0.entry(symbol.to_string()).or_default();
+        let mut realized = 0.0;
+        while remaining.abs() > QTY_EPSILON {
+            let Some(lot) = lots
+                .front_mut()
+                .filter(|lot| lot.0.signum() != remaining.signum())
+            else {
+                break;
+            };
+            let closed = remaining.abs().min(lot.0.abs());
+            realized += (price - lot.1) * closed * lot.0.signum();
+            lot.0 -= closed * lot.0.signum();
+            remaining -= closed * remaining.signum();
+            if lot.0.abs() <= QTY_EPSILON {
+                lots.pop_front();
+            }
+        }
+        if remaining.abs() > QTY_EPSILON {
+            lots.push_back((remaining, price));
+        }
+        realized
+    }
+}
+
+/// The US Eastern calendar date of `time`.
+fn eastern_date(time: DateTime<Utc>) -> NaiveDate {
+    time.with_timezone(&us_eastern_offset(time.date_naive()))
+        .date_naive()
+}
+
+/// Builds [`PnlReport`]s from the account's activities, positions and
+/// portfolio history.
+#[derive(Debug, Clone)]
+pub struct ReportBuilder {
+    client: AlpacaHttpClient,
+    start: NaiveDate,
+    end: NaiveDate,
+    history_start: Option<NaiveDate>,
+}
+
+impl ReportBuilder {
+    /// Create a builder for the days `start..=end`, replaying the whole
+    /// fill history for cost basis.
+    #[must_use]
+    pub fn new(client: AlpacaHttpClient, start: NaiveDate, end: NaiveDate) -> Self {
+        Self {
+            client,
+            start,
+            end,
+            history_start: None,
+        }
+    }
+
+    /// Only fetch activities from `date` on. Positions opened before it
+    /// and closed within the period then lack their cost basis.
+    #[must_use]
+    pub fn history_start(mut self, date: NaiveDate) -> Self {
+        self.history_start = Some(date);
+        self
+    }
+
+    /// Fetch every activity up to the end of the period, following
+    /// pagination.
+    pub async fn fetch_activities(&self) -> Result<Vec<Activity>> {
+        let mut params = ListActivitiesParams::new()
+            .until(&self.end.succ_opt().unwrap_or(self.end).to_string())
+            .direction(SortDirection::Asc)
+            .page_size(ACTIVITY_PAGE_SIZE);
+        if let Some(date) = self.history_start {
+            params = params.after(&date.to_string());
+        }
+        let mut activities: Vec<Activity> = Vec::new();
+        loop {
+            let page: Vec<Activity> = self
+                .client
+                .get_with_params("/v2/account/activities", &params)
+                .await?;
+            let Some(last) = page.last() else { break };
+            params.page_token = Some(last.id().to_string());
+            let page_len = page.len();
+            activities.extend(page);
+            if page_len < ACTIVITY_PAGE_SIZE as usize {
+                break;
+            }
+        }
+        Ok(activities)
+    }
+
+    /// Fetch the data and build the report.
+    pub async fn build(&self) -> Result<PnlReport> {
+        let activities = self.fetch_activities().await?;
+        let positions = self.client.get_positions().await?;
+        let days = (self.end - self.start).num_days() + 1;
+        let history = self
+            .client
+            .get_portfolio_history(&PortfolioHistoryParams {
+                period: Some(format!("{days}D")),
+                timeframe: Some("1D".to_string()),
+                date_end: Some(self.end.to_string()),
+                extended_hours: None,
+            })
+            .await?;
+        Ok(PnlReport::from_parts(
+            self.start,
+            self.end,
+            &activities,
+            &positions,
+            Some(&history),
+            Utc::now(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_position;
+
+    #[test]
+    fn test_pnl_report_from_parts() {
+        let activities: Vec<Activity> = serde_json::from_value(serde_json::json!([
+            {"id": "1", "activity_type": "FILL", "transaction_time": "2024-06-03T14:00:00Z",
+             "symbol": "AAPL", "order_id": "61e69015-8549-4bfd-b9c3-01e75843f47d",
+             "side": "buy", "qty": "10", "price": "100"},
+            {"id": "2", "activity_type": "FILL", "transaction_time": "2024-06-04T14:00:00Z",
+             "symbol": "AAPL", "order_id": "61e69015-8549-4bfd-b9c3-01e75843f47e",
+             "side": "sell", "qty": "4", "price": "110"},
+            {"id": "3", "activity_type": "FILL", "transaction_time": "2024-06-04T15:00:00Z",
+             "symbol": "TSLA", "order_id": "61e69015-8549-4bfd-b9c3-01e75843f47f",
+             "side": "sell", "qty": "2", "price": "200"},
+            {"id": "4", "activity_type": "FILL", "transaction_time": "2024-06-05T15:00:00Z",
+             "symbol": "TSLA", "order_id": "61e69015-8549-4bfd-b9c3-01e75843f480",
+             "side": "buy", "qty": "2", "price": "190"},
+            {"id": "5", "activity_type": "DIV", "date": "2024-06-05", "net_amount": "3.50",
+             "symbol": "AAPL"},
+            {"id": "6", "activity_type": "TRANSACTION_FEE", "date": "2024-06-05",
+             "net_amount": "-0.25", "symbol": "TSLA"}
+        ]))
+        .unwrap();
+        let mut position = sample_position("AAPL", "6", "100");
+        position.unrealized_pl = "30".to_string();
+        let history = PortfolioHistory {
+            // 2024-06-04 and 2024-06-05 00:00 ET.
+            timestamp: vec![1_717_473_600, 1_717_560_000],
+            equity: vec![Some(10_040.0), Some(10_063.25)],
+            profit_loss: vec![Some(40.0), Some(23.25)],
+            profit_loss_pct: vec![None, None],
+            base_value: 10_000.0,
+            timeframe: "1D".to_string(),
+        };
+
+        let start = NaiveDate::from_ymd_opt(2024, 6, 4).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+        let report = PnlReport::from_parts(
+            start,
+            end,
+            &activities,
+            &[position],
+            Some(&history),
+            Utc::now(),
+        );
+
+        // The June 3 buy is before the period: only its cost basis counts.
+        assert_eq!(report.days.len(), 2);
+        assert_eq!(report.days[0].equity, Some(10_040.0));
+        assert_eq!(report.days[0].total().realized, 40.0);
+        assert_eq!(report.days[1].total().realized, 20.0);
+        let aapl = &report.symbols[0];
+        assert_eq!(
+            (aapl.realized, aapl.unrealized, aapl.dividends),
+            (40.0, 30.0, 3.5)
+        );
+        let tsla = &report.symbols[1];
+        assert_eq!((tsla.realized, tsla.fees), (20.0, 0.25));
+        assert_eq!(report.total().net(), 40.0 + 30.0 + 3.5 + 20.0 - 0.25);
+
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "date,symbol,realized,unrealized,fees,dividends,net"
+        );
+        assert_eq!(lines[1], "2024-06-04,AAPL,40.00,0.00,0.00,0.00,40.00");
+        assert_eq!(lines.last(), Some(&"total,TSLA,20.00,0.00,0.25,0.00,19.75"));
+        assert!(report.to_json().unwrap().contains("\"profit_loss\": 23.25"));
+    }
+}
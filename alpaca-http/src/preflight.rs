@@ -0,0 +1,139 @@
+//! Account-level checks before an order is submitted.
+//!
+//! [`CreateOrderRequest::validate`] checks an order on its own; some
+//! rejections depend on the account instead: trading blocked by Alpaca or
+//! suspended by the user, shorting disabled on the account or by the
+//! `no_shorting` configuration. The API reports these with generic 403s.
+//! [`AccountRestrictions`] captures the relevant flags from an [`Account`]
+//! and its [`AccountConfigurations`] and checks orders against them
+//! locally, and [`AlpacaHttpClient::preflight_order`] does so with the
+//! (cacheable) account responses, so the caller gets a precise error such
+//! as "account has no_shorting set" before any order is sent.
+//!
+//! [`AlpacaHttpClient::preflight_order`]: crate::AlpacaHttpClient::preflight_order
+
+use crate::endpoints::{AccountConfigurations, CreateOrderRequest};
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{Account, AccountStatus, OrderSide},
+};
+
+/// Account flags that can reject an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccountRestrictions {
+    /// The account is not active.
+    pub inactive: bool,
+    /// The account is blocked.
+    pub account_blocked: bool,
+    /// Trading is blocked by Alpaca.
+    pub trading_blocked: bool,
+    /// Trading is suspended by the user (`trade_suspended_by_user` or the
+    /// `suspend_trade` configuration).
+    pub trade_suspended: bool,
+    /// Shorting is not enabled for the account.
+    pub shorting_disabled: bool,
+    /// The `no_shorting` configuration is set.
+    pub no_shorting: bool,
+}
+
+impl AccountRestrictions {
+    /// Collect the restrictions of `account` and its `configurations`.
+    #[must_use]
+    pub fn new(account: &Account, configurations: Option<&AccountConfigurations>) -> Self {
+        let configured = |flag: fn(&AccountConfigurations) -> Option<bool>| {
+            configurations.and_then(flag).unwrap_or(false)
+        };
+        Self {
+            inactive: account.status != AccountStatus::Active,
+            account_blocked: account.account_blocked,
+            trading_blocked: account.trading_blocked,
+            trade_suspended: account.trade_suspended_by_user || configured(|c| c.suspend_trade),
+            shorting_disabled: !account.shorting_enabled,
+            no_shorting: configured(|c| c.no_shorting),
+        }
+    }
+
+    /// Check `order` against the restrictions. `position_qty` is the signed
+    /// quantity currently held in the order's symbol (negative when short);
+    /// a sell of more than is held long opens or extends a short.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] naming the restriction the
+    /// order would violate.
+    pub fn check(&self, order: &CreateOrderRequest, position_qty: f64) -> Result<()> {
+        let reject = |reason: &str| {
+            Err(AlpacaError::Validation(format!(
+                "{:?} order for {} rejected: {reason}",
+                order.side, order.symbol
+            )))
+        };
+        if self.inactive {
+            return reject("account is not active");
+        }
+        if self.account_blocked {
+            return reject("account is blocked");
+        }
+        if self.trading_blocked {
+            return reject("account has trading_blocked set");
+        }
+        if self.trade_suspended {
+            return reject("account has trading suspended by the user");
+        }
+        if order.side == OrderSide::Sell && opens_short(order, position_qty) {
+            if self.no_shorting {
+                return reject("account has no_shorting set");
+            }
+            if self.shorting_disabled {
+                return reject("shorting is not enabled for the account");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a sell `order` would leave the position short.
+fn opens_short(order: &CreateOrderRequest, position_qty: f64) -> bool {
+    match order.qty.as_deref().map(str::parse::<f64>) {
+        Some(Ok(qty)) => qty > position_qty.max(0.0),
+        // Notional sells cannot open shorts; they only reduce longs.
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_account;
+
+    #[test]
+    fn test_account_restrictions() {
+        let mut account = sample_account();
+        let sell = CreateOrderRequest::market("AAPL", OrderSide::Sell, "10");
+        let buy = CreateOrderRequest::market("AAPL", OrderSide::Buy, "10");
+        let configurations: AccountConfigurations = serde_json::from_value(serde_json::json!({
+            "dtbp_check": "both",
+            "trade_confirm_email": "all",
+            "suspend_trade": false,
+            "no_shorting": true
+        }))
+        .unwrap();
+
+        let restrictions = AccountRestrictions::new(&account, Some(&configurations));
+        assert!(restrictions.check(&buy, 0.0).is_ok());
+        assert!(restrictions.check(&sell, 10.0).is_ok());
+        let err = restrictions.check(&sell, 5.0).unwrap_err();
+        assert!(err.to_string().contains("account has no_shorting set"));
+
+        account.trading_blocked = true;
+        let err = AccountRestrictions::new(&account, None)
+            .check(&buy, 0.0)
+            .unwrap_err();
+        assert!(err.to_string().contains("trading_blocked"));
+
+        account.trading_blocked = false;
+        account.shorting_enabled = false;
+        let restrictions = AccountRestrictions::new(&account, None);
+        assert!(restrictions.shorting_disabled && !restrictions.no_shorting);
+        assert!(restrictions.check(&sell, -1.0).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+//! Commonly used, stable types: the [`alpaca_base::prelude`] plus the HTTP
+//! client and its order and query builders.
+//!
+//! ```
+//! use alpaca_http::prelude::*;
+//!
+//! let order = CreateOrderRequest::market("AAPL", OrderSide::Buy, "1");
+//! assert!(order.validate().is_ok());
+//! ```
+
+pub use crate::client::AlpacaHttpClient;
+pub use crate::endpoints::{
+    BarsParams, CancelOrdersFilter, ClosePositionRequest, CreateOrderRequest, OrderParams,
+    ReplaceOrderRequest,
+};
+pub use crate::error::HttpError;
+pub use alpaca_base::prelude::*;
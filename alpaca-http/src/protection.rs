@@ -0,0 +1,321 @@
+//! Protective exit orders for open positions.
+//!
+//! [`AlpacaHttpClient::protect_position`] keeps one set of exit orders on a
+//! position: an OCO (take-profit limit plus stop) when both a stop and a
+//! target are given, otherwise a single stop or limit order. A bracket
+//! needs an entry order, so it cannot be attached to a position that is
+//! already open; the OCO is its exit-only equivalent. Orders are sized to
+//! the whole position and sent GTC.
+//!
+//! Every open order on the exit side of the position counts as protection.
+//! If those orders already are exactly the requested protection the call
+//! changes nothing, so it is safe to repeat, e.g. on every fill or restart.
+//! Otherwise they are cancelled, and the new orders are submitted once the
+//! cancellations are confirmed, so the position is never protected twice.
+//!
+//! [`AlpacaHttpClient::protect_position`]: crate::AlpacaHttpClient::protect_position
+
+use crate::endpoints::CreateOrderRequest;
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{
+        Order, OrderClass, OrderSide, OrderStatus, OrderType, Position, PositionSide, StopLoss,
+        TakeProfit, TimeInForce,
+    },
+    utils::{format_price, format_qty},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Price tolerance when comparing existing orders with the request.
+const PRICE_EPSILON: f64 = 1e-6;
+
+/// Exit prices requested for a position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Protection {
+    /// Stop price.
+    pub stop: Option<f64>,
+    /// Take-profit limit price.
+    pub target: Option<f64>,
+}
+
+/// Result of [`AlpacaHttpClient::protect_position`].
+///
+/// [`AlpacaHttpClient::protect_position`]: crate::AlpacaHttpClient::protect_position
+#[derive(Debug, Clone)]
+pub enum ProtectionOutcome {
+    /// The existing orders already matched; nothing was changed.
+    Unchanged(Order),
+    /// New protection was submitted after cancelling the previous orders.
+    Placed {
+        /// The submitted order (the OCO parent for stop plus target).
+        order: Order,
+        /// IDs of the cancelled protective orders.
+        replaced: Vec<Uuid>,
+    },
+}
+
+impl ProtectionOutcome {
+    /// The order protecting the position.
+    #[must_use]
+    pub fn order(&self) -> &Order {
+        match self {
+            Self::Unchanged(order) | Self::Placed { order, .. } => order,
+        }
+    }
+
+    /// Whether new orders were submitted.
+    #[must_use]
+    pub fn is_changed(&self) -> bool {
+        matches!(self, Self::Placed { .. })
+    }
+}
+
+/// What to do about a position's protection.
+#[derive(Debug)]
+pub(crate) enum ProtectionPlan {
+    /// This existing order already is the requested protection.
+    Keep(Order),
+    /// Cancel these orders, then submit the request.
+    Replace {
+        cancel: Vec<Order>,
+        request: CreateOrderRequest,
+    },
+}
+
+impl Protection {
+    /// The exit order for `position`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Validation`] if neither price is set, the
+    /// position is flat, or a price is not on the protective side of the
+    /// other.
+    pub fn exit_order(&self, position: &Position) -> Result<CreateOrderRequest> {
+        let qty = position_qty(position)?;
+        let side = exit_side(position);
+        let qty = format_qty(qty);
+        let request = match (self.stop, self.target) {
+            (Some(stop), Some(target)) => {
+                let misplaced = match side {
+                    OrderSide::Sell => stop >= target,
+                    OrderSide::Buy => stop <= target,
+                };
+                if misplaced {
+                    return Err(AlpacaError::Validation(format!(
+                        "stop {stop} and target {target} are on the wrong sides for a {:?} position",
+                        position.side
+                    )));
+                }
+                CreateOrderRequest::oco(
+                    &position.symbol,
+                    side,
+                    qty,
+                    TakeProfit::new(format_price(target)),
+                    StopLoss::new(format_price(stop)),
+                )
+            }
+            (Some(stop), None) => {
+                CreateOrderRequest::stop(&position.symbol, side, qty, format_price(stop))
+            }
+            (None, Some(target)) => {
+                CreateOrderRequest::limit(&position.symbol, side, qty, format_price(target))
+            }
+            (None, None) => {
+                return Err(AlpacaError::Validation(
+                    "protection needs a stop, a target or both".to_string(),
+                ));
+            }
+        };
+        Ok(request.time_in_force(TimeInForce::Gtc))
+    }
+
+    /// Whether `order` is exactly this protection for `position`.
+    pub(crate) fn is_satisfied_by(&self, position: &Position, order: &Order) -> bool {
+        let Ok(qty) = position_qty(position) else {
+            return false;
+        };
+        if order.side != exit_side(position) || !same(order.qty.as_deref(), qty) {
+            return false;
+        }
+        match (self.stop, self.target) {
+            (Some(stop), Some(target)) => {
+                let stop_leg = order.legs.as_deref().unwrap_or_default();
+                order.order_class == OrderClass::Oco
+                    && order.order_type == OrderType::Limit
+                    && same(order.limit_price.as_deref(), target)
+                    && stop_leg.len() == 1
+                    && stop_leg[0].order_type == OrderType::Stop
+                    && same(stop_leg[0].stop_price.as_deref(), stop)
+            }
+            (Some(stop), None) => {
+                order.order_class == OrderClass::Simple
+                    && order.order_type == OrderType::Stop
+                    && same(order.stop_price.as_deref(), stop)
+            }
+            (None, Some(target)) => {
+                order.order_class == OrderClass::Simple
+                    && order.order_type == OrderType::Limit
+                    && same(order.limit_price.as_deref(), target)
+            }
+            (None, None) => false,
+        }
+    }
+
+    /// Decide whether `open_orders` (top-level orders with nested legs)
+    /// already protect `position` or need replacing.
+    pub(crate) fn plan(
+        &self,
+        position: &Position,
+        open_orders: &[Order],
+    ) -> Result<ProtectionPlan> {
+        let request = self.exit_order(position)?;
+        let side = exit_side(position);
+        let existing: Vec<Order> = open_orders
+            .iter()
+            .filter(|order| order.symbol == position.symbol && order.side == side)
+            .cloned()
+            .collect();
+        if let [order] = existing.as_slice()
+            && self.is_satisfied_by(position, order)
+        {
+            return Ok(ProtectionPlan::Keep(order.clone()));
+        }
+        Ok(ProtectionPlan::Replace {
+            cancel: existing,
+            request,
+        })
+    }
+}
+
+/// Whether an order status is final, i.e. the order no longer holds
+/// position quantity.
+pub(crate) fn is_closed(status: &OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::Filled
+            | OrderStatus::Canceled
+            | OrderStatus::Expired
+            | OrderStatus::Replaced
+            | OrderStatus::Rejected
+            | OrderStatus::DoneForDay
+    )
+}
+
+fn position_qty(position: &Position) -> Result<f64> {
+    let qty = position
+        .qty
+        .parse::<f64>()
+        .map_err(|_| AlpacaError::InvalidData(format!("bad position qty {:?}", position.qty)))?
+        .abs();
+    if qty == 0.0 {
+        return Err(AlpacaError::Validation(format!(
+            "no open position in {}",
+            position.symbol
+        )));
+    }
+    Ok(qty)
+}
+
+fn exit_side(position: &Position) -> OrderSide {
+    match position.side {
+        PositionSide::Long => OrderSide::Sell,
+        PositionSide::Short => OrderSide::Buy,
+    }
+}
+
+pub(crate) fn same(value: Option<&str>, expected: f64) -> bool {
+    value
+        .and_then(|value| value.parse::<f64>().ok())
+        .is_some_and(|value| (value - expected).abs() < PRICE_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_order;
+
+    fn position(qty: &str, side: PositionSide) -> Position {
+        serde_json::from_value(serde_json::json!({
+            "asset_id": Uuid::nil(),
+            "symbol": "AAPL",
+            "exchange": "NASDAQ",
+            "asset_class": "us_equity",
+            "avg_entry_price": "180",
+            "qty": qty,
+            "side": side,
+            "market_value": "0",
+            "cost_basis": "0",
+            "unrealized_pl": "0",
+            "unrealized_plpc": "0",
+            "unrealized_intraday_pl": "0",
+            "unrealized_intraday_plpc": "0",
+            "current_price": "190",
+            "lastday_price": "185",
+            "change_today": "0"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_protection_plan_is_idempotent() {
+        let long = position("100", PositionSide::Long);
+        let protection = Protection {
+            stop: Some(175.0),
+            target: Some(200.0),
+        };
+        let request = protection.exit_order(&long).unwrap();
+        assert_eq!(request.order_class, Some(OrderClass::Oco));
+        assert_eq!(request.side, OrderSide::Sell);
+        assert_eq!(request.qty.as_deref(), Some("100"));
+        assert_eq!(request.time_in_force, TimeInForce::Gtc);
+
+        let mut oco = sample_order("AAPL", OrderSide::Sell, "100");
+        oco.order_class = OrderClass::Oco;
+        oco.order_type = OrderType::Limit;
+        oco.limit_price = Some("200.00".to_string());
+        let mut stop = sample_order("AAPL", OrderSide::Sell, "100");
+        stop.order_type = OrderType::Stop;
+        stop.stop_price = Some("175.00".to_string());
+        oco.legs = Some(vec![stop]);
+        let entry = sample_order("AAPL", OrderSide::Buy, "50");
+
+        let orders = [oco.clone(), entry.clone()];
+        assert!(matches!(
+            protection.plan(&long, &orders).unwrap(),
+            ProtectionPlan::Keep(order) if order.id == oco.id
+        ));
+
+        let moved = Protection {
+            stop: Some(180.0),
+            ..protection
+        };
+        let ProtectionPlan::Replace { cancel, request } = moved.plan(&long, &orders).unwrap()
+        else {
+            panic!("a moved stop must replace the OCO");
+        };
+        assert_eq!(cancel.len(), 1);
+        assert_eq!(cancel[0].id, oco.id);
+        assert_eq!(request.stop_loss.unwrap().stop_price, "180.00");
+
+        let short = position("-100", PositionSide::Short);
+        assert!(protection.exit_order(&short).is_err());
+        let cover = Protection {
+            stop: Some(200.0),
+            target: None,
+        }
+        .exit_order(&short)
+        .unwrap();
+        assert_eq!(
+            (cover.side, cover.order_type),
+            (OrderSide::Buy, OrderType::Stop)
+        );
+        assert!(
+            Protection {
+                stop: None,
+                target: None
+            }
+            .exit_order(&long)
+            .is_err()
+        );
+    }
+}
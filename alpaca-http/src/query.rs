@@ -0,0 +1,209 @@
+//! Query string serialization for request parameters.
+//!
+//! Every parameter struct passed to
+//! [`AlpacaHttpClient::get_with_params`](crate::AlpacaHttpClient::get_with_params)
+//! is turned into a query string by [`QueryParams`], so all endpoints
+//! encode values the same way:
+//!
+//! - parameters are sorted by name, so equal queries encode identically;
+//! - `None` fields and empty lists are left out;
+//! - timestamps use their serde form, RFC 3339 (`2024-01-03T00:00:00Z`);
+//! - booleans are `true`/`false` and enums use their serde names;
+//! - lists are joined with commas (`symbols=AAPL,TSLA`) instead of being
+//!   rejected;
+//! - values are percent-encoded, except for `,` and `:`, which the API
+//!   accepts literally, so a `+` in a UTC offset arrives as `%2B` rather
+//!   than as a space.
+
+use alpaca_base::{AlpacaError, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// Ordered, encoded query parameters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryParams {
+    pairs: Vec<(String, String)>,
+}
+
+impl QueryParams {
+    /// Create an empty query.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a query from the fields of `params`, sorted by name.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Json`] if `params` does not serialize to a
+    /// map of scalars and lists of scalars.
+    pub fn from_serialize<P: Serialize + ?Sized>(params: &P) -> Result<Self> {
+        let value = serde_json::to_value(params)
+            .map_err(|e| AlpacaError::Json(format!("Failed to serialize query params: {}", e)))?;
+        let fields = match value {
+            Value::Null => return Ok(Self::new()),
+            Value::Object(fields) => fields,
+            other => {
+                return Err(AlpacaError::Json(format!(
+                    "Query params must be a struct or map, got {other}"
+                )));
+            }
+        };
+        let mut query = Self::new();
+        for (key, value) in fields {
+            if let Some(value) = query_value(&key, value)? {
+                query.pairs.push((key, value));
+            }
+        }
+        Ok(query)
+    }
+
+    /// Add a parameter.
+    #[must_use]
+    pub fn param(mut self, key: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.pairs.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Whether there are no parameters.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// The unencoded value of `key`.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// `url` with the query appended.
+    #[must_use]
+    pub fn append_to(&self, url: &str) -> String {
+        if self.is_empty() {
+            url.to_string()
+        } else {
+            format!("{url}?{self}")
+        }
+    }
+}
+
+impl fmt::Display for QueryParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (key, value)) in self.pairs.iter().enumerate() {
+            if i > 0 {
+                f.write_str("&")?;
+            }
+            write!(f, "{}={}", encode(key), encode(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// The query string form of one field, or `None` to leave it out.
+fn query_value(key: &str, value: Value) -> Result<Option<String>> {
+    Ok(match value {
+        Value::Null => None,
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s),
+        Value::Array(items) => {
+            let items = items
+                .into_iter()
+                .map(|item| match item {
+                    Value::Array(_) | Value::Object(_) => Err(AlpacaError::Json(format!(
+                        "Query param {key} has a nested value"
+                    ))),
+                    item => Ok(query_value(key, item)?.unwrap_or_default()),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            (!items.is_empty()).then(|| items.join(","))
+        }
+        Value::Object(_) => {
+            return Err(AlpacaError::Json(format!(
+                "Query param {key} has a nested value"
+            )));
+        }
+    })
+}
+
+/// Percent-encode everything but unreserved characters, `,` and `:`.
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b',' | b':' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::{BarsParams, OrderParams};
+    use alpaca_base::types::{DataFeed, OrderQueryStatus, OrderSide, SortDirection};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_query_params_known_urls() {
+        // GET /v2/orders example from the Trading API docs.
+        let params = OrderParams::new()
+            .status(OrderQueryStatus::Closed)
+            .limit(100)
+            .after(Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap())
+            .direction(SortDirection::Desc)
+            .nested(true)
+            .symbols("AAPL,TSLA")
+            .side(OrderSide::Sell);
+        assert_eq!(
+            QueryParams::from_serialize(&params)
+                .unwrap()
+                .append_to("https://paper-api.alpaca.markets/v2/orders"),
+            "https://paper-api.alpaca.markets/v2/orders?after=2024-01-03T00:00:00Z\
+             &direction=desc&limit=100&nested=true&side=sell&status=closed&symbols=AAPL,TSLA"
+        );
+
+        // GET /v2/stocks/{symbol}/bars example from the Market Data docs.
+        let params = BarsParams {
+            start: Some(Utc.with_ymd_and_hms(2024, 1, 3, 9, 30, 0).unwrap()),
+            timeframe: Some("1Day".to_string()),
+            limit: Some(1000),
+            feed: Some(DataFeed::Sip),
+            ..BarsParams::default()
+        };
+        assert_eq!(
+            QueryParams::from_serialize(&params).unwrap().to_string(),
+            "feed=sip&limit=1000&start=2024-01-03T09:30:00Z&timeframe=1Day"
+        );
+
+        #[derive(Serialize)]
+        struct Custom {
+            symbols: Vec<&'static str>,
+            types: Vec<&'static str>,
+            since: &'static str,
+            page_token: Option<String>,
+        }
+        let query = QueryParams::from_serialize(&Custom {
+            symbols: vec!["BTC/USD", "ETH/USD"],
+            types: vec![],
+            since: "2024-01-03T09:30:00+05:00",
+            page_token: None,
+        })
+        .unwrap();
+        assert_eq!(query.get("symbols"), Some("BTC/USD,ETH/USD"));
+        assert_eq!(
+            query.to_string(),
+            "since=2024-01-03T09:30:00%2B05:00&symbols=BTC%2FUSD,ETH%2FUSD"
+        );
+        assert!(QueryParams::from_serialize(&()).unwrap().is_empty());
+        assert!(QueryParams::from_serialize(&vec![1]).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Client-side rate limiting with strict request priorities.
+//!
+//! [`PriorityRateLimiter`] is a token bucket shared by every clone of an
+//! [`AlpacaHttpClient`](crate::AlpacaHttpClient). When the bucket is empty,
+//! requests queue and are released strictly by [`RequestPriority`], FIFO
+//! within a priority. A cancel or kill-switch liquidation therefore never
+//! waits behind queued data fetches: it only waits for the next token.
+
+use alpaca_base::types::{RateLimitConfig, RequestPriority};
+use reqwest::Method;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+type Ticket = (Reverse<RequestPriority>, u64);
+
+#[derive(Debug)]
+struct LimiterState {
+    tokens: f64,
+    last_refill: Instant,
+    queue: BTreeSet<Ticket>,
+    next_seq: u64,
+}
+
+/// Token-bucket rate limiter releasing queued requests by priority.
+#[derive(Debug)]
+pub struct PriorityRateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    state: Mutex<LimiterState>,
+    notify: Notify,
+}
+
+impl PriorityRateLimiter {
+    /// Create a limiter allowing `requests_per_minute` on average with
+    /// bursts of up to `burst_limit` requests.
+    #[must_use]
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let capacity = f64::from(config.burst_limit.max(1));
+        Self {
+            capacity,
+            refill_per_sec: f64::from(config.requests_per_minute.max(1)) / 60.0,
+            state: Mutex::new(LimiterState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+                queue: BTreeSet::new(),
+                next_seq: 0,
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Wait for a permit to send one request at `priority`.
+    ///
+    /// The future is cancel-safe: dropping it removes the request from the
+    /// queue.
+    pub async fn acquire(&self, priority: RequestPriority) {
+        let ticket = {
+            let mut state = self.lock();
+            let ticket = (Reverse(priority), state.next_seq);
+            state.next_seq += 1;
+            state.queue.insert(ticket);
+            ticket
+        };
+        let mut guard = QueueGuard {
+            limiter: self,
+            ticket: Some(ticket),
+        };
+
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let wait = {
+                let mut state = self.lock();
+                self.refill(&mut state);
+                if state.queue.first() != Some(&ticket) {
+                    None
+                } else if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    state.queue.remove(&ticket);
+                    guard.ticket = None;
+                    drop(state);
+                    self.notify.notify_waiters();
+                    return;
+                } else {
+                    Some(Duration::from_secs_f64(
+                        (1.0 - state.tokens) / self.refill_per_sec,
+                    ))
+                }
+            };
+
+            match wait {
+                Some(delay) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = &mut notified => {}
+                    }
+                }
+                None => notified.await,
+            }
+        }
+    }
+
+    /// Number of requests waiting for a permit.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    /// Tokens currently available.
+    #[must_use]
+    pub fn available(&self) -> u32 {
+        let mut state = self.lock();
+        self.refill(&mut state);
+        state.tokens as u32
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn refill(&self, state: &mut LimiterState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        state.last_refill = now;
+    }
+}
+
+/// Removes an abandoned request from the queue.
+struct QueueGuard<'a> {
+    limiter: &'a PriorityRateLimiter,
+    ticket: Option<Ticket>,
+}
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(ticket) = self.ticket.take() {
+            self.limiter.lock().queue.remove(&ticket);
+            self.limiter.notify.notify_waiters();
+        }
+    }
+}
+
+/// Default priority for a request.
+///
+/// Order cancellations and position closes are [`RequestPriority::Critical`],
+/// order submissions and replacements [`RequestPriority::High`], market data
+/// [`RequestPriority::Low`], and everything else [`RequestPriority::Normal`].
+#[must_use]
+pub fn default_priority(method: &Method, path: &str) -> RequestPriority {
+    let trading = path.starts_with("/v2/orders") || path.starts_with("/v2/positions");
+    if *method == Method::DELETE && trading {
+        RequestPriority::Critical
+    } else if trading && (*method == Method::POST || *method == Method::PATCH) {
+        RequestPriority::High
+    } else if path.starts_with("/v2/stocks")
+        || path.starts_with("/v1beta")
+        || path.starts_with("/v2/options")
+    {
+        RequestPriority::Low
+    } else {
+        RequestPriority::Normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_default_priority() {
+        assert_eq!(
+            default_priority(&Method::DELETE, "/v2/orders"),
+            RequestPriority::Critical
+        );
+        assert_eq!(
+            default_priority(&Method::DELETE, "/v2/positions/AAPL"),
+            RequestPriority::Critical
+        );
+        assert_eq!(
+            default_priority(&Method::POST, "/v2/orders"),
+            RequestPriority::High
+        );
+        assert_eq!(
+            default_priority(&Method::GET, "/v2/stocks/AAPL/bars"),
+            RequestPriority::Low
+        );
+        assert_eq!(
+            default_priority(&Method::GET, "/v2/account"),
+            RequestPriority::Normal
+        );
+    }
+
+    #[tokio::test]
+    async fn test_critical_preempts_queued_requests() {
+        let config = RateLimitConfig::new()
+            .requests_per_minute(1200)
+            .burst_limit(1);
+        let limiter = Arc::new(PriorityRateLimiter::new(&config));
+        limiter.acquire(RequestPriority::Normal).await;
+        assert_eq!(limiter.available(), 0);
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        for (name, priority) in [
+            ("low-1", RequestPriority::Low),
+            ("low-2", RequestPriority::Low),
+            ("normal", RequestPriority::Normal),
+            ("critical", RequestPriority::Critical),
+        ] {
+            let (limiter, tx) = (Arc::clone(&limiter), tx.clone());
+            tokio::spawn(async move {
+                limiter.acquire(priority).await;
+                let _ = tx.send(name);
+            });
+            tokio::task::yield_now().await;
+        }
+        drop(tx);
+
+        let mut order = Vec::new();
+        while let Some(name) = rx.recv().await {
+            order.push(name);
+        }
+        assert_eq!(order, ["critical", "normal", "low-1", "low-2"]);
+        assert_eq!(limiter.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_request_leaves_queue() {
+        let config = RateLimitConfig::new()
+            .requests_per_minute(60)
+            .burst_limit(1);
+        let limiter = PriorityRateLimiter::new(&config);
+        limiter.acquire(RequestPriority::Normal).await;
+
+        let pending = tokio::time::timeout(
+            Duration::from_millis(20),
+            limiter.acquire(RequestPriority::Low),
+        )
+        .await;
+        assert!(pending.is_err());
+        assert_eq!(limiter.queued(), 0);
+    }
+}
@@ -0,0 +1,362 @@
+//! Local portfolio rebalancing.
+//!
+//! The Broker API can rebalance accounts server-side against a
+//! [`RebalancePortfolio`](alpaca_base::types::RebalancePortfolio). For a
+//! trading account, [`Rebalancer`] computes the same thing locally: given
+//! the current positions, account equity, [`TargetAllocation`]s and latest
+//! prices, it returns the orders that bring each symbol to its target.
+//!
+//! Only symbols that have drifted from their target weight by more than the
+//! drift band are traded, and trades smaller than the minimum trade value
+//! are skipped, so repeated runs on a balanced portfolio place no orders.
+//! Positions without a target are closed in full. Sells come first in the
+//! plan, so their proceeds are available to the buys.
+
+use crate::endpoints::CreateOrderRequest;
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{OrderSide, Position, PositionSide, TargetAllocation},
+    utils::{format_decimal, format_qty, parse_decimal},
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// Tolerance for the sum of target weights.
+const WEIGHT_EPSILON: f64 = 1e-9;
+
+/// One trade of a [`RebalancePlan`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RebalanceTrade {
+    /// Symbol.
+    pub symbol: String,
+    /// Side.
+    pub side: OrderSide,
+    /// Weight before the trade, as a fraction of equity.
+    pub current_weight: f64,
+    /// Target weight, as a fraction of equity.
+    pub target_weight: f64,
+    /// Shares to trade.
+    pub qty: f64,
+    /// Dollar value of the trade.
+    pub notional: f64,
+    /// Whether the trade closes the whole position.
+    pub closes_position: bool,
+}
+
+/// Trades computed by [`Rebalancer::plan`], sells first.
+#[derive(Debug, Clone, Default)]
+pub struct RebalancePlan {
+    /// Trades.
+    pub trades: Vec<RebalanceTrade>,
+    /// Whether orders are sized by notional rather than quantity.
+    notional_orders: bool,
+}
+
+impl RebalancePlan {
+    /// Whether the portfolio is already within its bands.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    /// Total value sold.
+    #[must_use]
+    pub fn sell_value(&self) -> f64 {
+        self.side_value(OrderSide::Sell)
+    }
+
+    /// Total value bought.
+    #[must_use]
+    pub fn buy_value(&self) -> f64 {
+        self.side_value(OrderSide::Buy)
+    }
+
+    fn side_value(&self, side: OrderSide) -> f64 {
+        self.trades
+            .iter()
+            .filter(|trade| trade.side == side)
+            .map(|trade| trade.notional)
+            .sum()
+    }
+
+    /// Market day orders for the trades.
+    ///
+    /// Full exits are sized by quantity so no fractional remainder is left;
+    /// other trades by notional when [`Rebalancer::notional_orders`] is on.
+    #[must_use]
+    pub fn orders(&self) -> Vec<CreateOrderRequest> {
+        self.trades
+            .iter()
+            .map(|trade| {
+                if self.notional_orders && !trade.closes_position {
+                    CreateOrderRequest {
+                        qty: None,
+                        notional: Some(format_decimal(trade.notional, 2)),
+                        ..CreateOrderRequest::market(&trade.symbol, trade.side.clone(), "")
+                    }
+                } else {
+                    CreateOrderRequest::market(
+                        &trade.symbol,
+                        trade.side.clone(),
+                        format_qty(trade.qty),
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+/// Computes the orders that bring a portfolio to its target allocation.
+#[derive(Debug, Clone)]
+pub struct Rebalancer {
+    targets: Vec<TargetAllocation>,
+    drift_band: f64,
+    min_trade_value: f64,
+    fractional: bool,
+    notional_orders: bool,
+}
+
+impl Rebalancer {
+    /// Create a rebalancer for `targets`, with a 1% drift band, a $1
+    /// minimum trade and fractional quantities.
+    #[must_use]
+    pub fn new(targets: Vec<TargetAllocation>) -> Self {
+        Self {
+            targets,
+            drift_band: 0.01,
+            min_trade_value: 1.0,
+            fractional: true,
+            notional_orders: false,
+        }
+    }
+
+    /// Set how far, as a fraction of equity, a weight may drift from its
+    /// target before it is traded.
+    #[must_use]
+    pub fn drift_band(mut self, drift_band: f64) -> Self {
+        self.drift_band = drift_band.max(0.0);
+        self
+    }
+
+    /// Set the smallest trade, in dollars, worth placing.
+    #[must_use]
+    pub fn min_trade_value(mut self, min_trade_value: f64) -> Self {
+        self.min_trade_value = min_trade_value.max(0.0);
+        self
+    }
+
+    /// Set whether fractional quantities are allowed; without them,
+    /// quantities are rounded down to whole shares.
+    #[must_use]
+    pub fn fractional(mut self, fractional: bool) -> Self {
+        self.fractional = fractional;
+        self
+    }
+
+    /// Set whether orders are sized by dollar amount. Implies fractional
+    /// trading.
+    #[must_use]
+    pub fn notional_orders(mut self, notional_orders: bool) -> Self {
+        self.notional_orders = notional_orders;
+        if notional_orders {
+            self.fractional = true;
+        }
+        self
+    }
+
+    /// Target weight per symbol as a fraction of `equity`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if a target has neither a
+    /// percent nor a notional, or the targets add up to more than equity.
+    pub fn target_weights(&self, equity: f64) -> Result<BTreeMap<String, f64>> {
+        if equity <= 0.0 {
+            return Err(AlpacaError::InvalidData(format!(
+                "cannot rebalance with equity {equity}"
+            )));
+        }
+        let mut weights = BTreeMap::new();
+        for target in &self.targets {
+            let weight = match (target.percent, &target.notional) {
+                (Some(percent), _) => percent / 100.0,
+                (None, Some(notional)) => parse_decimal(notional)? / equity,
+                (None, None) => {
+                    return Err(AlpacaError::InvalidData(format!(
+                        "target for {} has neither percent nor notional",
+                        target.symbol
+                    )));
+                }
+            };
+            if weight < 0.0 {
+                return Err(AlpacaError::InvalidData(format!(
+                    "target for {} is negative",
+                    target.symbol
+                )));
+            }
+            *weights.entry(target.symbol.to_uppercase()).or_insert(0.0) += weight;
+        }
+        let total: f64 = weights.values().sum();
+        if total > 1.0 + WEIGHT_EPSILON {
+            return Err(AlpacaError::InvalidData(format!(
+                "targets add up to {:.2}% of equity",
+                total * 100.0
+            )));
+        }
+        Ok(weights)
+    }
+
+    /// Compute the trades for `positions` and `equity` at `prices`.
+    ///
+    /// Prices missing from `prices` fall back to the position's
+    /// `current_price`.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if the targets are invalid, or a
+    /// target symbol has no price.
+    pub fn plan(
+        &self,
+        positions: &[Position],
+        equity: f64,
+        prices: &HashMap<String, f64>,
+    ) -> Result<RebalancePlan> {
+        let targets = self.target_weights(equity)?;
+        let mut holdings: BTreeMap<String, (f64, Option<f64>)> = BTreeMap::new();
+        for position in positions {
+            let qty = parse_decimal(&position.qty)?.abs();
+            let qty = if position.side == PositionSide::Short {
+                -qty
+            } else {
+                qty
+            };
+            let fallback = parse_decimal(&position.current_price).ok();
+            holdings.insert(position.symbol.to_uppercase(), (qty, fallback));
+        }
+
+        let symbols: Vec<&String> = targets
+            .keys()
+            .chain(holdings.keys())
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let mut trades = Vec::new();
+        for symbol in symbols {
+            let (held, fallback) = holdings.get(symbol).copied().unwrap_or((0.0, None));
+            let target_weight = targets.get(symbol).copied().unwrap_or(0.0);
+            if held == 0.0 && target_weight == 0.0 {
+                continue;
+            }
+            let price = prices
+                .get(symbol)
+                .copied()
+                .or(fallback)
+                .filter(|price| *price > 0.0)
+                .ok_or_else(|| AlpacaError::InvalidData(format!("no price for {symbol}")))?;
+            let current_weight = held * price / equity;
+            let closes_position = target_weight == 0.0;
+            if !closes_position && (target_weight - current_weight).abs() <= self.drift_band {
+                continue;
+            }
+
+            let delta = target_weight * equity - held * price;
+            let side = if delta > 0.0 {
+                OrderSide::Buy
+            } else {
+                OrderSide::Sell
+            };
+            let qty = if closes_position {
+                held.abs()
+            } else if self.fractional {
+                delta.abs() / price
+            } else {
+                (delta.abs() / price).floor()
+            };
+            let notional = qty * price;
+            if qty <= 0.0 || (!closes_position && notional < self.min_trade_value) {
+                continue;
+            }
+            trades.push(RebalanceTrade {
+                symbol: symbol.clone(),
+                side,
+                current_weight,
+                target_weight,
+                qty,
+                notional,
+                closes_position,
+            });
+        }
+        trades.sort_by_key(|trade| trade.side == OrderSide::Buy);
+
+        Ok(RebalancePlan {
+            trades,
+            notional_orders: self.notional_orders,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_position;
+
+    #[test]
+    fn test_rebalancer_plan() {
+        // Equity 10_000: AAPL 50% (at target), MSFT 30% (target 20%), TSLA
+        // held without target, NVDA not held (target 25%).
+        let positions = [
+            sample_position("AAPL", "25", "150"),
+            sample_position("MSFT", "10", "300"),
+            sample_position("TSLA", "2.5", "200"),
+        ];
+        let prices = HashMap::from([
+            ("AAPL".to_string(), 200.0),
+            ("MSFT".to_string(), 300.0),
+            ("TSLA".to_string(), 200.0),
+            ("NVDA".to_string(), 400.0),
+        ]);
+        let rebalancer = Rebalancer::new(vec![
+            TargetAllocation::percent("AAPL", 50.5),
+            TargetAllocation::percent("MSFT", 20.0),
+            TargetAllocation::notional("NVDA", "2500"),
+        ]);
+        let plan = rebalancer.plan(&positions, 10_000.0, &prices).unwrap();
+        let summary: Vec<(&str, OrderSide, f64)> = plan
+            .trades
+            .iter()
+            .map(|t| (t.symbol.as_str(), t.side.clone(), t.qty))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("MSFT", OrderSide::Sell, 10.0 / 3.0),
+                ("TSLA", OrderSide::Sell, 2.5),
+                ("NVDA", OrderSide::Buy, 6.25),
+            ]
+        );
+        assert!((plan.sell_value() - 1500.0).abs() < 1e-9);
+
+        let orders = plan.orders();
+        assert_eq!(orders[0].qty.as_deref(), Some("3.333333333"));
+        assert_eq!(orders[1].qty.as_deref(), Some("2.5"));
+
+        let orders = rebalancer
+            .clone()
+            .notional_orders(true)
+            .plan(&positions, 10_000.0, &prices)
+            .unwrap()
+            .orders();
+        assert_eq!(orders[0].notional.as_deref(), Some("1000.00"));
+        assert!(orders[0].qty.is_none());
+        assert_eq!(orders[1].qty.as_deref(), Some("2.5"));
+        assert!(orders.iter().all(|o| o.validate().is_ok()));
+
+        let whole = rebalancer.fractional(false);
+        let plan = whole.plan(&positions, 10_000.0, &prices).unwrap();
+        assert_eq!(plan.trades[0].qty, 3.0);
+        assert_eq!(plan.trades[2].qty, 6.0);
+
+        let over = Rebalancer::new(vec![
+            TargetAllocation::percent("AAPL", 80.0),
+            TargetAllocation::percent("MSFT", 30.0),
+        ]);
+        assert!(over.plan(&positions, 10_000.0, &prices).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Driving Broker sandbox accounts through their lifecycle in tests.
+//!
+//! The Broker API sandbox approves accounts, settles transfers and
+//! executes journals on its own, after a delay. These helpers trigger each
+//! step and wait for the sandbox to finish it, so integration tests can go
+//! from a new account to a funded, active one without manual steps:
+//!
+//! ```rust,ignore
+//! let client = AlpacaHttpClient::with_config(credentials, ClientConfig::broker_sandbox())?;
+//! let options = SandboxOptions::new();
+//! let account = client.create_broker_account(&request).await?;
+//! client.force_approve_account(&account.id, &options).await?;
+//! client.fund_account_via_ach(&account.id, "10000", &options).await?;
+//! ```
+//!
+//! Every helper refuses to run against production hosts.
+
+use crate::client::AlpacaHttpClient;
+use alpaca_base::{
+    AlpacaError, Result,
+    types::{
+        BankAccountType, BrokerAccount, BrokerAccountStatus, CipInfo, CreateAchRelationshipRequest,
+        CreateJournalRequest, CreateTransferRequest, Journal, JournalStatus, ListJournalsParams,
+        Transfer, TransferDirection, TransferStatus,
+    },
+};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Bank account number accepted by the sandbox for ACH relationships.
+pub const SANDBOX_BANK_ACCOUNT_NUMBER: &str = "32131231abc";
+
+/// Bank routing number accepted by the sandbox for ACH relationships.
+pub const SANDBOX_BANK_ROUTING_NUMBER: &str = "123103716";
+
+/// How long sandbox helpers wait for a step to complete.
+#[derive(Debug, Clone)]
+pub struct SandboxOptions {
+    /// How long to wait for the sandbox to complete a step.
+    pub timeout: Duration,
+    /// Delay between polls.
+    pub poll_interval: Duration,
+}
+
+impl Default for SandboxOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(120),
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+impl SandboxOptions {
+    /// Create options waiting up to two minutes, polling every two seconds.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the delay between polls.
+    #[must_use]
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+}
+
+impl AlpacaHttpClient {
+    /// Submit passing CIP results for `account_id` and wait until the
+    /// account is approved or active.
+    ///
+    /// Fails if the account is rejected, or with [`AlpacaError::Timeout`]
+    /// if it is still under review when `options.timeout` passes.
+    pub async fn force_approve_account(
+        &self,
+        account_id: &str,
+        options: &SandboxOptions,
+    ) -> Result<BrokerAccount> {
+        self.ensure_sandbox()?;
+        let cip = CipInfo {
+            provider_name: vec!["sandbox".to_string()],
+            id: None,
+            result: Some("clear".to_string()),
+            status: Some("complete".to_string()),
+            created_at: None,
+            updated_at: None,
+        };
+        self.submit_cip(account_id, &cip).await?;
+        poll(options, "account approval", || async {
+            let account = self.get_broker_account(account_id).await?;
+            match account.status {
+                BrokerAccountStatus::Approved | BrokerAccountStatus::Active => Ok(Some(account)),
+                BrokerAccountStatus::Rejected
+                | BrokerAccountStatus::Disabled
+                | BrokerAccountStatus::AccountClosed => Err(AlpacaError::Validation(format!(
+                    "account {account_id} is {:?}",
+                    account.status
+                ))),
+                _ => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Wait until the sandbox settles transfer `transfer_id` of
+    /// `account_id`.
+    ///
+    /// Fails if the transfer is returned or canceled, or with
+    /// [`AlpacaError::Timeout`] if it is still pending when
+    /// `options.timeout` passes.
+    pub async fn simulate_transfer_completion(
+        &self,
+        account_id: &str,
+        transfer_id: &str,
+        options: &SandboxOptions,
+    ) -> Result<Transfer> {
+        self.ensure_sandbox()?;
+        poll(options, "transfer completion", || async {
+            let transfer = self.get_transfer(account_id, transfer_id).await?;
+            match transfer.status {
+                TransferStatus::Complete => Ok(Some(transfer)),
+                TransferStatus::Returned | TransferStatus::Canceled => {
+                    Err(AlpacaError::Validation(format!(
+                        "transfer {transfer_id} is {:?}: {}",
+                        transfer.status,
+                        transfer.reason.as_deref().unwrap_or("no reason given")
+                    )))
+                }
+                _ => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Deposit `amount` into `account_id` over ACH and wait until the
+    /// transfer completes.
+    ///
+    /// Uses an existing ACH relationship of the account, or links the
+    /// sandbox test bank account first.
+    pub async fn fund_account_via_ach(
+        &self,
+        account_id: &str,
+        amount: &str,
+        options: &SandboxOptions,
+    ) -> Result<Transfer> {
+        self.ensure_sandbox()?;
+        let relationships = self.list_ach_relationships(account_id).await?;
+        let relationship_id = match relationships.into_iter().next() {
+            Some(relationship) => relationship.id,
+            None => {
+                let request = CreateAchRelationshipRequest::new(
+                    "Sandbox Tester",
+                    BankAccountType::Checking,
+                    SANDBOX_BANK_ACCOUNT_NUMBER,
+                    SANDBOX_BANK_ROUTING_NUMBER,
+                );
+                self.create_ach_relationship(account_id, &request).await?.id
+            }
+        };
+        let request =
+            CreateTransferRequest::ach(&relationship_id, amount, TransferDirection::Incoming);
+        let transfer = self.create_transfer(account_id, &request).await?;
+        self.simulate_transfer_completion(account_id, &transfer.id, options)
+            .await
+    }
+
+    /// Move `amount` from the firm account `firm_account_id` to
+    /// `account_id` with a cash journal and wait until it executes.
+    ///
+    /// Faster than ACH funding: sandbox journals execute within seconds.
+    pub async fn fund_account_via_journal(
+        &self,
+        firm_account_id: &str,
+        account_id: &str,
+        amount: &str,
+        options: &SandboxOptions,
+    ) -> Result<Journal> {
+        self.ensure_sandbox()?;
+        let journal = self
+            .create_journal(&CreateJournalRequest::cash(
+                firm_account_id,
+                account_id,
+                amount,
+            ))
+            .await?;
+        let params = ListJournalsParams {
+            to_account: Some(account_id.to_string()),
+            from_account: Some(firm_account_id.to_string()),
+            ..Default::default()
+        };
+        poll(options, "journal execution", || async {
+            let journals = self.list_journals(&params).await?;
+            let Some(current) = journals.into_iter().find(|j| j.id == journal.id) else {
+                return Ok(None);
+            };
+            match current.status {
+                JournalStatus::Executed => Ok(Some(current)),
+                status if status.is_failed() => Err(AlpacaError::Validation(format!(
+                    "journal {} is {:?}",
+                    current.id, status
+                ))),
+                _ => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Refuse to run sandbox helpers against production hosts.
+    fn ensure_sandbox(&self) -> Result<()> {
+        let host = self
+            .base_url()
+            .split_once("://")
+            .map_or(self.base_url(), |(_, rest)| rest)
+            .split(['/', ':'])
+            .next()
+            .unwrap_or_default();
+        if host.ends_with("alpaca.markets") && !host.ends_with(".sandbox.alpaca.markets") {
+            return Err(AlpacaError::Config(format!(
+                "sandbox helpers are disabled for {host}; use ClientConfig::broker_sandbox()"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Call `check` every `options.poll_interval` until it yields a value,
+/// fails, or `options.timeout` passes.
+async fn poll<T, F, Fut>(options: &SandboxOptions, step: &str, mut check: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<T>>>,
+{
+    let deadline = Instant::now() + options.timeout;
+    loop {
+        if let Some(value) = check().await? {
+            return Ok(value);
+        }
+        if Instant::now() >= deadline {
+            return Err(AlpacaError::Timeout(format!(
+                "sandbox {step} not done after {:?}",
+                options.timeout
+            )));
+        }
+        tokio::time::sleep(options.poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::{ClientConfig, auth::Credentials, types::Environment};
+
+    #[tokio::test]
+    async fn test_sandbox_helpers_refuse_production_hosts() {
+        let credentials = Credentials::new("key".to_string(), "secret".to_string());
+        let options = SandboxOptions::new().timeout(Duration::ZERO);
+        let live = AlpacaHttpClient::new(credentials.clone(), Environment::Live).unwrap();
+        assert!(matches!(
+            live.force_approve_account("id", &options).await,
+            Err(AlpacaError::Config(_))
+        ));
+        let broker =
+            ClientConfig::new(Environment::Live).base_url("https://broker-api.alpaca.markets");
+        let broker = AlpacaHttpClient::with_config(credentials.clone(), broker).unwrap();
+        assert!(broker.ensure_sandbox().is_err());
+
+        let sandbox =
+            AlpacaHttpClient::with_config(credentials.clone(), ClientConfig::broker_sandbox())
+                .unwrap();
+        assert!(sandbox.ensure_sandbox().is_ok());
+        let mock = ClientConfig::new(Environment::Paper).base_url("http://127.0.0.1:8080");
+        let mock = AlpacaHttpClient::with_config(credentials, mock).unwrap();
+        assert!(mock.ensure_sandbox().is_ok());
+
+        let mut calls = 0;
+        let timed_out = poll(&options, "test", || {
+            calls += 1;
+            async { Ok(None::<()>) }
+        })
+        .await;
+        assert!(matches!(timed_out, Err(AlpacaError::Timeout(_))));
+        assert_eq!(calls, 1);
+    }
+}
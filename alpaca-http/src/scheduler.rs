@@ -0,0 +1,275 @@
+//! Market-hours timing helpers.
+//!
+//! [`MarketScheduler`] answers the timing questions most bots end up
+//! hand-rolling: wait for the open, wake up N minutes before the close,
+//! run something at every pre-market start, open and close. Session times
+//! come from the market calendar, so half-days (e.g. the day after
+//! Thanksgiving, closing at 13:00 ET) and holidays are handled without
+//! hard-coded hours; the clock endpoint decides whether the market is open
+//! right now.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::CalendarParams;
+use alpaca_base::{CancellationToken, Result, types::Calendar, utils::SessionWindow};
+use chrono::{DateTime, Days, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use tracing::warn;
+
+/// Length of a full regular session.
+const FULL_SESSION: Duration = Duration::minutes(390);
+
+/// Calendar days fetched ahead when looking for the next session.
+const LOOKAHEAD_DAYS: u64 = 10;
+
+/// One trading day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradingSession {
+    /// Trading date (US Eastern).
+    pub date: NaiveDate,
+    /// Regular trading hours.
+    pub regular: SessionWindow,
+    /// Pre-market open to after-hours close.
+    pub extended: SessionWindow,
+}
+
+impl TradingSession {
+    /// Build a session from a calendar day.
+    pub fn from_calendar(day: &Calendar) -> Result<Self> {
+        let regular = SessionWindow::from_calendar(day)?;
+        Ok(Self {
+            date: regular.date,
+            regular,
+            extended: SessionWindow::extended_from_calendar(day)?,
+        })
+    }
+
+    /// Whether the regular session closes early.
+    #[must_use]
+    pub fn is_half_day(&self) -> bool {
+        self.regular.close - self.regular.open < FULL_SESSION
+    }
+
+    /// Time of `event` in this session.
+    #[must_use]
+    pub fn time_of(&self, event: SessionEvent) -> DateTime<Utc> {
+        match event {
+            SessionEvent::PreMarketOpen => self.extended.open,
+            SessionEvent::RegularOpen => self.regular.open,
+            SessionEvent::RegularClose => self.regular.close,
+            SessionEvent::AfterHoursClose => self.extended.close,
+        }
+    }
+}
+
+/// A point in the trading day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// Pre-market trading starts.
+    PreMarketOpen,
+    /// The regular session opens.
+    RegularOpen,
+    /// The regular session closes.
+    RegularClose,
+    /// After-hours trading ends.
+    AfterHoursClose,
+}
+
+impl SessionEvent {
+    /// All events in the order they occur during a day.
+    pub const ALL: [SessionEvent; 4] = [
+        Self::PreMarketOpen,
+        Self::RegularOpen,
+        Self::RegularClose,
+        Self::AfterHoursClose,
+    ];
+}
+
+/// An event of a specific session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledEvent {
+    /// Event.
+    pub event: SessionEvent,
+    /// Session the event belongs to.
+    pub session: TradingSession,
+    /// When the event occurs.
+    pub at: DateTime<Utc>,
+}
+
+/// The first of `events` strictly after `now` across `sessions`.
+#[must_use]
+pub fn next_event(
+    sessions: &[TradingSession],
+    events: &[SessionEvent],
+    now: DateTime<Utc>,
+) -> Option<ScheduledEvent> {
+    sessions
+        .iter()
+        .flat_map(|session| {
+            events.iter().map(|&event| ScheduledEvent {
+                event,
+                session: *session,
+                at: session.time_of(event),
+            })
+        })
+        .filter(|scheduled| scheduled.at > now)
+        .min_by_key(|scheduled| scheduled.at)
+}
+
+/// Calendar-driven timing helpers for a client.
+#[derive(Debug, Clone)]
+pub struct MarketScheduler {
+    client: AlpacaHttpClient,
+}
+
+impl MarketScheduler {
+    /// Create a scheduler using `client` for clock and calendar lookups.
+    #[must_use]
+    pub fn new(client: AlpacaHttpClient) -> Self {
+        Self { client }
+    }
+
+    /// Trading sessions from yesterday through the next ten calendar days.
+    pub async fn upcoming_sessions(&self) -> Result<Vec<TradingSession>> {
+        let today = Utc::now().date_naive();
+        let params = CalendarParams::new()
+            .start(&(today - Days::new(1)).to_string())
+            .end(&(today + Days::new(LOOKAHEAD_DAYS)).to_string());
+        self.client
+            .get_calendar(&params)
+            .await?
+            .iter()
+            .map(TradingSession::from_calendar)
+            .collect()
+    }
+
+    /// The session in progress, or the next one if the market is between
+    /// sessions.
+    pub async fn current_session(&self) -> Result<TradingSession> {
+        let now = Utc::now();
+        self.upcoming_sessions()
+            .await?
+            .into_iter()
+            .find(|session| session.regular.close > now)
+            .ok_or_else(no_session)
+    }
+
+    /// Return once the regular session is open; immediately if it already
+    /// is.
+    pub async fn wait_for_open(&self) -> Result<()> {
+        let clock = self.client.get_clock().await?;
+        if !clock.is_open {
+            sleep_until(clock.next_open).await;
+        }
+        Ok(())
+    }
+
+    /// Return `minutes` before the close of the current (or next) session,
+    /// honouring early closes. Returns immediately when already inside
+    /// that window. Yields the session being closed.
+    pub async fn wait_until_minutes_before_close(&self, minutes: i64) -> Result<TradingSession> {
+        let session = self.current_session().await?;
+        sleep_until(session.regular.close - Duration::minutes(minutes)).await;
+        Ok(session)
+    }
+
+    /// The next occurrence of any of `events`.
+    pub async fn next_event(&self, events: &[SessionEvent]) -> Result<ScheduledEvent> {
+        next_event(&self.upcoming_sessions().await?, events, Utc::now()).ok_or_else(no_session)
+    }
+
+    /// Call `callback` at every occurrence of `events` until `cancel` is
+    /// triggered.
+    ///
+    /// Calendar lookup failures are logged and retried a minute later; an
+    /// event is never delivered twice, even if the callback returns before
+    /// the clock passes it.
+    pub async fn run_session_callbacks<F, Fut>(
+        &self,
+        events: &[SessionEvent],
+        cancel: &CancellationToken,
+        mut callback: F,
+    ) where
+        F: FnMut(ScheduledEvent) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut last_delivered: Option<DateTime<Utc>> = None;
+        loop {
+            let next = match self.upcoming_sessions().await {
+                Ok(sessions) => {
+                    let after = last_delivered.map_or(Utc::now(), |at| at.max(Utc::now()));
+                    next_event(&sessions, events, after).ok_or_else(no_session)
+                }
+                Err(e) => Err(e),
+            };
+            let scheduled = match next {
+                Ok(scheduled) => scheduled,
+                Err(e) => {
+                    warn!("Session schedule unavailable: {}", e);
+                    tokio::select! {
+                        () = cancel.cancelled() => return,
+                        () = tokio::time::sleep(std::time::Duration::from_secs(60)) => continue,
+                    }
+                }
+            };
+            tokio::select! {
+                () = cancel.cancelled() => return,
+                () = sleep_until(scheduled.at) => {}
+            }
+            last_delivered = Some(scheduled.at);
+            callback(scheduled).await;
+        }
+    }
+}
+
+fn no_session() -> alpaca_base::AlpacaError {
+    alpaca_base::AlpacaError::InvalidData(format!(
+        "no trading session in the next {LOOKAHEAD_DAYS} days"
+    ))
+}
+
+async fn sleep_until(at: DateTime<Utc>) {
+    if let Ok(wait) = (at - Utc::now()).to_std() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, close: &str) -> TradingSession {
+        TradingSession::from_calendar(&Calendar {
+            date: date.to_string(),
+            open: "09:30".to_string(),
+            close: close.to_string(),
+            session_open: "0400".to_string(),
+            session_close: "2000".to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_next_event_across_half_day() {
+        // Black Friday 2024 closes at 13:00 ET (18:00 UTC).
+        let sessions = [day("2024-11-29", "13:00"), day("2024-12-02", "16:00")];
+        assert!(sessions[0].is_half_day());
+        assert!(!sessions[1].is_half_day());
+
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let close = next_event(
+            &sessions,
+            &[SessionEvent::RegularClose],
+            at("2024-11-29T15:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(close.at, at("2024-11-29T18:00:00Z"));
+        assert_eq!(close.session.date, sessions[0].date);
+
+        let next = next_event(&sessions, &SessionEvent::ALL, at("2024-11-30T02:00:00Z")).unwrap();
+        assert_eq!(next.event, SessionEvent::PreMarketOpen);
+        assert_eq!(next.at, at("2024-12-02T09:00:00Z"));
+        assert!(next_event(&sessions, &SessionEvent::ALL, at("2024-12-03T02:00:00Z")).is_none());
+    }
+}
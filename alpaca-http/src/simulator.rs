@@ -0,0 +1,536 @@
+//! Offline order lifecycle simulation against streamed quotes.
+//!
+//! [`SimulatedExchange`] accepts [`CreateOrderRequest`]s and produces the
+//! same transport-neutral [`Execution`] events as the trading stream or a
+//! FIX session, so strategy tests can drive a [`FillTracker`] or their own
+//! order handling without the paper trading environment:
+//!
+//! ```rust,ignore
+//! let exchange = SimulatedExchange::new(SimulatorConfig::default());
+//! exchange.on_quote("AAPL", &quote);
+//! let order = exchange.submit(&CreateOrderRequest::limit("AAPL", OrderSide::Buy, "100", "189.50"))?;
+//! for execution in exchange.take_events() {
+//!     tracker.apply(&execution);
+//! }
+//! ```
+//!
+//! Everything is deterministic: order IDs are sequential, and time only
+//! advances with quote timestamps. Orders match against the latest quote
+//! of their symbol, on submission and on every new quote:
+//!
+//! - buys fill at the ask and sells at the bid;
+//! - limit orders fill once the quote crosses the limit;
+//! - stop orders trigger when the ask (buys) or bid (sells) reaches the
+//!   stop, then fill as market or limit orders;
+//! - with partial fills enabled, each quote only offers its displayed
+//!   size, shared by the orders in submission order, so large orders fill
+//!   over several quotes.
+//!
+//! IOC orders cancel whatever did not fill against the first quote they
+//! see, and FOK orders fill completely against one quote or are canceled.
+//! Day orders do not expire. Notional, trailing stop and multi-leg orders
+//! are rejected.
+//!
+//! [`FillTracker`]: alpaca_base::FillTracker
+
+use crate::endpoints::CreateOrderRequest;
+use alpaca_base::types::{
+    Order, OrderClass, OrderSide, OrderStatus, OrderType, Quote, TimeInForce,
+};
+use alpaca_base::{
+    AlpacaError, Execution, ExecutionKind, OrderRequest, Result, utils::QTY_EPSILON,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Matching behaviour of a [`SimulatedExchange`].
+#[derive(Debug, Clone)]
+pub struct SimulatorConfig {
+    /// Limit each quote to its displayed size.
+    pub partial_fills: bool,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            partial_fills: true,
+        }
+    }
+}
+
+impl SimulatorConfig {
+    /// Set whether fills are limited to the quoted size.
+    #[must_use]
+    pub fn partial_fills(mut self, enabled: bool) -> Self {
+        self.partial_fills = enabled;
+        self
+    }
+}
+
+/// The latest quote of a symbol and the size still available on it.
+#[derive(Debug)]
+struct Book {
+    bid_price: f64,
+    ask_price: f64,
+    bid_left: f64,
+    ask_left: f64,
+}
+
+/// An open order and its matching state.
+#[derive(Debug)]
+struct Working {
+    order_index: usize,
+    symbol: String,
+    side: OrderSide,
+    time_in_force: TimeInForce,
+    qty: f64,
+    limit: Option<f64>,
+    stop: Option<f64>,
+    triggered: bool,
+    filled: f64,
+    cost: f64,
+}
+
+#[derive(Debug)]
+struct State {
+    now: DateTime<Utc>,
+    next_order: u128,
+    next_exec: u64,
+    books: HashMap<String, Book>,
+    orders: Vec<Order>,
+    working: Vec<Working>,
+    events: Vec<Execution>,
+}
+
+impl State {
+    fn event(&self, order: &Order, kind: ExecutionKind) -> Execution {
+        Execution {
+            order_id: Some(order.id.to_string()),
+            ..Execution::new(&order.client_order_id, kind, self.now)
+        }
+    }
+
+    /// Match the working order at `pos` against its book. Returns whether
+    /// the order is done.
+    fn attempt(&mut self, pos: usize, partial_fills: bool) -> bool {
+        let now = self.now;
+        let working = &mut self.working[pos];
+        let Some(book) = self.books.get_mut(&working.symbol) else {
+            return false;
+        };
+        let buy = working.side == OrderSide::Buy;
+        let (price, available) = if buy {
+            (book.ask_price, &mut book.ask_left)
+        } else {
+            (book.bid_price, &mut book.bid_left)
+        };
+
+        if let Some(stop) = working.stop
+            && !working.triggered
+        {
+            working.triggered = if buy { price >= stop } else { price <= stop };
+        }
+        let marketable = price > 0.0
+            && (working.stop.is_none() || working.triggered)
+            && working
+                .limit
+                .is_none_or(|limit| if buy { price <= limit } else { price >= limit });
+        let remaining = working.qty - working.filled;
+        let mut fill_qty = match (marketable, partial_fills) {
+            (false, _) => 0.0,
+            (true, true) => remaining.min(*available),
+            (true, false) => remaining,
+        };
+        if working.time_in_force == TimeInForce::Fok && fill_qty < remaining - QTY_EPSILON {
+            fill_qty = 0.0;
+        }
+
+        if fill_qty > QTY_EPSILON {
+            if partial_fills {
+                *available -= fill_qty;
+            }
+            working.filled += fill_qty;
+            working.cost += fill_qty * price;
+            let filled = working.filled;
+            let avg_price = working.cost / filled;
+            let done = filled >= working.qty - QTY_EPSILON;
+
+            let order = &mut self.orders[working.order_index];
+            order.filled_qty = filled.to_string();
+            order.filled_avg_price = Some(avg_price.to_string());
+            order.updated_at = now;
+            if done {
+                order.status = OrderStatus::Filled;
+                order.filled_at = Some(now);
+            } else {
+                order.status = OrderStatus::PartiallyFilled;
+            }
+
+            self.next_exec += 1;
+            let order = &self.orders[working.order_index];
+            self.events.push(Execution {
+                order_id: Some(order.id.to_string()),
+                exec_id: Some(format!("sim-exec-{}", self.next_exec)),
+                cum_qty: Some(filled),
+                avg_price: Some(avg_price),
+                ..Execution::fill(&order.client_order_id, fill_qty, price, now)
+            });
+            if done {
+                return true;
+            }
+        }
+
+        if matches!(working.time_in_force, TimeInForce::Ioc | TimeInForce::Fok) {
+            let index = working.order_index;
+            self.cancel_order(index, "unfilled remainder canceled");
+            return true;
+        }
+        false
+    }
+
+    fn cancel_order(&mut self, index: usize, reason: &str) {
+        let now = self.now;
+        let order = &mut self.orders[index];
+        order.status = OrderStatus::Canceled;
+        order.canceled_at = Some(now);
+        order.updated_at = now;
+        let event = Execution {
+            text: Some(reason.to_string()),
+            ..self.event(&self.orders[index], ExecutionKind::Canceled)
+        };
+        self.events.push(event);
+    }
+}
+
+/// Deterministic, offline exchange for strategy tests.
+///
+/// See the [module documentation](self) for the matching rules.
+#[derive(Debug)]
+pub struct SimulatedExchange {
+    config: SimulatorConfig,
+    state: Mutex<State>,
+}
+
+impl Default for SimulatedExchange {
+    fn default() -> Self {
+        Self::new(SimulatorConfig::default())
+    }
+}
+
+impl SimulatedExchange {
+    /// Create an exchange with no quotes and no orders.
+    #[must_use]
+    pub fn new(config: SimulatorConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State {
+                now: DateTime::<Utc>::UNIX_EPOCH,
+                next_order: 0,
+                next_exec: 0,
+                books: HashMap::new(),
+                orders: Vec::new(),
+                working: Vec::new(),
+                events: Vec::new(),
+            }),
+        }
+    }
+
+    /// Simulated time: the latest quote timestamp seen, or the Unix epoch
+    /// before the first quote.
+    #[must_use]
+    pub fn now(&self) -> DateTime<Utc> {
+        self.lock().now
+    }
+
+    /// Accept an order, emitting an `Accepted` event, and match it against
+    /// the current quote of its symbol.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if the request fails
+    /// [`CreateOrderRequest::validate`], or an API error (422) for order
+    /// kinds the simulator does not support.
+    pub fn submit(&self, request: &CreateOrderRequest) -> Result<Order> {
+        request.validate()?;
+        let unsupported = |message: &str| Err(AlpacaError::api(422, message));
+        if request.order_type == OrderType::TrailingStop {
+            return unsupported("trailing stop orders are not simulated");
+        }
+        if request
+            .order_class
+            .as_ref()
+            .is_some_and(|class| *class != OrderClass::Simple)
+        {
+            return unsupported("only simple orders are simulated");
+        }
+        let Some(qty) = request.qty.as_deref().and_then(|q| q.parse::<f64>().ok()) else {
+            return unsupported("notional orders are not simulated");
+        };
+        let price = |value: &Option<String>| value.as_deref().and_then(|p| p.parse::<f64>().ok());
+        let limit = price(&request.limit_price);
+        let stop = price(&request.stop_price);
+
+        let mut state = self.lock();
+        state.next_order += 1;
+        let id = Uuid::from_u128(state.next_order);
+        let client_order_id = request
+            .client_order_id
+            .clone()
+            .unwrap_or_else(|| format!("sim-{}", state.next_order));
+        let mut order = OrderRequest {
+            symbol: request.symbol.clone(),
+            side: request.side.clone(),
+            qty,
+            order_type: request.order_type.clone(),
+            time_in_force: request.time_in_force.clone(),
+            limit_price: limit,
+            stop_price: stop,
+            client_order_id: None,
+        }
+        .pending_order(id, &client_order_id, state.now);
+        order.status = OrderStatus::New;
+        order.extended_hours = request.extended_hours.unwrap_or(false);
+
+        let event = state.event(&order, ExecutionKind::Accepted);
+        state.events.push(event);
+        state.orders.push(order);
+        let order_index = state.orders.len() - 1;
+        state.working.push(Working {
+            order_index,
+            symbol: request.symbol.clone(),
+            side: request.side.clone(),
+            time_in_force: request.time_in_force.clone(),
+            qty,
+            limit,
+            stop,
+            triggered: false,
+            filled: 0.0,
+            cost: 0.0,
+        });
+        let pos = state.working.len() - 1;
+        if state.attempt(pos, self.config.partial_fills) {
+            state.working.remove(pos);
+        }
+        Ok(state.orders[order_index].clone())
+    }
+
+    /// Cancel an open order, emitting a `Canceled` event.
+    ///
+    /// # Errors
+    /// Returns an API error (422) if the order is unknown or already done.
+    pub fn cancel(&self, order_id: Uuid) -> Result<Order> {
+        let mut state = self.lock();
+        let pos = state
+            .working
+            .iter()
+            .position(|w| state.orders[w.order_index].id == order_id)
+            .ok_or_else(|| AlpacaError::api(422, format!("order {order_id} is not open")))?;
+        let working = state.working.remove(pos);
+        state.cancel_order(working.order_index, "canceled by request");
+        Ok(state.orders[working.order_index].clone())
+    }
+
+    /// Apply a new quote for `symbol` and match its open orders in
+    /// submission order.
+    pub fn on_quote(&self, symbol: &str, quote: &Quote) {
+        let mut state = self.lock();
+        state.now = state.now.max(quote.timestamp);
+        state.books.insert(
+            symbol.to_string(),
+            Book {
+                bid_price: quote.bid_price,
+                ask_price: quote.ask_price,
+                bid_left: f64::from(quote.bid_size),
+                ask_left: f64::from(quote.ask_size),
+            },
+        );
+        let mut pos = 0;
+        while pos < state.working.len() {
+            if state.working[pos].symbol == symbol && state.attempt(pos, self.config.partial_fills)
+            {
+                state.working.remove(pos);
+            } else {
+                pos += 1;
+            }
+        }
+    }
+
+    /// Drain the lifecycle events produced since the last call, in order.
+    pub fn take_events(&self) -> Vec<Execution> {
+        std::mem::take(&mut self.lock().events)
+    }
+
+    /// Look up an order by ID.
+    #[must_use]
+    pub fn order(&self, order_id: Uuid) -> Option<Order> {
+        self.lock()
+            .orders
+            .iter()
+            .find(|order| order.id == order_id)
+            .cloned()
+    }
+
+    /// All orders, in submission order.
+    #[must_use]
+    pub fn orders(&self) -> Vec<Order> {
+        self.lock().orders.clone()
+    }
+
+    /// Orders that are still working, in submission order.
+    #[must_use]
+    pub fn open_orders(&self) -> Vec<Order> {
+        let state = self.lock();
+        state
+            .working
+            .iter()
+            .map(|w| state.orders[w.order_index].clone())
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn quote(second: u32, bid: f64, ask: f64, size: u32) -> Quote {
+        Quote {
+            timestamp: Utc.with_ymd_and_hms(2024, 5, 1, 14, 30, second).unwrap(),
+            timeframe: String::new(),
+            bid_price: bid,
+            bid_size: size,
+            ask_price: ask,
+            ask_size: size,
+            bid_exchange: "V".to_string(),
+            ask_exchange: "V".to_string(),
+        }
+    }
+
+    fn kinds(events: &[Execution]) -> Vec<ExecutionKind> {
+        events.iter().map(|e| e.kind).collect()
+    }
+
+    #[test]
+    fn test_large_order_fills_over_several_quotes() {
+        let exchange = SimulatedExchange::default();
+        exchange.on_quote("AAPL", &quote(0, 99.0, 100.0, 60));
+        let order = exchange
+            .submit(&CreateOrderRequest::market("AAPL", OrderSide::Buy, "100"))
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+        exchange.on_quote("AAPL", &quote(1, 100.0, 101.0, 60));
+
+        let events = exchange.take_events();
+        assert_eq!(
+            kinds(&events),
+            [
+                ExecutionKind::Accepted,
+                ExecutionKind::Fill,
+                ExecutionKind::Fill
+            ]
+        );
+        assert_eq!(events[1].last_qty, Some(60.0));
+        assert_eq!(events[2].last_qty, Some(40.0));
+        assert_eq!(events[2].last_price, Some(101.0));
+        assert_eq!(events[2].cum_qty, Some(100.0));
+        assert!((events[2].avg_price.unwrap() - 100.4).abs() < 1e-9);
+
+        let order = exchange.order(order.id).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.filled_at, Some(quote(1, 0.0, 0.0, 0).timestamp));
+        assert!(exchange.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_limit_and_stop_orders_wait_for_price() {
+        let exchange = SimulatedExchange::new(SimulatorConfig::default().partial_fills(false));
+        exchange.on_quote("AAPL", &quote(0, 99.0, 100.0, 1));
+        let limit = exchange
+            .submit(&CreateOrderRequest::limit(
+                "AAPL",
+                OrderSide::Buy,
+                "10",
+                "98",
+            ))
+            .unwrap();
+        let stop = exchange
+            .submit(&CreateOrderRequest::stop(
+                "AAPL",
+                OrderSide::Sell,
+                "5",
+                "97",
+            ))
+            .unwrap();
+        assert_eq!(exchange.open_orders().len(), 2);
+
+        exchange.on_quote("AAPL", &quote(1, 97.0, 98.0, 1));
+        assert_eq!(
+            exchange.order(limit.id).unwrap().status,
+            OrderStatus::Filled
+        );
+        let stop = exchange.order(stop.id).unwrap();
+        assert_eq!(stop.status, OrderStatus::Filled);
+        assert_eq!(stop.filled_avg_price.as_deref(), Some("97"));
+    }
+
+    #[test]
+    fn test_ioc_remainder_and_explicit_cancel() {
+        let exchange = SimulatedExchange::default();
+        exchange.on_quote("AAPL", &quote(0, 99.0, 100.0, 30));
+        let mut ioc = CreateOrderRequest::market("AAPL", OrderSide::Sell, "50");
+        ioc.time_in_force = TimeInForce::Ioc;
+        let ioc = exchange.submit(&ioc).unwrap();
+        assert_eq!(ioc.status, OrderStatus::Canceled);
+        assert_eq!(ioc.filled_qty, "30");
+
+        let resting = exchange
+            .submit(&CreateOrderRequest::limit(
+                "AAPL",
+                OrderSide::Buy,
+                "1",
+                "90",
+            ))
+            .unwrap();
+        exchange.cancel(resting.id).unwrap();
+        assert!(exchange.cancel(resting.id).is_err());
+
+        let events = exchange.take_events();
+        assert_eq!(
+            kinds(&events),
+            [
+                ExecutionKind::Accepted,
+                ExecutionKind::Fill,
+                ExecutionKind::Canceled,
+                ExecutionKind::Accepted,
+                ExecutionKind::Canceled
+            ]
+        );
+        assert_eq!(events[0].order_id, Some(Uuid::from_u128(1).to_string()));
+        assert_eq!(events[0].client_order_id, "sim-1");
+    }
+
+    #[test]
+    fn test_unsupported_orders_are_rejected() {
+        let exchange = SimulatedExchange::default();
+        let mut notional = CreateOrderRequest::market("AAPL", OrderSide::Buy, "1");
+        notional.qty = None;
+        notional.notional = Some("100".to_string());
+        assert!(exchange.submit(&notional).is_err());
+        assert!(
+            exchange
+                .submit(&CreateOrderRequest::trailing_stop_price(
+                    "AAPL",
+                    OrderSide::Sell,
+                    "1",
+                    "2"
+                ))
+                .is_err()
+        );
+        assert!(exchange.orders().is_empty());
+    }
+}
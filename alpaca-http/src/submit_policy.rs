@@ -0,0 +1,238 @@
+//! Resubmitting orders rejected for transient reasons.
+//!
+//! Some rejections say nothing about the order itself: the market is not
+//! open for an order that is not eligible for extended hours, the symbol is
+//! briefly halted, or the server is overloaded. A [`SubmitPolicy`] maps each
+//! [`RejectionReason`] to the [`Reshape`]s to apply before trying again,
+//! e.g. flag the order for extended hours, wait for the next open or switch
+//! its time in force. [`AlpacaHttpClient::create_order_with_policy`] applies
+//! it; rejections without a rule are returned as they are.
+//!
+//! [`AlpacaHttpClient::create_order_with_policy`]: crate::AlpacaHttpClient::create_order_with_policy
+
+use crate::endpoints::CreateOrderRequest;
+use alpaca_base::{
+    AlpacaError,
+    types::{OrderType, TimeInForce},
+};
+use std::time::Duration;
+
+/// Why an order was rejected, as far as a [`SubmitPolicy`] cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+    /// The market is closed for this order, e.g. "market not open for
+    /// extended-hours ineligible order".
+    MarketClosed,
+    /// Trading in the symbol is halted.
+    Halted,
+    /// The time in force is not accepted for this order.
+    TimeInForce,
+    /// Rate limiting, a server error, a timeout or a network failure.
+    Transient,
+}
+
+impl RejectionReason {
+    /// Classify an order submission error, or `None` if it is not one a
+    /// policy can act on.
+    #[must_use]
+    pub fn classify(error: &AlpacaError) -> Option<Self> {
+        if let AlpacaError::Api { message, .. } = error {
+            let message = message.to_ascii_lowercase();
+            let mentions = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+            if mentions(&["market not open", "market is closed", "market closed"]) {
+                return Some(Self::MarketClosed);
+            }
+            if mentions(&["halt"]) {
+                return Some(Self::Halted);
+            }
+            if mentions(&["time_in_force", "time in force"]) {
+                return Some(Self::TimeInForce);
+            }
+        }
+        error.is_retryable().then_some(Self::Transient)
+    }
+}
+
+/// A change applied to an order before it is resubmitted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reshape {
+    /// Resubmit after the policy's retry delay.
+    Retry,
+    /// Flag the order for extended hours trading as a DAY order. Only
+    /// limit orders are eligible; market orders are not resubmitted.
+    ExtendedHours,
+    /// Wait until the next market open from the clock.
+    DelayUntilOpen,
+    /// Switch the time in force.
+    TimeInForce(TimeInForce),
+}
+
+/// How to resubmit an order after a rejection.
+#[derive(Debug, Clone)]
+pub struct Resubmission {
+    /// Why the order was rejected.
+    pub reason: RejectionReason,
+    /// The reshaped order to send.
+    pub order: CreateOrderRequest,
+    /// Whether to wait for the next market open first.
+    pub wait_for_open: bool,
+    /// Delay before sending.
+    pub delay: Duration,
+}
+
+/// Rules for resubmitting rejected orders.
+///
+/// ```rust,ignore
+/// let policy = SubmitPolicy::new()
+///     .on(RejectionReason::MarketClosed, Reshape::ExtendedHours)
+///     .on(RejectionReason::Halted, Reshape::DelayUntilOpen)
+///     .on(RejectionReason::Transient, Reshape::Retry)
+///     .max_attempts(3);
+/// let order = client.create_order_with_policy(&request, &policy).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmitPolicy {
+    rules: Vec<(RejectionReason, Reshape)>,
+    /// Submissions in total, including the first.
+    pub max_attempts: u32,
+    /// Delay before each resubmission.
+    pub retry_delay: Duration,
+}
+
+impl Default for SubmitPolicy {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            max_attempts: 3,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl SubmitPolicy {
+    /// Create a policy without rules, allowing three attempts one second
+    /// apart.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `reshape` to orders rejected for `reason`. Several reshapes
+    /// for one reason apply in the order they were added.
+    #[must_use]
+    pub fn on(mut self, reason: RejectionReason, reshape: Reshape) -> Self {
+        self.rules.push((reason, reshape));
+        self
+    }
+
+    /// Set the number of submissions in total, including the first.
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before each resubmission.
+    #[must_use]
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// How to resubmit `order` after `error` on submission `attempt`
+    /// (1-based), or `None` to give up.
+    #[must_use]
+    pub fn resubmission(
+        &self,
+        order: &CreateOrderRequest,
+        error: &AlpacaError,
+        attempt: u32,
+    ) -> Option<Resubmission> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let reason = RejectionReason::classify(error)?;
+        let mut reshapes = self
+            .rules
+            .iter()
+            .filter(|(r, _)| *r == reason)
+            .map(|(_, reshape)| reshape)
+            .peekable();
+        reshapes.peek()?;
+
+        let mut resubmission = Resubmission {
+            reason,
+            order: order.clone(),
+            wait_for_open: false,
+            delay: self.retry_delay,
+        };
+        for reshape in reshapes {
+            match reshape {
+                Reshape::Retry => {}
+                Reshape::ExtendedHours => {
+                    if resubmission.order.order_type != OrderType::Limit {
+                        return None;
+                    }
+                    resubmission.order.extended_hours = Some(true);
+                    resubmission.order.time_in_force = TimeInForce::Day;
+                }
+                Reshape::DelayUntilOpen => resubmission.wait_for_open = true,
+                Reshape::TimeInForce(time_in_force) => {
+                    resubmission.order.time_in_force = time_in_force.clone();
+                }
+            }
+        }
+        Some(resubmission)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::types::OrderSide;
+
+    #[test]
+    fn test_submit_policy_reshapes_rejections() {
+        let closed = AlpacaError::api(403, "market not open for extended-hours ineligible order");
+        let halted = AlpacaError::api(422, "asset XYZ is halted");
+        assert_eq!(
+            RejectionReason::classify(&closed),
+            Some(RejectionReason::MarketClosed)
+        );
+        assert_eq!(
+            RejectionReason::classify(&halted),
+            Some(RejectionReason::Halted)
+        );
+        assert_eq!(
+            RejectionReason::classify(&AlpacaError::api(503, "unavailable")),
+            Some(RejectionReason::Transient)
+        );
+        assert_eq!(
+            RejectionReason::classify(&AlpacaError::api(403, "insufficient buying power")),
+            None
+        );
+
+        let policy = SubmitPolicy::new()
+            .on(RejectionReason::MarketClosed, Reshape::ExtendedHours)
+            .on(RejectionReason::Halted, Reshape::DelayUntilOpen)
+            .on(
+                RejectionReason::Halted,
+                Reshape::TimeInForce(TimeInForce::Gtc),
+            )
+            .max_attempts(2);
+        let limit = CreateOrderRequest::limit("AAPL", OrderSide::Buy, "10", "190.00");
+
+        let extended = policy.resubmission(&limit, &closed, 1).unwrap();
+        assert_eq!(extended.order.extended_hours, Some(true));
+        assert!(!extended.wait_for_open);
+        let market = CreateOrderRequest::market("AAPL", OrderSide::Buy, "10");
+        assert!(policy.resubmission(&market, &closed, 1).is_none());
+
+        let delayed = policy.resubmission(&limit, &halted, 1).unwrap();
+        assert!(delayed.wait_for_open);
+        assert_eq!(delayed.order.time_in_force, TimeInForce::Gtc);
+        assert!(policy.resubmission(&limit, &halted, 2).is_none());
+        let transient = AlpacaError::api(500, "internal error");
+        assert!(policy.resubmission(&limit, &transient, 1).is_none());
+    }
+}
@@ -0,0 +1,582 @@
+//! Trading journal generation for trade review.
+//!
+//! [`JournalGenerator`] pulls fill activities from the account, pairs them
+//! into closed round trips per symbol, and annotates each round trip with a
+//! few bars of market context around its entry and exit. The resulting
+//! [`TradingJournal`] exports to JSON or Markdown.
+//!
+//! A round trip opens when a symbol's position leaves zero and closes when
+//! it returns to zero. A fill that flips the position (e.g. selling 15 while
+//! long 10) closes the current round trip and opens a new one with the
+//! remainder. Positions still open at the end of the fill history are not
+//! journaled.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::BarsParams;
+use alpaca_base::{
+    Result,
+    types::{Bar, DataFeed, ListActivitiesParams, OrderSide, SortDirection, TradeActivity},
+    utils::QTY_EPSILON,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::future::Future;
+
+/// Page size for fill activities when the caller sets none; the API
+/// default, made explicit so a full page can be told from the last one.
+const FILL_PAGE_SIZE: u32 = 100;
+
+/// Direction of a round trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeDirection {
+    /// Opened with a buy.
+    Long,
+    /// Opened with a sell.
+    Short,
+}
+
+/// A fill (or the part of one) attributed to a round trip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalFill {
+    /// Fill activity ID.
+    pub activity_id: String,
+    /// Order ID.
+    pub order_id: String,
+    /// Side.
+    pub side: OrderSide,
+    /// Quantity attributed to the round trip.
+    pub qty: f64,
+    /// Fill price.
+    pub price: f64,
+    /// Fill time.
+    pub time: DateTime<Utc>,
+}
+
+/// A closed round trip with its fills and market context.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundTrip {
+    /// Symbol.
+    pub symbol: String,
+    /// Long or short.
+    pub direction: TradeDirection,
+    /// Fills that built the position.
+    pub entry_fills: Vec<JournalFill>,
+    /// Fills that closed the position.
+    pub exit_fills: Vec<JournalFill>,
+    /// Bars around the first entry fill.
+    pub entry_context: Vec<Bar>,
+    /// Bars around the last exit fill.
+    pub exit_context: Vec<Bar>,
+}
+
+impl RoundTrip {
+    /// Total quantity traded in the round trip.
+    #[must_use]
+    pub fn qty(&self) -> f64 {
+        self.entry_fills.iter().map(|f| f.qty).sum()
+    }
+
+    /// Volume-weighted average entry price.
+    #[must_use]
+    pub fn avg_entry_price(&self) -> f64 {
+        vwap(&self.entry_fills)
+    }
+
+    /// Volume-weighted average exit price.
+    #[must_use]
+    pub fn avg_exit_price(&self) -> f64 {
+        vwap(&self.exit_fills)
+    }
+
+    /// Time of the first entry fill.
+    #[must_use]
+    pub fn opened_at(&self) -> DateTime<Utc> {
+        self.entry_fills.first().map_or_else(Utc::now, |f| f.time)
+    }
+
+    /// Time of the last exit fill.
+    #[must_use]
+    pub fn closed_at(&self) -> DateTime<Utc> {
+        self.exit_fills.last().map_or_else(Utc::now, |f| f.time)
+    }
+
+    /// Time between the first entry and the last exit.
+    #[must_use]
+    pub fn holding_time(&self) -> Duration {
+        self.closed_at() - self.opened_at()
+    }
+
+    /// Realized profit or loss, before fees.
+    #[must_use]
+    pub fn realized_pnl(&self) -> f64 {
+        let gross = (self.avg_exit_price() - self.avg_entry_price()) * self.qty();
+        match self.direction {
+            TradeDirection::Long => gross,
+            TradeDirection::Short => -gross,
+        }
+    }
+
+    /// Realized return relative to the entry notional, in percent.
+    #[must_use]
+    pub fn return_pct(&self) -> f64 {
+        let notional = self.avg_entry_price() * self.qty();
+        if notional == 0.0 {
+            0.0
+        } else {
+            self.realized_pnl() / notional * 100.0
+        }
+    }
+}
+
+fn vwap(fills: &[JournalFill]) -> f64 {
+    let qty: f64 = fills.iter().map(|f| f.qty).sum();
+    if qty == 0.0 {
+        return 0.0;
+    }
+    fills.iter().map(|f| f.qty * f.price).sum::<f64>() / qty
+}
+
+/// Journal of closed round trips.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradingJournal {
+    /// When the journal was generated.
+    pub generated_at: DateTime<Utc>,
+    /// Closed round trips ordered by close time.
+    pub round_trips: Vec<RoundTrip>,
+}
+
+impl TradingJournal {
+    /// Total realized P&L across all round trips.
+    #[must_use]
+    pub fn total_pnl(&self) -> f64 {
+        self.round_trips.iter().map(RoundTrip::realized_pnl).sum()
+    }
+
+    /// Fraction of round trips with positive P&L (0.0 when empty).
+    #[must_use]
+    pub fn win_rate(&self) -> f64 {
+        if self.round_trips.is_empty() {
+            return 0.0;
+        }
+        let wins = self
+            .round_trips
+            .iter()
+            .filter(|t| t.realized_pnl() > 0.0)
+            .count();
+        wins as f64 / self.round_trips.len() as f64
+    }
+
+    /// Serialize the journal as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Render the journal as Markdown for trade review.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Trading Journal\n");
+        let _ = writeln!(out, "Generated: {}\n", self.generated_at.to_rfc3339());
+        let _ = writeln!(
+            out,
+            "{} round trips, total P&L {:.2}, win rate {:.1}%\n",
+            self.round_trips.len(),
+            self.total_pnl(),
+            self.win_rate() * 100.0
+        );
+        for (i, trip) in self.round_trips.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "## {}. {} {:?} x{}\n",
+                i + 1,
+                trip.symbol,
+                trip.direction,
+                trip.qty()
+            );
+            let _ = writeln!(out, "| Entry | Exit | Holding | P&L | Return |");
+            let _ = writeln!(out, "|-------|------|---------|-----|--------|");
+            let _ = writeln!(
+                out,
+                "| {:.4} @ {} | {:.4} @ {} | {} | {:.2} | {:.2}% |\n",
+                trip.avg_entry_price(),
+                trip.opened_at().format("%Y-%m-%d %H:%M:%S"),
+                trip.avg_exit_price(),
+                trip.closed_at().format("%Y-%m-%d %H:%M:%S"),
+                format_duration(trip.holding_time()),
+                trip.realized_pnl(),
+                trip.return_pct()
+            );
+            let _ = writeln!(out, "| Fill | Side | Qty | Price | Time |");
+            let _ = writeln!(out, "|------|------|-----|-------|------|");
+            for fill in trip.entry_fills.iter().chain(&trip.exit_fills) {
+                let _ = writeln!(
+                    out,
+                    "| {} | {:?} | {} | {:.4} | {} |",
+                    fill.activity_id,
+                    fill.side,
+                    fill.qty,
+                    fill.price,
+                    fill.time.to_rfc3339()
+                );
+            }
+            let _ = writeln!(out);
+            write_context(&mut out, "Entry context", &trip.entry_context);
+            write_context(&mut out, "Exit context", &trip.exit_context);
+        }
+        out
+    }
+}
+
+fn write_context(out: &mut String, title: &str, bars: &[Bar]) {
+    if bars.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "**{title}**\n");
+    let _ = writeln!(out, "| Time | Open | High | Low | Close | Volume |");
+    let _ = writeln!(out, "|------|------|------|-----|-------|--------|");
+    for bar in bars {
+        let _ = writeln!(
+            out,
+            "| {} | {:.4} | {:.4} | {:.4} | {:.4} | {} |",
+            bar.timestamp.format("%H:%M"),
+            bar.open,
+            bar.high,
+            bar.low,
+            bar.close,
+            bar.volume
+        );
+    }
+    let _ = writeln!(out);
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    format!(
+        "{}h {:02}m {:02}s",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Pair fill activities into closed round trips, ordered by close time.
+///
+/// Fills whose quantity or price cannot be parsed are skipped. Market
+/// context is left empty.
+#[must_use]
+pub fn round_trips(fills: &[TradeActivity]) -> Vec<RoundTrip> {
+    let mut ordered: Vec<&TradeActivity> = fills.iter().collect();
+    ordered.sort_by_key(|f| f.transaction_time);
+
+    // Signed open quantity and the round trip in progress, per symbol.
+    let mut open: HashMap<&str, (f64, RoundTrip)> = HashMap::new();
+    let mut closed = Vec::new();
+
+    for activity in ordered {
+        let (Ok(qty), Ok(price)) = (activity.qty.parse::<f64>(), activity.price.parse::<f64>())
+        else {
+            continue;
+        };
+        let signed = match activity.side {
+            OrderSide::Buy => qty,
+            OrderSide::Sell => -qty,
+        };
+        let fill = |qty: f64| JournalFill {
+            activity_id: activity.id.clone(),
+            order_id: activity.order_id.to_string(),
+            side: activity.side.clone(),
+            qty,
+            price,
+            time: activity.transaction_time,
+        };
+
+        let entry = open.remove(activity.symbol.as_str());
+        let Some((position, mut trip)) = entry else {
+            open.insert(
+                &activity.symbol,
+                (signed, new_trip(activity, signed, fill(qty))),
+            );
+            continue;
+        };
+
+        if position.signum() == signed.signum() {
+            trip.entry_fills.push(fill(qty));
+            open.insert(&activity.symbol, (position + signed, trip));
+            continue;
+        }
+
+        let closing = qty.min(position.abs());
+        trip.exit_fills.push(fill(closing));
+        let remaining = position + signed;
+        if remaining.abs() < QTY_EPSILON {
+            closed.push(trip);
+        } else if remaining.signum() == position.signum() {
+            open.insert(&activity.symbol, (remaining, trip));
+        } else {
+            closed.push(trip);
+            open.insert(
+                &activity.symbol,
+                (
+                    remaining,
+                    new_trip(activity, remaining, fill(remaining.abs())),
+                ),
+            );
+        }
+    }
+
+    closed.sort_by_key(RoundTrip::closed_at);
+    closed
+}
+
+fn new_trip(activity: &TradeActivity, signed: f64, fill: JournalFill) -> RoundTrip {
+    RoundTrip {
+        symbol: activity.symbol.clone(),
+        direction: if signed > 0.0 {
+            TradeDirection::Long
+        } else {
+            TradeDirection::Short
+        },
+        entry_fills: vec![fill],
+        exit_fills: Vec::new(),
+        entry_context: Vec::new(),
+        exit_context: Vec::new(),
+    }
+}
+
+/// Builds [`TradingJournal`]s from account fills and market data.
+#[derive(Debug, Clone)]
+pub struct JournalGenerator {
+    client: AlpacaHttpClient,
+    timeframe: String,
+    context_window: Duration,
+    feed: Option<DataFeed>,
+}
+
+impl JournalGenerator {
+    /// Create a generator using 1-minute bars within 15 minutes of each
+    /// entry and exit as market context.
+    #[must_use]
+    pub fn new(client: AlpacaHttpClient) -> Self {
+        Self {
+            client,
+            timeframe: "1Min".to_string(),
+            context_window: Duration::minutes(15),
+            feed: None,
+        }
+    }
+
+    /// Set the bar timeframe used for market context (e.g. `"5Min"`).
+    #[must_use]
+    pub fn timeframe(mut self, timeframe: &str) -> Self {
+        self.timeframe = timeframe.to_string();
+        self
+    }
+
+    /// Set how far before and after each entry/exit to fetch bars.
+    #[must_use]
+    pub fn context_window(mut self, window: Duration) -> Self {
+        self.context_window = window;
+        self
+    }
+
+    /// Set the data feed for context bars.
+    #[must_use]
+    pub fn feed(mut self, feed: DataFeed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+
+    /// Fetch all fill activities matching `params`, following pagination.
+    pub async fn fetch_fills(&self, params: &ListActivitiesParams) -> Result<Vec<TradeActivity>> {
+        collect_fills(params, |params| async move {
+            self.client
+                .get_with_params("/v2/account/activities", &params)
+                .await
+        })
+        .await
+    }
+
+    /// Generate a journal of the round trips closed within `params`.
+    pub async fn generate(&self, params: &ListActivitiesParams) -> Result<TradingJournal> {
+        let fills = self.fetch_fills(params).await?;
+        let mut trips = round_trips(&fills);
+        for trip in &mut trips {
+            trip.entry_context = self.context(&trip.symbol, trip.opened_at()).await?;
+            trip.exit_context = self.context(&trip.symbol, trip.closed_at()).await?;
+        }
+        Ok(TradingJournal {
+            generated_at: Utc::now(),
+            round_trips: trips,
+        })
+    }
+
+    async fn context(&self, symbol: &str, at: DateTime<Utc>) -> Result<Vec<Bar>> {
+        let params = BarsParams {
+            start: Some(at - self.context_window),
+            end: Some(at + self.context_window),
+            timeframe: Some(self.timeframe.clone()),
+            feed: self.feed,
+            ..Default::default()
+        };
+        Ok(self.client.get_bars(symbol, &params).await?.bars)
+    }
+}
+
+/// Page through fill activities with `fetch`, `FILL_PAGE_SIZE` at a time
+/// unless `params` sets a page size.
+async fn collect_fills<F, Fut>(
+    params: &ListActivitiesParams,
+    mut fetch: F,
+) -> Result<Vec<TradeActivity>>
+where
+    F: FnMut(ListActivitiesParams) -> Fut,
+    Fut: Future<Output = Result<Vec<TradeActivity>>>,
+{
+    let page_size = params.page_size.unwrap_or(FILL_PAGE_SIZE);
+    let mut params = params
+        .clone()
+        .activity_types("FILL")
+        .direction(SortDirection::Asc)
+        .page_size(page_size);
+    let mut fills: Vec<TradeActivity> = Vec::new();
+    loop {
+        let page = fetch(params.clone()).await?;
+        let Some(last) = page.last() else { break };
+        params.page_token = Some(last.id.clone());
+        let page_len = page.len();
+        fills.extend(page);
+        if page_len < page_size as usize {
+            break;
+        }
+    }
+    Ok(fills)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::types::ActivityType;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    fn fill(
+        id: &str,
+        symbol: &str,
+        side: OrderSide,
+        qty: &str,
+        price: &str,
+        min: u32,
+    ) -> TradeActivity {
+        TradeActivity {
+            id: id.to_string(),
+            activity_type: ActivityType::Fill,
+            transaction_time: Utc.with_ymd_and_hms(2024, 3, 1, 15, min, 0).unwrap(),
+            symbol: symbol.to_string(),
+            order_id: Uuid::new_v4(),
+            side,
+            qty: qty.to_string(),
+            price: price.to_string(),
+            cum_qty: None,
+            leaves_qty: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trips_scale_in_and_out() {
+        let fills = vec![
+            fill("1", "AAPL", OrderSide::Buy, "10", "100", 0),
+            fill("2", "AAPL", OrderSide::Buy, "10", "102", 5),
+            fill("3", "AAPL", OrderSide::Sell, "5", "105", 10),
+            fill("4", "AAPL", OrderSide::Sell, "15", "104", 20),
+            fill("5", "MSFT", OrderSide::Buy, "1", "400", 25),
+        ];
+        let trips = round_trips(&fills);
+        assert_eq!(trips.len(), 1);
+        let trip = &trips[0];
+        assert_eq!(trip.direction, TradeDirection::Long);
+        assert_eq!(trip.qty(), 20.0);
+        assert_eq!(trip.avg_entry_price(), 101.0);
+        assert_eq!(trip.avg_exit_price(), 104.25);
+        assert!((trip.realized_pnl() - 65.0).abs() < 1e-9);
+        assert_eq!(trip.holding_time(), Duration::minutes(20));
+    }
+
+    #[test]
+    fn test_round_trips_flip_and_short() {
+        let fills = vec![
+            fill("1", "TSLA", OrderSide::Buy, "10", "200", 0),
+            fill("2", "TSLA", OrderSide::Sell, "15", "190", 1),
+            fill("3", "TSLA", OrderSide::Buy, "5", "180", 2),
+        ];
+        let trips = round_trips(&fills);
+        assert_eq!(trips.len(), 2);
+        assert_eq!(trips[0].direction, TradeDirection::Long);
+        assert_eq!(trips[0].exit_fills[0].qty, 10.0);
+        assert!((trips[0].realized_pnl() + 100.0).abs() < 1e-9);
+        assert_eq!(trips[1].direction, TradeDirection::Short);
+        assert_eq!(trips[1].qty(), 5.0);
+        assert!((trips[1].realized_pnl() - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_round_trips_fractional_residue() {
+        // 10.1 + 20.2 - 30.3 leaves -3.6e-15 in f64.
+        let fills = [
+            fill("1", "AAPL", OrderSide::Buy, "10.1", "100", 0),
+            fill("2", "AAPL", OrderSide::Buy, "20.2", "101", 1),
+            fill("3", "AAPL", OrderSide::Sell, "30.3", "102", 2),
+            fill("4", "AAPL", OrderSide::Buy, "1", "103", 3),
+            fill("5", "AAPL", OrderSide::Sell, "1", "104", 4),
+        ];
+        let trips = round_trips(&fills);
+        assert_eq!(trips.len(), 2);
+        assert!(
+            trips
+                .iter()
+                .all(|trip| trip.direction == TradeDirection::Long)
+        );
+    }
+
+    #[test]
+    fn test_journal_exports() {
+        let journal = TradingJournal {
+            generated_at: Utc::now(),
+            round_trips: round_trips(&[
+                fill("1", "AAPL", OrderSide::Buy, "2", "100", 0),
+                fill("2", "AAPL", OrderSide::Sell, "2", "99", 3),
+            ]),
+        };
+        assert_eq!(journal.win_rate(), 0.0);
+        let markdown = journal.to_markdown();
+        assert!(markdown.contains("## 1. AAPL Long x2"));
+        assert!(markdown.contains("| -2.00 |"));
+        let parsed: TradingJournal = serde_json::from_str(&journal.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, journal);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_fills_follows_pages() {
+        let fills: Vec<_> = (0..150)
+            .map(|i| fill(&format!("{i:03}"), "AAPL", OrderSide::Buy, "1", "100", 0))
+            .collect();
+        let mut requests = Vec::new();
+        let fetched = collect_fills(&ListActivitiesParams::new(), |params| {
+            requests.push((params.page_size, params.page_token.clone()));
+            let start = params
+                .page_token
+                .map_or(0, |token| token.parse::<usize>().unwrap() + 1);
+            let end = (start + params.page_size.unwrap() as usize).min(fills.len());
+            std::future::ready(Ok(fills[start..end].to_vec()))
+        })
+        .await
+        .unwrap();
+        assert_eq!(fetched.len(), 150);
+        assert_eq!(
+            requests,
+            [(Some(100), None), (Some(100), Some("099".to_string()))]
+        );
+    }
+}
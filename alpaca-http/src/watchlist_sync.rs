@@ -0,0 +1,261 @@
+//! Declarative watchlist reconciliation.
+//!
+//! [`WatchlistSync`] takes the set of symbols a watchlist should hold and
+//! brings the named remote watchlist in line with it: the watchlist is
+//! looked up by name (and created with the symbols if it does not exist),
+//! then missing symbols are added and extra ones removed. Additions and
+//! removals are sent in concurrent batches of
+//! [`WatchlistSync::batch_size`], and the outcome is reported as a
+//! [`WatchlistSyncReport`], with symbols that could not be changed listed
+//! rather than aborting the whole sync.
+
+use crate::client::AlpacaHttpClient;
+use crate::endpoints::CreateWatchlistRequest;
+use alpaca_base::{AlpacaError, ApiErrorCode, Result, types::Watchlist};
+use std::collections::BTreeSet;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+/// Difference between a watchlist's symbols and the desired ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchlistDiff {
+    /// Symbols to add.
+    pub added: Vec<String>,
+    /// Symbols to remove.
+    pub removed: Vec<String>,
+    /// Symbols already present.
+    pub unchanged: Vec<String>,
+}
+
+impl WatchlistDiff {
+    /// Whether the watchlist already matches.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Result of [`WatchlistSync::run`].
+#[derive(Debug)]
+pub struct WatchlistSyncReport {
+    /// ID of the synced watchlist.
+    pub watchlist_id: Uuid,
+    /// Whether the watchlist had to be created.
+    pub created: bool,
+    /// Changes that were applied.
+    pub diff: WatchlistDiff,
+    /// Symbols whose addition or removal failed, with the error.
+    pub failed: Vec<(String, AlpacaError)>,
+}
+
+impl WatchlistSyncReport {
+    /// Whether every change was applied.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl std::fmt::Display for WatchlistSyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "+{} -{} ={}",
+            self.diff.added.len(),
+            self.diff.removed.len(),
+            self.diff.unchanged.len()
+        )?;
+        if self.created {
+            write!(f, " (created)")?;
+        }
+        if !self.failed.is_empty() {
+            write!(f, " ({} failed)", self.failed.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Reconciles a named watchlist against a desired set of symbols.
+#[derive(Debug, Clone)]
+pub struct WatchlistSync {
+    name: String,
+    symbols: BTreeSet<String>,
+    create_if_missing: bool,
+    batch_size: usize,
+}
+
+impl WatchlistSync {
+    /// Sync the watchlist `name` to hold exactly `symbols`. Symbols are
+    /// compared upper-cased.
+    #[must_use]
+    pub fn new(
+        name: impl Into<String>,
+        symbols: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            symbols: symbols
+                .into_iter()
+                .map(|symbol| symbol.as_ref().trim().to_uppercase())
+                .filter(|symbol| !symbol.is_empty())
+                .collect(),
+            create_if_missing: true,
+            batch_size: 5,
+        }
+    }
+
+    /// Set whether a missing watchlist is created (default) or reported as
+    /// not found.
+    #[must_use]
+    pub fn create_if_missing(mut self, create_if_missing: bool) -> Self {
+        self.create_if_missing = create_if_missing;
+        self
+    }
+
+    /// Set how many additions or removals are sent at once.
+    #[must_use]
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// The changes that would bring `watchlist` in line.
+    #[must_use]
+    pub fn plan(&self, watchlist: &Watchlist) -> WatchlistDiff {
+        let current: BTreeSet<String> = watchlist
+            .assets
+            .iter()
+            .map(|asset| asset.symbol.to_uppercase())
+            .collect();
+        WatchlistDiff {
+            added: self.symbols.difference(&current).cloned().collect(),
+            removed: current.difference(&self.symbols).cloned().collect(),
+            unchanged: self.symbols.intersection(&current).cloned().collect(),
+        }
+    }
+
+    /// Reconcile the remote watchlist.
+    ///
+    /// # Errors
+    /// Returns an error if the watchlists cannot be listed or created, or
+    /// a 404 API error if the watchlist does not exist and
+    /// [`create_if_missing`](Self::create_if_missing) is off. Failures of
+    /// individual symbols are reported in
+    /// [`WatchlistSyncReport::failed`].
+    pub async fn run(&self, client: &AlpacaHttpClient) -> Result<WatchlistSyncReport> {
+        let existing = client
+            .get_watchlists()
+            .await?
+            .into_iter()
+            .find(|watchlist| watchlist.name == self.name);
+
+        let Some(watchlist) = existing else {
+            if !self.create_if_missing {
+                return Err(AlpacaError::api_with_details(
+                    404,
+                    format!("watchlist {} not found", self.name),
+                    ApiErrorCode::NotFound,
+                    None,
+                ));
+            }
+            let created = client
+                .create_watchlist(&CreateWatchlistRequest {
+                    name: self.name.clone(),
+                    symbols: Some(self.symbols.iter().cloned().collect()),
+                })
+                .await?;
+            return Ok(WatchlistSyncReport {
+                watchlist_id: created.id,
+                created: true,
+                diff: WatchlistDiff {
+                    added: self.symbols.iter().cloned().collect(),
+                    ..WatchlistDiff::default()
+                },
+                failed: Vec::new(),
+            });
+        };
+
+        let diff = self.plan(&watchlist);
+        let mut failed = Vec::new();
+        for batch in diff.added.chunks(self.batch_size) {
+            let mut tasks = JoinSet::new();
+            for symbol in batch {
+                let client = client.clone();
+                let symbol = symbol.clone();
+                let id = watchlist.id;
+                tasks.spawn(async move {
+                    let result = client.add_to_watchlist(&id, &symbol).await.map(drop);
+                    (symbol, result)
+                });
+            }
+            failed.extend(collect_failures(&mut tasks).await);
+        }
+        for batch in diff.removed.chunks(self.batch_size) {
+            let mut tasks = JoinSet::new();
+            for symbol in batch {
+                let client = client.clone();
+                let symbol = symbol.clone();
+                let id = watchlist.id;
+                tasks.spawn(async move {
+                    let result = client.remove_from_watchlist(&id, &symbol).await;
+                    (symbol, result)
+                });
+            }
+            failed.extend(collect_failures(&mut tasks).await);
+        }
+        failed.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(WatchlistSyncReport {
+            watchlist_id: watchlist.id,
+            created: false,
+            diff,
+            failed,
+        })
+    }
+}
+
+async fn collect_failures(tasks: &mut JoinSet<(String, Result<()>)>) -> Vec<(String, AlpacaError)> {
+    let mut failed = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((_, Ok(()))) => {}
+            Ok((symbol, Err(e))) => failed.push((symbol, e)),
+            Err(e) => tracing::warn!("Watchlist sync task failed: {}", e),
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::sample_asset;
+    use chrono::Utc;
+
+    #[test]
+    fn test_watchlist_sync_plan() {
+        let watchlist = Watchlist {
+            id: Uuid::new_v4(),
+            account_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            name: "Tech".to_string(),
+            assets: ["AAPL", "MSFT", "IBM"].map(sample_asset).to_vec(),
+        };
+        let sync = WatchlistSync::new("Tech", ["aapl", "MSFT", "NVDA", " ", "NVDA"]);
+        let diff = sync.plan(&watchlist);
+        assert_eq!(diff.added, ["NVDA"]);
+        assert_eq!(diff.removed, ["IBM"]);
+        assert_eq!(diff.unchanged, ["AAPL", "MSFT"]);
+        assert!(!diff.is_empty());
+
+        let report = WatchlistSyncReport {
+            watchlist_id: watchlist.id,
+            created: false,
+            diff,
+            failed: vec![("IBM".to_string(), AlpacaError::api(500, "internal error"))],
+        };
+        assert!(!report.is_complete());
+        assert_eq!(report.to_string(), "+1 -1 =2 (1 failed)");
+    }
+}
@@ -0,0 +1,466 @@
+//! Receiving Broker API events over webhooks.
+//!
+//! An alternative to the SSE endpoints for deployments that prefer being
+//! called over holding streams open. [`WebhookServer`] is an axum listener
+//! with one route per event kind, mirroring the SSE endpoints under its
+//! base path (default `/webhooks/alpaca`):
+//!
+//! | Route                | Event type                |
+//! |----------------------|---------------------------|
+//! | `/accounts/status`   | [`AccountStatusEvent`]    |
+//! | `/transfers/status`  | [`TransferStatusEvent`]   |
+//! | `/trades`            | [`BrokerTradeEvent`]      |
+//! | `/journals/status`   | [`JournalStatusEvent`]    |
+//! | `/nta`               | [`NonTradeActivityEvent`] |
+//!
+//! A request body holds one event or an array of them. Requests must carry
+//! an HMAC-SHA256 signature of the body (see [`verify_signature`]) in the
+//! signature header, or they are refused with `401`; bodies that are not
+//! events get `400`. Each event is passed to the matching handlers, and
+//! the request is answered `200` once they have all returned.
+//!
+//! ```rust,ignore
+//! let server = WebhookServer::new(secret)
+//!     .on_account_status(|event| async move { println!("{} is now {:?}", event.account_id, event.event_type) })
+//!     .on_transfer_status(|event| async move { ledger.record(event).await });
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
+//! server.serve(listener, cancel).await?;
+//! ```
+
+use alpaca_base::{
+    AlpacaError, CancellationToken, Result,
+    types::{
+        AccountStatusEvent, BrokerSseEvent, BrokerTradeEvent, JournalStatusEvent,
+        NonTradeActivityEvent, TransferStatusEvent,
+    },
+};
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::{MethodRouter, post},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use hmac::{Hmac, KeyInit, Mac};
+use serde::{Deserialize, de::DeserializeOwned};
+use sha2::Sha256;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Default base path of the webhook routes.
+pub const DEFAULT_WEBHOOK_PATH: &str = "/webhooks/alpaca";
+
+/// Default header carrying the body signature.
+pub const DEFAULT_SIGNATURE_HEADER: &str = "X-Alpaca-Signature";
+
+type HmacSha256 = Hmac<Sha256>;
+type HandlerFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type Handler = Arc<dyn Fn(&BrokerSseEvent) -> Option<HandlerFuture> + Send + Sync>;
+
+/// One event or a batch of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Payload<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+/// HMAC-SHA256 signature of `body` under `secret`, hex encoded. With a
+/// `timestamp`, the signed message is `"{timestamp}.{body}"`.
+#[must_use]
+pub fn sign_payload(secret: &[u8], timestamp: Option<&str>, body: &[u8]) -> String {
+    mac(secret, timestamp, body)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Check `signature` against the HMAC-SHA256 of `body` under `secret`, in
+/// constant time. The signature may be hex or base64 encoded, optionally
+/// prefixed with `sha256=`. With a `timestamp`, the signed message is
+/// `"{timestamp}.{body}"`.
+#[must_use]
+pub fn verify_signature(
+    secret: &[u8],
+    timestamp: Option<&str>,
+    body: &[u8],
+    signature: &str,
+) -> bool {
+    let signature = signature.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Some(expected) = decode_hex(signature).or_else(|| STANDARD.decode(signature).ok()) else {
+        return false;
+    };
+    mac(secret, timestamp, body).verify_slice(&expected).is_ok()
+}
+
+fn mac(secret: &[u8], timestamp: Option<&str>, body: &[u8]) -> HmacSha256 {
+    // HMAC accepts keys of any length.
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+    if let Some(timestamp) = timestamp {
+        mac.update(timestamp.as_bytes());
+        mac.update(b".");
+    }
+    mac.update(body);
+    mac
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Broker API webhook listener dispatching events to handlers.
+#[derive(Clone)]
+pub struct WebhookServer {
+    secret: Vec<u8>,
+    path: String,
+    signature_header: String,
+    timestamp_header: Option<(String, Duration)>,
+    handlers: Vec<Handler>,
+}
+
+impl std::fmt::Debug for WebhookServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookServer")
+            .field("path", &self.path)
+            .field("signature_header", &self.signature_header)
+            .field("timestamp_header", &self.timestamp_header)
+            .field("handlers", &self.handlers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebhookServer {
+    /// Create a server verifying signatures with `secret`.
+    #[must_use]
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+            path: DEFAULT_WEBHOOK_PATH.to_string(),
+            signature_header: DEFAULT_SIGNATURE_HEADER.to_string(),
+            timestamp_header: None,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Serve the routes under `path` instead of [`DEFAULT_WEBHOOK_PATH`].
+    #[must_use]
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Read the signature from `header` instead of
+    /// [`DEFAULT_SIGNATURE_HEADER`].
+    #[must_use]
+    pub fn signature_header(mut self, header: impl Into<String>) -> Self {
+        self.signature_header = header.into();
+        self
+    }
+
+    /// Require a Unix timestamp in `header`, included in the signature,
+    /// and refuse requests more than `tolerance` away from now, so a
+    /// captured request cannot be replayed later.
+    #[must_use]
+    pub fn timestamp_header(mut self, header: impl Into<String>, tolerance: Duration) -> Self {
+        self.timestamp_header = Some((header.into(), tolerance));
+        self
+    }
+
+    /// Call `handler` with every event.
+    #[must_use]
+    pub fn on_event<F, Fut>(self, handler: F) -> Self
+    where
+        F: Fn(BrokerSseEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on(Some, handler)
+    }
+
+    /// Call `handler` with account status events.
+    #[must_use]
+    pub fn on_account_status<F, Fut>(self, handler: F) -> Self
+    where
+        F: Fn(AccountStatusEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on(
+            |event| match event {
+                BrokerSseEvent::AccountStatus(event) => Some(event),
+                _ => None,
+            },
+            handler,
+        )
+    }
+
+    /// Call `handler` with transfer status events.
+    #[must_use]
+    pub fn on_transfer_status<F, Fut>(self, handler: F) -> Self
+    where
+        F: Fn(TransferStatusEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on(
+            |event| match event {
+                BrokerSseEvent::TransferStatus(event) => Some(event),
+                _ => None,
+            },
+            handler,
+        )
+    }
+
+    /// Call `handler` with trade events.
+    #[must_use]
+    pub fn on_trade<F, Fut>(self, handler: F) -> Self
+    where
+        F: Fn(BrokerTradeEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on(
+            |event| match event {
+                BrokerSseEvent::Trade(event) => Some(event),
+                _ => None,
+            },
+            handler,
+        )
+    }
+
+    /// Call `handler` with journal status events.
+    #[must_use]
+    pub fn on_journal_status<F, Fut>(self, handler: F) -> Self
+    where
+        F: Fn(JournalStatusEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on(
+            |event| match event {
+                BrokerSseEvent::JournalStatus(event) => Some(event),
+                _ => None,
+            },
+            handler,
+        )
+    }
+
+    /// Call `handler` with non-trade activity events.
+    #[must_use]
+    pub fn on_non_trade_activity<F, Fut>(self, handler: F) -> Self
+    where
+        F: Fn(NonTradeActivityEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on(
+            |event| match event {
+                BrokerSseEvent::NonTradeActivity(event) => Some(event),
+                _ => None,
+            },
+            handler,
+        )
+    }
+
+    fn on<T, F, Fut>(mut self, select: fn(BrokerSseEvent) -> Option<T>, handler: F) -> Self
+    where
+        T: 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.handlers.push(Arc::new(move |event: &BrokerSseEvent| {
+            select(event.clone()).map(|event| Box::pin(handler(event)) as HandlerFuture)
+        }));
+        self
+    }
+
+    /// The webhook routes, to serve or merge into an existing axum app.
+    pub fn router(self) -> Router {
+        let path = self.path.clone();
+        Router::new()
+            .route(
+                &format!("{path}/accounts/status"),
+                route(BrokerSseEvent::AccountStatus),
+            )
+            .route(
+                &format!("{path}/transfers/status"),
+                route(BrokerSseEvent::TransferStatus),
+            )
+            .route(&format!("{path}/trades"), route(BrokerSseEvent::Trade))
+            .route(
+                &format!("{path}/journals/status"),
+                route(BrokerSseEvent::JournalStatus),
+            )
+            .route(
+                &format!("{path}/nta"),
+                route(BrokerSseEvent::NonTradeActivity),
+            )
+            .with_state(Arc::new(self))
+    }
+
+    /// Serve the webhook routes on `listener` until `cancel` is triggered.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::Network`] if the listener fails.
+    pub async fn serve(
+        self,
+        listener: tokio::net::TcpListener,
+        cancel: CancellationToken,
+    ) -> Result<()> {
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(cancel.cancelled_owned())
+            .await
+            .map_err(|e| AlpacaError::Network(format!("webhook server: {e}")))
+    }
+
+    /// Verify, decode and dispatch one request.
+    async fn receive<T: DeserializeOwned>(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        wrap: fn(T) -> BrokerSseEvent,
+    ) -> StatusCode {
+        if let Err(reason) = self.authenticate(headers, body) {
+            warn!("Rejected webhook request: {}", reason);
+            return StatusCode::UNAUTHORIZED;
+        }
+        let events = match serde_json::from_slice::<Payload<T>>(body) {
+            Ok(Payload::One(event)) => vec![wrap(event)],
+            Ok(Payload::Many(events)) => events.into_iter().map(wrap).collect(),
+            Err(e) => {
+                warn!("Undecodable webhook payload: {}", e);
+                return StatusCode::BAD_REQUEST;
+            }
+        };
+        for event in &events {
+            for handler in &self.handlers {
+                if let Some(future) = handler(event) {
+                    future.await;
+                }
+            }
+        }
+        debug!("Dispatched {} webhook event(s)", events.len());
+        StatusCode::OK
+    }
+
+    fn authenticate(&self, headers: &HeaderMap, body: &[u8]) -> std::result::Result<(), String> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let timestamp = match &self.timestamp_header {
+            Some((name, tolerance)) => {
+                let timestamp = header(name).ok_or_else(|| format!("missing {name}"))?;
+                let at: i64 = timestamp
+                    .parse()
+                    .map_err(|_| format!("invalid {name}: {timestamp}"))?;
+                let skew = (chrono::Utc::now().timestamp() - at).unsigned_abs();
+                if skew > tolerance.as_secs() {
+                    return Err(format!("{name} is {skew}s away from now"));
+                }
+                Some(timestamp)
+            }
+            None => None,
+        };
+        let signature = header(&self.signature_header)
+            .ok_or_else(|| format!("missing {}", self.signature_header))?;
+        if verify_signature(&self.secret, timestamp, body, signature) {
+            Ok(())
+        } else {
+            Err("signature mismatch".to_string())
+        }
+    }
+}
+
+/// The route receiving events of type `T`.
+fn route<T>(wrap: fn(T) -> BrokerSseEvent) -> MethodRouter<Arc<WebhookServer>>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    post(
+        move |State(server): State<Arc<WebhookServer>>, headers: HeaderMap, body: Bytes| async move {
+            server.receive(&headers, &body, wrap).await
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_webhook_verifies_and_dispatches() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let all = tx.clone();
+        let server = WebhookServer::new("secret")
+            .timestamp_header("X-Alpaca-Timestamp", Duration::from_secs(300))
+            .on_transfer_status(move |event| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(format!("transfer {}", event.transfer_id));
+                }
+            })
+            .on_event(move |_| {
+                let all = all.clone();
+                async move {
+                    let _ = all.send("event".to_string());
+                }
+            });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}{DEFAULT_WEBHOOK_PATH}/transfers/status",
+            listener.local_addr().unwrap()
+        );
+        let cancel = CancellationToken::new();
+        let serving = tokio::spawn(server.serve(listener, cancel.clone()));
+
+        let body = serde_json::json!([{
+            "id": "1",
+            "account_id": "acct",
+            "transfer_id": "tr-1",
+            "event_type": "TRANSFER_QUEUED",
+            "at": "2024-06-03T14:30:00Z"
+        }])
+        .to_string();
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let post = |signature: String, timestamp: &str| {
+            reqwest::Client::new()
+                .post(&url)
+                .header(DEFAULT_SIGNATURE_HEADER, signature)
+                .header("X-Alpaca-Timestamp", timestamp)
+                .body(body.clone())
+                .send()
+        };
+
+        let signature = sign_payload(b"secret", Some(&timestamp), body.as_bytes());
+        assert_eq!(
+            post(signature.clone(), &timestamp).await.unwrap().status(),
+            200
+        );
+        assert_eq!(rx.recv().await.unwrap(), "transfer tr-1");
+        assert_eq!(rx.recv().await.unwrap(), "event");
+
+        let forged = sign_payload(b"other", Some(&timestamp), body.as_bytes());
+        assert_eq!(post(forged, &timestamp).await.unwrap().status(), 401);
+        assert_eq!(post(signature, "1000").await.unwrap().status(), 401);
+        assert!(rx.try_recv().is_err());
+
+        let base64 = STANDARD.encode(mac(b"k", None, b"{}").finalize().into_bytes());
+        assert!(verify_signature(b"k", None, b"{}", &base64));
+        assert!(verify_signature(
+            b"k",
+            None,
+            b"{}",
+            &format!("sha256={}", sign_payload(b"k", None, b"{}"))
+        ));
+        assert!(!verify_signature(b"k", None, b"{}", "zz"));
+
+        cancel.cancel();
+        serving.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,57 @@
+//! Contract tests: recorded API responses against the crate's serde models.
+//!
+//! Each file in `tests/contracts/` is a response body recorded from the
+//! live API (IDs and balances anonymised). To refresh one, capture the
+//! endpoint with your paper credentials, e.g.
+//!
+//! ```bash
+//! curl -s -H "APCA-API-KEY-ID: $ALPACA_API_KEY" \
+//!      -H "APCA-API-SECRET-KEY: $ALPACA_API_SECRET" \
+//!      https://paper-api.alpaca.markets/v2/account > tests/contracts/account.json
+//! ```
+//!
+//! A response that no longer deserializes fails the test. Fields the
+//! models don't cover yet are printed as drift; set
+//! `ALPACA_CONTRACT_STRICT=1` to fail on them as well.
+
+use alpaca_base::test_utils::contract::{ContractReport, check};
+use alpaca_base::types::{Account, Asset, Calendar, Clock, Order, Position};
+
+macro_rules! contract {
+    ($model:ty, $file:literal) => {
+        check::<$model>(
+            concat!($file, " -> ", stringify!($model)),
+            include_str!(concat!("contracts/", $file)),
+        )
+    };
+}
+
+fn reports() -> Vec<ContractReport> {
+    vec![
+        contract!(Account, "account.json"),
+        contract!(Order, "order.json"),
+        contract!(Position, "position.json"),
+        contract!(Asset, "asset.json"),
+        contract!(Clock, "clock.json"),
+        contract!(Vec<Calendar>, "calendar.json"),
+    ]
+}
+
+#[test]
+fn recorded_responses_match_models() {
+    let strict = std::env::var("ALPACA_CONTRACT_STRICT").is_ok_and(|v| v == "1");
+    let reports = reports();
+    for report in &reports {
+        eprintln!("{report}");
+    }
+
+    let breaking: Vec<_> = reports.iter().filter(|r| r.is_breaking()).collect();
+    assert!(
+        breaking.is_empty(),
+        "breaking schema changes: {breaking:#?}"
+    );
+    if strict {
+        let drifted: Vec<_> = reports.iter().filter(|r| !r.is_clean()).collect();
+        assert!(drifted.is_empty(), "unknown fields: {drifted:#?}");
+    }
+}
@@ -0,0 +1,133 @@
+//! Decode cost of a market data frame with each [`Codec`].
+//!
+//! Run with `cargo bench -p alpaca-websocket --bench codec`. Reports the
+//! heap allocations and wall time per decoded frame; the frame holds the
+//! same 100 trades and quotes in both encodings.
+
+use alpaca_websocket::Codec;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const MESSAGES: usize = 100;
+const ITERATIONS: usize = 2_000;
+
+fn json_frame() -> Vec<u8> {
+    let messages: Vec<String> = (0..MESSAGES)
+        .map(|i| {
+            if i % 2 == 0 {
+                format!(
+                    r#"{{"T":"t","S":"AAPL","t":"2024-01-02T14:30:00.{i:09}Z","p":190.{i},"s":100,"x":"V","c":["@"],"i":{i},"z":"C"}}"#
+                )
+            } else {
+                format!(
+                    r#"{{"T":"q","S":"AAPL","t":"2024-01-02T14:30:00.{i:09}Z","bp":190.1,"bs":3,"ap":190.2,"as":5,"bx":"V","ax":"V","c":["R"],"z":"C"}}"#
+                )
+            }
+        })
+        .collect();
+    format!("[{}]", messages.join(",")).into_bytes()
+}
+
+fn msgpack_frame() -> Vec<u8> {
+    use rmp::encode;
+
+    let mut frame = Vec::new();
+    encode::write_array_len(&mut frame, MESSAGES as u32).unwrap();
+    let write_timestamp = |frame: &mut Vec<u8>, nanos: u32| {
+        encode::write_str(frame, "t").unwrap();
+        encode::write_ext_meta(frame, 12, -1).unwrap();
+        frame.extend_from_slice(&nanos.to_be_bytes());
+        frame.extend_from_slice(&1_704_205_800i64.to_be_bytes());
+    };
+    for i in 0..MESSAGES {
+        let strings: &[(&str, &str)] = if i % 2 == 0 {
+            &[("T", "t"), ("S", "AAPL"), ("x", "V"), ("z", "C")]
+        } else {
+            &[
+                ("T", "q"),
+                ("S", "AAPL"),
+                ("bx", "V"),
+                ("ax", "V"),
+                ("z", "C"),
+            ]
+        };
+        let numbers: &[(&str, f64)] = if i % 2 == 0 {
+            &[("p", 190.5)]
+        } else {
+            &[("bp", 190.1), ("ap", 190.2)]
+        };
+        let integers: &[(&str, u64)] = if i % 2 == 0 {
+            &[("s", 100), ("i", i as u64)]
+        } else {
+            &[("bs", 3), ("as", 5)]
+        };
+        let len = strings.len() + numbers.len() + integers.len() + 2;
+        encode::write_map_len(&mut frame, len as u32).unwrap();
+        for (key, value) in strings {
+            encode::write_str(&mut frame, key).unwrap();
+            encode::write_str(&mut frame, value).unwrap();
+        }
+        for (key, value) in numbers {
+            encode::write_str(&mut frame, key).unwrap();
+            encode::write_f64(&mut frame, *value).unwrap();
+        }
+        for (key, value) in integers {
+            encode::write_str(&mut frame, key).unwrap();
+            encode::write_uint(&mut frame, *value).unwrap();
+        }
+        write_timestamp(&mut frame, i as u32);
+        encode::write_str(&mut frame, "c").unwrap();
+        encode::write_array_len(&mut frame, 1).unwrap();
+        encode::write_str(&mut frame, if i % 2 == 0 { "@" } else { "R" }).unwrap();
+    }
+    frame
+}
+
+fn bench(name: &str, codec: Codec, frame: &[u8]) {
+    let (updates, _) = codec.decode_market_data(frame);
+    assert_eq!(updates.len(), MESSAGES, "{name} frame must decode fully");
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(codec.decode_market_data(black_box(frame)));
+    }
+    let elapsed = started.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!(
+        "{name:<12} {:>6} bytes  {:>8} allocs/frame  {:>10.1?}/frame",
+        frame.len(),
+        allocations / ITERATIONS,
+        elapsed / ITERATIONS as u32,
+    );
+}
+
+fn main() {
+    bench("json", Codec::Json, &json_frame());
+    bench("messagepack", Codec::MessagePack, &msgpack_frame());
+}
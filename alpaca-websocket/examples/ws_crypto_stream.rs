@@ -17,7 +17,7 @@
 //! **Note**: Crypto markets are open 24/7, so this example should work anytime.
 
 use alpaca_base::Environment;
-use alpaca_websocket::{AlpacaWebSocketClient, SubscribeMessage};
+use alpaca_websocket::{AlpacaWebSocketClient, OrderBooks, SubscribeMessage};
 use futures_util::StreamExt;
 
 #[tokio::main]
@@ -33,12 +33,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let symbols = vec!["BTC/USD".to_string(), "ETH/USD".to_string()];
     println!("Subscribing to crypto data for: {:?}", symbols);
 
-    // Create subscription message for trades and quotes
+    // Create subscription message for trades, quotes and order books
     let subscription = SubscribeMessage {
         trades: Some(symbols.clone()),
         quotes: Some(symbols.clone()),
         bars: None,
         trade_updates: None,
+        orderbooks: Some(symbols.clone()),
     };
 
     // Connect and subscribe
@@ -52,6 +53,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut stream = stream;
     let mut update_count = 0;
+    let mut books = OrderBooks::new();
 
     while let Some(update) = stream.next().await {
         update_count += 1;
@@ -76,6 +78,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     update_count, symbol, bar.open, bar.high, bar.low, bar.close
                 );
             }
+            update @ alpaca_websocket::MarketDataUpdate::Orderbook { .. } => {
+                if let Some(change) = books.apply(&update)
+                    && change.top_changed
+                {
+                    let book = books.get(&change.symbol).expect("book was just updated");
+                    println!(
+                        "[{}] BOOK {} - Mid: ${:.2} | Spread: {:.1} bps | Levels: {}/{}",
+                        update_count,
+                        change.symbol,
+                        book.mid().unwrap_or_default(),
+                        book.spread_bps().unwrap_or_default(),
+                        book.level_count(alpaca_websocket::BookSide::Bid),
+                        book.level_count(alpaca_websocket::BookSide::Ask),
+                    );
+                }
+            }
         }
 
         // Stop after 20 updates for demo
@@ -39,6 +39,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         quotes: None,
         bars: Some(symbols.clone()),
         trade_updates: None,
+        orderbooks: None,
     };
 
     // Connect and subscribe
@@ -39,6 +39,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         quotes: Some(symbols.clone()),
         bars: None,
         trade_updates: None,
+        orderbooks: None,
     };
 
     // Connect and subscribe
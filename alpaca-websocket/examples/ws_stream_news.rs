@@ -17,104 +17,55 @@
 //!
 //! **Note**: News streaming requires appropriate API subscription.
 
-use alpaca_websocket::StreamType;
+use alpaca_base::Environment;
+use alpaca_websocket::{AlpacaWebSocketClient, NewsEvent};
+use futures_util::StreamExt;
 
-fn main() {
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== News Stream ===\n");
 
-    // News stream configuration
-    println!("--- News Stream Configuration ---");
-    let stream_type = StreamType::News;
-    println!("  Stream type: {:?}", stream_type);
-    println!("  Paper URL: {}", stream_type.url(true));
-    println!("  Live URL: {}", stream_type.url(false));
+    // Create news WebSocket client
+    let credentials = alpaca_base::Credentials::from_env()?;
+    let client = AlpacaWebSocketClient::news(credentials, Environment::Paper);
+    println!("News WebSocket client created: {}", client.url());
 
-    // News subscription format
-    println!("\n--- News Subscription ---");
-    println!("  To subscribe to news for specific symbols:");
-    println!("  {{");
-    println!("    \"action\": \"subscribe\",");
-    println!("    \"news\": [\"AAPL\", \"TSLA\", \"*\"]");
-    println!("  }}");
-    println!();
-    println!("  Use \"*\" to subscribe to all news");
+    // Subscribe to news for a few symbols ("*" subscribes to all news)
+    let symbols = ["AAPL", "TSLA", "NVDA"];
+    println!("\nSubscribing to news for {:?}...", symbols);
+    let mut stream = client.subscribe_news(&symbols).await?;
+    println!("Connected! Waiting for headlines...");
+    println!("(Press Ctrl+C to stop)\n");
 
-    // News message format
-    println!("\n--- News Message Format ---");
-    println!("  {{");
-    println!("    \"T\": \"n\",           // Message type: news");
-    println!("    \"id\": 12345,        // News article ID");
-    println!("    \"headline\": \"...\", // Article headline");
-    println!("    \"summary\": \"...\",  // Article summary");
-    println!("    \"author\": \"...\",   // Author name");
-    println!("    \"created_at\": \"...\", // ISO timestamp");
-    println!("    \"updated_at\": \"...\", // ISO timestamp");
-    println!("    \"url\": \"...\",      // Article URL");
-    println!("    \"content\": \"...\",  // Full content");
-    println!("    \"symbols\": [\"AAPL\"], // Related symbols");
-    println!("    \"source\": \"...\"    // News source");
-    println!("  }}");
+    let mut article_count = 0;
+    while let Some(event) = stream.next().await {
+        match event {
+            NewsEvent::Update(article) => {
+                article_count += 1;
+                println!("[{}] {}", article_count, article.headline);
+                println!("    Symbols: {}", article.symbols.join(", "));
+                println!("    Source: {}", article.source.as_deref().unwrap_or("-"));
+                println!("    Published: {}", article.created_at);
+                if let Some(url) = &article.url {
+                    println!("    URL: {}", url);
+                }
+                println!();
 
-    // News sources
-    println!("\n--- News Sources ---");
-    println!("  Alpaca aggregates news from multiple sources:");
-    println!("  - Benzinga");
-    println!("  - Bloomberg");
-    println!("  - Dow Jones");
-    println!("  - And more...");
-
-    // Use cases
-    println!("\n--- News Trading Use Cases ---");
-    println!("  1. Sentiment Analysis");
-    println!("     - Parse headlines for positive/negative sentiment");
-    println!("     - Trigger trades based on sentiment scores");
-    println!();
-    println!("  2. Event-Driven Trading");
-    println!("     - React to earnings announcements");
-    println!("     - Trade on merger/acquisition news");
-    println!("     - Respond to regulatory filings");
-    println!();
-    println!("  3. Risk Management");
-    println!("     - Monitor news for held positions");
-    println!("     - Set alerts for breaking news");
-    println!("     - Pause trading during high-impact events");
-
-    // Example workflow
-    println!("\n--- Example Workflow ---");
-    println!("  1. Connect to news stream");
-    println!("     let client = AlpacaWebSocketClient::new_news(credentials, env);");
-    println!();
-    println!("  2. Subscribe to symbols");
-    println!("     client.subscribe_news(&[\"AAPL\", \"TSLA\"]).await?;");
-    println!();
-    println!("  3. Process news events");
-    println!("     while let Some(news) = stream.next().await {{");
-    println!("         analyze_sentiment(&news.headline);");
-    println!("         if should_trade(&news) {{");
-    println!("             execute_trade(&news.symbols).await;");
-    println!("         }}");
-    println!("     }}");
-
-    // Filtering strategies
-    println!("\n--- Filtering Strategies ---");
-    println!("  Filter by symbol:");
-    println!("    news.symbols.contains(&\"AAPL\")");
-    println!();
-    println!("  Filter by source:");
-    println!("    news.source == \"Benzinga\"");
-    println!();
-    println!("  Filter by keywords:");
-    println!("    news.headline.contains(\"earnings\")");
-    println!();
-    println!("  Filter by time:");
-    println!("    news.created_at > market_open");
-
-    // Rate considerations
-    println!("\n--- Rate Considerations ---");
-    println!("  - News volume varies by market conditions");
-    println!("  - Major events can generate high volume");
-    println!("  - Consider buffering for processing");
-    println!("  - Implement deduplication if needed");
+                // Stop after 10 articles for demo
+                if article_count >= 10 {
+                    println!("Received 10 articles, stopping demo.");
+                    break;
+                }
+            }
+            NewsEvent::Lagged { missed } => println!("(lagged: {} articles dropped)", missed),
+            NewsEvent::Reconnecting { attempt, delay } => {
+                println!("(reconnecting, attempt {} in {:?})", attempt, delay)
+            }
+            NewsEvent::Reconnected => println!("(reconnected)"),
+            NewsEvent::Disconnected { reason } => println!("(disconnected: {})", reason),
+        }
+    }
 
     println!("\n=== Example Complete ===");
+    Ok(())
 }
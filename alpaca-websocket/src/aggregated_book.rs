@@ -0,0 +1,331 @@
+//! Depth-like liquidity metrics for equities from top-of-book data.
+//!
+//! Alpaca's equity feeds carry only the national best bid and offer, not
+//! full depth. [`AggregatedBook`] is an **approximation**: it keeps a rolling
+//! history of best bid/ask quotes and recent trades for one symbol and
+//! derives liquidity signals from them:
+//!
+//! - where the current spread ranks within recent spreads
+//!   ([`AggregatedBook::spread_percentile`]);
+//! - whether quoted top-of-book size is building or draining
+//!   ([`AggregatedBook::quoted_size_trend`]);
+//! - how recent volume is distributed across prices and between buyer- and
+//!   seller-initiated trades ([`AggregatedBook::volume_profile`],
+//!   [`AggregatedBook::trade_imbalance`]).
+//!
+//! None of these reflect resting orders beyond the top of book; they are
+//! heuristics for strategies that need a liquidity signal, not a substitute
+//! for a real order book.
+
+use crate::streams::MarketDataUpdate;
+use alpaca_base::types::{Quote, Trade};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, VecDeque};
+
+/// One best bid/ask observation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuoteSample {
+    /// Quote time.
+    pub timestamp: DateTime<Utc>,
+    /// Best bid price.
+    pub bid_price: f64,
+    /// Best bid size.
+    pub bid_size: u32,
+    /// Best ask price.
+    pub ask_price: f64,
+    /// Best ask size.
+    pub ask_size: u32,
+}
+
+impl QuoteSample {
+    /// Ask minus bid.
+    #[must_use]
+    pub fn spread(&self) -> f64 {
+        self.ask_price - self.bid_price
+    }
+
+    /// Midpoint of bid and ask.
+    #[must_use]
+    pub fn mid(&self) -> f64 {
+        (self.bid_price + self.ask_price) / 2.0
+    }
+
+    /// Combined top-of-book size.
+    #[must_use]
+    pub fn total_size(&self) -> u64 {
+        u64::from(self.bid_size) + u64::from(self.ask_size)
+    }
+}
+
+/// Aggressor side inferred for a trade by comparing it with the prevailing
+/// quote midpoint (quote rule).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggressor {
+    /// Traded above the midpoint.
+    Buyer,
+    /// Traded below the midpoint.
+    Seller,
+    /// Traded at the midpoint or before any quote was seen.
+    Unknown,
+}
+
+/// One trade observation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeSample {
+    /// Trade time.
+    pub timestamp: DateTime<Utc>,
+    /// Trade price.
+    pub price: f64,
+    /// Trade size.
+    pub size: u32,
+    /// Inferred aggressor side.
+    pub aggressor: Aggressor,
+}
+
+/// Rolling quote/trade history approximating depth for one symbol.
+///
+/// See the [module documentation](self) for what is and isn't measured.
+#[derive(Debug, Clone)]
+pub struct AggregatedBook {
+    symbol: String,
+    quote_capacity: usize,
+    trade_capacity: usize,
+    quotes: VecDeque<QuoteSample>,
+    trades: VecDeque<TradeSample>,
+}
+
+impl AggregatedBook {
+    /// Create a book keeping the last 500 quotes and 500 trades.
+    #[must_use]
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self::with_capacity(symbol, 500, 500)
+    }
+
+    /// Create a book keeping the last `quotes` quotes and `trades` trades.
+    #[must_use]
+    pub fn with_capacity(symbol: impl Into<String>, quotes: usize, trades: usize) -> Self {
+        Self {
+            symbol: symbol.into(),
+            quote_capacity: quotes.max(1),
+            trade_capacity: trades.max(1),
+            quotes: VecDeque::new(),
+            trades: VecDeque::new(),
+        }
+    }
+
+    /// Symbol tracked by this book.
+    #[must_use]
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Apply a stream update; updates for other symbols and bars are
+    /// ignored.
+    pub fn apply(&mut self, update: &MarketDataUpdate) {
+        match update {
+            MarketDataUpdate::Quote { symbol, quote } if *symbol == self.symbol => {
+                self.on_quote(quote);
+            }
+            MarketDataUpdate::Trade { symbol, trade } if *symbol == self.symbol => {
+                self.on_trade(trade);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record a quote. Crossed or empty quotes are ignored.
+    pub fn on_quote(&mut self, quote: &Quote) {
+        if quote.bid_price <= 0.0 || quote.ask_price < quote.bid_price {
+            return;
+        }
+        if self.quotes.len() == self.quote_capacity {
+            self.quotes.pop_front();
+        }
+        self.quotes.push_back(QuoteSample {
+            timestamp: quote.timestamp,
+            bid_price: quote.bid_price,
+            bid_size: quote.bid_size,
+            ask_price: quote.ask_price,
+            ask_size: quote.ask_size,
+        });
+    }
+
+    /// Record a trade, classifying its aggressor against the latest quote.
+    pub fn on_trade(&mut self, trade: &Trade) {
+        let aggressor = match self.quotes.back() {
+            Some(quote) if trade.price > quote.mid() => Aggressor::Buyer,
+            Some(quote) if trade.price < quote.mid() => Aggressor::Seller,
+            _ => Aggressor::Unknown,
+        };
+        if self.trades.len() == self.trade_capacity {
+            self.trades.pop_front();
+        }
+        self.trades.push_back(TradeSample {
+            timestamp: trade.timestamp,
+            price: trade.price,
+            size: trade.size,
+            aggressor,
+        });
+    }
+
+    /// Latest best bid/ask.
+    #[must_use]
+    pub fn top(&self) -> Option<&QuoteSample> {
+        self.quotes.back()
+    }
+
+    /// Quote history, oldest first.
+    pub fn quotes(&self) -> impl Iterator<Item = &QuoteSample> {
+        self.quotes.iter()
+    }
+
+    /// Trade history, oldest first.
+    pub fn trades(&self) -> impl Iterator<Item = &TradeSample> {
+        self.trades.iter()
+    }
+
+    /// Current spread in basis points of the midpoint.
+    #[must_use]
+    pub fn spread_bps(&self) -> Option<f64> {
+        self.top().map(|q| q.spread() / q.mid() * 10_000.0)
+    }
+
+    /// Fraction of recorded spreads (0.0–1.0) at or below the current
+    /// spread. High values mean the spread is unusually wide.
+    #[must_use]
+    pub fn spread_percentile(&self) -> Option<f64> {
+        let current = self.top()?.spread();
+        let at_or_below = self
+            .quotes
+            .iter()
+            .filter(|q| q.spread() <= current + f64::EPSILON)
+            .count();
+        Some(at_or_below as f64 / self.quotes.len() as f64)
+    }
+
+    /// Slope of combined top-of-book size per quote update, from a least
+    /// squares fit over the history. Positive means size is building.
+    #[must_use]
+    pub fn quoted_size_trend(&self) -> Option<f64> {
+        let n = self.quotes.len();
+        if n < 2 {
+            return None;
+        }
+        let n_f = n as f64;
+        let mean_x = (n_f - 1.0) / 2.0;
+        let mean_y = self
+            .quotes
+            .iter()
+            .map(|q| q.total_size() as f64)
+            .sum::<f64>()
+            / n_f;
+        let (mut cov, mut var) = (0.0, 0.0);
+        for (i, q) in self.quotes.iter().enumerate() {
+            let dx = i as f64 - mean_x;
+            cov += dx * (q.total_size() as f64 - mean_y);
+            var += dx * dx;
+        }
+        Some(cov / var)
+    }
+
+    /// Bid size as a fraction of combined top-of-book size for the latest
+    /// quote (0.5 is balanced).
+    #[must_use]
+    pub fn quote_imbalance(&self) -> Option<f64> {
+        let top = self.top()?;
+        let total = top.total_size();
+        (total > 0).then(|| top.bid_size as f64 / total as f64)
+    }
+
+    /// Traded volume per price level, rounded to `tick`, ascending by price.
+    #[must_use]
+    pub fn volume_profile(&self, tick: f64) -> Vec<(f64, u64)> {
+        let tick = if tick > 0.0 { tick } else { 0.01 };
+        let mut levels: BTreeMap<i64, u64> = BTreeMap::new();
+        for trade in &self.trades {
+            *levels
+                .entry((trade.price / tick).round() as i64)
+                .or_default() += u64::from(trade.size);
+        }
+        levels
+            .into_iter()
+            .map(|(level, volume)| (level as f64 * tick, volume))
+            .collect()
+    }
+
+    /// Buyer-initiated minus seller-initiated volume as a fraction of
+    /// classified volume, in `-1.0..=1.0`.
+    #[must_use]
+    pub fn trade_imbalance(&self) -> Option<f64> {
+        let (mut buys, mut sells) = (0u64, 0u64);
+        for trade in &self.trades {
+            match trade.aggressor {
+                Aggressor::Buyer => buys += u64::from(trade.size),
+                Aggressor::Seller => sells += u64::from(trade.size),
+                Aggressor::Unknown => {}
+            }
+        }
+        let total = buys + sells;
+        (total > 0).then(|| (buys as f64 - sells as f64) / total as f64)
+    }
+
+    /// Volume-weighted average price of the trade history.
+    #[must_use]
+    pub fn vwap(&self) -> Option<f64> {
+        let volume: u64 = self.trades.iter().map(|t| u64::from(t.size)).sum();
+        (volume > 0).then(|| {
+            self.trades
+                .iter()
+                .map(|t| t.price * f64::from(t.size))
+                .sum::<f64>()
+                / volume as f64
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::{quote_with, trade_with};
+
+    #[test]
+    fn test_spread_metrics_and_size_trend() {
+        let mut book = AggregatedBook::with_capacity("AAPL", 4, 10);
+        book.on_quote(&quote_with(100.00, 100, 100.02, 100));
+        book.on_quote(&quote_with(100.00, 200, 100.01, 200));
+        book.on_quote(&quote_with(100.00, 300, 100.03, 300));
+        book.on_quote(&quote_with(100.00, 400, 100.05, 400));
+        book.on_quote(&quote_with(100.00, 500, 100.04, 500));
+        book.on_quote(&quote_with(101.00, 1, 100.00, 1)); // crossed, ignored
+
+        assert_eq!(book.quotes().count(), 4);
+        assert_eq!(book.spread_percentile(), Some(0.75));
+        assert!((book.quoted_size_trend().unwrap() - 200.0).abs() < 1e-9);
+        assert!((book.spread_bps().unwrap() - 4.0).abs() < 0.01);
+        assert_eq!(book.quote_imbalance(), Some(0.5));
+    }
+
+    #[test]
+    fn test_trade_distribution() {
+        let mut book = AggregatedBook::new("AAPL");
+        book.on_trade(&trade_with(100.01, 50)); // no quote yet
+        book.on_quote(&quote_with(100.00, 100, 100.02, 100));
+        book.on_trade(&trade_with(100.02, 300));
+        book.on_trade(&trade_with(100.00, 100));
+        book.on_trade(&trade_with(100.02, 100));
+
+        assert_eq!(book.trades().next().unwrap().aggressor, Aggressor::Unknown);
+        assert_eq!(book.trade_imbalance(), Some(0.6));
+        let profile = book.volume_profile(0.01);
+        assert_eq!(profile.len(), 3);
+        assert_eq!(profile[2].1, 400);
+        assert!((profile[2].0 - 100.02).abs() < 1e-9);
+
+        let other = MarketDataUpdate::Trade {
+            symbol: "MSFT".to_string(),
+            trade: trade_with(400.0, 1),
+        };
+        book.apply(&other);
+        assert_eq!(book.trades().count(), 4);
+    }
+}
@@ -0,0 +1,290 @@
+//! What streams do when the consumer falls behind.
+//!
+//! Every stream delivers events over a bounded channel of
+//! [`WebSocketConfig::message_buffer_size`](crate::WebSocketConfig::message_buffer_size)
+//! entries. [`BackpressurePolicy`] decides what happens to data updates
+//! arriving while it is full, e.g. during the burst at the market open:
+//!
+//! | Policy | Full channel | Memory |
+//! |--------|--------------|--------|
+//! | [`DropNewest`](BackpressurePolicy::DropNewest) | the arriving update is dropped | channel |
+//! | [`DropOldest`](BackpressurePolicy::DropOldest) | updates queue behind the channel; the oldest queued is dropped | 2 × channel |
+//! | [`Block`](BackpressurePolicy::Block) | the socket is not read until there is room | channel |
+//! | [`CoalesceQuotes`](BackpressurePolicy::CoalesceQuotes) | as `DropOldest`, but a quote replaces the queued quote of its symbol | 2 × channel |
+//!
+//! Dropped and coalesced updates are reported to the consumer as one
+//! `Lagged { missed }` event once there is room, and, with the `metrics`
+//! feature, counted in `alpaca_ws_dropped_messages_total`.
+//! Lifecycle events (reconnects, disconnects, subscription changes) are
+//! never dropped. Under `Block` nothing is dropped, but a consumer that
+//! stays behind long enough may be disconnected by the server.
+
+use crate::client::StreamEvents;
+use std::collections::VecDeque;
+use std::future::Future;
+use tokio::sync::mpsc::{
+    self, OwnedPermit,
+    error::{SendError, TrySendError},
+};
+
+/// What a stream does with data updates while its channel is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackpressurePolicy {
+    /// Drop the arriving update.
+    #[default]
+    DropNewest,
+    /// Queue the update and drop the oldest queued one beyond the buffer
+    /// size.
+    DropOldest,
+    /// Stop reading the socket until the consumer catches up.
+    Block,
+    /// As [`Self::DropOldest`], but keep only the latest queued quote per
+    /// symbol.
+    CoalesceQuotes,
+}
+
+impl BackpressurePolicy {
+    /// Label of the policy in metrics.
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::DropNewest => "drop_newest",
+            Self::DropOldest => "drop_oldest",
+            Self::Block => "block",
+            Self::CoalesceQuotes => "coalesce_quotes",
+        }
+    }
+}
+
+/// The sending side of a stream channel, applying a
+/// [`BackpressurePolicy`]. `Err(())` and `false` mean the consumer dropped
+/// the stream.
+pub(crate) struct Outbox<E> {
+    sender: mpsc::Sender<E>,
+    policy: BackpressurePolicy,
+    capacity: usize,
+    pending: VecDeque<E>,
+    missed: u64,
+}
+
+impl<E: StreamEvents> Outbox<E> {
+    pub(crate) fn new(
+        sender: mpsc::Sender<E>,
+        policy: BackpressurePolicy,
+        capacity: usize,
+    ) -> Self {
+        Self {
+            sender,
+            policy,
+            capacity: capacity.max(1),
+            pending: VecDeque::new(),
+            missed: 0,
+        }
+    }
+
+    /// Forward a data update. Only waits under
+    /// [`BackpressurePolicy::Block`].
+    pub(crate) async fn push(&mut self, update: E) -> std::result::Result<(), ()> {
+        if self.policy == BackpressurePolicy::Block {
+            return self.sender.send(update).await.map_err(|_| ());
+        }
+        if self.pending.is_empty() {
+            if self.missed > 0 {
+                match self.sender.try_send(E::lagged(self.missed)) {
+                    Ok(()) => self.missed = 0,
+                    Err(TrySendError::Full(_)) => {}
+                    Err(TrySendError::Closed(_)) => return Err(()),
+                }
+            }
+            if self.missed == 0 {
+                match self.sender.try_send(update) {
+                    Ok(()) => return Ok(()),
+                    Err(TrySendError::Full(update)) => {
+                        self.overflow(update);
+                        return Ok(());
+                    }
+                    Err(TrySendError::Closed(_)) => return Err(()),
+                }
+            }
+        }
+        self.overflow(update);
+        Ok(())
+    }
+
+    /// Whether a lag report or queued updates wait for channel capacity.
+    pub(crate) fn has_pending(&self) -> bool {
+        self.missed > 0 || !self.pending.is_empty()
+    }
+
+    /// Capacity for the next pending item; see [`Self::flush_one`].
+    pub(crate) fn reserve(
+        &self,
+    ) -> impl Future<Output = std::result::Result<OwnedPermit<E>, SendError<()>>> + 'static {
+        self.sender.clone().reserve_owned()
+    }
+
+    /// Send the lag report, or else the oldest queued update.
+    pub(crate) fn flush_one(&mut self, permit: OwnedPermit<E>) {
+        if self.missed > 0 {
+            permit.send(E::lagged(self.missed));
+            self.missed = 0;
+        } else if let Some(update) = self.pending.pop_front() {
+            permit.send(update);
+        }
+    }
+
+    /// Forward a lifecycle event after everything pending, waiting for
+    /// capacity so it is never dropped.
+    pub(crate) async fn send_lifecycle(&mut self, event: E) -> bool {
+        while self.has_pending() {
+            let Ok(permit) = self.reserve().await else {
+                return false;
+            };
+            self.flush_one(permit);
+        }
+        self.sender.send(event).await.is_ok()
+    }
+
+    fn overflow(&mut self, update: E) {
+        match self.policy {
+            BackpressurePolicy::DropNewest | BackpressurePolicy::Block => self.dropped(),
+            BackpressurePolicy::DropOldest => self.enqueue(update),
+            BackpressurePolicy::CoalesceQuotes => {
+                let queued = update.coalesce_key().and_then(|key| {
+                    self.pending
+                        .iter()
+                        .position(|pending| pending.coalesce_key() == Some(key))
+                });
+                match queued {
+                    Some(index) => {
+                        self.pending[index] = update;
+                        self.dropped();
+                    }
+                    None => self.enqueue(update),
+                }
+            }
+        }
+    }
+
+    fn enqueue(&mut self, update: E) {
+        self.pending.push_back(update);
+        if self.pending.len() > self.capacity {
+            self.pending.pop_front();
+            self.dropped();
+        }
+    }
+
+    fn dropped(&mut self) {
+        self.missed += 1;
+        #[cfg(feature = "metrics")]
+        alpaca_base::metrics::record_ws_dropped(self.policy.label());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streams::{MarketDataEvent, MarketDataUpdate};
+    use alpaca_base::test_utils::fixtures::{quote_with, trade_with};
+
+    fn quote(symbol: &str, bid_price: f64) -> MarketDataEvent {
+        MarketDataEvent::Update(MarketDataUpdate::Quote {
+            symbol: symbol.to_string(),
+            quote: quote_with(bid_price, 1, bid_price + 0.1, 1),
+        })
+    }
+
+    fn trade(symbol: &str, price: f64) -> MarketDataEvent {
+        MarketDataEvent::Update(MarketDataUpdate::Trade {
+            symbol: symbol.to_string(),
+            trade: trade_with(price, 1),
+        })
+    }
+
+    fn describe(event: MarketDataEvent) -> String {
+        match event {
+            MarketDataEvent::Update(MarketDataUpdate::Quote { symbol, quote }) => {
+                format!("q {symbol} {}", quote.bid_price)
+            }
+            MarketDataEvent::Update(MarketDataUpdate::Trade { symbol, trade }) => {
+                format!("t {symbol} {}", trade.price)
+            }
+            MarketDataEvent::Lagged { missed } => format!("lagged {missed}"),
+            other => format!("{other:?}"),
+        }
+    }
+
+    /// Push `events` into a full one-slot channel, then drain it.
+    async fn drain(policy: BackpressurePolicy, events: Vec<MarketDataEvent>) -> Vec<String> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let mut outbox = Outbox::new(sender, policy, 2);
+        for event in events {
+            outbox.push(event).await.unwrap();
+        }
+        let drain = async {
+            let mut seen = Vec::new();
+            while let Some(event) = receiver.recv().await {
+                seen.push(describe(event));
+            }
+            seen
+        };
+        let flush = async move {
+            assert!(outbox.send_lifecycle(MarketDataEvent::Reconnected).await);
+        };
+        let (seen, ()) = tokio::join!(drain, flush);
+        seen
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_policies() {
+        let burst = || {
+            vec![
+                trade("AAPL", 1.0),
+                quote("AAPL", 2.0),
+                quote("MSFT", 3.0),
+                quote("AAPL", 4.0),
+                trade("MSFT", 5.0),
+            ]
+        };
+        assert_eq!(
+            drain(BackpressurePolicy::DropNewest, burst()).await,
+            ["t AAPL 1", "lagged 4", "Reconnected"]
+        );
+        assert_eq!(
+            drain(BackpressurePolicy::DropOldest, burst()).await,
+            [
+                "t AAPL 1",
+                "lagged 2",
+                "q AAPL 4",
+                "t MSFT 5",
+                "Reconnected"
+            ]
+        );
+        assert_eq!(
+            drain(BackpressurePolicy::CoalesceQuotes, burst()).await,
+            [
+                "t AAPL 1",
+                "lagged 2",
+                "q MSFT 3",
+                "t MSFT 5",
+                "Reconnected"
+            ]
+        );
+
+        // Block waits for the consumer instead of dropping.
+        let (sender, mut receiver) = mpsc::channel(1);
+        let mut outbox = Outbox::new(sender, BackpressurePolicy::Block, 1);
+        let consumer = tokio::spawn(async move {
+            let mut seen = Vec::new();
+            while let Some(event) = receiver.recv().await {
+                seen.push(describe(event));
+            }
+            seen
+        });
+        for event in burst() {
+            outbox.push(event).await.unwrap();
+        }
+        drop(outbox);
+        assert_eq!(consumer.await.unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,318 @@
+//! Minute bar stream with gap backfill.
+//!
+//! Minute bars that fall into a network gap (a reconnect, a stalled
+//! socket) are never re-sent by the WebSocket feed. [`BarStreamWithBackfill`]
+//! wraps a [`MarketDataStream`], remembers the last bar timestamp per
+//! symbol and, when the next bar is more than a minute later, fetches the
+//! missing minutes over REST before delivering the live bar. Consumers see
+//! one continuous, ordered bar stream per symbol.
+//!
+//! Minutes without trades produce no bar, so a quiet symbol also triggers a
+//! fetch; it simply returns nothing. While a fetch is in flight the live
+//! stream is not polled, so updates queue in its bounded channel.
+//!
+//! With the `http` feature, [`BarStreamWithBackfill::with_http`] fetches the
+//! missing range via `get_stock_bars`.
+
+use crate::streams::{MarketDataEvent, MarketDataStream, MarketDataUpdate};
+use alpaca_base::Result;
+use alpaca_base::types::Bar;
+use chrono::{DateTime, Duration, Utc};
+use futures_util::stream::Stream;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type BackfillFuture = Pin<Box<dyn Future<Output = Result<Vec<Bar>>> + Send>>;
+type Fetcher = Box<dyn FnMut(String, DateTime<Utc>, DateTime<Utc>) -> BackfillFuture + Send>;
+
+/// A backfill request in flight, with the live bar that revealed the gap.
+struct PendingGap {
+    symbol: String,
+    after: DateTime<Utc>,
+    live: Bar,
+    fetch: BackfillFuture,
+}
+
+/// A minute bar stream that fills gaps from the REST API.
+///
+/// Yields [`MarketDataEvent`]s so trades, quotes and lifecycle events pass
+/// through unchanged. Bars for each symbol are delivered in timestamp
+/// order; a bar at or before the last one delivered for its symbol (a
+/// replay after reconnecting) is dropped.
+pub struct BarStreamWithBackfill {
+    live: MarketDataStream,
+    fetch: Fetcher,
+    last_bar: HashMap<String, DateTime<Utc>>,
+    ready: VecDeque<MarketDataEvent>,
+    pending: Option<PendingGap>,
+    backfilled: u64,
+    unfilled_gaps: u64,
+}
+
+impl BarStreamWithBackfill {
+    /// Wrap `live`, fetching missing bars with `fetch`.
+    ///
+    /// `fetch(symbol, start, end)` must return the symbol's minute bars
+    /// from `start` to `end`, both inclusive. Bars outside the gap are
+    /// ignored, so returning a wider range is harmless.
+    pub fn new<F, Fut>(live: MarketDataStream, mut fetch: F) -> Self
+    where
+        F: FnMut(String, DateTime<Utc>, DateTime<Utc>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Vec<Bar>>> + Send + 'static,
+    {
+        Self {
+            live,
+            fetch: Box::new(move |symbol, start, end| Box::pin(fetch(symbol, start, end))),
+            last_bar: HashMap::new(),
+            ready: VecDeque::new(),
+            pending: None,
+            backfilled: 0,
+            unfilled_gaps: 0,
+        }
+    }
+
+    /// Timestamp of the last bar delivered for `symbol`.
+    #[must_use]
+    pub fn last_bar_time(&self, symbol: &str) -> Option<DateTime<Utc>> {
+        self.last_bar.get(symbol).copied()
+    }
+
+    /// Number of bars delivered from backfill.
+    #[must_use]
+    pub fn backfilled_bars(&self) -> u64 {
+        self.backfilled
+    }
+
+    /// Number of gaps left unfilled because the fetch failed.
+    #[must_use]
+    pub fn unfilled_gaps(&self) -> u64 {
+        self.unfilled_gaps
+    }
+
+    /// Route a live bar: drop replays, start a backfill on a gap, or
+    /// deliver it.
+    fn on_live_bar(&mut self, symbol: String, bar: Bar) -> Option<MarketDataEvent> {
+        match self.last_bar.get(&symbol).copied() {
+            Some(last) if bar.timestamp <= last => None,
+            Some(last) if bar.timestamp - last > Duration::minutes(1) => {
+                let start = last + Duration::minutes(1);
+                let end = bar.timestamp - Duration::minutes(1);
+                tracing::debug!(%symbol, %start, %end, "Backfilling bar gap");
+                let fetch = (self.fetch)(symbol.clone(), start, end);
+                self.pending = Some(PendingGap {
+                    symbol,
+                    after: last,
+                    live: bar,
+                    fetch,
+                });
+                None
+            }
+            _ => {
+                self.last_bar.insert(symbol.clone(), bar.timestamp);
+                Some(MarketDataEvent::Update(MarketDataUpdate::Bar {
+                    symbol,
+                    bar,
+                }))
+            }
+        }
+    }
+
+    /// Queue the backfilled bars of a finished gap, then its live bar.
+    fn complete_gap(&mut self, gap: PendingGap, fetched: Result<Vec<Bar>>) {
+        let PendingGap {
+            symbol,
+            after,
+            live,
+            ..
+        } = gap;
+        match fetched {
+            Ok(mut bars) => {
+                bars.retain(|bar| bar.timestamp > after && bar.timestamp < live.timestamp);
+                bars.sort_by_key(|bar| bar.timestamp);
+                bars.dedup_by_key(|bar| bar.timestamp);
+                self.backfilled += bars.len() as u64;
+                for bar in bars {
+                    self.ready
+                        .push_back(MarketDataEvent::Update(MarketDataUpdate::Bar {
+                            symbol: symbol.clone(),
+                            bar,
+                        }));
+                }
+            }
+            Err(e) => {
+                tracing::warn!(%symbol, error = %e, "Bar backfill failed, gap left unfilled");
+                self.unfilled_gaps += 1;
+            }
+        }
+        self.last_bar.insert(symbol.clone(), live.timestamp);
+        self.ready
+            .push_back(MarketDataEvent::Update(MarketDataUpdate::Bar {
+                symbol,
+                bar: live,
+            }));
+    }
+}
+
+#[cfg(feature = "http")]
+impl BarStreamWithBackfill {
+    /// Wrap `live`, fetching missing stock bars via `get_stock_bars`
+    /// from `feed`, following REST pagination.
+    pub fn with_http(
+        live: MarketDataStream,
+        http: alpaca_http::AlpacaHttpClient,
+        feed: Option<alpaca_base::types::DataFeed>,
+    ) -> Self {
+        Self::new(live, move |symbol, start, end| {
+            let http = http.clone();
+            async move {
+                let mut params =
+                    alpaca_base::types::MultiBarsParams::new(&symbol).timeframe("1Min");
+                params.start = Some(start.to_rfc3339());
+                params.end = Some(end.to_rfc3339());
+                params.feed = feed;
+
+                let mut bars = Vec::new();
+                loop {
+                    let mut page = http.get_stock_bars(&params).await?;
+                    bars.extend(page.bars.remove(&symbol).unwrap_or_default());
+                    match page.next_page_token {
+                        Some(token) => params.page_token = Some(token),
+                        None => break,
+                    }
+                }
+                Ok(bars)
+            }
+        })
+    }
+}
+
+impl Stream for BarStreamWithBackfill {
+    type Item = MarketDataEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(event) = this.ready.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            if let Some(gap) = this.pending.as_mut() {
+                let fetched = match gap.fetch.as_mut().poll(cx) {
+                    Poll::Ready(fetched) => fetched,
+                    Poll::Pending => return Poll::Pending,
+                };
+                if let Some(gap) = this.pending.take() {
+                    this.complete_gap(gap, fetched);
+                }
+                continue;
+            }
+            match Pin::new(&mut this.live).poll_next(cx) {
+                Poll::Ready(Some(MarketDataEvent::Update(MarketDataUpdate::Bar {
+                    symbol,
+                    bar,
+                }))) => {
+                    if let Some(event) = this.on_live_bar(symbol, bar) {
+                        return Poll::Ready(Some(event));
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::AlpacaError;
+    use chrono::TimeZone;
+    use futures_util::StreamExt;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+
+    fn bar(minute: u32) -> Bar {
+        Bar {
+            timestamp: Utc.with_ymd_and_hms(2024, 5, 1, 14, minute, 0).unwrap(),
+            open: 1.0,
+            high: 1.0,
+            low: 1.0,
+            close: 1.0,
+            volume: 100,
+            trade_count: None,
+            vwap: None,
+        }
+    }
+
+    fn update(minute: u32) -> MarketDataEvent {
+        MarketDataEvent::Update(MarketDataUpdate::Bar {
+            symbol: "AAPL".to_string(),
+            bar: bar(minute),
+        })
+    }
+
+    async fn minutes(stream: &mut BarStreamWithBackfill) -> Vec<u32> {
+        use chrono::Timelike;
+        let mut delivered = Vec::new();
+        while let Some(event) = stream.next().await {
+            match event {
+                MarketDataEvent::Update(MarketDataUpdate::Bar { bar, .. }) => {
+                    delivered.push(bar.timestamp.minute())
+                }
+                MarketDataEvent::Reconnected => delivered.push(0),
+                other => panic!("unexpected event {other:?}"),
+            }
+        }
+        delivered
+    }
+
+    #[tokio::test]
+    async fn test_gap_is_backfilled_in_order() {
+        let (tx, rx) = mpsc::channel(8);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        let mut stream =
+            BarStreamWithBackfill::new(MarketDataStream::new(rx), move |symbol, start, end| {
+                seen.lock().unwrap().push((symbol, start, end));
+                // Out of order, duplicated, and one bar outside the gap.
+                async { Ok(vec![bar(13), bar(12), bar(12), bar(10), bar(14)]) }
+            });
+
+        for event in [
+            update(10),
+            update(11),
+            MarketDataEvent::Reconnected,
+            update(11),
+            update(14),
+            update(15),
+        ] {
+            tx.send(event).await.unwrap();
+        }
+        drop(tx);
+
+        assert_eq!(minutes(&mut stream).await, [10, 11, 0, 12, 13, 14, 15]);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [("AAPL".to_string(), bar(12).timestamp, bar(13).timestamp)]
+        );
+        assert_eq!(stream.backfilled_bars(), 2);
+        assert_eq!(stream.last_bar_time("AAPL"), Some(bar(15).timestamp));
+    }
+
+    #[tokio::test]
+    async fn test_failed_backfill_delivers_live_bar() {
+        let (tx, rx) = mpsc::channel(8);
+        let mut stream = BarStreamWithBackfill::new(MarketDataStream::new(rx), |_, _, _| async {
+            Err(AlpacaError::Network("offline".to_string()))
+        });
+
+        for event in [update(10), update(20)] {
+            tx.send(event).await.unwrap();
+        }
+        drop(tx);
+
+        assert_eq!(minutes(&mut stream).await, [10, 20]);
+        assert_eq!(stream.unfilled_gaps(), 1);
+        assert_eq!(stream.backfilled_bars(), 0);
+    }
+}
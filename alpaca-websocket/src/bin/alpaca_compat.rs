@@ -0,0 +1,59 @@
+//! Run the compatibility battery against the paper account configured in
+//! `ALPACA_API_KEY` / `ALPACA_API_SECRET` and print the report.
+//!
+//! Usage: `cargo run -p alpaca-websocket --features integration-tests --bin alpaca-compat -- [--json] [--symbol SYM] [--no-orders] [--no-streams]`
+//!
+//! Exits with status 1 when any check fails.
+
+use alpaca_websocket::compat::{CompatibilityConfig, CompatibilityRunner};
+
+#[tokio::main]
+async fn main() {
+    let mut config = CompatibilityConfig::new();
+    let mut json = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--no-orders" => config = config.orders_enabled(false),
+            "--no-streams" => config = config.streams_enabled(false),
+            "--symbol" => match args.next() {
+                Some(symbol) => config = config.symbol(&symbol),
+                None => {
+                    eprintln!("--symbol requires a value");
+                    std::process::exit(2);
+                }
+            },
+            other => {
+                eprintln!("unknown argument: {other}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let runner = match CompatibilityRunner::from_env(config) {
+        Ok(runner) => runner,
+        Err(e) => {
+            eprintln!("failed to initialize: {e}");
+            std::process::exit(2);
+        }
+    };
+
+    let report = runner.run().await;
+    if json {
+        match report.to_json() {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                eprintln!("failed to serialize report: {e}");
+                std::process::exit(2);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+
+    if !report.is_compatible() {
+        std::process::exit(1);
+    }
+}
@@ -2,24 +2,39 @@
 
 #![allow(missing_docs)]
 
-use crate::{config::WebSocketConfig, messages::*, streams::*};
-use alpaca_base::types::Quote;
-use alpaca_base::{AlpacaError, Result, auth::Credentials, types::Environment};
+use crate::{
+    backpressure::Outbox,
+    config::{StreamType, WebSocketConfig},
+    error::WebSocketError,
+    lease::{LeaseGuard, lease_key},
+    messages::*,
+    streams::*,
+    subscription::{SubscriptionAck, SubscriptionControl, SubscriptionError},
+    transport::Transport,
+};
+use alpaca_base::types::EnhancedNewsArticle;
+use alpaca_base::{
+    AlpacaError, Backoff, ClientConfig, Result, RetryPolicy, WsDirection,
+    auth::{Credentials, CredentialsHandle},
+    types::Environment,
+};
 use futures_util::{
     sink::SinkExt,
     stream::{SplitSink, SplitStream, StreamExt},
 };
 use serde_json;
 use std::future::Future;
-use std::sync::Once;
+use std::sync::{Arc, Once};
 use std::time::Duration;
 use tokio::{
     net::TcpStream,
     sync::mpsc,
-    sync::mpsc::error::TrySendError,
+    sync::watch,
     time::{interval, sleep, timeout},
 };
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderValue, header::CONTENT_TYPE};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, tungstenite::Message};
 use tracing::{debug, error, info, warn};
 
 static CRYPTO_PROVIDER_INIT: Once = Once::new();
@@ -33,15 +48,18 @@ fn init_crypto_provider() {
 }
 
 type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
-type WsSink = SplitSink<WsStream, Message>;
+pub(crate) type WsSink = SplitSink<WsStream, Message>;
 type WsReceiver = SplitStream<WsStream>;
 
 /// WebSocket client for Alpaca API
 #[derive(Debug)]
 pub struct AlpacaWebSocketClient {
-    credentials: Credentials,
+    credentials: CredentialsHandle,
     environment: Environment,
     url: String,
+    feed: Option<DataFeed>,
+    client_config: Option<ClientConfig>,
+    transport: Arc<Transport>,
 }
 
 /// Data feed type for market data
@@ -61,19 +79,48 @@ pub enum DataFeed {
     Crypto,
 }
 
+impl DataFeed {
+    /// The stream URL for this feed.
+    #[must_use]
+    pub fn url(&self) -> &'static str {
+        match self {
+            Self::Iex => "wss://stream.data.alpaca.markets/v2/iex",
+            Self::Sip => "wss://stream.data.alpaca.markets/v2/sip",
+            Self::DelayedSip => "wss://stream.data.alpaca.markets/v2/delayed_sip",
+            Self::Boats => "wss://stream.data.alpaca.markets/v1beta1/boats",
+            Self::Overnight => "wss://stream.data.alpaca.markets/v1beta1/overnight",
+            Self::Crypto => "wss://stream.data.alpaca.markets/v1beta3/crypto/us",
+        }
+    }
+
+    /// The equivalent REST feed, if any.
+    #[must_use]
+    pub fn market_feed(&self) -> Option<alpaca_base::types::DataFeed> {
+        match self {
+            Self::Iex => Some(alpaca_base::types::DataFeed::Iex),
+            Self::Sip => Some(alpaca_base::types::DataFeed::Sip),
+            Self::DelayedSip => Some(alpaca_base::types::DataFeed::DelayedSip),
+            Self::Boats => Some(alpaca_base::types::DataFeed::Boats),
+            Self::Overnight => Some(alpaca_base::types::DataFeed::Overnight),
+            Self::Crypto => None,
+        }
+    }
+
+    /// Whether the feed lags the market (15 minutes for `DelayedSip`).
+    #[must_use]
+    pub fn is_delayed(&self) -> bool {
+        matches!(self, Self::DelayedSip)
+    }
+}
+
 impl AlpacaWebSocketClient {
     /// Create a new WebSocket client for stocks
-    pub fn new(credentials: Credentials, environment: Environment) -> Self {
-        let url = match environment {
-            Environment::Paper => "wss://stream.data.alpaca.markets/v2/iex",
-            Environment::Live => "wss://stream.data.alpaca.markets/v2/sip",
+    pub fn new(credentials: impl Into<CredentialsHandle>, environment: Environment) -> Self {
+        let feed = match environment {
+            Environment::Paper => DataFeed::Iex,
+            Environment::Live => DataFeed::Sip,
         };
-
-        Self {
-            credentials,
-            environment,
-            url: url.to_string(),
-        }
+        Self::with_feed(credentials, environment, feed)
     }
 
     /// Create a new client from environment variables
@@ -83,25 +130,23 @@ impl AlpacaWebSocketClient {
     }
 
     /// Create a WebSocket client for a specific data feed
-    pub fn with_feed(credentials: Credentials, environment: Environment, feed: DataFeed) -> Self {
-        let url = match feed {
-            DataFeed::Iex => "wss://stream.data.alpaca.markets/v2/iex",
-            DataFeed::Sip => "wss://stream.data.alpaca.markets/v2/sip",
-            DataFeed::DelayedSip => "wss://stream.data.alpaca.markets/v2/delayed_sip",
-            DataFeed::Boats => "wss://stream.data.alpaca.markets/v1beta1/boats",
-            DataFeed::Overnight => "wss://stream.data.alpaca.markets/v1beta1/overnight",
-            DataFeed::Crypto => "wss://stream.data.alpaca.markets/v1beta3/crypto/us",
-        };
-
+    pub fn with_feed(
+        credentials: impl Into<CredentialsHandle>,
+        environment: Environment,
+        feed: DataFeed,
+    ) -> Self {
         Self {
-            credentials,
+            credentials: credentials.into(),
             environment,
-            url: url.to_string(),
+            url: feed.url().to_string(),
+            feed: Some(feed),
+            client_config: None,
+            transport: Arc::default(),
         }
     }
 
     /// Create a crypto WebSocket client
-    pub fn crypto(credentials: Credentials, environment: Environment) -> Self {
+    pub fn crypto(credentials: impl Into<CredentialsHandle>, environment: Environment) -> Self {
         Self::with_feed(credentials, environment, DataFeed::Crypto)
     }
 
@@ -111,13 +156,28 @@ impl AlpacaWebSocketClient {
         Ok(Self::crypto(credentials, environment))
     }
 
+    /// Create a real-time news WebSocket client
+    pub fn news(credentials: impl Into<CredentialsHandle>, environment: Environment) -> Self {
+        Self {
+            credentials: credentials.into(),
+            environment,
+            url: StreamType::News.url(false).to_string(),
+            feed: None,
+            client_config: None,
+            transport: Arc::default(),
+        }
+    }
+
     /// Create a trading WebSocket client
-    pub fn trading(credentials: Credentials, environment: Environment) -> Self {
+    pub fn trading(credentials: impl Into<CredentialsHandle>, environment: Environment) -> Self {
         let url = environment.websocket_url();
         Self {
-            credentials,
+            credentials: credentials.into(),
             environment,
             url: url.to_string(),
+            feed: None,
+            client_config: None,
+            transport: Arc::default(),
         }
     }
 
@@ -126,17 +186,49 @@ impl AlpacaWebSocketClient {
     /// Useful for proxies or test servers; prefer [`Self::new`],
     /// [`Self::with_feed`], or [`Self::trading`] for the standard endpoints.
     pub fn with_url(
-        credentials: Credentials,
+        credentials: impl Into<CredentialsHandle>,
         environment: Environment,
         url: impl Into<String>,
     ) -> Self {
         Self {
-            credentials,
+            credentials: credentials.into(),
             environment,
             url: url.into(),
+            feed: None,
+            client_config: None,
+            transport: Arc::default(),
         }
     }
 
+    /// Apply custom endpoints, proxy or TLS roots.
+    ///
+    /// Market data and news clients keep their feed path on
+    /// [`ClientConfig::data_stream_url`], trading clients switch to
+    /// [`ClientConfig::trading_stream_url`], and clients built with
+    /// [`Self::with_url`] keep their URL. Streams are tunnelled through the
+    /// proxy with HTTP `CONNECT`. Fails with [`AlpacaError::Config`] if a
+    /// root certificate is invalid.
+    pub fn with_client_config(mut self, config: ClientConfig) -> Result<Self> {
+        self.url = match self.feed {
+            Some(feed) => config.data_stream_for(feed.url()),
+            None if self.url == self.environment.websocket_url() => {
+                config.trading_stream_url().to_string()
+            }
+            None if self.url == StreamType::News.url(false) => config.data_stream_for(&self.url),
+            None => self.url,
+        };
+        self.transport = Arc::new(Transport::from_config(&config)?);
+        self.environment = config.environment.clone();
+        self.client_config = Some(config);
+        Ok(self)
+    }
+
+    /// The credentials handle. Rotating it makes every open stream
+    /// reconnect and re-authenticate with the new credentials.
+    pub fn credentials(&self) -> &CredentialsHandle {
+        &self.credentials
+    }
+
     /// Connect to the WebSocket and return a stream of messages
     pub async fn connect(&self) -> Result<AlpacaStream> {
         // Initialize crypto provider for TLS
@@ -144,14 +236,14 @@ impl AlpacaWebSocketClient {
 
         let (sender, receiver) = mpsc::unbounded_channel();
         info!("Connecting to WebSocket: {}", self.url);
-        let (ws_stream, _) = connect_async(&self.url).await?;
+        let ws_stream = self.transport.connect(&self.url).await?;
         let (mut sink, mut stream) = ws_stream.split();
 
         // Authenticate
         self.authenticate(&mut sink).await?;
 
         // Spawn message handler
-        let credentials = self.credentials.clone();
+        let credentials = self.credentials.current();
         tokio::spawn(async move {
             Self::handle_messages(&mut stream, sender, credentials).await;
         });
@@ -161,32 +253,31 @@ impl AlpacaWebSocketClient {
 
     /// Connect with automatic reconnection
     pub async fn connect_with_reconnect(&self, max_retries: u32) -> Result<AlpacaStream> {
+        let policy = RetryPolicy::new()
+            .max_attempts(max_retries)
+            .backoff(Backoff::new(
+                Duration::from_secs(1),
+                Duration::from_secs(60),
+            ))
+            .retry_if(|_| true);
         let mut attempts = 0;
-        let mut delay = Duration::from_secs(1);
-
-        loop {
-            match self.connect().await {
-                Ok(stream) => {
-                    info!("Successfully connected to WebSocket");
-                    return Ok(stream);
-                }
-                Err(e) => {
-                    attempts += 1;
-                    if attempts >= max_retries {
-                        error!("Failed to connect after {} attempts", attempts);
-                        return Err(AlpacaError::WebSocket(format!(
-                            "Connection failed after {} attempts: {}",
-                            attempts, e
-                        )));
-                    }
-
-                    warn!(
-                        "Connection attempt {} failed: {}. Retrying in {:?}",
-                        attempts, e, delay
-                    );
-                    sleep(delay).await;
-                    delay = std::cmp::min(delay * 2, Duration::from_secs(60));
-                }
+        match policy
+            .retry(|attempt| {
+                attempts = attempt;
+                self.connect()
+            })
+            .await
+        {
+            Ok(stream) => {
+                info!("Successfully connected to WebSocket");
+                Ok(stream)
+            }
+            Err(e) => {
+                error!("Failed to connect after {} attempts", attempts);
+                Err(AlpacaError::WebSocket(format!(
+                    "Connection failed after {} attempts: {}",
+                    attempts, e
+                )))
             }
         }
     }
@@ -215,16 +306,25 @@ impl AlpacaWebSocketClient {
     /// - After a successful start, if the connection closes or errors the
     ///   task reconnects with capped exponential backoff
     ///   (`reconnect_base_delay_ms * 2^(attempt - 1)`, capped at
-    ///   `reconnect_max_delay_ms`) and re-issues the active subscription
+    ///   `reconnect_max_delay_ms`, with `reconnect_jitter` applied) and re-issues the active subscription
     ///   set. Progress is reported via [`MarketDataEvent::Reconnecting`]
-    ///   and [`MarketDataEvent::Reconnected`].
+    ///   and [`MarketDataEvent::Reconnected`], followed by
+    ///   [`MarketDataEvent::SubscriptionsChanged`] with the replayed set.
+    /// - [`MarketDataStream::current_subscriptions`] returns the set last
+    ///   confirmed by the server; every confirmation received on the socket
+    ///   also emits [`MarketDataEvent::SubscriptionsChanged`].
+    /// - [`MarketDataStream::subscribe`] and
+    ///   [`MarketDataStream::unsubscribe`] change the set on the open
+    ///   socket; reconnects replay the set as changed. A subscription above
+    ///   `symbol_limit` is rejected before connecting.
     /// - When reconnection is disabled or `reconnect_max_attempts`
     ///   consecutive attempts fail, a final
     ///   [`MarketDataEvent::Disconnected`] is emitted and the stream ends.
     /// - Events are delivered over a bounded channel of
     ///   `message_buffer_size` entries. If the consumer falls behind, data
-    ///   updates are dropped and reported via [`MarketDataEvent::Lagged`];
-    ///   lifecycle events are never dropped.
+    ///   updates are handled by the config's [`BackpressurePolicy`](crate::BackpressurePolicy):
+    ///   dropped or coalesced updates are reported via
+    ///   [`MarketDataEvent::Lagged`]; lifecycle events are never dropped.
     /// - Dropping the stream stops the background task and closes the
     ///   connection.
     pub async fn subscribe_market_data_with_config(
@@ -236,37 +336,149 @@ impl AlpacaWebSocketClient {
         init_crypto_provider();
 
         let url = self.url.clone();
+        let transport = Arc::clone(&self.transport);
         let credentials = self.credentials.clone();
-        let stream = open_market_data_stream(&url, &credentials, &subscription, &config).await?;
+        if let Some(limit) = config.symbol_limit {
+            let requested = Subscriptions::from_request(&subscription).symbols().len();
+            if requested > limit {
+                return Err(SubscriptionError::SymbolLimit { requested, limit }.into());
+            }
+        }
+        let rotation = credentials.subscribe();
+        let lease = self.acquire_lease(&config)?;
+        let (sink, stream, confirmed) = open_market_data_stream(
+            &transport,
+            &url,
+            &credentials.current(),
+            &subscription,
+            &config,
+        )
+        .await?;
+        let (subscriptions_tx, subscriptions_rx) = watch::channel(confirmed.clone());
+        let subscriptions_tx = Arc::new(subscriptions_tx);
+        let codec = config.codec;
+        let control = Arc::new(SubscriptionControl::new(
+            confirmed,
+            codec,
+            config.symbol_limit,
+            Duration::from_millis(config.connection_timeout_ms),
+            {
+                let (config, url, credentials) = (config.clone(), url.clone(), credentials.clone());
+                move |frame: &serde_json::Value| {
+                    observe_outbound(&config, &url, &credentials.current(), frame);
+                }
+            },
+        ));
+        control.attach(sink).await;
 
         let (sender, receiver) = mpsc::channel(config.message_buffer_size.max(1));
         let open = {
-            let (url, credentials, subscription, config) =
-                (url, credentials, subscription, config.clone());
+            let (url, credentials, config) = (url, credentials, config.clone());
+            let subscriptions_tx = Arc::clone(&subscriptions_tx);
+            let control = Arc::clone(&control);
             move || {
-                let (url, credentials, subscription, config) = (
-                    url.clone(),
-                    credentials.clone(),
-                    subscription.clone(),
-                    config.clone(),
-                );
-                async move { open_market_data_stream(&url, &credentials, &subscription, &config).await }
+                let (url, credentials, config) = (url.clone(), credentials.clone(), config.clone());
+                let transport = Arc::clone(&transport);
+                let subscriptions_tx = Arc::clone(&subscriptions_tx);
+                let control = Arc::clone(&control);
+                async move {
+                    // Replay the set held by the stream, including changes
+                    // made since the first connect.
+                    let (sink, stream, confirmed) = open_market_data_stream(
+                        &transport,
+                        &url,
+                        &credentials.current(),
+                        &control.current().to_request(),
+                        &config,
+                    )
+                    .await?;
+                    control.attach(sink).await;
+                    subscriptions_tx.send_replace(confirmed.clone());
+                    Ok((
+                        stream,
+                        vec![MarketDataEvent::SubscriptionsChanged(confirmed)],
+                    ))
+                }
             }
         };
+        let parse = {
+            let control = Arc::clone(&control);
+            observe_inbound(
+                &config,
+                &self.url,
+                &self.credentials,
+                move |frame: &[u8]| {
+                    let (updates, confirmed) = codec.decode_market_data(frame);
+                    let mut events: Vec<MarketDataEvent> =
+                        updates.into_iter().map(MarketDataEvent::Update).collect();
+                    if let Some(confirmed) = confirmed {
+                        subscriptions_tx.send_replace(confirmed.clone());
+                        control.ack(SubscriptionAck::Confirmed(confirmed.clone()));
+                        events.push(MarketDataEvent::SubscriptionsChanged(confirmed));
+                    } else if events.is_empty()
+                        && let Some(error) = codec.decode_error(frame)
+                    {
+                        control.ack(SubscriptionAck::Rejected {
+                            code: error.code,
+                            message: error.msg.clone(),
+                        });
+                        events.push(MarketDataEvent::SubscriptionError {
+                            code: error.code,
+                            message: error.msg,
+                        });
+                    }
+                    events
+                },
+            )
+        };
         tokio::spawn(run_stream_task(
-            stream,
-            open,
-            |text| {
-                parse_market_data_updates(text)
-                    .into_iter()
-                    .map(MarketDataEvent::Update)
-                    .collect()
-            },
-            config,
-            sender,
+            stream, open, parse, config, lease, rotation, sender,
         ));
 
-        Ok(MarketDataStream::new(receiver))
+        Ok(MarketDataStream::with_subscriptions(receiver, subscriptions_rx).with_control(control))
+    }
+
+    /// Subscribe to market data, falling back to a feed the plan includes
+    /// when the server refuses this client's feed.
+    ///
+    /// If the subscription fails with
+    /// [`WebSocketError::InsufficientSubscription`] on a feed with a
+    /// [`WebSocketError::fallback_feed`] (SIP is retried on IEX), it is
+    /// retried once on that feed. Returns the stream and the feed it
+    /// streams, `None` for clients not built for a feed.
+    pub async fn subscribe_market_data_with_fallback(
+        &self,
+        subscription: SubscribeMessage,
+        config: WebSocketConfig,
+    ) -> Result<(MarketDataStream, Option<DataFeed>)> {
+        let error = match self
+            .subscribe_market_data_with_config(subscription.clone(), config.clone())
+            .await
+        {
+            Ok(stream) => return Ok((stream, self.feed)),
+            Err(e) => e,
+        };
+        let Some(fallback) = self
+            .feed
+            .and_then(|feed| WebSocketError::classify(&error).and_then(|e| e.fallback_feed(feed)))
+        else {
+            return Err(error);
+        };
+        warn!(
+            "Feed {:?} not permitted ({}), falling back to {:?}",
+            self.feed, error, fallback
+        );
+        let mut client =
+            Self::with_feed(self.credentials.clone(), self.environment.clone(), fallback);
+        if let Some(config) = &self.client_config {
+            client.url = config.data_stream_for(fallback.url());
+        }
+        client.client_config = self.client_config.clone();
+        client.transport = Arc::clone(&self.transport);
+        let stream = client
+            .subscribe_market_data_with_config(subscription, config)
+            .await?;
+        Ok((stream, Some(fallback)))
     }
 
     /// Subscribe to trading updates with the default [`WebSocketConfig`].
@@ -298,36 +510,217 @@ impl AlpacaWebSocketClient {
         init_crypto_provider();
 
         let url = self.url.clone();
+        let transport = Arc::clone(&self.transport);
         let credentials = self.credentials.clone();
-        let stream = open_trading_stream(&url, &credentials, &config).await?;
+        let rotation = credentials.subscribe();
+        let lease = self.acquire_lease(&config)?;
+        let stream = open_trading_stream(&transport, &url, &credentials.current(), &config).await?;
 
         let (sender, receiver) = mpsc::channel(config.message_buffer_size.max(1));
         let open = {
             let (url, credentials, config) = (url, credentials, config.clone());
             move || {
                 let (url, credentials, config) = (url.clone(), credentials.clone(), config.clone());
-                async move { open_trading_stream(&url, &credentials, &config).await }
+                let transport = Arc::clone(&transport);
+                async move {
+                    let stream =
+                        open_trading_stream(&transport, &url, &credentials.current(), &config)
+                            .await?;
+                    Ok((stream, Vec::new()))
+                }
             }
         };
+        let parse = observe_inbound(&config, &self.url, &self.credentials, |frame| {
+            std::str::from_utf8(frame)
+                .map(parse_trading_updates)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|update| TradingEvent::Update(Box::new(update)))
+                .collect()
+        });
         tokio::spawn(run_stream_task(
-            stream,
-            open,
-            |text| {
-                parse_trading_updates(text)
+            stream, open, parse, config, lease, rotation, sender,
+        ));
+
+        Ok(TradingStream::new(receiver))
+    }
+
+    /// Subscribe to account updates with the default [`WebSocketConfig`].
+    ///
+    /// See [`Self::subscribe_account_updates_with_config`].
+    pub async fn subscribe_account_updates(&self) -> Result<AccountUpdatesStream> {
+        self.subscribe_account_updates_with_config(WebSocketConfig::default())
+            .await
+    }
+
+    /// Subscribe to the trading stream's `account_updates` channel with an
+    /// explicit [`WebSocketConfig`].
+    ///
+    /// Connection ownership, reconnection and lag reporting follow
+    /// [`Self::subscribe_trading_updates_with_config`]. Each update is
+    /// compared with the previous one, across reconnects, to report what
+    /// changed.
+    pub async fn subscribe_account_updates_with_config(
+        &self,
+        config: WebSocketConfig,
+    ) -> Result<AccountUpdatesStream> {
+        // Initialize crypto provider for TLS
+        init_crypto_provider();
+
+        let url = self.url.clone();
+        let transport = Arc::clone(&self.transport);
+        let credentials = self.credentials.clone();
+        let rotation = credentials.subscribe();
+        let lease = self.acquire_lease(&config)?;
+        let stream = open_account_stream(&transport, &url, &credentials.current(), &config).await?;
+
+        let (sender, receiver) = mpsc::channel(config.message_buffer_size.max(1));
+        let open = {
+            let (url, credentials, config) = (url, credentials, config.clone());
+            move || {
+                let (url, credentials, config) = (url.clone(), credentials.clone(), config.clone());
+                let transport = Arc::clone(&transport);
+                async move {
+                    let stream =
+                        open_account_stream(&transport, &url, &credentials.current(), &config)
+                            .await?;
+                    Ok((stream, Vec::new()))
+                }
+            }
+        };
+        let last = std::sync::Mutex::new(None::<AccountUpdateMessage>);
+        let parse = observe_inbound(
+            &config,
+            &self.url,
+            &self.credentials,
+            move |frame: &[u8]| {
+                let updates = std::str::from_utf8(frame)
+                    .map(parse_account_updates)
+                    .unwrap_or_default();
+                let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+                updates
                     .into_iter()
-                    .map(|update| TradingEvent::Update(Box::new(update)))
+                    .map(|account| {
+                        let changes = last
+                            .as_ref()
+                            .map(|previous| account.changes_since(previous))
+                            .unwrap_or_default();
+                        *last = Some(account.clone());
+                        AccountUpdateEvent::Update {
+                            account: Box::new(account),
+                            changes,
+                        }
+                    })
                     .collect()
             },
-            config,
-            sender,
+        );
+        tokio::spawn(run_stream_task(
+            stream, open, parse, config, lease, rotation, sender,
         ));
 
-        Ok(TradingStream::new(receiver))
+        Ok(AccountUpdatesStream::new(receiver))
+    }
+
+    /// Subscribe to real-time news with the default [`WebSocketConfig`].
+    ///
+    /// See [`Self::subscribe_news_with_config`].
+    pub async fn subscribe_news(&self, symbols: &[&str]) -> Result<NewsStream> {
+        self.subscribe_news_with_config(symbols, WebSocketConfig::default())
+            .await
+    }
+
+    /// Subscribe to real-time news articles for `symbols` (use `"*"` for
+    /// all symbols) on a client created with [`Self::news`].
+    ///
+    /// Articles are delivered as [`EnhancedNewsArticle`]s as they are
+    /// published or updated. Connection ownership, reconnection and lag
+    /// semantics are the same as [`Self::subscribe_market_data_with_config`].
+    pub async fn subscribe_news_with_config(
+        &self,
+        symbols: &[&str],
+        config: WebSocketConfig,
+    ) -> Result<NewsStream> {
+        // Initialize crypto provider for TLS
+        init_crypto_provider();
+
+        let url = self.url.clone();
+        let transport = Arc::clone(&self.transport);
+        let credentials = self.credentials.clone();
+        let subscription = serde_json::json!({
+            "action": "subscribe",
+            "news": symbols,
+        });
+        let rotation = credentials.subscribe();
+        let lease = self.acquire_lease(&config)?;
+        let (_, stream, _) = open_data_stream(
+            &transport,
+            &url,
+            &credentials.current(),
+            &subscription,
+            &config,
+            Codec::Json,
+        )
+        .await?;
+
+        let (sender, receiver) = mpsc::channel(config.message_buffer_size.max(1));
+        let open = {
+            let (url, credentials, subscription, config) =
+                (url, credentials, subscription, config.clone());
+            move || {
+                let (url, credentials, subscription, config) = (
+                    url.clone(),
+                    credentials.clone(),
+                    subscription.clone(),
+                    config.clone(),
+                );
+                let transport = Arc::clone(&transport);
+                async move {
+                    let (_, stream, _) = open_data_stream(
+                        &transport,
+                        &url,
+                        &credentials.current(),
+                        &subscription,
+                        &config,
+                        Codec::Json,
+                    )
+                    .await?;
+                    Ok((stream, Vec::new()))
+                }
+            }
+        };
+        let parse = observe_inbound(&config, &self.url, &self.credentials, |frame| {
+            std::str::from_utf8(frame)
+                .map(parse_news_articles)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|article| NewsEvent::Update(Box::new(article)))
+                .collect()
+        });
+        tokio::spawn(run_stream_task(
+            stream, open, parse, config, lease, rotation, sender,
+        ));
+
+        Ok(NewsStream::new(receiver))
+    }
+
+    /// Acquire the configured connection lease, if any, for this client's
+    /// URL and API key.
+    fn acquire_lease(&self, config: &WebSocketConfig) -> Result<Option<LeaseGuard>> {
+        config
+            .lease
+            .as_ref()
+            .map(|lease| {
+                LeaseGuard::acquire(
+                    lease,
+                    lease_key(&self.url, &self.credentials.current().api_key),
+                )
+            })
+            .transpose()
     }
 
     /// Authenticate with the WebSocket
     async fn authenticate(&self, sink: &mut WsSink) -> Result<()> {
-        send_auth(&self.credentials, sink).await
+        send_auth(&self.credentials.current(), sink, Codec::Json).await
     }
 
     /// Handle incoming WebSocket messages
@@ -413,6 +806,14 @@ impl AlpacaWebSocketClient {
         &self.url
     }
 
+    /// The market data feed this client streams, if it was built for one.
+    ///
+    /// Use it to label received data, e.g. to tell 15-minute delayed
+    /// `DelayedSip` data from real-time data.
+    pub fn feed(&self) -> Option<DataFeed> {
+        self.feed
+    }
+
     /// Get the environment
     pub fn environment(&self) -> &Environment {
         &self.environment
@@ -431,28 +832,91 @@ fn redact_key(key: &str) -> String {
     format!("****{suffix}")
 }
 
-/// Send the authentication frame. The frame itself is never logged because
-/// it contains the API key and secret.
-async fn send_auth(credentials: &Credentials, sink: &mut WsSink) -> Result<()> {
-    // Alpaca uses {"action": "auth", "key": "...", "secret": "..."}
-    let auth_msg = serde_json::json!({
+/// The authentication frame:
+/// `{"action": "auth", "key": "...", "secret": "..."}`.
+fn auth_frame(credentials: &Credentials) -> serde_json::Value {
+    serde_json::json!({
         "action": "auth",
         "key": credentials.api_key,
         "secret": credentials.secret_key
+    })
+}
+
+/// Report a frame sent during a handshake to the configured hooks.
+fn observe_outbound(
+    config: &WebSocketConfig,
+    url: &str,
+    credentials: &Credentials,
+    frame: &serde_json::Value,
+) {
+    if let Some(instrumentation) = &config.instrumentation {
+        instrumentation.ws_message(
+            url,
+            WsDirection::Outbound,
+            frame.to_string().as_bytes(),
+            credentials,
+        );
+    }
+}
+
+/// Wrap a frame parser so every received frame is first reported to the
+/// configured hooks. Binary frames are reported as JSON text when the
+/// codec can decode them.
+fn observe_inbound<E, P>(
+    config: &WebSocketConfig,
+    url: &str,
+    credentials: &CredentialsHandle,
+    parse: P,
+) -> impl Fn(&[u8]) -> Vec<E> + use<E, P>
+where
+    P: Fn(&[u8]) -> Vec<E>,
+{
+    let observer = config.instrumentation.clone().map(|instrumentation| {
+        (
+            instrumentation,
+            url.to_string(),
+            credentials.clone(),
+            config.codec,
+        )
     });
+    move |frame| {
+        if let Some((instrumentation, url, credentials, codec)) = &observer {
+            let credentials = credentials.current();
+            match std::str::from_utf8(frame) {
+                Ok(_) => instrumentation.ws_message(url, WsDirection::Inbound, frame, &credentials),
+                Err(_) => match codec.to_json_text(frame) {
+                    Ok(text) => instrumentation.ws_message(
+                        url,
+                        WsDirection::Inbound,
+                        text.as_bytes(),
+                        &credentials,
+                    ),
+                    Err(_) => {
+                        instrumentation.ws_message(url, WsDirection::Inbound, frame, &credentials)
+                    }
+                },
+            }
+        }
+        parse(frame)
+    }
+}
+
+/// Send the authentication frame. The frame itself is never logged because
+/// it contains the API key and secret.
+async fn send_auth(credentials: &Credentials, sink: &mut WsSink, codec: Codec) -> Result<()> {
+    let auth_msg = auth_frame(credentials);
 
-    let auth_json = serde_json::to_string(&auth_msg)?;
     debug!(
         "Sending auth message for key {}",
         redact_key(&credentials.api_key)
     );
-    sink.send(Message::Text(auth_json.into())).await?;
+    sink.send(codec.encode(&auth_msg)?).await?;
     Ok(())
 }
 
-/// Extract the error message from a server frame, if the frame (or any
-/// element of a frame array) is a `{"T": "error"}` message.
-fn frame_error(text: &str) -> Option<String> {
+/// Extract the error of a server frame, if the frame (or any element of a
+/// frame array) is a `{"T": "error"}` message or a failed authorization.
+fn frame_error(text: &str) -> Option<WebSocketError> {
     let value: serde_json::Value = serde_json::from_str(text).ok()?;
     let frames = match &value {
         serde_json::Value::Array(items) => items.as_slice(),
@@ -460,13 +924,16 @@ fn frame_error(text: &str) -> Option<String> {
     };
     frames.iter().find_map(|frame| {
         if frame.get("T").and_then(|t| t.as_str()) == Some("error") {
-            return Some(
-                frame
-                    .get("msg")
-                    .and_then(|m| m.as_str())
-                    .unwrap_or("unknown error")
-                    .to_string(),
-            );
+            let message = frame
+                .get("msg")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error");
+            let code = frame
+                .get("code")
+                .and_then(serde_json::Value::as_u64)
+                .and_then(|code| u16::try_from(code).ok())
+                .unwrap_or(500);
+            return Some(WebSocketError::from_code(code, message));
         }
         // Trading-stream style: {"stream":"authorization","data":{"status":...}}
         if frame.get("stream").and_then(|s| s.as_str()) == Some("authorization") {
@@ -476,24 +943,24 @@ fn frame_error(text: &str) -> Option<String> {
                 .and_then(|s| s.as_str())
                 .unwrap_or("unknown");
             if status != "authorized" {
-                return Some(format!("authorization status: {status}"));
+                return Some(WebSocketError::AuthenticationFailed(format!(
+                    "authorization status: {status}"
+                )));
             }
         }
         None
     })
 }
 
-/// Read the next text frame during the handshake, failing on error frames,
-/// unexpected frames, or a closed connection.
-async fn expect_ok_frame(stream: &mut WsReceiver, phase: &str) -> Result<()> {
+/// Read the next text (or, with MessagePack, binary) frame during the
+/// handshake as JSON text, failing on error frames, unexpected frames, or a
+/// closed connection.
+async fn expect_ok_frame(stream: &mut WsReceiver, phase: &str, codec: Codec) -> Result<String> {
     loop {
-        match stream.next().await {
-            Some(Ok(Message::Text(text))) => {
-                debug!("{} response: {}", phase, text);
-                return match frame_error(&text) {
-                    Some(msg) => Err(AlpacaError::WebSocket(format!("{phase} failed: {msg}"))),
-                    None => Ok(()),
-                };
+        let text = match stream.next().await {
+            Some(Ok(Message::Text(text))) => text.to_string(),
+            Some(Ok(Message::Binary(frame))) if codec != Codec::Json => {
+                codec.to_json_text(&frame)?
             }
             Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
             Some(Ok(other)) => {
@@ -507,42 +974,84 @@ async fn expect_ok_frame(stream: &mut WsReceiver, phase: &str) -> Result<()> {
                     "{phase} failed: connection closed"
                 )));
             }
-        }
+        };
+        debug!("{} response: {}", phase, text);
+        return match frame_error(&text) {
+            Some(WebSocketError::ConnectionLimitExceeded(msg)) => Err(
+                AlpacaError::ConnectionLimitExceeded(format!("{phase} failed: {msg}")),
+            ),
+            Some(error) => Err(AlpacaError::WebSocket(format!("{phase} failed: {error}"))),
+            None => Ok(text),
+        };
     }
 }
 
-/// Connect, authenticate, and subscribe on a market-data socket, bounded by
-/// the configured connection timeout. Performs the full handshake (server
-/// hello, auth, subscription) so the returned stream only yields data frames.
+/// Connect, authenticate, and subscribe on a market-data socket. Returns the
+/// write half for later subscription changes and the subscription set
+/// confirmed by the server (or the requested one if the confirmation cannot
+/// be parsed).
 async fn open_market_data_stream(
+    transport: &Transport,
     url: &str,
     credentials: &Credentials,
     subscription: &SubscribeMessage,
     config: &WebSocketConfig,
-) -> Result<WsReceiver> {
+) -> Result<(WsSink, WsReceiver, Subscriptions)> {
+    // Alpaca uses {"action": "subscribe", ...}
+    let mut sub_msg = serde_json::json!({
+        "action": "subscribe",
+        "trades": subscription.trades.clone().unwrap_or_default(),
+        "quotes": subscription.quotes.clone().unwrap_or_default(),
+        "bars": subscription.bars.clone().unwrap_or_default()
+    });
+    // Only crypto feeds know the orderbooks channel.
+    if let Some(orderbooks) = &subscription.orderbooks {
+        sub_msg["orderbooks"] = serde_json::json!(orderbooks);
+    }
+    let (sink, stream, confirmation) =
+        open_data_stream(transport, url, credentials, &sub_msg, config, config.codec).await?;
+    let confirmed = Subscriptions::from_frame(&confirmation)
+        .unwrap_or_else(|| Subscriptions::from_request(subscription));
+    Ok((sink, stream, confirmed))
+}
+
+/// Connect, authenticate, and send `sub_msg` on a data socket (market data
+/// or news), bounded by the configured connection timeout. Performs the
+/// full handshake (server hello, auth, subscription) so the returned stream
+/// only yields data frames; the write half and the subscription
+/// confirmation frame (as JSON text whatever the `codec`) are returned
+/// alongside it.
+async fn open_data_stream(
+    transport: &Transport,
+    url: &str,
+    credentials: &Credentials,
+    sub_msg: &serde_json::Value,
+    config: &WebSocketConfig,
+    codec: Codec,
+) -> Result<(WsSink, WsReceiver, String)> {
     let handshake = async {
-        info!("Connecting to WebSocket: {}", url);
-        let (ws_stream, _) = connect_async(url).await?;
+        info!("Connecting to WebSocket: {} ({:?})", url, codec);
+        let mut request = url.into_client_request()?;
+        if codec != Codec::Json {
+            request
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(codec.content_type()));
+        }
+        let ws_stream = transport.connect(request).await?;
         let (mut sink, mut stream) = ws_stream.split();
 
-        expect_ok_frame(&mut stream, "server hello").await?;
+        expect_ok_frame(&mut stream, "server hello", codec).await?;
 
-        send_auth(credentials, &mut sink).await?;
-        expect_ok_frame(&mut stream, "authentication").await?;
+        send_auth(credentials, &mut sink, codec).await?;
+        observe_outbound(config, url, credentials, &auth_frame(credentials));
+        expect_ok_frame(&mut stream, "authentication", codec).await?;
 
-        // Alpaca uses {"action": "subscribe", ...}
-        let sub_msg = serde_json::json!({
-            "action": "subscribe",
-            "trades": subscription.trades.clone().unwrap_or_default(),
-            "quotes": subscription.quotes.clone().unwrap_or_default(),
-            "bars": subscription.bars.clone().unwrap_or_default()
-        });
-        let sub_json = serde_json::to_string(&sub_msg)?;
-        debug!("Sending subscription: {}", sub_json);
-        sink.send(Message::Text(sub_json.into())).await?;
-        expect_ok_frame(&mut stream, "subscription").await?;
+        debug!("Sending subscription: {}", sub_msg);
+        sink.send(codec.encode(sub_msg)?).await?;
+        observe_outbound(config, url, credentials, sub_msg);
+        let confirmation = expect_ok_frame(&mut stream, "subscription", codec).await?;
 
-        Ok(stream)
+        Ok((sink, stream, confirmation))
     };
 
     match timeout(
@@ -559,61 +1068,16 @@ async fn open_market_data_stream(
     }
 }
 
-/// Parse a market-data text frame (a JSON array of messages) into updates.
-fn parse_market_data_updates(text: &str) -> Vec<MarketDataUpdate> {
+/// Parse a news text frame (a JSON array of messages) into articles,
+/// ignoring control messages.
+fn parse_news_articles(text: &str) -> Vec<EnhancedNewsArticle> {
     let Ok(messages) = serde_json::from_str::<Vec<serde_json::Value>>(text) else {
         return Vec::new();
     };
     messages
         .into_iter()
-        .filter_map(|msg_value| {
-            let msg_type = msg_value.get("T").and_then(|t| t.as_str())?;
-            match msg_type {
-                "t" => serde_json::from_value::<TradeMessage>(msg_value.clone())
-                    .ok()
-                    .map(|trade_msg| MarketDataUpdate::Trade {
-                        symbol: trade_msg.symbol.clone(),
-                        trade: trade_msg.into(),
-                    }),
-                // Quote message - try crypto format first
-                "q" => {
-                    if let Ok(quote_msg) =
-                        serde_json::from_value::<CryptoQuoteMessage>(msg_value.clone())
-                    {
-                        Some(MarketDataUpdate::Quote {
-                            symbol: quote_msg.symbol.clone(),
-                            quote: Quote {
-                                timestamp: quote_msg.timestamp,
-                                timeframe: "real-time".to_string(),
-                                bid_price: quote_msg.bid_price,
-                                bid_size: quote_msg.bid_size as u32,
-                                ask_price: quote_msg.ask_price,
-                                ask_size: quote_msg.ask_size as u32,
-                                bid_exchange: String::new(),
-                                ask_exchange: String::new(),
-                            },
-                        })
-                    } else {
-                        serde_json::from_value::<QuoteMessage>(msg_value)
-                            .ok()
-                            .map(|quote_msg| MarketDataUpdate::Quote {
-                                symbol: quote_msg.symbol.clone(),
-                                quote: quote_msg.into(),
-                            })
-                    }
-                }
-                "b" => serde_json::from_value::<BarMessage>(msg_value)
-                    .ok()
-                    .map(|bar_msg| MarketDataUpdate::Bar {
-                        symbol: bar_msg.symbol.clone(),
-                        bar: bar_msg.into(),
-                    }),
-                _ => {
-                    debug!("Ignoring message type: {}", msg_type);
-                    None
-                }
-            }
-        })
+        .filter(|msg| msg.get("T").and_then(|t| t.as_str()) == Some("n"))
+        .filter_map(|msg| serde_json::from_value(msg).ok())
         .collect()
 }
 
@@ -621,17 +1085,19 @@ fn parse_market_data_updates(text: &str) -> Vec<MarketDataUpdate> {
 /// connection timeout. Unlike market data there is no server hello and no
 /// subscription frame: authentication is the whole handshake.
 async fn open_trading_stream(
+    transport: &Transport,
     url: &str,
     credentials: &Credentials,
     config: &WebSocketConfig,
 ) -> Result<WsReceiver> {
     let handshake = async {
         info!("Connecting to WebSocket: {}", url);
-        let (ws_stream, _) = connect_async(url).await?;
+        let ws_stream = transport.connect(url).await?;
         let (mut sink, mut stream) = ws_stream.split();
 
-        send_auth(credentials, &mut sink).await?;
-        expect_ok_frame(&mut stream, "authentication").await?;
+        send_auth(credentials, &mut sink, Codec::Json).await?;
+        observe_outbound(config, url, credentials, &auth_frame(credentials));
+        expect_ok_frame(&mut stream, "authentication", Codec::Json).await?;
 
         Ok(stream)
     };
@@ -650,6 +1116,63 @@ async fn open_trading_stream(
     }
 }
 
+/// Connect, authenticate and listen to the `account_updates` channel.
+async fn open_account_stream(
+    transport: &Transport,
+    url: &str,
+    credentials: &Credentials,
+    config: &WebSocketConfig,
+) -> Result<WsReceiver> {
+    let handshake = async {
+        info!("Connecting to WebSocket: {}", url);
+        let ws_stream = transport.connect(url).await?;
+        let (mut sink, mut stream) = ws_stream.split();
+
+        send_auth(credentials, &mut sink, Codec::Json).await?;
+        observe_outbound(config, url, credentials, &auth_frame(credentials));
+        expect_ok_frame(&mut stream, "authentication", Codec::Json).await?;
+
+        let listen = serde_json::json!({
+            "action": "listen",
+            "data": {"streams": ["account_updates"]}
+        });
+        sink.send(Codec::Json.encode(&listen)?).await?;
+        observe_outbound(config, url, credentials, &listen);
+
+        Ok(stream)
+    };
+
+    match timeout(
+        Duration::from_millis(config.connection_timeout_ms),
+        handshake,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(_) => Err(AlpacaError::WebSocket(format!(
+            "handshake timed out after {}ms",
+            config.connection_timeout_ms
+        ))),
+    }
+}
+
+/// Parse a trading-stream text frame into account updates, ignoring other
+/// channels (`{"stream": "account_updates", "data": {...}}`).
+fn parse_account_updates(text: &str) -> Vec<AccountUpdateMessage> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(text) else {
+        return Vec::new();
+    };
+    let frames = match value {
+        serde_json::Value::Array(items) => items,
+        other => vec![other],
+    };
+    frames
+        .into_iter()
+        .filter(|frame| frame.get("stream").and_then(|s| s.as_str()) == Some("account_updates"))
+        .filter_map(|mut frame| serde_json::from_value(frame.get_mut("data")?.take()).ok())
+        .collect()
+}
+
 /// Parse a trading text frame (a single message or an array) into order
 /// updates, ignoring non-trade-update messages.
 fn parse_trading_updates(text: &str) -> Vec<TradeUpdateMessage> {
@@ -673,14 +1196,19 @@ fn parse_trading_updates(text: &str) -> Vec<TradeUpdateMessage> {
 
 /// Lifecycle-event constructors shared by the market-data and trading
 /// streaming tasks.
-trait StreamEvents: Sized + Send + 'static {
+pub(crate) trait StreamEvents: Sized + Send + 'static {
     fn lagged(missed: u64) -> Self;
     fn reconnecting(attempt: u32, delay: Duration) -> Self;
     fn reconnected() -> Self;
     fn disconnected(reason: String) -> Self;
+    /// Updates with the same key replace each other under
+    /// [`BackpressurePolicy::CoalesceQuotes`](crate::BackpressurePolicy::CoalesceQuotes).
+    fn coalesce_key(&self) -> Option<&str> {
+        None
+    }
 }
 
-impl StreamEvents for MarketDataEvent {
+impl StreamEvents for NewsEvent {
     fn lagged(missed: u64) -> Self {
         Self::Lagged { missed }
     }
@@ -695,7 +1223,7 @@ impl StreamEvents for MarketDataEvent {
     }
 }
 
-impl StreamEvents for TradingEvent {
+impl StreamEvents for MarketDataEvent {
     fn lagged(missed: u64) -> Self {
         Self::Lagged { missed }
     }
@@ -708,78 +1236,107 @@ impl StreamEvents for TradingEvent {
     fn disconnected(reason: String) -> Self {
         Self::Disconnected { reason }
     }
+    fn coalesce_key(&self) -> Option<&str> {
+        match self {
+            Self::Update(MarketDataUpdate::Quote { symbol, .. }) => Some(symbol),
+            _ => None,
+        }
+    }
 }
 
-/// Forward a data update without blocking the socket reader. When the
-/// bounded channel is full the update is dropped and counted; the count is
-/// delivered later as a `Lagged` event. `Err(())` means the consumer
-/// dropped the stream.
-fn send_update<E: StreamEvents>(
-    sender: &mpsc::Sender<E>,
-    missed: &mut u64,
-    update: E,
-) -> std::result::Result<(), ()> {
-    if *missed > 0 {
-        match sender.try_send(E::lagged(*missed)) {
-            Ok(()) => *missed = 0,
-            Err(TrySendError::Full(_)) => {
-                *missed += 1;
-                return Ok(());
-            }
-            Err(TrySendError::Closed(_)) => return Err(()),
-        }
+impl StreamEvents for AccountUpdateEvent {
+    fn lagged(missed: u64) -> Self {
+        Self::Lagged { missed }
     }
-    match sender.try_send(update) {
-        Ok(()) => Ok(()),
-        Err(TrySendError::Full(_)) => {
-            *missed += 1;
-            Ok(())
-        }
-        Err(TrySendError::Closed(_)) => Err(()),
+    fn reconnecting(attempt: u32, delay: Duration) -> Self {
+        Self::Reconnecting { attempt, delay }
+    }
+    fn reconnected() -> Self {
+        Self::Reconnected
+    }
+    fn disconnected(reason: String) -> Self {
+        Self::Disconnected { reason }
     }
 }
 
-/// Forward a lifecycle event, waiting for channel capacity so it is never
-/// dropped. Flushes any pending lag count first. Returns `false` when the
-/// consumer dropped the stream.
-async fn send_lifecycle<E: StreamEvents>(
-    sender: &mpsc::Sender<E>,
-    missed: &mut u64,
-    event: E,
-) -> bool {
-    if *missed > 0 {
-        if sender.send(E::lagged(*missed)).await.is_err() {
-            return false;
-        }
-        *missed = 0;
+impl StreamEvents for TradingEvent {
+    fn lagged(missed: u64) -> Self {
+        Self::Lagged { missed }
+    }
+    fn reconnecting(attempt: u32, delay: Duration) -> Self {
+        Self::Reconnecting { attempt, delay }
+    }
+    fn reconnected() -> Self {
+        Self::Reconnected
+    }
+    fn disconnected(reason: String) -> Self {
+        Self::Disconnected { reason }
     }
-    sender.send(event).await.is_ok()
 }
 
 /// Background task that owns a streaming socket: reads frames, forwards
 /// events to the consumer, and reconnects with capped exponential backoff
 /// by calling `open` (which re-runs the full handshake, so the active
-/// subscription/authentication is re-issued). Exits when the consumer
-/// drops the stream or reconnection gives up.
+/// subscription/authentication is re-issued, and returns any events to
+/// deliver after `Reconnected`). A credentials rotation closes the socket
+/// and reconnects immediately so the new credentials take effect, even when
+/// reconnection is otherwise disabled. Exits when the consumer
+/// drops the stream, reconnection gives up, or the connection lease is
+/// taken over by another holder.
 async fn run_stream_task<E, O, Fut, P>(
     mut stream: WsReceiver,
     open: O,
     parse: P,
     config: WebSocketConfig,
+    mut lease: Option<LeaseGuard>,
+    rotation: watch::Receiver<Credentials>,
     sender: mpsc::Sender<E>,
 ) where
     E: StreamEvents,
     O: Fn() -> Fut,
-    Fut: Future<Output = Result<WsReceiver>>,
-    P: Fn(&str) -> Vec<E>,
+    Fut: Future<Output = Result<(WsReceiver, Vec<E>)>>,
+    P: Fn(&[u8]) -> Vec<E>,
 {
-    let mut missed: u64 = 0;
+    let mut outbox = Outbox::new(sender, config.backpressure, config.message_buffer_size);
+    let mut rotation = Some(rotation);
     'connection: loop {
+        let mut rotated = false;
         let mut reason = loop {
-            match stream.next().await {
+            let message = tokio::select! {
+                message = stream.next() => message,
+                reason = lease_lost(&mut lease) => {
+                    warn!("Closing stream: {}", reason);
+                    let _ = outbox.send_lifecycle(E::disconnected(reason)).await;
+                    return;
+                }
+                () = credentials_rotated(&mut rotation) => {
+                    info!("Credentials rotated; re-authenticating");
+                    rotated = true;
+                    break "credentials rotated".to_string();
+                }
+                permit = outbox.reserve(), if outbox.has_pending() => {
+                    match permit {
+                        Ok(permit) => outbox.flush_one(permit),
+                        Err(_) => {
+                            debug!("Stream dropped by consumer");
+                            return;
+                        }
+                    }
+                    continue;
+                }
+            };
+            match message {
                 Some(Ok(Message::Text(text))) => {
-                    for update in parse(&text) {
-                        if send_update(&sender, &mut missed, update).is_err() {
+                    for update in parse(text.as_bytes()) {
+                        if outbox.push(update).await.is_err() {
+                            debug!("Stream dropped by consumer");
+                            return;
+                        }
+                    }
+                }
+                Some(Ok(Message::Binary(frame))) => {
+                    for update in parse(&frame) {
+                        if outbox.push(update).await.is_err() {
                             debug!("Stream dropped by consumer");
                             return;
                         }
@@ -792,11 +1349,13 @@ async fn run_stream_task<E, O, Fut, P>(
             }
         };
 
-        if !config.reconnect_enabled {
-            let _ = send_lifecycle(&sender, &mut missed, E::disconnected(reason)).await;
+        if !config.reconnect_enabled && !rotated {
+            let _ = outbox.send_lifecycle(E::disconnected(reason)).await;
             return;
         }
 
+        let backoff = config.reconnect_backoff();
+        let mut previous_delay = Duration::ZERO;
         let mut attempt: u32 = 0;
         loop {
             attempt += 1;
@@ -805,43 +1364,55 @@ async fn run_stream_task<E, O, Fut, P>(
                     "Reconnection gave up after {} attempts",
                     config.reconnect_max_attempts
                 );
-                let _ = send_lifecycle(
-                    &sender,
-                    &mut missed,
-                    E::disconnected(format!(
+                let _ = outbox
+                    .send_lifecycle(E::disconnected(format!(
                         "gave up after {} reconnect attempts: {}",
                         config.reconnect_max_attempts, reason
-                    )),
-                )
-                .await;
+                    )))
+                    .await;
                 return;
             }
 
-            let delay = Duration::from_millis(
-                config
-                    .reconnect_base_delay_ms
-                    .saturating_mul(1u64 << (attempt - 1).min(16))
-                    .min(config.reconnect_max_delay_ms),
-            );
+            let delay = if rotated && attempt == 1 {
+                Duration::ZERO
+            } else {
+                backoff.delay(attempt, previous_delay)
+            };
+            previous_delay = delay;
             warn!(
                 "Connection lost ({}); reconnecting in {:?} (attempt {}/{})",
                 reason, delay, attempt, config.reconnect_max_attempts
             );
-            if !send_lifecycle(&sender, &mut missed, E::reconnecting(attempt, delay)).await {
+            if !outbox.send_lifecycle(E::reconnecting(attempt, delay)).await {
                 return;
             }
-            sleep(delay).await;
+            tokio::select! {
+                _ = sleep(delay) => {}
+                reason = lease_lost(&mut lease) => {
+                    let _ = outbox.send_lifecycle(E::disconnected(reason)).await;
+                    return;
+                }
+            }
 
             match open().await {
-                Ok(new_stream) => {
+                Ok((new_stream, events)) => {
                     stream = new_stream;
                     info!("Connection re-established");
-                    if !send_lifecycle(&sender, &mut missed, E::reconnected()).await {
+                    #[cfg(feature = "metrics")]
+                    alpaca_base::metrics::record_ws_reconnect(true);
+                    if !outbox.send_lifecycle(E::reconnected()).await {
                         return;
                     }
+                    for event in events {
+                        if !outbox.send_lifecycle(event).await {
+                            return;
+                        }
+                    }
                     continue 'connection;
                 }
                 Err(e) => {
+                    #[cfg(feature = "metrics")]
+                    alpaca_base::metrics::record_ws_reconnect(false);
                     reason = format!("reconnect attempt {attempt} failed: {e}");
                 }
             }
@@ -849,6 +1420,26 @@ async fn run_stream_task<E, O, Fut, P>(
     }
 }
 
+/// Resolves when the watched credentials are rotated. Never resolves once
+/// every handle has been dropped.
+async fn credentials_rotated(rotation: &mut Option<watch::Receiver<Credentials>>) {
+    if let Some(receiver) = rotation {
+        if receiver.changed().await.is_ok() {
+            return;
+        }
+        *rotation = None;
+    }
+    std::future::pending().await
+}
+
+/// Resolves once the lease is lost; never resolves without a lease.
+async fn lease_lost(lease: &mut Option<LeaseGuard>) -> String {
+    match lease {
+        Some(guard) => guard.lost().await,
+        None => std::future::pending().await,
+    }
+}
+
 /// WebSocket connection manager with automatic reconnection
 pub struct WebSocketManager {
     client: AlpacaWebSocketClient,
@@ -923,6 +1514,30 @@ mod tests {
         assert!(client.url().contains("paper-api.alpaca.markets"));
     }
 
+    #[test]
+    fn test_with_client_config_urls() {
+        let credentials = Credentials::new("test_key".to_string(), "test_secret".to_string());
+        let config = ClientConfig::new(Environment::Paper)
+            .stream_url("ws://localhost:9000/stream")
+            .data_stream_url("ws://localhost:9001");
+        let data = AlpacaWebSocketClient::with_feed(
+            credentials.clone(),
+            Environment::Paper,
+            DataFeed::Sip,
+        )
+        .with_client_config(config.clone())
+        .unwrap();
+        assert_eq!(data.url(), "ws://localhost:9001/v2/sip");
+        let trading = AlpacaWebSocketClient::trading(credentials.clone(), Environment::Paper)
+            .with_client_config(config.clone())
+            .unwrap();
+        assert_eq!(trading.url(), "ws://localhost:9000/stream");
+        let custom = AlpacaWebSocketClient::with_url(credentials, Environment::Paper, "ws://mock")
+            .with_client_config(config)
+            .unwrap();
+        assert_eq!(custom.url(), "ws://mock");
+    }
+
     #[test]
     fn test_parse_message() {
         let json = r#"{"T":"success","msg":"authenticated"}"#;
@@ -939,22 +1554,27 @@ mod tests {
 
     #[test]
     fn test_frame_error() {
+        let message = |text: &str| frame_error(text).map(|e| e.to_string());
         assert_eq!(
-            frame_error(r#"[{"T":"error","code":402,"msg":"auth failed"}]"#),
-            Some("auth failed".to_string())
+            message(r#"[{"T":"error","code":402,"msg":"auth failed"}]"#),
+            Some("Authentication failed: auth failed".to_string())
         );
         assert_eq!(
-            frame_error(r#"{"T":"error","code":405,"msg":"symbol limit exceeded"}"#),
-            Some("symbol limit exceeded".to_string())
+            message(r#"{"T":"error","code":405,"msg":"symbol limit exceeded"}"#),
+            Some("Symbol limit exceeded: symbol limit exceeded".to_string())
         );
-        assert_eq!(frame_error(r#"[{"T":"success","msg":"connected"}]"#), None);
-        assert_eq!(frame_error("not json"), None);
+        assert!(matches!(
+            frame_error(r#"[{"T":"error","code":409,"msg":"insufficient subscription"}]"#),
+            Some(WebSocketError::InsufficientSubscription(_))
+        ));
+        assert_eq!(message(r#"[{"T":"success","msg":"connected"}]"#), None);
+        assert_eq!(message("not json"), None);
         assert_eq!(
-            frame_error(r#"{"stream":"authorization","data":{"status":"unauthorized"}}"#),
-            Some("authorization status: unauthorized".to_string())
+            message(r#"{"stream":"authorization","data":{"status":"unauthorized"}}"#),
+            Some("Authentication failed: authorization status: unauthorized".to_string())
         );
         assert_eq!(
-            frame_error(r#"{"stream":"authorization","data":{"status":"authorized"}}"#),
+            message(r#"{"stream":"authorization","data":{"status":"authorized"}}"#),
             None
         );
     }
@@ -972,6 +1592,7 @@ mod tests {
             position_qty: None,
             price: None,
             qty: None,
+            execution_id: None,
         };
         let text = serde_json::to_string(&WebSocketMessage::TradeUpdate(Box::new(update))).unwrap();
 
@@ -982,17 +1603,71 @@ mod tests {
         assert!(parse_trading_updates("not json").is_empty());
     }
 
+    #[test]
+    fn test_parse_account_updates() {
+        let frame = |cash: &str, status: &str| {
+            format!(
+                r#"{{"stream":"account_updates","data":{{"id":"ef505a9a-2f3c-4b8a-be95-6b6f185f8a03","created_at":"2024-01-02T14:30:00Z","updated_at":"2024-01-02T15:00:00Z","deleted_at":null,"status":"{status}","currency":"USD","cash":"{cash}","cash_withdrawable":"{cash}","buying_power":"2000"}}}}"#
+            )
+        };
+        let first = parse_account_updates(&frame("1000", "ACTIVE"));
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].cash(), Some(1000.0));
+        assert_eq!(first[0].buying_power(), Some(2000.0));
+
+        let second = parse_account_updates(&frame("750.5", "DISABLED"));
+        let changes = second[0].changes_since(&first[0]);
+        assert_eq!(
+            changes,
+            vec![
+                AccountChange::Cash {
+                    previous: 1000.0,
+                    current: 750.5
+                },
+                AccountChange::CashWithdrawable {
+                    previous: 1000.0,
+                    current: 750.5
+                },
+                AccountChange::Status {
+                    previous: alpaca_base::types::AccountStatus::Active,
+                    current: alpaca_base::types::AccountStatus::Disabled
+                },
+            ]
+        );
+        assert!(
+            parse_account_updates(
+                r#"{"stream":"listening","data":{"streams":["account_updates"]}}"#
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_news_articles() {
+        let frame = r#"[{"T":"subscription","news":["AAPL"]},{"T":"n","id":24918784,"headline":"Apple beats","summary":"","author":"Benzinga","created_at":"2024-01-09T14:30:00Z","updated_at":"2024-01-09T14:30:02Z","url":"https://example.com/a","content":"","symbols":["AAPL"],"source":"benzinga"}]"#;
+        let articles = parse_news_articles(frame);
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].id, 24918784);
+        assert_eq!(articles[0].headline, "Apple beats");
+        assert_eq!(articles[0].symbols, vec!["AAPL".to_string()]);
+        assert!(parse_news_articles("not json").is_empty());
+    }
+
     #[test]
     fn test_parse_market_data_updates() {
         let text = r#"[
             {"T":"t","S":"AAPL","t":"2026-07-13T10:00:00Z","p":190.5,"s":100,"x":"V","c":[],"i":1},
             {"T":"b","S":"AAPL","t":"2026-07-13T10:00:00Z","o":190.0,"h":191.0,"l":189.5,"c":190.5,"v":1000},
+            {"T":"o","S":"BTC/USD","t":"2026-07-13T10:00:00Z","b":[{"p":60000.0,"s":0.5}],"a":[],"r":true},
             {"T":"subscription","trades":["AAPL"]}
         ]"#;
         let updates = parse_market_data_updates(text);
-        assert_eq!(updates.len(), 2);
+        assert_eq!(updates.len(), 3);
         assert!(matches!(&updates[0], MarketDataUpdate::Trade { symbol, .. } if symbol == "AAPL"));
         assert!(matches!(&updates[1], MarketDataUpdate::Bar { symbol, .. } if symbol == "AAPL"));
+        assert!(
+            matches!(&updates[2], MarketDataUpdate::Orderbook { orderbook, .. } if orderbook.reset && orderbook.bids.len() == 1)
+        );
         assert!(parse_market_data_updates("not json").is_empty());
     }
 
@@ -1023,6 +1698,7 @@ mod tests {
             let credentials = Credentials::new("test_key".to_string(), "test_secret".to_string());
             let client = AlpacaWebSocketClient::with_feed(credentials, Environment::Paper, feed);
             assert_eq!(client.url(), expected_url);
+            assert_eq!(client.feed(), Some(feed));
         }
     }
 }
@@ -0,0 +1,500 @@
+//! Compatibility harness for validating a release against a live paper account.
+//!
+//! [`CompatibilityRunner`] executes a fixed battery of checks against the
+//! real Alpaca paper endpoints — account and clock reads, a market data
+//! fetch, a submit/cancel round trip on a limit order priced far away from
+//! the market, and trading / market data stream handshakes — and collects
+//! the outcome of each one into a [`CompatibilityReport`].
+//!
+//! The runner refuses to operate on live credentials: every client it
+//! builds targets [`Environment::Paper`].
+//!
+//! Only available with the `integration-tests` feature. The `alpaca-compat`
+//! binary wraps the runner for command-line use.
+
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use alpaca_base::{
+    AlpacaError, Credentials, Environment, OrderSide, OrderStatus, Result, TimeInForce,
+};
+use alpaca_http::{AlpacaHttpClient, CreateOrderRequest};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+use crate::client::AlpacaWebSocketClient;
+use crate::config::WebSocketConfig;
+use crate::messages::SubscriptionBuilder;
+
+/// Status reads after a cancel while waiting for it to take effect.
+const CANCEL_POLLS: u32 = 10;
+
+/// Pause between those reads.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Outcome of a single compatibility check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// The check ran and the API behaved as expected.
+    Passed,
+    /// The check ran and the API returned an error or unexpected data.
+    Failed,
+    /// The check was not run (disabled by configuration).
+    Skipped,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Passed => write!(f, "PASS"),
+            Self::Failed => write!(f, "FAIL"),
+            Self::Skipped => write!(f, "SKIP"),
+        }
+    }
+}
+
+/// Result of a single compatibility check.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    /// Short, stable check name (e.g. `"account"`).
+    pub name: String,
+    /// Outcome of the check.
+    pub status: CheckStatus,
+    /// Wall-clock duration of the check in milliseconds.
+    pub duration_ms: u64,
+    /// Human-readable detail: a summary on success, the error on failure.
+    pub detail: String,
+}
+
+impl CheckResult {
+    /// Create a passed check result.
+    #[must_use]
+    pub fn passed(name: &str, duration: Duration, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Passed, duration, detail)
+    }
+
+    /// Create a failed check result.
+    #[must_use]
+    pub fn failed(name: &str, duration: Duration, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Failed, duration, detail)
+    }
+
+    /// Create a skipped check result.
+    #[must_use]
+    pub fn skipped(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Skipped, Duration::ZERO, detail)
+    }
+
+    fn new(name: &str, status: CheckStatus, duration: Duration, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Report produced by a [`CompatibilityRunner`] run.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatibilityReport {
+    /// Version of `alpaca-websocket` that produced the report.
+    pub crate_version: String,
+    /// Time the run started.
+    pub started_at: DateTime<Utc>,
+    /// Time the run finished.
+    pub finished_at: DateTime<Utc>,
+    /// Per-check results, in execution order.
+    pub checks: Vec<CheckResult>,
+}
+
+impl CompatibilityReport {
+    /// Create an empty report stamped with the current time.
+    #[must_use]
+    pub fn new() -> Self {
+        let now = Utc::now();
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: now,
+            finished_at: now,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Record a check result.
+    pub fn push(&mut self, result: CheckResult) {
+        self.checks.push(result);
+    }
+
+    /// Number of checks with the given status.
+    #[must_use]
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    /// Whether no check failed. Skipped checks do not affect compatibility.
+    #[must_use]
+    pub fn is_compatible(&self) -> bool {
+        self.count(CheckStatus::Failed) == 0
+    }
+
+    /// Serialize the report as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Render the report as a Markdown table.
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# alpaca-rs compatibility report\n\n\
+             - version: {}\n- started: {}\n- finished: {}\n- result: {} \
+             ({} passed, {} failed, {} skipped)\n\n\
+             | check | status | duration (ms) | detail |\n\
+             |-------|--------|---------------|--------|\n",
+            self.crate_version,
+            self.started_at.to_rfc3339(),
+            self.finished_at.to_rfc3339(),
+            if self.is_compatible() {
+                "compatible"
+            } else {
+                "incompatible"
+            },
+            self.count(CheckStatus::Passed),
+            self.count(CheckStatus::Failed),
+            self.count(CheckStatus::Skipped),
+        );
+        for check in &self.checks {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                check.name,
+                check.status,
+                check.duration_ms,
+                check.detail.replace('|', "\\|")
+            ));
+        }
+        out
+    }
+}
+
+impl Default for CompatibilityReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_markdown())
+    }
+}
+
+/// Configuration for a [`CompatibilityRunner`].
+#[derive(Debug, Clone)]
+pub struct CompatibilityConfig {
+    /// Liquid symbol used for data fetches, stream subscriptions and the
+    /// order round trip.
+    pub symbol: String,
+    /// Limit price for the round-trip buy order. It should sit far below
+    /// the market so the order never fills before it is canceled.
+    pub order_limit_price: String,
+    /// Whether to run the order submit/cancel round trip.
+    pub orders_enabled: bool,
+    /// Whether to run the stream handshake checks.
+    pub streams_enabled: bool,
+    /// Upper bound for any single check.
+    pub check_timeout: Duration,
+}
+
+impl Default for CompatibilityConfig {
+    fn default() -> Self {
+        Self {
+            symbol: "SPY".to_string(),
+            order_limit_price: "1.00".to_string(),
+            orders_enabled: true,
+            streams_enabled: true,
+            check_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+impl CompatibilityConfig {
+    /// Create a configuration with default values.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the symbol used by data, stream and order checks.
+    #[must_use]
+    pub fn symbol(mut self, symbol: &str) -> Self {
+        self.symbol = symbol.to_string();
+        self
+    }
+
+    /// Set the limit price of the round-trip order.
+    #[must_use]
+    pub fn order_limit_price(mut self, price: &str) -> Self {
+        self.order_limit_price = price.to_string();
+        self
+    }
+
+    /// Enable or disable the order round trip.
+    #[must_use]
+    pub fn orders_enabled(mut self, enabled: bool) -> Self {
+        self.orders_enabled = enabled;
+        self
+    }
+
+    /// Enable or disable the stream handshake checks.
+    #[must_use]
+    pub fn streams_enabled(mut self, enabled: bool) -> Self {
+        self.streams_enabled = enabled;
+        self
+    }
+
+    /// Set the per-check timeout.
+    #[must_use]
+    pub fn check_timeout(mut self, timeout: Duration) -> Self {
+        self.check_timeout = timeout;
+        self
+    }
+}
+
+/// Runs the compatibility battery against a paper trading account.
+#[derive(Debug)]
+pub struct CompatibilityRunner {
+    credentials: Credentials,
+    http: AlpacaHttpClient,
+    config: CompatibilityConfig,
+}
+
+impl CompatibilityRunner {
+    /// Create a runner for the given credentials, always targeting paper.
+    pub fn new(credentials: Credentials, config: CompatibilityConfig) -> Result<Self> {
+        let http = AlpacaHttpClient::new(credentials.clone(), Environment::Paper)?;
+        Ok(Self {
+            credentials,
+            http,
+            config,
+        })
+    }
+
+    /// Create a runner from `ALPACA_API_KEY` / `ALPACA_API_SECRET`.
+    pub fn from_env(config: CompatibilityConfig) -> Result<Self> {
+        Self::new(Credentials::from_env()?, config)
+    }
+
+    /// Run every check and return the report. Individual failures are
+    /// recorded in the report rather than aborting the run.
+    pub async fn run(&self) -> CompatibilityReport {
+        let mut report = CompatibilityReport::new();
+
+        report.push(
+            self.check("account", async {
+                let account = self.http.get_account().await?;
+                Ok(format!("status {:?}", account.status))
+            })
+            .await,
+        );
+
+        report.push(
+            self.check("clock", async {
+                let clock = self.http.get_clock().await?;
+                Ok(format!("market open: {}", clock.is_open))
+            })
+            .await,
+        );
+
+        report.push(
+            self.check("latest_bar", async {
+                let latest = self.http.get_latest_bar(&self.config.symbol).await?;
+                Ok(format!("{} close {}", latest.symbol, latest.bar.close))
+            })
+            .await,
+        );
+
+        if self.config.orders_enabled {
+            let client_order_id = format!("alpaca-compat-{}", Uuid::new_v4());
+            let mut result = self
+                .check("order_round_trip", self.order_round_trip(&client_order_id))
+                .await;
+            if result.status == CheckStatus::Failed
+                && let Err(e) = self.cancel_leftover(&client_order_id).await
+            {
+                result.detail = format!("{}; cleanup failed: {e}", result.detail);
+            }
+            report.push(result);
+        } else {
+            report.push(CheckResult::skipped("order_round_trip", "disabled"));
+        }
+
+        if self.config.streams_enabled {
+            report.push(
+                self.check("trading_stream", async {
+                    let client = AlpacaWebSocketClient::trading(
+                        self.credentials.clone(),
+                        Environment::Paper,
+                    );
+                    client
+                        .subscribe_trading_updates_with_config(self.stream_config())
+                        .await?;
+                    Ok("authenticated".to_string())
+                })
+                .await,
+            );
+            report.push(
+                self.check("market_data_stream", async {
+                    let client =
+                        AlpacaWebSocketClient::new(self.credentials.clone(), Environment::Paper);
+                    let subscription = SubscriptionBuilder::new()
+                        .trades([self.config.symbol.as_str()])
+                        .build();
+                    client
+                        .subscribe_market_data_with_config(subscription, self.stream_config())
+                        .await?;
+                    Ok(format!("subscribed to {}", self.config.symbol))
+                })
+                .await,
+            );
+        } else {
+            report.push(CheckResult::skipped("trading_stream", "disabled"));
+            report.push(CheckResult::skipped("market_data_stream", "disabled"));
+        }
+
+        report.finished_at = Utc::now();
+        report
+    }
+
+    /// Submit a far-from-market limit buy, read it back, cancel it, and
+    /// wait for the order to reach [`OrderStatus::Canceled`]. The order is
+    /// canceled even if reading it back fails; [`Self::cancel_leftover`]
+    /// cleans up after a timeout.
+    async fn order_round_trip(&self, client_order_id: &str) -> Result<String> {
+        let request = CreateOrderRequest::limit(
+            &self.config.symbol,
+            OrderSide::Buy,
+            "1",
+            &self.config.order_limit_price,
+        )
+        .time_in_force(TimeInForce::Day)
+        .client_order_id(client_order_id);
+        let order = self.http.create_order(&request).await?;
+
+        let fetched = match self.http.get_order(&order.id).await {
+            Ok(fetched) if fetched.id != order.id => Err(AlpacaError::InvalidData(format!(
+                "get_order returned {} for {}",
+                fetched.id, order.id
+            ))),
+            result => result.map(|_| ()),
+        };
+        // Cancel before reporting a failed read-back so the test order
+        // never stays on the book.
+        let canceled = self.http.cancel_order(&order.id).await;
+        fetched?;
+        canceled?;
+
+        let mut status = self.http.get_order(&order.id).await?.status;
+        for _ in 0..CANCEL_POLLS {
+            if status == OrderStatus::Canceled {
+                break;
+            }
+            tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+            status = self.http.get_order(&order.id).await?.status;
+        }
+        if status != OrderStatus::Canceled {
+            return Err(AlpacaError::InvalidData(format!(
+                "order {} is {:?} after cancel, expected Canceled",
+                order.id, status
+            )));
+        }
+        Ok(format!("order {} submitted and canceled", order.id))
+    }
+
+    /// Cancel the round-trip order if it is still open, without a timeout,
+    /// so a check that timed out mid-flight leaves nothing on the book.
+    async fn cancel_leftover(&self, client_order_id: &str) -> Result<()> {
+        let order = match self.http.get_order_by_client_id(client_order_id).await {
+            Ok(order) => order,
+            // Never created.
+            Err(e) if e.is_not_found() => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if matches!(
+            order.status,
+            OrderStatus::Canceled
+                | OrderStatus::Filled
+                | OrderStatus::Expired
+                | OrderStatus::Rejected
+                | OrderStatus::Replaced
+        ) {
+            return Ok(());
+        }
+        match self.http.cancel_order(&order.id).await {
+            Err(e) if !e.is_not_found() => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Stream config for handshake checks: fail fast instead of retrying.
+    fn stream_config(&self) -> WebSocketConfig {
+        let timeout_ms = u64::try_from(self.config.check_timeout.as_millis()).unwrap_or(u64::MAX);
+        WebSocketConfig::new()
+            .no_reconnect()
+            .connection_timeout(timeout_ms)
+    }
+
+    /// Run a check under the configured timeout and time it.
+    async fn check<F>(&self, name: &str, fut: F) -> CheckResult
+    where
+        F: Future<Output = Result<String>>,
+    {
+        let started = Instant::now();
+        match timeout(self.config.check_timeout, fut).await {
+            Ok(Ok(detail)) => CheckResult::passed(name, started.elapsed(), detail),
+            Ok(Err(e)) => CheckResult::failed(name, started.elapsed(), e.to_string()),
+            Err(_) => CheckResult::failed(
+                name,
+                started.elapsed(),
+                format!("timed out after {:?}", self.config.check_timeout),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_compatibility() {
+        let mut report = CompatibilityReport::new();
+        report.push(CheckResult::passed(
+            "account",
+            Duration::from_millis(12),
+            "ok",
+        ));
+        report.push(CheckResult::skipped("order_round_trip", "disabled"));
+        assert!(report.is_compatible());
+
+        report.push(CheckResult::failed(
+            "clock",
+            Duration::from_millis(5),
+            "a|b",
+        ));
+        assert!(!report.is_compatible());
+        assert_eq!(report.count(CheckStatus::Passed), 1);
+        assert_eq!(report.count(CheckStatus::Skipped), 1);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| clock | FAIL | 5 | a\\|b |"));
+        assert!(markdown.contains("incompatible"));
+
+        let json = report.to_json().unwrap();
+        assert!(json.contains("\"status\": \"failed\""));
+    }
+}
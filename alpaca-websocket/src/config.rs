@@ -1,5 +1,11 @@
 //! WebSocket configuration types.
 
+use crate::backpressure::BackpressurePolicy;
+use crate::lease::LeaseConfig;
+use crate::messages::Codec;
+use alpaca_base::{Backoff, Instrumentation, Jitter};
+use std::time::Duration;
+
 /// Configuration for WebSocket connections.
 #[derive(Debug, Clone)]
 pub struct WebSocketConfig {
@@ -11,12 +17,28 @@ pub struct WebSocketConfig {
     pub reconnect_base_delay_ms: u64,
     /// Maximum delay between reconnection attempts in milliseconds.
     pub reconnect_max_delay_ms: u64,
+    /// Jitter applied to reconnection delays.
+    pub reconnect_jitter: Jitter,
     /// Interval for sending ping messages in milliseconds.
     pub ping_interval_ms: u64,
     /// Size of the message buffer.
     pub message_buffer_size: usize,
+    /// What data updates do while the message buffer is full.
+    pub backpressure: BackpressurePolicy,
     /// Connection timeout in milliseconds.
     pub connection_timeout_ms: u64,
+    /// Connection lease coordinating the stream slot across processes.
+    /// `None` connects without a lease.
+    pub lease: Option<LeaseConfig>,
+    /// Hooks receiving every frame sent or received, redacted. `None`
+    /// reports nothing.
+    pub instrumentation: Option<Instrumentation>,
+    /// Wire encoding of market data frames. News and trading streams
+    /// always use JSON.
+    pub codec: Codec,
+    /// Maximum distinct symbols per market data connection, as set by the
+    /// data plan. `None` leaves enforcement to the server.
+    pub symbol_limit: Option<usize>,
 }
 
 impl Default for WebSocketConfig {
@@ -26,9 +48,15 @@ impl Default for WebSocketConfig {
             reconnect_max_attempts: 10,
             reconnect_base_delay_ms: 1000,
             reconnect_max_delay_ms: 60000,
+            reconnect_jitter: Jitter::None,
             ping_interval_ms: 30000,
             message_buffer_size: 1000,
+            backpressure: BackpressurePolicy::DropNewest,
             connection_timeout_ms: 10000,
+            lease: None,
+            instrumentation: None,
+            codec: Codec::Json,
+            symbol_limit: None,
         }
     }
 }
@@ -61,6 +89,24 @@ impl WebSocketConfig {
         self
     }
 
+    /// Set the jitter applied to reconnection delays, e.g.
+    /// [`Jitter::Full`] to spread out many clients reconnecting at once.
+    #[must_use]
+    pub fn reconnect_jitter(mut self, jitter: Jitter) -> Self {
+        self.reconnect_jitter = jitter;
+        self
+    }
+
+    /// The reconnection delay schedule.
+    #[must_use]
+    pub fn reconnect_backoff(&self) -> Backoff {
+        Backoff::new(
+            Duration::from_millis(self.reconnect_base_delay_ms),
+            Duration::from_millis(self.reconnect_max_delay_ms),
+        )
+        .jitter(self.reconnect_jitter)
+    }
+
     /// Set ping interval in milliseconds.
     #[must_use]
     pub fn ping_interval(mut self, interval_ms: u64) -> Self {
@@ -75,12 +121,53 @@ impl WebSocketConfig {
         self
     }
 
+    /// Set what data updates do while the message buffer is full, e.g.
+    /// [`BackpressurePolicy::CoalesceQuotes`] for quote-heavy
+    /// subscriptions.
+    #[must_use]
+    pub fn backpressure(mut self, policy: BackpressurePolicy) -> Self {
+        self.backpressure = policy;
+        self
+    }
+
     /// Set connection timeout in milliseconds.
     #[must_use]
     pub fn connection_timeout(mut self, timeout_ms: u64) -> Self {
         self.connection_timeout_ms = timeout_ms;
         self
     }
+
+    /// Hold a connection lease while the stream is open.
+    #[must_use]
+    pub fn with_lease(mut self, lease: LeaseConfig) -> Self {
+        self.lease = Some(lease);
+        self
+    }
+
+    /// Report every frame sent or received to the hooks of
+    /// `instrumentation`.
+    #[must_use]
+    pub fn with_instrumentation(mut self, instrumentation: Instrumentation) -> Self {
+        self.instrumentation = Some(instrumentation);
+        self
+    }
+
+    /// Set the wire encoding of market data frames.
+    #[must_use]
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Reject subscriptions streaming more than `limit` distinct symbols
+    /// on one connection, e.g. [`BASIC_PLAN_SYMBOL_LIMIT`].
+    ///
+    /// [`BASIC_PLAN_SYMBOL_LIMIT`]: crate::subscription::BASIC_PLAN_SYMBOL_LIMIT
+    #[must_use]
+    pub fn symbol_limit(mut self, limit: usize) -> Self {
+        self.symbol_limit = Some(limit);
+        self
+    }
 }
 
 /// WebSocket stream type.
@@ -157,12 +244,14 @@ mod tests {
             .no_reconnect()
             .max_reconnect_attempts(5)
             .ping_interval(15000)
-            .buffer_size(500);
+            .buffer_size(500)
+            .backpressure(BackpressurePolicy::DropOldest);
 
         assert!(!config.reconnect_enabled);
         assert_eq!(config.reconnect_max_attempts, 5);
         assert_eq!(config.ping_interval_ms, 15000);
         assert_eq!(config.message_buffer_size, 500);
+        assert_eq!(config.backpressure, BackpressurePolicy::DropOldest);
     }
 
     #[test]
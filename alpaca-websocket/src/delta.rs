@@ -0,0 +1,494 @@
+//! Delta-compressed quote board snapshots for UI clients.
+//!
+//! Dashboards fed by a market data stream rarely need every trade and quote;
+//! they need the latest value of a handful of fields per symbol, refreshed at
+//! a fixed tick. [`DeltaEncoder`] folds [`MarketDataUpdate`]s into a per-symbol
+//! [`SymbolSnapshot`] and, on each flush, emits a [`DeltaFrame`] carrying only
+//! the fields that changed since the previous frame. [`DeltaApplier`] rebuilds
+//! the full board on the consumer side.
+//!
+//! # Protocol
+//!
+//! Frames serialize to compact JSON using Alpaca's short field names:
+//!
+//! ```json
+//! {"seq":7,"k":"delta","s":{"AAPL":{"bp":190.1,"as":300}}}
+//! ```
+//!
+//! - `seq` increases by one per delta frame; a full frame repeats the `seq`
+//!   of the frame it snapshots.
+//! - `k` is `"full"` for a complete board (always the first frame) or
+//!   `"delta"` for changed fields only; omitted fields are unchanged.
+//! - A consumer that sees a gap in `seq` must discard its state and wait for
+//!   (or request) the next full frame; [`DeltaApplier::apply`] reports gaps as
+//!   errors.
+
+use crate::streams::MarketDataUpdate;
+use alpaca_base::{AlpacaError, Result};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tokio::time::{MissedTickBehavior, interval};
+
+/// Latest known fields for one symbol. In a delta frame, `None` means
+/// "unchanged".
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SymbolSnapshot {
+    /// Best bid price.
+    #[serde(rename = "bp", skip_serializing_if = "Option::is_none")]
+    pub bid_price: Option<f64>,
+    /// Best bid size.
+    #[serde(rename = "bs", skip_serializing_if = "Option::is_none")]
+    pub bid_size: Option<u32>,
+    /// Best ask price.
+    #[serde(rename = "ap", skip_serializing_if = "Option::is_none")]
+    pub ask_price: Option<f64>,
+    /// Best ask size.
+    #[serde(rename = "as", skip_serializing_if = "Option::is_none")]
+    pub ask_size: Option<u32>,
+    /// Last trade price.
+    #[serde(rename = "p", skip_serializing_if = "Option::is_none")]
+    pub last_price: Option<f64>,
+    /// Last trade size.
+    #[serde(rename = "s", skip_serializing_if = "Option::is_none")]
+    pub last_size: Option<u32>,
+    /// Close of the latest bar.
+    #[serde(rename = "c", skip_serializing_if = "Option::is_none")]
+    pub bar_close: Option<f64>,
+    /// Volume of the latest bar.
+    #[serde(rename = "v", skip_serializing_if = "Option::is_none")]
+    pub bar_volume: Option<u64>,
+    /// Timestamp of the latest update.
+    #[serde(rename = "t", skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+impl SymbolSnapshot {
+    /// Fold a market data update into the snapshot.
+    pub fn update(&mut self, update: &MarketDataUpdate) {
+        match update {
+            MarketDataUpdate::Quote { quote, .. } => {
+                self.bid_price = Some(quote.bid_price);
+                self.bid_size = Some(quote.bid_size);
+                self.ask_price = Some(quote.ask_price);
+                self.ask_size = Some(quote.ask_size);
+                self.timestamp = Some(quote.timestamp);
+            }
+            MarketDataUpdate::Trade { trade, .. } => {
+                self.last_price = Some(trade.price);
+                self.last_size = Some(trade.size);
+                self.timestamp = Some(trade.timestamp);
+            }
+            MarketDataUpdate::Bar { bar, .. } => {
+                self.bar_close = Some(bar.close);
+                self.bar_volume = Some(bar.volume);
+                self.timestamp = Some(bar.timestamp);
+            }
+            MarketDataUpdate::Orderbook { .. } => {}
+        }
+    }
+
+    /// Fields of `self` that differ from `previous`, or `None` if nothing
+    /// changed.
+    #[must_use]
+    pub fn diff(&self, previous: &Self) -> Option<Self> {
+        fn changed<T: PartialEq + Copy>(new: Option<T>, old: Option<T>) -> Option<T> {
+            if new != old { new } else { None }
+        }
+        let delta = Self {
+            bid_price: changed(self.bid_price, previous.bid_price),
+            bid_size: changed(self.bid_size, previous.bid_size),
+            ask_price: changed(self.ask_price, previous.ask_price),
+            ask_size: changed(self.ask_size, previous.ask_size),
+            last_price: changed(self.last_price, previous.last_price),
+            last_size: changed(self.last_size, previous.last_size),
+            bar_close: changed(self.bar_close, previous.bar_close),
+            bar_volume: changed(self.bar_volume, previous.bar_volume),
+            timestamp: changed(self.timestamp, previous.timestamp),
+        };
+        (delta != Self::default()).then_some(delta)
+    }
+
+    /// Overwrite fields with every `Some` field of `delta`.
+    pub fn merge(&mut self, delta: &Self) {
+        fn set<T: Copy>(field: &mut Option<T>, value: Option<T>) {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+        set(&mut self.bid_price, delta.bid_price);
+        set(&mut self.bid_size, delta.bid_size);
+        set(&mut self.ask_price, delta.ask_price);
+        set(&mut self.ask_size, delta.ask_size);
+        set(&mut self.last_price, delta.last_price);
+        set(&mut self.last_size, delta.last_size);
+        set(&mut self.bar_close, delta.bar_close);
+        set(&mut self.bar_volume, delta.bar_volume);
+        set(&mut self.timestamp, delta.timestamp);
+    }
+}
+
+/// Kind of a [`DeltaFrame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeltaFrameKind {
+    /// Complete board; replaces any consumer state.
+    Full,
+    /// Changed fields only; merged into consumer state.
+    Delta,
+}
+
+/// One tick of the delta protocol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaFrame {
+    /// Frame sequence number, starting at 1. Full frames share the number
+    /// of the last frame published before them.
+    pub seq: u64,
+    /// Frame kind.
+    #[serde(rename = "k")]
+    pub kind: DeltaFrameKind,
+    /// Per-symbol fields (all fields for full frames, changed fields for
+    /// delta frames).
+    #[serde(rename = "s")]
+    pub symbols: BTreeMap<String, SymbolSnapshot>,
+}
+
+impl DeltaFrame {
+    /// Serialize the frame as compact JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parse a frame from JSON.
+    pub fn from_json(text: &str) -> Result<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+}
+
+/// Producer side: accumulates updates and emits delta frames on flush.
+#[derive(Debug, Clone, Default)]
+pub struct DeltaEncoder {
+    current: HashMap<String, SymbolSnapshot>,
+    published: HashMap<String, SymbolSnapshot>,
+    seq: u64,
+}
+
+impl DeltaEncoder {
+    /// Create an empty encoder.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold a market data update into the current board.
+    pub fn apply(&mut self, update: &MarketDataUpdate) {
+        let symbol = match update {
+            MarketDataUpdate::Trade { symbol, .. }
+            | MarketDataUpdate::Quote { symbol, .. }
+            | MarketDataUpdate::Bar { symbol, .. } => symbol,
+            // Depth is not part of the snapshot board.
+            MarketDataUpdate::Orderbook { .. } => return,
+        };
+        self.current
+            .entry(symbol.clone())
+            .or_default()
+            .update(update);
+    }
+
+    /// Emit the fields changed since the previous frame, or `None` when
+    /// nothing changed. The first frame produced is always a full frame.
+    pub fn flush(&mut self) -> Option<DeltaFrame> {
+        if self.seq == 0 {
+            if self.current.is_empty() {
+                return None;
+            }
+            self.published = self.current.clone();
+            self.seq = 1;
+            return Some(self.full_frame());
+        }
+        let symbols: BTreeMap<String, SymbolSnapshot> = self
+            .current
+            .iter()
+            .filter_map(|(symbol, snapshot)| {
+                let previous = self.published.get(symbol).cloned().unwrap_or_default();
+                snapshot
+                    .diff(&previous)
+                    .map(|delta| (symbol.clone(), delta))
+            })
+            .collect();
+        if symbols.is_empty() {
+            return None;
+        }
+        self.published = self.current.clone();
+        self.seq += 1;
+        Some(DeltaFrame {
+            seq: self.seq,
+            kind: DeltaFrameKind::Delta,
+            symbols,
+        })
+    }
+
+    /// Emit the complete board as of the last frame, e.g. for a newly
+    /// connected consumer or after a consumer reports a sequence gap.
+    ///
+    /// The snapshot carries the current `seq` without advancing it, so
+    /// consumers already in sync see no gap and the resyncing consumer
+    /// applies the next delta on top of it. Updates applied since the last
+    /// flush arrive in that delta.
+    #[must_use]
+    pub fn full_frame(&self) -> DeltaFrame {
+        DeltaFrame {
+            seq: self.seq,
+            kind: DeltaFrameKind::Full,
+            symbols: self.published.clone().into_iter().collect(),
+        }
+    }
+}
+
+/// Consumer side: rebuilds the board from a sequence of frames.
+#[derive(Debug, Clone, Default)]
+pub struct DeltaApplier {
+    state: HashMap<String, SymbolSnapshot>,
+    last_seq: Option<u64>,
+}
+
+impl DeltaApplier {
+    /// Create an empty applier.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a frame.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::InvalidData`] if a delta frame arrives before
+    /// any full frame or out of sequence. The caller should then resync
+    /// from the next full frame; state is left unchanged.
+    pub fn apply(&mut self, frame: &DeltaFrame) -> Result<()> {
+        match frame.kind {
+            DeltaFrameKind::Full => {
+                self.state = frame.symbols.clone().into_iter().collect();
+            }
+            DeltaFrameKind::Delta => {
+                let expected = self.last_seq.map(|seq| seq + 1);
+                if expected != Some(frame.seq) {
+                    return Err(AlpacaError::InvalidData(format!(
+                        "delta frame out of sequence: expected {:?}, got {}; resync required",
+                        expected, frame.seq
+                    )));
+                }
+                for (symbol, delta) in &frame.symbols {
+                    self.state.entry(symbol.clone()).or_default().merge(delta);
+                }
+            }
+        }
+        self.last_seq = Some(frame.seq);
+        Ok(())
+    }
+
+    /// Current fields for a symbol.
+    #[must_use]
+    pub fn get(&self, symbol: &str) -> Option<&SymbolSnapshot> {
+        self.state.get(symbol)
+    }
+
+    /// The whole reconstructed board.
+    #[must_use]
+    pub fn board(&self) -> &HashMap<String, SymbolSnapshot> {
+        &self.state
+    }
+
+    /// Sequence number of the last applied frame.
+    #[must_use]
+    pub fn last_seq(&self) -> Option<u64> {
+        self.last_seq
+    }
+}
+
+/// Convert a stream of market data updates into delta frames emitted at
+/// most once per `tick`. Ticks without changes emit nothing; a final frame
+/// is flushed when the input stream ends.
+pub fn delta_frames<S>(updates: S, tick: Duration) -> impl Stream<Item = DeltaFrame> + Unpin
+where
+    S: Stream<Item = MarketDataUpdate> + Unpin,
+{
+    Box::pin(stream::unfold(
+        Some((updates, DeltaEncoder::new(), None)),
+        move |state| async move {
+            let (mut updates, mut encoder, ticker) = state?;
+            // Created on first poll: `interval` needs a running runtime.
+            let mut ticker = ticker.unwrap_or_else(|| {
+                let mut ticker = interval(tick);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                ticker
+            });
+            loop {
+                tokio::select! {
+                    update = updates.next() => match update {
+                        Some(update) => encoder.apply(&update),
+                        None => return encoder.flush().map(|frame| (frame, None)),
+                    },
+                    _ = ticker.tick() => {
+                        if let Some(frame) = encoder.flush() {
+                            return Some((frame, Some((updates, encoder, Some(ticker)))));
+                        }
+                    }
+                }
+            }
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::{quote_with, trade_with};
+    use alpaca_base::types::{Quote, Trade};
+
+    fn quote(symbol: &str, bid: f64, ask: f64) -> MarketDataUpdate {
+        MarketDataUpdate::Quote {
+            symbol: symbol.to_string(),
+            quote: Quote {
+                timestamp: DateTime::UNIX_EPOCH,
+                ..quote_with(bid, 100, ask, 100)
+            },
+        }
+    }
+
+    fn trade(symbol: &str, price: f64) -> MarketDataUpdate {
+        MarketDataUpdate::Trade {
+            symbol: symbol.to_string(),
+            trade: Trade {
+                timestamp: DateTime::UNIX_EPOCH,
+                ..trade_with(price, 10)
+            },
+        }
+    }
+
+    #[test]
+    fn test_encoder_emits_only_changed_fields() {
+        let mut encoder = DeltaEncoder::new();
+        assert!(encoder.flush().is_none());
+
+        encoder.apply(&quote("AAPL", 190.0, 190.1));
+        let first = encoder.flush().unwrap();
+        assert_eq!(first.kind, DeltaFrameKind::Full);
+        assert_eq!(first.seq, 1);
+        assert!(encoder.flush().is_none());
+
+        encoder.apply(&quote("AAPL", 190.05, 190.1));
+        encoder.apply(&trade("MSFT", 410.0));
+        let delta = encoder.flush().unwrap();
+        assert_eq!(delta.kind, DeltaFrameKind::Delta);
+        assert_eq!(delta.seq, 2);
+        assert_eq!(
+            delta.symbols["AAPL"],
+            SymbolSnapshot {
+                bid_price: Some(190.05),
+                ..Default::default()
+            }
+        );
+        assert_eq!(delta.symbols["MSFT"].last_price, Some(410.0));
+        assert_eq!(
+            delta.to_json().unwrap(),
+            r#"{"seq":2,"k":"delta","s":{"AAPL":{"bp":190.05},"MSFT":{"p":410.0,"s":10,"t":"1970-01-01T00:00:00Z"}}}"#
+        );
+    }
+
+    #[test]
+    fn test_applier_rebuilds_board_and_detects_gaps() {
+        let mut encoder = DeltaEncoder::new();
+        let mut applier = DeltaApplier::new();
+
+        encoder.apply(&quote("AAPL", 190.0, 190.1));
+        let full = encoder.flush().unwrap();
+        encoder.apply(&quote("AAPL", 189.9, 190.0));
+        let delta = encoder.flush().unwrap();
+        encoder.apply(&trade("AAPL", 189.95));
+        let later = encoder.flush().unwrap();
+
+        assert!(applier.apply(&delta).is_err());
+        applier
+            .apply(&DeltaFrame::from_json(&full.to_json().unwrap()).unwrap())
+            .unwrap();
+        assert!(applier.apply(&later).is_err());
+        applier.apply(&delta).unwrap();
+        applier.apply(&later).unwrap();
+
+        let aapl = applier.get("AAPL").unwrap();
+        assert_eq!(aapl.bid_price, Some(189.9));
+        assert_eq!(aapl.ask_price, Some(190.0));
+        assert_eq!(aapl.last_price, Some(189.95));
+        assert_eq!(applier.last_seq(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_delta_frames_stream() {
+        let updates = stream::iter(vec![
+            quote("AAPL", 190.0, 190.1),
+            trade("AAPL", 190.05),
+            quote("MSFT", 410.0, 410.2),
+        ]);
+        let frames: Vec<DeltaFrame> = delta_frames(updates, Duration::from_millis(5))
+            .collect()
+            .await;
+
+        assert_eq!(frames[0].kind, DeltaFrameKind::Full);
+        let mut applier = DeltaApplier::new();
+        for frame in &frames {
+            applier.apply(frame).unwrap();
+        }
+        assert_eq!(applier.get("AAPL").unwrap().last_price, Some(190.05));
+        assert_eq!(applier.get("MSFT").unwrap().ask_price, Some(410.2));
+    }
+
+    #[test]
+    fn test_full_frame_keeps_seq() {
+        let mut encoder = DeltaEncoder::new();
+        encoder.apply(&quote("AAPL", 190.0, 190.1));
+        encoder.flush().unwrap();
+        encoder.apply(&quote("AAPL", 189.9, 190.0));
+        let delta = encoder.flush().unwrap();
+        encoder.apply(&trade("AAPL", 189.95));
+
+        // A snapshot for a late joiner must not open a gap for consumers
+        // already in sync.
+        let snapshot = encoder.full_frame();
+        assert_eq!(snapshot.kind, DeltaFrameKind::Full);
+        assert_eq!(snapshot.seq, delta.seq);
+        assert_eq!(snapshot.symbols["AAPL"].last_price, None);
+        assert_eq!(encoder.full_frame().seq, delta.seq);
+
+        let mut in_sync = DeltaApplier::new();
+        in_sync
+            .apply(&DeltaFrame {
+                seq: 1,
+                ..snapshot.clone()
+            })
+            .unwrap();
+        in_sync.apply(&delta).unwrap();
+        let mut joiner = DeltaApplier::new();
+        joiner.apply(&snapshot).unwrap();
+
+        let next = encoder.flush().unwrap();
+        assert_eq!(next.seq, delta.seq + 1);
+        in_sync.apply(&next).unwrap();
+        joiner.apply(&next).unwrap();
+        assert_eq!(in_sync.board(), joiner.board());
+    }
+
+    #[test]
+    fn test_delta_frames_outside_runtime() {
+        let frames = delta_frames(
+            stream::iter(vec![trade("AAPL", 1.0)]),
+            Duration::from_millis(5),
+        );
+        let frames: Vec<DeltaFrame> = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(frames.collect());
+        assert_eq!(frames.len(), 1);
+    }
+}
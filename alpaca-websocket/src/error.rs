@@ -1,7 +1,14 @@
 //! WebSocket-specific error types.
+//!
+//! Data-stream error frames (`{"T":"error","code":...}`) map to
+//! [`WebSocketError`] variants with [`WebSocketError::from_code`], so
+//! callers can act on them: back off on a connection limit, drop symbols
+//! on a symbol limit, or fall back to a feed the plan includes
+//! ([`WebSocketError::fallback_feed`]).
 
 #![allow(missing_docs)]
 
+use crate::client::DataFeed;
 use alpaca_base::AlpacaError;
 use thiserror::Error;
 
@@ -43,4 +50,139 @@ pub enum WebSocketError {
     /// Reconnection failed
     #[error("Reconnection failed after {attempts} attempts")]
     ReconnectionFailed { attempts: u32 },
+
+    /// More symbols subscribed than the plan allows (405)
+    #[error("Symbol limit exceeded: {0}")]
+    SymbolLimitExceeded(String),
+
+    /// Another connection already uses the account's stream slot (406)
+    #[error("Connection limit exceeded: {0}")]
+    ConnectionLimitExceeded(String),
+
+    /// The client did not read messages fast enough (407)
+    #[error("Slow client: {0}")]
+    SlowClient(String),
+
+    /// The plan does not include the requested feed, e.g. SIP (409)
+    #[error("Insufficient subscription: {0}")]
+    InsufficientSubscription(String),
+
+    /// The server rejected a malformed or invalid request (400, 403, 410)
+    #[error("Invalid request ({code}): {message}")]
+    InvalidRequest { code: u16, message: String },
+
+    /// The server failed internally (500)
+    #[error("Server error ({code}): {message}")]
+    Server { code: u16, message: String },
+}
+
+impl WebSocketError {
+    /// Map a data-stream error frame's code and message.
+    pub fn from_code(code: u16, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match code {
+            401 | 402 | 404 => Self::AuthenticationFailed(message),
+            405 => Self::SymbolLimitExceeded(message),
+            406 => Self::ConnectionLimitExceeded(message),
+            407 => Self::SlowClient(message),
+            409 => Self::InsufficientSubscription(message),
+            400..=499 => Self::InvalidRequest { code, message },
+            _ => Self::Server { code, message },
+        }
+    }
+
+    /// Recover the stream error behind an [`AlpacaError`] returned by a
+    /// connect or subscribe call, from the server's message texts.
+    pub fn classify(error: &AlpacaError) -> Option<Self> {
+        let message = match error {
+            AlpacaError::ConnectionLimitExceeded(message) => {
+                return Some(Self::ConnectionLimitExceeded(message.clone()));
+            }
+            AlpacaError::WebSocket(message) | AlpacaError::Auth(message) => message,
+            _ => return None,
+        };
+        let lower = message.to_ascii_lowercase();
+        let message = message.clone();
+        if lower.contains("insufficient subscription") {
+            Some(Self::InsufficientSubscription(message))
+        } else if lower.contains("connection limit exceeded") {
+            Some(Self::ConnectionLimitExceeded(message))
+        } else if lower.contains("symbol limit exceeded") {
+            Some(Self::SymbolLimitExceeded(message))
+        } else if lower.contains("slow client") {
+            Some(Self::SlowClient(message))
+        } else if lower.contains("authentication failed") {
+            Some(Self::AuthenticationFailed(message))
+        } else {
+            None
+        }
+    }
+
+    /// Whether reconnecting as is may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Connection(_)
+                | Self::ConnectionClosed(_)
+                | Self::SlowClient(_)
+                | Self::Server { .. }
+        )
+    }
+
+    /// The feed to retry with when `feed` is not included in the plan:
+    /// IEX, which every plan includes, for SIP and the overnight feeds.
+    pub fn fallback_feed(&self, feed: DataFeed) -> Option<DataFeed> {
+        match (self, feed) {
+            (
+                Self::InsufficientSubscription(_),
+                DataFeed::Sip | DataFeed::Boats | DataFeed::Overnight,
+            ) => Some(DataFeed::Iex),
+            _ => None,
+        }
+    }
+}
+
+impl From<WebSocketError> for AlpacaError {
+    fn from(err: WebSocketError) -> Self {
+        match err {
+            WebSocketError::Base(e) => e,
+            WebSocketError::ConnectionLimitExceeded(message) => {
+                AlpacaError::ConnectionLimitExceeded(message)
+            }
+            other => AlpacaError::WebSocket(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_error_from_code_and_fallback() {
+        let error = WebSocketError::from_code(409, "insufficient subscription");
+        assert!(matches!(error, WebSocketError::InsufficientSubscription(_)));
+        assert_eq!(error.fallback_feed(DataFeed::Sip), Some(DataFeed::Iex));
+        assert_eq!(error.fallback_feed(DataFeed::Iex), None);
+        assert!(!error.is_retryable());
+        assert!(matches!(
+            WebSocketError::from_code(402, "auth failed"),
+            WebSocketError::AuthenticationFailed(_)
+        ));
+        assert!(matches!(
+            WebSocketError::from_code(410, "invalid subscribe action for this feed"),
+            WebSocketError::InvalidRequest { code: 410, .. }
+        ));
+        assert!(WebSocketError::from_code(500, "internal error").is_retryable());
+
+        // Round trip through the AlpacaError returned by subscribe calls.
+        let returned = AlpacaError::WebSocket(format!("subscription failed: {error}"));
+        assert!(matches!(
+            WebSocketError::classify(&returned),
+            Some(WebSocketError::InsufficientSubscription(_))
+        ));
+        let limit = AlpacaError::from(WebSocketError::from_code(406, "connection limit exceeded"));
+        assert!(matches!(limit, AlpacaError::ConnectionLimitExceeded(_)));
+        assert!(WebSocketError::classify(&AlpacaError::Http("x".to_string())).is_none());
+    }
 }
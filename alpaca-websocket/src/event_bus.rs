@@ -0,0 +1,343 @@
+//! One typed event stream over every streaming source.
+//!
+//! Applications consuming several streams otherwise juggle a
+//! [`TradingStream`](crate::TradingStream), a
+//! [`MarketDataStream`](crate::MarketDataStream), Broker API SSE events and,
+//! with the `fix` feature, FIX execution reports, each with its own item
+//! type. [`EventBus::attach`] forwards any of them into one [`BusEvent`]
+//! channel, and [`EventBus::subscribe`] hands out receivers filtered by
+//! [`Topic`] and, optionally, symbol:
+//!
+//! ```rust,ignore
+//! let bus = EventBus::new();
+//! bus.attach(ws.subscribe_trading_updates().await?);
+//! bus.attach(ws.subscribe_market_data(subscription).await?);
+//! let mut fills = bus.subscribe(&[Topic::TradeUpdates, Topic::FixExecutions]);
+//! while let Some(event) = fills.recv().await {
+//!     // ...
+//! }
+//! ```
+//!
+//! Each subscriber buffers up to the bus capacity; a subscriber that falls
+//! further behind receives [`BusEvent::Lagged`] and skips ahead. Lifecycle
+//! events of the sources (reconnects, lag, subscription changes) are not
+//! forwarded. Dropping the bus stops forwarding.
+
+use crate::messages::TradeUpdateMessage;
+use crate::streams::{MarketDataEvent, MarketDataUpdate, TradingEvent};
+use alpaca_base::types::BrokerSseEvent;
+use futures_util::stream::{Stream, StreamExt};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use tokio::task::AbortHandle;
+
+/// Default number of events buffered per subscriber.
+pub const DEFAULT_BUS_CAPACITY: usize = 4096;
+
+/// Kind of event on an [`EventBus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// Order updates from the trading stream.
+    TradeUpdates,
+    /// Trades, quotes, bars and order books.
+    MarketData,
+    /// Broker API account, transfer, journal, trade and activity events.
+    BrokerEvents,
+    /// FIX execution reports.
+    FixExecutions,
+}
+
+/// An event from any source attached to an [`EventBus`].
+#[derive(Debug, Clone)]
+pub enum BusEvent {
+    /// An order update from the trading stream.
+    TradeUpdate(Box<TradeUpdateMessage>),
+    /// A market data update.
+    MarketData(MarketDataUpdate),
+    /// A Broker API SSE event.
+    Broker(Box<BrokerSseEvent>),
+    /// A FIX execution report.
+    #[cfg(feature = "fix")]
+    FixExecution(Box<alpaca_fix::ExecutionReport>),
+    /// This subscriber fell behind and `missed` events were skipped.
+    Lagged {
+        /// Number of events skipped.
+        missed: u64,
+    },
+}
+
+impl BusEvent {
+    /// The topic of the event; `None` for [`BusEvent::Lagged`].
+    #[must_use]
+    pub fn topic(&self) -> Option<Topic> {
+        match self {
+            Self::TradeUpdate(_) => Some(Topic::TradeUpdates),
+            Self::MarketData(_) => Some(Topic::MarketData),
+            Self::Broker(_) => Some(Topic::BrokerEvents),
+            #[cfg(feature = "fix")]
+            Self::FixExecution(_) => Some(Topic::FixExecutions),
+            Self::Lagged { .. } => None,
+        }
+    }
+
+    /// The symbol the event is about, if any.
+    #[must_use]
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Self::TradeUpdate(update) => Some(&update.order.symbol),
+            Self::MarketData(update) => Some(update.symbol()),
+            Self::Broker(event) => match event.as_ref() {
+                BrokerSseEvent::Trade(trade) => Some(&trade.symbol),
+                BrokerSseEvent::NonTradeActivity(activity) => activity.symbol.as_deref(),
+                _ => None,
+            },
+            #[cfg(feature = "fix")]
+            Self::FixExecution(report) => Some(&report.symbol),
+            Self::Lagged { .. } => None,
+        }
+    }
+}
+
+/// Items that can be published on an [`EventBus`].
+pub trait IntoBusEvent {
+    /// The bus event for this item, or `None` to skip it.
+    fn into_bus_event(self) -> Option<BusEvent>;
+}
+
+impl IntoBusEvent for BusEvent {
+    fn into_bus_event(self) -> Option<BusEvent> {
+        Some(self)
+    }
+}
+
+impl IntoBusEvent for TradingEvent {
+    fn into_bus_event(self) -> Option<BusEvent> {
+        match self {
+            Self::Update(update) => Some(BusEvent::TradeUpdate(update)),
+            _ => None,
+        }
+    }
+}
+
+impl IntoBusEvent for MarketDataEvent {
+    fn into_bus_event(self) -> Option<BusEvent> {
+        match self {
+            Self::Update(update) => Some(BusEvent::MarketData(update)),
+            _ => None,
+        }
+    }
+}
+
+impl IntoBusEvent for BrokerSseEvent {
+    fn into_bus_event(self) -> Option<BusEvent> {
+        Some(BusEvent::Broker(Box::new(self)))
+    }
+}
+
+#[cfg(feature = "fix")]
+impl IntoBusEvent for alpaca_fix::ExecutionReport {
+    fn into_bus_event(self) -> Option<BusEvent> {
+        Some(BusEvent::FixExecution(Box::new(self)))
+    }
+}
+
+/// Merges streaming sources into one typed, topic-filtered event channel.
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<BusEvent>,
+    tasks: Mutex<Vec<AbortHandle>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    /// Create a bus buffering up to [`DEFAULT_BUS_CAPACITY`] events per
+    /// subscriber.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_BUS_CAPACITY)
+    }
+
+    /// Create a bus buffering up to `capacity` events per subscriber.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            sender,
+            tasks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Forward every item of `stream` until it ends or the bus is dropped.
+    pub fn attach<S>(&self, stream: S)
+    where
+        S: Stream + Send + Unpin + 'static,
+        S::Item: IntoBusEvent,
+    {
+        let sender = self.sender.clone();
+        let task = tokio::spawn(async move {
+            let mut stream = stream;
+            while let Some(item) = stream.next().await {
+                if let Some(event) = item.into_bus_event() {
+                    // No subscribers is not an error: events are dropped.
+                    let _ = sender.send(event);
+                }
+            }
+        });
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task.abort_handle());
+    }
+
+    /// Publish `item` to current subscribers, returning how many received
+    /// it.
+    pub fn publish(&self, item: impl IntoBusEvent) -> usize {
+        item.into_bus_event()
+            .and_then(|event| self.sender.send(event).ok())
+            .unwrap_or(0)
+    }
+
+    /// Receive events of `topics` published from now on. An empty slice
+    /// receives every topic.
+    #[must_use]
+    pub fn subscribe(&self, topics: &[Topic]) -> BusSubscription {
+        BusSubscription {
+            receiver: self.sender.subscribe(),
+            topics: topics.iter().copied().collect(),
+            symbols: None,
+        }
+    }
+
+    /// Number of current subscribers.
+    #[must_use]
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Drop for EventBus {
+    fn drop(&mut self) {
+        let tasks = self.tasks.get_mut().unwrap_or_else(|e| e.into_inner());
+        for task in tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+/// Receiver of the [`EventBus`] events matching a set of topics.
+#[derive(Debug)]
+pub struct BusSubscription {
+    receiver: broadcast::Receiver<BusEvent>,
+    topics: HashSet<Topic>,
+    symbols: Option<HashSet<String>>,
+}
+
+impl BusSubscription {
+    /// Only receive events about `symbols`. Events not about any symbol,
+    /// e.g. account status changes, are still received.
+    #[must_use]
+    pub fn symbols(mut self, symbols: &[&str]) -> Self {
+        self.symbols = Some(symbols.iter().map(|s| s.to_string()).collect());
+        self
+    }
+
+    /// The next matching event, or `None` once the bus is dropped.
+    pub async fn recv(&mut self) -> Option<BusEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.matches(&event) => return Some(event),
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    return Some(BusEvent::Lagged { missed });
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Turn the subscription into a [`Stream`].
+    pub fn into_stream(self) -> impl Stream<Item = BusEvent> + Send + Unpin {
+        Box::pin(futures_util::stream::unfold(
+            self,
+            |mut subscription| async {
+                let event = subscription.recv().await?;
+                Some((event, subscription))
+            },
+        ))
+    }
+
+    fn matches(&self, event: &BusEvent) -> bool {
+        let topic_matches = event
+            .topic()
+            .is_none_or(|topic| self.topics.is_empty() || self.topics.contains(&topic));
+        let symbol_matches = match (&self.symbols, event.symbol()) {
+            (Some(symbols), Some(symbol)) => symbols.contains(symbol),
+            _ => true,
+        };
+        topic_matches && symbol_matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::quote_with;
+    use alpaca_base::types::AccountStatusEvent;
+
+    fn quote(symbol: &str) -> MarketDataEvent {
+        MarketDataEvent::Update(MarketDataUpdate::Quote {
+            symbol: symbol.to_string(),
+            quote: quote_with(100.0, 1, 100.1, 1),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_event_bus_filters_topics_and_symbols() {
+        let bus = EventBus::new();
+        let mut market = bus.subscribe(&[Topic::MarketData]).symbols(&["AAPL"]);
+        let mut broker = bus.subscribe(&[Topic::BrokerEvents]);
+        let mut all = bus.subscribe(&[]);
+
+        bus.attach(futures_util::stream::iter(vec![
+            quote("MSFT"),
+            MarketDataEvent::Lagged { missed: 3 },
+            quote("AAPL"),
+        ]));
+        let account: AccountStatusEvent = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "account_id": "acct",
+            "event_type": "ACCOUNT_UPDATED",
+            "at": "2024-06-03T14:30:00Z"
+        }))
+        .unwrap();
+
+        let event = market.recv().await.unwrap();
+        assert_eq!(event.symbol(), Some("AAPL"));
+        assert_eq!(bus.publish(BrokerSseEvent::AccountStatus(account)), 3);
+        // Not about a symbol, so the symbol filter lets it through, but
+        // the topic filter does not.
+        assert!(matches!(broker.recv().await, Some(BusEvent::Broker(_))));
+
+        let topics: Vec<_> = [
+            all.recv().await.unwrap(),
+            all.recv().await.unwrap(),
+            all.recv().await.unwrap(),
+        ]
+        .iter()
+        .map(BusEvent::topic)
+        .collect();
+        assert_eq!(
+            topics,
+            [
+                Some(Topic::MarketData),
+                Some(Topic::MarketData),
+                Some(Topic::BrokerEvents)
+            ]
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! Connection leases for coordinating stream connections across processes.
+//!
+//! Alpaca allows one market data connection per account and feed. When
+//! several processes on a host share credentials they would otherwise kick
+//! each other off in a loop. A [`ConnectionLease`] backend arbitrates the
+//! slot: a stream acquires the lease before connecting, renews it while
+//! connected, and releases it when dropped.
+//!
+//! - If the lease is held by another live holder the connection fails with
+//!   [`AlpacaError::ConnectionLimitExceeded`].
+//! - With [`LeaseConfig::takeover`] enabled the new holder overwrites the
+//!   lease; the previous holder notices on its next renewal and its stream
+//!   ends with a `Disconnected` event instead of reconnecting.
+//! - A lease not renewed within its TTL (e.g. its process crashed) is stale
+//!   and can be acquired by anyone.
+//!
+//! [`FileLease`] coordinates processes on one host through lock files and
+//! [`MemoryLease`] coordinates clients within one process. Other backends
+//! (e.g. Redis) implement [`ConnectionLease`].
+
+use alpaca_base::{AlpacaError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Current owner of a lease.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaseRecord {
+    /// Unique holder identifier.
+    pub holder: String,
+    /// Process ID of the holder.
+    pub pid: u32,
+    /// When the lease expires unless renewed.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl LeaseRecord {
+    /// Create a record for `holder` expiring `ttl` from now.
+    #[must_use]
+    pub fn new(holder: &str, ttl: Duration) -> Self {
+        Self {
+            holder: holder.to_string(),
+            pid: std::process::id(),
+            expires_at: Utc::now()
+                + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX),
+        }
+    }
+
+    /// Whether the lease has expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// Pluggable lease backend.
+pub trait ConnectionLease: fmt::Debug + Send + Sync {
+    /// Acquire the lease for `key` on behalf of `holder`.
+    ///
+    /// Succeeds when the lease is free, expired, already held by `holder`,
+    /// or `takeover` is set.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::ConnectionLimitExceeded`] when another live
+    /// holder owns the lease and `takeover` is not set.
+    fn acquire(&self, key: &str, holder: &str, ttl: Duration, takeover: bool) -> Result<()>;
+
+    /// Extend the lease. Returns `false` if `holder` no longer owns it.
+    fn renew(&self, key: &str, holder: &str, ttl: Duration) -> Result<bool>;
+
+    /// Release the lease if `holder` still owns it.
+    fn release(&self, key: &str, holder: &str) -> Result<()>;
+
+    /// Current owner of the lease, if any (expired records included).
+    fn current(&self, key: &str) -> Result<Option<LeaseRecord>>;
+}
+
+/// Error for a lease held by someone else.
+fn held_by(record: &LeaseRecord) -> AlpacaError {
+    AlpacaError::ConnectionLimitExceeded(format!(
+        "connection lease held by {} (pid {}) until {}",
+        record.holder,
+        record.pid,
+        record.expires_at.to_rfc3339()
+    ))
+}
+
+/// In-process lease backend.
+#[derive(Debug, Default)]
+pub struct MemoryLease {
+    records: Mutex<HashMap<String, LeaseRecord>>,
+}
+
+impl MemoryLease {
+    /// Create an empty in-memory backend.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn records(&self) -> std::sync::MutexGuard<'_, HashMap<String, LeaseRecord>> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ConnectionLease for MemoryLease {
+    fn acquire(&self, key: &str, holder: &str, ttl: Duration, takeover: bool) -> Result<()> {
+        let mut records = self.records();
+        if let Some(record) = records.get(key)
+            && record.holder != holder
+            && !record.is_expired()
+            && !takeover
+        {
+            return Err(held_by(record));
+        }
+        records.insert(key.to_string(), LeaseRecord::new(holder, ttl));
+        Ok(())
+    }
+
+    fn renew(&self, key: &str, holder: &str, ttl: Duration) -> Result<bool> {
+        let mut records = self.records();
+        match records.get_mut(key) {
+            Some(record) if record.holder == holder => {
+                *record = LeaseRecord::new(holder, ttl);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn release(&self, key: &str, holder: &str) -> Result<()> {
+        let mut records = self.records();
+        if records.get(key).is_some_and(|r| r.holder == holder) {
+            records.remove(key);
+        }
+        Ok(())
+    }
+
+    fn current(&self, key: &str) -> Result<Option<LeaseRecord>> {
+        Ok(self.records().get(key).cloned())
+    }
+}
+
+/// Lock-file lease backend for processes sharing a host.
+///
+/// Each lease is a JSON file in `dir`, replaced atomically via a temp file
+/// and rename. Acquisitions, renewals and releases hold an exclusive lock
+/// on a sibling `.lock` file while they check and replace the record, so a
+/// takeover cannot land between a renewal's read and write.
+#[derive(Debug, Clone)]
+pub struct FileLease {
+    dir: PathBuf,
+}
+
+impl FileLease {
+    /// Create a backend storing lock files in `dir` (created if missing).
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Backend using `alpaca-rs-leases` in the system temp directory.
+    #[must_use]
+    pub fn in_temp_dir() -> Self {
+        Self::new(std::env::temp_dir().join("alpaca-rs-leases"))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.lease", Self::file_name(key)))
+    }
+
+    /// Run `f` holding the exclusive lock for `key`.
+    fn locked<T>(&self, key: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        fs::create_dir_all(&self.dir).map_err(io_error)?;
+        let lock = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.dir.join(format!("{}.lock", Self::file_name(key))))
+            .map_err(io_error)?;
+        lock.lock().map_err(io_error)?;
+        // Unlocked when `lock` is closed.
+        f()
+    }
+
+    fn file_name(key: &str) -> String {
+        let name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        name
+    }
+
+    fn read(&self, key: &str) -> Result<Option<LeaseRecord>> {
+        match fs::read_to_string(self.path(key)) {
+            Ok(text) => Ok(serde_json::from_str(&text).ok()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    /// Replace the lease file atomically via a temp file and rename.
+    fn write(&self, key: &str, record: &LeaseRecord) -> Result<()> {
+        let path = self.path(key);
+        let tmp = path.with_extension(format!("lease.{}.tmp", record.pid));
+        fs::write(&tmp, serde_json::to_vec(record)?).map_err(io_error)?;
+        fs::rename(&tmp, &path).map_err(io_error)
+    }
+}
+
+fn io_error(e: std::io::Error) -> AlpacaError {
+    AlpacaError::Config(format!("connection lease i/o error: {e}"))
+}
+
+impl ConnectionLease for FileLease {
+    fn acquire(&self, key: &str, holder: &str, ttl: Duration, takeover: bool) -> Result<()> {
+        self.locked(key, || {
+            if let Some(existing) = self.read(key)?
+                && existing.holder != holder
+                && !existing.is_expired()
+                && !takeover
+            {
+                return Err(held_by(&existing));
+            }
+            self.write(key, &LeaseRecord::new(holder, ttl))
+        })
+    }
+
+    fn renew(&self, key: &str, holder: &str, ttl: Duration) -> Result<bool> {
+        self.locked(key, || match self.read(key)? {
+            Some(record) if record.holder == holder => {
+                self.write(key, &LeaseRecord::new(holder, ttl))?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        })
+    }
+
+    fn release(&self, key: &str, holder: &str) -> Result<()> {
+        self.locked(key, || {
+            if self.read(key)?.is_some_and(|r| r.holder == holder) {
+                match fs::remove_file(self.path(key)) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(io_error(e)),
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn current(&self, key: &str) -> Result<Option<LeaseRecord>> {
+        self.read(key)
+    }
+}
+
+/// Lease settings for a stream, see [`crate::WebSocketConfig::lease`].
+#[derive(Debug, Clone)]
+pub struct LeaseConfig {
+    /// Lease backend.
+    pub backend: Arc<dyn ConnectionLease>,
+    /// Lease time-to-live; renewed every third of this interval.
+    pub ttl: Duration,
+    /// Take the lease over from a live holder instead of failing.
+    pub takeover: bool,
+}
+
+impl LeaseConfig {
+    /// Create lease settings with a 30 second TTL and no takeover.
+    #[must_use]
+    pub fn new(backend: Arc<dyn ConnectionLease>) -> Self {
+        Self {
+            backend,
+            ttl: Duration::from_secs(30),
+            takeover: false,
+        }
+    }
+
+    /// Set the lease TTL.
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Enable or disable takeover of a live lease.
+    #[must_use]
+    pub fn takeover(mut self, takeover: bool) -> Self {
+        self.takeover = takeover;
+        self
+    }
+}
+
+/// Lease key for a stream URL and API key. The API key is hashed so it
+/// never appears in lock file names or shared stores; the SHA-256 digest
+/// is the same in every process and build.
+#[must_use]
+pub fn lease_key(url: &str, api_key: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(api_key.as_bytes())
+        .chain_update([0u8])
+        .chain_update(url.as_bytes())
+        .finalize();
+    let mut key = String::from("alpaca-ws-");
+    for byte in &digest[..16] {
+        let _ = write!(key, "{byte:02x}");
+    }
+    key
+}
+
+/// A held lease. Renews in the background and releases on drop.
+pub(crate) struct LeaseGuard {
+    backend: Arc<dyn ConnectionLease>,
+    key: String,
+    holder: String,
+    renew_task: JoinHandle<()>,
+    lost: oneshot::Receiver<String>,
+}
+
+impl LeaseGuard {
+    /// Acquire the lease for `key` and start renewing it.
+    pub(crate) fn acquire(config: &LeaseConfig, key: String) -> Result<Self> {
+        let holder = format!("{}-{}", std::process::id(), uuid::Uuid::new_v4());
+        config
+            .backend
+            .acquire(&key, &holder, config.ttl, config.takeover)?;
+        debug!("Acquired connection lease {} as {}", key, holder);
+
+        let (lost_tx, lost) = oneshot::channel();
+        let renew_task = {
+            let (backend, key, holder, ttl) = (
+                Arc::clone(&config.backend),
+                key.clone(),
+                holder.clone(),
+                config.ttl,
+            );
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval((ttl / 3).max(Duration::from_millis(1)));
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    // Backends may block on file or network i/o.
+                    let renewal = {
+                        let (backend, key, holder) =
+                            (Arc::clone(&backend), key.clone(), holder.clone());
+                        tokio::task::spawn_blocking(move || {
+                            backend.renew(&key, &holder, ttl).map(|renewed| {
+                                (!renewed).then(|| backend.current(&key).ok().flatten())
+                            })
+                        })
+                        .await
+                    };
+                    match renewal {
+                        Ok(Ok(None)) => {}
+                        Ok(Ok(Some(owner))) => {
+                            let owner = owner.map_or_else(|| "nobody".to_string(), |r| r.holder);
+                            let _ = lost_tx.send(format!("connection lease taken over by {owner}"));
+                            return;
+                        }
+                        Ok(Err(e)) => warn!("Failed to renew connection lease: {}", e),
+                        Err(e) => warn!("Connection lease renewal task failed: {}", e),
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            backend: Arc::clone(&config.backend),
+            key,
+            holder,
+            renew_task,
+            lost,
+        })
+    }
+
+    /// Resolves with a reason once the lease is lost to another holder.
+    pub(crate) async fn lost(&mut self) -> String {
+        match (&mut self.lost).await {
+            Ok(reason) => reason,
+            Err(_) => std::future::pending().await,
+        }
+    }
+}
+
+impl Drop for LeaseGuard {
+    fn drop(&mut self) {
+        self.renew_task.abort();
+        if let Err(e) = self.backend.release(&self.key, &self.holder) {
+            warn!("Failed to release connection lease: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(backend: &dyn ConnectionLease) {
+        let ttl = Duration::from_secs(30);
+        backend.acquire("k", "a", ttl, false).unwrap();
+        backend.acquire("k", "a", ttl, false).unwrap();
+
+        let err = backend.acquire("k", "b", ttl, false).unwrap_err();
+        assert!(matches!(err, AlpacaError::ConnectionLimitExceeded(_)));
+
+        backend.acquire("k", "b", ttl, true).unwrap();
+        assert!(!backend.renew("k", "a", ttl).unwrap());
+        assert!(backend.renew("k", "b", ttl).unwrap());
+
+        backend.release("k", "a").unwrap();
+        assert_eq!(backend.current("k").unwrap().unwrap().holder, "b");
+        backend.release("k", "b").unwrap();
+        assert!(backend.current("k").unwrap().is_none());
+
+        backend.acquire("k", "a", Duration::ZERO, false).unwrap();
+        backend.acquire("k", "b", ttl, false).unwrap();
+        backend.release("k", "b").unwrap();
+    }
+
+    #[test]
+    fn test_memory_lease() {
+        exercise(&MemoryLease::new());
+    }
+
+    #[test]
+    fn test_file_lease() {
+        let dir = std::env::temp_dir().join(format!("alpaca-lease-test-{}", uuid::Uuid::new_v4()));
+        let backend = FileLease::new(&dir);
+        exercise(&backend);
+
+        // A takeover racing a renewal loop is never overwritten.
+        let ttl = Duration::from_secs(30);
+        backend.acquire("race", "a", ttl, false).unwrap();
+        let renewer = {
+            let backend = backend.clone();
+            std::thread::spawn(move || while backend.renew("race", "a", ttl).unwrap() {})
+        };
+        std::thread::sleep(Duration::from_millis(5));
+        backend.acquire("race", "b", ttl, true).unwrap();
+        renewer.join().unwrap();
+        assert_eq!(backend.current("race").unwrap().unwrap().holder, "b");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_lease_key_hides_api_key() {
+        let key = lease_key("wss://stream.data.alpaca.markets/v2/iex", "PKSECRETKEY");
+        // Stable across processes and builds.
+        assert_eq!(key, "alpaca-ws-c1aea075fdc99362a71da1078bb59e54");
+        assert_ne!(
+            key,
+            lease_key("wss://stream.data.alpaca.markets/v2/sip", "PKSECRETKEY")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_guard_reports_takeover_and_releases() {
+        let backend: Arc<dyn ConnectionLease> = Arc::new(MemoryLease::new());
+        let config = LeaseConfig::new(Arc::clone(&backend)).ttl(Duration::from_millis(30));
+
+        let mut guard = LeaseGuard::acquire(&config, "k".to_string()).unwrap();
+        assert!(LeaseGuard::acquire(&config, "k".to_string()).is_err());
+
+        let other = LeaseGuard::acquire(&config.clone().takeover(true), "k".to_string()).unwrap();
+        let reason = tokio::time::timeout(Duration::from_secs(1), guard.lost())
+            .await
+            .unwrap();
+        assert!(reason.contains("taken over"));
+
+        drop(guard);
+        assert!(backend.current("k").unwrap().is_some());
+        drop(other);
+        assert!(backend.current("k").unwrap().is_none());
+    }
+}
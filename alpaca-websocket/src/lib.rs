@@ -2,16 +2,55 @@
 //!
 //! WebSocket client for Alpaca trading platform real-time data.
 //! This crate provides real-time market data and trading updates via WebSocket connections.
+//!
+//! [`prelude`] re-exports the stable, commonly used types. Experimental
+//! modules ([`tape`]) require the `unstable` feature and may change in
+//! minor releases.
 
+pub mod aggregated_book;
+pub mod backpressure;
+pub mod bar_backfill;
 pub mod client;
+#[cfg(feature = "integration-tests")]
+pub mod compat;
 pub mod config;
+pub mod delta;
 pub mod error;
+pub mod event_bus;
+pub mod lease;
 pub mod messages;
+pub mod news_feed;
+pub mod order_book;
+pub mod prelude;
+pub mod quote_analytics;
+pub mod sharding;
 pub mod streams;
+pub mod subscription;
+#[cfg(feature = "unstable")]
+pub mod tape;
+mod transport;
 
+pub use aggregated_book::{AggregatedBook, Aggressor, QuoteSample, TradeSample};
 pub use alpaca_base::*;
+pub use backpressure::BackpressurePolicy;
+pub use bar_backfill::BarStreamWithBackfill;
 pub use client::{AlpacaWebSocketClient, DataFeed};
 pub use config::{ConnectionState, StreamType, WebSocketConfig};
+pub use delta::{
+    DeltaApplier, DeltaEncoder, DeltaFrame, DeltaFrameKind, SymbolSnapshot, delta_frames,
+};
 pub use error::WebSocketError;
+pub use event_bus::{BusEvent, BusSubscription, EventBus, IntoBusEvent, Topic};
+pub use lease::{ConnectionLease, FileLease, LeaseConfig, LeaseRecord, MemoryLease, lease_key};
 pub use messages::*;
+pub use news_feed::NewsFeed;
+pub use order_book::{
+    BookChange, BookSide, CHECKSUM_LEVELS, LevelChange, OrderBook, OrderBooks, PriceLevel,
+    ResyncReason,
+};
+pub use quote_analytics::{NbboChanges, QuoteAnalytics, SymbolQuoteStats};
+pub use sharding::{ShardEvent, ShardedStreamManager};
 pub use streams::*;
+pub use subscription::{BASIC_PLAN_SYMBOL_LIMIT, Channel, SubscriptionError};
+#[cfg(feature = "unstable")]
+pub use tape::{Tape, TapeEvent, TapeMerger};
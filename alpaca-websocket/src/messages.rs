@@ -2,9 +2,17 @@
 
 #![allow(missing_docs)]
 
+use crate::streams::{MarketDataUpdate, Subscriptions};
 use alpaca_base::types::*;
+use alpaca_base::{AlpacaError, Result};
+#[cfg(feature = "unstable")]
+use alpaca_base::{Execution, ExecutionKind, PortfolioFill};
 use chrono::{DateTime, Utc};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::debug;
 
 /// WebSocket message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +70,9 @@ pub struct SubscribeMessage {
     pub quotes: Option<Vec<String>>,
     pub bars: Option<Vec<String>>,
     pub trade_updates: Option<bool>,
+    /// Crypto order book symbols (crypto feeds only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orderbooks: Option<Vec<String>>,
 }
 
 /// Unsubscription message
@@ -78,7 +89,7 @@ pub struct UnsubscribeMessage {
 pub struct TradeMessage {
     #[serde(rename = "S")]
     pub symbol: String,
-    #[serde(rename = "t")]
+    #[serde(rename = "t", deserialize_with = "wire_timestamp")]
     pub timestamp: DateTime<Utc>,
     #[serde(rename = "p")]
     pub price: f64,
@@ -97,7 +108,7 @@ pub struct TradeMessage {
 pub struct QuoteMessage {
     #[serde(rename = "S")]
     pub symbol: String,
-    #[serde(rename = "t")]
+    #[serde(rename = "t", deserialize_with = "wire_timestamp")]
     pub timestamp: DateTime<Utc>,
     #[serde(rename = "bp")]
     pub bid_price: f64,
@@ -118,7 +129,7 @@ pub struct QuoteMessage {
 pub struct BarMessage {
     #[serde(rename = "S")]
     pub symbol: String,
-    #[serde(rename = "t")]
+    #[serde(rename = "t", deserialize_with = "wire_timestamp")]
     pub timestamp: DateTime<Utc>,
     #[serde(rename = "o")]
     pub open: f64,
@@ -145,6 +156,65 @@ pub struct TradeUpdateMessage {
     pub position_qty: Option<String>,
     pub price: Option<String>,
     pub qty: Option<String>,
+    /// Execution ID of the fill.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
+}
+
+#[cfg(feature = "unstable")]
+impl TradeUpdateMessage {
+    /// The fill this update reports, for a [`PortfolioTracker`] (requires
+    /// `unstable` feature).
+    ///
+    /// [`PortfolioTracker`]: alpaca_base::PortfolioTracker
+    pub fn portfolio_fill(&self) -> Option<PortfolioFill> {
+        if !matches!(
+            self.event,
+            TradeUpdateEvent::Fill | TradeUpdateEvent::PartialFill
+        ) {
+            return None;
+        }
+        Some(PortfolioFill {
+            symbol: self.order.symbol.clone(),
+            side: self.order.side.clone(),
+            qty: self.qty.as_deref()?.parse().ok()?,
+            price: self.price.as_deref()?.parse().ok()?,
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl From<&TradeUpdateMessage> for Execution {
+    fn from(update: &TradeUpdateMessage) -> Self {
+        let parse = |value: Option<&str>| value.and_then(|v| v.parse::<f64>().ok());
+        let kind = match update.event {
+            TradeUpdateEvent::New => ExecutionKind::Accepted,
+            TradeUpdateEvent::Fill | TradeUpdateEvent::PartialFill => ExecutionKind::Fill,
+            TradeUpdateEvent::Canceled => ExecutionKind::Canceled,
+            TradeUpdateEvent::Expired => ExecutionKind::Expired,
+            TradeUpdateEvent::Replaced => ExecutionKind::Replaced,
+            TradeUpdateEvent::Rejected => ExecutionKind::Rejected,
+            _ => ExecutionKind::Other,
+        };
+        let fill = kind == ExecutionKind::Fill;
+        Execution {
+            client_order_id: update.order.client_order_id.clone(),
+            order_id: Some(update.order.id.to_string()),
+            exec_id: update.execution_id.clone(),
+            kind,
+            last_qty: parse(update.qty.as_deref()),
+            last_price: parse(update.price.as_deref()),
+            cum_qty: fill
+                .then(|| parse(Some(&update.order.filled_qty)))
+                .flatten(),
+            avg_price: fill
+                .then(|| parse(update.order.filled_avg_price.as_deref()))
+                .flatten(),
+            text: None,
+            timestamp: update.timestamp,
+        }
+    }
 }
 
 /// Trade update event types
@@ -169,6 +239,93 @@ pub enum TradeUpdateEvent {
     OrderCancelPending,
 }
 
+/// Account update from the trading stream's `account_updates` channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountUpdateMessage {
+    /// Account ID.
+    pub id: uuid::Uuid,
+    /// Account creation time.
+    pub created_at: DateTime<Utc>,
+    /// Time of this change.
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Deletion time, if the account was deleted.
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Account status.
+    pub status: AccountStatus,
+    /// Account currency.
+    pub currency: String,
+    /// Cash balance.
+    pub cash: String,
+    /// Withdrawable cash.
+    pub cash_withdrawable: String,
+    /// Buying power, when the update carries it.
+    #[serde(default)]
+    pub buying_power: Option<String>,
+}
+
+/// A field that changed between two account updates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountChange {
+    /// The cash balance changed.
+    Cash { previous: f64, current: f64 },
+    /// Withdrawable cash changed.
+    CashWithdrawable { previous: f64, current: f64 },
+    /// Buying power changed.
+    BuyingPower { previous: f64, current: f64 },
+    /// The account status changed, e.g. the account was disabled.
+    Status {
+        previous: AccountStatus,
+        current: AccountStatus,
+    },
+}
+
+impl AccountUpdateMessage {
+    /// Cash balance as a number.
+    #[must_use]
+    pub fn cash(&self) -> Option<f64> {
+        self.cash.parse().ok()
+    }
+
+    /// Buying power as a number, when the update carries it.
+    #[must_use]
+    pub fn buying_power(&self) -> Option<f64> {
+        self.buying_power.as_deref().and_then(|v| v.parse().ok())
+    }
+
+    /// What changed since `previous`.
+    #[must_use]
+    pub fn changes_since(&self, previous: &Self) -> Vec<AccountChange> {
+        let number = |value: &str| value.parse::<f64>().ok();
+        let mut changes = Vec::new();
+        if let (Some(previous), Some(current)) = (number(&previous.cash), number(&self.cash))
+            && previous != current
+        {
+            changes.push(AccountChange::Cash { previous, current });
+        }
+        if let (Some(previous), Some(current)) = (
+            number(&previous.cash_withdrawable),
+            number(&self.cash_withdrawable),
+        ) && previous != current
+        {
+            changes.push(AccountChange::CashWithdrawable { previous, current });
+        }
+        if let (Some(previous), Some(current)) = (previous.buying_power(), self.buying_power())
+            && previous != current
+        {
+            changes.push(AccountChange::BuyingPower { previous, current });
+        }
+        if previous.status != self.status {
+            changes.push(AccountChange::Status {
+                previous: previous.status.clone(),
+                current: self.status.clone(),
+            });
+        }
+        changes
+    }
+}
+
 /// Success message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SuccessMessage {
@@ -205,6 +362,7 @@ pub struct SubscriptionBuilder {
     trades: Vec<String>,
     quotes: Vec<String>,
     bars: Vec<String>,
+    orderbooks: Vec<String>,
     trade_updates: bool,
 }
 
@@ -244,6 +402,17 @@ impl SubscriptionBuilder {
         self
     }
 
+    /// Subscribe to crypto order books for symbols
+    pub fn orderbooks<I, S>(mut self, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.orderbooks
+            .extend(symbols.into_iter().map(|s| s.into()));
+        self
+    }
+
     /// Subscribe to trade updates
     pub fn trade_updates(mut self) -> Self {
         self.trade_updates = true;
@@ -269,6 +438,11 @@ impl SubscriptionBuilder {
                 Some(self.bars)
             },
             trade_updates: if self.trade_updates { Some(true) } else { None },
+            orderbooks: if self.orderbooks.is_empty() {
+                None
+            } else {
+                Some(self.orderbooks)
+            },
         }
     }
 }
@@ -350,7 +524,7 @@ pub struct CryptoQuoteMessage {
     #[serde(rename = "S")]
     pub symbol: String,
     /// Timestamp.
-    #[serde(rename = "t")]
+    #[serde(rename = "t", deserialize_with = "wire_timestamp")]
     pub timestamp: DateTime<Utc>,
     /// Bid price.
     #[serde(rename = "bp")]
@@ -398,6 +572,38 @@ pub struct CryptoBarMessage {
     pub vwap: Option<f64>,
 }
 
+/// Crypto order book message from WebSocket.
+///
+/// The first message after subscribing (and any later message with
+/// `reset` set) is a full snapshot; all others are incremental updates in
+/// which a level with size `0` is removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptoOrderbookMessage {
+    /// Symbol.
+    #[serde(rename = "S")]
+    pub symbol: String,
+    /// Timestamp.
+    #[serde(rename = "t", deserialize_with = "wire_timestamp")]
+    pub timestamp: DateTime<Utc>,
+    /// Changed bid levels.
+    #[serde(rename = "b", default)]
+    pub bids: Vec<CryptoOrderbookEntry>,
+    /// Changed ask levels.
+    #[serde(rename = "a", default)]
+    pub asks: Vec<CryptoOrderbookEntry>,
+    /// Whether this message replaces the whole book.
+    #[serde(rename = "r", default)]
+    pub reset: bool,
+    /// Sequence number, when the feed provides one. Alpaca's own feed
+    /// currently does not; relays and recorded replays may.
+    #[serde(rename = "sq", default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    /// Checksum of the book after applying this message, when provided
+    /// (see [`OrderBook::checksum`](crate::order_book::OrderBook::checksum)).
+    #[serde(rename = "cs", default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<u32>,
+}
+
 /// Options trade message from WebSocket.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptionTradeMessage {
@@ -591,6 +797,355 @@ pub struct DailyBarMessage {
     pub vwap: Option<f64>,
 }
 
+// ============================================================================
+// Wire Codecs
+// ============================================================================
+
+/// Wire encoding of market data frames.
+///
+/// Alpaca's data streams send JSON text by default. With
+/// [`Codec::MessagePack`] the client connects with
+/// `Content-Type: application/msgpack` and receives binary frames, which
+/// decode straight into the typed messages instead of going through a
+/// `serde_json::Value` tree first. A MessagePack frame that fails to decode
+/// is dropped whole; the JSON path skips individual malformed messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Codec {
+    /// JSON text frames.
+    #[default]
+    Json,
+    /// MessagePack binary frames.
+    MessagePack,
+}
+
+impl Codec {
+    /// The `Content-Type` requested when connecting.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::MessagePack => "application/msgpack",
+        }
+    }
+
+    /// Encode an outgoing message as a text (JSON) or binary (MessagePack)
+    /// frame.
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Message> {
+        Ok(match self {
+            Self::Json => Message::Text(serde_json::to_string(value)?.into()),
+            Self::MessagePack => Message::Binary(
+                rmp_serde::to_vec_named(value)
+                    .map_err(|e| AlpacaError::Json(format!("MessagePack encode failed: {e}")))?
+                    .into(),
+            ),
+        })
+    }
+
+    /// Decode one frame.
+    pub fn decode<'de, T: Deserialize<'de>>(&self, frame: &'de [u8]) -> Result<T> {
+        match self {
+            Self::Json => Ok(serde_json::from_slice(frame)?),
+            Self::MessagePack => rmp_serde::from_slice(frame)
+                .map_err(|e| AlpacaError::Json(format!("invalid MessagePack frame: {e}"))),
+        }
+    }
+
+    /// Decode a control frame (hello, authentication or subscription
+    /// confirmation) into JSON text.
+    pub fn to_json_text(&self, frame: &[u8]) -> Result<String> {
+        match self {
+            Self::Json => String::from_utf8(frame.to_vec())
+                .map_err(|e| AlpacaError::Json(format!("frame is not UTF-8: {e}"))),
+            Self::MessagePack => Ok(serde_json::to_string(
+                &self.decode::<serde_json::Value>(frame)?,
+            )?),
+        }
+    }
+
+    /// Decode the `{"T": "error"}` message of a frame, if it carries one.
+    pub fn decode_error(&self, frame: &[u8]) -> Option<ErrorMessage> {
+        let text = self.to_json_text(frame).ok()?;
+        serde_json::from_str::<Vec<serde_json::Value>>(&text)
+            .ok()?
+            .into_iter()
+            .find(|msg| msg.get("T").and_then(|t| t.as_str()) == Some("error"))
+            .and_then(|msg| serde_json::from_value(msg).ok())
+    }
+
+    /// Decode a market data frame into its updates and the subscription
+    /// confirmation it carries, if any. Control messages and unknown
+    /// message types are skipped.
+    pub fn decode_market_data(
+        &self,
+        frame: &[u8],
+    ) -> (Vec<MarketDataUpdate>, Option<Subscriptions>) {
+        match self {
+            Self::Json => match std::str::from_utf8(frame) {
+                Ok(text) => (
+                    parse_market_data_updates(text),
+                    Subscriptions::from_frame(text),
+                ),
+                Err(_) => (Vec::new(), None),
+            },
+            Self::MessagePack => match self.decode::<Vec<MarketDataFrame>>(frame) {
+                Ok(messages) => {
+                    let mut updates = Vec::with_capacity(messages.len());
+                    let mut confirmed = None;
+                    for message in messages {
+                        match message {
+                            MarketDataFrame::Subscription(subscription) => {
+                                confirmed = Some(subscription.into());
+                            }
+                            other => updates.extend(other.into_update()),
+                        }
+                    }
+                    (updates, confirmed)
+                }
+                Err(e) => {
+                    debug!("Dropping market data frame: {}", e);
+                    (Vec::new(), None)
+                }
+            },
+        }
+    }
+}
+
+/// One message of a MessagePack market data frame.
+#[derive(Deserialize)]
+#[serde(tag = "T")]
+enum MarketDataFrame {
+    #[serde(rename = "t")]
+    Trade(TradeMessage),
+    #[serde(rename = "q")]
+    Quote(QuoteFrame),
+    #[serde(rename = "b")]
+    Bar(BarMessage),
+    #[serde(rename = "o")]
+    Orderbook(CryptoOrderbookMessage),
+    #[serde(rename = "subscription")]
+    Subscription(SubscriptionFrame),
+    #[serde(other)]
+    Other,
+}
+
+/// Quotes are tried in crypto format first, as on the JSON path.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QuoteFrame {
+    Crypto(CryptoQuoteMessage),
+    Stock(QuoteMessage),
+}
+
+#[derive(Deserialize)]
+struct SubscriptionFrame {
+    #[serde(default)]
+    trades: Vec<String>,
+    #[serde(default)]
+    quotes: Vec<String>,
+    #[serde(default)]
+    bars: Vec<String>,
+    #[serde(default)]
+    orderbooks: Vec<String>,
+}
+
+impl From<SubscriptionFrame> for Subscriptions {
+    fn from(frame: SubscriptionFrame) -> Self {
+        Self {
+            trades: frame.trades.into_iter().collect(),
+            quotes: frame.quotes.into_iter().collect(),
+            bars: frame.bars.into_iter().collect(),
+            orderbooks: frame.orderbooks.into_iter().collect(),
+        }
+    }
+}
+
+impl MarketDataFrame {
+    fn into_update(self) -> Option<MarketDataUpdate> {
+        match self {
+            Self::Trade(msg) => Some(trade_update(msg)),
+            Self::Quote(QuoteFrame::Crypto(msg)) => Some(crypto_quote_update(msg)),
+            Self::Quote(QuoteFrame::Stock(msg)) => Some(quote_update(msg)),
+            Self::Bar(msg) => Some(bar_update(msg)),
+            Self::Orderbook(orderbook) => Some(MarketDataUpdate::Orderbook {
+                symbol: orderbook.symbol.clone(),
+                orderbook,
+            }),
+            Self::Subscription(_) | Self::Other => None,
+        }
+    }
+}
+
+fn trade_update(msg: TradeMessage) -> MarketDataUpdate {
+    MarketDataUpdate::Trade {
+        symbol: msg.symbol.clone(),
+        trade: msg.into(),
+    }
+}
+
+fn quote_update(msg: QuoteMessage) -> MarketDataUpdate {
+    MarketDataUpdate::Quote {
+        symbol: msg.symbol.clone(),
+        quote: msg.into(),
+    }
+}
+
+fn crypto_quote_update(msg: CryptoQuoteMessage) -> MarketDataUpdate {
+    MarketDataUpdate::Quote {
+        quote: Quote {
+            timestamp: msg.timestamp,
+            timeframe: "real-time".to_string(),
+            bid_price: msg.bid_price,
+            bid_size: msg.bid_size as u32,
+            ask_price: msg.ask_price,
+            ask_size: msg.ask_size as u32,
+            bid_exchange: String::new(),
+            ask_exchange: String::new(),
+        },
+        symbol: msg.symbol,
+    }
+}
+
+fn bar_update(msg: BarMessage) -> MarketDataUpdate {
+    MarketDataUpdate::Bar {
+        symbol: msg.symbol.clone(),
+        bar: msg.into(),
+    }
+}
+
+/// Parse a market-data text frame (a JSON array of messages) into updates.
+pub(crate) fn parse_market_data_updates(text: &str) -> Vec<MarketDataUpdate> {
+    let Ok(messages) = serde_json::from_str::<Vec<serde_json::Value>>(text) else {
+        return Vec::new();
+    };
+    messages
+        .into_iter()
+        .filter_map(|msg_value| {
+            let msg_type = msg_value.get("T").and_then(|t| t.as_str())?;
+            match msg_type {
+                "t" => serde_json::from_value::<TradeMessage>(msg_value)
+                    .ok()
+                    .map(trade_update),
+                // Quote message - try crypto format first
+                "q" => {
+                    if let Ok(quote_msg) =
+                        serde_json::from_value::<CryptoQuoteMessage>(msg_value.clone())
+                    {
+                        Some(crypto_quote_update(quote_msg))
+                    } else {
+                        serde_json::from_value::<QuoteMessage>(msg_value)
+                            .ok()
+                            .map(quote_update)
+                    }
+                }
+                "b" => serde_json::from_value::<BarMessage>(msg_value)
+                    .ok()
+                    .map(bar_update),
+                "o" => serde_json::from_value::<CryptoOrderbookMessage>(msg_value)
+                    .ok()
+                    .map(|orderbook| MarketDataUpdate::Orderbook {
+                        symbol: orderbook.symbol.clone(),
+                        orderbook,
+                    }),
+                _ => {
+                    debug!("Ignoring message type: {}", msg_type);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// MessagePack timestamp extension type.
+const MSGPACK_TIMESTAMP_EXT: i8 = -1;
+
+/// Deserialize a timestamp sent as an RFC 3339 string (JSON) or as a
+/// MessagePack timestamp extension.
+fn wire_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<DateTime<Utc>, D::Error> {
+    deserializer.deserialize_any(TimestampVisitor)
+}
+
+struct TimestampVisitor;
+
+impl<'de> Visitor<'de> for TimestampVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an RFC 3339 string or a MessagePack timestamp")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<Self::Value, E> {
+        DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(E::custom)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let tag: i8 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let ExtData(data) = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        if tag != MSGPACK_TIMESTAMP_EXT {
+            return Err(de::Error::custom(format!(
+                "unexpected extension type {tag}"
+            )));
+        }
+        let (secs, nanos) = match data.len() {
+            4 => (
+                i64::from(u32::from_be_bytes(data[..4].try_into().unwrap_or_default())),
+                0,
+            ),
+            8 => {
+                let raw = u64::from_be_bytes(data[..8].try_into().unwrap_or_default());
+                ((raw & 0x3_ffff_ffff) as i64, (raw >> 34) as u32)
+            }
+            12 => (
+                i64::from_be_bytes(data[4..12].try_into().unwrap_or_default()),
+                u32::from_be_bytes(data[..4].try_into().unwrap_or_default()),
+            ),
+            len => return Err(de::Error::invalid_length(len, &self)),
+        };
+        DateTime::from_timestamp(secs, nanos)
+            .ok_or_else(|| de::Error::custom("timestamp out of range"))
+    }
+}
+
+/// Raw bytes of an extension value.
+struct ExtData(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ExtData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = ExtData;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("extension bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> std::result::Result<ExtData, E> {
+                Ok(ExtData(value.to_vec()))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -618,6 +1173,90 @@ mod tests {
         assert_eq!(json, "\"fill\"");
     }
 
+    #[cfg(feature = "unstable")]
+    #[test]
+    fn test_trade_update_to_execution() {
+        let mut order =
+            alpaca_base::test_utils::fixtures::sample_order("AAPL", OrderSide::Buy, "10");
+        order.filled_qty = "4".to_string();
+        order.filled_avg_price = Some("190.5".to_string());
+        let update = TradeUpdateMessage {
+            event: TradeUpdateEvent::PartialFill,
+            order,
+            timestamp: Utc::now(),
+            position_qty: Some("4".to_string()),
+            price: Some("190.5".to_string()),
+            qty: Some("4".to_string()),
+            execution_id: Some("exec-1".to_string()),
+        };
+        let execution = Execution::from(&update);
+        assert_eq!(execution.kind, ExecutionKind::Fill);
+        assert_eq!(execution.last_qty, Some(4.0));
+        assert_eq!(execution.cum_qty, Some(4.0));
+        assert_eq!(execution.avg_price, Some(190.5));
+        assert_eq!(execution.exec_id.as_deref(), Some("exec-1"));
+
+        let fill = update.portfolio_fill().unwrap();
+        assert_eq!((fill.qty, fill.price), (4.0, 190.5));
+    }
+
+    #[test]
+    fn test_message_pack_market_data_frame() {
+        use rmp::encode;
+
+        let mut frame = Vec::new();
+        encode::write_array_len(&mut frame, 2).unwrap();
+        encode::write_map_len(&mut frame, 8).unwrap();
+        for (key, value) in [("T", "t"), ("S", "AAPL"), ("x", "V")] {
+            encode::write_str(&mut frame, key).unwrap();
+            encode::write_str(&mut frame, value).unwrap();
+        }
+        encode::write_str(&mut frame, "t").unwrap();
+        encode::write_ext_meta(&mut frame, 12, MSGPACK_TIMESTAMP_EXT).unwrap();
+        frame.extend_from_slice(&500_000_000u32.to_be_bytes());
+        frame.extend_from_slice(&1_700_000_000i64.to_be_bytes());
+        encode::write_str(&mut frame, "p").unwrap();
+        encode::write_f64(&mut frame, 190.25).unwrap();
+        encode::write_str(&mut frame, "s").unwrap();
+        encode::write_uint(&mut frame, 100).unwrap();
+        encode::write_str(&mut frame, "i").unwrap();
+        encode::write_uint(&mut frame, 7).unwrap();
+        encode::write_str(&mut frame, "c").unwrap();
+        encode::write_array_len(&mut frame, 1).unwrap();
+        encode::write_str(&mut frame, "@").unwrap();
+        encode::write_map_len(&mut frame, 2).unwrap();
+        encode::write_str(&mut frame, "T").unwrap();
+        encode::write_str(&mut frame, "subscription").unwrap();
+        encode::write_str(&mut frame, "trades").unwrap();
+        encode::write_array_len(&mut frame, 1).unwrap();
+        encode::write_str(&mut frame, "AAPL").unwrap();
+
+        let (updates, confirmed) = Codec::MessagePack.decode_market_data(&frame);
+        assert_eq!(updates.len(), 1);
+        let MarketDataUpdate::Trade { symbol, trade } = &updates[0] else {
+            panic!("expected a trade update");
+        };
+        assert_eq!(symbol, "AAPL");
+        assert_eq!(trade.price, 190.25);
+        assert_eq!(
+            trade.timestamp,
+            DateTime::from_timestamp(1_700_000_000, 500_000_000).unwrap()
+        );
+        assert!(confirmed.unwrap().trades.contains("AAPL"));
+
+        let text = r#"[{"T":"t","S":"AAPL","t":"2023-11-14T22:13:20.5Z","p":190.25,"s":100,"x":"V","c":["@"],"i":7}]"#;
+        let (json_updates, _) = Codec::Json.decode_market_data(text.as_bytes());
+        assert_eq!(
+            format!("{json_updates:?}"),
+            format!("{updates:?}"),
+            "both codecs decode the same update"
+        );
+        assert!(matches!(
+            Codec::MessagePack.encode(&SubscriptionBuilder::new().trades(["AAPL"]).build()),
+            Ok(Message::Binary(_))
+        ));
+    }
+
     #[test]
     fn test_connection_status_serialization() {
         let status = ConnectionStatus::Connected;
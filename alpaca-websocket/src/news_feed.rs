@@ -0,0 +1,222 @@
+//! Backfilled, deduplicated news feed.
+//!
+//! Event-driven strategies that start mid-session usually want the last
+//! few hours of headlines before switching to live news. Fetching history
+//! and subscribing separately leaves a window where an article shows up in
+//! both, and the strategy triggers twice. [`NewsFeed`] merges the two into a
+//! single stream: backfilled articles first, oldest to newest, then live
+//! [`NewsEvent`]s, with every article ID delivered at most once.
+//!
+//! With the `http` feature, [`NewsFeed::connect`] performs the backfill via
+//! the REST news endpoint. The live subscription is opened before the
+//! backfill is requested so nothing published in between is missed.
+
+use crate::streams::{NewsEvent, NewsStream};
+use alpaca_base::types::EnhancedNewsArticle;
+use futures_util::stream::Stream;
+use std::collections::{HashSet, VecDeque};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Default number of article IDs remembered for deduplication.
+const DEFAULT_SEEN_CAPACITY: usize = 10_000;
+
+/// Bounded set of recently delivered article IDs.
+#[derive(Debug)]
+struct SeenIds {
+    capacity: usize,
+    ids: HashSet<u64>,
+    order: VecDeque<u64>,
+}
+
+impl SeenIds {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ids: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Record `id`, returning `false` if it was already seen.
+    fn insert(&mut self, id: u64) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.ids.remove(&oldest);
+        }
+        true
+    }
+}
+
+/// A single ordered news stream: backfill, then live, without duplicates.
+///
+/// Yields [`NewsEvent`]s so live lifecycle events (lag, reconnects,
+/// disconnect) still reach the consumer.
+pub struct NewsFeed {
+    backfill: VecDeque<EnhancedNewsArticle>,
+    live: NewsStream,
+    seen: SeenIds,
+}
+
+impl NewsFeed {
+    /// Merge already-fetched `backfill` articles with a `live` stream.
+    ///
+    /// Backfilled articles are sorted by creation time and deduplicated
+    /// before any live event is delivered.
+    #[must_use]
+    pub fn new(mut backfill: Vec<EnhancedNewsArticle>, live: NewsStream) -> Self {
+        backfill.sort_by_key(|article| (article.created_at, article.id));
+        Self {
+            backfill: backfill.into(),
+            live,
+            seen: SeenIds::new(DEFAULT_SEEN_CAPACITY),
+        }
+    }
+
+    /// Set how many article IDs are remembered for deduplication.
+    #[must_use]
+    pub fn seen_capacity(mut self, capacity: usize) -> Self {
+        self.seen.capacity = capacity.max(1);
+        self
+    }
+
+    /// Number of backfilled articles not yet delivered.
+    #[must_use]
+    pub fn pending_backfill(&self) -> usize {
+        self.backfill.len()
+    }
+}
+
+#[cfg(feature = "http")]
+impl NewsFeed {
+    /// Subscribe to live news for `symbols` and backfill articles published
+    /// within `lookback`, following REST pagination.
+    ///
+    /// `ws` must be a news client ([`AlpacaWebSocketClient::news`]).
+    ///
+    /// [`AlpacaWebSocketClient::news`]: crate::AlpacaWebSocketClient::news
+    pub async fn connect(
+        http: &alpaca_http::AlpacaHttpClient,
+        ws: &crate::AlpacaWebSocketClient,
+        symbols: &[&str],
+        lookback: chrono::Duration,
+        config: crate::WebSocketConfig,
+    ) -> alpaca_base::Result<Self> {
+        let live = ws.subscribe_news_with_config(symbols, config).await?;
+
+        let start = (chrono::Utc::now() - lookback).to_rfc3339();
+        let mut params = alpaca_base::types::NewsParams {
+            start: Some(start),
+            limit: Some(50),
+            ..Default::default()
+        };
+        if !symbols.contains(&"*") {
+            params.symbols = Some(symbols.join(","));
+        }
+
+        let mut backfill = Vec::new();
+        loop {
+            let page = http.get_enhanced_news(&params).await?;
+            backfill.extend(page.news);
+            match page.next_page_token {
+                Some(token) => params.page_token = Some(token),
+                None => break,
+            }
+        }
+        Ok(Self::new(backfill, live))
+    }
+}
+
+impl Stream for NewsFeed {
+    type Item = NewsEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while let Some(article) = this.backfill.pop_front() {
+            if this.seen.insert(article.id) {
+                return Poll::Ready(Some(NewsEvent::Update(Box::new(article))));
+            }
+        }
+        loop {
+            match Pin::new(&mut this.live).poll_next(cx) {
+                Poll::Ready(Some(NewsEvent::Update(article))) => {
+                    if this.seen.insert(article.id) {
+                        return Poll::Ready(Some(NewsEvent::Update(article)));
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use futures_util::StreamExt;
+    use tokio::sync::mpsc;
+
+    fn article(id: u64, minute: u32) -> EnhancedNewsArticle {
+        EnhancedNewsArticle {
+            id,
+            headline: format!("headline {id}"),
+            author: None,
+            created_at: Utc.with_ymd_and_hms(2024, 5, 1, 14, minute, 0).unwrap(),
+            updated_at: None,
+            summary: None,
+            content: None,
+            url: None,
+            images: Vec::new(),
+            symbols: vec!["AAPL".to_string()],
+            source: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backfill_then_live_without_duplicates() {
+        let (tx, rx) = mpsc::channel(8);
+        let backfill = vec![
+            article(3, 30),
+            article(1, 10),
+            article(2, 20),
+            article(1, 10),
+        ];
+        let mut feed = NewsFeed::new(backfill, NewsStream::new(rx));
+
+        for event in [
+            NewsEvent::Update(Box::new(article(3, 30))),
+            NewsEvent::Reconnected,
+            NewsEvent::Update(Box::new(article(4, 40))),
+            NewsEvent::Update(Box::new(article(2, 20))),
+        ] {
+            tx.send(event).await.unwrap();
+        }
+        drop(tx);
+
+        let mut delivered = Vec::new();
+        while let Some(event) = feed.next().await {
+            match event {
+                NewsEvent::Update(article) => delivered.push(article.id),
+                NewsEvent::Reconnected => delivered.push(0),
+                other => panic!("unexpected event {other:?}"),
+            }
+        }
+        assert_eq!(delivered, [1, 2, 3, 0, 4]);
+    }
+
+    #[test]
+    fn test_seen_ids_evicts_oldest() {
+        let mut seen = SeenIds::new(2);
+        assert!(seen.insert(1));
+        assert!(seen.insert(2));
+        assert!(!seen.insert(1));
+        assert!(seen.insert(3));
+        assert!(seen.insert(1));
+    }
+}
@@ -0,0 +1,880 @@
+//! Local crypto order books maintained from orderbook stream messages.
+//!
+//! Alpaca's crypto feeds publish Level 2 data on the `orderbooks` channel:
+//! a full snapshot right after subscribing, followed by incremental
+//! updates that carry only the levels that changed (size `0` removes a
+//! level). [`OrderBook`] folds those messages into a sorted local book for
+//! one symbol and answers best bid/ask, depth, mid and spread queries.
+//! [`OrderBooks`] does the same for every symbol on a stream and
+//! broadcasts a [`BookChange`] for each applied message.
+//!
+//! # Integrity and resync
+//!
+//! Incremental updates are only meaningful on top of the book they were
+//! computed from. When a message carries a sequence number, a gap marks
+//! the book out of sync; when it carries a checksum, the local book is
+//! verified against it after the update. Either failure clears the book
+//! and reports a [`ResyncReason`]; deltas received while out of sync are
+//! buffered and replayed on top of the next snapshot, whether that comes
+//! from the stream (after a reconnect) or from
+//! [`OrderBook::apply_snapshot`]. With the `http` feature,
+//! `OrderBooks::with_snapshot_source` fetches that snapshot over REST
+//! automatically.
+//!
+//! Deep books can be bounded with a depth cap; levels beyond it are
+//! dropped from the worst end of each side.
+
+use crate::messages::CryptoOrderbookMessage;
+use crate::streams::MarketDataUpdate;
+use alpaca_base::types::{CryptoOrderbook, CryptoOrderbookEntry};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tokio::sync::broadcast;
+
+/// Levels per side covered by [`OrderBook::checksum`], and the smallest
+/// allowed depth cap.
+pub const CHECKSUM_LEVELS: usize = 10;
+
+/// Deltas buffered per book while waiting for a snapshot.
+const MAX_PENDING: usize = 1024;
+
+/// Side of an order book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BookSide {
+    /// Resting buy orders.
+    Bid,
+    /// Resting sell orders.
+    Ask,
+}
+
+/// Aggregate size resting at one price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceLevel {
+    /// Level price.
+    pub price: f64,
+    /// Total size at the level.
+    pub size: f64,
+}
+
+/// A level that changed while applying a message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelChange {
+    /// Side of the level.
+    pub side: BookSide,
+    /// Level price.
+    pub price: f64,
+    /// New size; `0.0` when the level was removed.
+    pub size: f64,
+}
+
+/// Why a book was cleared and is waiting for a fresh snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResyncReason {
+    /// A message skipped one or more sequence numbers.
+    SequenceGap {
+        /// Sequence number that should have come next.
+        expected: u64,
+        /// Sequence number received.
+        received: u64,
+    },
+    /// The local book did not match the checksum sent with a message.
+    ChecksumMismatch {
+        /// Checksum carried by the message.
+        expected: u32,
+        /// Checksum of the local book.
+        computed: u32,
+    },
+}
+
+/// Change notification produced by [`OrderBook::apply`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookChange {
+    /// Symbol of the book.
+    pub symbol: String,
+    /// Timestamp of the applied message.
+    pub timestamp: DateTime<Utc>,
+    /// Whether the message was a snapshot that replaced the whole book.
+    pub reset: bool,
+    /// Levels whose size changed, in message order.
+    pub levels: Vec<LevelChange>,
+    /// Best bid after the update.
+    pub best_bid: Option<PriceLevel>,
+    /// Best ask after the update.
+    pub best_ask: Option<PriceLevel>,
+    /// Whether the best bid or best ask moved (price or size).
+    pub top_changed: bool,
+    /// Set when an integrity check failed: the book was cleared and
+    /// waits for a snapshot.
+    pub resync: Option<ResyncReason>,
+}
+
+/// Totally ordered price key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Sorted local order book for one crypto symbol.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    symbol: String,
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+    timestamp: Option<DateTime<Utc>>,
+    max_depth: Option<usize>,
+    sequence: Option<u64>,
+    synced: bool,
+    pending: VecDeque<CryptoOrderbookMessage>,
+}
+
+impl OrderBook {
+    /// Create an empty book for `symbol`.
+    #[must_use]
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            timestamp: None,
+            max_depth: None,
+            sequence: None,
+            synced: false,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Keep at most `levels` price levels per side (never fewer than
+    /// [`CHECKSUM_LEVELS`]).
+    #[must_use]
+    pub fn with_max_depth(mut self, levels: usize) -> Self {
+        self.max_depth = Some(levels.max(CHECKSUM_LEVELS));
+        self
+    }
+
+    /// Symbol tracked by this book.
+    #[must_use]
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Timestamp of the last applied message.
+    #[must_use]
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.timestamp
+    }
+
+    /// Whether the book reflects a snapshot plus every delta since.
+    ///
+    /// A new book is out of sync until its first snapshot arrives.
+    #[must_use]
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    /// Sequence number of the last applied message, if the feed sends
+    /// them.
+    #[must_use]
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    /// Whether the book holds no levels on either side.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    /// Apply a snapshot or incremental message.
+    ///
+    /// Returns `None` when the message belongs to another symbol, is a
+    /// delta buffered while the book is out of sync, or is a duplicate of
+    /// an already applied sequence number. Levels trimmed by the depth cap
+    /// are not reported in [`BookChange::levels`].
+    pub fn apply(&mut self, message: &CryptoOrderbookMessage) -> Option<BookChange> {
+        if message.symbol != self.symbol {
+            return None;
+        }
+        if !message.reset && !self.synced {
+            if self.pending.len() == MAX_PENDING {
+                self.pending.pop_front();
+            }
+            self.pending.push_back(message.clone());
+            return None;
+        }
+        if !message.reset
+            && let (Some(last), Some(seq)) = (self.sequence, message.sequence)
+            && seq <= last
+        {
+            return None;
+        }
+
+        let before = (self.best_bid(), self.best_ask());
+        let mut levels = Vec::new();
+        if let Err(reason) = self.apply_levels(message, &mut levels) {
+            return Some(self.invalidate(reason, message.timestamp, before));
+        }
+        if message.reset {
+            for pending in std::mem::take(&mut self.pending) {
+                let stale = match (message.sequence, pending.sequence) {
+                    (Some(snapshot), Some(seq)) => seq <= snapshot,
+                    _ => pending.timestamp <= message.timestamp,
+                };
+                if stale {
+                    continue;
+                }
+                if let Err(reason) = self.apply_levels(&pending, &mut levels) {
+                    return Some(self.invalidate(reason, pending.timestamp, before));
+                }
+            }
+        }
+
+        let best_bid = self.best_bid();
+        let best_ask = self.best_ask();
+        Some(BookChange {
+            symbol: self.symbol.clone(),
+            timestamp: self.timestamp.unwrap_or(message.timestamp),
+            reset: message.reset,
+            levels,
+            best_bid,
+            best_ask,
+            top_changed: before != (best_bid, best_ask),
+            resync: None,
+        })
+    }
+
+    /// Replace the book with a REST snapshot and replay deltas buffered
+    /// since it was taken.
+    pub fn apply_snapshot(&mut self, snapshot: &CryptoOrderbook) -> Option<BookChange> {
+        self.apply(&CryptoOrderbookMessage {
+            symbol: self.symbol.clone(),
+            timestamp: snapshot.timestamp,
+            bids: snapshot.bids.clone(),
+            asks: snapshot.asks.clone(),
+            reset: true,
+            sequence: None,
+            checksum: None,
+        })
+    }
+
+    /// Apply one message's levels and run its integrity checks.
+    fn apply_levels(
+        &mut self,
+        message: &CryptoOrderbookMessage,
+        levels: &mut Vec<LevelChange>,
+    ) -> std::result::Result<(), ResyncReason> {
+        if message.reset {
+            self.bids.clear();
+            self.asks.clear();
+            levels.clear();
+        } else if let (Some(last), Some(seq)) = (self.sequence, message.sequence)
+            && seq != last + 1
+        {
+            return Err(ResyncReason::SequenceGap {
+                expected: last + 1,
+                received: seq,
+            });
+        }
+
+        for (side, entries) in [
+            (BookSide::Bid, &message.bids),
+            (BookSide::Ask, &message.asks),
+        ] {
+            for entry in entries {
+                if self.set_level(side, entry) {
+                    levels.push(LevelChange {
+                        side,
+                        price: entry.price,
+                        size: entry.size.max(0.0),
+                    });
+                }
+            }
+        }
+        self.trim();
+        self.timestamp = Some(message.timestamp);
+        self.sequence = message.sequence;
+        self.synced = true;
+
+        if let Some(expected) = message.checksum {
+            let computed = self.checksum();
+            if computed != expected {
+                return Err(ResyncReason::ChecksumMismatch { expected, computed });
+            }
+        }
+        Ok(())
+    }
+
+    /// Clear the book after a failed integrity check.
+    fn invalidate(
+        &mut self,
+        reason: ResyncReason,
+        timestamp: DateTime<Utc>,
+        before: (Option<PriceLevel>, Option<PriceLevel>),
+    ) -> BookChange {
+        tracing::warn!("order book {} out of sync: {:?}", self.symbol, reason);
+        self.bids.clear();
+        self.asks.clear();
+        self.sequence = None;
+        self.synced = false;
+        self.pending.clear();
+        BookChange {
+            symbol: self.symbol.clone(),
+            timestamp,
+            reset: true,
+            levels: Vec::new(),
+            best_bid: None,
+            best_ask: None,
+            top_changed: before != (None, None),
+            resync: Some(reason),
+        }
+    }
+
+    /// Drop levels beyond the depth cap from the worst end of each side.
+    fn trim(&mut self) {
+        let Some(max) = self.max_depth else {
+            return;
+        };
+        while self.bids.len() > max {
+            self.bids.pop_first();
+        }
+        while self.asks.len() > max {
+            self.asks.pop_last();
+        }
+    }
+
+    /// CRC-32 (IEEE) of the top [`CHECKSUM_LEVELS`] levels, interleaved as
+    /// `bid_price:bid_size:ask_price:ask_size:...` best first, with each
+    /// number in its shortest decimal form. A side with fewer levels simply
+    /// contributes fewer fields.
+    #[must_use]
+    pub fn checksum(&self) -> u32 {
+        let mut bids = self.bids().take(CHECKSUM_LEVELS);
+        let mut asks = self.asks().take(CHECKSUM_LEVELS);
+        let mut fields = Vec::with_capacity(CHECKSUM_LEVELS * 4);
+        for _ in 0..CHECKSUM_LEVELS {
+            for level in [bids.next(), asks.next()].into_iter().flatten() {
+                fields.push(level.price.to_string());
+                fields.push(level.size.to_string());
+            }
+        }
+        crc32(fields.join(":").as_bytes())
+    }
+
+    /// Set or remove one level, returning whether the book changed.
+    fn set_level(&mut self, side: BookSide, entry: &CryptoOrderbookEntry) -> bool {
+        if !entry.price.is_finite() {
+            return false;
+        }
+        let book = match side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        };
+        let key = Price(entry.price);
+        if entry.size > 0.0 {
+            book.insert(key, entry.size) != Some(entry.size)
+        } else {
+            book.remove(&key).is_some()
+        }
+    }
+
+    /// Highest bid.
+    #[must_use]
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.iter().next_back().map(level)
+    }
+
+    /// Lowest ask.
+    #[must_use]
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.iter().next().map(level)
+    }
+
+    /// Midpoint of best bid and best ask.
+    #[must_use]
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Best ask minus best bid.
+    #[must_use]
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    /// Spread in basis points of the midpoint.
+    #[must_use]
+    pub fn spread_bps(&self) -> Option<f64> {
+        Some(self.spread()? / self.mid()? * 10_000.0)
+    }
+
+    /// Bid levels, best (highest) first.
+    pub fn bids(&self) -> impl Iterator<Item = PriceLevel> + '_ {
+        self.bids.iter().rev().map(level)
+    }
+
+    /// Ask levels, best (lowest) first.
+    pub fn asks(&self) -> impl Iterator<Item = PriceLevel> + '_ {
+        self.asks.iter().map(level)
+    }
+
+    /// Up to `levels` best levels on `side`, best first.
+    #[must_use]
+    pub fn depth(&self, side: BookSide, levels: usize) -> Vec<PriceLevel> {
+        match side {
+            BookSide::Bid => self.bids().take(levels).collect(),
+            BookSide::Ask => self.asks().take(levels).collect(),
+        }
+    }
+
+    /// Number of price levels on `side`.
+    #[must_use]
+    pub fn level_count(&self, side: BookSide) -> usize {
+        match side {
+            BookSide::Bid => self.bids.len(),
+            BookSide::Ask => self.asks.len(),
+        }
+    }
+
+    /// Total size on `side` at prices at least as good as `limit`: bids at
+    /// or above it, asks at or below it.
+    #[must_use]
+    pub fn size_through(&self, side: BookSide, limit: f64) -> f64 {
+        match side {
+            BookSide::Bid => self.bids.range(Price(limit)..).map(|(_, s)| s).sum(),
+            BookSide::Ask => self.asks.range(..=Price(limit)).map(|(_, s)| s).sum(),
+        }
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn level((price, size): (&Price, &f64)) -> PriceLevel {
+    PriceLevel {
+        price: price.0,
+        size: *size,
+    }
+}
+
+/// Order books for every symbol on a stream, with change broadcasting.
+///
+/// Feed it [`MarketDataUpdate`]s from a crypto market data stream; books
+/// are created on the first order book message for a symbol.
+#[derive(Debug)]
+pub struct OrderBooks {
+    books: HashMap<String, OrderBook>,
+    changes: broadcast::Sender<BookChange>,
+    max_depth: Option<usize>,
+    #[cfg(feature = "http")]
+    snapshots: Option<SnapshotSource>,
+}
+
+/// Fetches REST snapshots for books that fell out of sync.
+#[cfg(feature = "http")]
+#[derive(Debug)]
+struct SnapshotSource {
+    http: alpaca_http::AlpacaHttpClient,
+    sender: tokio::sync::mpsc::UnboundedSender<(String, CryptoOrderbook)>,
+    receiver: tokio::sync::mpsc::UnboundedReceiver<(String, CryptoOrderbook)>,
+    in_flight: std::collections::HashSet<String>,
+}
+
+impl Default for OrderBooks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderBooks {
+    /// Create an empty set of books buffering up to 1024 unread changes
+    /// per subscriber.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_capacity(1024)
+    }
+
+    /// Create an empty set of books buffering up to `capacity` unread
+    /// changes per subscriber.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (changes, _) = broadcast::channel(capacity.max(1));
+        Self {
+            books: HashMap::new(),
+            changes,
+            max_depth: None,
+            #[cfg(feature = "http")]
+            snapshots: None,
+        }
+    }
+
+    /// Cap every book at `levels` price levels per side (see
+    /// [`OrderBook::with_max_depth`]).
+    #[must_use]
+    pub fn with_max_depth(mut self, levels: usize) -> Self {
+        self.max_depth = Some(levels);
+        self
+    }
+
+    /// Receive a [`BookChange`] for every message applied from now on.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<BookChange> {
+        self.changes.subscribe()
+    }
+
+    /// Apply a stream update; non order book updates are ignored.
+    pub fn apply(&mut self, update: &MarketDataUpdate) -> Option<BookChange> {
+        match update {
+            MarketDataUpdate::Orderbook { orderbook, .. } => self.apply_message(orderbook),
+            _ => None,
+        }
+    }
+
+    /// Apply an order book message and broadcast the resulting change.
+    pub fn apply_message(&mut self, message: &CryptoOrderbookMessage) -> Option<BookChange> {
+        #[cfg(feature = "http")]
+        self.drain_snapshots();
+
+        let max_depth = self.max_depth;
+        let change = self
+            .books
+            .entry(message.symbol.clone())
+            .or_insert_with(|| {
+                let book = OrderBook::new(message.symbol.clone());
+                match max_depth {
+                    Some(levels) => book.with_max_depth(levels),
+                    None => book,
+                }
+            })
+            .apply(message)?;
+
+        #[cfg(feature = "http")]
+        if change.resync.is_some() {
+            self.request_snapshot(&change.symbol);
+        }
+        Some(self.publish(change))
+    }
+
+    /// Replace the book for `symbol` with a REST snapshot and broadcast the
+    /// result. Ignored for symbols without a book.
+    pub fn apply_snapshot(
+        &mut self,
+        symbol: &str,
+        snapshot: &CryptoOrderbook,
+    ) -> Option<BookChange> {
+        let change = self.books.get_mut(symbol)?.apply_snapshot(snapshot)?;
+        Some(self.publish(change))
+    }
+
+    fn publish(&self, change: BookChange) -> BookChange {
+        // No subscribers is not an error; the change is still returned.
+        let _ = self.changes.send(change.clone());
+        change
+    }
+
+    /// Symbols whose books are waiting for a snapshot.
+    pub fn needs_resync(&self) -> impl Iterator<Item = &str> {
+        self.books
+            .values()
+            .filter(|book| !book.is_synced())
+            .map(OrderBook::symbol)
+    }
+
+    /// Book for `symbol`, if any message for it has been applied.
+    #[must_use]
+    pub fn get(&self, symbol: &str) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    /// Symbols with a book.
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.books.keys().map(String::as_str)
+    }
+}
+
+#[cfg(feature = "http")]
+impl OrderBooks {
+    /// Resync out-of-sync books automatically from the REST latest
+    /// orderbooks endpoint.
+    ///
+    /// When a book fails an integrity check, a snapshot request is spawned
+    /// on the current Tokio runtime; the snapshot is applied (and buffered
+    /// deltas replayed) on the next call to [`apply_message`](Self::apply_message).
+    #[must_use]
+    pub fn with_snapshot_source(mut self, http: alpaca_http::AlpacaHttpClient) -> Self {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        self.snapshots = Some(SnapshotSource {
+            http,
+            sender,
+            receiver,
+            in_flight: std::collections::HashSet::new(),
+        });
+        self
+    }
+
+    /// Fetch REST snapshots for every book in [`needs_resync`](Self::needs_resync)
+    /// and apply them now.
+    pub async fn resync(
+        &mut self,
+        http: &alpaca_http::AlpacaHttpClient,
+    ) -> alpaca_base::Result<Vec<BookChange>> {
+        let symbols: Vec<String> = self.needs_resync().map(String::from).collect();
+        if symbols.is_empty() {
+            return Ok(Vec::new());
+        }
+        let response = http.get_crypto_orderbooks(&symbols.join(",")).await?;
+        Ok(response
+            .orderbooks
+            .iter()
+            .filter_map(|(symbol, snapshot)| self.apply_snapshot(symbol, snapshot))
+            .collect())
+    }
+
+    fn request_snapshot(&mut self, symbol: &str) {
+        let Some(source) = &mut self.snapshots else {
+            return;
+        };
+        if !source.in_flight.insert(symbol.to_string()) {
+            return;
+        }
+        let http = source.http.clone();
+        let sender = source.sender.clone();
+        let symbol = symbol.to_string();
+        tokio::spawn(async move {
+            match http.get_crypto_orderbooks(&symbol).await {
+                Ok(mut response) => {
+                    if let Some(snapshot) = response.orderbooks.remove(&symbol) {
+                        let _ = sender.send((symbol, snapshot));
+                    }
+                }
+                Err(e) => tracing::warn!("order book snapshot for {} failed: {}", symbol, e),
+            }
+        });
+    }
+
+    fn drain_snapshots(&mut self) {
+        let mut ready = Vec::new();
+        if let Some(source) = &mut self.snapshots {
+            while let Ok((symbol, snapshot)) = source.receiver.try_recv() {
+                source.in_flight.remove(&symbol);
+                ready.push((symbol, snapshot));
+            }
+        }
+        for (symbol, snapshot) in ready {
+            // The stream may have delivered its own snapshot meanwhile.
+            if self
+                .books
+                .get(&symbol)
+                .is_some_and(|book| !book.is_synced())
+            {
+                self.apply_snapshot(&symbol, &snapshot);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(price: f64, size: f64) -> CryptoOrderbookEntry {
+        CryptoOrderbookEntry { price, size }
+    }
+
+    fn message(
+        bids: Vec<CryptoOrderbookEntry>,
+        asks: Vec<CryptoOrderbookEntry>,
+        reset: bool,
+    ) -> CryptoOrderbookMessage {
+        CryptoOrderbookMessage {
+            symbol: "BTC/USD".to_string(),
+            timestamp: Utc::now(),
+            bids,
+            asks,
+            reset,
+            sequence: None,
+            checksum: None,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_then_deltas() {
+        let mut book = OrderBook::new("BTC/USD");
+        let change = book
+            .apply(&message(
+                vec![entry(100.0, 1.0), entry(99.5, 2.0), entry(99.0, 3.0)],
+                vec![entry(100.5, 1.5), entry(101.0, 2.5)],
+                true,
+            ))
+            .unwrap();
+        assert!(change.reset && change.top_changed);
+        assert_eq!(change.levels.len(), 5);
+        assert_eq!(
+            book.best_bid(),
+            Some(PriceLevel {
+                price: 100.0,
+                size: 1.0
+            })
+        );
+        assert_eq!(book.mid(), Some(100.25));
+        assert_eq!(book.spread(), Some(0.5));
+
+        // Remove the best bid, resize a deeper ask, repeat an unchanged level.
+        let change = book
+            .apply(&message(
+                vec![entry(100.0, 0.0), entry(99.0, 3.0)],
+                vec![entry(101.0, 4.0)],
+                false,
+            ))
+            .unwrap();
+        assert!(change.top_changed);
+        assert_eq!(change.levels.len(), 2);
+        assert_eq!(change.best_bid.unwrap().price, 99.5);
+        assert_eq!(
+            book.depth(BookSide::Ask, 5),
+            vec![
+                PriceLevel {
+                    price: 100.5,
+                    size: 1.5
+                },
+                PriceLevel {
+                    price: 101.0,
+                    size: 4.0
+                },
+            ]
+        );
+        assert_eq!(book.size_through(BookSide::Bid, 99.0), 5.0);
+        assert_eq!(book.size_through(BookSide::Ask, 100.5), 1.5);
+
+        // A later snapshot replaces everything.
+        book.apply(&message(vec![entry(98.0, 1.0)], vec![], true));
+        assert_eq!(book.level_count(BookSide::Bid), 1);
+        assert!(book.best_ask().is_none());
+        assert!(
+            book.apply(&CryptoOrderbookMessage {
+                symbol: "ETH/USD".to_string(),
+                ..message(vec![], vec![], false)
+            })
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_sequence_gap_triggers_resync_and_replay() {
+        let mut book = OrderBook::new("BTC/USD").with_max_depth(12);
+        let sequenced = |seq: u64, bids, asks, reset| CryptoOrderbookMessage {
+            sequence: Some(seq),
+            ..message(bids, asks, reset)
+        };
+
+        // Deltas before the first snapshot are held back.
+        assert!(
+            book.apply(&sequenced(1, vec![entry(1.0, 1.0)], vec![], false))
+                .is_none()
+        );
+        let levels: Vec<_> = (0..15).map(|i| entry(100.0 - i as f64, 1.0)).collect();
+        book.apply(&sequenced(10, levels, vec![entry(101.0, 1.0)], true))
+            .unwrap();
+        assert_eq!(book.level_count(BookSide::Bid), 12);
+        assert_eq!(book.bids().last().unwrap().price, 89.0);
+        assert!(book.apply(&sequenced(10, vec![], vec![], false)).is_none());
+
+        let gap = book
+            .apply(&sequenced(12, vec![entry(100.0, 2.0)], vec![], false))
+            .unwrap();
+        assert_eq!(
+            gap.resync,
+            Some(ResyncReason::SequenceGap {
+                expected: 11,
+                received: 12
+            })
+        );
+        assert!(!book.is_synced() && book.is_empty());
+
+        // Deltas are buffered until a REST snapshot arrives, then replayed.
+        let taken_at = Utc::now();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        book.apply(&sequenced(13, vec![entry(100.0, 5.0)], vec![], false));
+        let change = book
+            .apply_snapshot(&CryptoOrderbook {
+                timestamp: taken_at,
+                bids: vec![entry(100.0, 3.0)],
+                asks: vec![entry(101.0, 1.0)],
+            })
+            .unwrap();
+        assert!(book.is_synced());
+        assert_eq!(change.best_bid.unwrap().size, 5.0);
+        assert_eq!(book.sequence(), Some(13));
+    }
+
+    #[test]
+    fn test_checksum_validation() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let mut book = OrderBook::new("BTC/USD");
+        book.apply(&message(
+            vec![entry(100.0, 1.0), entry(99.5, 2.0)],
+            vec![entry(100.5, 1.5)],
+            true,
+        ));
+        assert_eq!(book.checksum(), crc32(b"100:1:100.5:1.5:99.5:2"));
+
+        let mut expected = book.clone();
+        expected.apply(&message(vec![entry(99.5, 0.0)], vec![], false));
+        let good = CryptoOrderbookMessage {
+            checksum: Some(expected.checksum()),
+            ..message(vec![entry(99.5, 0.0)], vec![], false)
+        };
+        assert!(book.apply(&good).unwrap().resync.is_none());
+
+        let bad = CryptoOrderbookMessage {
+            checksum: Some(1),
+            ..message(vec![entry(100.0, 4.0)], vec![], false)
+        };
+        let change = book.apply(&bad).unwrap();
+        assert!(matches!(
+            change.resync,
+            Some(ResyncReason::ChecksumMismatch { expected: 1, .. })
+        ));
+        assert!(!book.is_synced());
+    }
+
+    #[tokio::test]
+    async fn test_order_books_broadcast_changes() {
+        let mut books = OrderBooks::new();
+        let mut changes = books.subscribe();
+        let update = MarketDataUpdate::Orderbook {
+            symbol: "BTC/USD".to_string(),
+            orderbook: message(vec![entry(100.0, 1.0)], vec![entry(101.0, 1.0)], true),
+        };
+        books.apply(&update);
+
+        let change = changes.recv().await.unwrap();
+        assert_eq!(change.symbol, "BTC/USD");
+        assert_eq!(change.best_ask.unwrap().price, 101.0);
+        assert_eq!(books.get("BTC/USD").unwrap().spread(), Some(1.0));
+    }
+}
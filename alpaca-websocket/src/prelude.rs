@@ -0,0 +1,17 @@
+//! Commonly used, stable types: the [`alpaca_base::prelude`] plus the
+//! WebSocket client, its configuration and the stream event types.
+//!
+//! ```
+//! use alpaca_websocket::prelude::*;
+//!
+//! let config = WebSocketConfig::default();
+//! assert!(config.reconnect_enabled);
+//! ```
+
+pub use crate::client::{AlpacaWebSocketClient, DataFeed};
+pub use crate::config::{ConnectionState, StreamType, WebSocketConfig};
+pub use crate::error::WebSocketError;
+pub use crate::streams::{
+    MarketDataEvent, MarketDataStream, MarketDataUpdate, Subscriptions, TradingEvent, TradingStream,
+};
+pub use alpaca_base::prelude::*;
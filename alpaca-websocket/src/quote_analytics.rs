@@ -0,0 +1,321 @@
+//! Incremental spread, imbalance and NBBO change statistics over quotes.
+//!
+//! [`QuoteAnalytics`] keeps one [`SymbolQuoteStats`] per symbol and updates
+//! it in constant time and memory as quotes stream in; no quote history is
+//! buffered. Per symbol it tracks:
+//!
+//! - spread statistics: latest, running mean, standard deviation, min and
+//!   max (Welford's algorithm) and an exponentially weighted mean that
+//!   follows recent conditions;
+//! - quote imbalance: `(bid size - ask size) / (bid size + ask size)`, in
+//!   `-1.0..=1.0`, latest and exponentially weighted;
+//! - [`NbboChanges`]: how often the best bid and ask moved up or down, and
+//!   how many updates changed only sizes.
+//!
+//! Unlike [`AggregatedBook`](crate::AggregatedBook), which keeps a bounded
+//! history for percentiles and trends, these figures cover every quote
+//! since the symbol was first seen or [`QuoteAnalytics::reset`].
+
+use crate::streams::MarketDataUpdate;
+use alpaca_base::types::Quote;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Default weight of the newest quote in exponentially weighted figures.
+pub const DEFAULT_EWMA_ALPHA: f64 = 0.05;
+
+/// Counts of best bid and offer changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NbboChanges {
+    /// Best bid moved up.
+    pub bid_up: u64,
+    /// Best bid moved down.
+    pub bid_down: u64,
+    /// Best ask moved up.
+    pub ask_up: u64,
+    /// Best ask moved down.
+    pub ask_down: u64,
+    /// Prices unchanged, sizes changed.
+    pub size_only: u64,
+    /// Crossed or empty quotes, which are not otherwise counted.
+    pub invalid: u64,
+}
+
+impl NbboChanges {
+    /// Bid and ask price moves; a quote moving both sides counts twice.
+    #[must_use]
+    pub fn price_changes(&self) -> u64 {
+        self.bid_up + self.bid_down + self.ask_up + self.ask_down
+    }
+}
+
+/// Running quote statistics of one symbol.
+#[derive(Debug, Clone)]
+pub struct SymbolQuoteStats {
+    alpha: f64,
+    count: u64,
+    last: Option<Quote>,
+    spread_mean: f64,
+    spread_m2: f64,
+    spread_min: f64,
+    spread_max: f64,
+    spread_ewma: f64,
+    spread_bps_ewma: f64,
+    imbalance_ewma: f64,
+    changes: NbboChanges,
+}
+
+impl SymbolQuoteStats {
+    fn new(alpha: f64) -> Self {
+        Self {
+            alpha,
+            count: 0,
+            last: None,
+            spread_mean: 0.0,
+            spread_m2: 0.0,
+            spread_min: f64::INFINITY,
+            spread_max: f64::NEG_INFINITY,
+            spread_ewma: 0.0,
+            spread_bps_ewma: 0.0,
+            imbalance_ewma: 0.0,
+            changes: NbboChanges::default(),
+        }
+    }
+
+    fn on_quote(&mut self, quote: &Quote) {
+        if quote.bid_price <= 0.0 || quote.ask_price < quote.bid_price {
+            self.changes.invalid += 1;
+            return;
+        }
+        if let Some(last) = &self.last {
+            let changes = &mut self.changes;
+            if quote.bid_price > last.bid_price {
+                changes.bid_up += 1;
+            } else if quote.bid_price < last.bid_price {
+                changes.bid_down += 1;
+            }
+            if quote.ask_price > last.ask_price {
+                changes.ask_up += 1;
+            } else if quote.ask_price < last.ask_price {
+                changes.ask_down += 1;
+            }
+            if quote.bid_price == last.bid_price
+                && quote.ask_price == last.ask_price
+                && (quote.bid_size != last.bid_size || quote.ask_size != last.ask_size)
+            {
+                changes.size_only += 1;
+            }
+        }
+
+        let spread = quote.ask_price - quote.bid_price;
+        let spread_bps = spread / ((quote.ask_price + quote.bid_price) / 2.0) * 10_000.0;
+        let imbalance = imbalance(quote).unwrap_or(0.0);
+        self.count += 1;
+        let delta = spread - self.spread_mean;
+        self.spread_mean += delta / self.count as f64;
+        self.spread_m2 += delta * (spread - self.spread_mean);
+        self.spread_min = self.spread_min.min(spread);
+        self.spread_max = self.spread_max.max(spread);
+        if self.count == 1 {
+            self.spread_ewma = spread;
+            self.spread_bps_ewma = spread_bps;
+            self.imbalance_ewma = imbalance;
+        } else {
+            let a = self.alpha;
+            self.spread_ewma += a * (spread - self.spread_ewma);
+            self.spread_bps_ewma += a * (spread_bps - self.spread_bps_ewma);
+            self.imbalance_ewma += a * (imbalance - self.imbalance_ewma);
+        }
+        self.last = Some(quote.clone());
+    }
+
+    /// Valid quotes recorded.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Latest valid quote.
+    #[must_use]
+    pub fn last(&self) -> Option<&Quote> {
+        self.last.as_ref()
+    }
+
+    /// Time of the latest valid quote.
+    #[must_use]
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.last.as_ref().map(|q| q.timestamp)
+    }
+
+    /// Latest spread.
+    #[must_use]
+    pub fn spread(&self) -> Option<f64> {
+        self.last.as_ref().map(|q| q.ask_price - q.bid_price)
+    }
+
+    /// Mean spread.
+    #[must_use]
+    pub fn mean_spread(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.spread_mean)
+    }
+
+    /// Sample standard deviation of the spread.
+    #[must_use]
+    pub fn spread_std_dev(&self) -> Option<f64> {
+        (self.count > 1).then(|| (self.spread_m2 / (self.count - 1) as f64).sqrt())
+    }
+
+    /// Narrowest spread.
+    #[must_use]
+    pub fn min_spread(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.spread_min)
+    }
+
+    /// Widest spread.
+    #[must_use]
+    pub fn max_spread(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.spread_max)
+    }
+
+    /// Exponentially weighted mean spread.
+    #[must_use]
+    pub fn ewma_spread(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.spread_ewma)
+    }
+
+    /// Exponentially weighted mean spread in basis points of the midpoint.
+    #[must_use]
+    pub fn ewma_spread_bps(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.spread_bps_ewma)
+    }
+
+    /// Imbalance of the latest quote, in `-1.0..=1.0`; positive when the
+    /// bid is larger. `None` without sizes.
+    #[must_use]
+    pub fn imbalance(&self) -> Option<f64> {
+        self.last.as_ref().and_then(imbalance)
+    }
+
+    /// Exponentially weighted imbalance.
+    #[must_use]
+    pub fn ewma_imbalance(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.imbalance_ewma)
+    }
+
+    /// Best bid and offer change counters.
+    #[must_use]
+    pub fn changes(&self) -> NbboChanges {
+        self.changes
+    }
+}
+
+fn imbalance(quote: &Quote) -> Option<f64> {
+    let (bid, ask) = (f64::from(quote.bid_size), f64::from(quote.ask_size));
+    (bid + ask > 0.0).then(|| (bid - ask) / (bid + ask))
+}
+
+/// Running quote statistics for every streamed symbol.
+#[derive(Debug, Clone)]
+pub struct QuoteAnalytics {
+    alpha: f64,
+    symbols: HashMap<String, SymbolQuoteStats>,
+}
+
+impl Default for QuoteAnalytics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuoteAnalytics {
+    /// Create analytics weighting the newest quote by
+    /// [`DEFAULT_EWMA_ALPHA`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_alpha(DEFAULT_EWMA_ALPHA)
+    }
+
+    /// Create analytics weighting the newest quote by `alpha`, clamped to
+    /// `0.0..=1.0`. Higher values follow recent quotes more closely.
+    #[must_use]
+    pub fn with_alpha(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// Apply a stream update; anything but quotes is ignored.
+    pub fn apply(&mut self, update: &MarketDataUpdate) {
+        if let MarketDataUpdate::Quote { symbol, quote } = update {
+            self.on_quote(symbol, quote);
+        }
+    }
+
+    /// Record a quote of `symbol`.
+    pub fn on_quote(&mut self, symbol: &str, quote: &Quote) {
+        let alpha = self.alpha;
+        self.symbols
+            .entry(symbol.to_string())
+            .or_insert_with(|| SymbolQuoteStats::new(alpha))
+            .on_quote(quote);
+    }
+
+    /// Statistics of `symbol`, if any quote was seen.
+    #[must_use]
+    pub fn get(&self, symbol: &str) -> Option<&SymbolQuoteStats> {
+        self.symbols.get(symbol)
+    }
+
+    /// Symbols with statistics, in no particular order.
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.symbols.keys().map(String::as_str)
+    }
+
+    /// Forget the statistics of `symbol`, e.g. at the start of a session.
+    pub fn reset(&mut self, symbol: &str) {
+        self.symbols.remove(symbol);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::quote_with;
+
+    #[test]
+    fn test_quote_analytics_incremental_stats() {
+        let mut analytics = QuoteAnalytics::with_alpha(0.5);
+        analytics.on_quote("AAPL", &quote_with(100.00, 300, 100.02, 100));
+        analytics.on_quote("AAPL", &quote_with(100.01, 300, 100.02, 100)); // bid up
+        analytics.on_quote("AAPL", &quote_with(100.01, 100, 100.02, 300)); // sizes only
+        analytics.on_quote("AAPL", &quote_with(100.00, 100, 100.04, 300)); // bid down, ask up
+        analytics.on_quote("AAPL", &quote_with(100.05, 100, 100.04, 300)); // crossed
+        analytics.apply(&MarketDataUpdate::Quote {
+            symbol: "MSFT".to_string(),
+            quote: quote_with(400.00, 1, 400.10, 1),
+        });
+
+        let aapl = analytics.get("AAPL").unwrap();
+        assert_eq!(aapl.count(), 4);
+        let spreads = [0.02, 0.01, 0.01, 0.04];
+        let mean = spreads.iter().sum::<f64>() / 4.0;
+        assert!((aapl.mean_spread().unwrap() - mean).abs() < 1e-9);
+        assert!((aapl.min_spread().unwrap() - 0.01).abs() < 1e-9);
+        assert!((aapl.max_spread().unwrap() - 0.04).abs() < 1e-9);
+        let var = spreads.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / 3.0;
+        assert!((aapl.spread_std_dev().unwrap() - var.sqrt()).abs() < 1e-9);
+        // 0.02 -> 0.015 -> 0.0125 -> 0.02625 with alpha 0.5.
+        assert!((aapl.ewma_spread().unwrap() - 0.02625).abs() < 1e-9);
+        assert_eq!(aapl.imbalance(), Some(-0.5));
+
+        let changes = aapl.changes();
+        assert_eq!((changes.bid_up, changes.bid_down), (1, 1));
+        assert_eq!((changes.ask_up, changes.ask_down), (1, 0));
+        assert_eq!((changes.size_only, changes.invalid), (1, 1));
+        assert_eq!(analytics.get("MSFT").unwrap().count(), 1);
+
+        analytics.reset("AAPL");
+        assert!(analytics.get("AAPL").is_none());
+    }
+}
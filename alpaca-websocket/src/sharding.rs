@@ -0,0 +1,311 @@
+//! Spreading a large symbol universe over several market data connections.
+//!
+//! Alpaca caps the symbols a single connection may stream. A
+//! [`ShardedStreamManager`] places each symbol on one connection ("shard")
+//! with room, opening new connections as needed, and merges the shards'
+//! events into one stream. Because a symbol lives on exactly one shard, its
+//! updates keep their order in the merged output.
+//!
+//! When a shard's connection goes down for good, its symbols are moved to
+//! the remaining shards (or to new connections) and a
+//! [`ShardEvent::Rebalanced`] reports the move.
+
+use crate::client::AlpacaWebSocketClient;
+use crate::config::WebSocketConfig;
+use crate::streams::{MarketDataEvent, MarketDataStream, Subscriptions};
+use crate::subscription::Channel;
+use alpaca_base::{AlpacaError, Result};
+use futures_util::StreamExt;
+use futures_util::future::select_all;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tracing::{info, warn};
+
+/// Event emitted by a [`ShardedStreamManager`].
+#[derive(Debug, Clone)]
+pub enum ShardEvent {
+    /// An event from the connection with id `shard`.
+    Market {
+        shard: usize,
+        event: MarketDataEvent,
+    },
+    /// Connection `shard` went down and its symbols were resubscribed on
+    /// other connections.
+    Rebalanced { shard: usize, moved: Subscriptions },
+    /// Connection `shard` went down and its symbols could not be placed
+    /// elsewhere; they are no longer streamed.
+    RebalanceFailed {
+        shard: usize,
+        lost: Subscriptions,
+        reason: String,
+    },
+}
+
+struct Shard {
+    id: usize,
+    stream: MarketDataStream,
+}
+
+/// Market data subscriptions spread across several connections.
+///
+/// Each shard is a [`MarketDataStream`] opened with the manager's
+/// [`WebSocketConfig`], so reconnection and lag reporting work per shard.
+/// A shard is only rebalanced after its stream emits
+/// [`MarketDataEvent::Disconnected`]. Shards share the client's
+/// credentials, so a connection lease in the config would block every
+/// shard but the first; use one without a lease.
+///
+/// ```no_run
+/// # async fn run(client: alpaca_websocket::AlpacaWebSocketClient) -> alpaca_base::Result<()> {
+/// use alpaca_websocket::{Channel, ShardedStreamManager, WebSocketConfig};
+///
+/// let mut manager = ShardedStreamManager::new(client, WebSocketConfig::default(), 1000);
+/// manager.subscribe(&["AAPL", "MSFT", "NVDA"], &[Channel::Trades]).await?;
+/// while let Some(event) = manager.next().await {
+///     println!("{event:?}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ShardedStreamManager {
+    client: AlpacaWebSocketClient,
+    config: WebSocketConfig,
+    symbols_per_shard: usize,
+    max_shards: Option<usize>,
+    shards: Vec<Shard>,
+    placement: HashMap<String, usize>,
+    next_id: usize,
+    pending: VecDeque<ShardEvent>,
+}
+
+impl ShardedStreamManager {
+    /// Create a manager placing at most `symbols_per_shard` distinct
+    /// symbols on each connection. No connection is opened until the first
+    /// [`Self::subscribe`].
+    #[must_use]
+    pub fn new(
+        client: AlpacaWebSocketClient,
+        config: WebSocketConfig,
+        symbols_per_shard: usize,
+    ) -> Self {
+        let symbols_per_shard = symbols_per_shard.max(1);
+        Self {
+            client,
+            config: config.symbol_limit(symbols_per_shard),
+            symbols_per_shard,
+            max_shards: None,
+            shards: Vec::new(),
+            placement: HashMap::new(),
+            next_id: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Open at most `max` connections, e.g. the account's connection limit.
+    #[must_use]
+    pub fn max_shards(mut self, max: usize) -> Self {
+        self.max_shards = Some(max);
+        self
+    }
+
+    /// Number of open connections.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The id of the connection streaming `symbol`, if any.
+    pub fn shard_of(&self, symbol: &str) -> Option<usize> {
+        self.placement.get(symbol).copied()
+    }
+
+    /// The symbols confirmed on every connection.
+    pub fn subscriptions(&self) -> Subscriptions {
+        self.shards
+            .iter()
+            .fold(Subscriptions::default(), |all, shard| {
+                all.union(&shard.stream.current_subscriptions())
+            })
+    }
+
+    /// Subscribe `symbols` on each of `channels`.
+    ///
+    /// Symbols already streamed stay on their connection; new ones go to
+    /// the least loaded connection with room, and new connections are
+    /// opened once every connection is full.
+    ///
+    /// # Errors
+    /// Returns [`AlpacaError::ConnectionLimitExceeded`] if the symbols do
+    /// not fit in [`Self::max_shards`] connections, or the error of the
+    /// connection that failed to subscribe. Symbols placed before the
+    /// failure stay subscribed.
+    pub async fn subscribe(&mut self, symbols: &[&str], channels: &[Channel]) -> Result<()> {
+        self.place(&Subscriptions::from_symbols(symbols, channels))
+            .await
+    }
+
+    /// Unsubscribe `symbols` from each of `channels`, closing connections
+    /// left without symbols.
+    ///
+    /// # Errors
+    /// Returns the error of the connection that failed to unsubscribe.
+    pub async fn unsubscribe(&mut self, symbols: &[&str], channels: &[Channel]) -> Result<()> {
+        let mut by_shard: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+        for symbol in symbols {
+            if let Some(id) = self.placement.get(*symbol) {
+                by_shard.entry(*id).or_default().push(symbol);
+            }
+        }
+        for (id, symbols) in by_shard {
+            let Some(index) = self.shards.iter().position(|s| s.id == id) else {
+                continue;
+            };
+            let confirmed = self.shards[index]
+                .stream
+                .unsubscribe(&symbols, channels)
+                .await?;
+            let remaining = confirmed.symbols();
+            for symbol in symbols {
+                if !remaining.contains(symbol) {
+                    self.placement.remove(symbol);
+                }
+            }
+            if confirmed.is_empty() {
+                info!("Closing empty market data shard {}", id);
+                self.shards.remove(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// The next event from any connection, or `None` once no connection
+    /// is left.
+    pub async fn next(&mut self) -> Option<ShardEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            if self.shards.is_empty() {
+                return None;
+            }
+            let (event, index, _) =
+                select_all(self.shards.iter_mut().map(|shard| shard.stream.next())).await;
+            let shard = self.shards[index].id;
+            match event {
+                Some(MarketDataEvent::Disconnected { reason }) => {
+                    self.pending.push_back(ShardEvent::Market {
+                        shard,
+                        event: MarketDataEvent::Disconnected { reason },
+                    });
+                    self.rebalance(index).await;
+                }
+                Some(event) => return Some(ShardEvent::Market { shard, event }),
+                None => self.rebalance(index).await,
+            }
+        }
+    }
+
+    /// Drop the shard at `index` and move its symbols elsewhere.
+    async fn rebalance(&mut self, index: usize) {
+        let dropped = self.shards.remove(index);
+        let moved = dropped.stream.current_subscriptions();
+        self.placement.retain(|_, id| *id != dropped.id);
+        if moved.is_empty() {
+            return;
+        }
+        warn!(
+            "Market data shard {} disconnected, moving {} symbols",
+            dropped.id,
+            moved.symbols().len()
+        );
+        let event = match self.place(&moved).await {
+            Ok(()) => ShardEvent::Rebalanced {
+                shard: dropped.id,
+                moved,
+            },
+            Err(e) => ShardEvent::RebalanceFailed {
+                shard: dropped.id,
+                lost: moved,
+                reason: e.to_string(),
+            },
+        };
+        self.pending.push_back(event);
+    }
+
+    /// Subscribe `wanted` across the shards, opening new ones as needed.
+    async fn place(&mut self, wanted: &Subscriptions) -> Result<()> {
+        let mut load: BTreeMap<usize, usize> = self.shards.iter().map(|s| (s.id, 0)).collect();
+        for id in self.placement.values() {
+            *load.entry(*id).or_default() += 1;
+        }
+
+        let mut existing: BTreeMap<usize, Subscriptions> = BTreeMap::new();
+        let mut opening: Vec<Subscriptions> = Vec::new();
+        for symbol in wanted.symbols() {
+            let target = match self.placement.get(&symbol) {
+                Some(id) => Some(*id),
+                None => load
+                    .iter()
+                    .filter(|(_, count)| **count < self.symbols_per_shard)
+                    .min_by_key(|(_, count)| **count)
+                    .map(|(id, _)| *id),
+            };
+            let portion = match target {
+                Some(id) => {
+                    if !self.placement.contains_key(&symbol) {
+                        *load.entry(id).or_default() += 1;
+                    }
+                    existing.entry(id).or_default()
+                }
+                None => {
+                    let full = opening
+                        .last()
+                        .is_none_or(|s| s.symbols().len() >= self.symbols_per_shard);
+                    if full {
+                        let shards = self.shards.len() + opening.len() + 1;
+                        if let Some(max) = self.max_shards.filter(|max| shards > *max) {
+                            return Err(AlpacaError::ConnectionLimitExceeded(format!(
+                                "{} symbols do not fit in {max} connections of {}",
+                                wanted.symbols().len(),
+                                self.symbols_per_shard
+                            )));
+                        }
+                        opening.push(Subscriptions::default());
+                    }
+                    opening.last_mut().expect("pushed above")
+                }
+            };
+            for channel in Channel::ALL {
+                if wanted.channel(channel).contains(&symbol) {
+                    portion.channel_mut(channel).insert(symbol.clone());
+                }
+            }
+        }
+
+        for (id, portion) in existing {
+            let Some(shard) = self.shards.iter().find(|s| s.id == id) else {
+                continue;
+            };
+            let confirmed = shard.stream.subscribe_set(&portion).await?;
+            for symbol in confirmed.symbols() {
+                self.placement.insert(symbol, id);
+            }
+        }
+        for portion in opening {
+            let stream = self
+                .client
+                .subscribe_market_data_with_config(portion.to_request(), self.config.clone())
+                .await?;
+            let id = self.next_id;
+            self.next_id += 1;
+            info!(
+                "Opened market data shard {} with {} symbols",
+                id,
+                portion.symbols().len()
+            );
+            for symbol in stream.current_subscriptions().symbols() {
+                self.placement.insert(symbol, id);
+            }
+            self.shards.push(Shard { id, stream });
+        }
+        Ok(())
+    }
+}
@@ -2,13 +2,18 @@
 
 #![allow(missing_docs)]
 
+use crate::client::WsSink;
+use crate::error::WebSocketError;
 use crate::messages::*;
+use crate::subscription::{Channel, SubscriptionControl, SubscriptionError};
 use alpaca_base::types::*;
 use futures_util::stream::Stream;
+use std::collections::BTreeSet;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
 /// Stream of market data events.
 ///
@@ -22,14 +27,137 @@ use tokio::sync::mpsc;
 /// stops the background task that owns it.
 pub struct MarketDataStream {
     receiver: mpsc::Receiver<MarketDataEvent>,
+    subscriptions: watch::Receiver<Subscriptions>,
+    control: Option<Arc<SubscriptionControl<WsSink>>>,
+}
+
+/// Symbols subscribed on each market-data channel.
+///
+/// Built from the server's `subscription` confirmation, so it reflects what
+/// the feed actually streams rather than what was requested.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subscriptions {
+    pub trades: BTreeSet<String>,
+    pub quotes: BTreeSet<String>,
+    pub bars: BTreeSet<String>,
+    /// Crypto order book symbols (crypto feeds only).
+    pub orderbooks: BTreeSet<String>,
+}
+
+impl Subscriptions {
+    /// The symbol sets requested by a subscribe message.
+    pub fn from_request(subscription: &SubscribeMessage) -> Self {
+        let set = |symbols: &Option<Vec<String>>| symbols.iter().flatten().cloned().collect();
+        Self {
+            trades: set(&subscription.trades),
+            quotes: set(&subscription.quotes),
+            bars: set(&subscription.bars),
+            orderbooks: set(&subscription.orderbooks),
+        }
+    }
+
+    /// Parse the `subscription` confirmation out of a market-data text frame,
+    /// if the frame carries one.
+    pub fn from_frame(text: &str) -> Option<Self> {
+        let messages = serde_json::from_str::<Vec<serde_json::Value>>(text).ok()?;
+        let confirmation = messages
+            .into_iter()
+            .find(|msg| msg.get("T").and_then(|t| t.as_str()) == Some("subscription"))?;
+        let set = |channel: &str| {
+            confirmation
+                .get(channel)
+                .and_then(|v| v.as_array())
+                .map(|symbols| {
+                    symbols
+                        .iter()
+                        .filter_map(|s| s.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Some(Self {
+            trades: set("trades"),
+            quotes: set("quotes"),
+            bars: set("bars"),
+            orderbooks: set("orderbooks"),
+        })
+    }
+
+    /// Whether no channel has any symbol.
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+            && self.quotes.is_empty()
+            && self.bars.is_empty()
+            && self.orderbooks.is_empty()
+    }
+
+    /// Every symbol subscribed on any channel.
+    pub fn symbols(&self) -> BTreeSet<String> {
+        self.trades
+            .iter()
+            .chain(&self.quotes)
+            .chain(&self.bars)
+            .chain(&self.orderbooks)
+            .cloned()
+            .collect()
+    }
+
+    /// Per channel, the symbols in `intended` that are not subscribed here.
+    pub fn missing(&self, intended: &Self) -> Self {
+        let diff = |want: &BTreeSet<String>, have: &BTreeSet<String>| {
+            want.difference(have).cloned().collect()
+        };
+        Self {
+            trades: diff(&intended.trades, &self.trades),
+            quotes: diff(&intended.quotes, &self.quotes),
+            bars: diff(&intended.bars, &self.bars),
+            orderbooks: diff(&intended.orderbooks, &self.orderbooks),
+        }
+    }
 }
 
 /// Market data update enum
 #[derive(Debug, Clone)]
 pub enum MarketDataUpdate {
-    Trade { symbol: String, trade: Trade },
-    Quote { symbol: String, quote: Quote },
-    Bar { symbol: String, bar: Bar },
+    Trade {
+        symbol: String,
+        trade: Trade,
+    },
+    Quote {
+        symbol: String,
+        quote: Quote,
+    },
+    Bar {
+        symbol: String,
+        bar: Bar,
+    },
+    /// Crypto order book snapshot or incremental update.
+    Orderbook {
+        symbol: String,
+        orderbook: CryptoOrderbookMessage,
+    },
+}
+
+impl MarketDataUpdate {
+    /// Symbol of the update.
+    pub fn symbol(&self) -> &str {
+        match self {
+            Self::Trade { symbol, .. }
+            | Self::Quote { symbol, .. }
+            | Self::Bar { symbol, .. }
+            | Self::Orderbook { symbol, .. } => symbol,
+        }
+    }
+
+    /// Exchange timestamp of the update.
+    pub fn timestamp(&self) -> chrono::DateTime<chrono::Utc> {
+        match self {
+            Self::Trade { trade, .. } => trade.timestamp,
+            Self::Quote { quote, .. } => quote.timestamp,
+            Self::Bar { bar, .. } => bar.timestamp,
+            Self::Orderbook { orderbook, .. } => orderbook.timestamp,
+        }
+    }
 }
 
 /// Event emitted by a [`MarketDataStream`].
@@ -39,7 +167,7 @@ pub enum MarketDataUpdate {
 /// the channel silently closing.
 #[derive(Debug, Clone)]
 pub enum MarketDataEvent {
-    /// A market data update (trade, quote, or bar).
+    /// A market data update (trade, quote, bar, or order book).
     Update(MarketDataUpdate),
     /// The consumer was too slow and `missed` updates were dropped because
     /// the bounded channel was full.
@@ -49,15 +177,117 @@ pub enum MarketDataEvent {
     /// The connection was re-established and the active subscription set
     /// was re-issued.
     Reconnected,
+    /// The server confirmed a new subscription set, either because it was
+    /// modified or because it was replayed after [`Self::Reconnected`].
+    SubscriptionsChanged(Subscriptions),
+    /// The server reported an error on the subscription, e.g. a symbol
+    /// limit or an unknown channel.
+    SubscriptionError { code: u16, message: String },
     /// The connection is permanently down (reconnection disabled or
     /// retries exhausted). This is the last event before the stream ends.
     Disconnected { reason: String },
 }
 
+impl MarketDataEvent {
+    /// The typed error of a [`MarketDataEvent::SubscriptionError`].
+    pub fn error(&self) -> Option<WebSocketError> {
+        match self {
+            Self::SubscriptionError { code, message } => {
+                Some(WebSocketError::from_code(*code, message.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl MarketDataStream {
     /// Create a new market data stream
     pub fn new(receiver: mpsc::Receiver<MarketDataEvent>) -> Self {
-        Self { receiver }
+        Self::with_subscriptions(receiver, watch::channel(Subscriptions::default()).1)
+    }
+
+    /// Create a market data stream whose subscription state is published
+    /// through `subscriptions`.
+    pub fn with_subscriptions(
+        receiver: mpsc::Receiver<MarketDataEvent>,
+        subscriptions: watch::Receiver<Subscriptions>,
+    ) -> Self {
+        Self {
+            receiver,
+            subscriptions,
+            control: None,
+        }
+    }
+
+    /// Attach the connection task's subscription control, enabling
+    /// [`Self::subscribe`] and [`Self::unsubscribe`].
+    pub(crate) fn with_control(mut self, control: Arc<SubscriptionControl<WsSink>>) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// The subscription set last confirmed by the server.
+    pub fn current_subscriptions(&self) -> Subscriptions {
+        self.subscriptions.borrow().clone()
+    }
+
+    /// Subscribe `symbols` on each of `channels`.
+    ///
+    /// Only the symbols not already subscribed are sent. Returns the set
+    /// the server confirms, which is also replayed after a reconnect.
+    ///
+    /// # Errors
+    /// Returns [`SubscriptionError::SymbolLimit`] without sending anything
+    /// if the result would exceed [`WebSocketConfig::symbol_limit`], and
+    /// [`SubscriptionError::Rejected`] if the server answers with an error.
+    ///
+    /// [`WebSocketConfig::symbol_limit`]: crate::config::WebSocketConfig::symbol_limit
+    pub async fn subscribe(
+        &self,
+        symbols: &[&str],
+        channels: &[Channel],
+    ) -> Result<Subscriptions, SubscriptionError> {
+        let control = self
+            .control
+            .as_ref()
+            .ok_or(SubscriptionError::NotConnected)?;
+        control
+            .subscribe(&Subscriptions::from_symbols(symbols, channels))
+            .await
+    }
+
+    /// Subscribe every symbol of `subscriptions` on its channels.
+    pub(crate) async fn subscribe_set(
+        &self,
+        subscriptions: &Subscriptions,
+    ) -> Result<Subscriptions, SubscriptionError> {
+        let control = self
+            .control
+            .as_ref()
+            .ok_or(SubscriptionError::NotConnected)?;
+        control.subscribe(subscriptions).await
+    }
+
+    /// Unsubscribe `symbols` from each of `channels`.
+    ///
+    /// Only the symbols currently subscribed are sent. Returns the set the
+    /// server confirms.
+    ///
+    /// # Errors
+    /// Returns [`SubscriptionError::Rejected`] if the server answers with
+    /// an error.
+    pub async fn unsubscribe(
+        &self,
+        symbols: &[&str],
+        channels: &[Channel],
+    ) -> Result<Subscriptions, SubscriptionError> {
+        let control = self
+            .control
+            .as_ref()
+            .ok_or(SubscriptionError::NotConnected)?;
+        control
+            .unsubscribe(&Subscriptions::from_symbols(symbols, channels))
+            .await
     }
 
     /// Filter the stream down to data updates only, discarding lifecycle
@@ -142,6 +372,119 @@ impl Stream for TradingStream {
     }
 }
 
+/// Stream of account updates from the trading WebSocket.
+///
+/// Yields [`AccountUpdateEvent`]s with the same ownership and delivery
+/// semantics as [`TradingStream`], so monitoring components can follow
+/// cash, buying power and status changes without polling `/v2/account`.
+pub struct AccountUpdatesStream {
+    receiver: mpsc::Receiver<AccountUpdateEvent>,
+}
+
+/// Event emitted by an [`AccountUpdatesStream`].
+#[derive(Debug, Clone)]
+pub enum AccountUpdateEvent {
+    /// The account changed. `changes` lists the fields that differ from
+    /// the previous update; it is empty for the first update received.
+    Update {
+        account: Box<AccountUpdateMessage>,
+        changes: Vec<AccountChange>,
+    },
+    /// `missed` updates were dropped because the bounded channel was full.
+    Lagged { missed: u64 },
+    /// The connection was lost; a reconnect will be attempted after `delay`.
+    Reconnecting { attempt: u32, delay: Duration },
+    /// The connection was re-established and re-authenticated.
+    Reconnected,
+    /// The connection is permanently down. This is the last event before
+    /// the stream ends.
+    Disconnected { reason: String },
+}
+
+impl AccountUpdatesStream {
+    /// Create a new account updates stream
+    pub fn new(receiver: mpsc::Receiver<AccountUpdateEvent>) -> Self {
+        Self { receiver }
+    }
+
+    /// Filter the stream down to the changes of each update, discarding
+    /// lifecycle events and updates that changed nothing tracked.
+    pub fn changes(self) -> impl Stream<Item = AccountChange> + Unpin {
+        Box::pin(futures_util::stream::StreamExt::flat_map(
+            self,
+            |event| match event {
+                AccountUpdateEvent::Update { changes, .. } => futures_util::stream::iter(changes),
+                _ => futures_util::stream::iter(Vec::new()),
+            },
+        ))
+    }
+}
+
+impl Stream for AccountUpdatesStream {
+    type Item = AccountUpdateEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Stream of real-time news events.
+///
+/// Yields [`NewsEvent`]s with the same ownership and delivery semantics as
+/// [`MarketDataStream`].
+pub struct NewsStream {
+    receiver: mpsc::Receiver<NewsEvent>,
+}
+
+/// Event emitted by a [`NewsStream`].
+///
+/// Mirrors [`MarketDataEvent`] for the news channel.
+#[derive(Debug, Clone)]
+pub enum NewsEvent {
+    /// A newly published or updated article.
+    Update(Box<EnhancedNewsArticle>),
+    /// The consumer was too slow and `missed` articles were dropped because
+    /// the bounded channel was full.
+    Lagged { missed: u64 },
+    /// The connection was lost; a reconnect will be attempted after `delay`.
+    Reconnecting { attempt: u32, delay: Duration },
+    /// The connection was re-established and the news subscription was
+    /// re-issued.
+    Reconnected,
+    /// The connection is permanently down (reconnection disabled or
+    /// retries exhausted). This is the last event before the stream ends.
+    Disconnected { reason: String },
+}
+
+impl NewsStream {
+    /// Create a new news stream
+    pub fn new(receiver: mpsc::Receiver<NewsEvent>) -> Self {
+        Self { receiver }
+    }
+
+    /// Filter the stream down to articles only, discarding lifecycle
+    /// events. Convenient when reconnection/lag signals are not needed.
+    pub fn articles(self) -> impl Stream<Item = EnhancedNewsArticle> + Unpin {
+        Box::pin(futures_util::stream::StreamExt::filter_map(
+            self,
+            |event| async move {
+                match event {
+                    NewsEvent::Update(article) => Some(*article),
+                    _ => None,
+                }
+            },
+        ))
+    }
+}
+
+impl Stream for NewsStream {
+    type Item = NewsEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
 /// Stream of connection status updates
 pub struct StatusStream {
     receiver: mpsc::UnboundedReceiver<ConnectionStatus>,
@@ -231,3 +574,29 @@ impl Stream for AlpacaStream {
         self.receiver.poll_recv(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::SubscriptionBuilder;
+
+    #[test]
+    fn test_subscriptions_from_frame_and_missing() {
+        let frame =
+            r#"[{"T":"subscription","trades":["AAPL"],"quotes":["AAPL","MSFT"],"bars":[]}]"#;
+        let confirmed = Subscriptions::from_frame(frame).unwrap();
+        assert!(confirmed.trades.contains("AAPL"));
+        assert_eq!(confirmed.quotes.len(), 2);
+        assert!(confirmed.orderbooks.is_empty());
+        assert_eq!(confirmed.symbols().len(), 2);
+        assert!(Subscriptions::from_frame(r#"[{"T":"success"}]"#).is_none());
+
+        let intended = Subscriptions::from_request(
+            &SubscriptionBuilder::new().trades(["AAPL", "TSLA"]).build(),
+        );
+        let missing = confirmed.missing(&intended);
+        assert_eq!(missing.trades.into_iter().collect::<Vec<_>>(), vec!["TSLA"]);
+        assert!(missing.quotes.is_empty());
+        assert!(confirmed.missing(&confirmed).is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+//! Changing the subscriptions of a running market data stream.
+//!
+//! [`MarketDataStream::subscribe`] and [`MarketDataStream::unsubscribe`]
+//! diff the request against the set the stream currently holds, send only
+//! the delta on the open socket and wait for the server's confirmation.
+//! The confirmed set is what the stream replays after a reconnect.
+//!
+//! [`MarketDataStream::subscribe`]: crate::streams::MarketDataStream::subscribe
+//! [`MarketDataStream::unsubscribe`]: crate::streams::MarketDataStream::unsubscribe
+
+use crate::messages::{Codec, SubscribeMessage};
+use crate::streams::Subscriptions;
+use alpaca_base::AlpacaError;
+use futures_util::SinkExt;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::debug;
+
+/// Distinct symbols a Basic (free) data plan may stream per connection.
+pub const BASIC_PLAN_SYMBOL_LIMIT: usize = 30;
+
+/// A market data channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// Trades.
+    Trades,
+    /// Quotes.
+    Quotes,
+    /// Minute bars.
+    Bars,
+    /// Crypto order books (crypto feeds only).
+    Orderbooks,
+}
+
+impl Channel {
+    /// Every channel.
+    pub const ALL: [Channel; 4] = [
+        Channel::Trades,
+        Channel::Quotes,
+        Channel::Bars,
+        Channel::Orderbooks,
+    ];
+}
+
+/// Why a subscription change failed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SubscriptionError {
+    /// The change would stream more distinct symbols than the data plan
+    /// allows on one connection. Nothing was sent.
+    #[error("subscription would stream {requested} symbols, above the limit of {limit}")]
+    SymbolLimit { requested: usize, limit: usize },
+    /// The server answered with an error message.
+    #[error("subscription rejected ({code}): {message}")]
+    Rejected { code: u16, message: String },
+    /// The stream has no open connection, e.g. while reconnecting.
+    #[error("stream is not connected")]
+    NotConnected,
+    /// The server did not confirm the change in time.
+    #[error("no subscription confirmation within {0:?}")]
+    Timeout(Duration),
+    /// The change could not be encoded or written to the socket.
+    #[error("failed to send subscription change: {0}")]
+    Send(String),
+}
+
+impl From<SubscriptionError> for AlpacaError {
+    fn from(err: SubscriptionError) -> Self {
+        AlpacaError::WebSocket(err.to_string())
+    }
+}
+
+impl Subscriptions {
+    /// `symbols` on each of `channels`.
+    pub fn from_symbols(symbols: &[&str], channels: &[Channel]) -> Self {
+        let mut subscriptions = Self::default();
+        for channel in channels {
+            subscriptions
+                .channel_mut(*channel)
+                .extend(symbols.iter().map(|s| s.to_string()));
+        }
+        subscriptions
+    }
+
+    /// The symbols subscribed on `channel`.
+    pub fn channel(&self, channel: Channel) -> &BTreeSet<String> {
+        match channel {
+            Channel::Trades => &self.trades,
+            Channel::Quotes => &self.quotes,
+            Channel::Bars => &self.bars,
+            Channel::Orderbooks => &self.orderbooks,
+        }
+    }
+
+    pub(crate) fn channel_mut(&mut self, channel: Channel) -> &mut BTreeSet<String> {
+        match channel {
+            Channel::Trades => &mut self.trades,
+            Channel::Quotes => &mut self.quotes,
+            Channel::Bars => &mut self.bars,
+            Channel::Orderbooks => &mut self.orderbooks,
+        }
+    }
+
+    /// Per channel, the symbols of `other` that are also subscribed here.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let both =
+            |a: &BTreeSet<String>, b: &BTreeSet<String>| a.intersection(b).cloned().collect();
+        Self {
+            trades: both(&self.trades, &other.trades),
+            quotes: both(&self.quotes, &other.quotes),
+            bars: both(&self.bars, &other.bars),
+            orderbooks: both(&self.orderbooks, &other.orderbooks),
+        }
+    }
+
+    /// Per channel, the symbols subscribed here or in `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        let either = |a: &BTreeSet<String>, b: &BTreeSet<String>| a.union(b).cloned().collect();
+        Self {
+            trades: either(&self.trades, &other.trades),
+            quotes: either(&self.quotes, &other.quotes),
+            bars: either(&self.bars, &other.bars),
+            orderbooks: either(&self.orderbooks, &other.orderbooks),
+        }
+    }
+
+    /// The subscribe message requesting this set. The `orderbooks`
+    /// channel is left out when empty, as stock feeds reject it.
+    pub fn to_request(&self) -> SubscribeMessage {
+        let list = |set: &BTreeSet<String>| Some(set.iter().cloned().collect());
+        SubscribeMessage {
+            trades: list(&self.trades),
+            quotes: list(&self.quotes),
+            bars: list(&self.bars),
+            trade_updates: None,
+            orderbooks: (!self.orderbooks.is_empty())
+                .then(|| list(&self.orderbooks))
+                .flatten(),
+        }
+    }
+
+    /// The `subscribe`/`unsubscribe` frame for this set, listing only the
+    /// channels with symbols.
+    fn action_frame(&self, action: &str) -> serde_json::Value {
+        let mut frame = serde_json::json!({ "action": action });
+        for (name, set) in [
+            ("trades", &self.trades),
+            ("quotes", &self.quotes),
+            ("bars", &self.bars),
+            ("orderbooks", &self.orderbooks),
+        ] {
+            if !set.is_empty() {
+                frame[name] = serde_json::json!(set);
+            }
+        }
+        frame
+    }
+}
+
+/// Server reply to a subscription change.
+#[derive(Debug, Clone)]
+pub(crate) enum SubscriptionAck {
+    Confirmed(Subscriptions),
+    Rejected { code: u16, message: String },
+}
+
+/// Shared state between a [`MarketDataStream`] and its connection task:
+/// the write half of the current socket, the set to replay on reconnect
+/// and the channel carrying the server's replies.
+///
+/// [`MarketDataStream`]: crate::streams::MarketDataStream
+pub(crate) struct SubscriptionControl<S> {
+    sink: tokio::sync::Mutex<Option<S>>,
+    current: Mutex<Subscriptions>,
+    acks: broadcast::Sender<SubscriptionAck>,
+    codec: Codec,
+    symbol_limit: Option<usize>,
+    ack_timeout: Duration,
+    observe: Box<dyn Fn(&serde_json::Value) + Send + Sync>,
+}
+
+impl<S> SubscriptionControl<S>
+where
+    S: futures_util::Sink<Message> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    pub(crate) fn new(
+        current: Subscriptions,
+        codec: Codec,
+        symbol_limit: Option<usize>,
+        ack_timeout: Duration,
+        observe: impl Fn(&serde_json::Value) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            sink: tokio::sync::Mutex::new(None),
+            current: Mutex::new(current),
+            acks: broadcast::channel(16).0,
+            codec,
+            symbol_limit,
+            ack_timeout,
+            observe: Box::new(observe),
+        }
+    }
+
+    /// The set held by the stream, replayed on reconnect.
+    pub(crate) fn current(&self) -> Subscriptions {
+        self.current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Use `sink` for changes from now on; called on every (re)connect.
+    pub(crate) async fn attach(&self, sink: S) {
+        *self.sink.lock().await = Some(sink);
+    }
+
+    /// Deliver a server reply to the change waiting for it, if any.
+    pub(crate) fn ack(&self, ack: SubscriptionAck) {
+        let _ = self.acks.send(ack);
+    }
+
+    /// Add `requested` to the stream's subscriptions.
+    pub(crate) async fn subscribe(
+        &self,
+        requested: &Subscriptions,
+    ) -> Result<Subscriptions, SubscriptionError> {
+        let current = self.current();
+        let delta = current.missing(requested);
+        if let Some(limit) = self.symbol_limit {
+            let requested = current.union(&delta).symbols().len();
+            if requested > limit {
+                return Err(SubscriptionError::SymbolLimit { requested, limit });
+            }
+        }
+        self.send("subscribe", &delta).await
+    }
+
+    /// Remove `requested` from the stream's subscriptions.
+    pub(crate) async fn unsubscribe(
+        &self,
+        requested: &Subscriptions,
+    ) -> Result<Subscriptions, SubscriptionError> {
+        let delta = self.current().intersection(requested);
+        self.send("unsubscribe", &delta).await
+    }
+
+    /// Send `delta` and wait for the server's reply. Changes are
+    /// serialized by the sink lock, so each reply belongs to the change
+    /// that sent it.
+    async fn send(
+        &self,
+        action: &str,
+        delta: &Subscriptions,
+    ) -> Result<Subscriptions, SubscriptionError> {
+        let mut sink = self.sink.lock().await;
+        if delta.is_empty() {
+            return Ok(self.current());
+        }
+        let sink = sink.as_mut().ok_or(SubscriptionError::NotConnected)?;
+        let frame = delta.action_frame(action);
+        let message = self
+            .codec
+            .encode(&frame)
+            .map_err(|e| SubscriptionError::Send(e.to_string()))?;
+        let mut acks = self.acks.subscribe();
+        debug!("Sending subscription change: {}", frame);
+        sink.send(message)
+            .await
+            .map_err(|e| SubscriptionError::Send(e.to_string()))?;
+        (self.observe)(&frame);
+
+        let reply = async {
+            loop {
+                match acks.recv().await {
+                    Ok(ack) => return Some(ack),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        };
+        match tokio::time::timeout(self.ack_timeout, reply).await {
+            Err(_) => Err(SubscriptionError::Timeout(self.ack_timeout)),
+            Ok(None) => Err(SubscriptionError::NotConnected),
+            Ok(Some(SubscriptionAck::Rejected { code, message })) => {
+                Err(SubscriptionError::Rejected { code, message })
+            }
+            Ok(Some(SubscriptionAck::Confirmed(confirmed))) => {
+                *self.current.lock().unwrap_or_else(|e| e.into_inner()) = confirmed.clone();
+                Ok(confirmed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_delta_frames() {
+        let current = Subscriptions::from_symbols(&["AAPL"], &[Channel::Trades, Channel::Quotes]);
+        let requested = Subscriptions::from_symbols(&["AAPL", "MSFT"], &[Channel::Trades]);
+
+        let added = current.missing(&requested);
+        assert_eq!(
+            added.action_frame("subscribe"),
+            serde_json::json!({"action": "subscribe", "trades": ["MSFT"]})
+        );
+        let removed = current.intersection(&Subscriptions::from_symbols(
+            &["AAPL", "TSLA"],
+            &[Channel::Quotes],
+        ));
+        assert_eq!(
+            removed.action_frame("unsubscribe"),
+            serde_json::json!({"action": "unsubscribe", "quotes": ["AAPL"]})
+        );
+        assert_eq!(current.union(&added).symbols().len(), 2);
+        assert_eq!(current.to_request().orderbooks, None);
+    }
+}
@@ -0,0 +1,422 @@
+//! Cross-stream, timestamp-ordered event tape.
+//!
+//! Cross-asset strategies often consume several feeds at once, e.g. SIP
+//! equities and crypto, each arriving on its own connection with its own
+//! latency. [`TapeMerger`] merges any number of [`MarketDataEvent`] streams
+//! into one [`Tape`] ordered by exchange timestamp.
+//!
+//! Ordering uses a bounded reordering buffer. An update is released once
+//! every open source has delivered something at least as recent, so the
+//! tape is exact while all feeds are flowing. A quiet or lagging source
+//! cannot stall the tape: updates are also released once they are more
+//! than the reorder window older than the newest update seen, once they
+//! have waited the window in wall-clock time, or when the buffer is full.
+//! Anything that then arrives older than what was already released is
+//! delivered immediately and flagged [`TapeEvent::late`].
+
+use crate::streams::{MarketDataEvent, MarketDataUpdate};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{Stream, StreamExt};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// Default reorder window.
+const DEFAULT_WINDOW: Duration = Duration::from_millis(250);
+
+/// Default maximum number of buffered updates.
+const DEFAULT_MAX_BUFFERED: usize = 10_000;
+
+/// Channel buffer size for the merged tape.
+const TAPE_CHANNEL_SIZE: usize = 1000;
+
+/// One update on the merged tape.
+#[derive(Debug, Clone)]
+pub struct TapeEvent {
+    /// Label of the source stream.
+    pub source: String,
+    /// Exchange timestamp of the update.
+    pub timestamp: DateTime<Utc>,
+    /// The update.
+    pub update: MarketDataUpdate,
+    /// Whether the update arrived after newer updates had already been
+    /// released, and is therefore out of order.
+    pub late: bool,
+}
+
+#[derive(Debug)]
+struct Buffered {
+    timestamp: DateTime<Utc>,
+    seq: u64,
+    arrived: Instant,
+    source: usize,
+    update: MarketDataUpdate,
+}
+
+impl PartialEq for Buffered {
+    fn eq(&self, other: &Self) -> bool {
+        (self.timestamp, self.seq) == (other.timestamp, other.seq)
+    }
+}
+
+impl Eq for Buffered {}
+
+impl PartialOrd for Buffered {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Buffered {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.timestamp, self.seq).cmp(&(other.timestamp, other.seq))
+    }
+}
+
+/// Bounded reordering buffer behind a [`Tape`].
+#[derive(Debug)]
+struct ReorderBuffer {
+    labels: Vec<String>,
+    window: Duration,
+    max_buffered: usize,
+    heap: BinaryHeap<Reverse<Buffered>>,
+    /// Latest timestamp per open source (`None` until it delivers).
+    latest: BTreeMap<usize, Option<DateTime<Utc>>>,
+    newest: Option<DateTime<Utc>>,
+    released: Option<DateTime<Utc>>,
+    seq: u64,
+}
+
+impl ReorderBuffer {
+    fn new(labels: Vec<String>, window: Duration, max_buffered: usize) -> Self {
+        Self {
+            latest: (0..labels.len()).map(|source| (source, None)).collect(),
+            labels,
+            window,
+            max_buffered: max_buffered.max(1),
+            heap: BinaryHeap::new(),
+            newest: None,
+            released: None,
+            seq: 0,
+        }
+    }
+
+    /// Add an update, returning it straight away if it is already late.
+    fn push(&mut self, source: usize, update: MarketDataUpdate, now: Instant) -> Option<TapeEvent> {
+        let timestamp = update.timestamp();
+        if let Some(latest) = self.latest.get_mut(&source) {
+            *latest = Some(latest.map_or(timestamp, |t| t.max(timestamp)));
+        }
+        self.newest = Some(self.newest.map_or(timestamp, |t| t.max(timestamp)));
+        if self.released.is_some_and(|released| timestamp < released) {
+            return Some(self.event(source, timestamp, update, true));
+        }
+        self.seq += 1;
+        self.heap.push(Reverse(Buffered {
+            timestamp,
+            seq: self.seq,
+            arrived: now,
+            source,
+            update,
+        }));
+        None
+    }
+
+    /// Stop waiting for `source`.
+    fn close(&mut self, source: usize) {
+        self.latest.remove(&source);
+    }
+
+    /// Whether every source has closed.
+    fn is_closed(&self) -> bool {
+        self.latest.is_empty()
+    }
+
+    /// Release the updates that can no longer be overtaken.
+    fn ready(&mut self, now: Instant) -> Vec<TapeEvent> {
+        let all_open_delivered = self
+            .latest
+            .values()
+            .try_fold(None::<DateTime<Utc>>, |low, latest| {
+                latest.map(|t| Some(low.map_or(t, |low| low.min(t))))
+            });
+        let safe = match all_open_delivered {
+            Some(Some(low)) => Some(low),
+            // No open sources: everything is safe.
+            Some(None) => self.newest,
+            // Some open source has not delivered yet.
+            None => None,
+        };
+        let horizon = self.newest.and_then(|newest| {
+            chrono::Duration::from_std(self.window)
+                .ok()
+                .map(|window| newest - window)
+        });
+
+        let mut events = Vec::new();
+        while let Some(Reverse(next)) = self.heap.peek() {
+            let release = safe.is_some_and(|safe| next.timestamp <= safe)
+                || horizon.is_some_and(|horizon| next.timestamp <= horizon)
+                || now.duration_since(next.arrived) >= self.window
+                || self.heap.len() > self.max_buffered;
+            if !release {
+                break;
+            }
+            let Some(Reverse(next)) = self.heap.pop() else {
+                break;
+            };
+            self.released = Some(
+                self.released
+                    .map_or(next.timestamp, |t| t.max(next.timestamp)),
+            );
+            events.push(self.event(next.source, next.timestamp, next.update, false));
+        }
+        events
+    }
+
+    /// Release everything, in order.
+    fn drain(&mut self) -> Vec<TapeEvent> {
+        let mut events = Vec::with_capacity(self.heap.len());
+        while let Some(Reverse(next)) = self.heap.pop() {
+            events.push(self.event(next.source, next.timestamp, next.update, false));
+        }
+        events
+    }
+
+    fn event(
+        &self,
+        source: usize,
+        timestamp: DateTime<Utc>,
+        update: MarketDataUpdate,
+        late: bool,
+    ) -> TapeEvent {
+        TapeEvent {
+            source: self.labels[source].clone(),
+            timestamp,
+            update,
+            late,
+        }
+    }
+}
+
+type SourceStream = Pin<Box<dyn Stream<Item = MarketDataEvent> + Send>>;
+
+/// Builds a [`Tape`] from several market data streams.
+pub struct TapeMerger {
+    sources: Vec<(String, SourceStream)>,
+    window: Duration,
+    max_buffered: usize,
+}
+
+impl Default for TapeMerger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for TapeMerger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TapeMerger")
+            .field(
+                "sources",
+                &self
+                    .sources
+                    .iter()
+                    .map(|(label, _)| label)
+                    .collect::<Vec<_>>(),
+            )
+            .field("window", &self.window)
+            .field("max_buffered", &self.max_buffered)
+            .finish()
+    }
+}
+
+impl TapeMerger {
+    /// Create a merger with a 250 ms reorder window.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            window: DEFAULT_WINDOW,
+            max_buffered: DEFAULT_MAX_BUFFERED,
+        }
+    }
+
+    /// Add a stream, labelled `label` on the tape.
+    #[must_use]
+    pub fn source<S>(mut self, label: impl Into<String>, stream: S) -> Self
+    where
+        S: Stream<Item = MarketDataEvent> + Send + 'static,
+    {
+        self.sources.push((label.into(), Box::pin(stream)));
+        self
+    }
+
+    /// Set how long an update may be held back waiting for older ones.
+    #[must_use]
+    pub fn reorder_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the maximum number of updates held for reordering.
+    #[must_use]
+    pub fn max_buffered(mut self, max_buffered: usize) -> Self {
+        self.max_buffered = max_buffered;
+        self
+    }
+
+    /// Start merging. Must be called within a Tokio runtime.
+    ///
+    /// A source ends when its stream ends or reports
+    /// [`MarketDataEvent::Disconnected`]; the tape ends once every source
+    /// has ended and the buffer is drained. Dropping the tape stops the
+    /// merge and drops the sources.
+    #[must_use]
+    pub fn build(self) -> Tape {
+        let (labels, streams): (Vec<_>, Vec<_>) = self.sources.into_iter().unzip();
+        let mut buffer = ReorderBuffer::new(labels, self.window, self.max_buffered);
+        let mut merged = futures_util::stream::select_all(streams.into_iter().enumerate().map(
+            |(source, stream)| {
+                stream
+                    .map(move |event| (source, Some(event)))
+                    .chain(futures_util::stream::once(async move { (source, None) }))
+                    .boxed()
+            },
+        ));
+        let (sender, receiver) = mpsc::channel(TAPE_CHANNEL_SIZE);
+        let tick = (self.window / 4).max(Duration::from_millis(1));
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            while !buffer.is_closed() {
+                let mut out = Vec::new();
+                tokio::select! {
+                    next = merged.next() => match next {
+                        Some((source, Some(MarketDataEvent::Update(update)))) => {
+                            out.extend(buffer.push(source, update, Instant::now()));
+                        }
+                        Some((source, Some(MarketDataEvent::Disconnected { reason }))) => {
+                            debug!("Tape source {} disconnected: {}", source, reason);
+                            buffer.close(source);
+                        }
+                        Some((_, Some(_))) => {}
+                        Some((source, None)) => buffer.close(source),
+                        None => break,
+                    },
+                    _ = interval.tick() => {}
+                }
+                out.extend(buffer.ready(Instant::now()));
+                for event in out {
+                    if sender.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            for event in buffer.drain() {
+                if sender.send(event).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Tape { receiver }
+    }
+}
+
+/// Merged, timestamp-ordered stream of [`TapeEvent`]s.
+pub struct Tape {
+    receiver: mpsc::Receiver<TapeEvent>,
+}
+
+impl std::fmt::Debug for Tape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tape").finish()
+    }
+}
+
+impl Stream for Tape {
+    type Item = TapeEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpaca_base::test_utils::fixtures::trade_with;
+    use alpaca_base::types::Trade;
+
+    fn trade(symbol: &str, millis: i64) -> MarketDataUpdate {
+        MarketDataUpdate::Trade {
+            symbol: symbol.to_string(),
+            trade: Trade {
+                timestamp: DateTime::from_timestamp_millis(1_700_000_000_000 + millis).unwrap(),
+                id: millis as u64,
+                ..trade_with(100.0, 1)
+            },
+        }
+    }
+
+    #[test]
+    fn test_reorder_buffer_orders_across_sources() {
+        let labels = vec!["sip".to_string(), "crypto".to_string()];
+        let mut buffer = ReorderBuffer::new(labels, Duration::from_millis(100), 100);
+        let now = Instant::now();
+
+        assert!(buffer.push(0, trade("AAPL", 30), now).is_none());
+        assert!(buffer.push(0, trade("AAPL", 10), now).is_none());
+        // The crypto source has not delivered yet: nothing is safe.
+        assert!(buffer.ready(now).is_empty());
+
+        buffer.push(1, trade("BTC/USD", 20), now);
+        let released: Vec<i64> = buffer
+            .ready(now)
+            .iter()
+            .map(|e| e.timestamp.timestamp_millis() % 1000)
+            .collect();
+        assert_eq!(released, [10, 20]);
+
+        // Crypto falls silent; the window bounds how long AAPL waits.
+        buffer.push(0, trade("AAPL", 200), now);
+        let released: Vec<String> = buffer.ready(now).into_iter().map(|e| e.source).collect();
+        assert_eq!(released, ["sip"]);
+        let late = buffer.push(1, trade("BTC/USD", 25), now).unwrap();
+        assert!(late.late);
+        assert_eq!(late.source, "crypto");
+
+        buffer.close(1);
+        let rest = buffer.ready(now);
+        assert_eq!(rest.len(), 1);
+        assert!(!rest[0].late);
+    }
+
+    #[tokio::test]
+    async fn test_tape_merges_streams() {
+        let sip = futures_util::stream::iter(
+            [5, 15, 25].map(|t| MarketDataEvent::Update(trade("AAPL", t))),
+        );
+        let crypto = futures_util::stream::iter(
+            [0, 10, 20].map(|t| MarketDataEvent::Update(trade("BTC/USD", t))),
+        );
+        let tape = TapeMerger::new()
+            .source("sip", sip)
+            .source("crypto", crypto)
+            .build();
+        let events: Vec<TapeEvent> = tape.collect().await;
+        let times: Vec<i64> = events
+            .iter()
+            .map(|e| e.timestamp.timestamp_millis() % 1000)
+            .collect();
+        assert_eq!(times, [0, 5, 10, 15, 20, 25]);
+        assert!(events.iter().all(|e| !e.late));
+    }
+}
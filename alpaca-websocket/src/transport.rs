@@ -0,0 +1,173 @@
+//! Proxy tunnelling and custom TLS roots for stream connections.
+
+use alpaca_base::{AlpacaError, ClientConfig, ProxyConfig, Result};
+use rustls::RootCertStore;
+use rustls::pki_types::{CertificateDer, pem::PemObject};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::Uri;
+use tokio_tungstenite::{
+    Connector, MaybeTlsStream, WebSocketStream, client_async_tls_with_config, connect_async,
+    connect_async_tls_with_config,
+};
+
+/// Longest proxy response header accepted.
+const MAX_PROXY_RESPONSE: usize = 8192;
+
+/// How stream connections are opened: directly or through a proxy, with
+/// the built-in roots or extra ones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Transport {
+    proxy: Option<ProxyConfig>,
+    tls: Option<Arc<rustls::ClientConfig>>,
+}
+
+impl Transport {
+    /// The transport for the proxy and root certificates of `config`.
+    pub(crate) fn from_config(config: &ClientConfig) -> Result<Self> {
+        let tls = if config.root_certificates.is_empty() {
+            None
+        } else {
+            let mut roots = RootCertStore::empty();
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            for pem in &config.root_certificates {
+                let certificates = CertificateDer::pem_slice_iter(pem)
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| AlpacaError::Config(format!("invalid root certificate: {e}")))?;
+                if certificates.is_empty() {
+                    return Err(AlpacaError::Config(
+                        "no certificate found in root certificate PEM".to_string(),
+                    ));
+                }
+                for certificate in certificates {
+                    roots.add(certificate).map_err(|e| {
+                        AlpacaError::Config(format!("invalid root certificate: {e}"))
+                    })?;
+                }
+            }
+            let tls = rustls::ClientConfig::builder_with_provider(Arc::new(
+                rustls::crypto::ring::default_provider(),
+            ))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| AlpacaError::Config(e.to_string()))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+            Some(Arc::new(tls))
+        };
+        Ok(Self {
+            proxy: config.proxy.clone(),
+            tls,
+        })
+    }
+
+    /// Open a WebSocket connection for `request`.
+    pub(crate) async fn connect(
+        &self,
+        request: impl IntoClientRequest + Unpin,
+    ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+        let connector = self.tls.clone().map(Connector::Rustls);
+        let (ws_stream, _) = match &self.proxy {
+            None if connector.is_none() => connect_async(request).await?,
+            None => connect_async_tls_with_config(request, None, false, connector).await?,
+            Some(proxy) => {
+                let request = request.into_client_request()?;
+                let stream = tunnel(proxy, request.uri()).await?;
+                client_async_tls_with_config(request, stream, None, connector).await?
+            }
+        };
+        Ok(ws_stream)
+    }
+}
+
+/// Open a TCP connection to the host of `uri` through an HTTP `CONNECT`
+/// tunnel on `proxy`.
+async fn tunnel(proxy: &ProxyConfig, uri: &Uri) -> Result<TcpStream> {
+    let proxy_url = url::Url::parse(&proxy.url)
+        .map_err(|e| AlpacaError::Config(format!("invalid proxy URL: {e}")))?;
+    let proxy_host = proxy_url
+        .host_str()
+        .ok_or_else(|| AlpacaError::Config(format!("proxy URL without host: {}", proxy.url)))?;
+    let proxy_port = proxy_url.port_or_known_default().unwrap_or(80);
+    let host = uri
+        .host()
+        .ok_or_else(|| AlpacaError::Config(format!("stream URL without host: {uri}")))?;
+    let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("ws") {
+        80
+    } else {
+        443
+    });
+
+    let network = |e: std::io::Error| AlpacaError::Network(format!("proxy {}: {e}", proxy.url));
+    let mut stream = TcpStream::connect((proxy_host, proxy_port))
+        .await
+        .map_err(network)?;
+    let mut connect = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if let Some(authorization) = proxy.authorization() {
+        connect.push_str(&format!("Proxy-Authorization: {authorization}\r\n"));
+    }
+    connect.push_str("\r\n");
+    stream
+        .write_all(connect.as_bytes())
+        .await
+        .map_err(network)?;
+
+    // Read byte by byte so nothing past the header is consumed.
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_PROXY_RESPONSE {
+            return Err(AlpacaError::Network(
+                "proxy response header too long".to_string(),
+            ));
+        }
+        let byte = stream.read_u8().await.map_err(network)?;
+        response.push(byte);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(AlpacaError::Network(format!(
+            "proxy refused tunnel to {host}:{port}: {status}"
+        )));
+    }
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tunnel_sends_authenticated_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = ProxyConfig::new(format!("http://{}", listener.local_addr().unwrap()))
+            .basic_auth("user", "pass");
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut reader = tokio::io::BufReader::new(socket);
+            let mut request = String::new();
+            while !request.ends_with("\r\n\r\n") {
+                reader.read_line(&mut request).await.unwrap();
+            }
+            let mut socket = reader.into_inner();
+            let status = if request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz") {
+                "HTTP/1.1 200 Connection established\r\n\r\n"
+            } else {
+                "HTTP/1.1 407 Proxy Authentication Required\r\n\r\n"
+            };
+            socket.write_all(status.as_bytes()).await.unwrap();
+            request
+        });
+
+        let uri: Uri = "wss://stream.data.alpaca.markets/v2/iex".parse().unwrap();
+        tunnel(&proxy, &uri).await.unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("CONNECT stream.data.alpaca.markets:443 HTTP/1.1\r\n"));
+
+        let invalid = ClientConfig::default().add_root_certificate("not a pem");
+        assert!(Transport::from_config(&invalid).is_err());
+    }
+}
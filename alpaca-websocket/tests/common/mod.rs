@@ -107,6 +107,7 @@ pub fn trade_update_frame() -> Message {
         position_qty: None,
         price: None,
         qty: None,
+        execution_id: None,
     };
     let frame = serde_json::to_string(&WebSocketMessage::TradeUpdate(Box::new(update))).unwrap();
     Message::Text(frame.into())
@@ -65,3 +65,67 @@ async fn credentials_never_appear_in_logs() {
         "expected redacted key marker in logs:\n{output}"
     );
 }
+
+/// Instrumentation hooks see every frame, including the auth frame, with
+/// the credentials masked.
+#[tokio::test]
+async fn instrumentation_hooks_see_redacted_frames() {
+    use alpaca_base::{ClientHooks, Instrumentation, WsDirection, WsMessageEvent};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Frames(Arc<Mutex<Vec<(WsDirection, String)>>>);
+
+    impl ClientHooks for Frames {
+        fn on_ws_message(&self, event: &WsMessageEvent) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((event.direction, event.payload.clone()));
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut ws = accept_ws(&listener).await;
+        server_handshake(&mut ws).await;
+        ws.send(trade_frame(7)).await.unwrap();
+        ws.close(None).await.unwrap();
+    });
+
+    let frames = Frames::default();
+    let config = WebSocketConfig::new()
+        .no_reconnect()
+        .with_instrumentation(Instrumentation::new().hook(frames.clone()));
+    let stream = test_client(addr)
+        .subscribe_market_data_with_config(test_subscription(), config)
+        .await
+        .expect("subscribe should succeed");
+    collect_events(stream).await;
+    server.await.unwrap();
+
+    let frames = frames.0.lock().unwrap().clone();
+    let outbound: Vec<_> = frames
+        .iter()
+        .filter(|(direction, _)| *direction == WsDirection::Outbound)
+        .map(|(_, payload)| payload.as_str())
+        .collect();
+    assert_eq!(outbound.len(), 2, "expected auth and subscribe: {frames:?}");
+    assert!(outbound[0].contains(r#""key":"****7890""#));
+    assert!(outbound[0].contains(r#""secret":"****""#));
+    assert!(outbound[1].contains(r#""action":"subscribe""#));
+    assert!(
+        frames
+            .iter()
+            .any(|(direction, payload)| *direction == WsDirection::Inbound
+                && payload.contains(r#""i":7"#)),
+        "expected the trade frame: {frames:?}"
+    );
+    assert!(
+        frames
+            .iter()
+            .all(|(_, payload)| !payload.contains(TEST_SECRET) && !payload.contains(TEST_KEY))
+    );
+}
@@ -8,7 +8,9 @@ use common::*;
 
 use std::time::Duration;
 
-use alpaca_websocket::{MarketDataEvent, MarketDataUpdate, WebSocketConfig};
+use alpaca_websocket::{
+    Channel, MarketDataEvent, MarketDataUpdate, SubscriptionError, WebSocketConfig,
+};
 use futures_util::SinkExt;
 use tokio::net::TcpListener;
 use tokio_tungstenite::tungstenite::Message;
@@ -45,6 +47,9 @@ async fn reconnects_and_resubscribes_then_gives_up() {
         .subscribe_market_data_with_config(test_subscription(), config)
         .await
         .expect("subscribe should succeed");
+    let confirmed = stream.current_subscriptions();
+    assert_eq!(confirmed.trades.iter().collect::<Vec<_>>(), ["AAPL"]);
+    assert!(confirmed.quotes.is_empty());
 
     let events = collect_events(stream).await;
     let (first_sub, second_sub) = server.await.unwrap();
@@ -69,6 +74,11 @@ async fn reconnects_and_resubscribes_then_gives_up() {
             .any(|e| matches!(e, MarketDataEvent::Reconnecting { attempt: 1, .. })),
         "expected Reconnecting before Reconnected, got {events:?}"
     );
+    assert!(
+        matches!(&events[reconnected_at + 1], MarketDataEvent::SubscriptionsChanged(replayed)
+            if *replayed == confirmed),
+        "expected the replayed subscriptions after Reconnected, got {events:?}"
+    );
     assert!(
         matches!(events.last(), Some(MarketDataEvent::Disconnected { reason })
             if reason.contains("3 reconnect attempts")),
@@ -159,3 +169,135 @@ async fn initial_auth_failure_returns_error() {
         "unexpected error: {err}"
     );
 }
+
+/// Rotating the client's credentials closes the socket and re-runs the
+/// handshake with the new key straight away, even with reconnection
+/// disabled.
+#[tokio::test]
+async fn credential_rotation_reauthenticates() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut ws = accept_ws(&listener).await;
+        let (first_auth, _) = server_handshake(&mut ws).await;
+        let mut ws2 = accept_ws(&listener).await;
+        let (second_auth, _) = server_handshake(&mut ws2).await;
+        ws2.send(trade_frame(1)).await.unwrap();
+        ws2.close(None).await.unwrap();
+        (first_auth, second_auth)
+    });
+
+    let client = test_client(addr);
+    let stream = client
+        .subscribe_market_data_with_config(
+            test_subscription(),
+            WebSocketConfig::new().no_reconnect(),
+        )
+        .await
+        .expect("subscribe should succeed");
+    client
+        .credentials()
+        .rotate(alpaca_base::auth::Credentials::new(
+            "PKROTATEDKEY".to_string(),
+            "ROTATEDSECRET".to_string(),
+        ));
+
+    let events = collect_events(stream).await;
+    let (first_auth, second_auth) = server.await.unwrap();
+
+    assert!(first_auth.contains(TEST_KEY));
+    assert!(second_auth.contains("PKROTATEDKEY"));
+    assert!(
+        matches!(events.first(), Some(MarketDataEvent::Reconnecting { attempt: 1, delay })
+            if delay.is_zero()),
+        "expected an immediate reconnect, got {events:?}"
+    );
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, MarketDataEvent::Update(_))),
+        "expected the trade from the re-authenticated socket, got {events:?}"
+    );
+}
+
+/// Subscription changes on a live stream send only the delta, return the
+/// server's confirmation or error as a typed result, and respect the
+/// configured symbol limit without touching the socket.
+#[tokio::test]
+async fn subscribe_sends_delta_and_reports_acks() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut ws = accept_ws(&listener).await;
+        server_handshake(&mut ws).await;
+        let subscribe = next_text(&mut ws).await;
+        ws.send(Message::Text(
+            r#"[{"T":"subscription","trades":["AAPL","MSFT"],"quotes":["MSFT"],"bars":[]}]"#.into(),
+        ))
+        .await
+        .unwrap();
+        let unsubscribe = next_text(&mut ws).await;
+        ws.send(Message::Text(
+            r#"[{"T":"error","code":410,"msg":"invalid subscribe action for this feed"}]"#.into(),
+        ))
+        .await
+        .unwrap();
+        ws.close(None).await.unwrap();
+        (subscribe, unsubscribe)
+    });
+
+    let config = WebSocketConfig::new().no_reconnect().symbol_limit(2);
+    let stream = test_client(addr)
+        .subscribe_market_data_with_config(test_subscription(), config)
+        .await
+        .expect("subscribe should succeed");
+
+    let confirmed = stream
+        .subscribe(&["AAPL", "MSFT"], &[Channel::Trades, Channel::Quotes])
+        .await
+        .expect("server confirms the change");
+    assert_eq!(confirmed.symbols().len(), 2);
+    assert_eq!(stream.current_subscriptions(), confirmed);
+
+    let limited = stream.subscribe(&["TSLA"], &[Channel::Bars]).await;
+    assert_eq!(
+        limited,
+        Err(SubscriptionError::SymbolLimit {
+            requested: 3,
+            limit: 2
+        })
+    );
+    // Nothing subscribed on bars, so there is nothing to send.
+    assert_eq!(
+        stream.unsubscribe(&["AAPL"], &[Channel::Bars]).await,
+        Ok(confirmed)
+    );
+
+    let rejected = stream.unsubscribe(&["MSFT"], &[Channel::Quotes]).await;
+    assert!(
+        matches!(rejected, Err(SubscriptionError::Rejected { code: 410, .. })),
+        "expected a typed rejection, got {rejected:?}"
+    );
+
+    let (subscribe, unsubscribe) = server.await.unwrap();
+    let subscribe: serde_json::Value = serde_json::from_str(&subscribe).unwrap();
+    assert_eq!(
+        subscribe,
+        serde_json::json!({"action": "subscribe", "trades": ["MSFT"], "quotes": ["AAPL", "MSFT"]})
+    );
+    let unsubscribe: serde_json::Value = serde_json::from_str(&unsubscribe).unwrap();
+    assert_eq!(
+        unsubscribe,
+        serde_json::json!({"action": "unsubscribe", "quotes": ["MSFT"]})
+    );
+
+    let events = collect_events(stream).await;
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, MarketDataEvent::SubscriptionError { code: 410, .. })),
+        "expected the error as an event too, got {events:?}"
+    );
+}
@@ -0,0 +1,141 @@
+//! Integration tests for `ShardedStreamManager` against a mock server that
+//! confirms whatever each connection subscribes.
+
+mod common;
+
+use common::*;
+
+use std::collections::BTreeSet;
+
+use alpaca_websocket::{
+    Channel, MarketDataEvent, MarketDataUpdate, ShardEvent, ShardedStreamManager, WebSocketConfig,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Serve one market data connection: hello, auth, then confirm every
+/// subscribe/unsubscribe. With `drop_after_subscribe` the connection sends
+/// one AAPL trade after the first confirmation and closes.
+async fn serve_echo(mut ws: ServerWs, drop_after_subscribe: bool) {
+    ws.send(Message::Text(
+        r#"[{"T":"success","msg":"connected"}]"#.into(),
+    ))
+    .await
+    .unwrap();
+    let _auth = next_text(&mut ws).await;
+    ws.send(Message::Text(
+        r#"[{"T":"success","msg":"authenticated"}]"#.into(),
+    ))
+    .await
+    .unwrap();
+
+    let mut trades = BTreeSet::new();
+    while let Some(Ok(frame)) = ws.next().await {
+        let Message::Text(text) = frame else {
+            continue;
+        };
+        let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let symbols = request["trades"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|s| s.as_str().map(str::to_string));
+        if request["action"] == "subscribe" {
+            trades.extend(symbols);
+        } else {
+            for symbol in symbols {
+                trades.remove(&symbol);
+            }
+        }
+        let confirmation = serde_json::json!([{
+            "T": "subscription", "trades": trades, "quotes": [], "bars": []
+        }]);
+        ws.send(Message::Text(confirmation.to_string().into()))
+            .await
+            .unwrap();
+        if drop_after_subscribe {
+            ws.send(trade_frame(1)).await.unwrap();
+            ws.close(None).await.unwrap();
+            return;
+        }
+    }
+}
+
+/// Symbols are spread over connections of the configured size, events of
+/// every shard reach the merged output, and a dropped shard's symbols move
+/// to the shard with room and a new connection.
+#[tokio::test]
+async fn shards_symbols_and_rebalances_on_drop() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        for index in 0..3 {
+            let ws = accept_ws(&listener).await;
+            tokio::spawn(serve_echo(ws, index == 0));
+        }
+    });
+
+    let config = WebSocketConfig::new().no_reconnect();
+    let mut manager = ShardedStreamManager::new(test_client(addr), config, 2);
+    manager
+        .subscribe(&["AAPL", "MSFT", "NVDA"], &[Channel::Trades])
+        .await
+        .expect("subscribe should succeed");
+    assert_eq!(manager.shard_count(), 2);
+    assert_eq!(manager.shard_of("AAPL"), Some(0));
+    assert_eq!(manager.shard_of("MSFT"), Some(0));
+    assert_eq!(manager.shard_of("NVDA"), Some(1));
+
+    let mut events = Vec::new();
+    while let Some(event) = manager.next().await {
+        let rebalanced = matches!(event, ShardEvent::Rebalanced { .. });
+        events.push(event);
+        if rebalanced {
+            break;
+        }
+    }
+    server.await.unwrap();
+
+    assert!(
+        events.iter().any(|e| matches!(
+            e,
+            ShardEvent::Market {
+                shard: 0,
+                event: MarketDataEvent::Update(MarketDataUpdate::Trade { .. })
+            }
+        )),
+        "expected the trade from shard 0, got {events:?}"
+    );
+    let disconnected = events
+        .iter()
+        .position(|e| {
+            matches!(
+                e,
+                ShardEvent::Market {
+                    shard: 0,
+                    event: MarketDataEvent::Disconnected { .. }
+                }
+            )
+        })
+        .expect("expected shard 0 to disconnect");
+    assert!(
+        matches!(events.last(), Some(ShardEvent::Rebalanced { shard: 0, moved })
+            if moved.trades.len() == 2),
+        "expected shard 0's symbols to be moved, got {events:?}"
+    );
+    assert!(disconnected < events.len() - 1);
+
+    assert_eq!(manager.shard_count(), 2);
+    assert_eq!(manager.shard_of("AAPL"), Some(1));
+    assert_eq!(manager.shard_of("MSFT"), Some(2));
+    assert_eq!(manager.subscriptions().trades.len(), 3);
+
+    manager
+        .unsubscribe(&["MSFT"], &[Channel::Trades])
+        .await
+        .expect("unsubscribe should succeed");
+    assert_eq!(manager.shard_count(), 1);
+    assert_eq!(manager.shard_of("MSFT"), None);
+}
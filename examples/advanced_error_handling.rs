@@ -41,7 +41,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Pattern 2: API request error handling
     println!("\n--- Pattern 2: API Request Errors ---");
-    
+
     // Handle account request errors
     match http_client.get_account().await {
         Ok(account) => {
@@ -86,13 +86,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Pattern 5: Result combinators
     println!("\n--- Pattern 5: Result Combinators ---");
-    
+
     // Using map_err to add context
     let account_result = http_client
         .get_account()
         .await
         .map_err(|e| format!("Failed to fetch account: {}", e));
-    
+
     match account_result {
         Ok(acc) => println!("  Account status: {:?}", acc.status),
         Err(e) => println!("  Contextualized error: {}", e),
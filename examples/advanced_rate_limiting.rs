@@ -16,8 +16,8 @@
 
 use alpaca_base::{Credentials, Environment};
 use alpaca_http::AlpacaHttpClient;
-use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 
@@ -54,7 +54,7 @@ impl TokenBucket {
         let mut last = self.last_refill.lock().unwrap();
         let now = Instant::now();
         let elapsed = now.duration_since(*last).as_secs();
-        
+
         if elapsed > 0 {
             let new_tokens = elapsed * self.refill_rate;
             let current = self.tokens.load(Ordering::SeqCst);
@@ -90,7 +90,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  Max tokens: 200");
     println!("  Refill rate: 3/second");
     println!("  Current tokens: {}", bucket.tokens.load(Ordering::SeqCst));
-    
+
     // Simulate some requests
     for i in 1..=5 {
         if bucket.try_acquire() {
@@ -99,14 +99,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("  Request {} rate limited", i);
         }
     }
-    println!("  Remaining tokens: {}", bucket.tokens.load(Ordering::SeqCst));
+    println!(
+        "  Remaining tokens: {}",
+        bucket.tokens.load(Ordering::SeqCst)
+    );
 
     // Pattern 2: Semaphore for concurrent requests
     println!("\n--- Pattern 2: Concurrent Request Limiting ---");
     let semaphore = Arc::new(Semaphore::new(5)); // Max 5 concurrent requests
     println!("  Max concurrent requests: 5");
     println!("  Available permits: {}", semaphore.available_permits());
-    
+
     println!("  Usage:");
     println!("    let permit = semaphore.acquire().await?;");
     println!("    let result = client.get_account().await;");
@@ -170,7 +173,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Live demo with rate limiting
     println!("\n--- Live Rate-Limited Requests ---");
     let rate_limiter = Arc::new(TokenBucket::new(10, 2));
-    
+
     let start = Instant::now();
     for i in 1..=3 {
         rate_limiter.acquire().await;
@@ -40,8 +40,7 @@ impl Default for RetryConfig {
 
 impl RetryConfig {
     fn delay_for_attempt(&self, attempt: u32) -> Duration {
-        let delay = self.initial_delay_ms as f64 
-            * self.backoff_multiplier.powi(attempt as i32 - 1);
+        let delay = self.initial_delay_ms as f64 * self.backoff_multiplier.powi(attempt as i32 - 1);
         let delay_ms = delay.min(self.max_delay_ms as f64) as u64;
         Duration::from_millis(delay_ms)
     }
@@ -130,9 +129,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Demonstrate actual retry
     println!("\n--- Live Retry Demo ---");
-    let result = retry_with_backoff(|| async {
-        http_client.get_clock().await
-    }, &RetryConfig::default()).await;
+    let result = retry_with_backoff(
+        || async { http_client.get_clock().await },
+        &RetryConfig::default(),
+    )
+    .await;
 
     match result {
         Ok(clock) => {
@@ -174,17 +175,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// Retry a future with exponential backoff
-async fn retry_with_backoff<T, E, F, Fut>(
-    f: F,
-    config: &RetryConfig,
-) -> Result<T, E>
+async fn retry_with_backoff<T, E, F, Fut>(f: F, config: &RetryConfig) -> Result<T, E>
 where
     F: Fn() -> Fut,
     Fut: std::future::Future<Output = Result<T, E>>,
     E: std::fmt::Display,
 {
     let mut last_error = None;
-    
+
     for attempt in 1..=config.max_attempts {
         match f().await {
             Ok(value) => {
@@ -196,7 +194,7 @@ where
             Err(e) => {
                 println!("  Attempt {} failed: {}", attempt, e);
                 last_error = Some(e);
-                
+
                 if attempt < config.max_attempts {
                     let delay = config.delay_for_attempt(attempt);
                     println!("  Retrying in {:?}...", delay);
@@ -205,6 +203,6 @@ where
             }
         }
     }
-    
+
     Err(last_error.unwrap())
 }
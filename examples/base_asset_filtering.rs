@@ -9,7 +9,7 @@
 //! cargo run --example base_asset_filtering
 //! ```
 
-use alpaca_base::{Asset, AssetClass, AssetStatus, ListAssetsParams};
+use alpaca_base::{Asset, AssetClass, AssetStatus, types::ListAssetsParams};
 use uuid::Uuid;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -37,14 +37,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         easy_to_borrow: true,
         fractionable: true,
     };
-    println!("  Sample: {} ({}) - Tradable: {}", asset.name, asset.symbol, asset.tradable);
+    println!(
+        "  Sample: {} ({}) - Tradable: {}",
+        asset.name, asset.symbol, asset.tradable
+    );
 
     // 3. ListAssetsParams Builder
     println!("\n--- ListAssetsParams Builder ---");
     let params = ListAssetsParams::new()
         .status(AssetStatus::Active)
         .asset_class("us_equity");
-    
+
     println!("  Query for active US equities: {:?}", params);
 
     // 4. Filtering In-Memory
@@ -60,8 +63,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .iter()
         .filter(|a| a.tradable && a.shortable)
         .collect();
-        
-    println!("  Shortable assets found: {:?}", 
+
+    println!(
+        "  Shortable assets found: {:?}",
         shortable.iter().map(|a| &a.symbol).collect::<Vec<_>>()
     );
 
@@ -76,7 +80,11 @@ fn create_sample_asset(symbol: &str, tradable: bool, shortable: bool) -> Asset {
         exchange: "NASDAQ".to_string(),
         symbol: symbol.to_string(),
         name: format!("{} Inc.", symbol),
-        status: if tradable { AssetStatus::Active } else { AssetStatus::Inactive },
+        status: if tradable {
+            AssetStatus::Active
+        } else {
+            AssetStatus::Inactive
+        },
         tradable,
         marginable: tradable,
         shortable,
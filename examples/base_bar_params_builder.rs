@@ -9,8 +9,8 @@
 //! cargo run --example base_bar_params_builder
 //! ```
 
-use alpaca_base::{MultiBarsParams, Timeframe};
-use chrono::{Utc, Duration};
+use alpaca_base::{Timeframe, types::MultiBarsParams};
+use chrono::{Duration, Utc};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Bar Params Builder ===\n");
@@ -35,7 +35,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Serialization (as it would be sent over HTTP)
     println!("\n  Encoded query string (simulated):");
     // In actual use, alpaca-http handles the mapping of these params to the URL
-    println!("  ?symbols=AAPL,MSFT,GOOGL&timeframe=1Day&limit=100&start={}", start.to_rfc3339());
+    println!(
+        "  ?symbols=AAPL,MSFT,GOOGL&timeframe=1Day&limit=100&start={}",
+        start.to_rfc3339()
+    );
 
     println!("\n=== Example Complete ===");
     Ok(())
@@ -28,26 +28,31 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("\nMethod 1: Credentials::from_env()");
     match Credentials::from_env() {
         Ok(creds) => {
-            println!("✅ Successfully loaded credentials from environment (via .env or system env).");
+            println!(
+                "✅ Successfully loaded credentials from environment (via .env or system env)."
+            );
             println!("API Key: {}...", &creds.api_key[..5]); // Only show prefix for security
         }
         Err(e) => {
             println!("❌ Failed to load credentials: {}", e);
-            println!("   Make sure ALPACA_API_KEY and ALPACA_API_SECRET are set in your .env file or environment.");
+            println!(
+                "   Make sure ALPACA_API_KEY and ALPACA_API_SECRET are set in your .env file or environment."
+            );
         }
     }
 
     // Method 2: Manual construction
     println!("\nMethod 2: Manual construction");
     let api_key = env::var("ALPACA_API_KEY").unwrap_or_else(|_| "your_api_key".to_string());
-    let secret_key = env::var("ALPACA_API_SECRET").unwrap_or_else(|_| "your_secret_key".to_string());
-    
+    let secret_key =
+        env::var("ALPACA_API_SECRET").unwrap_or_else(|_| "your_secret_key".to_string());
+
     let creds = Credentials::new(api_key, secret_key);
     println!("✅ Manually created credentials object.");
 
     // Demonstrating environment selection
     println!("\n--- Environment Selection ---");
-    
+
     let paper = Environment::Paper;
     println!("Paper Base URL: {}", paper.base_url());
     let live = Environment::Live;
@@ -24,10 +24,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("\n--- Rate Limit Case ---");
     let rate_limit_error = AlpacaError::RateLimit("too many requests".to_string());
     handle_error(&rate_limit_error);
-    
+
     // Check if retryable
     if rate_limit_error.is_retryable() {
-        println!("  💡 Pro-tip: This error is retryable. You should implement an exponential backoff.");
+        println!(
+            "  💡 Pro-tip: This error is retryable. You should implement an exponential backoff."
+        );
     }
 
     // 3. Simulating a Validation Error
@@ -38,10 +40,16 @@ fn main() -> Result<(), Box<dyn Error>> {
     // 4. Working with ApiErrorCode
     println!("\n--- Specific API Error Codes ---");
     let forbidden_code = ApiErrorCode::Forbidden;
-    println!("  Error Code: {:?} (Integer: {})", forbidden_code, forbidden_code as u16);
-    
+    println!(
+        "  Error Code: {:?} (Integer: {})",
+        forbidden_code, forbidden_code as u16
+    );
+
     let order_not_found = ApiErrorCode::OrderNotFound;
-    println!("  Error Code: {:?} (Integer: {})", order_not_found, order_not_found as u16);
+    println!(
+        "  Error Code: {:?} (Integer: {})",
+        order_not_found, order_not_found as u16
+    );
 
     println!("\n=== Example Complete ===");
     Ok(())
@@ -50,7 +58,9 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn handle_error(err: &AlpacaError) {
     match err {
         AlpacaError::Api(msg) => println!("  Caught API Error: {}", msg),
-        AlpacaError::RateLimit(msg) => println!("  Caught Rate Limit: {}. Wait before retrying.", msg),
+        AlpacaError::RateLimit(msg) => {
+            println!("  Caught Rate Limit: {}. Wait before retrying.", msg)
+        }
         AlpacaError::Validation(msg) => println!("  Caught Validation Error: {}", msg),
         AlpacaError::Auth(msg) => println!("  Caught Authentication Error: {}", msg),
         _ => println!("  Caught Other Error: {:?}", err),
@@ -9,7 +9,7 @@
 //! cargo run --example base_order_types
 //! ```
 
-use alpaca_base::{OrderType, OrderSide, TimeInForce, OrderClass};
+use alpaca_base::{OrderClass, OrderSide, OrderType, TimeInForce};
 
 fn main() {
     println!("=== Alpaca Order Configuration ===\n");
@@ -18,7 +18,7 @@ fn main() {
     let side = OrderSide::Buy;
     let order_type = OrderType::Market;
     let tif = TimeInForce::Gtc; // Good 'Til Cancelled
-    
+
     println!("--- Market Buy Order (GTC) ---");
     println!("  Side: {:?}", side);
     println!("  Type: {:?}", order_type);
@@ -28,7 +28,7 @@ fn main() {
     let side = OrderSide::Sell;
     let order_type = OrderType::Limit;
     let tif = TimeInForce::Day;
-    
+
     println!("\n--- Limit Sell Order (Day) ---");
     println!("  Side: {:?}", side);
     println!("  Type: {:?}", order_type);
@@ -36,13 +36,13 @@ fn main() {
 
     // 3. Advanced Order Classes (Bracket, OCO, OTO)
     println!("\n--- Order Classes ---");
-    
+
     let simple = OrderClass::Simple;
     println!("  Simple: {:?}", simple);
-    
+
     let bracket = OrderClass::Bracket;
     println!("  Bracket: {:?} (Entry + Take Profit + Stop Loss)", bracket);
-    
+
     let oco = OrderClass::Oco;
     println!("  OCO: {:?} (One-Cancels-Other)", oco);
 
@@ -33,7 +33,9 @@ fn main() {
     // Initialize clients
     println!("\n--- Initialize Clients ---");
     println!("  let credentials = Credentials::from_env()?;");
-    println!("  let http_client = AlpacaHttpClient::new(credentials.clone(), Environment::Paper)?;");
+    println!(
+        "  let http_client = AlpacaHttpClient::new(credentials.clone(), Environment::Paper)?;"
+    );
     println!("  let ws_client = AlpacaWebSocketClient::from_env(Environment::Paper)?;");
     println!("  let mut stream = ws_client.subscribe_trade_updates().await?;");
 
@@ -79,7 +81,9 @@ fn main() {
     println!("          if order_id == &state.entry_id {{");
     println!("              match tu.event {{");
     println!("                  TradeUpdateEvent::Fill => {{");
-    println!("                      println!(\"Entry filled at ${{}}\", tu.order.filled_avg_price);");
+    println!(
+        "                      println!(\"Entry filled at ${{}}\", tu.order.filled_avg_price);"
+    );
     println!("                      state.entry_filled = true;");
     println!("                  }}");
     println!("                  TradeUpdateEvent::Rejected => {{");
@@ -90,13 +94,17 @@ fn main() {
     println!("              }}");
     println!("          }} else if Some(order_id) == state.take_profit_id.as_ref() {{");
     println!("              if tu.event == TradeUpdateEvent::Fill {{");
-    println!("                  println!(\"Take profit hit! Sold at ${{}}\", tu.order.filled_avg_price);");
+    println!(
+        "                  println!(\"Take profit hit! Sold at ${{}}\", tu.order.filled_avg_price);"
+    );
     println!("                  state.exit_filled = true;");
     println!("                  break;");
     println!("              }}");
     println!("          }} else if Some(order_id) == state.stop_loss_id.as_ref() {{");
     println!("              if tu.event == TradeUpdateEvent::Fill {{");
-    println!("                  println!(\"Stop loss triggered! Sold at ${{}}\", tu.order.filled_avg_price);");
+    println!(
+        "                  println!(\"Stop loss triggered! Sold at ${{}}\", tu.order.filled_avg_price);"
+    );
     println!("                  state.exit_filled = true;");
     println!("                  break;");
     println!("              }}");
@@ -106,7 +114,9 @@ fn main() {
 
     // Calculate P&L
     println!("\n--- Calculate P&L ---");
-    println!("  fn calculate_pnl(entry_price: f64, exit_price: f64, qty: f64, side: OrderSide) -> f64 {{");
+    println!(
+        "  fn calculate_pnl(entry_price: f64, exit_price: f64, qty: f64, side: OrderSide) -> f64 {{"
+    );
     println!("      match side {{");
     println!("          OrderSide::Buy => (exit_price - entry_price) * qty,");
     println!("          OrderSide::Sell => (entry_price - exit_price) * qty,");
@@ -83,6 +83,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         quotes: Some(symbols.clone()),
         bars: None,
         trade_updates: None,
+        orderbooks: None,
     };
 
     // Integration workflow description
@@ -93,7 +93,9 @@ fn main() {
     println!("  ");
     println!("  println!(\"High IV options:\");");
     println!("  for opt in high_iv {{");
-    println!("      println!(\"  {{}} IV: {{:.1}}%\", opt.symbol, opt.implied_volatility * 100.0);");
+    println!(
+        "      println!(\"  {{}} IV: {{:.1}}%\", opt.symbol, opt.implied_volatility * 100.0);"
+    );
     println!("  }}");
 
     // Scan for liquid options
@@ -108,7 +110,9 @@ fn main() {
     println!("  let today = Utc::now().date_naive();");
     println!("  let near_expiry = chain.iter()");
     println!("      .filter(|c| {{");
-    println!("          let expiry = NaiveDate::parse_from_str(&c.expiration_date, \"%Y-%m-%d\").unwrap();");
+    println!(
+        "          let expiry = NaiveDate::parse_from_str(&c.expiration_date, \"%Y-%m-%d\").unwrap();"
+    );
     println!("          (expiry - today).num_days() < 30");
     println!("      }})");
     println!("      .collect::<Vec<_>>();");
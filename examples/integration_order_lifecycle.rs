@@ -33,20 +33,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Phase 1: Pre-trade checks
     println!("\n--- Phase 1: Pre-Trade Checks ---");
-    
+
     // Check account status
     let account = http_client.get_account().await?;
     println!("Account Status: {:?}", account.status);
     println!("Buying Power: ${}", account.buying_power);
     println!("Day Trade Count: {}", account.daytrade_count);
-    
+
     // Check if market is open
     let clock = http_client.get_clock().await?;
     println!("Market Open: {}", clock.is_open);
 
     // Phase 2: Order creation (demonstration)
     println!("\n--- Phase 2: Order Creation ---");
-    
+
     // Create a market order request
     let market_order = CreateOrderRequest::market("AAPL", OrderSide::Buy, "1");
     println!("Market Order Request:");
@@ -74,9 +74,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Phase 4: Order monitoring
     println!("\n--- Phase 4: Order Monitoring ---");
-    
+
     // List open orders
-    let params = OrderParams::new().status(alpaca_base::OrderQueryStatus::Open);
+    let params = OrderParams::new().status(alpaca_base::types::OrderQueryStatus::Open);
     match http_client.get_orders(&params).await {
         Ok(orders) => {
             println!("Open Orders: {}", orders.len());
@@ -23,7 +23,9 @@ fn main() {
     // Initialize clients
     println!("--- Initialize Clients ---");
     println!("  let credentials = Credentials::from_env()?;");
-    println!("  let http_client = AlpacaHttpClient::new(credentials.clone(), Environment::Paper)?;");
+    println!(
+        "  let http_client = AlpacaHttpClient::new(credentials.clone(), Environment::Paper)?;"
+    );
     println!("  let ws_client = AlpacaWebSocketClient::from_env(Environment::Paper)?;");
 
     // Connect to WebSocket for trade updates
@@ -63,7 +65,9 @@ fn main() {
     println!("                      TradeUpdateEvent::Canceled |");
     println!("                      TradeUpdateEvent::Rejected |");
     println!("                      TradeUpdateEvent::Expired => {{");
-    println!("                          println!(\"Order terminated: {{:?}}\", trade_update.event);");
+    println!(
+        "                          println!(\"Order terminated: {{:?}}\", trade_update.event);"
+    );
     println!("                          break;");
     println!("                      }}");
     println!("                      _ => {{}}");